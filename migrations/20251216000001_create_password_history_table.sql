@@ -0,0 +1,14 @@
+-- Create password_history table so previously used passwords can be rejected
+CREATE TABLE password_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    password_hash VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_password_history_user_created ON password_history(user_id, created_at DESC);
+
+-- Seed the history with every user's current password
+INSERT INTO password_history (user_id, password_hash, created_at)
+SELECT id, password_hash, COALESCE(updated_at, created_at, NOW())
+FROM users;
@@ -0,0 +1,13 @@
+-- Record actions admins take on behalf of other users.
+-- No foreign keys so entries outlive the accounts they mention.
+CREATE TABLE admin_audit_log (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    admin_id UUID NOT NULL,
+    action VARCHAR(100) NOT NULL,
+    target_user_id UUID NULL,
+    details TEXT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_admin_audit_log_admin_id ON admin_audit_log(admin_id);
+CREATE INDEX idx_admin_audit_log_target_user_id ON admin_audit_log(target_user_id);
@@ -0,0 +1,7 @@
+-- Track how many lockouts in a row an account has had, so each one can last longer
+ALTER TABLE account_lockouts
+    ADD COLUMN lockout_count INTEGER NOT NULL DEFAULT 1,
+    ADD COLUMN escalation_reset_at TIMESTAMPTZ NULL;
+
+CREATE INDEX idx_account_lockouts_user_id_escalating ON account_lockouts(user_id)
+    WHERE escalation_reset_at IS NULL;
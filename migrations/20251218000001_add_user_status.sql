@@ -0,0 +1,11 @@
+-- Replace users.is_active with a status that can also mark an account as deleted.
+-- Deleted accounts keep their row (and email) so audit records still resolve.
+ALTER TABLE users
+    ADD COLUMN status VARCHAR(20) NOT NULL DEFAULT 'active'
+        CONSTRAINT chk_users_status CHECK (status IN ('active', 'disabled', 'deleted'));
+
+UPDATE users SET status = 'disabled' WHERE is_active = FALSE;
+
+ALTER TABLE users DROP COLUMN is_active;
+
+CREATE INDEX idx_users_status ON users(status);
@@ -0,0 +1,3 @@
+-- A user-chosen label for the session a refresh token belongs to.
+-- Rotation copies it to the replacement token.
+ALTER TABLE refresh_tokens ADD COLUMN device_name VARCHAR(100) NULL;
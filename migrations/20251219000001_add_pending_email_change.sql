@@ -0,0 +1,9 @@
+-- An email change waiting for the new address to be confirmed. The current
+-- email stays in use until then; the token is stored as a SHA-256 hex digest.
+ALTER TABLE users
+    ADD COLUMN pending_email VARCHAR(255) NULL,
+    ADD COLUMN email_change_token_hash VARCHAR(64) NULL,
+    ADD COLUMN email_change_expires_at TIMESTAMP WITH TIME ZONE NULL;
+
+CREATE UNIQUE INDEX idx_users_email_change_token_hash ON users(email_change_token_hash)
+    WHERE email_change_token_hash IS NOT NULL;
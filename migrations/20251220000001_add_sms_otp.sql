@@ -0,0 +1,24 @@
+-- An optional phone number for SMS codes. It only acts as a second factor once
+-- phone_verified_at is set by the user entering a code sent to it.
+ALTER TABLE users
+    ADD COLUMN phone_number VARCHAR(20) NULL,
+    ADD COLUMN phone_verified_at TIMESTAMP WITH TIME ZONE NULL;
+
+-- One-time codes sent by SMS, for signing in or for verifying a new phone number.
+-- Codes are stored as Argon2 hashes; a challenge is dead once consumed_at is set.
+CREATE TABLE otp_challenges (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose VARCHAR(32) NOT NULL CHECK (purpose IN ('login', 'phone_verification')),
+    phone_number VARCHAR(20) NOT NULL,
+    code_hash VARCHAR(255) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    remember_me BOOLEAN NOT NULL DEFAULT FALSE,
+    device_name VARCHAR(100) NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_otp_challenges_user_id_purpose ON otp_challenges(user_id, purpose)
+    WHERE consumed_at IS NULL;
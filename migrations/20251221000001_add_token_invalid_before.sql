@@ -0,0 +1,3 @@
+-- Tokens issued before this instant are rejected even if they have not expired.
+-- Moved forward on password changes and when an admin revokes a user's tokens.
+ALTER TABLE users ADD COLUMN token_invalid_before TIMESTAMPTZ NULL;
@@ -0,0 +1,8 @@
+-- One-time token mailed to a locked-out user so they can end the lockout early.
+-- Stored as a SHA-256 hex digest and cleared once used.
+ALTER TABLE account_lockouts
+    ADD COLUMN unlock_token_hash VARCHAR(64) NULL,
+    ADD COLUMN unlock_token_expires_at TIMESTAMPTZ NULL;
+
+CREATE UNIQUE INDEX idx_account_lockouts_unlock_token_hash ON account_lockouts(unlock_token_hash)
+    WHERE unlock_token_hash IS NOT NULL;
@@ -0,0 +1,12 @@
+-- JWT signing keys created through the admin key rotation endpoint, loaded into
+-- the key ring on startup. Revoked keys keep their row without the secret, so a
+-- key from JWT_SECRET or JWT_SIGNING_KEYS stays revoked across restarts.
+CREATE TABLE signing_keys (
+    kid VARCHAR(64) PRIMARY KEY,
+    secret TEXT NULL,
+    active BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ NULL
+);
+
+CREATE UNIQUE INDEX idx_signing_keys_active ON signing_keys(active) WHERE active;
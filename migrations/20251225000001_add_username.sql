@@ -0,0 +1,5 @@
+-- Optional login name next to the email, stored lowercase. NULLs don't collide,
+-- so accounts without one are unaffected by the constraint.
+ALTER TABLE users ADD COLUMN username VARCHAR(32) NULL;
+
+ALTER TABLE users ADD CONSTRAINT users_username_key UNIQUE (username);
@@ -0,0 +1,4 @@
+-- When the current password was set, for PASSWORD_MAX_AGE_DAYS. Existing
+-- accounts count from their creation.
+ALTER TABLE users ADD COLUMN password_changed_at TIMESTAMPTZ NULL;
+UPDATE users SET password_changed_at = created_at;
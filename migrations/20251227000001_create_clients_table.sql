@@ -0,0 +1,12 @@
+-- Client apps tokens can be issued for. A login naming a client_id gets tokens
+-- whose aud is the client_id followed by the client's extra audiences.
+CREATE TABLE clients (
+    client_id VARCHAR(64) PRIMARY KEY,
+    name VARCHAR(100) NOT NULL,
+    redirect_uris TEXT[] NOT NULL DEFAULT '{}',
+    audiences TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- The client an SMS login was started for, so its tokens get the same audience
+ALTER TABLE otp_challenges ADD COLUMN client_id VARCHAR(64) NULL;
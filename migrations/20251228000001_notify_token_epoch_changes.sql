@@ -0,0 +1,15 @@
+-- Tell every server when a user's token_invalid_before moves, so cached copies
+-- are dropped at once instead of when they expire. The payload is the user id.
+CREATE OR REPLACE FUNCTION notify_token_epoch_changed()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('token_epoch_changed', NEW.id::text);
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER notify_users_token_epoch_changed
+    AFTER UPDATE OF token_invalid_before ON users
+    FOR EACH ROW
+    WHEN (OLD.token_invalid_before IS DISTINCT FROM NEW.token_invalid_before)
+    EXECUTE FUNCTION notify_token_epoch_changed();
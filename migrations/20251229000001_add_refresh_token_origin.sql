@@ -0,0 +1,4 @@
+-- The client a refresh token was issued to, for REFRESH_TOKEN_BINDING.
+-- Rotation copies both to the replacement token.
+ALTER TABLE refresh_tokens ADD COLUMN user_agent TEXT NULL;
+ALTER TABLE refresh_tokens ADD COLUMN ip_address VARCHAR(45) NULL;
@@ -0,0 +1,14 @@
+-- One-time sign-in codes sent by email, for logging in without a password.
+-- Codes are stored as Argon2 hashes; a code is dead once consumed_at is set.
+CREATE TABLE email_login_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(255) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_login_codes_user_id ON email_login_codes(user_id)
+    WHERE consumed_at IS NULL;
@@ -0,0 +1,15 @@
+-- Devices that answered an SMS code at login and asked to skip it next time.
+-- The device keeps a random token in a cookie, stored here as a SHA-256 hash.
+-- Each use replaces the token, but the expiry set when the device was trusted stays.
+CREATE TABLE trusted_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    user_agent TEXT NULL,
+    ip_address VARCHAR(45) NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    last_used_at TIMESTAMPTZ NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_trusted_devices_user_id ON trusted_devices(user_id);
@@ -0,0 +1,5 @@
+-- Reset tokens are now stored as a SHA-256 hash and looked up by it. Tokens
+-- issued before that have an Argon2 hash no lookup can match, so they are
+-- marked used; they expire within the hour anyway. The rows stay so they
+-- still count towards the reset request limit.
+UPDATE password_reset_tokens SET used = true WHERE used = false;
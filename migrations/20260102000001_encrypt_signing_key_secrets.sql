@@ -0,0 +1,7 @@
+-- Secrets in signing_keys are now encrypted with SIGNING_KEY_ENCRYPTION_KEY,
+-- which the database never sees. Revoke the keys stored in plain text rather
+-- than leave their secrets readable; tokens they signed stop validating, and
+-- the active key falls back to JWT_ACTIVE_KID until the next rotation.
+UPDATE signing_keys
+SET secret = NULL, active = false, revoked_at = COALESCE(revoked_at, NOW())
+WHERE secret IS NOT NULL;
@@ -0,0 +1,15 @@
+-- Tell every server when signing keys are rotated or revoked, so they reload
+-- the table instead of only picking the change up on restart. Sent once per
+-- statement; the payload is empty, listeners reload every key.
+CREATE OR REPLACE FUNCTION notify_signing_keys_changed()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('signing_keys_changed', '');
+    RETURN NULL;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER notify_signing_keys_changed
+    AFTER INSERT OR UPDATE OR DELETE ON signing_keys
+    FOR EACH STATEMENT
+    EXECUTE FUNCTION notify_signing_keys_changed();
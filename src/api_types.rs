@@ -0,0 +1,42 @@
+//! Request and response types exchanged over the HTTP API.
+//!
+//! The server handlers and Rust API clients both use these definitions, so a
+//! field added or renamed here changes the wire format for every consumer.
+
+pub mod auth {
+    pub use crate::app::models::auth::{
+        AccountUnlockRequest, AccountUnlockResponse, AuthError, AuthErrorBody, AuthErrorResponse,
+        ChangeEmailRequest, ChangeEmailResponse, ChangePasswordRequest, ChangePasswordResponse,
+        FieldError, ForgotPasswordRequest, ForgotPasswordResponse, LockoutStatusResponse,
+        MeResponse, ProfileResponse, ProfileUpdateRequest, RegisterRequest, RegisterResponse,
+        RegistrationAcceptedResponse, ResetPasswordRequest, ResetPasswordResponse,
+        ValidateResetTokenResponse,
+    };
+    pub use crate::app::models::jwt::{
+        CsrfTokenResponse, LoginRequest, LoginResponse, LogoutRequest, LogoutResponse,
+        RefreshTokenRequest, RefreshTokenResponse, TokenErrorResponse, TokenPair,
+    };
+}
+
+pub mod users {
+    pub use crate::app::models::user::UserResponse;
+}
+
+pub mod projects {
+    pub use crate::app::models::project::{
+        CreateProjectRequest, ProjectResponse, UpdateProjectRequest,
+    };
+}
+
+pub mod tasks {
+    pub use crate::app::models::task::{CreateTaskRequest, TaskResponse, UpdateTaskRequest};
+}
+
+pub mod time_entries {
+    pub use crate::app::models::time_entry::{
+        CreateTimeEntryRequest, StartTimerRequest, TimeEntriesListResponse, TimeEntryFilters,
+        TimeEntryResponse, UpdateTimeEntryRequest,
+    };
+}
+
+pub use crate::app::models::common::ErrorResponse;
@@ -0,0 +1,423 @@
+// Environment-aware flags for auth cookies.
+//
+// Browsers drop cookies marked `Secure` when they arrive over plain HTTP, so
+// the flag is only set in production. `HttpOnly` and `SameSite=Strict` are
+// always set regardless of the environment.
+//
+// Names, path and domain are configurable so several apps can share a domain
+// without their cookies colliding. The refresh token cookie gets its own path
+// so browsers only send it to the refresh endpoint.
+//
+// With cookie sessions enabled, login keeps the refresh token out of the
+// response body and sets it as a cookie instead, alongside a CSRF cookie the
+// client has to echo in X-CSRF-Token (see `csrf_protection`).
+//
+// TOKEN_DELIVERY=cookie or both goes further and sets the access token as a
+// cookie too, which the JWT middleware accepts when there is no Authorization
+// header. Either implies cookie sessions, so the CSRF check applies.
+//
+// The trusted device cookie is set whatever the session settings, once a
+// login that answered an SMS code asks to trust the device.
+//
+// The OAuth state cookie ties a provider callback to the browser that started
+// the flow. It is the one `SameSite=Lax` cookie: the callback is a cross-site
+// navigation from the provider, which never carries Strict cookies.
+use axum::http::{HeaderMap, HeaderValue, header, header::InvalidHeaderValue};
+use std::time::Duration;
+
+pub const ACCESS_TOKEN_COOKIE: &str = "access_token";
+pub const REFRESH_TOKEN_COOKIE: &str = "refresh_token";
+pub const CSRF_TOKEN_COOKIE: &str = "csrf_token";
+pub const TRUSTED_DEVICE_COOKIE: &str = "trusted_device";
+pub const OAUTH_STATE_COOKIE: &str = "oauth_state";
+const DEFAULT_PATH: &str = "/";
+const DEFAULT_REFRESH_PATH: &str = "/api/auth/refresh";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppEnvironment {
+    Development,
+    Production,
+}
+
+impl AppEnvironment {
+    // APP_ENV (or ENVIRONMENT) set to development, dev, local or test relaxes
+    // HTTPS-only behaviour and the JWT_SECRET checks; anything else, including
+    // unset, is treated as production
+    pub fn from_env() -> Self {
+        std::env::var("APP_ENV")
+            .or_else(|_| std::env::var("ENVIRONMENT"))
+            .map(|value| Self::parse(&value))
+            .unwrap_or(AppEnvironment::Production)
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "development" | "dev" | "local" | "test" => AppEnvironment::Development,
+            _ => AppEnvironment::Production,
+        }
+    }
+
+    pub fn is_production(&self) -> bool {
+        matches!(self, AppEnvironment::Production)
+    }
+}
+
+// Where login and refresh put the tokens they issue
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TokenDelivery {
+    // In the response body only
+    #[default]
+    Body,
+    // In cookies only; the body leaves them out
+    Cookie,
+    // In cookies and in the body
+    Both,
+}
+
+impl TokenDelivery {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "body" => Some(TokenDelivery::Body),
+            "cookie" => Some(TokenDelivery::Cookie),
+            "both" => Some(TokenDelivery::Both),
+            _ => None,
+        }
+    }
+
+    pub fn uses_cookies(&self) -> bool {
+        !matches!(self, TokenDelivery::Body)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CookieConfigError {
+    #[error("{0} may only contain letters, digits, '-', '_' and '.'")]
+    InvalidNamePrefix(&'static str),
+    #[error("{0} must start with '/' and must not contain ';', ',' or whitespace")]
+    InvalidPath(&'static str),
+    #[error("{0} must not contain ';', ',' or whitespace")]
+    InvalidDomain(&'static str),
+    #[error("TOKEN_DELIVERY must be body, cookie or both")]
+    InvalidTokenDelivery,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookiePolicy {
+    secure: bool,
+    name_prefix: String,
+    path: String,
+    refresh_path: String,
+    domain: Option<String>,
+    sessions: bool,
+    token_delivery: TokenDelivery,
+}
+
+impl CookiePolicy {
+    pub fn for_environment(environment: AppEnvironment) -> Self {
+        Self {
+            secure: environment.is_production(),
+            name_prefix: String::new(),
+            path: DEFAULT_PATH.to_string(),
+            refresh_path: DEFAULT_REFRESH_PATH.to_string(),
+            domain: None,
+            sessions: false,
+            token_delivery: TokenDelivery::Body,
+        }
+    }
+
+    // APP_ENV plus COOKIE_NAME_PREFIX, COOKIE_PATH, COOKIE_REFRESH_PATH,
+    // COOKIE_DOMAIN, COOKIE_SESSIONS and TOKEN_DELIVERY; unset or empty values
+    // keep the defaults
+    pub fn from_env() -> Result<Self, CookieConfigError> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let mut policy = Self::for_environment(AppEnvironment::from_env());
+        if let Some(prefix) = var("COOKIE_NAME_PREFIX") {
+            if !is_valid_name_prefix(&prefix) {
+                return Err(CookieConfigError::InvalidNamePrefix("COOKIE_NAME_PREFIX"));
+            }
+            policy = policy.with_name_prefix(prefix);
+        }
+        if let Some(path) = var("COOKIE_PATH") {
+            if !is_valid_path(&path) {
+                return Err(CookieConfigError::InvalidPath("COOKIE_PATH"));
+            }
+            policy = policy.with_path(path);
+        }
+        if let Some(refresh_path) = var("COOKIE_REFRESH_PATH") {
+            if !is_valid_path(&refresh_path) {
+                return Err(CookieConfigError::InvalidPath("COOKIE_REFRESH_PATH"));
+            }
+            policy = policy.with_refresh_path(refresh_path);
+        }
+        if let Some(domain) = var("COOKIE_DOMAIN") {
+            if !is_valid_attribute(&domain) {
+                return Err(CookieConfigError::InvalidDomain("COOKIE_DOMAIN"));
+            }
+            policy = policy.with_domain(domain);
+        }
+        if let Some(sessions) = var("COOKIE_SESSIONS") {
+            policy = policy.with_sessions(sessions.eq_ignore_ascii_case("true") || sessions == "1");
+        }
+        if let Some(delivery) = var("TOKEN_DELIVERY") {
+            let delivery =
+                TokenDelivery::parse(&delivery).ok_or(CookieConfigError::InvalidTokenDelivery)?;
+            policy = policy.with_token_delivery(delivery);
+        }
+
+        Ok(policy)
+    }
+
+    // Prepended to every cookie name, e.g. "chronos_" gives "chronos_access_token"
+    pub fn with_name_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.name_prefix = prefix.into();
+        self
+    }
+
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    pub fn with_refresh_path(mut self, refresh_path: impl Into<String>) -> Self {
+        self.refresh_path = refresh_path.into();
+        self
+    }
+
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    // Refresh tokens travel in a cookie instead of the response body
+    pub fn with_sessions(mut self, enabled: bool) -> Self {
+        self.sessions = enabled;
+        self
+    }
+
+    pub fn with_token_delivery(mut self, token_delivery: TokenDelivery) -> Self {
+        self.token_delivery = token_delivery;
+        self
+    }
+
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    // Cookie token delivery needs the refresh cookie and CSRF check as well
+    pub fn sessions_enabled(&self) -> bool {
+        self.sessions || self.token_delivery.uses_cookies()
+    }
+
+    pub fn token_delivery(&self) -> TokenDelivery {
+        self.token_delivery
+    }
+
+    pub fn access_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, ACCESS_TOKEN_COOKIE)
+    }
+
+    pub fn refresh_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, REFRESH_TOKEN_COOKIE)
+    }
+
+    pub fn csrf_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, CSRF_TOKEN_COOKIE)
+    }
+
+    pub fn trusted_device_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, TRUSTED_DEVICE_COOKIE)
+    }
+
+    pub fn oauth_state_cookie_name(&self) -> String {
+        format!("{}{}", self.name_prefix, OAUTH_STATE_COOKIE)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn refresh_path(&self) -> &str {
+        &self.refresh_path
+    }
+
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    pub fn access_cookie(
+        &self,
+        token: &str,
+        max_age: Option<Duration>,
+    ) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_at(&self.access_cookie_name(), token, max_age, &self.path)
+    }
+
+    pub fn refresh_cookie(
+        &self,
+        token: &str,
+        max_age: Option<Duration>,
+    ) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_at(
+            &self.refresh_cookie_name(),
+            token,
+            max_age,
+            &self.refresh_path,
+        )
+    }
+
+    pub fn clear_access_cookie(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_at(
+            &self.access_cookie_name(),
+            "",
+            Some(Duration::ZERO),
+            &self.path,
+        )
+    }
+
+    // A session cookie; clients that lost the token fetch a new one from /api/auth/csrf
+    pub fn csrf_cookie(&self, token: &str) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build(&self.csrf_cookie_name(), token, None)
+    }
+
+    pub fn clear_csrf_cookie(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.clear(&self.csrf_cookie_name())
+    }
+
+    pub fn trusted_device_cookie(
+        &self,
+        token: &str,
+        max_age: Duration,
+    ) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build(&self.trusted_device_cookie_name(), token, Some(max_age))
+    }
+
+    pub fn clear_trusted_device_cookie(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.clear(&self.trusted_device_cookie_name())
+    }
+
+    pub fn oauth_state_cookie(
+        &self,
+        state: &str,
+        max_age: Duration,
+    ) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_with(
+            &self.oauth_state_cookie_name(),
+            state,
+            Some(max_age),
+            &self.path,
+            "Lax",
+        )
+    }
+
+    pub fn clear_oauth_state_cookie(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_with(
+            &self.oauth_state_cookie_name(),
+            "",
+            Some(Duration::ZERO),
+            &self.path,
+            "Lax",
+        )
+    }
+
+    // Must use the same path the cookie was set with, or the browser keeps it
+    pub fn clear_refresh_cookie(&self) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_at(
+            &self.refresh_cookie_name(),
+            "",
+            Some(Duration::ZERO),
+            &self.refresh_path,
+        )
+    }
+
+    // Set-Cookie value for `name=value` on the configured path; `max_age` of
+    // None makes it a session cookie
+    pub fn build(
+        &self,
+        name: &str,
+        value: &str,
+        max_age: Option<Duration>,
+    ) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_at(name, value, max_age, &self.path)
+    }
+
+    // Set-Cookie value that makes the browser discard `name` immediately
+    pub fn clear(&self, name: &str) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build(name, "", Some(Duration::ZERO))
+    }
+
+    fn build_at(
+        &self,
+        name: &str,
+        value: &str,
+        max_age: Option<Duration>,
+        path: &str,
+    ) -> Result<HeaderValue, InvalidHeaderValue> {
+        self.build_with(name, value, max_age, path, "Strict")
+    }
+
+    fn build_with(
+        &self,
+        name: &str,
+        value: &str,
+        max_age: Option<Duration>,
+        path: &str,
+        same_site: &str,
+    ) -> Result<HeaderValue, InvalidHeaderValue> {
+        let mut cookie = format!(
+            "{}={}; Path={}; HttpOnly; SameSite={}",
+            name, value, path, same_site
+        );
+        if let Some(domain) = &self.domain {
+            cookie.push_str(&format!("; Domain={}", domain));
+        }
+        if let Some(max_age) = max_age {
+            cookie.push_str(&format!("; Max-Age={}", max_age.as_secs()));
+        }
+        if self.secure {
+            cookie.push_str("; Secure");
+        }
+
+        HeaderValue::from_str(&cookie)
+    }
+}
+
+impl Default for CookiePolicy {
+    fn default() -> Self {
+        Self::for_environment(AppEnvironment::Production)
+    }
+}
+
+// Value of the cookie called `name` in the request's Cookie headers
+pub fn read_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+fn is_valid_name_prefix(prefix: &str) -> bool {
+    prefix
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn is_valid_path(path: &str) -> bool {
+    path.starts_with('/') && is_valid_attribute(path)
+}
+
+// Anything that could end the attribute early or inject another one is rejected
+fn is_valid_attribute(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_graphic() && c != ';' && c != ',')
+}
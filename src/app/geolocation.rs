@@ -0,0 +1,73 @@
+// Coarse location of a client IP from a local MaxMind GeoLite2 database, so
+// security events can say where a login came from. Lookups read an in-memory
+// file and never touch the network.
+use maxminddb::{MaxMindDBError, Reader, geoip2};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GeoConfigError {
+    #[error("Failed to open GeoIP database {path:?}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: MaxMindDBError,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeoLocation {
+    // ISO 3166-1 code, e.g. "GB"
+    pub country: Option<String>,
+    // English name of the largest subdivision, e.g. "England"
+    pub region: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct GeoResolver {
+    reader: Arc<Reader<Vec<u8>>>,
+}
+
+impl GeoResolver {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, GeoConfigError> {
+        let path = path.as_ref();
+        let reader = Reader::open_readfile(path).map_err(|source| GeoConfigError::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self {
+            reader: Arc::new(reader),
+        })
+    }
+
+    // GEOIP_DATABASE_PATH points at a GeoLite2 City or Country .mmdb file;
+    // unset means no geolocation
+    pub fn from_env() -> Result<Option<Self>, GeoConfigError> {
+        match std::env::var("GEOIP_DATABASE_PATH") {
+            Ok(path) if !path.trim().is_empty() => Self::open(path.trim()).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    // None for unparseable, private or unlisted addresses
+    pub fn lookup(&self, ip: &str) -> Option<GeoLocation> {
+        let ip = ip.trim().parse::<IpAddr>().ok()?;
+        let city = self.reader.lookup::<geoip2::City>(ip).ok()?;
+
+        let country = city
+            .country
+            .and_then(|country| country.iso_code)
+            .map(str::to_string);
+        let region = city
+            .subdivisions
+            .and_then(|subdivisions| subdivisions.into_iter().next())
+            .and_then(|subdivision| subdivision.names)
+            .and_then(|names| names.get("en").map(|name| name.to_string()));
+
+        if country.is_none() && region.is_none() {
+            return None;
+        }
+        Some(GeoLocation { country, region })
+    }
+}
@@ -0,0 +1,264 @@
+// Argon2id cost parameters for password and refresh-token hashing.
+//
+// Every hash records the parameters it was made with, so verification keeps
+// working after the costs change; these values only apply to new hashes.
+//
+// Hashing and verifying take tens of milliseconds of CPU each, so request
+// paths use the `*_async` variants, which run on Tokio's blocking pool instead
+// of stalling an async worker thread.
+//
+// Passwords imported from other systems may be bcrypt or PBKDF2 hashes.
+// PasswordHasher verifies those too and reports them as needing a rehash, so
+// login can replace them with Argon2id once the plain password is known.
+use argon2::{
+    Algorithm, Argon2, Params, PasswordHash, PasswordHasher as _, PasswordVerifier, Version,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use pbkdf2::Pbkdf2;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Argon2ConfigError {
+    #[error("{0} must be a positive integer")]
+    InvalidValue(&'static str),
+    #[error("Invalid Argon2 parameters: {0}")]
+    InvalidParams(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HashingError {
+    #[error("{0}")]
+    Hash(argon2::password_hash::Error),
+    #[error("Hashing task failed: {0}")]
+    Task(String),
+}
+
+impl From<argon2::password_hash::Error> for HashingError {
+    fn from(e: argon2::password_hash::Error) -> Self {
+        HashingError::Hash(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Params {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Argon2Params {
+    pub fn new(
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    ) -> Result<Self, Argon2ConfigError> {
+        Params::new(memory_kib, iterations, parallelism, None)
+            .map_err(|e| Argon2ConfigError::InvalidParams(e.to_string()))?;
+
+        Ok(Self {
+            memory_kib,
+            iterations,
+            parallelism,
+        })
+    }
+
+    // ARGON2_MEMORY_KIB, ARGON2_ITERATIONS and ARGON2_PARALLELISM; each falls
+    // back to the argon2 crate default when unset
+    pub fn from_env() -> Result<Self, Argon2ConfigError> {
+        let var = |name: &'static str, default: u32| match std::env::var(name) {
+            Ok(value) if !value.trim().is_empty() => value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| Argon2ConfigError::InvalidValue(name)),
+            _ => Ok(default),
+        };
+
+        Self::new(
+            var("ARGON2_MEMORY_KIB", Params::DEFAULT_M_COST)?,
+            var("ARGON2_ITERATIONS", Params::DEFAULT_T_COST)?,
+            var("ARGON2_PARALLELISM", Params::DEFAULT_P_COST)?,
+        )
+    }
+
+    pub fn memory_kib(&self) -> u32 {
+        self.memory_kib
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    pub fn parallelism(&self) -> u32 {
+        self.parallelism
+    }
+
+    pub fn hasher(&self) -> Argon2<'static> {
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .expect("Argon2Params are validated on construction");
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    }
+
+    // PHC-format hash of `secret` with a fresh random salt
+    pub fn hash(&self, secret: &str) -> Result<String, argon2::password_hash::Error> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = self.hasher().hash_password(secret.as_bytes(), &salt)?;
+        Ok(hash.to_string())
+    }
+
+    pub async fn hash_async(&self, secret: &str) -> Result<String, HashingError> {
+        let params = *self;
+        let secret = secret.to_string();
+        run_blocking(move || params.hash(&secret)).await
+    }
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+// Check `secret` against a PHC-format hash, using the parameters stored in it
+pub fn verify(hash: &str, secret: &str) -> Result<bool, argon2::password_hash::Error> {
+    let parsed_hash = PasswordHash::new(hash)?;
+    Ok(Argon2::default()
+        .verify_password(secret.as_bytes(), &parsed_hash)
+        .is_ok())
+}
+
+pub async fn verify_async(hash: &str, secret: &str) -> Result<bool, HashingError> {
+    let hash = hash.to_string();
+    let secret = secret.to_string();
+    run_blocking(move || verify(&hash, &secret)).await
+}
+
+// Whether `secret` matches any of `hashes`; unparseable hashes never match
+pub async fn matches_any_async(hashes: Vec<String>, secret: &str) -> Result<bool, HashingError> {
+    let secret = secret.to_string();
+    run_blocking(move || {
+        Ok(hashes
+            .iter()
+            .any(|hash| verify(hash, &secret).unwrap_or(false)))
+    })
+    .await
+}
+
+// The formats a stored password hash can be in. New hashes are always Argon2id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashFormat {
+    Argon2id,
+    // argon2i and argon2d
+    Argon2,
+    Bcrypt,
+    Pbkdf2,
+}
+
+impl HashFormat {
+    // From the PHC identifier (`$argon2id$`, `$pbkdf2-sha256$`) or bcrypt's
+    // `$2b$`-style prefix; None for anything else
+    pub fn detect(hash: &str) -> Option<Self> {
+        let id = hash.strip_prefix('$')?.split('$').next()?;
+        match id {
+            "argon2id" => Some(HashFormat::Argon2id),
+            "argon2i" | "argon2d" => Some(HashFormat::Argon2),
+            "2a" | "2b" | "2x" | "2y" => Some(HashFormat::Bcrypt),
+            "pbkdf2-sha256" | "pbkdf2-sha512" => Some(HashFormat::Pbkdf2),
+            _ => None,
+        }
+    }
+
+    // The scheme an import declares for its hashes, e.g. "bcrypt"
+    pub fn from_scheme(scheme: &str) -> Option<Self> {
+        match scheme.trim().to_ascii_lowercase().as_str() {
+            "argon2id" => Some(HashFormat::Argon2id),
+            "argon2" | "argon2i" | "argon2d" => Some(HashFormat::Argon2),
+            "bcrypt" => Some(HashFormat::Bcrypt),
+            "pbkdf2" | "pbkdf2-sha256" | "pbkdf2-sha512" => Some(HashFormat::Pbkdf2),
+            _ => None,
+        }
+    }
+
+    // Whether `hash` is a well-formed hash in this format, i.e. one
+    // PasswordHasher can verify later
+    pub fn is_valid_hash(self, hash: &str) -> bool {
+        if HashFormat::detect(hash) != Some(self) {
+            return false;
+        }
+        match self {
+            HashFormat::Bcrypt => hash.parse::<bcrypt::HashParts>().is_ok(),
+            _ => PasswordHash::new(hash).is_ok(),
+        }
+    }
+}
+
+// Password hashing: new hashes are Argon2id with `params`, and stored hashes
+// are verified in whichever supported format they were made
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PasswordHasher {
+    params: Argon2Params,
+}
+
+impl PasswordHasher {
+    pub fn new(params: Argon2Params) -> Self {
+        Self { params }
+    }
+
+    pub fn hash_password(&self, password: &str) -> Result<String, argon2::password_hash::Error> {
+        self.params.hash(password)
+    }
+
+    pub async fn hash_password_async(&self, password: &str) -> Result<String, HashingError> {
+        self.params.hash_async(password).await
+    }
+
+    // Unknown formats are an error rather than a mismatch, so a bad import
+    // shows up instead of looking like a wrong password
+    pub fn verify_password(
+        &self,
+        hash: &str,
+        password: &str,
+    ) -> Result<bool, argon2::password_hash::Error> {
+        match HashFormat::detect(hash) {
+            Some(HashFormat::Argon2id | HashFormat::Argon2) => verify(hash, password),
+            Some(HashFormat::Bcrypt) => bcrypt::verify(password, hash)
+                .map_err(|_| argon2::password_hash::Error::PhcStringField),
+            Some(HashFormat::Pbkdf2) => {
+                let parsed_hash = PasswordHash::new(hash)?;
+                Ok(Pbkdf2
+                    .verify_password(password.as_bytes(), &parsed_hash)
+                    .is_ok())
+            }
+            None => Err(argon2::password_hash::Error::Algorithm),
+        }
+    }
+
+    pub async fn verify_password_async(
+        &self,
+        hash: &str,
+        password: &str,
+    ) -> Result<bool, HashingError> {
+        let hasher = *self;
+        let hash = hash.to_string();
+        let password = password.to_string();
+        run_blocking(move || hasher.verify_password(&hash, &password)).await
+    }
+
+    // Whether a verified hash should be replaced with a fresh Argon2id one
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        HashFormat::detect(hash) != Some(HashFormat::Argon2id)
+    }
+}
+
+async fn run_blocking<T, F>(work: F) -> Result<T, HashingError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, argon2::password_hash::Error> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| HashingError::Task(e.to_string()))?
+        .map_err(HashingError::from)
+}
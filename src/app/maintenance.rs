@@ -0,0 +1,169 @@
+// Periodic cleanup of expired auth rows. Logout already purges opportunistically,
+// but idle accounts never log out, so without this task expired blacklist
+// entries, refresh tokens, reset tokens, SMS and email codes, trusted devices
+// and old login attempts pile up.
+use crate::app::repositories::email_login_code_repository::EmailLoginCodeRepository;
+use crate::app::repositories::login_attempt_repository::{
+    LoginAttemptRepository, RefreshTokenRepository,
+};
+use crate::app::repositories::otp_challenge_repository::OtpChallengeRepository;
+use crate::app::repositories::password_reset_repository::PasswordResetRepository;
+use crate::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use crate::app::repositories::trusted_device_repository::TrustedDeviceRepository;
+use crate::app::task_health::TaskHeartbeat;
+use sqlx::PgPool;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+pub const CLEANUP_TASK: &str = "auth_cleanup";
+const DEFAULT_CLEANUP_INTERVAL_SECS: u64 = 3600;
+// Login attempts feed lockouts and the account export; a month is plenty for both
+const LOGIN_ATTEMPT_RETENTION: time::Duration = time::Duration::days(30);
+
+// CLEANUP_INTERVAL_SECS, default one hour; zero or unparseable values fall back to the default
+pub fn get_cleanup_interval() -> Duration {
+    let secs = std::env::var("CLEANUP_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_CLEANUP_INTERVAL_SECS);
+    Duration::from_secs(secs)
+}
+
+// Rows removed by one cleanup cycle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CleanupReport {
+    pub blacklisted_tokens: u64,
+    pub refresh_tokens: u64,
+    pub password_reset_tokens: u64,
+    pub otp_challenges: u64,
+    pub email_login_codes: u64,
+    pub trusted_devices: u64,
+    pub login_attempts: u64,
+}
+
+impl CleanupReport {
+    pub fn total(&self) -> u64 {
+        self.blacklisted_tokens
+            + self.refresh_tokens
+            + self.password_reset_tokens
+            + self.otp_challenges
+            + self.email_login_codes
+            + self.trusted_devices
+            + self.login_attempts
+    }
+}
+
+#[derive(Clone)]
+pub struct CleanupTask {
+    blacklist_repository: TokenBlacklistRepository,
+    refresh_token_repository: RefreshTokenRepository,
+    password_reset_repository: PasswordResetRepository,
+    otp_challenge_repository: OtpChallengeRepository,
+    email_login_code_repository: EmailLoginCodeRepository,
+    trusted_device_repository: TrustedDeviceRepository,
+    login_attempt_repository: LoginAttemptRepository,
+    interval: Duration,
+    heartbeat: TaskHeartbeat,
+}
+
+impl CleanupTask {
+    pub fn new(pool: PgPool) -> Self {
+        let interval = get_cleanup_interval();
+
+        Self {
+            blacklist_repository: TokenBlacklistRepository::new(pool.clone()),
+            refresh_token_repository: RefreshTokenRepository::new(pool.clone()),
+            password_reset_repository: PasswordResetRepository::new(pool.clone()),
+            otp_challenge_repository: OtpChallengeRepository::new(pool.clone()),
+            email_login_code_repository: EmailLoginCodeRepository::new(pool.clone()),
+            trusted_device_repository: TrustedDeviceRepository::new(pool.clone()),
+            login_attempt_repository: LoginAttemptRepository::new(pool),
+            interval,
+            heartbeat: TaskHeartbeat::new(CLEANUP_TASK, interval),
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self.heartbeat = TaskHeartbeat::new(CLEANUP_TASK, interval);
+        self
+    }
+
+    // Shared handle for health reporting; it keeps updating after `spawn`
+    pub fn heartbeat(&self) -> TaskHeartbeat {
+        self.heartbeat.clone()
+    }
+
+    // Run one cleanup cycle now; the periodic loop calls this too
+    pub async fn run_once(&self) -> Result<CleanupReport, sqlx::Error> {
+        let now = OffsetDateTime::now_utc();
+
+        let report = CleanupReport {
+            blacklisted_tokens: self
+                .blacklist_repository
+                .cleanup_expired_tokens(now)
+                .await? as u64,
+            refresh_tokens: self
+                .refresh_token_repository
+                .cleanup_expired_tokens()
+                .await?,
+            password_reset_tokens: self
+                .password_reset_repository
+                .cleanup_expired_tokens()
+                .await?,
+            otp_challenges: self.otp_challenge_repository.cleanup_expired(now).await?,
+            email_login_codes: self
+                .email_login_code_repository
+                .cleanup_expired(now)
+                .await?,
+            trusted_devices: self.trusted_device_repository.cleanup_expired(now).await?,
+            login_attempts: self
+                .login_attempt_repository
+                .cleanup_old_attempts(now - LOGIN_ATTEMPT_RETENTION)
+                .await?,
+        };
+
+        self.heartbeat.beat(report.total());
+        Ok(report)
+    }
+
+    // Run a cycle every interval until `shutdown` flips to true. A failed cycle
+    // is logged and skips its heartbeat, so the task shows up as degraded.
+    pub fn spawn(self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.interval);
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = shutdown.changed() => break,
+                }
+                if *shutdown.borrow() {
+                    break;
+                }
+
+                match self.run_once().await {
+                    Ok(report) => info!(
+                        task = CLEANUP_TASK,
+                        blacklisted_tokens = report.blacklisted_tokens,
+                        refresh_tokens = report.refresh_tokens,
+                        password_reset_tokens = report.password_reset_tokens,
+                        otp_challenges = report.otp_challenges,
+                        email_login_codes = report.email_login_codes,
+                        trusted_devices = report.trusted_devices,
+                        login_attempts = report.login_attempts,
+                        "Purged {} expired rows",
+                        report.total()
+                    ),
+                    Err(e) => warn!(task = CLEANUP_TASK, error = %e, "Cleanup cycle failed"),
+                }
+            }
+
+            info!(task = CLEANUP_TASK, "Cleanup task stopped");
+        })
+    }
+}
@@ -0,0 +1,103 @@
+// Authentication metrics. Every recorder is a no-op unless the `metrics` feature is enabled.
+use std::time::Duration;
+use time::OffsetDateTime;
+
+#[cfg(feature = "metrics")]
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+#[cfg(feature = "metrics")]
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+// METRICS_ENABLED=false turns the /metrics endpoint off; on by default when the feature is built
+pub fn metrics_enabled() -> bool {
+    cfg!(feature = "metrics")
+        && std::env::var("METRICS_ENABLED")
+            .map(|value| !value.eq_ignore_ascii_case("false") && value != "0")
+            .unwrap_or(true)
+}
+
+// Install the global Prometheus recorder once and hand out its handle for rendering
+#[cfg(feature = "metrics")]
+pub fn install_recorder() -> PrometheusHandle {
+    static HANDLE: std::sync::OnceLock<PrometheusHandle> = std::sync::OnceLock::new();
+
+    HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .set_buckets_for_metric(
+                    Matcher::Suffix("duration_seconds".to_string()),
+                    LATENCY_BUCKETS,
+                )
+                .expect("Invalid histogram buckets")
+                .install_recorder()
+                .expect("Failed to install Prometheus recorder")
+        })
+        .clone()
+}
+
+pub fn record_login(success: bool, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        let result = if success { "success" } else { "failure" };
+        metrics::counter!("chronos_logins_total", "result" => result).increment(1);
+        metrics::histogram!("chronos_login_duration_seconds").record(duration.as_secs_f64());
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (success, duration);
+}
+
+pub fn record_registration() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("chronos_registrations_total").increment(1);
+}
+
+// `stage` is "requested", "admin_requested" or "completed"
+pub fn record_password_reset(stage: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("chronos_password_resets_total", "stage" => stage).increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = stage;
+}
+
+pub fn record_token_refresh(success: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let result = if success { "success" } else { "failure" };
+        metrics::counter!("chronos_token_refreshes_total", "result" => result).increment(1);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = success;
+}
+
+pub fn record_rate_limit_hit(endpoint: &str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("chronos_rate_limit_hits_total", "endpoint" => endpoint.to_string())
+        .increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = endpoint;
+}
+
+pub fn record_account_lockout() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("chronos_account_lockouts_total").increment(1);
+}
+
+pub fn record_token_generation(duration: Duration) {
+    #[cfg(feature = "metrics")]
+    metrics::histogram!("chronos_token_generation_duration_seconds").record(duration.as_secs_f64());
+    #[cfg(not(feature = "metrics"))]
+    let _ = duration;
+}
+
+// Rows removed per run and the time of the last run, per background task
+pub fn record_background_task_run(task: &'static str, removed: u64, at: OffsetDateTime) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("chronos_background_task_rows_removed_total", "task" => task)
+            .increment(removed);
+        metrics::gauge!("chronos_background_task_last_run_timestamp_seconds", "task" => task)
+            .set(at.unix_timestamp() as f64);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (task, removed, at);
+}
@@ -1,10 +1,12 @@
-use crate::app::models::jwt::{AuthContext, Claims, JwtError};
+use crate::app::models::auth::AuthenticationError;
+use crate::app::models::jwt::{AuthContext, JwtError, TokenRejection};
 use crate::app::services::jwt_service::JwtService;
 use axum::{
     extract::{Request, State},
-    http::{StatusCode, header::AUTHORIZATION},
-    middleware::Next,
-    response::Response,
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
 };
 use std::sync::Arc;
 
@@ -14,89 +16,120 @@ pub async fn jwt_auth_middleware(
     mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    // Extract authorization header
-    let auth_header = request
-        .headers()
-        .get(AUTHORIZATION)
-        .and_then(|header| header.to_str().ok())
+    // Bearer token from the Authorization header, or the access token cookie
+    let token = jwt_service
+        .extract_request_token(request.headers())
+        .map_err(|_| StatusCode::UNAUTHORIZED)?
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    // Extract token from header
-    let token =
-        JwtService::extract_token_from_header(auth_header).map_err(|_| StatusCode::UNAUTHORIZED)?;
-
-    // Validate token
-    let claims = jwt_service
-        .validate_token(token)
+    // Validate the token or API key, then make sure its account is still active
+    let auth_context = jwt_service
+        .authenticate(token)
         .await
         .map_err(|err| match err {
             JwtError::ExpiredToken => StatusCode::UNAUTHORIZED,
             JwtError::BlacklistedToken => StatusCode::UNAUTHORIZED,
             JwtError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            JwtError::AccountUnavailable(_) => StatusCode::UNAUTHORIZED,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         })?;
 
-    // Create auth context and add to request extensions
-    let auth_context = AuthContext::from(claims);
+    // Add auth context to request extensions
     request.extensions_mut().insert(auth_context);
 
     Ok(next.run(request).await)
 }
 
-// Optional middleware for handling JWT auth errors with custom responses
+// JWT middleware with JSON errors. Rejections carry a TOKEN_* code and a
+// WWW-Authenticate challenge so clients can tell an expired token from a revoked one.
 pub async fn jwt_auth_middleware_with_json_errors(
     State(jwt_service): State<Arc<JwtService>>,
     mut request: Request,
     next: Next,
 ) -> Response {
-    // Extract authorization header
-    let auth_header = match request
-        .headers()
-        .get(AUTHORIZATION)
-        .and_then(|header| header.to_str().ok())
-    {
-        Some(header) => header,
-        None => {
-            return create_auth_error_response(
-                StatusCode::UNAUTHORIZED,
-                "Missing authorization header",
-            );
-        }
+    // Bearer token from the Authorization header, or the access token cookie
+    let token = match jwt_service.extract_request_token(request.headers()) {
+        Ok(Some(token)) => token,
+        Ok(None) => return TokenRejection::Missing.into_response(),
+        Err(_) => return TokenRejection::Malformed.into_response(),
     };
 
-    // Extract token from header
-    let token = match JwtService::extract_token_from_header(auth_header) {
-        Ok(token) => token,
-        Err(_) => {
-            return create_auth_error_response(
-                StatusCode::UNAUTHORIZED,
-                "Invalid authorization header format",
-            );
-        }
-    };
-
-    // Validate token
-    let claims = match jwt_service.validate_token(token).await {
-        Ok(claims) => claims,
+    // Validate the token or API key, then make sure its account is still active
+    let auth_context = match jwt_service.authenticate(token).await {
+        Ok(auth_context) => auth_context,
         Err(err) => {
-            let (status, message) = match err {
-                JwtError::ExpiredToken => (StatusCode::UNAUTHORIZED, "Token has expired"),
-                JwtError::BlacklistedToken => (StatusCode::UNAUTHORIZED, "Token has been revoked"),
-                JwtError::InvalidToken(_) => (StatusCode::UNAUTHORIZED, "Invalid token"),
-                JwtError::MissingToken => (StatusCode::UNAUTHORIZED, "Missing token"),
-                _ => (StatusCode::INTERNAL_SERVER_ERROR, "Authentication error"),
+            return match TokenRejection::from_jwt_error(&err) {
+                Some(rejection) => rejection.into_response(),
+                None => create_auth_error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Authentication error",
+                ),
             };
-            return create_auth_error_response(status, message);
         }
     };
 
-    // Create auth context and add to request extensions
-    let auth_context = AuthContext::from(claims);
+    // Add auth context to request extensions
     request.extensions_mut().insert(auth_context);
 
     next.run(request).await
 }
 
+// Role required to pass `require_role`, e.g.
+// `middleware::from_fn_with_state(RequireRole("admin"), require_role)`.
+// Must run after the JWT middleware so the auth context is available.
+#[derive(Debug, Clone, Copy)]
+pub struct RequireRole(pub &'static str);
+
+pub async fn require_role(
+    State(RequireRole(role)): State<RequireRole>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let has_role = request
+        .extensions()
+        .get::<AuthContext>()
+        .is_some_and(|ctx| ctx.roles.iter().any(|r| r == role));
+
+    if !has_role {
+        return AuthenticationError::InsufficientPermissions.into_response();
+    }
+
+    next.run(request).await
+}
+
+// Scope required to pass `require_scope`, see `scoped`. Like RequireRole it
+// must run after the JWT middleware.
+#[derive(Debug, Clone, Copy)]
+pub struct RequireScope(pub &'static str);
+
+pub async fn require_scope(
+    State(RequireScope(scope)): State<RequireScope>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let has_scope = request
+        .extensions()
+        .get::<AuthContext>()
+        .is_some_and(|ctx| ctx.has_scope(scope));
+
+    if !has_scope {
+        return AuthenticationError::InsufficientPermissions.into_response();
+    }
+
+    next.run(request).await
+}
+
+// Guards one route with RequireScope, e.g. `.route("/profile", scoped(PROFILE_WRITE, put(update_profile)))`
+pub fn scoped<S>(scope: &'static str, method_router: MethodRouter<S>) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    method_router.route_layer(middleware::from_fn_with_state(
+        RequireScope(scope),
+        require_scope,
+    ))
+}
+
 // Helper function to create JSON error responses
 fn create_auth_error_response(status: StatusCode, message: &str) -> Response {
     let error_json = format!(r#"{{"error": "{}"}}"#, message);
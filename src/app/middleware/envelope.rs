@@ -0,0 +1,71 @@
+// Opt-in `{ "data": ..., "meta": { "request_id", "timestamp" } }` wrapper for
+// successful JSON responses, so clients get one shape for every endpoint.
+// Errors keep their `{ "error": ... }` body either way.
+use crate::app::middleware::request_id::{REQUEST_ID_HEADER, current_request_id};
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::header::{CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{Value, json};
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+// RESPONSE_ENVELOPE=true wraps successful responses; bare bodies are the default
+pub fn get_response_envelope() -> bool {
+    std::env::var("RESPONSE_ENVELOPE")
+        .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        .unwrap_or(false)
+}
+
+pub async fn wrap_success_response(request: Request, next: Next) -> Response {
+    let request_id = current_request_id().or_else(|| {
+        request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    });
+
+    let response = next.run(request).await;
+    if !should_wrap(&response) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(_) => return Response::from_parts(parts, Body::empty()),
+    };
+    // Not actually JSON, so there is nothing to nest
+    let Ok(data) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    let wrapped = json!({
+        "data": data,
+        "meta": {
+            "request_id": request_id,
+            "timestamp": OffsetDateTime::now_utc().format(&Rfc3339).ok(),
+        },
+    });
+    parts.headers.remove(CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(wrapped.to_string()))
+}
+
+// Successful JSON bodies only; 204s, redirects and downloads are left alone
+fn should_wrap(response: &Response) -> bool {
+    let headers = response.headers();
+    let is_json = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let is_download = headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("attachment"));
+
+    response.status().is_success() && is_json && !is_download
+}
@@ -0,0 +1,220 @@
+// CIDR allow and deny lists for the auth routes.
+//
+// The client address comes from `extract_real_ip`, so deployments behind a
+// proxy listed in TRUSTED_PROXIES are filtered on the forwarded address.
+use crate::app::middleware::security::{extract_real_ip, log_security_event};
+use axum::{
+    Json,
+    body::Body,
+    extract::{ConnectInfo, FromRequestParts, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tower::{Layer, Service};
+
+#[derive(Debug, thiserror::Error)]
+pub enum IpFilterError {
+    #[error("{0}: invalid CIDR {1:?}")]
+    InvalidCidr(&'static str, String),
+}
+
+// An address range such as 10.0.0.0/8 or 2001:db8::/32; a bare address is a
+// single-host range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+            None => (value.parse::<IpAddr>().ok()?, None),
+        };
+
+        let max_prefix = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        let prefix = prefix.unwrap_or(max_prefix);
+        if prefix > max_prefix {
+            return None;
+        }
+
+        Some(Self { addr, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // Dual-stack sockets report IPv4 clients as ::ffff:a.b.c.d
+        let ip = match ip {
+            IpAddr::V6(v6) => v6
+                .to_ipv4_mapped()
+                .map(IpAddr::V4)
+                .unwrap_or(IpAddr::V6(v6)),
+            ip => ip,
+        };
+
+        match (self.addr, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IpFilter {
+    allow: Vec<IpNetwork>,
+    deny: Vec<IpNetwork>,
+}
+
+impl IpFilter {
+    pub fn new(allow: Vec<IpNetwork>, deny: Vec<IpNetwork>) -> Self {
+        Self { allow, deny }
+    }
+
+    // IP_ALLOWLIST and IP_DENYLIST, comma-separated CIDRs; both empty by default
+    pub fn from_env() -> Result<Self, IpFilterError> {
+        Ok(Self::new(
+            parse_list_env("IP_ALLOWLIST")?,
+            parse_list_env("IP_DENYLIST")?,
+        ))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    // The deny list wins; a non-empty allow list admits only its ranges. An
+    // address that can't be parsed only passes when there is no allow list.
+    pub fn is_allowed(&self, ip: Option<IpAddr>) -> bool {
+        let Some(ip) = ip else {
+            return self.allow.is_empty();
+        };
+        if self.deny.iter().any(|network| network.contains(ip)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|network| network.contains(ip))
+    }
+}
+
+pub(crate) fn parse_list_env(name: &'static str) -> Result<Vec<IpNetwork>, IpFilterError> {
+    let Ok(value) = std::env::var(name) else {
+        return Ok(Vec::new());
+    };
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            IpNetwork::parse(entry)
+                .ok_or_else(|| IpFilterError::InvalidCidr(name, entry.to_string()))
+        })
+        .collect()
+}
+
+#[derive(Clone)]
+pub struct IpFilterLayer {
+    filter: Arc<IpFilter>,
+}
+
+impl IpFilterLayer {
+    pub fn new(filter: IpFilter) -> Self {
+        Self {
+            filter: Arc::new(filter),
+        }
+    }
+}
+
+impl<S> Layer<S> for IpFilterLayer {
+    type Service = IpFilterService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        IpFilterService {
+            inner,
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IpFilterService<S> {
+    inner: S,
+    filter: Arc<IpFilter>,
+}
+
+impl<S> Service<Request> for IpFilterService<S>
+where
+    S: Service<Request, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+        let filter = self.filter.clone();
+
+        Box::pin(async move {
+            if filter.is_empty() {
+                return inner.call(req).await;
+            }
+
+            // Same lookup as the handlers, so MockConnectInfo works in tests
+            let (mut parts, body) = req.into_parts();
+            let client_ip = ConnectInfo::<SocketAddr>::from_request_parts(&mut parts, &())
+                .await
+                .ok()
+                .map(|ConnectInfo(addr)| extract_real_ip(addr, &parts.headers));
+            let parsed = client_ip
+                .as_deref()
+                .and_then(|ip| ip.parse::<IpAddr>().ok());
+
+            if filter.is_allowed(parsed) {
+                return inner.call(Request::from_parts(parts, body)).await;
+            }
+
+            let user_agent = parts
+                .headers
+                .get("user-agent")
+                .and_then(|h| h.to_str().ok());
+            log_security_event(
+                "suspicious_activity",
+                client_ip.as_deref().unwrap_or("unknown"),
+                user_agent,
+                None,
+                None,
+                false,
+                Some("Request blocked by IP filter"),
+            );
+
+            Ok((StatusCode::FORBIDDEN, Json(json!({ "error": "Forbidden" }))).into_response())
+        })
+    }
+}
@@ -1,2 +1,7 @@
 pub mod auth_middleware;
+pub mod envelope;
+pub mod ip_filter;
+pub mod rate_limiter;
+pub mod request_id;
 pub mod security;
+pub mod timeout;
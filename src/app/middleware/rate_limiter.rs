@@ -0,0 +1,346 @@
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RateLimitError {
+    #[error("Unknown rate limit backend: {0}")]
+    UnknownBackend(String),
+    #[error("Rate limit backend unavailable: {0}")]
+    Backend(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitDecision {
+    // `remaining` hits are left after this one; `reset_after` is how long until
+    // the oldest hit leaves the window and frees a slot
+    Allowed {
+        remaining: usize,
+        reset_after: Duration,
+    },
+    // `retry_after` is how long until the oldest hit leaves the window
+    Limited {
+        retry_after: Duration,
+    },
+}
+
+impl RateLimitDecision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, RateLimitDecision::Allowed { .. })
+    }
+}
+
+// Counts hits per key inside a sliding window
+#[async_trait]
+pub trait RateLimiter: Send + Sync {
+    // Record a hit for `key` unless it already has `max_hits` hits within `window`
+    async fn check(
+        &self,
+        key: &str,
+        max_hits: usize,
+        window: Duration,
+    ) -> Result<RateLimitDecision, RateLimitError>;
+
+    // Drop state older than `max_age`; backends that expire keys themselves can ignore this
+    async fn cleanup(&self, _max_age: Duration) {}
+}
+
+// Per-process limiter; counters are not shared between instances
+#[derive(Clone, Default)]
+pub struct InMemoryRateLimiter {
+    hits: Arc<DashMap<String, Vec<Instant>>>,
+}
+
+impl InMemoryRateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Number of keys currently tracked
+    pub fn len(&self) -> usize {
+        self.hits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hits.is_empty()
+    }
+}
+
+#[async_trait]
+impl RateLimiter for InMemoryRateLimiter {
+    async fn check(
+        &self,
+        key: &str,
+        max_hits: usize,
+        window: Duration,
+    ) -> Result<RateLimitDecision, RateLimitError> {
+        let mut hits = self.hits.entry(key.to_string()).or_default();
+
+        let now = Instant::now();
+        hits.retain(|&time| now.duration_since(time) < window);
+
+        if hits.len() >= max_hits {
+            let oldest = hits.iter().min().copied().unwrap_or(now);
+            return Ok(RateLimitDecision::Limited {
+                retry_after: window.saturating_sub(now.duration_since(oldest)),
+            });
+        }
+
+        hits.push(now);
+        let oldest = hits.iter().min().copied().unwrap_or(now);
+        Ok(RateLimitDecision::Allowed {
+            remaining: max_hits - hits.len(),
+            reset_after: window.saturating_sub(now.duration_since(oldest)),
+        })
+    }
+
+    async fn cleanup(&self, max_age: Duration) {
+        let now = Instant::now();
+        self.hits.retain(|_, hits| {
+            hits.retain(|&time| now.duration_since(time) < max_age);
+            !hits.is_empty()
+        });
+    }
+}
+
+#[cfg(feature = "redis")]
+pub use redis_backend::RedisRateLimiter;
+
+#[cfg(feature = "redis")]
+mod redis_backend {
+    use super::{RateLimitDecision, RateLimitError, RateLimiter};
+    use async_trait::async_trait;
+    use redis::aio::MultiplexedConnection;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tokio::sync::OnceCell;
+    use uuid::Uuid;
+
+    // Sliding window kept in a sorted set scored by hit time in milliseconds.
+    // Runs as one script so concurrent instances can't both take the last slot.
+    // Returns {allowed (1 or 0), hits remaining, milliseconds until a slot frees up}.
+    const SLIDING_WINDOW_SCRIPT: &str = r"
+        local now = tonumber(ARGV[1])
+        local window = tonumber(ARGV[2])
+        local max_hits = tonumber(ARGV[3])
+        redis.call('ZREMRANGEBYSCORE', KEYS[1], 0, now - window)
+        local count = redis.call('ZCARD', KEYS[1])
+        local allowed = 0
+        if count < max_hits then
+            redis.call('ZADD', KEYS[1], now, ARGV[4])
+            redis.call('PEXPIRE', KEYS[1], window)
+            count = count + 1
+            allowed = 1
+        end
+        local oldest = redis.call('ZRANGE', KEYS[1], 0, 0, 'WITHSCORES')
+        local reset_after = 0
+        if oldest[2] then
+            reset_after = math.max(tonumber(oldest[2]) + window - now, 0)
+        end
+        return {allowed, math.max(max_hits - count, 0), reset_after}
+    ";
+
+    // Limiter shared by every instance pointing at the same Redis
+    pub struct RedisRateLimiter {
+        client: redis::Client,
+        connection: OnceCell<MultiplexedConnection>,
+        script: redis::Script,
+        key_prefix: String,
+    }
+
+    impl RedisRateLimiter {
+        pub fn new(redis_url: &str) -> Result<Self, RateLimitError> {
+            let client = redis::Client::open(redis_url)
+                .map_err(|e| RateLimitError::Backend(e.to_string()))?;
+
+            Ok(Self {
+                client,
+                connection: OnceCell::new(),
+                script: redis::Script::new(SLIDING_WINDOW_SCRIPT),
+                key_prefix: "chronos:rate_limit:".to_string(),
+            })
+        }
+
+        // Namespace keys, e.g. to keep test runs apart
+        pub fn with_key_prefix(mut self, key_prefix: &str) -> Self {
+            self.key_prefix = key_prefix.to_string();
+            self
+        }
+
+        async fn connection(&self) -> Result<MultiplexedConnection, RateLimitError> {
+            self.connection
+                .get_or_try_init(|| self.client.get_multiplexed_async_connection())
+                .await
+                .cloned()
+                .map_err(|e| RateLimitError::Backend(e.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl RateLimiter for RedisRateLimiter {
+        async fn check(
+            &self,
+            key: &str,
+            max_hits: usize,
+            window: Duration,
+        ) -> Result<RateLimitDecision, RateLimitError> {
+            let mut connection = self.connection().await?;
+            let now_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+
+            let (allowed, remaining, reset_after_ms): (i64, i64, i64) = self
+                .script
+                .key(format!("{}{}", self.key_prefix, key))
+                .arg(now_ms)
+                .arg(window.as_millis() as u64)
+                .arg(max_hits)
+                .arg(format!("{}-{}", now_ms, Uuid::new_v4()))
+                .invoke_async(&mut connection)
+                .await
+                .map_err(|e| RateLimitError::Backend(e.to_string()))?;
+
+            let reset_after = Duration::from_millis(reset_after_ms.max(0) as u64);
+            if allowed == 1 {
+                Ok(RateLimitDecision::Allowed {
+                    remaining: remaining.max(0) as usize,
+                    reset_after,
+                })
+            } else {
+                Ok(RateLimitDecision::Limited {
+                    retry_after: reset_after,
+                })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitRule {
+    pub max_attempts: usize,
+    pub window: Duration,
+}
+
+impl RateLimitRule {
+    pub const fn new(max_attempts: usize, window: Duration) -> Self {
+        Self {
+            max_attempts,
+            window,
+        }
+    }
+
+    // Override the defaults with RATE_LIMIT_<NAME>_MAX and RATE_LIMIT_<NAME>_WINDOW_SECS
+    fn from_env(name: &str, default: Self) -> Self {
+        let read = |suffix: &str| {
+            std::env::var(format!("RATE_LIMIT_{}_{}", name, suffix))
+                .ok()
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+
+        Self {
+            max_attempts: read("MAX").map_or(default.max_attempts, |max| max as usize),
+            window: read("WINDOW_SECS").map_or(default.window, Duration::from_secs),
+        }
+    }
+}
+
+// Per-endpoint limits used by the check_*_rate_limit functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub registration: RateLimitRule,
+    // Logins per client IP, whichever accounts they are for
+    pub login: RateLimitRule,
+    // Logins per email address, whichever IPs they come from
+    pub login_email: RateLimitRule,
+    pub refresh: RateLimitRule,
+    pub password_reset: RateLimitRule,
+    // Reset token checks per client IP, so tokens can't be guessed through them
+    pub password_reset_validate: RateLimitRule,
+    // Lockout status lookups per client IP
+    pub lockout_status: RateLimitRule,
+    // Unlock emails per email address
+    pub account_unlock: RateLimitRule,
+    // Sign-in code emails per email address
+    pub email_code: RateLimitRule,
+    pub password_strength: RateLimitRule,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            registration: RateLimitRule::new(5, Duration::from_secs(3600)),
+            login: RateLimitRule::new(20, Duration::from_secs(900)),
+            login_email: RateLimitRule::new(10, Duration::from_secs(900)),
+            refresh: RateLimitRule::new(10, Duration::from_secs(60)),
+            password_reset: RateLimitRule::new(3, Duration::from_secs(3600)),
+            password_reset_validate: RateLimitRule::new(10, Duration::from_secs(900)),
+            lockout_status: RateLimitRule::new(10, Duration::from_secs(900)),
+            account_unlock: RateLimitRule::new(3, Duration::from_secs(3600)),
+            email_code: RateLimitRule::new(3, Duration::from_secs(900)),
+            password_strength: RateLimitRule::new(30, Duration::from_secs(60)),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            registration: RateLimitRule::from_env("REGISTRATION", defaults.registration),
+            login: RateLimitRule::from_env("LOGIN", defaults.login),
+            login_email: RateLimitRule::from_env("LOGIN_EMAIL", defaults.login_email),
+            refresh: RateLimitRule::from_env("REFRESH", defaults.refresh),
+            password_reset: RateLimitRule::from_env("PASSWORD_RESET", defaults.password_reset),
+            password_reset_validate: RateLimitRule::from_env(
+                "PASSWORD_RESET_VALIDATE",
+                defaults.password_reset_validate,
+            ),
+            lockout_status: RateLimitRule::from_env("LOCKOUT_STATUS", defaults.lockout_status),
+            account_unlock: RateLimitRule::from_env("ACCOUNT_UNLOCK", defaults.account_unlock),
+            email_code: RateLimitRule::from_env("EMAIL_CODE", defaults.email_code),
+            password_strength: RateLimitRule::from_env(
+                "PASSWORD_STRENGTH",
+                defaults.password_strength,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitBackend {
+    Memory,
+    Redis { url: String },
+}
+
+impl RateLimitBackend {
+    // Reads RATE_LIMIT_BACKEND (memory|redis, default memory) and REDIS_URL
+    pub fn from_env() -> Result<Self, RateLimitError> {
+        let backend = std::env::var("RATE_LIMIT_BACKEND").unwrap_or_else(|_| "memory".to_string());
+
+        match backend.to_lowercase().as_str() {
+            "memory" => Ok(RateLimitBackend::Memory),
+            "redis" => {
+                let url = std::env::var("REDIS_URL").map_err(|_| {
+                    RateLimitError::Backend(
+                        "REDIS_URL must be set when RATE_LIMIT_BACKEND=redis".to_string(),
+                    )
+                })?;
+                Ok(RateLimitBackend::Redis { url })
+            }
+            other => Err(RateLimitError::UnknownBackend(other.to_string())),
+        }
+    }
+
+    pub fn build(&self) -> Result<Arc<dyn RateLimiter>, RateLimitError> {
+        match self {
+            RateLimitBackend::Memory => Ok(Arc::new(InMemoryRateLimiter::new())),
+            #[cfg(feature = "redis")]
+            RateLimitBackend::Redis { url } => Ok(Arc::new(RedisRateLimiter::new(url)?)),
+            #[cfg(not(feature = "redis"))]
+            RateLimitBackend::Redis { .. } => Err(RateLimitError::Backend(
+                "Chronos was built without the `redis` feature".to_string(),
+            )),
+        }
+    }
+}
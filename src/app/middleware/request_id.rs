@@ -0,0 +1,84 @@
+// Makes the x-request-id of the request being handled available to log_security_event
+use axum::extract::Request;
+use tower::{Layer, Service};
+use tower_http::request_id::RequestId;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// The id of the request this task is handling, if it runs inside RequestIdScopeLayer
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// Carries the current request id into a task spawned while handling the request
+pub fn in_current_request<F: std::future::Future>(
+    future: F,
+) -> impl std::future::Future<Output = F::Output> {
+    let request_id = current_request_id();
+    async move {
+        match request_id {
+            Some(request_id) => REQUEST_ID.scope(request_id, future).await,
+            None => future.await,
+        }
+    }
+}
+
+// Goes inside SetRequestIdLayer so every request already has an id to scope
+#[derive(Clone)]
+pub struct RequestIdScopeLayer;
+
+impl<S> Layer<S> for RequestIdScopeLayer {
+    type Service = RequestIdScopeService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdScopeService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct RequestIdScopeService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RequestIdScopeService<S>
+where
+    S: Service<Request> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let mut inner = self.inner.clone();
+
+        // SetRequestIdLayer stores the id as an extension; fall back to the header
+        let request_id = req
+            .extensions()
+            .get::<RequestId>()
+            .map(|id| id.header_value())
+            .or_else(|| req.headers().get(REQUEST_ID_HEADER))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        Box::pin(async move {
+            match request_id {
+                Some(request_id) => REQUEST_ID.scope(request_id, inner.call(req)).await,
+                None => inner.call(req).await,
+            }
+        })
+    }
+}
@@ -1,200 +1,488 @@
+use crate::app::cookies::{CookiePolicy, read_cookie};
+use crate::app::metrics;
+use crate::app::middleware::ip_filter::{IpFilterError, IpNetwork, parse_list_env};
+use crate::app::middleware::rate_limiter::{
+    InMemoryRateLimiter, RateLimitBackend, RateLimitConfig, RateLimitDecision, RateLimitError,
+    RateLimitRule, RateLimiter,
+};
+use crate::app::middleware::request_id::current_request_id;
+use crate::app::models::auth::{AuthenticationError, normalize_email};
+use crate::app::models::password_reset::PasswordResetToken;
+use crate::app::security_events::{self, SecurityEvent, Severity};
 use axum::{
-    Json,
-    body::Body,
-    extract::{ConnectInfo, Request},
-    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
     response::{IntoResponse, Response},
 };
 use dashmap::DashMap;
-use serde_json::json;
 use std::{
-    net::SocketAddr,
-    sync::Arc,
+    cell::Cell,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, RwLock},
     time::{Duration, Instant},
 };
 use tower::{Layer, Service};
-use tower_http::cors::{Any, CorsLayer};
-use tracing::{error, info, warn};
+use tower_http::cors::CorsLayer;
+use tracing::{error, warn};
+
+const OAUTH_STATE_TTL: Duration = Duration::from_secs(600);
+const FAILED_ATTEMPT_WINDOW: Duration = Duration::from_secs(900);
+
+// Auth requests are small JSON documents; anything bigger is rejected unread
+pub const DEFAULT_AUTH_BODY_LIMIT: usize = 16 * 1024;
 
+// Header clients echo the CSRF cookie in when cookie sessions are enabled
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+// Counter state reported on rate-limited endpoints, see rate_limit_headers
+pub const X_RATELIMIT_LIMIT: &str = "x-ratelimit-limit";
+pub const X_RATELIMIT_REMAINING: &str = "x-ratelimit-remaining";
+pub const X_RATELIMIT_RESET: &str = "x-ratelimit-reset";
+
+// Short-lived, single-use `state` values handed out when an OAuth login starts
 #[derive(Clone)]
-pub struct SecurityState {
-    pub ip_rate_limiter: Arc<DashMap<String, IpRateLimit>>,
-    pub user_rate_limiter: Arc<DashMap<String, UserRateLimit>>,
-    pub email_rate_limiter: Arc<DashMap<String, EmailRateLimit>>,
+pub struct OAuthStateStore {
+    states: Arc<DashMap<String, Instant>>,
+    ttl: Duration,
 }
 
-impl Default for SecurityState {
+impl Default for OAuthStateStore {
     fn default() -> Self {
-        Self::new()
+        Self::new(OAUTH_STATE_TTL)
     }
 }
 
-impl SecurityState {
-    pub fn new() -> Self {
+impl OAuthStateStore {
+    pub fn new(ttl: Duration) -> Self {
         Self {
-            ip_rate_limiter: Arc::new(DashMap::new()),
-            user_rate_limiter: Arc::new(DashMap::new()),
-            email_rate_limiter: Arc::new(DashMap::new()),
+            states: Arc::new(DashMap::new()),
+            ttl,
         }
     }
+
+    pub fn issue(&self) -> String {
+        let now = Instant::now();
+        self.states.retain(|_, expires_at| *expires_at > now);
+
+        let state = PasswordResetToken::generate_secure_token();
+        self.states.insert(state.clone(), now + self.ttl);
+        state
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    // True only the first time a state that has not expired is presented
+    pub fn consume(&self, state: &str) -> bool {
+        self.states
+            .remove(state)
+            .is_some_and(|(_, expires_at)| Instant::now() < expires_at)
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct IpRateLimit {
-    pub registration_attempts: Vec<Instant>,
-    pub login_attempts: Vec<Instant>,
+// Recent failed login and registration attempts per IP, used to decide when a
+// client has to solve a CAPTCHA. Kept in memory, so each instance counts its own.
+#[derive(Clone)]
+pub struct FailedAttemptTracker {
+    attempts: Arc<DashMap<String, Vec<Instant>>>,
+    window: Duration,
 }
 
-impl IpRateLimit {
-    pub fn new() -> Self {
+impl Default for FailedAttemptTracker {
+    fn default() -> Self {
+        Self::new(FAILED_ATTEMPT_WINDOW)
+    }
+}
+
+impl FailedAttemptTracker {
+    pub fn new(window: Duration) -> Self {
         Self {
-            registration_attempts: Vec::new(),
-            login_attempts: Vec::new(),
+            attempts: Arc::new(DashMap::new()),
+            window,
         }
     }
+
+    pub fn record(&self, ip: &str) {
+        let now = Instant::now();
+        // Drop IPs whose latest failure has left the window
+        self.attempts.retain(|_, attempts| {
+            attempts
+                .last()
+                .is_some_and(|at| now.duration_since(*at) < self.window)
+        });
+
+        let mut attempts = self.attempts.entry(ip.to_string()).or_default();
+        attempts.retain(|at| now.duration_since(*at) < self.window);
+        attempts.push(now);
+    }
+
+    // Failures from `ip` within the window
+    pub fn count(&self, ip: &str) -> u32 {
+        let now = Instant::now();
+        self.attempts.get(ip).map_or(0, |attempts| {
+            attempts
+                .iter()
+                .filter(|at| now.duration_since(**at) < self.window)
+                .count() as u32
+        })
+    }
+
+    pub fn clear(&self, ip: &str) {
+        self.attempts.remove(ip);
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct UserRateLimit {
-    pub refresh_attempts: Vec<Instant>,
+#[derive(Clone)]
+pub struct SecurityState {
+    pub rate_limiter: Arc<dyn RateLimiter>,
+    pub config: RateLimitConfig,
+    pub oauth_states: OAuthStateStore,
+    pub failed_attempts: FailedAttemptTracker,
+}
+
+impl Default for SecurityState {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
 }
 
-impl UserRateLimit {
-    pub fn new() -> Self {
+impl SecurityState {
+    pub fn new(config: RateLimitConfig) -> Self {
         Self {
-            refresh_attempts: Vec::new(),
+            rate_limiter: Arc::new(InMemoryRateLimiter::new()),
+            config,
+            oauth_states: OAuthStateStore::default(),
+            failed_attempts: FailedAttemptTracker::default(),
         }
     }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<dyn RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    // Limits from RATE_LIMIT_<ENDPOINT>_*, backend from RATE_LIMIT_BACKEND / REDIS_URL
+    pub fn from_env() -> Result<Self, RateLimitError> {
+        let rate_limiter = RateLimitBackend::from_env()?.build()?;
+        Ok(Self::new(RateLimitConfig::from_env()).with_rate_limiter(rate_limiter))
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct EmailRateLimit {
-    pub password_reset_attempts: Vec<Instant>,
+// Counter state of a rate-limited endpoint, sent back as the X-RateLimit-* headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    pub limit: usize,
+    pub remaining: usize,
+    // Seconds until the oldest counted request leaves the window
+    pub reset_after: u64,
 }
 
-impl EmailRateLimit {
-    pub fn new() -> Self {
-        Self {
-            password_reset_attempts: Vec::new(),
+impl RateLimitStatus {
+    pub fn write_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(X_RATELIMIT_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(X_RATELIMIT_REMAINING, HeaderValue::from(self.remaining));
+        headers.insert(X_RATELIMIT_RESET, HeaderValue::from(self.reset_after));
+    }
+
+    // Where a request counts against several limits, the one closest to running out
+    fn tightest(self, other: Option<Self>) -> Self {
+        match other {
+            Some(other) if other.remaining < self.remaining => other,
+            _ => self,
         }
     }
 }
 
-pub fn check_registration_rate_limit(
+tokio::task_local! {
+    static RATE_LIMIT_STATUS: Cell<Option<RateLimitStatus>>;
+}
+
+// Remembers the status for rate_limit_headers; a no-op outside that middleware
+fn record_rate_limit_status(status: RateLimitStatus) {
+    let _ = RATE_LIMIT_STATUS.try_with(|current| current.set(Some(status.tightest(current.get()))));
+}
+
+// Round up so clients never retry before the window has moved on
+fn whole_seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+// Adds X-RateLimit-* headers to responses from handlers that ran a check_*_rate_limit
+pub async fn rate_limit_headers(request: Request, next: Next) -> Response {
+    RATE_LIMIT_STATUS
+        .scope(Cell::new(None), async move {
+            let mut response = next.run(request).await;
+            if let Some(status) = RATE_LIMIT_STATUS.with(Cell::get) {
+                status.write_headers(response.headers_mut());
+            }
+            response
+        })
+        .await
+}
+
+// Ok(None) when the backend is unavailable and the request was let through unchecked
+async fn enforce_rate_limit(
+    security_state: &SecurityState,
+    name: &str,
+    rule: RateLimitRule,
+    subject: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let key = format!("{}:{}", name, subject);
+
+    match security_state
+        .rate_limiter
+        .check(&key, rule.max_attempts, rule.window)
+        .await
+    {
+        Ok(RateLimitDecision::Allowed {
+            remaining,
+            reset_after,
+        }) => {
+            let status = RateLimitStatus {
+                limit: rule.max_attempts,
+                remaining,
+                reset_after: whole_seconds(reset_after),
+            };
+            record_rate_limit_status(status);
+            Ok(Some(status))
+        }
+        Ok(RateLimitDecision::Limited { retry_after }) => {
+            warn!("{} rate limit exceeded for: {}", name, subject);
+            metrics::record_rate_limit_hit(name);
+            let retry_after = whole_seconds(retry_after).max(1);
+            record_rate_limit_status(RateLimitStatus {
+                limit: rule.max_attempts,
+                remaining: 0,
+                reset_after: retry_after,
+            });
+            Err(AuthenticationError::RateLimitExceeded { retry_after })
+        }
+        Err(e) => {
+            // Fail open so an unavailable backend doesn't take authentication down with it
+            error!("Rate limiter unavailable, allowing {} request: {}", name, e);
+            Ok(None)
+        }
+    }
+}
+
+pub async fn check_registration_rate_limit(
     security_state: &SecurityState,
     ip: &str,
-) -> Result<(), Response> {
-    let mut ip_limit = security_state
-        .ip_rate_limiter
-        .entry(ip.to_string())
-        .or_insert_with(IpRateLimit::new);
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.registration;
+    enforce_rate_limit(security_state, "registration", rule, ip).await
+}
 
-    let now = Instant::now();
-    let one_hour_ago = now - Duration::from_secs(3600);
+// Separate from account lockout: the IP limit stops one client from trying many
+// accounts, the email limit stops many clients from trying one account
+pub async fn check_login_rate_limit(
+    security_state: &SecurityState,
+    ip: &str,
+    email: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.login;
+    let ip_status = enforce_rate_limit(security_state, "login", rule, ip).await?;
+
+    let rule = security_state.config.login_email;
+    let email = normalize_email(email).to_lowercase();
+    let email_status = enforce_rate_limit(security_state, "login_email", rule, &email).await?;
+    Ok(match ip_status {
+        Some(ip_status) => Some(ip_status.tightest(email_status)),
+        None => email_status,
+    })
+}
 
-    ip_limit
-        .registration_attempts
-        .retain(|&time| time > one_hour_ago);
+pub async fn check_refresh_rate_limit(
+    security_state: &SecurityState,
+    user_id: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.refresh;
+    enforce_rate_limit(security_state, "refresh", rule, user_id).await
+}
 
-    if ip_limit.registration_attempts.len() >= 5 {
-        warn!("Registration rate limit exceeded for IP: {}", ip);
-        let response = Json(json!({
-            "error": "Rate limit exceeded",
-            "message": "Too many registration attempts. Please try again later.",
-            "retry_after": 3600
-        }));
-        return Err((StatusCode::TOO_MANY_REQUESTS, response).into_response());
-    }
+pub async fn check_password_reset_rate_limit(
+    security_state: &SecurityState,
+    email: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.password_reset;
+    enforce_rate_limit(security_state, "password_reset", rule, email).await
+}
+
+pub async fn check_reset_token_validation_rate_limit(
+    security_state: &SecurityState,
+    ip: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.password_reset_validate;
+    enforce_rate_limit(security_state, "password_reset_validate", rule, ip).await
+}
+
+pub async fn check_lockout_status_rate_limit(
+    security_state: &SecurityState,
+    ip: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.lockout_status;
+    enforce_rate_limit(security_state, "lockout_status", rule, ip).await
+}
 
-    ip_limit.registration_attempts.push(now);
-    Ok(())
+pub async fn check_account_unlock_rate_limit(
+    security_state: &SecurityState,
+    email: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.account_unlock;
+    enforce_rate_limit(security_state, "account_unlock", rule, email).await
 }
 
-pub fn check_login_rate_limit(security_state: &SecurityState, ip: &str) -> Result<(), Response> {
-    let mut ip_limit = security_state
-        .ip_rate_limiter
-        .entry(ip.to_string())
-        .or_insert_with(IpRateLimit::new);
+pub async fn check_email_code_rate_limit(
+    security_state: &SecurityState,
+    email: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.email_code;
+    enforce_rate_limit(security_state, "email_code", rule, email).await
+}
 
-    let now = Instant::now();
-    let fifteen_minutes_ago = now - Duration::from_secs(900);
+pub async fn check_password_strength_rate_limit(
+    security_state: &SecurityState,
+    ip: &str,
+) -> Result<Option<RateLimitStatus>, AuthenticationError> {
+    let rule = security_state.config.password_strength;
+    enforce_rate_limit(security_state, "password_strength", rule, ip).await
+}
 
-    ip_limit
-        .login_attempts
-        .retain(|&time| time > fifteen_minutes_ago);
+pub fn generate_csrf_token() -> String {
+    PasswordResetToken::generate_secure_token()
+}
+
+// Double-submit check for cookie sessions: a state-changing request that
+// carries one of our cookies must repeat the CSRF cookie in X-CSRF-Token.
+// Requests without cookies (Bearer clients, a first login) have nothing a
+// forged request could ride on, so they pass.
+pub async fn csrf_protection(
+    State(cookie_policy): State<CookiePolicy>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method().is_safe() {
+        return next.run(request).await;
+    }
 
-    if ip_limit.login_attempts.len() >= 5 {
-        warn!("Login rate limit exceeded for IP: {}", ip);
-        let response = Json(json!({
-            "error": "Rate limit exceeded",
-            "message": "Too many login attempts. Please try again in 15 minutes.",
-            "retry_after": 900
-        }));
-        return Err((StatusCode::TOO_MANY_REQUESTS, response).into_response());
+    let headers = request.headers();
+    let csrf_cookie = read_cookie(headers, &cookie_policy.csrf_cookie_name());
+    let has_session = csrf_cookie.is_some()
+        || read_cookie(headers, &cookie_policy.refresh_cookie_name()).is_some()
+        || read_cookie(headers, &cookie_policy.access_cookie_name()).is_some();
+    if !has_session {
+        return next.run(request).await;
     }
 
-    ip_limit.login_attempts.push(now);
-    Ok(())
+    let csrf_header = headers
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok());
+    match (csrf_cookie, csrf_header) {
+        (Some(cookie), Some(header)) if !cookie.is_empty() && constant_time_eq(cookie, header) => {
+            next.run(request).await
+        }
+        _ => {
+            warn!(path = %request.uri().path(), "Rejected request with a missing or mismatched CSRF token");
+            AuthenticationError::CsrfTokenInvalid.into_response()
+        }
+    }
 }
 
-pub fn check_refresh_rate_limit(
-    security_state: &SecurityState,
-    user_id: &str,
-) -> Result<(), Response> {
-    let mut user_limit = security_state
-        .user_rate_limiter
-        .entry(user_id.to_string())
-        .or_insert_with(UserRateLimit::new);
+pub(crate) fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
 
-    let now = Instant::now();
-    let one_minute_ago = now - Duration::from_secs(60);
+// STRICT_CONTENT_TYPE, off by default so lenient clients keep working
+pub fn get_strict_content_type() -> bool {
+    std::env::var("STRICT_CONTENT_TYPE")
+        .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        .unwrap_or(false)
+}
 
-    user_limit
-        .refresh_attempts
-        .retain(|&time| time > one_minute_ago);
+// Strict mode for the JSON routes: a state-changing request with a body must
+// declare it as application/json instead of leaving the extractor to guess.
+// Safe methods and empty bodies (a cookie-session refresh) have nothing to
+// parse, so they pass.
+pub async fn require_json_content_type(request: Request, next: Next) -> Response {
+    if request.method().is_safe() || request.body().size_hint().exact() == Some(0) {
+        return next.run(request).await;
+    }
 
-    if user_limit.refresh_attempts.len() >= 10 {
-        warn!("Token refresh rate limit exceeded for user: {}", user_id);
-        let response = Json(json!({
-            "error": "Rate limit exceeded",
-            "message": "Too many token refresh attempts. Please wait before retrying.",
-            "retry_after": 60
-        }));
-        return Err((StatusCode::TOO_MANY_REQUESTS, response).into_response());
+    let is_json = request
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        warn!(path = %request.uri().path(), "Rejected request without a JSON Content-Type");
+        return AuthenticationError::UnsupportedMediaType.into_response();
     }
+    next.run(request).await
+}
 
-    user_limit.refresh_attempts.push(now);
-    Ok(())
+lazy_static::lazy_static! {
+    static ref TRUSTED_PROXIES: RwLock<Vec<IpNetwork>> = RwLock::new(Vec::new());
 }
 
-pub fn check_password_reset_rate_limit(
-    security_state: &SecurityState,
-    email: &str,
-) -> Result<(), Response> {
-    let mut email_limit = security_state
-        .email_rate_limiter
-        .entry(email.to_string())
-        .or_insert_with(EmailRateLimit::new);
+// TRUSTED_PROXIES, comma-separated CIDRs of the proxies in front of the server;
+// empty by default, so forwarding headers are ignored
+pub fn trusted_proxies_from_env() -> Result<Vec<IpNetwork>, IpFilterError> {
+    parse_list_env("TRUSTED_PROXIES")
+}
 
-    let now = Instant::now();
-    let one_hour_ago = now - Duration::from_secs(3600);
+// Applies to every request handled from now on
+pub fn set_trusted_proxies(proxies: Vec<IpNetwork>) {
+    *TRUSTED_PROXIES.write().unwrap() = proxies;
+}
 
-    email_limit
-        .password_reset_attempts
-        .retain(|&time| time > one_hour_ago);
+// Client address as a string, see `client_ip`
+pub fn extract_real_ip(addr: SocketAddr, headers: &HeaderMap) -> String {
+    client_ip(addr, headers, &TRUSTED_PROXIES.read().unwrap()).to_string()
+}
 
-    if email_limit.password_reset_attempts.len() >= 3 {
-        warn!("Password reset rate limit exceeded for email: {}", email);
-        let response = Json(json!({
-            "error": "Rate limit exceeded",
-            "message": "Too many password reset requests. Please try again later.",
-            "retry_after": 3600
-        }));
-        return Err((StatusCode::TOO_MANY_REQUESTS, response).into_response());
+// The socket address, unless it belongs to a trusted proxy. Then the client is
+// the last X-Forwarded-For hop that isn't a trusted proxy itself, since every
+// proxy appends the address it received the request from and anything to the
+// left of our own proxies is whatever the client chose to send. X-Real-IP is
+// the fallback when there is no usable X-Forwarded-For.
+pub fn client_ip(addr: SocketAddr, headers: &HeaderMap, trusted_proxies: &[IpNetwork]) -> IpAddr {
+    let is_trusted = |ip: IpAddr| trusted_proxies.iter().any(|network| network.contains(ip));
+    if !is_trusted(addr.ip()) {
+        return addr.ip();
     }
 
-    email_limit.password_reset_attempts.push(now);
-    Ok(())
+    let forwarded_for = headers
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok());
+    if let Some(forwarded_for) = forwarded_for {
+        let mut client = None;
+        for hop in forwarded_for.split(',').rev() {
+            let Ok(ip) = hop.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = Some(ip);
+            if !is_trusted(ip) {
+                break;
+            }
+        }
+        if let Some(client) = client {
+            return client;
+        }
+    }
+
+    headers
+        .get("x-real-ip")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or_else(|| addr.ip())
 }
 
 pub fn log_security_event(
@@ -206,27 +494,70 @@ pub fn log_security_event(
     success: bool,
     details: Option<&str>,
 ) {
-    if success {
-        info!(
-            event_type = event_type,
-            ip_address = ip,
-            user_agent = user_agent,
-            user_id = user_id,
-            email = email,
-            details = details,
-            "Security event logged"
-        );
-    } else {
-        warn!(
-            event_type = event_type,
-            ip_address = ip,
-            user_agent = user_agent,
-            user_id = user_id,
-            email = email,
-            details = details,
-            "Failed security event logged"
-        );
-    }
+    security_events::dispatch(&SecurityEvent {
+        event_type: event_type.to_string(),
+        severity: Severity::for_event(event_type, success),
+        // Set by RequestIdScopeLayer; None outside an HTTP request
+        request_id: current_request_id(),
+        ip_address: ip.to_string(),
+        user_agent: user_agent.map(str::to_string),
+        user_id: user_id.map(str::to_string),
+        email: email.map(str::to_string),
+        success,
+        details: details.map(str::to_string),
+        occurred_at: time::OffsetDateTime::now_utc(),
+        country: None,
+        region: None,
+    });
+}
+
+// AUTH_BODY_LIMIT_BYTES, default 16 KiB; zero or unparseable values fall back to the default
+pub fn get_auth_body_limit() -> usize {
+    std::env::var("AUTH_BODY_LIMIT_BYTES")
+        .ok()
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_AUTH_BODY_LIMIT)
+}
+
+// Response headers browser clients may read beyond the CORS-safelisted ones
+pub const DEFAULT_CORS_EXPOSE_HEADERS: &[&str] = &[
+    "retry-after",
+    "x-request-id",
+    "content-disposition",
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset",
+    "x-total-count",
+    "link",
+    "www-authenticate",
+];
+
+// CORS_EXPOSE_HEADERS (comma-separated) replaces the default list; invalid names are skipped
+pub fn cors_expose_headers() -> Vec<HeaderName> {
+    let configured = std::env::var("CORS_EXPOSE_HEADERS").ok();
+    let names: Vec<&str> = match configured.as_deref() {
+        Some(list) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect(),
+        None => DEFAULT_CORS_EXPOSE_HEADERS.to_vec(),
+    };
+
+    names
+        .into_iter()
+        .filter_map(|name| match HeaderName::try_from(name) {
+            Ok(header) => Some(header),
+            Err(_) => {
+                warn!(
+                    header = name,
+                    "Ignoring invalid header name in CORS_EXPOSE_HEADERS"
+                );
+                None
+            }
+        })
+        .collect()
 }
 
 pub fn get_cors_layer() -> CorsLayer {
@@ -247,25 +578,194 @@ pub fn get_cors_layer() -> CorsLayer {
             axum::http::header::AUTHORIZATION,
             axum::http::header::CONTENT_TYPE,
             axum::http::header::ACCEPT,
+            HeaderName::from_static(CSRF_HEADER),
         ])
+        .expose_headers(cors_expose_headers())
         .allow_credentials(true)
         .max_age(Duration::from_secs(3600))
 }
 
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; script-src 'self' 'unsafe-inline'; style-src 'self' 'unsafe-inline'; img-src 'self' data: https:; font-src 'self'; connect-src 'self'; frame-ancestors 'none';";
+pub const DEFAULT_HSTS_MAX_AGE: u64 = 31_536_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SecurityHeadersConfigError {
+    #[error("{0} is not a valid header value")]
+    InvalidValue(&'static str),
+    #[error("{0} must be a non-negative integer")]
+    InvalidMaxAge(&'static str),
+}
+
+// Values of the headers SecurityHeadersLayer adds to every response; None
+// leaves that header out. X-Content-Type-Options and X-XSS-Protection are
+// always sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityHeadersConfig {
+    pub content_security_policy: Option<String>,
+    // None sends no Strict-Transport-Security, e.g. in development over plain HTTP
+    pub hsts: Option<HstsConfig>,
+    pub frame_options: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub permissions_policy: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HstsConfig {
+    pub max_age: u64,
+    pub include_subdomains: bool,
+    pub preload: bool,
+}
+
+impl HstsConfig {
+    pub fn header_value(&self) -> String {
+        let mut value = format!("max-age={}", self.max_age);
+        if self.include_subdomains {
+            value.push_str("; includeSubDomains");
+        }
+        if self.preload {
+            value.push_str("; preload");
+        }
+        value
+    }
+}
+
+impl Default for HstsConfig {
+    fn default() -> Self {
+        Self {
+            max_age: DEFAULT_HSTS_MAX_AGE,
+            include_subdomains: true,
+            preload: true,
+        }
+    }
+}
+
+impl Default for SecurityHeadersConfig {
+    fn default() -> Self {
+        Self {
+            content_security_policy: Some(DEFAULT_CONTENT_SECURITY_POLICY.to_string()),
+            hsts: Some(HstsConfig::default()),
+            frame_options: Some("DENY".to_string()),
+            referrer_policy: Some("strict-origin-when-cross-origin".to_string()),
+            permissions_policy: Some("geolocation=(), microphone=(), camera=()".to_string()),
+        }
+    }
+}
+
+impl SecurityHeadersConfig {
+    // SECURITY_CSP, SECURITY_FRAME_OPTIONS, SECURITY_REFERRER_POLICY and
+    // SECURITY_PERMISSIONS_POLICY replace the defaults, and an empty value leaves
+    // the header out. SECURITY_HSTS_MAX_AGE=0 turns HSTS off;
+    // SECURITY_HSTS_INCLUDE_SUBDOMAINS and SECURITY_HSTS_PRELOAD default to true.
+    pub fn from_env() -> Result<Self, SecurityHeadersConfigError> {
+        let default = Self::default();
+        let header = |name: &'static str, default: Option<String>| match std::env::var(name) {
+            Ok(value) if value.trim().is_empty() => Ok(None),
+            Ok(value) => match HeaderValue::from_str(value.trim()) {
+                Ok(_) => Ok(Some(value.trim().to_string())),
+                Err(_) => Err(SecurityHeadersConfigError::InvalidValue(name)),
+            },
+            Err(_) => Ok(default),
+        };
+        let enabled = |name: &str| match std::env::var(name) {
+            Ok(value) => !(value.eq_ignore_ascii_case("false") || value.trim() == "0"),
+            Err(_) => true,
+        };
+
+        let max_age = match std::env::var("SECURITY_HSTS_MAX_AGE") {
+            Ok(value) if !value.trim().is_empty() => value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| SecurityHeadersConfigError::InvalidMaxAge("SECURITY_HSTS_MAX_AGE"))?,
+            _ => DEFAULT_HSTS_MAX_AGE,
+        };
+        let hsts = (max_age > 0).then(|| HstsConfig {
+            max_age,
+            include_subdomains: enabled("SECURITY_HSTS_INCLUDE_SUBDOMAINS"),
+            preload: enabled("SECURITY_HSTS_PRELOAD"),
+        });
+
+        Ok(Self {
+            content_security_policy: header("SECURITY_CSP", default.content_security_policy)?,
+            hsts,
+            frame_options: header("SECURITY_FRAME_OPTIONS", default.frame_options)?,
+            referrer_policy: header("SECURITY_REFERRER_POLICY", default.referrer_policy)?,
+            permissions_policy: header("SECURITY_PERMISSIONS_POLICY", default.permissions_policy)?,
+        })
+    }
+
+    // Values that aren't valid in a header are logged and left out
+    fn headers(&self) -> Vec<(HeaderName, HeaderValue)> {
+        let configured = [
+            (
+                "content-security-policy",
+                self.content_security_policy.clone(),
+            ),
+            (
+                "strict-transport-security",
+                self.hsts.map(|hsts| hsts.header_value()),
+            ),
+            ("x-frame-options", self.frame_options.clone()),
+            ("referrer-policy", self.referrer_policy.clone()),
+            ("permissions-policy", self.permissions_policy.clone()),
+        ];
+
+        let mut headers = vec![
+            (
+                HeaderName::from_static("x-content-type-options"),
+                HeaderValue::from_static("nosniff"),
+            ),
+            (
+                HeaderName::from_static("x-xss-protection"),
+                HeaderValue::from_static("1; mode=block"),
+            ),
+        ];
+        for (name, value) in configured {
+            let Some(value) = value else {
+                continue;
+            };
+            match HeaderValue::from_str(&value) {
+                Ok(value) => headers.push((HeaderName::from_static(name), value)),
+                Err(_) => warn!(header = name, "Ignoring invalid security header value"),
+            }
+        }
+        headers
+    }
+}
+
 #[derive(Clone)]
-pub struct SecurityHeadersLayer;
+pub struct SecurityHeadersLayer {
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl SecurityHeadersLayer {
+    pub fn new(config: &SecurityHeadersConfig) -> Self {
+        Self {
+            headers: Arc::new(config.headers()),
+        }
+    }
+}
+
+impl Default for SecurityHeadersLayer {
+    fn default() -> Self {
+        Self::new(&SecurityHeadersConfig::default())
+    }
+}
 
 impl<S> Layer<S> for SecurityHeadersLayer {
     type Service = SecurityHeadersService<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        SecurityHeadersService { inner }
+        SecurityHeadersService {
+            inner,
+            headers: self.headers.clone(),
+        }
     }
 }
 
 #[derive(Clone)]
 pub struct SecurityHeadersService<S> {
     inner: S,
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
 }
 
 impl<S> Service<Request> for SecurityHeadersService<S>
@@ -288,77 +788,22 @@ where
 
     fn call(&mut self, req: Request) -> Self::Future {
         let mut inner = self.inner.clone();
+        let security_headers = self.headers.clone();
 
         Box::pin(async move {
             let mut response = inner.call(req).await?;
 
             let headers = response.headers_mut();
-
-            headers.insert(
-                HeaderName::from_static("x-content-type-options"),
-                HeaderValue::from_static("nosniff"),
-            );
-
-            headers.insert(
-                HeaderName::from_static("x-frame-options"),
-                HeaderValue::from_static("DENY"),
-            );
-
-            headers.insert(
-                HeaderName::from_static("x-xss-protection"),
-                HeaderValue::from_static("1; mode=block"),
-            );
-
-            headers.insert(
-                HeaderName::from_static("strict-transport-security"),
-                HeaderValue::from_static("max-age=31536000; includeSubDomains; preload"),
-            );
-
-            headers.insert(
-                HeaderName::from_static("content-security-policy"),
-                HeaderValue::from_static("default-src 'self'; script-src 'self' 'unsafe-inline'; style-src 'self' 'unsafe-inline'; img-src 'self' data: https:; font-src 'self'; connect-src 'self'; frame-ancestors 'none';"),
-            );
-
-            headers.insert(
-                HeaderName::from_static("referrer-policy"),
-                HeaderValue::from_static("strict-origin-when-cross-origin"),
-            );
-
-            headers.insert(
-                HeaderName::from_static("permissions-policy"),
-                HeaderValue::from_static("geolocation=(), microphone=(), camera=()"),
-            );
+            for (name, value) in security_headers.iter() {
+                headers.insert(name.clone(), value.clone());
+            }
 
             Ok(response)
         })
     }
 }
 
-pub fn cleanup_rate_limiters(security_state: &SecurityState) {
-    let now = Instant::now();
+pub async fn cleanup_rate_limiters(security_state: &SecurityState) {
     let cleanup_threshold = Duration::from_secs(7200); // 2 hours
-
-    security_state.ip_rate_limiter.retain(|_, limit| {
-        limit
-            .registration_attempts
-            .retain(|&time| now.duration_since(time) < cleanup_threshold);
-        limit
-            .login_attempts
-            .retain(|&time| now.duration_since(time) < cleanup_threshold);
-        !limit.registration_attempts.is_empty() || !limit.login_attempts.is_empty()
-    });
-
-    security_state.user_rate_limiter.retain(|_, limit| {
-        limit
-            .refresh_attempts
-            .retain(|&time| now.duration_since(time) < cleanup_threshold);
-        !limit.refresh_attempts.is_empty()
-    });
-
-    security_state.email_rate_limiter.retain(|_, limit| {
-        limit
-            .password_reset_attempts
-            .retain(|&time| now.duration_since(time) < cleanup_threshold);
-        !limit.password_reset_attempts.is_empty()
-    });
+    security_state.rate_limiter.cleanup(cleanup_threshold).await;
 }
@@ -0,0 +1,53 @@
+// Answers 504 when a handler takes longer than the deadline, e.g. behind a
+// stalled database or mail provider. The handler runs in its own task and is
+// left to finish after the deadline, so a request is never cut off halfway
+// through, say, changing a password and revoking the old sessions.
+use crate::app::middleware::request_id::in_current_request;
+use crate::app::models::common::ErrorResponse;
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+use tracing::{Instrument, Span, warn};
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// REQUEST_TIMEOUT_SECS, default 10; 0 turns the deadline off and unparseable values keep the default
+pub fn get_request_timeout() -> Option<Duration> {
+    let timeout = std::env::var("REQUEST_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+    (!timeout.is_zero()).then_some(timeout)
+}
+
+pub async fn request_timeout(
+    State(timeout): State<Duration>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let mut handler = tokio::spawn(in_current_request(
+        next.run(request).instrument(Span::current()),
+    ));
+
+    match tokio::time::timeout(timeout, &mut handler).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(error)) => std::panic::resume_unwind(error.into_panic()),
+        Err(_) => {
+            warn!(path = %path, timeout_secs = timeout.as_secs_f64(), "Request timed out");
+            (
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(ErrorResponse {
+                    error: "Request timed out".to_string(),
+                }),
+            )
+                .into_response()
+        }
+    }
+}
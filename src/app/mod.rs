@@ -1,4 +1,17 @@
+pub mod clock;
+pub mod cookies;
+pub mod geolocation;
+pub mod hashing;
+pub mod maintenance;
+pub mod metrics;
 pub mod middleware;
 pub mod models;
 pub mod repositories;
+pub mod security_events;
 pub mod services;
+pub mod signing_key_cipher;
+pub mod signing_key_listener;
+pub mod task_health;
+pub mod telemetry;
+pub mod token_epoch_listener;
+pub mod token_epochs;
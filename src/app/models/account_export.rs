@@ -0,0 +1,108 @@
+use crate::app::models::admin_audit::AdminAuditEntry;
+use crate::app::models::auth::ProfileResponse;
+use crate::app::models::login_attempt::{AccountLockout, LoginAttempt, RefreshTokenStorage};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const ACCOUNT_LOCKED_EVENT: &str = "account_locked";
+
+// Everything Chronos stores about a user, for GET /api/auth/account/export.
+// Password hashes, token hashes and JTIs are never included.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccountExport {
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    pub profile: ProfileResponse,
+    pub sessions: Vec<SessionExport>,
+    pub login_attempts: Vec<LoginAttemptExport>,
+    pub security_events: Vec<SecurityEventExport>,
+}
+
+// An active refresh token, identified by its row id rather than its JTI
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionExport {
+    pub id: Uuid,
+    pub device_name: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub created_at: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub last_used_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+impl From<RefreshTokenStorage> for SessionExport {
+    fn from(token: RefreshTokenStorage) -> Self {
+        Self {
+            id: token.id,
+            device_name: token.device_name,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+// Failed attempts can come from anyone who typed the user's email, so their
+// IP address and user agent are left out
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LoginAttemptExport {
+    pub success: bool,
+    pub failure_reason: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl From<LoginAttempt> for LoginAttemptExport {
+    fn from(attempt: LoginAttempt) -> Self {
+        let (ip_address, user_agent) = if attempt.success {
+            (Some(attempt.ip_address), attempt.user_agent)
+        } else {
+            (None, None)
+        };
+
+        Self {
+            success: attempt.success,
+            failure_reason: attempt.failure_reason,
+            ip_address,
+            user_agent,
+            created_at: attempt.created_at,
+        }
+    }
+}
+
+// Lockouts and admin actions taken on the account; the acting admin is not named
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SecurityEventExport {
+    pub event: String,
+    pub details: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub occurred_at: OffsetDateTime,
+}
+
+impl From<AccountLockout> for SecurityEventExport {
+    fn from(lockout: AccountLockout) -> Self {
+        Self {
+            event: ACCOUNT_LOCKED_EVENT.to_string(),
+            details: Some(format!(
+                "Locked after {} failed attempts",
+                lockout.failed_attempts
+            )),
+            occurred_at: lockout.locked_at,
+        }
+    }
+}
+
+impl From<AdminAuditEntry> for SecurityEventExport {
+    fn from(entry: AdminAuditEntry) -> Self {
+        Self {
+            event: entry.action,
+            details: entry.details,
+            occurred_at: entry.created_at,
+        }
+    }
+}
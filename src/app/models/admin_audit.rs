@@ -0,0 +1,254 @@
+use crate::app::hashing::{HashFormat, PasswordHasher};
+use crate::app::models::auth::{
+    normalize_email, normalize_username, validate_email, validate_password, validate_username,
+};
+use crate::app::models::user::{ImportOutcome, User, UserStatus};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+pub const ADMIN_PASSWORD_RESET_ACTION: &str = "admin_password_reset";
+pub const ADMIN_DISABLE_USER_ACTION: &str = "admin_user_disabled";
+pub const ADMIN_ENABLE_USER_ACTION: &str = "admin_user_enabled";
+pub const ADMIN_REVOKE_TOKENS_ACTION: &str = "admin_tokens_revoked";
+pub const ADMIN_ROTATE_SIGNING_KEY_ACTION: &str = "admin_signing_key_rotated";
+pub const ADMIN_IMPORT_USERS_ACTION: &str = "admin_users_imported";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminAuditEntry {
+    pub id: Uuid,
+    pub admin_id: Uuid,
+    pub action: String,
+    pub target_user_id: Option<Uuid>,
+    pub details: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminPasswordResetResponse {
+    pub message: String,
+    pub user_id: Uuid,
+    pub reset_token: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUserStatusResponse {
+    pub message: String,
+    pub user_id: Uuid,
+    pub status: UserStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminTokenRevocationResponse {
+    pub message: String,
+    pub user_id: Uuid,
+    pub revoked_sessions: u64,
+    // False when some refresh tokens are still marked active; their access tokens
+    // and refresh tokens are rejected regardless
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdminKeyRotationRequest {
+    // Drop every other key too, invalidating all tokens issued so far
+    #[serde(default)]
+    pub revoke_old: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminKeyRotationResponse {
+    pub message: String,
+    pub kid: String,
+    pub revoked_kids: Vec<String>,
+}
+
+// Aggregate numbers for the admin dashboard; sessions are unrevoked, unexpired refresh tokens
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminStatsResponse {
+    pub total_users: i64,
+    pub new_users_24h: i64,
+    pub new_users_7d: i64,
+    pub new_users_30d: i64,
+    pub active_sessions: i64,
+    pub failed_logins_24h: i64,
+}
+
+// What an import does with a row whose email is already registered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportDuplicatePolicy {
+    #[default]
+    Skip,
+    // Replace the name, username and password of the existing account
+    Update,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdminUserImportQuery {
+    #[serde(default)]
+    pub on_duplicate: ImportDuplicatePolicy,
+}
+
+// One user to import, with either a plaintext `password`, hashed on import,
+// or a `password_hash` made with the declared `hash_scheme`. Every field has a
+// default so a bad row is reported on its own instead of failing the request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdminUserImportRow {
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub password_hash: Option<String>,
+    #[serde(default)]
+    pub hash_scheme: Option<String>,
+}
+
+impl AdminUserImportRow {
+    // The user to insert for this row; the error is the message reported for it
+    pub async fn into_user(
+        self,
+        hasher: &PasswordHasher,
+        usernames_enabled: bool,
+    ) -> Result<User, String> {
+        if validate_email(&self.email).is_err() {
+            return Err("Invalid email format".to_string());
+        }
+        let email = normalize_email(&self.email);
+
+        let username = match self.username.as_deref() {
+            Some(_) if !usernames_enabled => return Err("Usernames are not enabled".to_string()),
+            Some(username) => {
+                let username = normalize_username(username);
+                if validate_username(&username).is_err() {
+                    return Err("Invalid username".to_string());
+                }
+                Some(username)
+            }
+            None => None,
+        };
+
+        let password_hash = match (self.password, self.password_hash) {
+            (Some(password), None) => {
+                if validate_password(&password).is_err() {
+                    return Err("Password must be at least 8 characters long, contain at least one uppercase letter, one lowercase letter, one number, and one special character".to_string());
+                }
+                hasher
+                    .hash_password_async(&password)
+                    .await
+                    .map_err(|e| e.to_string())?
+            }
+            (None, Some(hash)) => {
+                let scheme = self
+                    .hash_scheme
+                    .ok_or_else(|| "hash_scheme is required with password_hash".to_string())?;
+                let format = HashFormat::from_scheme(&scheme)
+                    .ok_or_else(|| format!("Unsupported hash_scheme '{}'", scheme))?;
+                if !format.is_valid_hash(&hash) {
+                    return Err(format!("password_hash is not a valid {} hash", scheme));
+                }
+                hash
+            }
+            _ => return Err("Give either password or password_hash".to_string()),
+        };
+
+        let mut user = User::with_password_hash(self.name, email, password_hash);
+        user.username = username;
+        Ok(user)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Created,
+    Updated,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUserImportResult {
+    // Position of the row in the request, from 0
+    pub index: usize,
+    pub email: String,
+    pub status: ImportRowStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AdminUserImportResult {
+    pub fn failed(index: usize, email: String, error: String) -> Self {
+        Self {
+            index,
+            email,
+            status: ImportRowStatus::Failed,
+            user_id: None,
+            error: Some(error),
+        }
+    }
+
+    pub fn from_outcome(index: usize, email: String, outcome: ImportOutcome) -> Self {
+        let (status, user_id, error) = match outcome {
+            ImportOutcome::Created(id) => (ImportRowStatus::Created, Some(id), None),
+            ImportOutcome::Updated(id) => (ImportRowStatus::Updated, Some(id), None),
+            ImportOutcome::Skipped(id) => (ImportRowStatus::Skipped, Some(id), None),
+            ImportOutcome::Failed(error) => (ImportRowStatus::Failed, None, Some(error)),
+        };
+        Self {
+            index,
+            email,
+            status,
+            user_id,
+            error,
+        }
+    }
+}
+
+// Per-row results in request order, with totals per status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminUserImportResponse {
+    pub message: String,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub results: Vec<AdminUserImportResult>,
+}
+
+impl AdminUserImportResponse {
+    pub fn new(results: Vec<AdminUserImportResult>) -> Self {
+        let count = |status| results.iter().filter(|r| r.status == status).count();
+        let (created, updated, skipped, failed) = (
+            count(ImportRowStatus::Created),
+            count(ImportRowStatus::Updated),
+            count(ImportRowStatus::Skipped),
+            count(ImportRowStatus::Failed),
+        );
+
+        Self {
+            message: format!(
+                "Imported {} users: {} created, {} updated, {} skipped, {} failed",
+                results.len(),
+                created,
+                updated,
+                skipped,
+                failed
+            ),
+            created,
+            updated,
+            skipped,
+            failed,
+            results,
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::app::models::password_reset::PasswordResetToken;
+use crate::app::models::scope::{default_scopes, is_known_scope};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// Marks a bearer token as an API key rather than a JWT
+pub const API_KEY_PREFIX: &str = "chr_";
+
+// The prefix followed by 64 random characters
+pub fn generate_api_key() -> String {
+    format!(
+        "{}{}",
+        API_KEY_PREFIX,
+        PasswordResetToken::generate_secure_token()
+    )
+}
+
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_PREFIX)
+}
+
+// Trimmed and deduplicated, in the order given; no scopes at all means every
+// scope. Err with the first scope that isn't one of ALL_SCOPES.
+pub fn normalize_scopes(scopes: &[String]) -> Result<Vec<String>, String> {
+    if scopes.is_empty() {
+        return Ok(default_scopes());
+    }
+
+    let mut normalized: Vec<String> = Vec::with_capacity(scopes.len());
+    for scope in scopes {
+        let scope = scope.trim();
+        if !is_known_scope(scope) {
+            return Err(scope.to_string());
+        }
+        if !normalized.iter().any(|seen| seen == scope) {
+            normalized.push(scope.to_string());
+        }
+    }
+    Ok(normalized)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: Option<String>,
+    pub scopes: Vec<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+// A key found by its hash, with what the auth middleware needs about its owner
+#[derive(Debug, Clone)]
+pub struct ApiKeyOwner {
+    pub key_id: Uuid,
+    pub user_id: Uuid,
+    pub email: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<OffsetDateTime>,
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // See ALL_SCOPES; left out or empty grants all of them
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    // The key never expires when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyCreatedResponse {
+    pub id: Uuid,
+    // The only time the plaintext key is shown
+    pub key: String,
+    pub name: Option<String>,
+    pub scopes: Vec<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl ApiKeyCreatedResponse {
+    pub fn new(api_key: ApiKey, key: String) -> Self {
+        Self {
+            id: api_key.id,
+            key,
+            name: api_key.name,
+            scopes: api_key.scopes,
+            expires_at: api_key.expires_at,
+            created_at: api_key.created_at,
+        }
+    }
+}
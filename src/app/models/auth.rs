@@ -1,28 +1,58 @@
-use regex::Regex;
+use crate::app::models::jwt::{JwtError, TokenRejection};
+use crate::app::models::login_attempt::describe_duration;
+use crate::app::models::oauth::OAuthError;
+use crate::app::models::otp::OtpError;
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use time;
+use utoipa::{IntoParams, ToSchema};
 use uuid;
-use validator::{Validate, ValidationError};
+use validator::{Validate, ValidateEmail, ValidationError};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct RegisterRequest {
     pub name: Option<String>,
-    #[validate(email(message = "Invalid email format"))]
+    #[validate(custom(function = "validate_email", message = "Invalid email format"))]
     pub email: String,
+    // Only accepted with USERNAME_LOGIN; lets the account log in by name too
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate(custom(
+        function = "validate_username",
+        message = "Username must be 3 to 32 letters, digits, '.', '_' or '-', starting with a letter or digit"
+    ))]
+    pub username: Option<String>,
     #[validate(custom(
         function = "validate_password",
         message = "Password must be at least 8 characters long, contain at least one uppercase letter, one lowercase letter, one number, and one special character"
     ))]
     pub password: String,
+    // Only checked once the client's IP has failed too often, see CaptchaService
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captcha_token: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RegisterResponse {
     pub message: String,
     pub user: crate::app::models::user::UserResponse,
 }
 
-#[derive(Debug, Serialize)]
+pub const REGISTRATION_ACCEPTED_MESSAGE: &str =
+    "Registration received. Please check your email for next steps.";
+
+// Returned by POST /api/auth/register in enumeration-safe mode; the body is the
+// same whether or not the email was already registered
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RegistrationAcceptedResponse {
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct AuthError {
     pub error: String,
     pub details: Option<Vec<String>>,
@@ -44,35 +74,429 @@ impl AuthError {
     }
 
     pub fn validation_error(errors: &validator::ValidationErrors) -> Self {
-        let details: Vec<String> = errors
+        let details = FieldError::from_validation_errors(errors)
+            .iter()
+            .map(FieldError::to_string)
+            .collect();
+
+        Self::with_details("Validation failed", details)
+    }
+}
+
+// One rejected request field, listed in the `details` of an error response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    // Sorted by field so responses don't depend on hash map order
+    pub fn from_validation_errors(errors: &validator::ValidationErrors) -> Vec<Self> {
+        let mut details: Vec<Self> = errors
             .field_errors()
             .iter()
             .flat_map(|(field, field_errors)| {
-                field_errors.iter().map(move |error| {
-                    format!(
-                        "{}: {}",
-                        field,
-                        error.message.as_ref().unwrap_or(&"Invalid value".into())
-                    )
+                field_errors.iter().map(move |error| Self {
+                    field: field.to_string(),
+                    message: error
+                        .message
+                        .as_deref()
+                        .unwrap_or("Invalid value")
+                        .to_string(),
                 })
             })
             .collect();
+        details.sort_by(|a, b| a.field.cmp(&b.field));
+        details
+    }
 
-        Self::with_details("Validation failed", details)
+    // Reads back an AuthError detail, which is formatted as "field: message"
+    pub fn from_detail(detail: &str) -> Self {
+        match detail.split_once(": ") {
+            Some((field, message)) => Self::new(field, message),
+            None => Self::new("", detail),
+        }
+    }
+}
+
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+// Body of every error from the auth routes. `code` is stable for clients to
+// match on; `message` is for people and may change.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuthErrorResponse {
+    pub error: AuthErrorBody,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuthErrorBody {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub details: Vec<FieldError>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AuthenticationError {
+    #[error("New password must not match any of your recent passwords")]
+    PasswordReused,
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Insufficient permissions")]
+    InsufficientPermissions,
+    #[error("Account is disabled")]
+    AccountDisabled,
+    #[error(
+        "Account has been temporarily locked due to too many failed login attempts. Please try again in {}.",
+        describe_duration(.lockout_duration)
+    )]
+    AccountLocked { lockout_duration: time::Duration },
+    #[error("Email address is already in use")]
+    EmailInUse,
+    #[error("Invalid or expired confirmation token")]
+    InvalidConfirmationToken,
+    #[error("Too many requests. Please try again in {retry_after} seconds.")]
+    RateLimitExceeded { retry_after: u64 },
+    #[error("CAPTCHA verification required")]
+    CaptchaRequired,
+    #[error("CAPTCHA verification failed")]
+    CaptchaFailed,
+    #[error("Missing or invalid CSRF token")]
+    CsrfTokenInvalid,
+    #[error("Content-Type must be application/json")]
+    UnsupportedMediaType,
+    #[error("{field}: {message}")]
+    InvalidInput {
+        field: &'static str,
+        message: String,
+    },
+    #[error("Password hashing error: {0}")]
+    PasswordHashError(String),
+    #[error("Email sending failed: {0}")]
+    EmailDeliveryError(String),
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] sqlx::Error),
+    #[error("Validation failed")]
+    ValidationFailed(Vec<FieldError>),
+    #[error("Invalid email or password")]
+    InvalidCredentials,
+    #[error("Authentication failed")]
+    AuthenticationFailed,
+    #[error("Current password is incorrect")]
+    IncorrectPassword,
+    #[error("Email already registered")]
+    EmailAlreadyRegistered,
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error("Invalid or expired reset token")]
+    PasswordResetTokenInvalid,
+    #[error("Reset token has expired")]
+    ExpiredResetToken,
+    // Throttled logins and reset requests; the service words the message
+    #[error("{0}")]
+    TooManyAttempts(String),
+    // A lock reported by the login service, which already formatted the message
+    #[error("{0}")]
+    Locked(String),
+    #[error("SMS verification is unavailable")]
+    SmsUnavailable,
+    #[error("{0}")]
+    NotFound(&'static str),
+    #[error(transparent)]
+    Otp(#[from] OtpError),
+    #[error(transparent)]
+    OAuth(#[from] OAuthError),
+    #[error(transparent)]
+    Token(#[from] JwtError),
+    // The message is shown as is, so the cause should be logged, not put here
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl From<argon2::password_hash::Error> for AuthenticationError {
+    fn from(e: argon2::password_hash::Error) -> Self {
+        AuthenticationError::PasswordHashError(e.to_string())
+    }
+}
+
+impl From<crate::app::hashing::HashingError> for AuthenticationError {
+    fn from(e: crate::app::hashing::HashingError) -> Self {
+        AuthenticationError::PasswordHashError(e.to_string())
+    }
+}
+
+impl From<validator::ValidationErrors> for AuthenticationError {
+    fn from(errors: validator::ValidationErrors) -> Self {
+        AuthenticationError::ValidationFailed(FieldError::from_validation_errors(&errors))
     }
 }
 
+// AuthError is only a response body now; validation details survive the trip
+// back, anything else is reported as is with a 500
+impl From<AuthError> for AuthenticationError {
+    fn from(error: AuthError) -> Self {
+        match error.details {
+            Some(details) if !details.is_empty() => AuthenticationError::ValidationFailed(
+                details
+                    .iter()
+                    .map(|detail| FieldError::from_detail(detail))
+                    .collect(),
+            ),
+            _ => AuthenticationError::Internal(error.error),
+        }
+    }
+}
+
+impl AuthenticationError {
+    // Registration failures also report how strong the rejected password was
+    pub fn validation_failed_with_strength(
+        errors: &validator::ValidationErrors,
+        password: &str,
+    ) -> Self {
+        let mut details = FieldError::from_validation_errors(errors);
+        if errors.field_errors().contains_key("password") {
+            let score = password_strength(password).score;
+            details.push(FieldError::new(
+                "password",
+                &format!("strength score {} of 4", score),
+            ));
+        }
+        AuthenticationError::ValidationFailed(details)
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AuthenticationError::PasswordReused
+            | AuthenticationError::InvalidInput { .. }
+            | AuthenticationError::ValidationFailed(_)
+            | AuthenticationError::InvalidConfirmationToken
+            | AuthenticationError::PasswordResetTokenInvalid
+            | AuthenticationError::ExpiredResetToken => StatusCode::BAD_REQUEST,
+            AuthenticationError::InvalidCredentials
+            | AuthenticationError::AuthenticationFailed
+            | AuthenticationError::IncorrectPassword => StatusCode::UNAUTHORIZED,
+            AuthenticationError::EmailInUse
+            | AuthenticationError::EmailAlreadyRegistered
+            | AuthenticationError::UsernameTaken => StatusCode::CONFLICT,
+            AuthenticationError::UserNotFound | AuthenticationError::NotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            AuthenticationError::InsufficientPermissions | AuthenticationError::AccountDisabled => {
+                StatusCode::FORBIDDEN
+            }
+            AuthenticationError::CaptchaRequired
+            | AuthenticationError::CaptchaFailed
+            | AuthenticationError::CsrfTokenInvalid => StatusCode::FORBIDDEN,
+            AuthenticationError::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AuthenticationError::AccountLocked { .. } | AuthenticationError::Locked(_) => {
+                StatusCode::LOCKED
+            }
+            AuthenticationError::RateLimitExceeded { .. }
+            | AuthenticationError::TooManyAttempts(_) => StatusCode::TOO_MANY_REQUESTS,
+            AuthenticationError::SmsUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            AuthenticationError::Otp(error) => error.status_code(),
+            AuthenticationError::OAuth(error) => error.status_code(),
+            AuthenticationError::Token(error) => match TokenRejection::from_jwt_error(error) {
+                Some(_) => StatusCode::UNAUTHORIZED,
+                None => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            AuthenticationError::PasswordHashError(_)
+            | AuthenticationError::EmailDeliveryError(_)
+            | AuthenticationError::DatabaseError(_)
+            | AuthenticationError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Machine-readable counterpart of the message, e.g. VALIDATION_FAILED
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthenticationError::PasswordReused => "PASSWORD_REUSED",
+            AuthenticationError::UserNotFound => "USER_NOT_FOUND",
+            AuthenticationError::InsufficientPermissions => "INSUFFICIENT_PERMISSIONS",
+            AuthenticationError::AccountDisabled => "ACCOUNT_DISABLED",
+            AuthenticationError::AccountLocked { .. } | AuthenticationError::Locked(_) => {
+                "ACCOUNT_LOCKED"
+            }
+            AuthenticationError::EmailInUse | AuthenticationError::EmailAlreadyRegistered => {
+                "EMAIL_IN_USE"
+            }
+            AuthenticationError::UsernameTaken => "USERNAME_IN_USE",
+            AuthenticationError::InvalidConfirmationToken
+            | AuthenticationError::PasswordResetTokenInvalid => "INVALID_TOKEN",
+            AuthenticationError::ExpiredResetToken => "TOKEN_EXPIRED",
+            AuthenticationError::RateLimitExceeded { .. }
+            | AuthenticationError::TooManyAttempts(_) => "RATE_LIMITED",
+            AuthenticationError::CaptchaRequired => "CAPTCHA_REQUIRED",
+            AuthenticationError::CaptchaFailed => "CAPTCHA_FAILED",
+            AuthenticationError::CsrfTokenInvalid => "CSRF_TOKEN_INVALID",
+            AuthenticationError::UnsupportedMediaType => "UNSUPPORTED_MEDIA_TYPE",
+            AuthenticationError::InvalidInput { .. } | AuthenticationError::ValidationFailed(_) => {
+                "VALIDATION_FAILED"
+            }
+            AuthenticationError::InvalidCredentials | AuthenticationError::AuthenticationFailed => {
+                "INVALID_CREDENTIALS"
+            }
+            AuthenticationError::IncorrectPassword => "INCORRECT_PASSWORD",
+            AuthenticationError::SmsUnavailable => "SMS_UNAVAILABLE",
+            AuthenticationError::NotFound(_) => "NOT_FOUND",
+            AuthenticationError::Otp(error) => error.code(),
+            AuthenticationError::OAuth(error) => error.code(),
+            AuthenticationError::Token(error) => TokenRejection::from_jwt_error(error)
+                .map_or("INTERNAL_ERROR", |rejection| rejection.code()),
+            AuthenticationError::PasswordHashError(_)
+            | AuthenticationError::EmailDeliveryError(_)
+            | AuthenticationError::DatabaseError(_)
+            | AuthenticationError::Internal(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    // What clients see; provider and token failures keep their causes out of it
+    pub fn message(&self) -> String {
+        match self {
+            AuthenticationError::InvalidInput { .. } | AuthenticationError::ValidationFailed(_) => {
+                "Validation failed".to_string()
+            }
+            AuthenticationError::Otp(error) => error.public_message(),
+            AuthenticationError::OAuth(error) => error.public_message(),
+            AuthenticationError::Token(error) => TokenRejection::from_jwt_error(error)
+                .map_or("Token generation failed", |rejection| rejection.message())
+                .to_string(),
+            error => error.to_string(),
+        }
+    }
+
+    pub fn field_errors(&self) -> Vec<FieldError> {
+        match self {
+            AuthenticationError::InvalidInput { field, message } => {
+                vec![FieldError::new(field, message)]
+            }
+            AuthenticationError::ValidationFailed(details) => details.clone(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl From<AuthenticationError> for AuthError {
+    fn from(e: AuthenticationError) -> Self {
+        let details = e.field_errors();
+        if details.is_empty() {
+            AuthError::new(&e.message())
+        } else {
+            // Same shape as validator failures
+            AuthError::with_details(
+                &e.message(),
+                details.iter().map(FieldError::to_string).collect(),
+            )
+        }
+    }
+}
+
+impl IntoResponse for AuthenticationError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        let retry_after = match &self {
+            AuthenticationError::RateLimitExceeded { retry_after } => Some(*retry_after),
+            _ => None,
+        };
+
+        let body = AuthErrorResponse {
+            error: AuthErrorBody {
+                code: self.code().to_string(),
+                message: self.message(),
+                details: self.field_errors(),
+            },
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let Some(retry_after) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
+    }
+}
+
+// Which characters count towards the password complexity rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCharset {
+    // Letters, digits and symbols from any script
+    Unicode,
+    // Only ASCII characters are accepted
+    Ascii,
+}
+
+impl PasswordCharset {
+    // PASSWORD_ASCII_ONLY=true restricts passwords to ASCII; Unicode is the default
+    pub fn from_env() -> Self {
+        match std::env::var("PASSWORD_ASCII_ONLY") {
+            Ok(value) if value.eq_ignore_ascii_case("true") || value == "1" => {
+                PasswordCharset::Ascii
+            }
+            _ => PasswordCharset::Unicode,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref PASSWORD_CHARSET: PasswordCharset = PasswordCharset::from_env();
+}
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+// What counts as a special character with PASSWORD_ASCII_ONLY
+const ASCII_SPECIAL_CHARACTERS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
 pub fn validate_password(password: &str) -> Result<(), ValidationError> {
-    if password.len() < 8 {
+    validate_password_with_charset(password, *PASSWORD_CHARSET)
+}
+
+pub fn validate_password_with_charset(
+    password: &str,
+    charset: PasswordCharset,
+) -> Result<(), ValidationError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
         return Err(ValidationError::new("password_too_short"));
     }
 
-    let has_uppercase = Regex::new(r"[A-Z]").unwrap().is_match(password);
-    let has_lowercase = Regex::new(r"[a-z]").unwrap().is_match(password);
-    let has_number = Regex::new(r"\d").unwrap().is_match(password);
-    let has_special = Regex::new(r"[!@#$%^&*(),.?\x22:{}|<>]")
-        .unwrap()
-        .is_match(password);
+    let (has_uppercase, has_lowercase, has_number, has_special) = match charset {
+        PasswordCharset::Ascii => {
+            if !password.is_ascii() {
+                return Err(ValidationError::new("password_not_ascii"));
+            }
+            (
+                password.chars().any(|c| c.is_ascii_uppercase()),
+                password.chars().any(|c| c.is_ascii_lowercase()),
+                password.chars().any(|c| c.is_ascii_digit()),
+                password.chars().any(|c| c.is_ascii_punctuation()),
+            )
+        }
+        PasswordCharset::Unicode => {
+            // Letters from scripts without case (e.g. CJK, Arabic) satisfy both case rules
+            let has_uncased_letter = password
+                .chars()
+                .any(|c| c.is_alphabetic() && !c.is_uppercase() && !c.is_lowercase());
+            (
+                has_uncased_letter || password.chars().any(char::is_uppercase),
+                has_uncased_letter || password.chars().any(char::is_lowercase),
+                password.chars().any(char::is_numeric),
+                password
+                    .chars()
+                    .any(|c| !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control()),
+            )
+        }
+    };
 
     if !has_uppercase || !has_lowercase || !has_number || !has_special {
         return Err(ValidationError::new("password_complexity"));
@@ -81,9 +505,457 @@ pub fn validate_password(password: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+// The rules validate_password enforces, so clients can show them and check
+// passwords before submitting instead of hardcoding their own copy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    // null: no upper limit
+    pub max_length: Option<usize>,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    // PASSWORD_ASCII_ONLY: any other character is refused
+    pub ascii_only: bool,
+    // The characters that count as special; null when any symbol from any script does
+    pub special_characters: Option<String>,
+    // Whether passwords are checked against known data breaches
+    pub breach_check: bool,
+    // How many previous passwords a new one may not repeat
+    pub history_depth: i64,
+}
+
+impl PasswordPolicy {
+    pub fn new(charset: PasswordCharset, history_depth: i64) -> Self {
+        let ascii_only = charset == PasswordCharset::Ascii;
+        Self {
+            min_length: MIN_PASSWORD_LENGTH,
+            max_length: None,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: true,
+            ascii_only,
+            special_characters: ascii_only.then(|| ASCII_SPECIAL_CHARACTERS.to_string()),
+            breach_check: false,
+            history_depth,
+        }
+    }
+
+    // The policy validate_password applies, with PASSWORD_ASCII_ONLY as read at startup
+    pub fn current(history_depth: i64) -> Self {
+        Self::new(*PASSWORD_CHARSET, history_depth)
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct PasswordStrengthRequest {
+    pub password: String,
+}
+
+// Keeps the password out of logs and error messages
+impl std::fmt::Debug for PasswordStrengthRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordStrengthRequest")
+            .field("password", &"[redacted]")
+            .finish()
+    }
+}
+
+// zxcvbn estimate for a password meter; separate from the pass/fail rules above
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StrengthReport {
+    // 0 (guessable in under 10^3 tries) to 4 (over 10^10)
+    pub score: u8,
+    pub feedback: Vec<String>,
+    // Offline attack against a slow hash such as Argon2, e.g. "3 hours"
+    pub crack_time_estimate: String,
+}
+
+pub fn password_strength(password: &str) -> StrengthReport {
+    let entropy = zxcvbn::zxcvbn(password, &[]);
+
+    let mut feedback = Vec::new();
+    if let Some(entropy_feedback) = entropy.feedback() {
+        if let Some(warning) = entropy_feedback.warning() {
+            feedback.push(warning.to_string());
+        }
+        feedback.extend(
+            entropy_feedback
+                .suggestions()
+                .iter()
+                .map(ToString::to_string),
+        );
+    }
+
+    StrengthReport {
+        score: entropy.score().into(),
+        feedback,
+        crack_time_estimate: entropy
+            .crack_times()
+            .offline_slow_hashing_1e4_per_second()
+            .to_string(),
+    }
+}
+
+// Which email addresses are accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmailMode {
+    // ASCII addresses only
+    #[default]
+    Strict,
+    // Unicode local parts and internationalized domains; the domain is stored in Punycode
+    Idn,
+}
+
+impl EmailMode {
+    // ALLOW_IDN_EMAIL=true accepts internationalized addresses; strict ASCII is the default
+    pub fn from_env() -> Self {
+        match std::env::var("ALLOW_IDN_EMAIL") {
+            Ok(value) if value.eq_ignore_ascii_case("true") || value == "1" => EmailMode::Idn,
+            _ => EmailMode::Strict,
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref EMAIL_MODE: EmailMode = EmailMode::from_env();
+}
+
+pub fn validate_email(email: &str) -> Result<(), ValidationError> {
+    validate_enhanced_email(email, *EMAIL_MODE).map(|_| ())
+}
+
+// The form an address is stored and looked up in; unchanged if it does not validate
+pub fn normalize_email(email: &str) -> String {
+    validate_enhanced_email(email, *EMAIL_MODE).unwrap_or_else(|_| email.to_string())
+}
+
+// RFC 5321 limits, in octets
+const MAX_EMAIL_LOCAL_LEN: usize = 64;
+const MAX_EMAIL_DOMAIN_LEN: usize = 255;
+
+// Length and dot rules shared by both modes: "a..b", ".a" and "a." are refused
+fn is_valid_local_part(local: &str) -> bool {
+    !local.is_empty()
+        && local.len() <= MAX_EMAIL_LOCAL_LEN
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+}
+
+// Checks an address and returns its normalized form. Strict mode leaves the address
+// as it is. IDN mode converts the domain to lowercase Punycode (例え.jp becomes
+// xn--r8jz45g.jp) and keeps the local part, which may use letters and digits from
+// any script.
+//
+// Every check here is a single pass over the input, and validator's email regexes
+// run on the regex crate, which matches in linear time, so no address can cause
+// catastrophic backtracking. The length bounds are checked first, so oversized input
+// is refused before it reaches the regexes or the IDNA conversion.
+pub fn validate_enhanced_email(email: &str, mode: EmailMode) -> Result<String, ValidationError> {
+    let invalid = || ValidationError::new("email");
+
+    let (local, domain) = email.rsplit_once('@').ok_or_else(invalid)?;
+    // A Punycode domain is at least as many characters as its Unicode form
+    if !is_valid_local_part(local) || domain.chars().count() > MAX_EMAIL_DOMAIN_LEN {
+        return Err(invalid());
+    }
+
+    match mode {
+        EmailMode::Strict => {
+            if !email.is_ascii() || !email.validate_email() {
+                return Err(invalid());
+            }
+            Ok(email.to_string())
+        }
+        EmailMode::Idn => {
+            let local_ok = local.chars().all(|c| {
+                if c.is_ascii() {
+                    c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c)
+                } else {
+                    c.is_alphanumeric()
+                }
+            });
+            if !local_ok || mixes_confusable_scripts(local) {
+                return Err(invalid());
+            }
+
+            let ascii_domain = idna::domain_to_ascii(domain).map_err(|_| invalid())?;
+            if ascii_domain.len() > MAX_EMAIL_DOMAIN_LEN {
+                return Err(invalid());
+            }
+            // Decode again so a domain sent as xn--... gets the same label checks
+            let (unicode_domain, decoded) = idna::domain_to_unicode(&ascii_domain);
+            let bad_label = |label: &str| {
+                label.starts_with('-') || label.ends_with('-') || mixes_confusable_scripts(label)
+            };
+            if decoded.is_err() || unicode_domain.split('.').any(bad_label) {
+                return Err(invalid());
+            }
+
+            // The ASCII rules apply to the Punycode domain
+            if !format!("user@{}", ascii_domain).validate_email() {
+                return Err(invalid());
+            }
+            Ok(format!("{}@{}", local, ascii_domain))
+        }
+    }
+}
+
+// Scripts with letters that look alike, e.g. Latin "a" and Cyrillic "а"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConfusableScript {
+    Latin,
+    Greek,
+    Cyrillic,
+}
+
+fn confusable_script(c: char) -> Option<ConfusableScript> {
+    match c {
+        'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => {
+            Some(ConfusableScript::Latin)
+        }
+        '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Some(ConfusableScript::Greek),
+        '\u{0400}'..='\u{052F}' => Some(ConfusableScript::Cyrillic),
+        _ => None,
+    }
+}
+
+// A label mixing these scripts (pаypal with a Cyrillic "а") is a likely homograph
+fn mixes_confusable_scripts(label: &str) -> bool {
+    let mut scripts = label.chars().filter_map(confusable_script);
+    match scripts.next() {
+        Some(first) => scripts.any(|script| script != first),
+        None => false,
+    }
+}
+
+const DEFAULT_DISPOSABLE_DOMAINS: &str = include_str!("disposable_email_domains.txt");
+
+#[derive(Debug, thiserror::Error)]
+pub enum DisposableEmailConfigError {
+    #[error("Failed to read DISPOSABLE_EMAIL_DOMAINS_FILE {path:?}: {source}")]
+    File {
+        path: String,
+        source: std::io::Error,
+    },
+}
+
+// Email domains registration refuses; a listed domain also covers its subdomains
+#[derive(Debug, Clone, Default)]
+pub struct DisposableEmailBlocklist {
+    domains: HashSet<String>,
+}
+
+impl DisposableEmailBlocklist {
+    // One domain per line; blank lines and # comments are skipped
+    pub fn parse(list: &str) -> Self {
+        Self::from_domains(
+            list.lines()
+                .map(|line| line.split('#').next().unwrap_or_default()),
+        )
+    }
+
+    pub fn from_domains<'a>(domains: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            domains: domains
+                .into_iter()
+                .map(normalize_domain)
+                .filter(|domain| !domain.is_empty())
+                .collect(),
+        }
+    }
+
+    // Blocks nothing
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn embedded() -> Self {
+        Self::parse(DEFAULT_DISPOSABLE_DOMAINS)
+    }
+
+    // DISPOSABLE_EMAIL_BLOCKING=false disables the check. DISPOSABLE_EMAIL_DOMAINS_FILE
+    // replaces the embedded list, and DISPOSABLE_EMAIL_DOMAINS (comma-separated) adds to it.
+    pub fn from_env() -> Result<Self, DisposableEmailConfigError> {
+        if let Ok(value) = std::env::var("DISPOSABLE_EMAIL_BLOCKING")
+            && (value.eq_ignore_ascii_case("false") || value == "0")
+        {
+            return Ok(Self::disabled());
+        }
+
+        let mut blocklist = match std::env::var("DISPOSABLE_EMAIL_DOMAINS_FILE") {
+            Ok(path) => match std::fs::read_to_string(&path) {
+                Ok(list) => Self::parse(&list),
+                Err(source) => return Err(DisposableEmailConfigError::File { path, source }),
+            },
+            Err(_) => Self::embedded(),
+        };
+        if let Ok(extra) = std::env::var("DISPOSABLE_EMAIL_DOMAINS") {
+            blocklist
+                .domains
+                .extend(Self::from_domains(extra.split(',')).domains);
+        }
+
+        Ok(blocklist)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    pub fn is_blocked(&self, email: &str) -> bool {
+        let Some((_, domain)) = email.rsplit_once('@') else {
+            return false;
+        };
+        let domain = normalize_domain(domain);
+
+        // mail.yopmail.com is checked as mail.yopmail.com, yopmail.com and com
+        let mut candidate = domain.as_str();
+        loop {
+            if self.domains.contains(candidate) {
+                return true;
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) => candidate = parent,
+                None => return false,
+            }
+        }
+    }
+}
+
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+pub fn validate_email_not_disposable(
+    email: &str,
+    blocklist: &DisposableEmailBlocklist,
+) -> Result<(), AuthenticationError> {
+    if blocklist.is_blocked(email) {
+        return Err(AuthenticationError::InvalidInput {
+            field: "email",
+            message:
+                "Disposable email addresses are not allowed. Please use a permanent email address."
+                    .to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Email domains registration is limited to; empty allows every domain. A
+// "*.example.com" entry covers the subdomains of example.com but not
+// example.com itself, which needs its own entry.
+#[derive(Debug, Clone, Default)]
+pub struct EmailDomainAllowlist {
+    domains: HashSet<String>,
+    wildcard_parents: HashSet<String>,
+}
+
+impl EmailDomainAllowlist {
+    pub fn from_domains<'a>(domains: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut allowlist = Self::default();
+        let domains = domains
+            .into_iter()
+            .map(normalize_domain)
+            .filter(|domain| !domain.is_empty() && domain != "*.");
+        for domain in domains {
+            match domain.strip_prefix("*.") {
+                Some(parent) => allowlist.wildcard_parents.insert(parent.to_string()),
+                None => allowlist.domains.insert(domain),
+            };
+        }
+        allowlist
+    }
+
+    // Allows every domain
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    // EMAIL_ALLOWED_DOMAINS, comma-separated; unset or empty allows every domain
+    pub fn from_env() -> Self {
+        match std::env::var("EMAIL_ALLOWED_DOMAINS") {
+            Ok(domains) => Self::from_domains(domains.split(',')),
+            Err(_) => Self::disabled(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty() && self.wildcard_parents.is_empty()
+    }
+
+    pub fn is_allowed(&self, email: &str) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let Some((_, domain)) = email.rsplit_once('@') else {
+            return false;
+        };
+        let domain = normalize_domain(domain);
+        if self.domains.contains(&domain) {
+            return true;
+        }
+
+        // eu.mail.example.com is covered by *.mail.example.com and *.example.com
+        let mut candidate = domain.as_str();
+        while let Some((_, parent)) = candidate.split_once('.') {
+            if self.wildcard_parents.contains(parent) {
+                return true;
+            }
+            candidate = parent;
+        }
+        false
+    }
+}
+
+pub fn validate_email_domain_allowed(
+    email: &str,
+    allowlist: &EmailDomainAllowlist,
+) -> Result<(), AuthenticationError> {
+    if !allowlist.is_allowed(email) {
+        return Err(AuthenticationError::InvalidInput {
+            field: "email",
+            message: "Registration is not open to this email domain.".to_string(),
+        });
+    }
+    Ok(())
+}
+
+const MIN_USERNAME_LEN: usize = 3;
+const MAX_USERNAME_LEN: usize = 32;
+
+// 3 to 32 ASCII letters, digits, '.', '_' or '-', starting with a letter or
+// digit. Never contains '@', which is how a login identifier is told apart
+// from an email.
+pub fn validate_username(username: &str) -> Result<(), ValidationError> {
+    let length_ok = (MIN_USERNAME_LEN..=MAX_USERNAME_LEN).contains(&username.len());
+    let starts_ok = username
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric());
+    let chars_ok = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+
+    if length_ok && starts_ok && chars_ok {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_username"))
+    }
+}
+
+// Usernames are unique regardless of case, so they are stored lowercase
+pub fn normalize_username(username: &str) -> String {
+    username.trim().to_ascii_lowercase()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::models::user::User;
 
     #[test]
     fn test_password_validation() {
@@ -105,20 +977,354 @@ mod tests {
         // Missing special character
         assert!(validate_password("MyPassw0rd1").is_err());
     }
+
+    #[test]
+    fn test_unicode_password_validation() {
+        let unicode = PasswordCharset::Unicode;
+
+        // Accented letters count as upper and lower case
+        assert!(validate_password_with_charset("ÉlégantMot1!", unicode).is_ok());
+        assert!(validate_password_with_charset("Ñandú2024#", unicode).is_ok());
+
+        // Non-ASCII symbols count as special characters
+        assert!(validate_password_with_charset("Passwort9€", unicode).is_ok());
+        assert!(validate_password_with_charset("Contraseña7¿", unicode).is_ok());
+
+        // Letters from scripts without case
+        assert!(validate_password_with_charset("密码安全测试12★", unicode).is_ok());
+
+        // Length is counted in characters, not bytes
+        assert!(validate_password_with_charset("Éé1€Éé1", unicode).is_err());
+
+        // Complexity rules still apply
+        assert!(validate_password_with_charset("élégantmot1!", unicode).is_err());
+        assert!(validate_password_with_charset("ÉlégantMot!!", unicode).is_err());
+        assert!(validate_password_with_charset("ÉlégantMot12", unicode).is_err());
+    }
+
+    #[test]
+    fn test_ascii_only_password_validation() {
+        let ascii = PasswordCharset::Ascii;
+
+        assert!(validate_password_with_charset("MyPassw0rd!", ascii).is_ok());
+        assert!(validate_password_with_charset("ÉlégantMot1!", ascii).is_err());
+        assert!(validate_password_with_charset("Passwort9€", ascii).is_err());
+    }
+
+    #[test]
+    fn test_password_policy_matches_validation() {
+        let ascii = PasswordPolicy::new(PasswordCharset::Ascii, 5);
+        let special_characters = ascii.special_characters.unwrap();
+        assert_eq!(special_characters.len(), 32);
+        // Each listed character is enough to meet the special character rule
+        for special in special_characters.chars() {
+            let password = format!("MyPassw0rd{}", special);
+            assert!(validate_password_with_charset(&password, PasswordCharset::Ascii).is_ok());
+        }
+        let too_short: String = "Aa1!aaaaaaaaaaaa"
+            .chars()
+            .take(ascii.min_length - 1)
+            .collect();
+        assert!(validate_password_with_charset(&too_short, PasswordCharset::Ascii).is_err());
+
+        let unicode = PasswordPolicy::new(PasswordCharset::Unicode, 5);
+        assert!(!unicode.ascii_only);
+        assert_eq!(unicode.special_characters, None);
+    }
+
+    #[test]
+    fn test_idn_email_accepted_only_in_idn_mode() {
+        assert_eq!(
+            validate_enhanced_email("用户@例え.jp", EmailMode::Idn).unwrap(),
+            "用户@xn--r8jz45g.jp"
+        );
+        assert_eq!(
+            validate_enhanced_email("jörg@Bücher.DE", EmailMode::Idn).unwrap(),
+            "jörg@xn--bcher-kva.de"
+        );
+        // The Punycode form normalizes to itself
+        assert_eq!(
+            validate_enhanced_email("用户@xn--r8jz45g.jp", EmailMode::Idn).unwrap(),
+            "用户@xn--r8jz45g.jp"
+        );
+
+        assert!(validate_enhanced_email("用户@例え.jp", EmailMode::Strict).is_err());
+        assert!(validate_enhanced_email("user@例え.jp", EmailMode::Strict).is_err());
+        assert_eq!(
+            validate_enhanced_email("User@Example.com", EmailMode::Strict).unwrap(),
+            "User@Example.com"
+        );
+    }
+
+    #[test]
+    fn test_idn_email_rejects_homographs_and_bad_input() {
+        // "pаypal.com" with a Cyrillic "а"
+        assert!(validate_enhanced_email("user@p\u{0430}ypal.com", EmailMode::Idn).is_err());
+        // The same domain sent already in Punycode
+        let punycode = idna::domain_to_ascii("p\u{0430}ypal.com").unwrap();
+        assert!(validate_enhanced_email(&format!("user@{}", punycode), EmailMode::Idn).is_err());
+        // A Greek omicron in an otherwise Latin local part
+        assert!(validate_enhanced_email("j\u{03BF}hn@example.com", EmailMode::Idn).is_err());
+
+        assert!(validate_enhanced_email("no-at-sign.example.com", EmailMode::Idn).is_err());
+        assert!(validate_enhanced_email("@例え.jp", EmailMode::Idn).is_err());
+        assert!(validate_enhanced_email("用 户@例え.jp", EmailMode::Idn).is_err());
+        assert!(validate_enhanced_email("user@-例え.jp", EmailMode::Idn).is_err());
+    }
+
+    #[test]
+    fn test_email_dot_and_length_rules() {
+        for mode in [EmailMode::Strict, EmailMode::Idn] {
+            assert!(validate_enhanced_email("first.last@example.com", mode).is_ok());
+            assert!(validate_enhanced_email(".first@example.com", mode).is_err());
+            assert!(validate_enhanced_email("first.@example.com", mode).is_err());
+            assert!(validate_enhanced_email("first..last@example.com", mode).is_err());
+
+            let local = "a".repeat(64);
+            assert!(validate_enhanced_email(&format!("{}@example.com", local), mode).is_ok());
+            assert!(validate_enhanced_email(&format!("{}a@example.com", local), mode).is_err());
+
+            // 255 characters of labels, each within the 63 character limit
+            let domain = vec!["a".repeat(63); 4].join(".");
+            assert_eq!(domain.len(), 255);
+            assert!(validate_enhanced_email(&format!("user@{}", domain), mode).is_ok());
+            assert!(validate_enhanced_email(&format!("user@a{}", domain), mode).is_err());
+        }
+
+        // 64 octets, not characters: 22 three-byte characters are 66 octets
+        assert!(
+            validate_enhanced_email(&format!("{}@例え.jp", "用".repeat(21)), EmailMode::Idn)
+                .is_ok()
+        );
+        assert!(
+            validate_enhanced_email(&format!("{}@例え.jp", "用".repeat(22)), EmailMode::Idn)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_user_and_request_email_validation_agree() {
+        let addresses = [
+            "user@example.com",
+            "first.last+tag@sub.example.com",
+            ".user@example.com",
+            "user.@example.com",
+            "us..er@example.com",
+            "user@例え.jp",
+            "user@-example.com",
+            "no-at-sign",
+        ];
+
+        for address in addresses {
+            let user = User::with_password_hash(None, address.to_string(), "x".repeat(97));
+            let request = ForgotPasswordRequest {
+                email: address.to_string(),
+            };
+            let expected = validate_email(address).is_ok();
+            assert_eq!(user.validate().is_ok(), expected, "{}", address);
+            assert_eq!(request.validate().is_ok(), expected, "{}", address);
+        }
+    }
+
+    // Long and random inputs built from the characters the rules care about. Anything
+    // accepted must satisfy the bounds, and the whole run must stay fast: a single
+    // backtracking or quadratic path would take seconds on inputs this size.
+    #[test]
+    fn test_email_validation_is_bounded_on_adversarial_input() {
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut inputs = vec![
+            format!("{}@example.com", "a".repeat(100_000)),
+            format!("a@{}com", "a.".repeat(50_000)),
+            format!("a@{}", "a-".repeat(50_000)),
+            format!("a@{}.jp", "例".repeat(50_000)),
+            // Distinct code points are the slow case for Punycode
+            format!(
+                "a@{}.jp",
+                (0..20_000u32)
+                    .filter_map(|i| char::from_u32(0x4E00 + i))
+                    .collect::<String>()
+            ),
+            format!("a@{}", "xn--".repeat(25_000)),
+            "@".repeat(100_000),
+            ".".repeat(100_000),
+            format!("{}@{}", "a.".repeat(32), "a".repeat(300)),
+        ];
+        let alphabet = [
+            'a', 'Z', '0', '.', '-', '+', '@', '!', '例', '\u{0430}', ' ',
+        ];
+        let mut rng = StdRng::seed_from_u64(798);
+        for _ in 0..2_000 {
+            let len = rng.random_range(0..2_000);
+            inputs.push(
+                (0..len)
+                    .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+                    .collect(),
+            );
+        }
+
+        let started = std::time::Instant::now();
+        for input in &inputs {
+            for mode in [EmailMode::Strict, EmailMode::Idn] {
+                let Ok(normalized) = validate_enhanced_email(input, mode) else {
+                    continue;
+                };
+                let (local, domain) = normalized.rsplit_once('@').unwrap();
+                assert!(local.len() <= MAX_EMAIL_LOCAL_LEN, "{}", input);
+                assert!(domain.len() <= MAX_EMAIL_DOMAIN_LEN, "{}", input);
+                assert!(
+                    !local.starts_with('.') && !local.ends_with('.'),
+                    "{}",
+                    input
+                );
+                assert!(!local.contains(".."), "{}", input);
+                // Normalizing is idempotent
+                assert_eq!(
+                    validate_enhanced_email(&normalized, mode).as_deref(),
+                    Ok(normalized.as_str())
+                );
+            }
+        }
+        let elapsed = started.elapsed();
+        assert!(
+            elapsed < std::time::Duration::from_secs(5),
+            "validation took {:?}",
+            elapsed
+        );
+    }
+
+    #[test]
+    fn test_username_validation() {
+        for valid in ["bob", "Bob_99", "jane.doe", "a-b", &"x".repeat(32)] {
+            assert!(
+                validate_username(valid).is_ok(),
+                "{} should be valid",
+                valid
+            );
+        }
+        for invalid in [
+            "",
+            "ab",
+            &"x".repeat(33),
+            "_bob",
+            ".bob",
+            "bob@example.com",
+            "bob smith",
+            "bøb",
+        ] {
+            assert!(
+                validate_username(invalid).is_err(),
+                "{} should be invalid",
+                invalid
+            );
+        }
+        assert_eq!(normalize_username(" Jane.Doe "), "jane.doe");
+    }
+
+    #[test]
+    fn test_disposable_domain_is_blocked() {
+        let blocklist = DisposableEmailBlocklist::embedded();
+
+        assert!(validate_email_not_disposable("someone@mailinator.com", &blocklist).is_err());
+        // The domain is matched case-insensitively
+        assert!(validate_email_not_disposable("Someone@YopMail.COM", &blocklist).is_err());
+
+        let error =
+            validate_email_not_disposable("someone@mailinator.com", &blocklist).unwrap_err();
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert!(matches!(
+            error,
+            AuthenticationError::InvalidInput { field: "email", .. }
+        ));
+    }
+
+    #[test]
+    fn test_regular_domain_is_allowed() {
+        let blocklist = DisposableEmailBlocklist::embedded();
+
+        assert!(validate_email_not_disposable("someone@example.com", &blocklist).is_ok());
+        // Only whole labels match, so a look-alike domain passes
+        assert!(validate_email_not_disposable("someone@notmailinator.com", &blocklist).is_ok());
+        assert!(
+            validate_email_not_disposable(
+                "someone@mailinator.com",
+                &DisposableEmailBlocklist::disabled()
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_subdomain_of_disposable_domain_is_blocked() {
+        let blocklist = DisposableEmailBlocklist::parse("# custom list\nthrowaway.test\n");
+
+        assert!(blocklist.is_blocked("someone@throwaway.test"));
+        assert!(blocklist.is_blocked("someone@inbox.throwaway.test"));
+        assert!(blocklist.is_blocked("someone@a.b.throwaway.test."));
+        assert!(!blocklist.is_blocked("someone@throwaway.test.example.com"));
+    }
+
+    #[test]
+    fn test_allowed_domain_passes() {
+        let allowlist =
+            EmailDomainAllowlist::from_domains("mycompany.com, *.partner.org".split(','));
+
+        assert!(validate_email_domain_allowed("someone@mycompany.com", &allowlist).is_ok());
+        assert!(validate_email_domain_allowed("Someone@MyCompany.COM", &allowlist).is_ok());
+        assert!(validate_email_domain_allowed("someone@eu.partner.org", &allowlist).is_ok());
+        assert!(validate_email_domain_allowed("someone@a.b.partner.org", &allowlist).is_ok());
+    }
+
+    #[test]
+    fn test_disallowed_domain_is_rejected() {
+        let allowlist =
+            EmailDomainAllowlist::from_domains("mycompany.com, *.partner.org".split(','));
+
+        for email in [
+            "someone@example.com",
+            // Exact entries don't cover subdomains, wildcards don't cover the apex
+            "someone@eu.mycompany.com",
+            "someone@partner.org",
+            "someone@notpartner.org",
+            "someone@mycompany.com.example.com",
+        ] {
+            let error = validate_email_domain_allowed(email, &allowlist).unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    AuthenticationError::InvalidInput { field: "email", .. }
+                ),
+                "{} should be rejected",
+                email
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_allowlist_allows_every_domain() {
+        for allowlist in [
+            EmailDomainAllowlist::disabled(),
+            EmailDomainAllowlist::from_domains("".split(',')),
+            EmailDomainAllowlist::from_domains(" , ".split(',')),
+        ] {
+            assert!(allowlist.is_empty());
+            assert!(validate_email_domain_allowed("someone@example.com", &allowlist).is_ok());
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct ForgotPasswordRequest {
-    #[validate(email(message = "Invalid email format"))]
+    #[validate(custom(function = "validate_email", message = "Invalid email format"))]
     pub email: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ForgotPasswordResponse {
     pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct ResetPasswordRequest {
     pub token: String,
     #[validate(custom(
@@ -128,20 +1334,96 @@ pub struct ResetPasswordRequest {
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ResetPasswordResponse {
     pub message: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ValidateResetTokenQuery {
+    // The token from the reset email
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ValidateResetTokenResponse {
+    pub valid: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConfirmEmailChangeQuery {
+    // The token mailed to the new address
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfirmEmailChangeResponse {
+    pub message: String,
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LockoutStatusQuery {
+    pub email: String,
+}
+
+// Unknown emails get the same answer as accounts that aren't locked
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LockoutStatusResponse {
+    pub locked: bool,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "time::serde::rfc3339::option"
+    )]
+    pub locked_until: Option<time::OffsetDateTime>,
+    // Seconds until the lockout ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+// POST /api/auth/unlock answers with this whether or not anything was sent
+pub const ACCOUNT_UNLOCK_MESSAGE: &str =
+    "If the account is locked, an unlock email has been sent to its address.";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct AccountUnlockRequest {
+    #[validate(custom(function = "validate_email", message = "Invalid email format"))]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccountUnlockResponse {
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConsumeUnlockTokenQuery {
+    // The token from the unlock email
+    pub token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct ProfileUpdateRequest {
     pub name: Option<String>,
-    #[validate(email(message = "Invalid email format"))]
-    pub email: Option<String>,
-    pub current_password: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ChangeEmailRequest {
+    #[validate(custom(function = "validate_email", message = "Invalid email format"))]
+    pub new_email: String,
+    #[validate(length(min = 1, message = "Current password is required"))]
+    pub current_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChangeEmailResponse {
+    pub message: String,
+    // Stays pending until confirmed from the new address
+    pub pending_email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ProfileResponse {
     pub id: uuid::Uuid,
     pub name: Option<String>,
@@ -152,7 +1434,59 @@ pub struct ProfileResponse {
     pub updated_at: Option<time::OffsetDateTime>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+impl From<crate::app::models::user::User> for ProfileResponse {
+    fn from(user: crate::app::models::user::User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+// GET /api/auth/me: who the caller is and what they may do
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct MeResponse {
+    pub id: uuid::Uuid,
+    pub name: Option<String>,
+    pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub created_at: Option<time::OffsetDateTime>,
+    // The account's roles, alphabetical
+    pub roles: Vec<String>,
+    // What the token or API key making this request may do
+    pub scopes: Vec<String>,
+    pub two_factor_enabled: bool,
+    pub email_verified: bool,
+    pub active_sessions: i64,
+}
+
+impl MeResponse {
+    pub fn new(
+        user: crate::app::models::user::User,
+        summary: crate::app::models::user::AccountSummary,
+        scopes: Vec<String>,
+    ) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            username: user.username,
+            created_at: user.created_at,
+            roles: summary.roles,
+            scopes,
+            two_factor_enabled: summary.two_factor_enabled,
+            email_verified: summary.email_verified,
+            active_sessions: summary.active_sessions,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct ChangePasswordRequest {
     pub current_password: String,
     #[validate(custom(
@@ -162,7 +1496,13 @@ pub struct ChangePasswordRequest {
     pub new_password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChangePasswordResponse {
     pub message: String,
 }
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct DeleteAccountRequest {
+    #[validate(length(min = 1, message = "Current password is required"))]
+    pub current_password: String,
+}
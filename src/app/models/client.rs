@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+// A client app logins can ask tokens for; see JwtService::with_audience
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Client {
+    pub client_id: String,
+    pub name: String,
+    pub redirect_uris: Vec<String>,
+    // Resource servers besides the client itself that its tokens are meant for
+    pub audiences: Vec<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl Client {
+    // The aud claim of tokens issued for this client: its own id first, then
+    // the extra audiences without repeats
+    pub fn token_audience(&self) -> Vec<String> {
+        let mut audience = vec![self.client_id.clone()];
+        for extra in &self.audiences {
+            if !audience.contains(extra) {
+                audience.push(extra.clone());
+            }
+        }
+        audience
+    }
+}
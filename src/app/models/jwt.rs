@@ -1,5 +1,12 @@
+use crate::app::models::scope::{default_scopes, grants};
+use axum::{
+    Json,
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
 use serde::{Deserialize, Serialize};
 use time::OffsetDateTime;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -11,60 +18,153 @@ pub struct Claims {
     pub iat: usize,            // Issued at (as UTC timestamp)
     pub jti: String,           // JWT ID (unique identifier for this token)
     pub token_type: TokenType, // Access or Refresh token
+    // Not before (as UTC timestamp); tokens issued before this claim existed have none
+    #[serde(default)]
+    pub nbf: usize,
+    // On access tokens, the jti of the refresh token (session) they were issued with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    // What the token may do, see RequireScope
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+    // The client and resource servers the token is meant for; empty unless the
+    // login named a client_id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aud: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum TokenType {
     Access,
     Refresh,
+    // Never in a JWT; marks an AuthContext built from an API key
+    ApiKey,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct TokenPair {
+    // Empty, and left out, with TOKEN_DELIVERY=cookie
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub access_token: String,
+    // Empty, and left out, when the refresh token was set as a cookie instead
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub refresh_token: String,
     pub token_type: String,        // "Bearer"
     pub expires_in: usize,         // Access token expiry in seconds
     pub refresh_expires_in: usize, // Refresh token expiry in seconds
+    // The access token's jti, only when JWT_EXPOSE_JTI is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token_jti: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginRequest {
+    // May be left empty when `identifier` is given
+    #[serde(default)]
     pub email: String,
+    // Email or username; takes the place of `email` when USERNAME_LOGIN is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
     pub password: String,
+    // Only checked once the client's IP has failed too often, see CaptchaService
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captcha_token: Option<String>,
+    // Issue a refresh token that lives REMEMBER_ME_TTL_SECS instead of 7 days
+    #[serde(default)]
+    pub remember_me: bool,
+    // Label for the new session; the X-Device-Name header is used when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    // A registered client; the tokens' aud is limited to it, see Client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+}
+
+impl LoginRequest {
+    // What the account is looked up by: `identifier` when present, else `email`
+    pub fn login_identifier(&self) -> &str {
+        self.identifier.as_deref().unwrap_or(&self.email)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LoginResponse {
     pub message: String,
     pub user: crate::app::models::user::UserResponse,
     pub tokens: TokenPair,
+    // Only with COOKIE_SESSIONS; echo it in X-CSRF-Token on state-changing requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub csrf_token: Option<String>,
+    // The password is older than PASSWORD_MAX_AGE_DAYS; the tokens can only
+    // change it, see PASSWORD_CHANGE
+    #[serde(default)]
+    pub password_change_required: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RefreshTokenRequest {
+    // May be left out with COOKIE_SESSIONS, which sends it as a cookie
+    #[serde(default)]
     pub refresh_token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RefreshTokenResponse {
+    // Left out with TOKEN_DELIVERY=cookie, which sets it as a cookie instead
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub access_token: String,
     pub refresh_token: Option<String>,
     pub token_type: String,
     pub expires_in: usize,
     pub refresh_expires_in: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_token_jti: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CsrfTokenResponse {
+    pub csrf_token: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LogoutRequest {
     pub refresh_token: Option<String>,
     pub logout_all_devices: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct LogoutResponse {
     pub message: String,
     pub logged_out_devices: Option<u32>,
+    // Only set for logout_all_devices; false means nothing was revoked and the
+    // request should be repeated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub complete: Option<bool>,
+}
+
+// Result of revoking every refresh token a user holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RevocationOutcome {
+    pub revoked: u64,
+    pub complete: bool,
+    // Last database error when the revocation stopped early; for logs only
+    pub error: Option<String>,
+}
+
+// Batch size and per-batch retries used when revoking all of a user's refresh tokens
+#[derive(Debug, Clone, Copy)]
+pub struct RevocationPolicy {
+    pub batch_size: i64,
+    pub max_retries: u32,
+}
+
+impl Default for RevocationPolicy {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            max_retries: 2,
+        }
+    }
 }
 
 // Token blacklist model for database storage
@@ -96,6 +196,23 @@ impl BlacklistedToken {
     }
 }
 
+// A row of signing_keys; revoked keys keep their kid but lose the secret
+#[derive(Debug, Clone)]
+pub struct StoredSigningKey {
+    pub kid: String,
+    pub secret: Option<String>,
+    pub active: bool,
+    pub created_at: OffsetDateTime,
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+// What a key rotation did: the new active kid and the kids dropped with it
+#[derive(Debug, Clone)]
+pub struct KeyRotation {
+    pub kid: String,
+    pub revoked: Vec<String>,
+}
+
 // JWT error types
 #[derive(Debug)]
 pub enum JwtError {
@@ -105,6 +222,9 @@ pub enum JwtError {
     MissingToken,
     BlacklistedToken,
     InvalidClaims(String),
+    AccountUnavailable(String),
+    // A refresh token presented by a client other than the one it was bound to
+    BindingMismatch,
 }
 
 impl std::fmt::Display for JwtError {
@@ -116,12 +236,101 @@ impl std::fmt::Display for JwtError {
             JwtError::MissingToken => write!(f, "No token provided"),
             JwtError::BlacklistedToken => write!(f, "Token has been revoked"),
             JwtError::InvalidClaims(e) => write!(f, "Invalid token claims: {}", e),
+            JwtError::AccountUnavailable(e) => write!(f, "Account unavailable: {}", e),
+            JwtError::BindingMismatch => {
+                write!(f, "Refresh token presented by a different client")
+            }
         }
     }
 }
 
 impl std::error::Error for JwtError {}
 
+// Body of a 401 from the JWT middleware. `code` tells clients what to do next:
+// only TOKEN_EXPIRED is worth a refresh, the others need a new login.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TokenErrorResponse {
+    pub error: String,
+    pub code: String,
+}
+
+// Why the JWT middleware rejected a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenRejection {
+    Missing,
+    Malformed,
+    Expired,
+    Revoked,
+    Invalid,
+    AccountUnavailable,
+}
+
+impl TokenRejection {
+    // Maps validation failures; None for errors that are not the client's fault
+    pub fn from_jwt_error(error: &JwtError) -> Option<Self> {
+        match error {
+            JwtError::MissingToken => Some(TokenRejection::Missing),
+            JwtError::ExpiredToken => Some(TokenRejection::Expired),
+            JwtError::BlacklistedToken | JwtError::BindingMismatch => Some(TokenRejection::Revoked),
+            JwtError::InvalidToken(_) | JwtError::InvalidClaims(_) => Some(TokenRejection::Invalid),
+            JwtError::AccountUnavailable(_) => Some(TokenRejection::AccountUnavailable),
+            JwtError::TokenCreationError(_) => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            TokenRejection::Missing => "TOKEN_MISSING",
+            TokenRejection::Malformed | TokenRejection::Invalid => "TOKEN_INVALID",
+            TokenRejection::Expired => "TOKEN_EXPIRED",
+            TokenRejection::Revoked => "TOKEN_REVOKED",
+            TokenRejection::AccountUnavailable => "ACCOUNT_UNAVAILABLE",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            TokenRejection::Missing => "Missing authorization header",
+            TokenRejection::Malformed => "Invalid authorization header format",
+            TokenRejection::Expired => "Token has expired",
+            TokenRejection::Revoked => "Token has been revoked",
+            TokenRejection::Invalid => "Invalid token",
+            TokenRejection::AccountUnavailable => "Account is disabled or no longer exists",
+        }
+    }
+
+    // RFC 6750 challenge; a request without credentials gets no error attribute
+    pub fn www_authenticate(&self) -> String {
+        let error = match self {
+            TokenRejection::Missing => return "Bearer".to_string(),
+            TokenRejection::Malformed => "invalid_request",
+            _ => "invalid_token",
+        };
+        format!(
+            r#"Bearer error="{}", error_description="{}""#,
+            error,
+            self.message()
+        )
+    }
+}
+
+impl IntoResponse for TokenRejection {
+    fn into_response(self) -> Response {
+        let body = TokenErrorResponse {
+            error: self.message().to_string(),
+            code: self.code().to_string(),
+        };
+
+        let mut response = (StatusCode::UNAUTHORIZED, Json(body)).into_response();
+        if let Ok(challenge) = HeaderValue::from_str(&self.www_authenticate()) {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, challenge);
+        }
+        response
+    }
+}
+
 // Authentication context for middleware
 #[derive(Debug, Clone)]
 pub struct AuthContext {
@@ -129,6 +338,18 @@ pub struct AuthContext {
     pub email: String,
     pub roles: Vec<String>,
     pub jti: String,
+    // The refresh token jti the access token belongs to, see Claims::sid
+    pub session_id: Option<String>,
+    // Access for JWTs, ApiKey when the bearer token was an API key
+    pub token_type: TokenType,
+    // Checked by RequireScope
+    pub scopes: Vec<String>,
+}
+
+impl AuthContext {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| grants(granted, scope))
+    }
 }
 
 impl From<Claims> for AuthContext {
@@ -138,6 +359,9 @@ impl From<Claims> for AuthContext {
             email: claims.email,
             roles: claims.roles,
             jti: claims.jti,
+            session_id: claims.sid,
+            token_type: claims.token_type,
+            scopes: claims.scopes,
         }
     }
 }
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,6 +53,46 @@ impl LoginAttempt {
             created_at: OffsetDateTime::now_utc(),
         }
     }
+
+    // Links a failure to the account it was made against, so it shows up in
+    // that user's login history
+    pub fn for_user(mut self, user_id: Uuid) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+}
+
+// Query string of GET /api/auth/login-history
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LoginHistoryQuery {
+    // 1-based, defaults to 1
+    pub page: Option<i64>,
+    // Defaults to 20, at most 100
+    pub per_page: Option<i64>,
+}
+
+// One sign-in attempt as shown to the account owner; the email typed and the
+// attempt's id are left out
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LoginHistoryEntry {
+    pub success: bool,
+    pub failure_reason: Option<String>,
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+impl From<LoginAttempt> for LoginHistoryEntry {
+    fn from(attempt: LoginAttempt) -> Self {
+        Self {
+            success: attempt.success,
+            failure_reason: attempt.failure_reason,
+            ip_address: attempt.ip_address,
+            user_agent: attempt.user_agent,
+            created_at: attempt.created_at,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,27 +103,297 @@ pub struct AccountLockout {
     pub failed_attempts: i32,
     pub locked_at: OffsetDateTime,
     pub unlocked_at: Option<OffsetDateTime>,
+    // 1 for a first lockout, 2 if the account was locked again before escalation reset, ...
+    pub lockout_count: i32,
+    // Set by a successful login; later lockouts start over from the first duration
+    pub escalation_reset_at: Option<OffsetDateTime>,
 }
 
 impl AccountLockout {
     pub fn new(user_id: Uuid, failed_attempts: i32, lockout_duration_minutes: i64) -> Self {
+        Self::escalated(
+            user_id,
+            failed_attempts,
+            1,
+            time::Duration::minutes(lockout_duration_minutes),
+        )
+    }
+
+    pub fn escalated(
+        user_id: Uuid,
+        failed_attempts: i32,
+        lockout_count: i32,
+        duration: time::Duration,
+    ) -> Self {
         let now = OffsetDateTime::now_utc();
         Self {
             id: Uuid::new_v4(),
             user_id,
-            locked_until: now + time::Duration::minutes(lockout_duration_minutes),
+            locked_until: now + duration,
             failed_attempts,
             locked_at: now,
             unlocked_at: None,
+            lockout_count,
+            escalation_reset_at: None,
         }
     }
 
+    pub fn duration(&self) -> time::Duration {
+        self.locked_until - self.locked_at
+    }
+
     pub fn is_locked(&self) -> bool {
-        let now = OffsetDateTime::now_utc();
+        self.is_locked_at(OffsetDateTime::now_utc())
+    }
+
+    pub fn is_locked_at(&self, now: OffsetDateTime) -> bool {
         self.unlocked_at.is_none() && now < self.locked_until
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum LockoutConfigError {
+    #[error("{0} must be a comma-separated list of positive integers")]
+    InvalidDurations(&'static str),
+    #[error("{0} must be a non-negative integer")]
+    InvalidCooldown(&'static str),
+    #[error("{0} must be a positive integer")]
+    InvalidThreshold(&'static str),
+    #[error("{0} must be a non-negative number of milliseconds")]
+    InvalidDelay(&'static str),
+}
+
+// When an account gets locked: after `max_attempts` failed logins within
+// `window` the next failure locks it. Only failures since the user's last
+// successful login count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockoutConfig {
+    pub max_attempts: u32,
+    pub window: time::Duration,
+    pub lockout_duration: LockoutPolicy,
+    // Whether a locked-out user may end the lockout with an emailed token
+    pub self_service_unlock: bool,
+}
+
+impl LockoutConfig {
+    // LOCKOUT_MAX_ATTEMPTS, LOCKOUT_WINDOW_SECS and LOCKOUT_SELF_SERVICE_UNLOCK,
+    // plus the LockoutPolicy variables for the duration; unset or empty values
+    // keep the defaults
+    pub fn from_env() -> Result<Self, LockoutConfigError> {
+        let default = Self::default();
+        let positive = |name: &'static str| {
+            let value = std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+            match value {
+                Some(value) => match value.parse::<u32>() {
+                    Ok(number) if number > 0 => Ok(Some(number)),
+                    _ => Err(LockoutConfigError::InvalidThreshold(name)),
+                },
+                None => Ok(None),
+            }
+        };
+
+        Ok(Self {
+            max_attempts: positive("LOCKOUT_MAX_ATTEMPTS")?.unwrap_or(default.max_attempts),
+            window: positive("LOCKOUT_WINDOW_SECS")?
+                .map(|secs| time::Duration::seconds(secs.into()))
+                .unwrap_or(default.window),
+            lockout_duration: LockoutPolicy::from_env()?,
+            self_service_unlock: match std::env::var("LOCKOUT_SELF_SERVICE_UNLOCK") {
+                Ok(value) => !(value.eq_ignore_ascii_case("false") || value.trim() == "0"),
+                Err(_) => default.self_service_unlock,
+            },
+        })
+    }
+}
+
+impl Default for LockoutConfig {
+    // The 10th failed login within an hour locks the account
+    fn default() -> Self {
+        Self {
+            max_attempts: 9,
+            window: time::Duration::hours(1),
+            lockout_duration: LockoutPolicy::default(),
+            self_service_unlock: true,
+        }
+    }
+}
+
+// How long each consecutive lockout lasts. The n-th lockout in a row uses the
+// n-th duration, and anything past the end of the list reuses the last one.
+// A lockout only counts towards the streak if the previous one ended less than
+// `cooldown` ago and no successful login happened since.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    durations: Vec<time::Duration>,
+    cooldown: time::Duration,
+}
+
+impl LockoutPolicy {
+    // None when `durations` is empty
+    pub fn new(durations: Vec<time::Duration>, cooldown: time::Duration) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        Some(Self {
+            durations,
+            cooldown,
+        })
+    }
+
+    // LOCKOUT_DURATIONS_SECS (comma-separated) and LOCKOUT_ESCALATION_COOLDOWN_SECS;
+    // unset or empty values keep the defaults
+    pub fn from_env() -> Result<Self, LockoutConfigError> {
+        let default = Self::default();
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+
+        let durations = match var("LOCKOUT_DURATIONS_SECS") {
+            Some(value) => value
+                .split(',')
+                .map(|secs| match secs.trim().parse::<i64>() {
+                    Ok(secs) if secs > 0 => Ok(time::Duration::seconds(secs)),
+                    _ => Err(LockoutConfigError::InvalidDurations(
+                        "LOCKOUT_DURATIONS_SECS",
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => default.durations,
+        };
+        let cooldown = match var("LOCKOUT_ESCALATION_COOLDOWN_SECS") {
+            Some(value) => match value.parse::<i64>() {
+                Ok(secs) if secs >= 0 => time::Duration::seconds(secs),
+                _ => {
+                    return Err(LockoutConfigError::InvalidCooldown(
+                        "LOCKOUT_ESCALATION_COOLDOWN_SECS",
+                    ));
+                }
+            },
+            None => default.cooldown,
+        };
+
+        Ok(Self {
+            durations,
+            cooldown,
+        })
+    }
+
+    // Duration of the `lockout_count`-th lockout in a row, starting at 1
+    pub fn duration_for(&self, lockout_count: i32) -> time::Duration {
+        let index = (lockout_count.max(1) as usize - 1).min(self.durations.len() - 1);
+        self.durations[index]
+    }
+
+    pub fn cooldown(&self) -> time::Duration {
+        self.cooldown
+    }
+}
+
+// How long failed logins are held back before the response goes out. The n-th
+// failure in a row from an IP or against an account waits `base_delay` doubled
+// n - 1 times, up to `max_delay`. A successful login starts the count over, and
+// a zero `max_delay` turns the delay off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TarpitConfig {
+    pub base_delay: std::time::Duration,
+    pub max_delay: std::time::Duration,
+    pub window: time::Duration,
+}
+
+impl TarpitConfig {
+    pub fn disabled() -> Self {
+        Self {
+            max_delay: std::time::Duration::ZERO,
+            ..Self::default()
+        }
+    }
+
+    // LOGIN_TARPIT_BASE_MS and LOGIN_TARPIT_MAX_MS; unset or empty values keep the defaults
+    pub fn from_env() -> Result<Self, LockoutConfigError> {
+        let default = Self::default();
+        let millis = |name: &'static str, default: std::time::Duration| {
+            let value = std::env::var(name)
+                .ok()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+            match value {
+                Some(value) => value
+                    .parse::<u64>()
+                    .map(std::time::Duration::from_millis)
+                    .map_err(|_| LockoutConfigError::InvalidDelay(name)),
+                None => Ok(default),
+            }
+        };
+
+        Ok(Self {
+            base_delay: millis("LOGIN_TARPIT_BASE_MS", default.base_delay)?,
+            max_delay: millis("LOGIN_TARPIT_MAX_MS", default.max_delay)?,
+            window: default.window,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.base_delay.is_zero() && !self.max_delay.is_zero()
+    }
+
+    // Delay owed after `failures` failed logins in a row
+    pub fn delay_for(&self, failures: i64) -> std::time::Duration {
+        if failures <= 0 || !self.is_enabled() {
+            return std::time::Duration::ZERO;
+        }
+        let doublings = (failures - 1).min(31) as u32;
+        self.base_delay
+            .saturating_mul(1 << doublings)
+            .min(self.max_delay)
+    }
+}
+
+impl Default for TarpitConfig {
+    // 250ms after the first failure within 15 minutes, doubling up to 2s
+    fn default() -> Self {
+        Self {
+            base_delay: std::time::Duration::from_millis(250),
+            max_delay: std::time::Duration::from_secs(2),
+            window: time::Duration::minutes(15),
+        }
+    }
+}
+
+// "1 minute", "15 minutes", "2 hours"; falls back to seconds for uneven values
+pub fn describe_duration(duration: &time::Duration) -> String {
+    let secs = duration.whole_seconds();
+    let (amount, unit) = if secs % 3600 == 0 {
+        (secs / 3600, "hour")
+    } else if secs % 60 == 0 {
+        (secs / 60, "minute")
+    } else {
+        (secs, "second")
+    };
+    let plural = if amount == 1 { "" } else { "s" };
+    format!("{} {}{}", amount, unit, plural)
+}
+
+impl Default for LockoutPolicy {
+    // 1 minute, 5 minutes, 15 minutes, then 1 hour; the streak resets after a day
+    fn default() -> Self {
+        Self {
+            durations: vec![
+                time::Duration::minutes(1),
+                time::Duration::minutes(5),
+                time::Duration::minutes(15),
+                time::Duration::hours(1),
+            ],
+            cooldown: time::Duration::hours(24),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RefreshTokenStorage {
     pub id: Uuid,
@@ -92,6 +404,10 @@ pub struct RefreshTokenStorage {
     pub revoked_at: Option<OffsetDateTime>,
     pub created_at: Option<OffsetDateTime>, // Nullable in the database
     pub last_used_at: Option<OffsetDateTime>,
+    pub device_name: Option<String>,
+    // The client the token was issued to, checked under REFRESH_TOKEN_BINDING
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
 }
 
 impl RefreshTokenStorage {
@@ -105,15 +421,125 @@ impl RefreshTokenStorage {
             revoked_at: None,
             created_at: Some(OffsetDateTime::now_utc()),
             last_used_at: None,
+            device_name: None,
+            user_agent: None,
+            ip_address: None,
+        }
+    }
+
+    pub fn with_device_name(mut self, device_name: Option<String>) -> Self {
+        self.device_name = device_name;
+        self
+    }
+
+    pub fn with_origin(mut self, origin: TokenOrigin) -> Self {
+        self.user_agent = origin.user_agent;
+        self.ip_address = origin.ip_address;
+        self
+    }
+
+    pub fn origin(&self) -> TokenOrigin {
+        TokenOrigin {
+            user_agent: self.user_agent.clone(),
+            ip_address: self.ip_address.clone(),
         }
     }
 
     pub fn is_valid(&self) -> bool {
-        let now = OffsetDateTime::now_utc();
+        self.is_valid_at(OffsetDateTime::now_utc())
+    }
+
+    pub fn is_valid_at(&self, now: OffsetDateTime) -> bool {
         self.revoked_at.is_none() && now < self.expires_at
     }
 
+    // Not used for longer than `idle_timeout` since it was issued or last refreshed
+    pub fn is_idle(&self, idle_timeout: time::Duration) -> bool {
+        self.is_idle_at(idle_timeout, OffsetDateTime::now_utc())
+    }
+
+    pub fn is_idle_at(&self, idle_timeout: time::Duration, now: OffsetDateTime) -> bool {
+        self.last_used_at
+            .or(self.created_at)
+            .is_some_and(|last_activity| now - last_activity > idle_timeout)
+    }
+
     pub fn revoke(&mut self) {
         self.revoked_at = Some(OffsetDateTime::now_utc());
     }
 }
+
+// The user agent and IP address of the request a refresh token was issued to
+// or presented by
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenOrigin {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl TokenOrigin {
+    pub fn new(user_agent: Option<String>, ip_address: Option<String>) -> Self {
+        Self {
+            user_agent,
+            ip_address,
+        }
+    }
+
+    // Fields this origin lacks are taken from `other`
+    pub fn or(self, other: TokenOrigin) -> Self {
+        Self {
+            user_agent: self.user_agent.or(other.user_agent),
+            ip_address: self.ip_address.or(other.ip_address),
+        }
+    }
+}
+
+// How strictly a refresh token is tied to the client it was issued to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RefreshTokenBinding {
+    #[default]
+    Off,
+    // The presenting user agent must be exactly the stored one
+    UserAgent,
+    // The presenting IP must be in the same /24 (IPv4) or /64 (IPv6)
+    IpSubnet,
+}
+
+impl RefreshTokenBinding {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "off" => Some(RefreshTokenBinding::Off),
+            "user_agent" => Some(RefreshTokenBinding::UserAgent),
+            "ip_subnet" => Some(RefreshTokenBinding::IpSubnet),
+            _ => None,
+        }
+    }
+
+    // Tokens issued without the bound field recorded pass, so turning binding
+    // on doesn't end sessions that started before it
+    pub fn allows(&self, stored: &TokenOrigin, presented: &TokenOrigin) -> bool {
+        match self {
+            RefreshTokenBinding::Off => true,
+            RefreshTokenBinding::UserAgent => match &stored.user_agent {
+                Some(user_agent) => presented.user_agent.as_ref() == Some(user_agent),
+                None => true,
+            },
+            RefreshTokenBinding::IpSubnet => match &stored.ip_address {
+                Some(ip_address) => presented
+                    .ip_address
+                    .as_deref()
+                    .is_some_and(|presented| same_subnet(ip_address, presented)),
+                None => true,
+            },
+        }
+    }
+}
+
+// Addresses that don't parse only match themselves
+fn same_subnet(a: &str, b: &str) -> bool {
+    match (a.parse::<IpAddr>(), b.parse::<IpAddr>()) {
+        (Ok(IpAddr::V4(a)), Ok(IpAddr::V4(b))) => a.octets()[..3] == b.octets()[..3],
+        (Ok(IpAddr::V6(a)), Ok(IpAddr::V6(b))) => a.segments()[..4] == b.segments()[..4],
+        _ => a == b,
+    }
+}
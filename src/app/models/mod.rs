@@ -1,8 +1,19 @@
+pub mod account_export;
+pub mod admin_audit;
+pub mod api_key;
 pub mod auth;
+pub mod client;
+pub mod common;
 pub mod jwt;
 pub mod login_attempt;
+pub mod oauth;
+pub mod otp;
 pub mod password_reset;
 pub mod project;
+pub mod role;
+pub mod scope;
+pub mod session;
 pub mod task;
 pub mod time_entry;
+pub mod trusted_device;
 pub mod user;
@@ -0,0 +1,92 @@
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+pub const GOOGLE_PROVIDER: &str = "google";
+pub const GITHUB_PROVIDER: &str = "github";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub email: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+// What Chronos needs to know about the user from any provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OAuthUserInfo {
+    pub subject: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct OAuthCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OAuthError {
+    #[error("OAuth provider is not configured")]
+    NotConfigured,
+    #[error("Invalid or expired OAuth state")]
+    InvalidState,
+    #[error("Authorization was denied: {0}")]
+    AuthorizationDenied(String),
+    #[error("OAuth provider error: {0}")]
+    Provider(String),
+    #[error("Email address is not verified by the provider")]
+    EmailNotVerified,
+    #[error("Account unavailable")]
+    AccountUnavailable,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to create user: {0}")]
+    UserCreation(String),
+}
+
+impl OAuthError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            OAuthError::NotConfigured => StatusCode::NOT_FOUND,
+            OAuthError::InvalidState | OAuthError::AuthorizationDenied(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            OAuthError::EmailNotVerified | OAuthError::AccountUnavailable => StatusCode::FORBIDDEN,
+            OAuthError::Provider(_) => StatusCode::BAD_GATEWAY,
+            OAuthError::Database(_) | OAuthError::UserCreation(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            OAuthError::NotConfigured => "OAUTH_NOT_CONFIGURED",
+            OAuthError::InvalidState => "INVALID_STATE",
+            OAuthError::AuthorizationDenied(_) => "AUTHORIZATION_DENIED",
+            OAuthError::Provider(_) => "OAUTH_PROVIDER_ERROR",
+            OAuthError::EmailNotVerified => "EMAIL_NOT_VERIFIED",
+            OAuthError::AccountUnavailable => "ACCOUNT_UNAVAILABLE",
+            OAuthError::Database(_) | OAuthError::UserCreation(_) => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn public_message(&self) -> String {
+        match self {
+            OAuthError::Database(_) | OAuthError::UserCreation(_) => {
+                "Failed to complete OAuth login".to_string()
+            }
+            error => error.to_string(),
+        }
+    }
+}
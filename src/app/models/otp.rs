@@ -0,0 +1,336 @@
+use crate::app::models::auth::validate_email;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+pub const OTP_CODE_LEN: usize = 6;
+
+// What a code was sent for; stored in otp_challenges.purpose
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpPurpose {
+    Login,
+    PhoneVerification,
+}
+
+impl OtpPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OtpPurpose::Login => "login",
+            OtpPurpose::PhoneVerification => "phone_verification",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OtpChallenge {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: String,
+    pub phone_number: String,
+    pub code_hash: String,
+    pub attempts: i32,
+    pub remember_me: bool,
+    pub device_name: Option<String>,
+    pub client_id: Option<String>,
+    pub expires_at: OffsetDateTime,
+    pub consumed_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+impl OtpChallenge {
+    pub fn new(
+        user_id: Uuid,
+        purpose: OtpPurpose,
+        phone_number: &str,
+        code_hash: String,
+        ttl: time::Duration,
+    ) -> Self {
+        let now = OffsetDateTime::now_utc();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            purpose: purpose.as_str().to_string(),
+            phone_number: phone_number.to_string(),
+            code_hash,
+            attempts: 0,
+            remember_me: false,
+            device_name: None,
+            client_id: None,
+            expires_at: now + ttl,
+            consumed_at: None,
+            created_at: now,
+        }
+    }
+
+    // Login options carried over to the tokens issued once the code is entered
+    pub fn with_login_options(
+        mut self,
+        remember_me: bool,
+        device_name: Option<String>,
+        client_id: Option<String>,
+    ) -> Self {
+        self.remember_me = remember_me;
+        self.device_name = device_name;
+        self.client_id = client_id;
+        self
+    }
+
+    // A uniformly random code of OTP_CODE_LEN digits, leading zeros included
+    pub fn generate_code() -> String {
+        use rand::Rng;
+        let code = rand::rng().random_range(0..10u32.pow(OTP_CODE_LEN as u32));
+        format!("{:0width$}", code, width = OTP_CODE_LEN)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        OffsetDateTime::now_utc() > self.expires_at
+    }
+}
+
+// A passwordless sign-in code sent by email
+#[derive(Debug, Clone)]
+pub struct EmailLoginCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: String,
+    pub attempts: i32,
+    pub expires_at: OffsetDateTime,
+    pub consumed_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+impl EmailLoginCode {
+    pub fn new(user_id: Uuid, code_hash: String, ttl: time::Duration) -> Self {
+        let now = OffsetDateTime::now_utc();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            code_hash,
+            attempts: 0,
+            expires_at: now + ttl,
+            consumed_at: None,
+            created_at: now,
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        OffsetDateTime::now_utc() > self.expires_at
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OtpError {
+    #[error("SMS verification is not configured")]
+    NotConfigured,
+    #[error("Invalid or expired verification challenge")]
+    InvalidChallenge,
+    #[error("Verification code has expired")]
+    Expired,
+    #[error("Invalid verification code")]
+    InvalidCode,
+    #[error("Too many incorrect codes. Please start again.")]
+    TooManyAttempts,
+    #[error("Account unavailable")]
+    AccountUnavailable,
+    #[error("Authentication failed")]
+    AuthenticationFailed,
+    #[error("No phone number is waiting for verification")]
+    NoPendingVerification,
+    #[error("SMS delivery failed: {0}")]
+    Delivery(String),
+    #[error("Code hashing error: {0}")]
+    Hashing(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl OtpError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            OtpError::NotConfigured => StatusCode::NOT_FOUND,
+            OtpError::Expired | OtpError::InvalidCode | OtpError::NoPendingVerification => {
+                StatusCode::BAD_REQUEST
+            }
+            OtpError::InvalidChallenge => StatusCode::UNAUTHORIZED,
+            OtpError::AccountUnavailable => StatusCode::FORBIDDEN,
+            OtpError::TooManyAttempts => StatusCode::TOO_MANY_REQUESTS,
+            OtpError::Delivery(_) => StatusCode::BAD_GATEWAY,
+            OtpError::AuthenticationFailed | OtpError::Hashing(_) | OtpError::Database(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            OtpError::NotConfigured => "SMS_NOT_CONFIGURED",
+            OtpError::InvalidChallenge => "INVALID_CHALLENGE",
+            OtpError::Expired => "CODE_EXPIRED",
+            OtpError::InvalidCode => "INVALID_CODE",
+            OtpError::TooManyAttempts => "RATE_LIMITED",
+            OtpError::AccountUnavailable => "ACCOUNT_UNAVAILABLE",
+            OtpError::NoPendingVerification => "NO_PENDING_VERIFICATION",
+            OtpError::Delivery(_) => "SMS_DELIVERY_FAILED",
+            OtpError::AuthenticationFailed | OtpError::Hashing(_) | OtpError::Database(_) => {
+                "INTERNAL_ERROR"
+            }
+        }
+    }
+
+    // Provider and database errors are logged; clients get a generic message
+    pub fn public_message(&self) -> String {
+        match self {
+            OtpError::Delivery(_) => "Failed to send the SMS code".to_string(),
+            OtpError::AuthenticationFailed | OtpError::Hashing(_) | OtpError::Database(_) => {
+                "SMS verification failed".to_string()
+            }
+            error => error.to_string(),
+        }
+    }
+}
+
+impl From<crate::app::hashing::HashingError> for OtpError {
+    fn from(e: crate::app::hashing::HashingError) -> Self {
+        OtpError::Hashing(e.to_string())
+    }
+}
+
+// Strips spaces, dashes, dots and brackets and checks the rest is E.164:
+// a + and 8 to 15 digits, the first of them not 0
+pub fn normalize_phone_number(phone_number: &str) -> Option<String> {
+    let compact: String = phone_number
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+    let digits = compact.strip_prefix('+')?;
+    let valid = (8..=15).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && !digits.starts_with('0');
+    valid.then_some(compact)
+}
+
+// "+*********6789": enough for the user to recognise their own number
+pub fn mask_phone_number(phone_number: &str) -> String {
+    let visible = phone_number.len().saturating_sub(4).max(1);
+    let (head, tail) = phone_number.split_at(visible);
+    let masked: String = head
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i == 0 { c } else { '*' })
+        .collect();
+    format!("{}{}", masked, tail)
+}
+
+// Returned by login instead of tokens when the account has SMS verification on
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OtpChallengeResponse {
+    pub message: String,
+    pub challenge_id: Uuid,
+    pub sent_to: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OtpLoginRequest {
+    pub challenge_id: Uuid,
+    pub code: String,
+    // Skip the SMS code on this device's next logins, see TRUSTED_DEVICE_DAYS
+    #[serde(default)]
+    pub trust_device: bool,
+}
+
+// The same answer whether or not the email is registered
+pub const EMAIL_CODE_MESSAGE: &str =
+    "If the email is registered, a sign-in code has been sent to it.";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, ToSchema)]
+pub struct EmailCodeRequest {
+    #[validate(custom(function = "validate_email", message = "Invalid email format"))]
+    pub email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EmailCodeResponse {
+    pub message: String,
+    // How long a code stays valid, whether or not one was sent
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EmailCodeLoginRequest {
+    pub email: String,
+    pub code: String,
+    // Issue a refresh token that lives REMEMBER_ME_TTL_SECS instead of 7 days
+    #[serde(default)]
+    pub remember_me: bool,
+    // Label for the new session; the X-Device-Name header is used when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device_name: Option<String>,
+    // A registered client; the tokens' aud is limited to it, see Client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SetPhoneRequest {
+    pub phone_number: String,
+    pub current_password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyPhoneRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RemovePhoneRequest {
+    pub current_password: String,
+}
+
+// A phone number only counts as a second factor once verified
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PhoneResponse {
+    pub phone_number: Option<String>,
+    pub verified: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_phone_number() {
+        assert_eq!(
+            normalize_phone_number(" +49 (151) 2345-6789 "),
+            Some("+4915123456789".to_string())
+        );
+        assert_eq!(
+            normalize_phone_number("+1.415.555.0100"),
+            Some("+14155550100".to_string())
+        );
+        assert_eq!(normalize_phone_number("015123456789"), None);
+        assert_eq!(normalize_phone_number("+0151234567"), None);
+        assert_eq!(normalize_phone_number("+1234567"), None);
+        assert_eq!(normalize_phone_number("+1234567890123456"), None);
+        assert_eq!(normalize_phone_number("+1 415 CALL NOW"), None);
+    }
+
+    #[test]
+    fn test_generate_code() {
+        for _ in 0..100 {
+            let code = OtpChallenge::generate_code();
+            assert_eq!(code.len(), OTP_CODE_LEN);
+            assert!(code.chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn test_mask_phone_number() {
+        assert_eq!(mask_phone_number("+4915123456789"), "+*********6789");
+        assert_eq!(mask_phone_number("+123"), "+123");
+    }
+}
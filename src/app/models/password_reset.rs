@@ -1,8 +1,5 @@
-use argon2::{
-    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
-    password_hash::{SaltString, rand_core::OsRng},
-};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
@@ -20,40 +17,54 @@ pub struct PasswordResetToken {
 
 impl PasswordResetToken {
     pub fn new(user_id: Uuid, plain_token: &str) -> Result<Self, argon2::password_hash::Error> {
-        let token_hash = Self::hash_token(plain_token)?;
-        let expires_at = OffsetDateTime::now_utc() + time::Duration::hours(1);
+        Self::new_at(user_id, plain_token, OffsetDateTime::now_utc())
+    }
+
+    // A token issued at `now`, valid for an hour from then
+    pub fn new_at(
+        user_id: Uuid,
+        plain_token: &str,
+        now: OffsetDateTime,
+    ) -> Result<Self, argon2::password_hash::Error> {
+        let token_hash = Self::hash_token(plain_token);
 
         Ok(Self {
             id: Uuid::new_v4(),
             user_id,
             token_hash,
-            expires_at,
+            expires_at: now + time::Duration::hours(1),
             used: false,
-            created_at: OffsetDateTime::now_utc(),
+            created_at: now,
         })
     }
 
-    pub fn hash_token(token: &str) -> Result<String, argon2::password_hash::Error> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let token_hash = argon2.hash_password(token.as_bytes(), &salt)?;
-        Ok(token_hash.to_string())
+    // The token is 64 random characters, so a plain SHA-256 is enough and lets
+    // the row be looked up by its hash instead of checked against every token
+    pub fn hash_token(token: &str) -> String {
+        Sha256::digest(token.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
     }
 
     pub fn verify_token(&self, token: &str) -> Result<bool, argon2::password_hash::Error> {
-        let parsed_hash = PasswordHash::new(&self.token_hash)?;
-        let argon2 = Argon2::default();
-        Ok(argon2
-            .verify_password(token.as_bytes(), &parsed_hash)
-            .is_ok())
+        Ok(Self::hash_token(token) == self.token_hash)
     }
 
     pub fn is_expired(&self) -> bool {
-        OffsetDateTime::now_utc() > self.expires_at
+        self.is_expired_at(OffsetDateTime::now_utc())
+    }
+
+    pub fn is_expired_at(&self, now: OffsetDateTime) -> bool {
+        now > self.expires_at
     }
 
     pub fn is_valid(&self) -> bool {
-        !self.used && !self.is_expired()
+        self.is_valid_at(OffsetDateTime::now_utc())
+    }
+
+    pub fn is_valid_at(&self, now: OffsetDateTime) -> bool {
+        !self.used && !self.is_expired_at(now)
     }
 
     pub fn generate_secure_token() -> String {
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+use validator::Validate;
+
+// Role every authenticated account holds, whether or not it is stored in user_roles
+pub const DEFAULT_ROLE: &str = "user";
+pub const ADMIN_ROLE: &str = "admin";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Role {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct RoleAssignmentRequest {
+    #[validate(length(min = 1, max = 50, message = "Role name must be 1-50 characters"))]
+    pub role: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserRolesResponse {
+    pub user_id: Uuid,
+    pub roles: Vec<String>,
+}
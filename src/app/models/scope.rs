@@ -0,0 +1,55 @@
+// What an access token or API key may do. Routes check them with RequireScope;
+// logins get every scope, API keys only the ones they were created with.
+pub const PROFILE_READ: &str = "profile:read";
+pub const PROFILE_WRITE: &str = "profile:write";
+// Password, phone number, API keys and account deletion
+pub const ACCOUNT_WRITE: &str = "account:write";
+pub const SESSIONS_READ: &str = "sessions:read";
+pub const SESSIONS_WRITE: &str = "sessions:write";
+pub const TIME_ENTRIES_READ: &str = "time-entries:read";
+pub const TIME_ENTRIES_WRITE: &str = "time-entries:write";
+pub const PROJECTS_READ: &str = "projects:read";
+pub const PROJECTS_WRITE: &str = "projects:write";
+pub const TASKS_READ: &str = "tasks:read";
+pub const TASKS_WRITE: &str = "tasks:write";
+// The only scope of the tokens a login with an expired password gets, see
+// PASSWORD_MAX_AGE_DAYS. Not in ALL_SCOPES; ACCOUNT_WRITE covers it instead.
+pub const PASSWORD_CHANGE: &str = "password:change";
+
+pub const ALL_SCOPES: &[&str] = &[
+    PROFILE_READ,
+    PROFILE_WRITE,
+    ACCOUNT_WRITE,
+    SESSIONS_READ,
+    SESSIONS_WRITE,
+    TIME_ENTRIES_READ,
+    TIME_ENTRIES_WRITE,
+    PROJECTS_READ,
+    PROJECTS_WRITE,
+    TASKS_READ,
+    TASKS_WRITE,
+];
+
+// The scopes of a user's own login; also what tokens issued before scopes
+// existed are read with
+pub fn default_scopes() -> Vec<String> {
+    ALL_SCOPES.iter().map(|scope| scope.to_string()).collect()
+}
+
+pub fn is_known_scope(scope: &str) -> bool {
+    ALL_SCOPES.contains(&scope)
+}
+
+// Whether holding `granted` passes a RequireScope(`required`)
+pub fn grants(granted: &str, required: &str) -> bool {
+    granted == required || (granted == ACCOUNT_WRITE && required == PASSWORD_CHANGE)
+}
+
+pub fn password_change_scopes() -> Vec<String> {
+    vec![PASSWORD_CHANGE.to_string()]
+}
+
+// Tokens that may do nothing but change the password
+pub fn is_password_change_only(scopes: &[String]) -> bool {
+    scopes == [PASSWORD_CHANGE]
+}
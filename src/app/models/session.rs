@@ -0,0 +1,87 @@
+use crate::app::models::login_attempt::RefreshTokenStorage;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+
+// Longest device name stored, matching refresh_tokens.device_name
+pub const MAX_DEVICE_NAME_LEN: usize = 100;
+
+// Trims the name and drops control characters; None if nothing is left.
+// Longer names are cut at MAX_DEVICE_NAME_LEN characters rather than rejected,
+// since the login header is set by clients the user doesn't control.
+pub fn normalize_device_name(name: &str) -> Option<String> {
+    let name: String = name
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .chars()
+        .take(MAX_DEVICE_NAME_LEN)
+        .collect();
+    let name = name.trim_end();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+// An active refresh token, as shown to the user who owns it
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionResponse {
+    pub jti: String,
+    pub device_name: Option<String>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub created_at: Option<OffsetDateTime>,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub last_used_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+    // The session the request's access token was issued for
+    pub current: bool,
+}
+
+impl SessionResponse {
+    pub fn new(token: RefreshTokenStorage, current_session: Option<&str>) -> Self {
+        let current = current_session == Some(token.jti.as_str());
+        Self {
+            jti: token.jti,
+            device_name: token.device_name,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+            expires_at: token.expires_at,
+            current,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionListResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RenameSessionRequest {
+    pub device_name: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_device_name() {
+        assert_eq!(
+            normalize_device_name("  Work laptop \n"),
+            Some("Work laptop".to_string())
+        );
+        assert_eq!(
+            normalize_device_name("Pixel\u{0}\u{7} 8"),
+            Some("Pixel 8".to_string())
+        );
+        assert_eq!(normalize_device_name(" \t "), None);
+        assert_eq!(normalize_device_name(""), None);
+
+        let long = "é".repeat(150);
+        assert_eq!(
+            normalize_device_name(&long).unwrap().chars().count(),
+            MAX_DEVICE_NAME_LEN
+        );
+    }
+}
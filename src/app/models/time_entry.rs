@@ -33,6 +33,14 @@ pub struct CreateTimeEntryRequest {
     pub end_time: Option<OffsetDateTime>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct StartTimerRequest {
+    #[validate(length(max = 1000, message = "Description cannot exceed 1000 characters"))]
+    pub description: Option<String>,
+    pub project_id: Option<Uuid>,
+    pub task_id: Option<Uuid>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct UpdateTimeEntryRequest {
     #[validate(length(max = 1000, message = "Description cannot exceed 1000 characters"))]
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// A device allowed to skip the SMS code at login until `expires_at`
+#[derive(Debug, Clone)]
+pub struct TrustedDevice {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub expires_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+impl TrustedDevice {
+    pub fn new(
+        user_id: Uuid,
+        token_hash: String,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+        ttl: time::Duration,
+    ) -> Self {
+        let now = OffsetDateTime::now_utc();
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            token_hash,
+            user_agent,
+            ip_address,
+            expires_at: now + ttl,
+            last_used_at: None,
+            created_at: now,
+        }
+    }
+}
+
+// A trusted device, as shown to the user who owns it
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrustedDeviceResponse {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub last_used_at: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+    // The device the request came from, going by its trusted device cookie
+    pub current: bool,
+}
+
+impl TrustedDeviceResponse {
+    pub fn new(device: TrustedDevice, current_token_hash: Option<&str>) -> Self {
+        let current = current_token_hash == Some(device.token_hash.as_str());
+        Self {
+            id: device.id,
+            user_agent: device.user_agent,
+            ip_address: device.ip_address,
+            created_at: device.created_at,
+            last_used_at: device.last_used_at,
+            expires_at: device.expires_at,
+            current,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrustedDeviceListResponse {
+    pub devices: Vec<TrustedDeviceResponse>,
+}
@@ -1,9 +1,8 @@
-use argon2::{
-    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
-    password_hash::{SaltString, rand_core::OsRng},
-};
+use crate::app::hashing::{Argon2Params, HashingError, PasswordHasher};
+use crate::app::models::auth::validate_email;
 use serde::{Deserialize, Serialize};
 use time;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -11,55 +10,158 @@ use validator::Validate;
 pub struct User {
     pub id: Uuid,
     pub name: Option<String>,
-    #[validate(email(message = "Invalid email format"))]
+    #[validate(custom(function = "validate_email", message = "Invalid email format"))]
     pub email: String,
+    // Lowercase; only set for accounts registered with USERNAME_LOGIN
+    #[serde(default)]
+    pub username: Option<String>,
     pub password_hash: String,
+    // When the current password was set; None for accounts older than the column
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub password_changed_at: Option<time::OffsetDateTime>,
     #[serde(default, with = "time::serde::rfc3339::option")]
     pub created_at: Option<time::OffsetDateTime>,
     #[serde(default, with = "time::serde::rfc3339::option")]
     pub updated_at: Option<time::OffsetDateTime>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
     pub name: Option<String>,
     pub email: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
     #[serde(default, with = "time::serde::rfc3339::option")]
     pub created_at: Option<time::OffsetDateTime>,
 }
 
+// What GET /api/auth/me reports about an account besides its profile
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountSummary {
+    // Granted roles, alphabetical
+    pub roles: Vec<String>,
+    pub email_verified: bool,
+    // A verified phone, so logins wait for an SMS code
+    pub two_factor_enabled: bool,
+    // Unrevoked, unexpired refresh tokens
+    pub active_sessions: i64,
+}
+
+// What happened to one user of a batch import, see UserRepository::import
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Created(Uuid),
+    // The email was taken and the existing account got the imported details
+    Updated(Uuid),
+    // The email was taken and the existing account was left alone
+    Skipped(Uuid),
+    Failed(String),
+}
+
+// users.status. Disabled accounts can't log in or use their tokens until an
+// admin re-enables them; deleted accounts are kept only for the audit trail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UserStatus {
+    Active,
+    Disabled,
+    Deleted,
+}
+
+impl UserStatus {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(UserStatus::Active),
+            "disabled" => Some(UserStatus::Disabled),
+            "deleted" => Some(UserStatus::Deleted),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::Active => "active",
+            UserStatus::Disabled => "disabled",
+            UserStatus::Deleted => "deleted",
+        }
+    }
+}
+
 impl User {
     pub fn new(
         name: Option<String>,
         email: String,
         password: &str,
     ) -> Result<Self, argon2::password_hash::Error> {
-        let password_hash = Self::hash_password(password)?;
+        Self::new_with_params(name, email, password, &Argon2Params::default())
+    }
 
-        Ok(Self {
+    pub fn new_with_params(
+        name: Option<String>,
+        email: String,
+        password: &str,
+        params: &Argon2Params,
+    ) -> Result<Self, argon2::password_hash::Error> {
+        let password_hash = Self::hash_password_with(password, params)?;
+        Ok(Self::with_password_hash(name, email, password_hash))
+    }
+
+    // Hashes on the blocking pool; use this from async request handling
+    pub async fn new_async(
+        name: Option<String>,
+        email: String,
+        password: &str,
+        params: &Argon2Params,
+    ) -> Result<Self, HashingError> {
+        let password_hash = params.hash_async(password).await?;
+        Ok(Self::with_password_hash(name, email, password_hash))
+    }
+
+    pub fn with_password_hash(name: Option<String>, email: String, password_hash: String) -> Self {
+        let now = time::OffsetDateTime::now_utc();
+        Self {
             id: Uuid::new_v4(),
             name,
             email,
+            username: None,
             password_hash,
-            created_at: Some(time::OffsetDateTime::now_utc()),
-            updated_at: Some(time::OffsetDateTime::now_utc()),
-        })
+            password_changed_at: Some(now),
+            created_at: Some(now),
+            updated_at: Some(now),
+        }
+    }
+
+    // Whether the password is older than `max_age` at `now`. Accounts without
+    // password_changed_at count from their creation, and never expire without either.
+    pub fn password_expired(&self, max_age: time::Duration, now: time::OffsetDateTime) -> bool {
+        self.password_changed_at
+            .or(self.created_at)
+            .is_some_and(|changed_at| now - changed_at > max_age)
     }
 
     pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt)?;
-        Ok(password_hash.to_string())
+        Self::hash_password_with(password, &Argon2Params::default())
+    }
+
+    pub fn hash_password_with(
+        password: &str,
+        params: &Argon2Params,
+    ) -> Result<String, argon2::password_hash::Error> {
+        params.hash(password)
     }
 
+    // The cost parameters are read from the stored hash, so this works for
+    // hashes made under any earlier Argon2Params, and for imported bcrypt and
+    // PBKDF2 hashes
     pub fn verify_password(&self, password: &str) -> Result<bool, argon2::password_hash::Error> {
-        let parsed_hash = PasswordHash::new(&self.password_hash)?;
-        let argon2 = Argon2::default();
-        Ok(argon2
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .is_ok())
+        PasswordHasher::default().verify_password(&self.password_hash, password)
+    }
+
+    pub async fn verify_password_async(&self, password: &str) -> Result<bool, HashingError> {
+        PasswordHasher::default()
+            .verify_password_async(&self.password_hash, password)
+            .await
     }
 
     pub fn to_response(&self) -> UserResponse {
@@ -67,7 +169,100 @@ impl User {
             id: self.id,
             name: self.name.clone(),
             email: self.email.clone(),
+            username: self.username.clone(),
             created_at: self.created_at,
         }
     }
 }
+
+// Keyset position in the users listing: the (created_at, id) of the last row
+// returned. The id breaks ties between users created in the same instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserCursor {
+    pub created_at: time::OffsetDateTime,
+    pub id: Uuid,
+}
+
+impl UserCursor {
+    pub fn from_user(user: &User) -> Option<Self> {
+        Some(Self {
+            created_at: user.created_at?,
+            id: user.id,
+        })
+    }
+
+    // Opaque to clients: "<created_at as unix nanoseconds>_<id>"
+    pub fn encode(&self) -> String {
+        format!("{}_{}", self.created_at.unix_timestamp_nanos(), self.id)
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let (nanos, id) = cursor.split_once('_')?;
+        Some(Self {
+            created_at: time::OffsetDateTime::from_unix_timestamp_nanos(nanos.parse().ok()?)
+                .ok()?,
+            id: Uuid::parse_str(id).ok()?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserListQuery {
+    pub after: Option<String>,
+    pub limit: Option<i64>,
+}
+
+// Query string of GET /api/admin/users
+#[derive(Debug, Deserialize)]
+pub struct AdminUserListQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub search: Option<String>,
+    pub sort: Option<String>,
+}
+
+// Orders for the admin users listing, newest first by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UserSort {
+    #[default]
+    CreatedAtDesc,
+    CreatedAtAsc,
+    EmailAsc,
+    EmailDesc,
+    NameAsc,
+    NameDesc,
+}
+
+impl UserSort {
+    // `created_at`, `email` or `name`; a leading `-` sorts descending
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "-created_at" => Some(UserSort::CreatedAtDesc),
+            "created_at" => Some(UserSort::CreatedAtAsc),
+            "email" => Some(UserSort::EmailAsc),
+            "-email" => Some(UserSort::EmailDesc),
+            "name" => Some(UserSort::NameAsc),
+            "-name" => Some(UserSort::NameDesc),
+            _ => None,
+        }
+    }
+
+    // The spelling parse accepts, which is also what UserRepository::list matches on
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserSort::CreatedAtDesc => "-created_at",
+            UserSort::CreatedAtAsc => "created_at",
+            UserSort::EmailAsc => "email",
+            UserSort::EmailDesc => "-email",
+            UserSort::NameAsc => "name",
+            UserSort::NameDesc => "-name",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UserListFilter {
+    // Case-insensitive substring of the name or email
+    pub search: Option<String>,
+    pub sort: UserSort,
+}
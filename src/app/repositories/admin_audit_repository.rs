@@ -0,0 +1,56 @@
+use crate::app::models::admin_audit::AdminAuditEntry;
+use sqlx::{PgPool, Result as SqlxResult};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct AdminAuditRepository {
+    pool: PgPool,
+}
+
+impl AdminAuditRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(
+        &self,
+        admin_id: Uuid,
+        action: &str,
+        target_user_id: Option<Uuid>,
+        details: Option<&str>,
+    ) -> SqlxResult<AdminAuditEntry> {
+        sqlx::query_as!(
+            AdminAuditEntry,
+            r#"
+            INSERT INTO admin_audit_log (admin_id, action, target_user_id, details)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, admin_id, action, target_user_id, details, created_at
+            "#,
+            admin_id,
+            action,
+            target_user_id,
+            details
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // Audit entries about a user, newest first
+    pub async fn find_by_target_user(
+        &self,
+        target_user_id: Uuid,
+    ) -> SqlxResult<Vec<AdminAuditEntry>> {
+        sqlx::query_as!(
+            AdminAuditEntry,
+            r#"
+            SELECT id, admin_id, action, target_user_id, details, created_at
+            FROM admin_audit_log
+            WHERE target_user_id = $1
+            ORDER BY created_at DESC
+            "#,
+            target_user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,72 @@
+use crate::app::models::api_key::{ApiKey, ApiKeyOwner};
+use sqlx::{PgPool, Result as SqlxResult};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct ApiKeyRepository {
+    pool: PgPool,
+}
+
+impl ApiKeyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        name: Option<&str>,
+        key_hash: &str,
+        scopes: &[String],
+        expires_at: Option<OffsetDateTime>,
+    ) -> SqlxResult<ApiKey> {
+        sqlx::query_as!(
+            ApiKey,
+            r#"
+            INSERT INTO api_keys (user_id, name, key_hash, scopes, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id, user_id, name, scopes, expires_at, created_at, revoked_at
+            "#,
+            user_id,
+            name,
+            key_hash,
+            scopes,
+            expires_at
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // Revoked and expired keys are returned too, so the caller can say why they fail
+    pub async fn find_by_hash(&self, key_hash: &str) -> SqlxResult<Option<ApiKeyOwner>> {
+        sqlx::query_as!(
+            ApiKeyOwner,
+            r#"
+            SELECT k.id AS key_id, k.user_id, u.email, k.scopes, k.expires_at, k.revoked_at
+            FROM api_keys k
+            JOIN users u ON u.id = k.user_id
+            WHERE k.key_hash = $1
+            "#,
+            key_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // False if the user has no unrevoked key `id`
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE api_keys SET revoked_at = NOW()
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+            "#,
+            id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::app::models::client::Client;
+use sqlx::{PgPool, Result as SqlxResult};
+use tracing::instrument;
+
+#[derive(Clone)]
+pub struct ClientRepository {
+    pool: PgPool,
+}
+
+impl ClientRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    #[instrument(name = "db.clients.create", skip_all)]
+    pub async fn create(
+        &self,
+        client_id: &str,
+        name: &str,
+        redirect_uris: &[String],
+        audiences: &[String],
+    ) -> SqlxResult<Client> {
+        sqlx::query_as!(
+            Client,
+            r#"
+            INSERT INTO clients (client_id, name, redirect_uris, audiences)
+            VALUES ($1, $2, $3, $4)
+            RETURNING client_id, name, redirect_uris, audiences, created_at
+            "#,
+            client_id,
+            name,
+            redirect_uris,
+            audiences
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    #[instrument(name = "db.clients.find", skip_all)]
+    pub async fn find(&self, client_id: &str) -> SqlxResult<Option<Client>> {
+        sqlx::query_as!(
+            Client,
+            r#"
+            SELECT client_id, name, redirect_uris, audiences, created_at
+            FROM clients
+            WHERE client_id = $1
+            "#,
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,117 @@
+use crate::app::models::otp::EmailLoginCode;
+use sqlx::{PgPool, Result as SqlxResult};
+use time::OffsetDateTime;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct EmailLoginCodeRepository {
+    pool: PgPool,
+}
+
+impl EmailLoginCodeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    #[instrument(name = "db.email_login_codes.create", skip_all)]
+    pub async fn create(&self, code: &EmailLoginCode) -> SqlxResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO email_login_codes (id, user_id, code_hash, attempts, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            "#,
+            code.id,
+            code.user_id,
+            code.code_hash,
+            code.attempts,
+            code.expires_at,
+            code.created_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // The user's newest unconsumed code, expired or not, so callers can tell the two apart
+    #[instrument(name = "db.email_login_codes.find_open_for_user", skip_all)]
+    pub async fn find_open_for_user(&self, user_id: Uuid) -> SqlxResult<Option<EmailLoginCode>> {
+        sqlx::query_as!(
+            EmailLoginCode,
+            r#"
+            SELECT id, user_id, code_hash, attempts, expires_at, consumed_at, created_at
+            FROM email_login_codes
+            WHERE user_id = $1 AND consumed_at IS NULL
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Uses up one attempt before the code is checked, so concurrent guesses
+    // can't exceed `max_attempts`. Returns the new count, or None if the code
+    // is closed or already out of attempts.
+    #[instrument(name = "db.email_login_codes.reserve_attempt", skip_all)]
+    pub async fn reserve_attempt(&self, id: Uuid, max_attempts: i32) -> SqlxResult<Option<i32>> {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE email_login_codes
+            SET attempts = attempts + 1
+            WHERE id = $1 AND consumed_at IS NULL AND attempts < $2
+            RETURNING attempts
+            "#,
+            id,
+            max_attempts
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Closes the code; false if it was already closed
+    #[instrument(name = "db.email_login_codes.consume", skip_all)]
+    pub async fn consume(&self, id: Uuid) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            "UPDATE email_login_codes SET consumed_at = $1 WHERE id = $2 AND consumed_at IS NULL",
+            OffsetDateTime::now_utc(),
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Closes the user's open codes, so only the newest one works
+    #[instrument(name = "db.email_login_codes.consume_all_for_user", skip_all)]
+    pub async fn consume_all_for_user(&self, user_id: Uuid) -> SqlxResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_login_codes
+            SET consumed_at = $1
+            WHERE user_id = $2 AND consumed_at IS NULL
+            "#,
+            OffsetDateTime::now_utc(),
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Maintenance: drops codes that expired before `cutoff`
+    pub async fn cleanup_expired(&self, cutoff: OffsetDateTime) -> SqlxResult<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM email_login_codes WHERE expires_at < $1",
+            cutoff
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,8 +1,70 @@
+use crate::app::models::common::Pagination;
 use crate::app::models::login_attempt::{AccountLockout, LoginAttempt, RefreshTokenStorage};
-use sqlx::{PgPool, Result as SqlxResult};
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, Result as SqlxResult, Transaction};
 use time::OffsetDateTime;
+use tracing::instrument;
 use uuid::Uuid;
 
+// Login history as SecureLoginService reads and writes it
+#[async_trait]
+pub trait LoginAttemptStore: Send + Sync {
+    async fn create_attempt(&self, attempt: &LoginAttempt) -> SqlxResult<()>;
+    async fn count_failed_attempts_by_ip(
+        &self,
+        ip_address: &str,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64>;
+    async fn count_failures_since_last_success(
+        &self,
+        user_id: Uuid,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64>;
+    async fn count_ip_failures_since_last_success(
+        &self,
+        ip_address: &str,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64>;
+    async fn is_new_device(
+        &self,
+        user_id: Uuid,
+        ip_address: &str,
+        user_agent: Option<&str>,
+    ) -> SqlxResult<bool>;
+    async fn get_recent_attempts_by_email(
+        &self,
+        email: &str,
+        limit: i32,
+    ) -> SqlxResult<Vec<LoginAttempt>>;
+    async fn recent_for_user(
+        &self,
+        user_id: Uuid,
+        pagination: Pagination,
+    ) -> SqlxResult<(Vec<LoginAttempt>, i64)>;
+    async fn cleanup_old_attempts(&self, older_than: OffsetDateTime) -> SqlxResult<u64>;
+}
+
+// Account lockouts as SecureLoginService reads and writes them
+#[async_trait]
+pub trait AccountLockoutStore: Send + Sync {
+    async fn create_lockout(&self, lockout: &AccountLockout) -> SqlxResult<()>;
+    async fn get_active_lockout(&self, user_id: Uuid) -> SqlxResult<Option<AccountLockout>>;
+    async fn get_escalating_lockout(
+        &self,
+        user_id: Uuid,
+        ended_after: OffsetDateTime,
+    ) -> SqlxResult<Option<AccountLockout>>;
+    async fn reset_escalation(&self, user_id: Uuid) -> SqlxResult<()>;
+    async fn unlock_account(&self, user_id: Uuid) -> SqlxResult<()>;
+    async fn set_unlock_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> SqlxResult<bool>;
+    async fn consume_unlock_token(&self, token_hash: &str) -> SqlxResult<Option<Uuid>>;
+}
+
 #[derive(Clone)]
 pub struct LoginAttemptRepository {
     pool: PgPool,
@@ -14,6 +76,7 @@ impl LoginAttemptRepository {
     }
 
     // Record a login attempt
+    #[instrument(name = "db.login_attempts.create_attempt", skip_all)]
     pub async fn create_attempt(&self, attempt: &LoginAttempt) -> SqlxResult<()> {
         sqlx::query!(
             r#"
@@ -36,6 +99,7 @@ impl LoginAttemptRepository {
     }
 
     // Count failed attempts for an IP address within a time window
+    #[instrument(name = "db.login_attempts.count_failed_attempts_by_ip", skip_all)]
     pub async fn count_failed_attempts_by_ip(
         &self,
         ip_address: &str,
@@ -56,7 +120,23 @@ impl LoginAttemptRepository {
         Ok(count.unwrap_or(0))
     }
 
+    // Failed attempts from anyone since `since`, for the admin dashboard
+    #[instrument(name = "db.login_attempts.failed_count_since", skip_all)]
+    pub async fn failed_count_since(&self, since: OffsetDateTime) -> SqlxResult<i64> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM login_attempts
+            WHERE created_at >= $1 AND success = false
+            "#,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
     // Count failed attempts for a user (by email) within a time window
+    #[instrument(name = "db.login_attempts.count_failed_attempts_by_email", skip_all)]
     pub async fn count_failed_attempts_by_email(
         &self,
         email: &str,
@@ -77,6 +157,91 @@ impl LoginAttemptRepository {
         Ok(count.unwrap_or(0))
     }
 
+    // Failed attempts against a user since `since` or their last successful login,
+    // whichever is later
+    #[instrument(name = "db.login_attempts.count_failures_since_last_success", skip_all)]
+    pub async fn count_failures_since_last_success(
+        &self,
+        user_id: Uuid,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM login_attempts
+            WHERE user_id = $1 AND success = false AND created_at >= $2
+                AND created_at > COALESCE(
+                    (SELECT MAX(created_at) FROM login_attempts WHERE user_id = $1 AND success = true),
+                    '-infinity'
+                )
+            "#,
+            user_id,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    // Failed attempts from an IP since `since` or the last successful login from
+    // it, whichever is later
+    #[instrument(
+        name = "db.login_attempts.count_ip_failures_since_last_success",
+        skip_all
+    )]
+    pub async fn count_ip_failures_since_last_success(
+        &self,
+        ip_address: &str,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM login_attempts
+            WHERE ip_address = $1 AND success = false AND created_at >= $2
+                AND created_at > COALESCE(
+                    (SELECT MAX(created_at) FROM login_attempts WHERE ip_address = $1 AND success = true),
+                    '-infinity'
+                )
+            "#,
+            ip_address,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    // True when the user has signed in before, but never from this IP and user agent
+    #[instrument(name = "db.login_attempts.is_new_device", skip_all)]
+    pub async fn is_new_device(
+        &self,
+        user_id: Uuid,
+        ip_address: &str,
+        user_agent: Option<&str>,
+    ) -> SqlxResult<bool> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) AS "total!",
+                COUNT(*) FILTER (
+                    WHERE ip_address = $2 AND user_agent IS NOT DISTINCT FROM $3
+                ) AS "matching!"
+            FROM login_attempts
+            WHERE user_id = $1 AND success = true
+            "#,
+            user_id,
+            ip_address,
+            user_agent
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.total > 0 && row.matching == 0)
+    }
+
     // Get recent login attempts for analysis
     pub async fn get_recent_attempts_by_email(
         &self,
@@ -101,6 +266,41 @@ impl LoginAttemptRepository {
         Ok(attempts)
     }
 
+    // One page of the attempts linked to a user, newest first, plus how many there are.
+    // Attempts with an unknown email or rejected before the user was looked up have no
+    // user_id and are not included.
+    #[instrument(name = "db.login_attempts.recent_for_user", skip_all)]
+    pub async fn recent_for_user(
+        &self,
+        user_id: Uuid,
+        pagination: Pagination,
+    ) -> SqlxResult<(Vec<LoginAttempt>, i64)> {
+        let attempts = sqlx::query_as!(
+            LoginAttempt,
+            r#"
+            SELECT id, ip_address, email, user_id, success, failure_reason, user_agent, created_at
+            FROM login_attempts
+            WHERE user_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+            user_id,
+            pagination.per_page,
+            pagination.offset()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM login_attempts WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((attempts, total))
+    }
+
     // Clean up old login attempts (maintenance)
     pub async fn cleanup_old_attempts(&self, older_than: OffsetDateTime) -> SqlxResult<u64> {
         let result = sqlx::query!(
@@ -125,18 +325,21 @@ impl AccountLockoutRepository {
     }
 
     // Create an account lockout
+    #[instrument(name = "db.account_lockouts.create_lockout", skip_all)]
     pub async fn create_lockout(&self, lockout: &AccountLockout) -> SqlxResult<()> {
         sqlx::query!(
             r#"
-            INSERT INTO account_lockouts (id, user_id, locked_until, failed_attempts, locked_at, unlocked_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            INSERT INTO account_lockouts (id, user_id, locked_until, failed_attempts, locked_at, unlocked_at, lockout_count, escalation_reset_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             "#,
             lockout.id,
             lockout.user_id,
             lockout.locked_until,
             lockout.failed_attempts,
             lockout.locked_at,
-            lockout.unlocked_at
+            lockout.unlocked_at,
+            lockout.lockout_count,
+            lockout.escalation_reset_at
         )
         .execute(&self.pool)
         .await?;
@@ -145,11 +348,13 @@ impl AccountLockoutRepository {
     }
 
     // Get active lockout for a user
+    #[instrument(name = "db.account_lockouts.get_active_lockout", skip_all)]
     pub async fn get_active_lockout(&self, user_id: Uuid) -> SqlxResult<Option<AccountLockout>> {
         let lockout = sqlx::query_as!(
             AccountLockout,
             r#"
-            SELECT id, user_id, locked_until, failed_attempts, locked_at, unlocked_at
+            SELECT id, user_id, locked_until, failed_attempts, locked_at, unlocked_at,
+                   lockout_count, escalation_reset_at
             FROM account_lockouts
             WHERE user_id = $1 AND unlocked_at IS NULL AND locked_until > NOW()
             ORDER BY locked_at DESC
@@ -163,7 +368,74 @@ impl AccountLockoutRepository {
         Ok(lockout)
     }
 
+    // All lockouts for a user, newest first
+    pub async fn find_by_user(&self, user_id: Uuid) -> SqlxResult<Vec<AccountLockout>> {
+        let lockouts = sqlx::query_as!(
+            AccountLockout,
+            r#"
+            SELECT id, user_id, locked_until, failed_attempts, locked_at, unlocked_at,
+                   lockout_count, escalation_reset_at
+            FROM account_lockouts
+            WHERE user_id = $1
+            ORDER BY locked_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(lockouts)
+    }
+
+    // Most recent lockout that still counts towards escalation: it ended (expired
+    // or was unlocked) after `ended_after` and no successful login has reset the
+    // streak since
+    #[instrument(name = "db.account_lockouts.get_escalating_lockout", skip_all)]
+    pub async fn get_escalating_lockout(
+        &self,
+        user_id: Uuid,
+        ended_after: OffsetDateTime,
+    ) -> SqlxResult<Option<AccountLockout>> {
+        let lockout = sqlx::query_as!(
+            AccountLockout,
+            r#"
+            SELECT id, user_id, locked_until, failed_attempts, locked_at, unlocked_at,
+                   lockout_count, escalation_reset_at
+            FROM account_lockouts
+            WHERE user_id = $1
+              AND escalation_reset_at IS NULL
+              AND LEAST(locked_until, COALESCE(unlocked_at, locked_until)) > $2
+            ORDER BY locked_at DESC
+            LIMIT 1
+            "#,
+            user_id,
+            ended_after
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(lockout)
+    }
+
+    // Start the next lockout from the first duration again
+    #[instrument(name = "db.account_lockouts.reset_escalation", skip_all)]
+    pub async fn reset_escalation(&self, user_id: Uuid) -> SqlxResult<()> {
+        sqlx::query!(
+            r#"
+            UPDATE account_lockouts
+            SET escalation_reset_at = NOW()
+            WHERE user_id = $1 AND escalation_reset_at IS NULL
+            "#,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     // Unlock an account (set unlocked_at)
+    #[instrument(name = "db.account_lockouts.unlock_account", skip_all)]
     pub async fn unlock_account(&self, user_id: Uuid) -> SqlxResult<()> {
         sqlx::query!(
             r#"
@@ -179,6 +451,52 @@ impl AccountLockoutRepository {
         Ok(())
     }
 
+    // Attach an unlock token to the user's active lockout, replacing any earlier
+    // one; false if the account isn't locked
+    #[instrument(name = "db.account_lockouts.set_unlock_token", skip_all)]
+    pub async fn set_unlock_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE account_lockouts
+            SET unlock_token_hash = $2, unlock_token_expires_at = $3
+            WHERE user_id = $1 AND unlocked_at IS NULL AND locked_until > NOW()
+            "#,
+            user_id,
+            token_hash,
+            expires_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Ends the lockout an unexpired token was issued for and returns its user;
+    // the token can't be used twice
+    #[instrument(name = "db.account_lockouts.consume_unlock_token", skip_all)]
+    pub async fn consume_unlock_token(&self, token_hash: &str) -> SqlxResult<Option<Uuid>> {
+        let user_id = sqlx::query_scalar!(
+            r#"
+            UPDATE account_lockouts
+            SET unlocked_at = NOW(), unlock_token_hash = NULL, unlock_token_expires_at = NULL
+            WHERE unlock_token_hash = $1
+              AND unlock_token_expires_at > NOW()
+              AND unlocked_at IS NULL
+            RETURNING user_id
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user_id)
+    }
+
     // Clean up expired lockouts
     pub async fn cleanup_expired_lockouts(&self) -> SqlxResult<u64> {
         let result = sqlx::query!(
@@ -195,6 +513,106 @@ impl AccountLockoutRepository {
     }
 }
 
+#[async_trait]
+impl LoginAttemptStore for LoginAttemptRepository {
+    async fn create_attempt(&self, attempt: &LoginAttempt) -> SqlxResult<()> {
+        LoginAttemptRepository::create_attempt(self, attempt).await
+    }
+
+    async fn count_failed_attempts_by_ip(
+        &self,
+        ip_address: &str,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64> {
+        LoginAttemptRepository::count_failed_attempts_by_ip(self, ip_address, since).await
+    }
+
+    async fn count_failures_since_last_success(
+        &self,
+        user_id: Uuid,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64> {
+        LoginAttemptRepository::count_failures_since_last_success(self, user_id, since).await
+    }
+
+    async fn count_ip_failures_since_last_success(
+        &self,
+        ip_address: &str,
+        since: OffsetDateTime,
+    ) -> SqlxResult<i64> {
+        LoginAttemptRepository::count_ip_failures_since_last_success(self, ip_address, since).await
+    }
+
+    async fn is_new_device(
+        &self,
+        user_id: Uuid,
+        ip_address: &str,
+        user_agent: Option<&str>,
+    ) -> SqlxResult<bool> {
+        LoginAttemptRepository::is_new_device(self, user_id, ip_address, user_agent).await
+    }
+
+    async fn get_recent_attempts_by_email(
+        &self,
+        email: &str,
+        limit: i32,
+    ) -> SqlxResult<Vec<LoginAttempt>> {
+        LoginAttemptRepository::get_recent_attempts_by_email(self, email, limit).await
+    }
+
+    async fn recent_for_user(
+        &self,
+        user_id: Uuid,
+        pagination: Pagination,
+    ) -> SqlxResult<(Vec<LoginAttempt>, i64)> {
+        LoginAttemptRepository::recent_for_user(self, user_id, pagination).await
+    }
+
+    async fn cleanup_old_attempts(&self, older_than: OffsetDateTime) -> SqlxResult<u64> {
+        LoginAttemptRepository::cleanup_old_attempts(self, older_than).await
+    }
+}
+
+#[async_trait]
+impl AccountLockoutStore for AccountLockoutRepository {
+    async fn create_lockout(&self, lockout: &AccountLockout) -> SqlxResult<()> {
+        AccountLockoutRepository::create_lockout(self, lockout).await
+    }
+
+    async fn get_active_lockout(&self, user_id: Uuid) -> SqlxResult<Option<AccountLockout>> {
+        AccountLockoutRepository::get_active_lockout(self, user_id).await
+    }
+
+    async fn get_escalating_lockout(
+        &self,
+        user_id: Uuid,
+        ended_after: OffsetDateTime,
+    ) -> SqlxResult<Option<AccountLockout>> {
+        AccountLockoutRepository::get_escalating_lockout(self, user_id, ended_after).await
+    }
+
+    async fn reset_escalation(&self, user_id: Uuid) -> SqlxResult<()> {
+        AccountLockoutRepository::reset_escalation(self, user_id).await
+    }
+
+    async fn unlock_account(&self, user_id: Uuid) -> SqlxResult<()> {
+        AccountLockoutRepository::unlock_account(self, user_id).await
+    }
+
+    async fn set_unlock_token(
+        &self,
+        user_id: Uuid,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> SqlxResult<bool> {
+        AccountLockoutRepository::set_unlock_token(self, user_id, token_hash, expires_at).await
+    }
+
+    async fn consume_unlock_token(&self, token_hash: &str) -> SqlxResult<Option<Uuid>> {
+        AccountLockoutRepository::consume_unlock_token(self, token_hash).await
+    }
+}
+
 #[derive(Clone)]
 pub struct RefreshTokenRepository {
     pool: PgPool,
@@ -205,12 +623,18 @@ impl RefreshTokenRepository {
         Self { pool }
     }
 
+    // For operations that span this and other repositories, see with_transaction
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
     // Store a refresh token
+    #[instrument(name = "db.refresh_tokens.store_token", skip_all)]
     pub async fn store_token(&self, token: &RefreshTokenStorage) -> SqlxResult<()> {
         sqlx::query!(
             r#"
-            INSERT INTO refresh_tokens (id, jti, user_id, token_hash, expires_at, revoked_at, created_at, last_used_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            INSERT INTO refresh_tokens (id, jti, user_id, token_hash, expires_at, revoked_at, created_at, last_used_at, device_name, user_agent, ip_address)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
             "#,
             token.id,
             token.jti,
@@ -219,7 +643,10 @@ impl RefreshTokenRepository {
             token.expires_at,
             token.revoked_at,
             token.created_at,
-            token.last_used_at
+            token.last_used_at,
+            token.device_name,
+            token.user_agent,
+            token.ip_address
         )
         .execute(&self.pool)
         .await?;
@@ -228,10 +655,11 @@ impl RefreshTokenRepository {
     }
 
     // Find refresh token by JTI
+    #[instrument(name = "db.refresh_tokens.find_by_jti", skip_all)]
     pub async fn find_by_jti(&self, jti: &str) -> SqlxResult<Option<RefreshTokenStorage>> {
         let row = sqlx::query!(
             r#"
-            SELECT id, jti, user_id, token_hash, expires_at, revoked_at, created_at, last_used_at
+            SELECT id, jti, user_id, token_hash, expires_at, revoked_at, created_at, last_used_at, device_name, user_agent, ip_address
             FROM refresh_tokens
             WHERE jti = $1
             "#,
@@ -250,12 +678,59 @@ impl RefreshTokenRepository {
                 revoked_at: row.revoked_at,
                 created_at: row.created_at,
                 last_used_at: row.last_used_at,
+                device_name: row.device_name,
+                user_agent: row.user_agent,
+                ip_address: row.ip_address,
             }))
         } else {
             Ok(None)
         }
     }
 
+    // Unrevoked, unexpired refresh tokens for a user, newest first
+    pub async fn find_active_by_user(&self, user_id: Uuid) -> SqlxResult<Vec<RefreshTokenStorage>> {
+        let tokens = sqlx::query_as!(
+            RefreshTokenStorage,
+            r#"
+            SELECT id, jti, user_id, token_hash, expires_at, revoked_at, created_at, last_used_at, device_name, user_agent, ip_address
+            FROM refresh_tokens
+            WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    // Relabel one of the user's active sessions; None if they have no such session
+    #[instrument(name = "db.refresh_tokens.rename", skip_all)]
+    pub async fn rename(
+        &self,
+        user_id: Uuid,
+        jti: &str,
+        device_name: &str,
+    ) -> SqlxResult<Option<RefreshTokenStorage>> {
+        let token = sqlx::query_as!(
+            RefreshTokenStorage,
+            r#"
+            UPDATE refresh_tokens
+            SET device_name = $1
+            WHERE jti = $2 AND user_id = $3 AND revoked_at IS NULL AND expires_at > NOW()
+            RETURNING id, jti, user_id, token_hash, expires_at, revoked_at, created_at, last_used_at, device_name, user_agent, ip_address
+            "#,
+            device_name,
+            jti,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(token)
+    }
+
     // Update last used time
     pub async fn update_last_used(&self, jti: &str) -> SqlxResult<()> {
         sqlx::query!(
@@ -273,6 +748,7 @@ impl RefreshTokenRepository {
     }
 
     // Revoke a refresh token
+    #[instrument(name = "db.refresh_tokens.revoke_token", skip_all)]
     pub async fn revoke_token(&self, jti: &str) -> SqlxResult<()> {
         sqlx::query!(
             r#"
@@ -288,20 +764,60 @@ impl RefreshTokenRepository {
         Ok(())
     }
 
-    // Revoke all refresh tokens for a user
-    pub async fn revoke_all_user_tokens(&self, user_id: Uuid) -> SqlxResult<()> {
-        sqlx::query!(
+    // Revoke up to `limit` of a user's unrevoked refresh tokens, oldest first.
+    // Already revoked rows are skipped, so a failed batch can simply be retried.
+    #[instrument(name = "db.refresh_tokens.revoke_user_tokens_batch", skip_all)]
+    pub async fn revoke_user_tokens_batch(&self, user_id: Uuid, limit: i64) -> SqlxResult<u64> {
+        let result = sqlx::query!(
             r#"
             UPDATE refresh_tokens
             SET revoked_at = NOW()
-            WHERE user_id = $1 AND revoked_at IS NULL
+            WHERE id IN (
+                SELECT id FROM refresh_tokens
+                WHERE user_id = $1 AND revoked_at IS NULL
+                ORDER BY created_at, id
+                LIMIT $2
+            )
             "#,
-            user_id
+            user_id,
+            limit
         )
         .execute(&self.pool)
         .await?;
 
-        Ok(())
+        Ok(result.rows_affected())
+    }
+
+    // Revoke every unrevoked refresh token of a user as part of a larger
+    // transaction; unlike the batched variant it is all-or-nothing
+    #[instrument(name = "db.refresh_tokens.revoke_user_tokens_tx", skip_all)]
+    pub async fn revoke_user_tokens_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+    ) -> SqlxResult<u64> {
+        let result = sqlx::query!(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+            user_id
+        )
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Sessions across all users that can still be refreshed
+    #[instrument(name = "db.refresh_tokens.active_count", skip_all)]
+    pub async fn active_count(&self) -> SqlxResult<i64> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM refresh_tokens
+            WHERE expires_at > NOW() AND revoked_at IS NULL
+            "#
+        )
+        .fetch_one(&self.pool)
+        .await
     }
 
     // Clean up expired tokens
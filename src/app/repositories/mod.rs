@@ -1,7 +1,18 @@
+pub mod admin_audit_repository;
+pub mod api_key_repository;
+pub mod client_repository;
+pub mod email_login_code_repository;
 pub mod login_attempt_repository;
+pub mod oauth_identity_repository;
+pub mod otp_challenge_repository;
+pub mod password_history_repository;
 pub mod password_reset_repository;
 pub mod project_repository;
+pub mod role_repository;
+pub mod signing_key_repository;
 pub mod task_repository;
 pub mod time_entry_repository;
 pub mod token_blacklist_repository;
+pub mod transaction;
+pub mod trusted_device_repository;
 pub mod user_repository;
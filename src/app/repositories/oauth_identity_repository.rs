@@ -0,0 +1,72 @@
+use crate::app::models::oauth::OAuthIdentity;
+use sqlx::{PgPool, Result as SqlxResult};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct OAuthIdentityRepository {
+    pool: PgPool,
+}
+
+impl OAuthIdentityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_subject(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> SqlxResult<Option<OAuthIdentity>> {
+        sqlx::query_as!(
+            OAuthIdentity,
+            r#"
+            SELECT id, user_id, provider, subject, email, created_at
+            FROM oauth_identities
+            WHERE provider = $1 AND subject = $2
+            "#,
+            provider,
+            subject
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Link a provider identity to a user
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+        email: &str,
+    ) -> SqlxResult<OAuthIdentity> {
+        sqlx::query_as!(
+            OAuthIdentity,
+            r#"
+            INSERT INTO oauth_identities (user_id, provider, subject, email)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, user_id, provider, subject, email, created_at
+            "#,
+            user_id,
+            provider,
+            subject,
+            email
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    pub async fn find_by_user(&self, user_id: Uuid) -> SqlxResult<Vec<OAuthIdentity>> {
+        sqlx::query_as!(
+            OAuthIdentity,
+            r#"
+            SELECT id, user_id, provider, subject, email, created_at
+            FROM oauth_identities
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
@@ -0,0 +1,155 @@
+use crate::app::models::otp::{OtpChallenge, OtpPurpose};
+use sqlx::{PgPool, Result as SqlxResult};
+use time::OffsetDateTime;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct OtpChallengeRepository {
+    pool: PgPool,
+}
+
+impl OtpChallengeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    #[instrument(name = "db.otp_challenges.create", skip_all)]
+    pub async fn create(&self, challenge: &OtpChallenge) -> SqlxResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO otp_challenges (
+                id, user_id, purpose, phone_number, code_hash, attempts,
+                remember_me, device_name, client_id, expires_at, created_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+            challenge.id,
+            challenge.user_id,
+            challenge.purpose,
+            challenge.phone_number,
+            challenge.code_hash,
+            challenge.attempts,
+            challenge.remember_me,
+            challenge.device_name,
+            challenge.client_id,
+            challenge.expires_at,
+            challenge.created_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // An unconsumed challenge, expired or not, so callers can tell the two apart
+    #[instrument(name = "db.otp_challenges.find_open", skip_all)]
+    pub async fn find_open(
+        &self,
+        id: Uuid,
+        purpose: OtpPurpose,
+    ) -> SqlxResult<Option<OtpChallenge>> {
+        sqlx::query_as!(
+            OtpChallenge,
+            r#"
+            SELECT id, user_id, purpose, phone_number, code_hash, attempts,
+                   remember_me, device_name, client_id, expires_at, consumed_at, created_at
+            FROM otp_challenges
+            WHERE id = $1 AND purpose = $2 AND consumed_at IS NULL
+            "#,
+            id,
+            purpose.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    #[instrument(name = "db.otp_challenges.find_open_for_user", skip_all)]
+    pub async fn find_open_for_user(
+        &self,
+        user_id: Uuid,
+        purpose: OtpPurpose,
+    ) -> SqlxResult<Option<OtpChallenge>> {
+        sqlx::query_as!(
+            OtpChallenge,
+            r#"
+            SELECT id, user_id, purpose, phone_number, code_hash, attempts,
+                   remember_me, device_name, client_id, expires_at, consumed_at, created_at
+            FROM otp_challenges
+            WHERE user_id = $1 AND purpose = $2 AND consumed_at IS NULL
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            user_id,
+            purpose.as_str()
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Uses up one attempt before the code is checked, so concurrent guesses
+    // can't exceed `max_attempts`. Returns the new count, or None if the
+    // challenge is closed or already out of attempts.
+    #[instrument(name = "db.otp_challenges.reserve_attempt", skip_all)]
+    pub async fn reserve_attempt(&self, id: Uuid, max_attempts: i32) -> SqlxResult<Option<i32>> {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE otp_challenges
+            SET attempts = attempts + 1
+            WHERE id = $1 AND consumed_at IS NULL AND attempts < $2
+            RETURNING attempts
+            "#,
+            id,
+            max_attempts
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Closes the challenge; false if it was already closed. Used both for a
+    // correct code and to invalidate one, so a code only ever works once.
+    #[instrument(name = "db.otp_challenges.consume", skip_all)]
+    pub async fn consume(&self, id: Uuid) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            "UPDATE otp_challenges SET consumed_at = $1 WHERE id = $2 AND consumed_at IS NULL",
+            OffsetDateTime::now_utc(),
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Closes the user's open challenges, so only the newest code works
+    #[instrument(name = "db.otp_challenges.consume_all_for_user", skip_all)]
+    pub async fn consume_all_for_user(
+        &self,
+        user_id: Uuid,
+        purpose: OtpPurpose,
+    ) -> SqlxResult<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE otp_challenges
+            SET consumed_at = $1
+            WHERE user_id = $2 AND purpose = $3 AND consumed_at IS NULL
+            "#,
+            OffsetDateTime::now_utc(),
+            user_id,
+            purpose.as_str()
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Maintenance: drops challenges that expired before `cutoff`
+    pub async fn cleanup_expired(&self, cutoff: OffsetDateTime) -> SqlxResult<u64> {
+        let result = sqlx::query!("DELETE FROM otp_challenges WHERE expires_at < $1", cutoff)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
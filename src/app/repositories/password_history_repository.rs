@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use sqlx::{PgPool, Result as SqlxResult};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+#[async_trait]
+pub trait PasswordHistoryStore: Send + Sync {
+    // Record a password hash the user has just started using
+    async fn add(&self, user_id: Uuid, password_hash: &str) -> SqlxResult<()>;
+    // The user's last `n` password hashes, newest first
+    async fn recent_hashes(&self, user_id: Uuid, n: i64) -> SqlxResult<Vec<String>>;
+}
+
+#[derive(Clone)]
+pub struct PasswordHistoryRepository {
+    pool: PgPool,
+}
+
+impl PasswordHistoryRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Record a password hash the user has just started using
+    pub async fn add(&self, user_id: Uuid, password_hash: &str) -> SqlxResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO password_history (id, user_id, password_hash)
+            VALUES ($1, $2, $3)
+            "#,
+            Uuid::new_v4(),
+            user_id,
+            password_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // The user's last `n` password hashes, newest first
+    pub async fn recent_hashes(&self, user_id: Uuid, n: i64) -> SqlxResult<Vec<String>> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT password_hash
+            FROM password_history
+            WHERE user_id = $1
+            ORDER BY created_at DESC
+            LIMIT $2
+            "#,
+            user_id,
+            n
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+}
+
+#[async_trait]
+impl PasswordHistoryStore for PasswordHistoryRepository {
+    async fn add(&self, user_id: Uuid, password_hash: &str) -> SqlxResult<()> {
+        PasswordHistoryRepository::add(self, user_id, password_hash).await
+    }
+
+    async fn recent_hashes(&self, user_id: Uuid, n: i64) -> SqlxResult<Vec<String>> {
+        PasswordHistoryRepository::recent_hashes(self, user_id, n).await
+    }
+}
+
+// Hashes in the order they were added
+#[derive(Clone, Default)]
+pub struct InMemoryPasswordHistoryStore {
+    hashes: Arc<Mutex<Vec<(Uuid, String)>>>,
+}
+
+impl InMemoryPasswordHistoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PasswordHistoryStore for InMemoryPasswordHistoryStore {
+    async fn add(&self, user_id: Uuid, password_hash: &str) -> SqlxResult<()> {
+        self.hashes
+            .lock()
+            .unwrap()
+            .push((user_id, password_hash.to_string()));
+        Ok(())
+    }
+
+    async fn recent_hashes(&self, user_id: Uuid, n: i64) -> SqlxResult<Vec<String>> {
+        let hashes = self.hashes.lock().unwrap();
+        Ok(hashes
+            .iter()
+            .rev()
+            .filter(|(owner, _)| *owner == user_id)
+            .take(n.max(0) as usize)
+            .map(|(_, hash)| hash.clone())
+            .collect())
+    }
+}
@@ -1,8 +1,24 @@
 use crate::app::models::password_reset::PasswordResetToken;
+use async_trait::async_trait;
 use sqlx::{PgPool, Result as SqlxResult};
+use std::sync::{Arc, Mutex};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
+// Reset tokens as AuthService uses them; see InMemoryPasswordResetStore for tests
+#[async_trait]
+pub trait PasswordResetStore: Send + Sync {
+    async fn create(&self, token: &PasswordResetToken) -> SqlxResult<PasswordResetToken>;
+    async fn find_valid_tokens_by_user_id(
+        &self,
+        user_id: Uuid,
+    ) -> SqlxResult<Vec<PasswordResetToken>>;
+    // The token with this hash, used or expired ones included
+    async fn find_by_token_hash(&self, token_hash: &str) -> SqlxResult<Option<PasswordResetToken>>;
+    async fn mark_as_used(&self, id: Uuid) -> SqlxResult<bool>;
+    async fn count_recent_requests(&self, user_id: Uuid, since: OffsetDateTime) -> SqlxResult<i64>;
+}
+
 #[derive(Clone)]
 pub struct PasswordResetRepository {
     pool: PgPool,
@@ -95,6 +111,31 @@ impl PasswordResetRepository {
             .collect())
     }
 
+    pub async fn find_by_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> SqlxResult<Option<PasswordResetToken>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT id, user_id, token_hash, expires_at, used, created_at
+            FROM password_reset_tokens
+            WHERE token_hash = $1
+            "#,
+            token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| PasswordResetToken {
+            id: r.id,
+            user_id: r.user_id,
+            token_hash: r.token_hash,
+            expires_at: r.expires_at,
+            used: r.used.unwrap_or(false),
+            created_at: r.created_at.unwrap_or_else(OffsetDateTime::now_utc),
+        }))
+    }
+
     pub async fn mark_as_used(&self, id: Uuid) -> SqlxResult<bool> {
         let result = sqlx::query!(
             "UPDATE password_reset_tokens SET used = true WHERE id = $1",
@@ -133,3 +174,92 @@ impl PasswordResetRepository {
         Ok(result.rows_affected())
     }
 }
+
+#[async_trait]
+impl PasswordResetStore for PasswordResetRepository {
+    async fn create(&self, token: &PasswordResetToken) -> SqlxResult<PasswordResetToken> {
+        PasswordResetRepository::create(self, token).await
+    }
+
+    async fn find_valid_tokens_by_user_id(
+        &self,
+        user_id: Uuid,
+    ) -> SqlxResult<Vec<PasswordResetToken>> {
+        PasswordResetRepository::find_valid_tokens_by_user_id(self, user_id).await
+    }
+
+    async fn find_by_token_hash(&self, token_hash: &str) -> SqlxResult<Option<PasswordResetToken>> {
+        PasswordResetRepository::find_by_token_hash(self, token_hash).await
+    }
+
+    async fn mark_as_used(&self, id: Uuid) -> SqlxResult<bool> {
+        PasswordResetRepository::mark_as_used(self, id).await
+    }
+
+    async fn count_recent_requests(&self, user_id: Uuid, since: OffsetDateTime) -> SqlxResult<i64> {
+        PasswordResetRepository::count_recent_requests(self, user_id, since).await
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct InMemoryPasswordResetStore {
+    tokens: Arc<Mutex<Vec<PasswordResetToken>>>,
+}
+
+impl InMemoryPasswordResetStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PasswordResetStore for InMemoryPasswordResetStore {
+    async fn create(&self, token: &PasswordResetToken) -> SqlxResult<PasswordResetToken> {
+        self.tokens.lock().unwrap().push(token.clone());
+        Ok(token.clone())
+    }
+
+    async fn find_valid_tokens_by_user_id(
+        &self,
+        user_id: Uuid,
+    ) -> SqlxResult<Vec<PasswordResetToken>> {
+        let now = OffsetDateTime::now_utc();
+        let mut tokens: Vec<PasswordResetToken> = self
+            .tokens
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|token| token.user_id == user_id && !token.used && token.expires_at > now)
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|token| std::cmp::Reverse(token.created_at));
+        Ok(tokens)
+    }
+
+    async fn find_by_token_hash(&self, token_hash: &str) -> SqlxResult<Option<PasswordResetToken>> {
+        let tokens = self.tokens.lock().unwrap();
+        Ok(tokens
+            .iter()
+            .find(|token| token.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn mark_as_used(&self, id: Uuid) -> SqlxResult<bool> {
+        let mut tokens = self.tokens.lock().unwrap();
+        match tokens.iter_mut().find(|token| token.id == id) {
+            Some(token) => {
+                token.used = true;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn count_recent_requests(&self, user_id: Uuid, since: OffsetDateTime) -> SqlxResult<i64> {
+        let tokens = self.tokens.lock().unwrap();
+        Ok(tokens
+            .iter()
+            .filter(|token| token.user_id == user_id && token.created_at > since)
+            .count() as i64)
+    }
+}
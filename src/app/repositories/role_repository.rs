@@ -0,0 +1,76 @@
+use crate::app::models::role::Role;
+use sqlx::{PgPool, Result as SqlxResult};
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct RoleRepository {
+    pool: PgPool,
+}
+
+impl RoleRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_name(&self, name: &str) -> SqlxResult<Option<Role>> {
+        sqlx::query_as!(
+            Role,
+            r#"
+            SELECT id, name, description, created_at
+            FROM roles
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // Names of the roles granted to a user, ordered alphabetically
+    #[instrument(name = "db.user_roles.get_user_roles", skip_all)]
+    pub async fn get_user_roles(&self, user_id: Uuid) -> SqlxResult<Vec<String>> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT r.name
+            FROM user_roles ur
+            JOIN roles r ON r.id = ur.role_id
+            WHERE ur.user_id = $1
+            ORDER BY r.name
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // Grant a role to a user; granting a role the user already holds is a no-op
+    pub async fn grant_role(&self, user_id: Uuid, role_id: Uuid) -> SqlxResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO user_roles (user_id, role_id)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id, role_id) DO NOTHING
+            "#,
+            user_id,
+            role_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Revoke a role from a user, returning whether the user held it
+    pub async fn revoke_role(&self, user_id: Uuid, role_id: Uuid) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM user_roles WHERE user_id = $1 AND role_id = $2",
+            user_id,
+            role_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,72 @@
+use crate::app::models::jwt::StoredSigningKey;
+use crate::app::signing_key_cipher::SigningKeyCipher;
+use sqlx::{PgPool, Result as SqlxResult};
+
+// Secrets are encrypted with `cipher` on the way in and decrypted on the way out
+#[derive(Clone)]
+pub struct SigningKeyRepository {
+    pool: PgPool,
+    cipher: SigningKeyCipher,
+}
+
+impl SigningKeyRepository {
+    pub fn new(pool: PgPool, cipher: SigningKeyCipher) -> Self {
+        Self { pool, cipher }
+    }
+
+    // Every stored key, revoked ones included, oldest first. Fails when a
+    // secret was encrypted with another SIGNING_KEY_ENCRYPTION_KEY.
+    pub async fn list(&self) -> SqlxResult<Vec<StoredSigningKey>> {
+        let mut keys = sqlx::query_as!(
+            StoredSigningKey,
+            r#"
+            SELECT kid, secret, active, created_at, revoked_at
+            FROM signing_keys
+            ORDER BY created_at, kid
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        for key in &mut keys {
+            if let Some(stored) = &key.secret {
+                let secret = self
+                    .cipher
+                    .decrypt(&key.kid, stored)
+                    .map_err(|e| sqlx::Error::Decode(Box::new(e)))?;
+                key.secret = Some(secret);
+            }
+        }
+        Ok(keys)
+    }
+
+    // Stores `kid` as the only active key and marks `revoked` as revoked,
+    // adding rows for keys that only came from the environment
+    pub async fn rotate(&self, kid: &str, secret: &str, revoked: &[String]) -> SqlxResult<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("UPDATE signing_keys SET active = false WHERE active")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            "INSERT INTO signing_keys (kid, secret, active) VALUES ($1, $2, true)",
+            kid,
+            self.cipher.encrypt(kid, secret)
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO signing_keys (kid, secret, active, revoked_at)
+            SELECT revoked_kid, NULL, false, NOW() FROM UNNEST($1::text[]) AS revoked_kid
+            ON CONFLICT (kid) DO UPDATE
+            SET secret = NULL, active = false, revoked_at = NOW()
+            "#,
+            revoked
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await
+    }
+}
@@ -1,5 +1,5 @@
 use crate::app::models::jwt::{BlacklistedToken, TokenType};
-use sqlx::{PgPool, Row};
+use sqlx::{PgExecutor, PgPool, Postgres, Row, Transaction};
 use time::OffsetDateTime;
 use uuid::Uuid;
 
@@ -17,41 +17,16 @@ impl TokenBlacklistRepository {
 
     // Blacklist a token
     pub async fn blacklist_token(&self, token: &BlacklistedToken) -> SqlxResult<BlacklistedToken> {
-        let token_type_str = match token.token_type {
-            TokenType::Access => "access",
-            TokenType::Refresh => "refresh",
-        };
-
-        let row = sqlx::query(
-            r#"
-            INSERT INTO blacklisted_tokens (id, jti, user_id, token_type, expires_at, blacklisted_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, jti, user_id, token_type, expires_at, blacklisted_at
-            "#
-        )
-        .bind(token.id)
-        .bind(&token.jti)
-        .bind(token.user_id)
-        .bind(token_type_str)
-        .bind(token.expires_at)
-        .bind(token.blacklisted_at)
-        .fetch_one(&self.pool)
-        .await?;
+        insert_blacklisted_token(&self.pool, token).await
+    }
 
-        let token_type = match row.get::<&str, _>("token_type") {
-            "access" => TokenType::Access,
-            "refresh" => TokenType::Refresh,
-            _ => TokenType::Access, // Default fallback
-        };
-
-        Ok(BlacklistedToken {
-            id: row.get("id"),
-            jti: row.get("jti"),
-            user_id: row.get("user_id"),
-            token_type,
-            expires_at: row.get("expires_at"),
-            blacklisted_at: row.get("blacklisted_at"),
-        })
+    // Blacklist a token as part of a larger transaction
+    pub async fn blacklist_token_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        token: &BlacklistedToken,
+    ) -> SqlxResult<BlacklistedToken> {
+        insert_blacklisted_token(&mut **tx, token).await
     }
 
     // Check if a token is blacklisted
@@ -185,3 +160,45 @@ impl TokenBlacklistRepository {
         Ok(row.get("count"))
     }
 }
+
+async fn insert_blacklisted_token<'e>(
+    executor: impl PgExecutor<'e>,
+    token: &BlacklistedToken,
+) -> SqlxResult<BlacklistedToken> {
+    // API keys are revoked in api_keys and never blacklisted
+    let token_type_str = match token.token_type {
+        TokenType::Access | TokenType::ApiKey => "access",
+        TokenType::Refresh => "refresh",
+    };
+
+    let row = sqlx::query(
+        r#"
+        INSERT INTO blacklisted_tokens (id, jti, user_id, token_type, expires_at, blacklisted_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id, jti, user_id, token_type, expires_at, blacklisted_at
+        "#,
+    )
+    .bind(token.id)
+    .bind(&token.jti)
+    .bind(token.user_id)
+    .bind(token_type_str)
+    .bind(token.expires_at)
+    .bind(token.blacklisted_at)
+    .fetch_one(executor)
+    .await?;
+
+    let token_type = match row.get::<&str, _>("token_type") {
+        "access" => TokenType::Access,
+        "refresh" => TokenType::Refresh,
+        _ => TokenType::Access, // Default fallback
+    };
+
+    Ok(BlacklistedToken {
+        id: row.get("id"),
+        jti: row.get("jti"),
+        user_id: row.get("user_id"),
+        token_type,
+        expires_at: row.get("expires_at"),
+        blacklisted_at: row.get("blacklisted_at"),
+    })
+}
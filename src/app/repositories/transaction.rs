@@ -0,0 +1,29 @@
+use futures::future::BoxFuture;
+use sqlx::{PgPool, Postgres, Transaction};
+
+// Runs `operation` inside one database transaction, for writes that span
+// several repositories. The transaction is committed when `operation` returns
+// Ok and rolled back when it returns Err, so either every step lands or none
+// does. Repositories take part through their `*_tx` methods:
+//
+//     with_transaction(&pool, |tx| Box::pin(async move { ... })).await
+pub async fn with_transaction<'a, T, E, F>(pool: &'a PgPool, operation: F) -> Result<T, E>
+where
+    F: for<'t> FnOnce(&'t mut Transaction<'a, Postgres>) -> BoxFuture<'t, Result<T, E>>,
+    E: From<sqlx::Error>,
+{
+    let mut tx: Transaction<'a, Postgres> = pool.begin().await?;
+
+    match operation(&mut tx).await {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(error) => {
+            // A failed rollback leaves nothing committed either, so the
+            // operation's own error is the one worth reporting
+            let _ = tx.rollback().await;
+            Err(error)
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::app::models::trusted_device::TrustedDevice;
+use sqlx::{PgPool, Postgres, Result as SqlxResult, Transaction};
+use time::OffsetDateTime;
+use tracing::instrument;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct TrustedDeviceRepository {
+    pool: PgPool,
+}
+
+impl TrustedDeviceRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    #[instrument(name = "db.trusted_devices.create", skip_all)]
+    pub async fn create(&self, device: &TrustedDevice) -> SqlxResult<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO trusted_devices (id, user_id, token_hash, user_agent, ip_address, expires_at, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            device.id,
+            device.user_id,
+            device.token_hash,
+            device.user_agent,
+            device.ip_address,
+            device.expires_at,
+            device.created_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // Whether `token_hash` is an unexpired trusted device of the user's
+    #[instrument(name = "db.trusted_devices.is_trusted", skip_all)]
+    pub async fn is_trusted(&self, user_id: Uuid, token_hash: &str) -> SqlxResult<bool> {
+        let trusted = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM trusted_devices
+                WHERE user_id = $1 AND token_hash = $2 AND expires_at > NOW()
+            ) AS "trusted!"
+            "#,
+            user_id,
+            token_hash
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(trusted)
+    }
+
+    // Swaps the device's token for `new_hash` and marks it used. Returns the
+    // unchanged expiry, or None if `old_hash` is unknown, expired or someone else's.
+    #[instrument(name = "db.trusted_devices.rotate", skip_all)]
+    pub async fn rotate(
+        &self,
+        user_id: Uuid,
+        old_hash: &str,
+        new_hash: &str,
+    ) -> SqlxResult<Option<OffsetDateTime>> {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE trusted_devices SET token_hash = $3, last_used_at = NOW()
+            WHERE user_id = $1 AND token_hash = $2 AND expires_at > NOW()
+            RETURNING expires_at
+            "#,
+            user_id,
+            old_hash,
+            new_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    // The user's unexpired devices, newest first
+    #[instrument(name = "db.trusted_devices.list_for_user", skip_all)]
+    pub async fn list_for_user(&self, user_id: Uuid) -> SqlxResult<Vec<TrustedDevice>> {
+        sqlx::query_as!(
+            TrustedDevice,
+            r#"
+            SELECT id, user_id, token_hash, user_agent, ip_address, expires_at, last_used_at, created_at
+            FROM trusted_devices
+            WHERE user_id = $1 AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+
+    // False if the user has no device `id`
+    #[instrument(name = "db.trusted_devices.delete", skip_all)]
+    pub async fn delete(&self, user_id: Uuid, id: Uuid) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM trusted_devices WHERE id = $1 AND user_id = $2",
+            id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Forgets all of the user's devices, for callers that already hold a transaction
+    #[instrument(name = "db.trusted_devices.delete_for_user_tx", skip_all)]
+    pub async fn delete_for_user_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        user_id: Uuid,
+    ) -> SqlxResult<u64> {
+        let result = sqlx::query!("DELETE FROM trusted_devices WHERE user_id = $1", user_id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Maintenance: drops devices whose trust ran out before `cutoff`
+    #[instrument(name = "db.trusted_devices.cleanup_expired", skip_all)]
+    pub async fn cleanup_expired(&self, cutoff: OffsetDateTime) -> SqlxResult<u64> {
+        let result = sqlx::query!("DELETE FROM trusted_devices WHERE expires_at < $1", cutoff)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
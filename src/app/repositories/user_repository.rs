@@ -1,8 +1,63 @@
-use crate::app::models::user::User;
-use sqlx::{PgPool, Result as SqlxResult};
+use crate::app::models::common::Pagination;
+use crate::app::models::user::{
+    AccountSummary, ImportOutcome, User, UserCursor, UserListFilter, UserStatus,
+};
+use crate::app::repositories::transaction::with_transaction;
+use async_trait::async_trait;
+use sqlx::error::{DatabaseError, ErrorKind};
+use sqlx::{Connection, PgConnection, PgPool, Postgres, Result as SqlxResult, Transaction};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use time::OffsetDateTime;
+use tracing::instrument;
 use uuid::Uuid;
 
+// The user lookups and updates the services need. UserRepository is the Postgres
+// store; InMemoryUserStore lets tests run the services without a database.
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    async fn create(&self, user: &User) -> SqlxResult<User>;
+    async fn find_by_id(&self, id: Uuid) -> SqlxResult<Option<User>>;
+    async fn find_by_email(&self, email: &str) -> SqlxResult<Option<User>>;
+    // The user and their AccountSummary in one round trip
+    async fn find_with_summary(&self, id: Uuid) -> SqlxResult<Option<(User, AccountSummary)>>;
+    // `username` must already be lowercase, see normalize_username
+    async fn find_by_username(&self, username: &str) -> SqlxResult<Option<User>>;
+    async fn get_all(&self) -> SqlxResult<Vec<User>>;
+    async fn list_after(&self, cursor: Option<UserCursor>, limit: i64) -> SqlxResult<Vec<User>>;
+    // Accounts that aren't deleted, created after `since`; None counts all of them
+    async fn count_since(&self, since: Option<OffsetDateTime>) -> SqlxResult<i64>;
+    async fn update(
+        &self,
+        id: Uuid,
+        name: Option<&str>,
+        email: Option<&str>,
+        password_hash: Option<&str>,
+    ) -> SqlxResult<Option<User>>;
+    // Swaps an imported hash for an upgraded one without counting as a password
+    // change; false when the stored hash is no longer `current_hash`
+    async fn upgrade_password_hash(
+        &self,
+        id: Uuid,
+        current_hash: &str,
+        new_hash: &str,
+    ) -> SqlxResult<bool>;
+    async fn set_pending_email(
+        &self,
+        id: Uuid,
+        pending_email: &str,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> SqlxResult<bool>;
+    async fn confirm_pending_email(&self, token_hash: &str) -> SqlxResult<Option<User>>;
+    async fn verified_phone_number(&self, id: Uuid) -> SqlxResult<Option<String>>;
+    async fn status(&self, id: Uuid) -> SqlxResult<Option<UserStatus>>;
+    async fn invalidate_tokens(&self, id: Uuid) -> SqlxResult<Option<OffsetDateTime>>;
+    async fn set_status(&self, id: Uuid, status: UserStatus) -> SqlxResult<bool>;
+    async fn soft_delete(&self, id: Uuid) -> SqlxResult<bool>;
+    async fn delete_account(&self, id: Uuid) -> SqlxResult<bool>;
+}
+
 #[derive(Clone)]
 pub struct UserRepository {
     pool: PgPool,
@@ -17,14 +72,16 @@ impl UserRepository {
         let user = sqlx::query_as!(
             User,
             r#"
-            INSERT INTO users (id, first_name, email, password_hash, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            RETURNING id, first_name as name, email, password_hash, created_at, updated_at
+            INSERT INTO users (id, first_name, email, username, password_hash, password_changed_at, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
             "#,
             user.id,
             user.name,
             user.email,
+            user.username,
             user.password_hash,
+            user.password_changed_at,
             user.created_at,
             user.updated_at
         )
@@ -34,13 +91,113 @@ impl UserRepository {
         Ok(user)
     }
 
+    // Inserts `users` in one transaction, each row in its own savepoint so a
+    // failing row (such as a taken username) is reported without undoing the
+    // others. A row whose email is taken is skipped, or with `update_existing`
+    // replaces that account's name, username and password; the password change
+    // invalidates its tokens like any other.
+    #[instrument(name = "db.users.import", skip_all)]
+    pub async fn import(
+        &self,
+        users: &[User],
+        update_existing: bool,
+    ) -> SqlxResult<Vec<ImportOutcome>> {
+        let mut tx = self.pool.begin().await?;
+        let mut outcomes = Vec::with_capacity(users.len());
+
+        for user in users {
+            let mut savepoint = tx.begin().await?;
+            match Self::import_one(&mut savepoint, user, update_existing).await {
+                Ok(outcome) => {
+                    savepoint.commit().await?;
+                    outcomes.push(outcome);
+                }
+                Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                    savepoint.rollback().await?;
+                    let error = if e.constraint() == Some("users_username_key") {
+                        "Username is already taken"
+                    } else {
+                        "Email is already registered"
+                    };
+                    outcomes.push(ImportOutcome::Failed(error.to_string()));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        tx.commit().await?;
+        Ok(outcomes)
+    }
+
+    async fn import_one(
+        conn: &mut PgConnection,
+        user: &User,
+        update_existing: bool,
+    ) -> SqlxResult<ImportOutcome> {
+        let created = sqlx::query_scalar!(
+            r#"
+            INSERT INTO users (id, first_name, email, username, password_hash, password_changed_at, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (email) DO NOTHING
+            RETURNING id
+            "#,
+            user.id,
+            user.name,
+            user.email,
+            user.username,
+            user.password_hash,
+            user.password_changed_at,
+            user.created_at,
+            user.updated_at
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+        if let Some(id) = created {
+            return Ok(ImportOutcome::Created(id));
+        }
+
+        if !update_existing {
+            let id = sqlx::query_scalar!("SELECT id FROM users WHERE email = $1", user.email)
+                .fetch_one(&mut *conn)
+                .await?;
+            return Ok(ImportOutcome::Skipped(id));
+        }
+
+        let updated = sqlx::query_scalar!(
+            r#"
+            UPDATE users
+            SET first_name = COALESCE($2, first_name),
+                username = COALESCE($3, username),
+                password_hash = $4,
+                password_changed_at = $5,
+                token_invalid_before = $5,
+                updated_at = $5
+            WHERE email = $1 AND status <> 'deleted'
+            RETURNING id
+            "#,
+            user.email,
+            user.name,
+            user.username,
+            user.password_hash,
+            OffsetDateTime::now_utc()
+        )
+        .fetch_optional(&mut *conn)
+        .await?;
+
+        Ok(match updated {
+            Some(id) => ImportOutcome::Updated(id),
+            None => ImportOutcome::Failed("Email belongs to a deleted account".to_string()),
+        })
+    }
+
+    #[instrument(name = "db.users.find_by_id", skip_all)]
     pub async fn find_by_id(&self, id: Uuid) -> SqlxResult<Option<User>> {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, first_name as name, email, password_hash, created_at, updated_at
+            SELECT id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
             FROM users
-            WHERE id = $1
+            WHERE id = $1 AND status <> 'deleted'
             "#,
             id
         )
@@ -50,13 +207,65 @@ impl UserRepository {
         Ok(user)
     }
 
+    // Roles and the session count come from subqueries so /me costs one query
+    #[instrument(name = "db.users.find_with_summary", skip_all)]
+    pub async fn find_with_summary(&self, id: Uuid) -> SqlxResult<Option<(User, AccountSummary)>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                u.id, u.first_name, u.email, u.username, u.password_hash,
+                u.password_changed_at, u.created_at, u.updated_at,
+                COALESCE(u.is_verified, FALSE) AS "email_verified!",
+                (u.phone_verified_at IS NOT NULL) AS "two_factor_enabled!",
+                ARRAY(
+                    SELECT r.name
+                    FROM user_roles ur
+                    JOIN roles r ON r.id = ur.role_id
+                    WHERE ur.user_id = u.id
+                    ORDER BY r.name
+                ) AS "roles!",
+                (
+                    SELECT COUNT(*)
+                    FROM refresh_tokens t
+                    WHERE t.user_id = u.id AND t.revoked_at IS NULL AND t.expires_at > NOW()
+                ) AS "active_sessions!"
+            FROM users u
+            WHERE u.id = $1 AND u.status <> 'deleted'
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let user = User {
+                id: row.id,
+                name: row.first_name,
+                email: row.email,
+                username: row.username,
+                password_hash: row.password_hash,
+                password_changed_at: row.password_changed_at,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            };
+            let summary = AccountSummary {
+                roles: row.roles,
+                email_verified: row.email_verified,
+                two_factor_enabled: row.two_factor_enabled,
+                active_sessions: row.active_sessions,
+            };
+            (user, summary)
+        }))
+    }
+
+    #[instrument(name = "db.users.find_by_email", skip_all)]
     pub async fn find_by_email(&self, email: &str) -> SqlxResult<Option<User>> {
         let user = sqlx::query_as!(
             User,
             r#"
-            SELECT id, first_name as name, email, password_hash, created_at, updated_at
+            SELECT id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
             FROM users
-            WHERE email = $1
+            WHERE email = $1 AND status <> 'deleted'
             "#,
             email
         )
@@ -66,13 +275,31 @@ impl UserRepository {
         Ok(user)
     }
 
+    #[instrument(name = "db.users.find_by_username", skip_all)]
+    pub async fn find_by_username(&self, username: &str) -> SqlxResult<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
+            FROM users
+            WHERE username = $1 AND status <> 'deleted'
+            "#,
+            username
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
     pub async fn get_all(&self) -> SqlxResult<Vec<User>> {
         let users = sqlx::query_as!(
             User,
             r#"
-            SELECT id, first_name as name, email, password_hash, created_at, updated_at
+            SELECT id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
             FROM users
-            ORDER BY created_at DESC
+            WHERE status <> 'deleted'
+            ORDER BY created_at DESC, id DESC
             "#
         )
         .fetch_all(&self.pool)
@@ -81,6 +308,101 @@ impl UserRepository {
         Ok(users)
     }
 
+    // Keyset page of users, newest first, strictly after `cursor`. Ordering on
+    // (created_at, id) stays stable while users are inserted between pages.
+    pub async fn list_after(
+        &self,
+        cursor: Option<UserCursor>,
+        limit: i64,
+    ) -> SqlxResult<Vec<User>> {
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
+            FROM users
+            WHERE status <> 'deleted'
+                AND ($1::timestamptz IS NULL OR (created_at, id) < ($1, $2))
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
+            "#,
+            cursor.map(|c| c.created_at),
+            cursor.map(|c| c.id),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    // Uses the created_at index when `since` is given
+    pub async fn count_since(&self, since: Option<OffsetDateTime>) -> SqlxResult<i64> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM users
+            WHERE status <> 'deleted' AND ($1::timestamptz IS NULL OR created_at > $1)
+            "#,
+            since
+        )
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    // One page of users matching `filter`, plus how many match in total. The sort
+    // is picked with CASE so the query stays static; id breaks ties between pages.
+    pub async fn list(
+        &self,
+        filter: &UserListFilter,
+        pagination: Pagination,
+    ) -> SqlxResult<(Vec<User>, i64)> {
+        let pattern = filter
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|search| !search.is_empty())
+            .map(|search| format!("%{}%", escape_like(search)));
+
+        let users = sqlx::query_as!(
+            User,
+            r#"
+            SELECT id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
+            FROM users
+            WHERE status <> 'deleted'
+                AND ($1::text IS NULL OR email ILIKE $1 OR first_name ILIKE $1)
+            ORDER BY
+                CASE WHEN $2 = 'created_at' THEN created_at END ASC,
+                CASE WHEN $2 = '-created_at' THEN created_at END DESC,
+                CASE WHEN $2 = 'email' THEN email END ASC,
+                CASE WHEN $2 = '-email' THEN email END DESC,
+                CASE WHEN $2 = 'name' THEN first_name END ASC NULLS LAST,
+                CASE WHEN $2 = '-name' THEN first_name END DESC NULLS LAST,
+                id ASC
+            LIMIT $3 OFFSET $4
+            "#,
+            pattern.as_deref(),
+            filter.sort.as_str(),
+            pagination.per_page,
+            pagination.offset()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM users
+            WHERE status <> 'deleted'
+                AND ($1::text IS NULL OR email ILIKE $1 OR first_name ILIKE $1)
+            "#,
+            pattern.as_deref()
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((users, total))
+    }
+
     pub async fn update(
         &self,
         id: Uuid,
@@ -96,9 +418,10 @@ impl UserRepository {
                 first_name = COALESCE($2, first_name),
                 email = COALESCE($3, email),
                 password_hash = COALESCE($4, password_hash),
+                password_changed_at = CASE WHEN $4 IS NULL THEN password_changed_at ELSE $5 END,
                 updated_at = $5
-            WHERE id = $1
-            RETURNING id, first_name as name, email, password_hash, created_at, updated_at
+            WHERE id = $1 AND status <> 'deleted'
+            RETURNING id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
             "#,
             id,
             name,
@@ -112,6 +435,301 @@ impl UserRepository {
         Ok(user)
     }
 
+    // Only matches while the stored hash is still `current_hash`, so a password
+    // changed in the meantime is never overwritten. password_changed_at stays put.
+    #[instrument(name = "db.users.upgrade_password_hash", skip_all)]
+    pub async fn upgrade_password_hash(
+        &self,
+        id: Uuid,
+        current_hash: &str,
+        new_hash: &str,
+    ) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET password_hash = $3
+            WHERE id = $1 AND password_hash = $2 AND status <> 'deleted'
+            "#,
+            id,
+            current_hash,
+            new_hash
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Replaces any earlier pending change; the current email is left alone
+    #[instrument(name = "db.users.set_pending_email", skip_all)]
+    pub async fn set_pending_email(
+        &self,
+        id: Uuid,
+        pending_email: &str,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET pending_email = $1, email_change_token_hash = $2, email_change_expires_at = $3
+            WHERE id = $4 AND status <> 'deleted'
+            "#,
+            pending_email,
+            token_hash,
+            expires_at,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Switches to the pending email if `token_hash` matches an unexpired change.
+    // Fails with a unique violation when the address was taken in the meantime.
+    #[instrument(name = "db.users.confirm_pending_email", skip_all)]
+    pub async fn confirm_pending_email(&self, token_hash: &str) -> SqlxResult<Option<User>> {
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            UPDATE users
+            SET
+                email = pending_email,
+                pending_email = NULL,
+                email_change_token_hash = NULL,
+                email_change_expires_at = NULL,
+                updated_at = $2
+            WHERE email_change_token_hash = $1
+                AND email_change_expires_at > $2
+                AND pending_email IS NOT NULL
+                AND status <> 'deleted'
+            RETURNING id, first_name as name, email, username, password_hash, password_changed_at, created_at, updated_at
+            "#,
+            token_hash,
+            OffsetDateTime::now_utc()
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    // Replaces the phone number; a new number is unverified until a code sent to it is entered
+    #[instrument(name = "db.users.set_phone_number", skip_all)]
+    pub async fn set_phone_number(&self, id: Uuid, phone_number: &str) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET phone_number = $1, phone_verified_at = NULL, updated_at = $2
+            WHERE id = $3 AND status <> 'deleted'
+            "#,
+            phone_number,
+            OffsetDateTime::now_utc(),
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Marks the account's email as verified; returns whether a live account was updated
+    #[instrument(name = "db.users.mark_verified", skip_all)]
+    pub async fn mark_verified(&self, id: Uuid) -> SqlxResult<bool> {
+        let now = OffsetDateTime::now_utc();
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET is_verified = TRUE, updated_at = $1
+            WHERE id = $2 AND status <> 'deleted'
+            "#,
+            now,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Only verifies `phone_number` if it is still the user's number
+    #[instrument(name = "db.users.mark_phone_verified", skip_all)]
+    pub async fn mark_phone_verified(&self, id: Uuid, phone_number: &str) -> SqlxResult<bool> {
+        let now = OffsetDateTime::now_utc();
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET phone_verified_at = $1, updated_at = $1
+            WHERE id = $2 AND phone_number = $3 AND status <> 'deleted'
+            "#,
+            now,
+            id,
+            phone_number
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(name = "db.users.clear_phone_number", skip_all)]
+    pub async fn clear_phone_number(&self, id: Uuid) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET phone_number = NULL, phone_verified_at = NULL, updated_at = $1
+            WHERE id = $2 AND phone_number IS NOT NULL AND status <> 'deleted'
+            "#,
+            OffsetDateTime::now_utc(),
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // The phone number and whether it has been verified
+    #[instrument(name = "db.users.phone_number", skip_all)]
+    pub async fn phone_number(&self, id: Uuid) -> SqlxResult<Option<(String, bool)>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT phone_number, phone_verified_at
+            FROM users
+            WHERE id = $1 AND status <> 'deleted'
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(|row| {
+            row.phone_number
+                .map(|phone_number| (phone_number, row.phone_verified_at.is_some()))
+        }))
+    }
+
+    // The number SMS codes go to when signing in; None unless verified
+    #[instrument(name = "db.users.verified_phone_number", skip_all)]
+    pub async fn verified_phone_number(&self, id: Uuid) -> SqlxResult<Option<String>> {
+        Ok(self
+            .phone_number(id)
+            .await?
+            .and_then(|(phone_number, verified)| verified.then_some(phone_number)))
+    }
+
+    // The account's status, including deleted accounts; None when there is no such row
+    #[instrument(name = "db.users.status", skip_all)]
+    pub async fn status(&self, id: Uuid) -> SqlxResult<Option<UserStatus>> {
+        let status = sqlx::query_scalar!("SELECT status FROM users WHERE id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(status.and_then(|status| UserStatus::parse(&status)))
+    }
+
+    // Whether the account may be used; None when the user no longer exists
+    #[instrument(name = "db.users.is_active", skip_all)]
+    pub async fn is_active(&self, id: Uuid) -> SqlxResult<Option<bool>> {
+        Ok(match self.status(id).await? {
+            Some(UserStatus::Deleted) | None => None,
+            Some(status) => Some(status == UserStatus::Active),
+        })
+    }
+
+    // Tokens issued before this are no longer accepted; None if never set or no such user
+    #[instrument(name = "db.users.token_invalid_before", skip_all)]
+    pub async fn token_invalid_before(&self, id: Uuid) -> SqlxResult<Option<OffsetDateTime>> {
+        let epoch = sqlx::query_scalar!("SELECT token_invalid_before FROM users WHERE id = $1", id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(epoch.flatten())
+    }
+
+    // Invalidates every token issued to the user so far, trusted devices included;
+    // returns the new epoch, or None if there is no live account
+    #[instrument(name = "db.users.invalidate_tokens", skip_all)]
+    pub async fn invalidate_tokens(&self, id: Uuid) -> SqlxResult<Option<OffsetDateTime>> {
+        let now = OffsetDateTime::now_utc();
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET token_invalid_before = $1
+            WHERE id = $2 AND status <> 'deleted'
+            "#,
+            now,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        // A device trusted before this must answer the SMS code again
+        sqlx::query!("DELETE FROM trusted_devices WHERE user_id = $1", id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok((result.rows_affected() > 0).then_some(now))
+    }
+
+    // Deleted accounts stay deleted; returns whether a live account was updated
+    pub async fn set_status(&self, id: Uuid, status: UserStatus) -> SqlxResult<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET status = $1, updated_at = $2
+            WHERE id = $3 AND status <> 'deleted'
+            "#,
+            status.as_str(),
+            OffsetDateTime::now_utc(),
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_active(&self, id: Uuid, active: bool) -> SqlxResult<bool> {
+        let status = if active {
+            UserStatus::Active
+        } else {
+            UserStatus::Disabled
+        };
+        self.set_status(id, status).await
+    }
+
+    // Marks the account deleted and drops its sessions; the row itself is kept
+    // so login attempts and audit entries still point at it
+    #[instrument(name = "db.users.soft_delete", skip_all)]
+    pub async fn soft_delete(&self, id: Uuid) -> SqlxResult<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET status = 'deleted', updated_at = $1
+            WHERE id = $2 AND status <> 'deleted'
+            "#,
+            OffsetDateTime::now_utc(),
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            "UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     pub async fn delete(&self, id: Uuid) -> SqlxResult<bool> {
         let result = sqlx::query!("DELETE FROM users WHERE id = $1", id)
             .execute(&self.pool)
@@ -119,4 +737,454 @@ impl UserRepository {
 
         Ok(result.rows_affected() > 0)
     }
+
+    // Remove the user and everything tied to them in one transaction
+    #[instrument(name = "db.users.delete_account", skip_all)]
+    pub async fn delete_account(&self, id: Uuid) -> SqlxResult<bool> {
+        with_transaction(&self.pool, |tx| Box::pin(self.delete_account_tx(tx, id))).await
+    }
+
+    // The deletes behind delete_account, for callers that already hold a
+    // transaction. Login attempts only SET NULL on delete, so they are cleared
+    // by id and email.
+    pub async fn delete_account_tx(
+        &self,
+        tx: &mut Transaction<'_, Postgres>,
+        id: Uuid,
+    ) -> SqlxResult<bool> {
+        sqlx::query!("DELETE FROM refresh_tokens WHERE user_id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query!("DELETE FROM blacklisted_tokens WHERE user_id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query!("DELETE FROM password_reset_tokens WHERE user_id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+        sqlx::query!(
+            "DELETE FROM login_attempts WHERE user_id = $1 OR email = (SELECT email FROM users WHERE id = $1)",
+            id
+        )
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query!("DELETE FROM account_lockouts WHERE user_id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+
+        let result = sqlx::query!("DELETE FROM users WHERE id = $1", id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+// Search terms are matched literally, so % and _ must not act as wildcards
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[async_trait]
+impl UserStore for UserRepository {
+    async fn create(&self, user: &User) -> SqlxResult<User> {
+        UserRepository::create(self, user).await
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> SqlxResult<Option<User>> {
+        UserRepository::find_by_id(self, id).await
+    }
+
+    async fn find_by_email(&self, email: &str) -> SqlxResult<Option<User>> {
+        UserRepository::find_by_email(self, email).await
+    }
+
+    async fn find_with_summary(&self, id: Uuid) -> SqlxResult<Option<(User, AccountSummary)>> {
+        UserRepository::find_with_summary(self, id).await
+    }
+
+    async fn find_by_username(&self, username: &str) -> SqlxResult<Option<User>> {
+        UserRepository::find_by_username(self, username).await
+    }
+
+    async fn get_all(&self) -> SqlxResult<Vec<User>> {
+        UserRepository::get_all(self).await
+    }
+
+    async fn list_after(&self, cursor: Option<UserCursor>, limit: i64) -> SqlxResult<Vec<User>> {
+        UserRepository::list_after(self, cursor, limit).await
+    }
+
+    async fn count_since(&self, since: Option<OffsetDateTime>) -> SqlxResult<i64> {
+        UserRepository::count_since(self, since).await
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        name: Option<&str>,
+        email: Option<&str>,
+        password_hash: Option<&str>,
+    ) -> SqlxResult<Option<User>> {
+        UserRepository::update(self, id, name, email, password_hash).await
+    }
+
+    async fn upgrade_password_hash(
+        &self,
+        id: Uuid,
+        current_hash: &str,
+        new_hash: &str,
+    ) -> SqlxResult<bool> {
+        UserRepository::upgrade_password_hash(self, id, current_hash, new_hash).await
+    }
+
+    async fn set_pending_email(
+        &self,
+        id: Uuid,
+        pending_email: &str,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> SqlxResult<bool> {
+        UserRepository::set_pending_email(self, id, pending_email, token_hash, expires_at).await
+    }
+
+    async fn confirm_pending_email(&self, token_hash: &str) -> SqlxResult<Option<User>> {
+        UserRepository::confirm_pending_email(self, token_hash).await
+    }
+
+    async fn verified_phone_number(&self, id: Uuid) -> SqlxResult<Option<String>> {
+        UserRepository::verified_phone_number(self, id).await
+    }
+
+    async fn status(&self, id: Uuid) -> SqlxResult<Option<UserStatus>> {
+        UserRepository::status(self, id).await
+    }
+
+    async fn invalidate_tokens(&self, id: Uuid) -> SqlxResult<Option<OffsetDateTime>> {
+        UserRepository::invalidate_tokens(self, id).await
+    }
+
+    async fn set_status(&self, id: Uuid, status: UserStatus) -> SqlxResult<bool> {
+        UserRepository::set_status(self, id, status).await
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> SqlxResult<bool> {
+        UserRepository::soft_delete(self, id).await
+    }
+
+    async fn delete_account(&self, id: Uuid) -> SqlxResult<bool> {
+        UserRepository::delete_account(self, id).await
+    }
+}
+
+struct StoredUser {
+    user: User,
+    status: UserStatus,
+    // (pending email, token hash, expiry)
+    pending_email: Option<(String, String, OffsetDateTime)>,
+}
+
+impl StoredUser {
+    fn is_live(&self) -> bool {
+        self.status != UserStatus::Deleted
+    }
+}
+
+// Per-process user store for tests. Emails and usernames are unique across all
+// rows, deleted ones included, like the users table. Phone numbers and token epochs aren't kept.
+#[derive(Clone, Default)]
+pub struct InMemoryUserStore {
+    users: Arc<Mutex<HashMap<Uuid, StoredUser>>>,
+}
+
+impl InMemoryUserStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn live_user(&self, id: Uuid) -> Option<User> {
+        let users = self.users.lock().unwrap();
+        users
+            .get(&id)
+            .filter(|stored| stored.is_live())
+            .map(|stored| stored.user.clone())
+    }
+
+    // Live users, newest first, the order the users queries use
+    fn sorted_live_users(&self) -> Vec<User> {
+        let users = self.users.lock().unwrap();
+        let mut live: Vec<User> = users
+            .values()
+            .filter(|stored| stored.is_live())
+            .map(|stored| stored.user.clone())
+            .collect();
+        live.sort_by_key(|user| std::cmp::Reverse((user.created_at, user.id)));
+        live
+    }
+}
+
+#[async_trait]
+impl UserStore for InMemoryUserStore {
+    async fn create(&self, user: &User) -> SqlxResult<User> {
+        let mut users = self.users.lock().unwrap();
+        if users.contains_key(&user.id)
+            || users.values().any(|stored| stored.user.email == user.email)
+        {
+            return Err(unique_violation("users_email_key"));
+        }
+        if user.username.is_some()
+            && users
+                .values()
+                .any(|stored| stored.user.username == user.username)
+        {
+            return Err(unique_violation("users_username_key"));
+        }
+        users.insert(
+            user.id,
+            StoredUser {
+                user: user.clone(),
+                status: UserStatus::Active,
+                pending_email: None,
+            },
+        );
+        Ok(user.clone())
+    }
+
+    async fn find_by_id(&self, id: Uuid) -> SqlxResult<Option<User>> {
+        Ok(self.live_user(id))
+    }
+
+    async fn find_by_email(&self, email: &str) -> SqlxResult<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .values()
+            .find(|stored| stored.is_live() && stored.user.email == email)
+            .map(|stored| stored.user.clone()))
+    }
+
+    // Roles and sessions live in other tables, so they're always empty here
+    async fn find_with_summary(&self, id: Uuid) -> SqlxResult<Option<(User, AccountSummary)>> {
+        Ok(self
+            .live_user(id)
+            .map(|user| (user, AccountSummary::default())))
+    }
+
+    async fn find_by_username(&self, username: &str) -> SqlxResult<Option<User>> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .values()
+            .find(|stored| stored.is_live() && stored.user.username.as_deref() == Some(username))
+            .map(|stored| stored.user.clone()))
+    }
+
+    async fn get_all(&self) -> SqlxResult<Vec<User>> {
+        Ok(self.sorted_live_users())
+    }
+
+    async fn list_after(&self, cursor: Option<UserCursor>, limit: i64) -> SqlxResult<Vec<User>> {
+        Ok(self
+            .sorted_live_users()
+            .into_iter()
+            .filter(|user| match cursor {
+                Some(cursor) => user.created_at.is_some_and(|created_at| {
+                    (created_at, user.id) < (cursor.created_at, cursor.id)
+                }),
+                None => true,
+            })
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn count_since(&self, since: Option<OffsetDateTime>) -> SqlxResult<i64> {
+        let users = self.users.lock().unwrap();
+        Ok(users
+            .values()
+            .filter(|stored| stored.is_live())
+            .filter(|stored| match (since, stored.user.created_at) {
+                (Some(since), Some(created_at)) => created_at > since,
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .count() as i64)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        name: Option<&str>,
+        email: Option<&str>,
+        password_hash: Option<&str>,
+    ) -> SqlxResult<Option<User>> {
+        let mut users = self.users.lock().unwrap();
+        if let Some(email) = email
+            && users
+                .iter()
+                .any(|(other, stored)| *other != id && stored.user.email == email)
+        {
+            return Err(unique_violation("users_email_key"));
+        }
+        let Some(stored) = users.get_mut(&id).filter(|stored| stored.is_live()) else {
+            return Ok(None);
+        };
+        if let Some(name) = name {
+            stored.user.name = Some(name.to_string());
+        }
+        if let Some(email) = email {
+            stored.user.email = email.to_string();
+        }
+        let now = OffsetDateTime::now_utc();
+        if let Some(password_hash) = password_hash {
+            stored.user.password_hash = password_hash.to_string();
+            stored.user.password_changed_at = Some(now);
+        }
+        stored.user.updated_at = Some(now);
+        Ok(Some(stored.user.clone()))
+    }
+
+    async fn upgrade_password_hash(
+        &self,
+        id: Uuid,
+        current_hash: &str,
+        new_hash: &str,
+    ) -> SqlxResult<bool> {
+        let mut users = self.users.lock().unwrap();
+        match users.get_mut(&id) {
+            Some(stored) if stored.is_live() && stored.user.password_hash == current_hash => {
+                stored.user.password_hash = new_hash.to_string();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn set_pending_email(
+        &self,
+        id: Uuid,
+        pending_email: &str,
+        token_hash: &str,
+        expires_at: OffsetDateTime,
+    ) -> SqlxResult<bool> {
+        let mut users = self.users.lock().unwrap();
+        let Some(stored) = users.get_mut(&id).filter(|stored| stored.is_live()) else {
+            return Ok(false);
+        };
+        stored.pending_email = Some((
+            pending_email.to_string(),
+            token_hash.to_string(),
+            expires_at,
+        ));
+        Ok(true)
+    }
+
+    async fn confirm_pending_email(&self, token_hash: &str) -> SqlxResult<Option<User>> {
+        let now = OffsetDateTime::now_utc();
+        let mut users = self.users.lock().unwrap();
+        let Some((id, pending_email)) = users.iter().find_map(|(id, stored)| {
+            let (email, hash, expires_at) = stored.pending_email.as_ref()?;
+            (stored.is_live() && hash == token_hash && *expires_at > now)
+                .then(|| (*id, email.clone()))
+        }) else {
+            return Ok(None);
+        };
+        if users
+            .values()
+            .any(|stored| stored.user.email == pending_email)
+        {
+            return Err(unique_violation("users_email_key"));
+        }
+
+        let stored = users.get_mut(&id).expect("user found above");
+        stored.user.email = pending_email;
+        stored.user.updated_at = Some(now);
+        stored.pending_email = None;
+        Ok(Some(stored.user.clone()))
+    }
+
+    // Phone numbers aren't kept, so SMS sign-in codes never apply
+    async fn verified_phone_number(&self, _id: Uuid) -> SqlxResult<Option<String>> {
+        Ok(None)
+    }
+
+    async fn status(&self, id: Uuid) -> SqlxResult<Option<UserStatus>> {
+        Ok(self
+            .users
+            .lock()
+            .unwrap()
+            .get(&id)
+            .map(|stored| stored.status))
+    }
+
+    // Tokens aren't checked against the store, so only report whether the user is live
+    async fn invalidate_tokens(&self, id: Uuid) -> SqlxResult<Option<OffsetDateTime>> {
+        Ok(self.live_user(id).map(|_| OffsetDateTime::now_utc()))
+    }
+
+    async fn set_status(&self, id: Uuid, status: UserStatus) -> SqlxResult<bool> {
+        let mut users = self.users.lock().unwrap();
+        let Some(stored) = users.get_mut(&id).filter(|stored| stored.is_live()) else {
+            return Ok(false);
+        };
+        stored.status = status;
+        stored.user.updated_at = Some(OffsetDateTime::now_utc());
+        Ok(true)
+    }
+
+    async fn soft_delete(&self, id: Uuid) -> SqlxResult<bool> {
+        self.set_status(id, UserStatus::Deleted).await
+    }
+
+    async fn delete_account(&self, id: Uuid) -> SqlxResult<bool> {
+        Ok(self.users.lock().unwrap().remove(&id).is_some())
+    }
+}
+
+// What Postgres reports when an insert or update hits a unique index, so callers
+// checking for duplicates behave the same against the in-memory store
+fn unique_violation(constraint: &'static str) -> sqlx::Error {
+    sqlx::Error::Database(Box::new(UniqueViolation { constraint }))
+}
+
+#[derive(Debug)]
+struct UniqueViolation {
+    constraint: &'static str,
+}
+
+impl std::fmt::Display for UniqueViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "duplicate key value violates unique constraint \"{}\"",
+            self.constraint
+        )
+    }
+}
+
+impl std::error::Error for UniqueViolation {}
+
+impl DatabaseError for UniqueViolation {
+    fn message(&self) -> &str {
+        "duplicate key value violates unique constraint"
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn constraint(&self) -> Option<&str> {
+        Some(self.constraint)
+    }
+
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::UniqueViolation
+    }
 }
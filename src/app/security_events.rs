@@ -0,0 +1,454 @@
+// Where log_security_event sends its events: the tracing log always, plus any sinks
+// installed at startup, such as a webhook for alerting
+use crate::app::geolocation::GeoResolver;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_WEBHOOK_ATTEMPTS: u32 = 3;
+const DEFAULT_WEBHOOK_RETRY_DELAY: Duration = Duration::from_millis(500);
+const DEFAULT_WEBHOOK_QUEUE_CAPACITY: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Severity::Low),
+            "medium" => Some(Severity::Medium),
+            "high" => Some(Severity::High),
+            "critical" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+
+    // Blocked clients and changes made on someone else's behalf are critical;
+    // throttling, CAPTCHA failures, lockouts and credential changes are high
+    pub fn for_event(event_type: &str, success: bool) -> Self {
+        match event_type {
+            "suspicious_activity"
+            | "token_reuse_detected"
+            | "admin_password_reset"
+            | "admin_user_disabled"
+            | "admin_user_enabled"
+            | "admin_tokens_revoked"
+            | "admin_signing_key_rotated"
+            | "account_deleted" => Severity::Critical,
+            "oauth_invalid_state"
+            | "refresh_token_invalid_user_id"
+            | "password_changed"
+            | "api_key_created"
+            | "account_locked"
+            | "email_changed"
+            | "email_change_requested"
+            | "phone_number_changed"
+            | "phone_number_removed"
+            | "phone_change_invalid_password"
+            | "logout_all_devices"
+            | "password_change_invalid_current"
+            | "profile_update_invalid_password"
+            | "account_deletion_invalid_password" => Severity::High,
+            _ if event_type.ends_with("_rate_limit_exceeded")
+                || event_type.ends_with("_captcha_failed") =>
+            {
+                Severity::High
+            }
+            _ if !success => Severity::Medium,
+            _ => Severity::Low,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityEvent {
+    pub event_type: String,
+    pub severity: Severity,
+    pub request_id: Option<String>,
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+    pub user_id: Option<String>,
+    pub email: Option<String>,
+    pub success: bool,
+    pub details: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub occurred_at: OffsetDateTime,
+    // Filled in from GEOIP_DATABASE_PATH when it is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+// Sinks must not block: log_security_event runs on the request path
+pub trait SecurityEventSink: Send + Sync {
+    fn record(&self, event: &SecurityEvent);
+}
+
+// The structured log record every event gets
+pub struct TracingSink;
+
+impl SecurityEventSink for TracingSink {
+    fn record(&self, event: &SecurityEvent) {
+        let severity = format!("{:?}", event.severity).to_ascii_lowercase();
+        if event.success {
+            info!(
+                event_type = event.event_type,
+                severity = severity,
+                request_id = event.request_id,
+                ip_address = event.ip_address,
+                user_agent = event.user_agent,
+                user_id = event.user_id,
+                email = event.email,
+                details = event.details,
+                country = event.country,
+                region = event.region,
+                "Security event logged"
+            );
+        } else {
+            warn!(
+                event_type = event.event_type,
+                severity = severity,
+                request_id = event.request_id,
+                ip_address = event.ip_address,
+                user_agent = event.user_agent,
+                user_id = event.user_id,
+                email = event.email,
+                details = event.details,
+                country = event.country,
+                region = event.region,
+                "Failed security event logged"
+            );
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PiiConfigError {
+    #[error("LOG_PII_MODE must be full, masked or hashed, got {0:?}")]
+    InvalidMode(String),
+    #[error("LOG_PII_MODE=hashed needs LOG_PII_SALT")]
+    MissingSalt,
+}
+
+// How emails and IP addresses appear in security events before any sink sees them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PiiMode {
+    #[default]
+    Full,
+    // j***@example.com, and IPs without their host part
+    Masked,
+    // Salted hashes, so events about the same person or client still line up
+    Hashed {
+        salt: String,
+    },
+}
+
+impl PiiMode {
+    // LOG_PII_MODE=full|masked|hashed, default full; hashed reads LOG_PII_SALT
+    pub fn from_env() -> Result<Self, PiiConfigError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        match var("LOG_PII_MODE") {
+            None => Ok(PiiMode::Full),
+            Some(mode) => match mode.trim().to_ascii_lowercase().as_str() {
+                "full" => Ok(PiiMode::Full),
+                "masked" => Ok(PiiMode::Masked),
+                "hashed" => {
+                    let salt = var("LOG_PII_SALT").ok_or(PiiConfigError::MissingSalt)?;
+                    Ok(PiiMode::Hashed { salt })
+                }
+                _ => Err(PiiConfigError::InvalidMode(mode)),
+            },
+        }
+    }
+
+    pub fn email(&self, email: &str) -> String {
+        match self {
+            PiiMode::Full => email.to_string(),
+            PiiMode::Masked => match email.rsplit_once('@') {
+                Some((local, domain)) => match local.chars().next() {
+                    Some(first) => format!("{}***@{}", first, domain),
+                    None => format!("***@{}", domain),
+                },
+                None => "***".to_string(),
+            },
+            // Case-insensitive, like email lookups
+            PiiMode::Hashed { salt } => salted_hash(salt, &email.to_lowercase()),
+        }
+    }
+
+    // Masking keeps the /24 of IPv4 addresses and the /48 of IPv6 ones
+    pub fn ip(&self, ip: &str) -> String {
+        match self {
+            PiiMode::Full => ip.to_string(),
+            PiiMode::Masked => match ip.trim().parse::<IpAddr>() {
+                Ok(IpAddr::V4(ip)) => {
+                    let [a, b, c, _] = ip.octets();
+                    IpAddr::from([a, b, c, 0]).to_string()
+                }
+                Ok(IpAddr::V6(ip)) => {
+                    let [a, b, c, ..] = ip.segments();
+                    IpAddr::from([a, b, c, 0, 0, 0, 0, 0]).to_string()
+                }
+                Err(_) => "***".to_string(),
+            },
+            PiiMode::Hashed { salt } => salted_hash(salt, ip.trim()),
+        }
+    }
+
+    pub fn redact(&self, event: &SecurityEvent) -> SecurityEvent {
+        SecurityEvent {
+            ip_address: self.ip(&event.ip_address),
+            email: event.email.as_deref().map(|email| self.email(email)),
+            ..event.clone()
+        }
+    }
+}
+
+fn salted_hash(salt: &str, value: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(salt.as_bytes())
+        .chain_update(b":")
+        .chain_update(value.as_bytes())
+        .finalize();
+    digest[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+lazy_static::lazy_static! {
+    static ref SINKS: RwLock<Vec<Arc<dyn SecurityEventSink>>> =
+        RwLock::new(vec![Arc::new(TracingSink)]);
+    static ref PII_MODE: RwLock<PiiMode> = RwLock::new(PiiMode::default());
+    static ref GEO_RESOLVER: RwLock<Option<GeoResolver>> = RwLock::new(None);
+}
+
+// Adds a sink for the rest of the process, next to the tracing log
+pub fn add_security_event_sink(sink: Arc<dyn SecurityEventSink>) {
+    SINKS.write().unwrap().push(sink);
+}
+
+// Applies to every event dispatched from now on, in all sinks
+pub fn set_pii_mode(mode: PiiMode) {
+    *PII_MODE.write().unwrap() = mode;
+}
+
+// Events dispatched from now on get a country and region from their IP
+pub fn set_geo_resolver(resolver: Option<GeoResolver>) {
+    *GEO_RESOLVER.write().unwrap() = resolver;
+}
+
+pub fn dispatch(event: &SecurityEvent) {
+    // Located before redaction, which may mask or hash the IP
+    let mut event = event.clone();
+    if let Some(location) = GEO_RESOLVER
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|resolver| resolver.lookup(&event.ip_address))
+    {
+        event.country = event.country.or(location.country);
+        event.region = event.region.or(location.region);
+    }
+    let event = PII_MODE.read().unwrap().redact(&event);
+    for sink in SINKS.read().unwrap().iter() {
+        sink.record(&event);
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExternalServiceError {
+    #[error("Webhook request failed: {0}")]
+    Request(String),
+    #[error("Webhook responded with status {0}")]
+    Status(u16),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookConfigError {
+    #[error("SECURITY_WEBHOOK_URL is not a valid URL: {0}")]
+    InvalidUrl(String),
+    #[error("SECURITY_WEBHOOK_MIN_SEVERITY must be low, medium, high or critical, got {0:?}")]
+    InvalidSeverity(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub url: reqwest::Url,
+    pub min_severity: Severity,
+}
+
+impl WebhookConfig {
+    // SECURITY_WEBHOOK_URL enables the webhook; SECURITY_WEBHOOK_MIN_SEVERITY
+    // defaults to high
+    pub fn from_env() -> Result<Option<Self>, WebhookConfigError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let Some(url) = var("SECURITY_WEBHOOK_URL") else {
+            return Ok(None);
+        };
+        let url =
+            reqwest::Url::parse(url.trim()).map_err(|_| WebhookConfigError::InvalidUrl(url))?;
+        let min_severity = match var("SECURITY_WEBHOOK_MIN_SEVERITY") {
+            Some(value) => {
+                Severity::parse(&value).ok_or(WebhookConfigError::InvalidSeverity(value))?
+            }
+            None => Severity::High,
+        };
+
+        Ok(Some(Self { url, min_severity }))
+    }
+}
+
+// POSTs events at or above `min_severity` as JSON. Events go through a bounded
+// queue to a single worker task that delivers them one at a time, with a timeout
+// and retries, so a slow or failing endpoint never holds up a request. Clients
+// can trigger many of these events at will, so once the queue is full further
+// events are dropped and counted rather than piling up as tasks.
+#[derive(Clone)]
+pub struct WebhookSink {
+    url: reqwest::Url,
+    min_severity: Severity,
+    http: reqwest::Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+    queue_capacity: usize,
+    // Started by the first event recorded inside a runtime
+    queue: Arc<OnceLock<mpsc::Sender<SecurityEvent>>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl WebhookSink {
+    pub fn new(url: reqwest::Url, min_severity: Severity) -> Self {
+        Self {
+            url,
+            min_severity,
+            http: Self::client(DEFAULT_WEBHOOK_TIMEOUT),
+            max_attempts: DEFAULT_WEBHOOK_ATTEMPTS,
+            retry_delay: DEFAULT_WEBHOOK_RETRY_DELAY,
+            queue_capacity: DEFAULT_WEBHOOK_QUEUE_CAPACITY,
+            queue: Arc::new(OnceLock::new()),
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn from_config(config: WebhookConfig) -> Self {
+        Self::new(config.url, config.min_severity)
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.http = Self::client(timeout);
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    // Waits `retry_delay` times the attempt number between attempts
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    // Events waiting for delivery beyond this are dropped; takes effect before the first event
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity.max(1);
+        self
+    }
+
+    // Events dropped so far because the queue was full
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn client(timeout: Duration) -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default()
+    }
+
+    async fn post(&self, event: &SecurityEvent) -> Result<(), ExternalServiceError> {
+        let response = self
+            .http
+            .post(self.url.clone())
+            .json(event)
+            .send()
+            .await
+            .map_err(|e| ExternalServiceError::Request(e.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(ExternalServiceError::Status(response.status().as_u16()));
+        }
+        Ok(())
+    }
+
+    pub async fn deliver(&self, event: &SecurityEvent) -> Result<(), ExternalServiceError> {
+        let mut attempt = 1;
+        loop {
+            match self.post(event).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.max_attempts => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(self.retry_delay * attempt).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    fn start_worker(&self, runtime: &tokio::runtime::Handle) -> mpsc::Sender<SecurityEvent> {
+        let (sender, mut receiver) = mpsc::channel::<SecurityEvent>(self.queue_capacity);
+        let sink = self.clone();
+        runtime.spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                if let Err(e) = sink.deliver(&event).await {
+                    error!(
+                        event_type = event.event_type,
+                        error = %e,
+                        "Failed to deliver security event webhook"
+                    );
+                }
+            }
+        });
+        sender
+    }
+}
+
+impl SecurityEventSink for WebhookSink {
+    fn record(&self, event: &SecurityEvent) {
+        if event.severity < self.min_severity {
+            return;
+        }
+        // Outside a runtime there is nothing to spawn onto; the tracing log still has it
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let queue = self.queue.get_or_init(|| self.start_worker(&runtime));
+        if queue.try_send(event.clone()).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            warn!(
+                event_type = event.event_type,
+                dropped, "Security webhook queue is full, dropping event"
+            );
+        }
+    }
+}
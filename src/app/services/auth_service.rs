@@ -1,151 +1,396 @@
+use crate::app::hashing::{self, Argon2Params, PasswordHasher};
 use crate::app::models::auth::{
-    AuthError, ForgotPasswordRequest, ForgotPasswordResponse, RegisterRequest, RegisterResponse,
-    ResetPasswordRequest, ResetPasswordResponse,
+    AuthenticationError, DisposableEmailBlocklist, EmailDomainAllowlist, ForgotPasswordRequest,
+    ForgotPasswordResponse, PasswordPolicy, RegisterRequest, RegisterResponse,
+    ResetPasswordRequest, ResetPasswordResponse, normalize_email, normalize_username,
+    validate_email_domain_allowed, validate_email_not_disposable,
 };
 use crate::app::models::password_reset::PasswordResetToken;
-use crate::app::models::user::User;
-use crate::app::repositories::password_reset_repository::PasswordResetRepository;
-use crate::app::repositories::user_repository::UserRepository;
-use crate::app::services::email_service::MockEmailService;
+use crate::app::models::user::{User, UserResponse, UserStatus};
+use crate::app::repositories::password_history_repository::PasswordHistoryStore;
+use crate::app::repositories::password_reset_repository::PasswordResetStore;
+use crate::app::repositories::user_repository::UserStore;
+use crate::app::services::email_service::{MockEmailService, NotificationSettings};
+use crate::app::token_epochs::TokenEpochCache;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use time::OffsetDateTime;
+use tracing::warn;
+use uuid::Uuid;
 use validator::Validate;
 
+const DEFAULT_PASSWORD_HISTORY_DEPTH: i64 = 5;
+const EMAIL_CHANGE_TOKEN_TTL: time::Duration = time::Duration::hours(24);
+// Reset tokens this old are reported as invalid rather than expired
+const RESET_TOKEN_LOOKBACK: time::Duration = time::Duration::hours(24);
+
+// Number of previous passwords a new password is checked against
+pub fn get_password_history_depth() -> i64 {
+    std::env::var("PASSWORD_HISTORY_DEPTH")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_PASSWORD_HISTORY_DEPTH)
+}
+
+// USERNAME_LOGIN=true lets accounts register a username and log in with it
+pub fn get_username_login() -> bool {
+    matches!(
+        std::env::var("USERNAME_LOGIN"),
+        Ok(value) if value.eq_ignore_ascii_case("true") || value == "1"
+    )
+}
+
+// How POST /api/auth/register answers for an email that is already registered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RegistrationMode {
+    // 409 Conflict with "Email already registered"
+    #[default]
+    Explicit,
+    // The same 202 response as a new registration; the existing owner is emailed instead
+    EnumerationSafe,
+}
+
+impl RegistrationMode {
+    // REGISTRATION_ENUMERATION_SAFE=true, or its alias PREVENT_ACCOUNT_ENUMERATION=true,
+    // switches to enumeration-safe responses
+    pub fn from_env() -> Self {
+        let enabled = |name: &str| {
+            std::env::var(name)
+                .is_ok_and(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        };
+        if enabled("REGISTRATION_ENUMERATION_SAFE") || enabled("PREVENT_ACCOUNT_ENUMERATION") {
+            RegistrationMode::EnumerationSafe
+        } else {
+            RegistrationMode::Explicit
+        }
+    }
+}
+
+// What an enumeration-safe registration did; callers must answer the same way
+// for both so the client cannot tell them apart
+#[derive(Debug)]
+pub enum RegistrationOutcome {
+    Created(UserResponse),
+    AlreadyRegistered,
+}
+
 #[derive(Clone)]
 pub struct AuthService {
-    user_repository: UserRepository,
-    password_reset_repository: PasswordResetRepository,
+    user_repository: Arc<dyn UserStore>,
+    password_reset_repository: Arc<dyn PasswordResetStore>,
+    password_history_repository: Arc<dyn PasswordHistoryStore>,
     email_service: MockEmailService,
+    password_history_depth: i64,
+    registration_mode: RegistrationMode,
+    argon2_params: Argon2Params,
+    disposable_email_blocklist: Arc<DisposableEmailBlocklist>,
+    email_domain_allowlist: Arc<EmailDomainAllowlist>,
+    notification_settings: NotificationSettings,
+    token_epochs: TokenEpochCache,
+    username_login: bool,
 }
 
 impl AuthService {
     pub fn new(
-        user_repository: UserRepository,
-        password_reset_repository: PasswordResetRepository,
+        user_repository: impl UserStore + 'static,
+        password_reset_repository: impl PasswordResetStore + 'static,
+        password_history_repository: impl PasswordHistoryStore + 'static,
         email_service: MockEmailService,
     ) -> Self {
         Self {
-            user_repository,
-            password_reset_repository,
+            user_repository: Arc::new(user_repository),
+            password_reset_repository: Arc::new(password_reset_repository),
+            password_history_repository: Arc::new(password_history_repository),
             email_service,
+            password_history_depth: get_password_history_depth(),
+            registration_mode: RegistrationMode::from_env(),
+            argon2_params: Argon2Params::default(),
+            disposable_email_blocklist: Arc::new(DisposableEmailBlocklist::embedded()),
+            email_domain_allowlist: Arc::new(EmailDomainAllowlist::from_env()),
+            notification_settings: NotificationSettings::from_env(),
+            token_epochs: TokenEpochCache::disabled(),
+            username_login: get_username_login(),
         }
     }
 
+    pub fn with_password_history_depth(mut self, depth: i64) -> Self {
+        self.password_history_depth = depth;
+        self
+    }
+
+    pub fn with_registration_mode(mut self, mode: RegistrationMode) -> Self {
+        self.registration_mode = mode;
+        self
+    }
+
+    pub fn with_argon2_params(mut self, params: Argon2Params) -> Self {
+        self.argon2_params = params;
+        self
+    }
+
+    // Domains registration refuses; DisposableEmailBlocklist::disabled() turns the check off
+    pub fn with_disposable_email_blocklist(mut self, blocklist: DisposableEmailBlocklist) -> Self {
+        self.disposable_email_blocklist = Arc::new(blocklist);
+        self
+    }
+
+    // Domains registration is limited to; EmailDomainAllowlist::disabled() allows all
+    pub fn with_email_domain_allowlist(mut self, allowlist: EmailDomainAllowlist) -> Self {
+        self.email_domain_allowlist = Arc::new(allowlist);
+        self
+    }
+
+    pub fn with_notification_settings(mut self, settings: NotificationSettings) -> Self {
+        self.notification_settings = settings;
+        self
+    }
+
+    // The cache JwtService checks tokens against, updated when a password changes
+    pub fn with_token_epoch_cache(mut self, token_epochs: TokenEpochCache) -> Self {
+        self.token_epochs = token_epochs;
+        self
+    }
+
+    pub fn with_username_login(mut self, enabled: bool) -> Self {
+        self.username_login = enabled;
+        self
+    }
+
+    pub fn registration_mode(&self) -> RegistrationMode {
+        self.registration_mode
+    }
+
+    pub fn password_policy(&self) -> PasswordPolicy {
+        PasswordPolicy::current(self.password_history_depth)
+    }
+
     pub async fn find_user_by_email(&self, email: &str) -> Result<Option<User>, sqlx::Error> {
-        self.user_repository.find_by_email(email).await
+        self.user_repository
+            .find_by_email(&normalize_email(email))
+            .await
     }
 
-    pub async fn register(&self, request: RegisterRequest) -> Result<RegisterResponse, AuthError> {
-        // Validate request
-        if let Err(validation_errors) = request.validate() {
-            return Err(AuthError::validation_error(&validation_errors));
+    // The account a login identifier names: a username when username login is
+    // enabled and the identifier has no '@', otherwise an email
+    pub async fn find_user_for_login(&self, identifier: &str) -> Result<Option<User>, sqlx::Error> {
+        if self.username_login && !identifier.contains('@') {
+            self.user_repository
+                .find_by_username(&normalize_username(identifier))
+                .await
+        } else {
+            self.find_user_by_email(identifier).await
         }
+    }
 
-        // Check if email already exists
-        match self.user_repository.find_by_email(&request.email).await {
-            Ok(Some(_)) => {
-                return Err(AuthError::new("Email already registered"));
-            }
-            Ok(None) => {
-                // Email is available, continue
-            }
+    pub async fn user_status(&self, user_id: Uuid) -> Result<Option<UserStatus>, sqlx::Error> {
+        self.user_repository.status(user_id).await
+    }
+
+    // Called once `password` has been verified: replaces an imported bcrypt or
+    // PBKDF2 hash with Argon2id. A failure only means the upgrade waits for the
+    // next login.
+    pub async fn upgrade_password_hash(&self, user: &User, password: &str) {
+        let hasher = PasswordHasher::new(self.argon2_params);
+        if !hasher.needs_rehash(&user.password_hash) {
+            return;
+        }
+
+        let new_hash = match hasher.hash_password_async(password).await {
+            Ok(new_hash) => new_hash,
             Err(e) => {
-                return Err(AuthError::new(&format!("Database error: {}", e)));
+                warn!(error = %e, "Failed to rehash imported password");
+                return;
             }
+        };
+        if let Err(e) = self
+            .user_repository
+            .upgrade_password_hash(user.id, &user.password_hash, &new_hash)
+            .await
+        {
+            warn!(error = %e, "Failed to store upgraded password hash");
         }
+    }
 
-        // Create user
-        let user = match User::new(request.name, request.email, &request.password) {
-            Ok(user) => user,
-            Err(e) => {
-                return Err(AuthError::new(&format!("Password hashing error: {}", e)));
+    pub async fn find_user_by_id(&self, user_id: Uuid) -> Result<Option<User>, sqlx::Error> {
+        self.user_repository.find_by_id(user_id).await
+    }
+
+    // Set when the account signs in with an SMS code as well as its password
+    pub async fn verified_phone_number(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<String>, sqlx::Error> {
+        self.user_repository.verified_phone_number(user_id).await
+    }
+
+    pub async fn register(
+        &self,
+        mut request: RegisterRequest,
+    ) -> Result<RegisterResponse, AuthenticationError> {
+        // Validate request
+        if let Err(validation_errors) = request.validate() {
+            return Err(AuthenticationError::validation_failed_with_strength(
+                &validation_errors,
+                &request.password,
+            ));
+        }
+        request.email = normalize_email(&request.email);
+        validate_email_domain_allowed(&request.email, &self.email_domain_allowlist)?;
+        validate_email_not_disposable(&request.email, &self.disposable_email_blocklist)?;
+
+        let username = match request.username.as_deref() {
+            Some(_) if !self.username_login => {
+                return Err(AuthenticationError::InvalidInput {
+                    field: "username",
+                    message: "Usernames are not enabled".to_string(),
+                });
             }
+            Some(username) => Some(normalize_username(username)),
+            None => None,
+        };
+
+        // Check if email already exists
+        if self
+            .user_repository
+            .find_by_email(&request.email)
+            .await?
+            .is_some()
+        {
+            return Err(AuthenticationError::EmailAlreadyRegistered);
+        }
+        let username_taken = match &username {
+            Some(username) => self
+                .user_repository
+                .find_by_username(username)
+                .await?
+                .is_some(),
+            None => false,
         };
+        if username_taken {
+            return Err(AuthenticationError::UsernameTaken);
+        }
+
+        // Create user
+        let mut user = User::new_async(
+            request.name,
+            request.email,
+            &request.password,
+            &self.argon2_params,
+        )
+        .await?;
+        user.username = username;
 
         // Save user to database
         match self.user_repository.create(&user).await {
-            Ok(created_user) => Ok(RegisterResponse {
-                message: "User registered successfully".to_string(),
-                user: created_user.to_response(),
-            }),
-            Err(e) => {
-                // Check if it's a unique constraint violation (email already exists)
-                if e.to_string().contains("duplicate key")
-                    || e.to_string().contains("UNIQUE constraint")
+            Ok(created_user) => {
+                // The account exists either way; without the entry its first
+                // password can be reused later
+                if let Err(e) = self
+                    .password_history_repository
+                    .add(created_user.id, &created_user.password_hash)
+                    .await
                 {
-                    Err(AuthError::new("Email already registered"))
+                    warn!(error = %e, user_id = %created_user.id, "Failed to record initial password in history");
+                }
+
+                Ok(RegisterResponse {
+                    message: "User registered successfully".to_string(),
+                    user: created_user.to_response(),
+                })
+            }
+            // Lost a race with a concurrent registration for the same email or username
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                if e.constraint() == Some("users_username_key") {
+                    Err(AuthenticationError::UsernameTaken)
                 } else {
-                    Err(AuthError::new(&format!("Failed to create user: {}", e)))
+                    Err(AuthenticationError::EmailAlreadyRegistered)
                 }
             }
+            Err(e) => Err(e.into()),
         }
     }
 
-    pub async fn forgot_password(
+    // Registration that never reveals whether the email was taken. New accounts
+    // are created and welcomed; for an existing one the owner is told someone
+    // tried to sign up, and nothing is created.
+    pub async fn register_enumeration_safe(
         &self,
-        request: ForgotPasswordRequest,
-    ) -> Result<ForgotPasswordResponse, AuthError> {
-        // Validate request
+        mut request: RegisterRequest,
+    ) -> Result<RegistrationOutcome, AuthenticationError> {
         if let Err(validation_errors) = request.validate() {
-            return Err(AuthError::validation_error(&validation_errors));
+            return Err(AuthenticationError::validation_failed_with_strength(
+                &validation_errors,
+                &request.password,
+            ));
         }
+        request.email = normalize_email(&request.email);
+        validate_email_domain_allowed(&request.email, &self.email_domain_allowlist)?;
+        validate_email_not_disposable(&request.email, &self.disposable_email_blocklist)?;
 
-        // Check if user exists (but don't reveal if email doesn't exist for security)
-        let user = match self.user_repository.find_by_email(&request.email).await {
-            Ok(user_opt) => user_opt,
-            Err(e) => {
-                return Err(AuthError::new(&format!("Database error: {}", e)));
+        let email = request.email.clone();
+        let existing = self.user_repository.find_by_email(&email).await?;
+
+        let outcome = if existing.is_some() {
+            // Hash anyway so a duplicate takes as long as a new registration
+            let _ = self.argon2_params.hash_async(&request.password).await;
+            RegistrationOutcome::AlreadyRegistered
+        } else {
+            match self.register(request).await {
+                Ok(response) => RegistrationOutcome::Created(response.user),
+                // Lost a race with a concurrent registration for the same email
+                Err(AuthenticationError::EmailAlreadyRegistered) => {
+                    RegistrationOutcome::AlreadyRegistered
+                }
+                Err(error) => return Err(error),
             }
         };
 
+        // A failed email must not change the response, or it becomes the tell
+        let sent = match outcome {
+            RegistrationOutcome::Created(_) => self.email_service.send_welcome_email(&email).await,
+            RegistrationOutcome::AlreadyRegistered => {
+                self.email_service.send_existing_account_email(&email).await
+            }
+        };
+        if let Err(e) = sent {
+            eprintln!("Failed to send registration email: {}", e);
+        }
+
+        Ok(outcome)
+    }
+
+    pub async fn forgot_password(
+        &self,
+        request: ForgotPasswordRequest,
+    ) -> Result<ForgotPasswordResponse, AuthenticationError> {
+        request.validate()?;
+
+        // Check if user exists (but don't reveal if email doesn't exist for security)
+        let user = self.find_user_by_email(&request.email).await?;
+
         if let Some(user) = user {
             // Check rate limiting - max 3 requests per hour
             let one_hour_ago = OffsetDateTime::now_utc() - time::Duration::hours(1);
-            match self
+            let count = self
                 .password_reset_repository
                 .count_recent_requests(user.id, one_hour_ago)
-                .await
-            {
-                Ok(count) if count >= 3 => {
-                    return Err(AuthError::new(
-                        "Too many password reset requests. Please try again later.",
-                    ));
-                }
-                Ok(_) => {
-                    // Rate limit not exceeded, continue
-                }
-                Err(e) => {
-                    return Err(AuthError::new(&format!("Database error: {}", e)));
-                }
+                .await?;
+            if count >= 3 {
+                return Err(AuthenticationError::TooManyAttempts(
+                    "Too many password reset requests. Please try again later.".to_string(),
+                ));
             }
 
             // Generate secure token
             let plain_token = PasswordResetToken::generate_secure_token();
-            let reset_token = match PasswordResetToken::new(user.id, &plain_token) {
-                Ok(token) => token,
-                Err(e) => {
-                    return Err(AuthError::new(&format!("Token generation error: {}", e)));
-                }
-            };
-
-            // Save token to database
-            match self.password_reset_repository.create(&reset_token).await {
-                Ok(_) => {
-                    // Send email with token
-                    if let Err(e) = self
-                        .email_service
-                        .send_password_reset_email(&request.email, &plain_token)
-                        .await
-                    {
-                        return Err(AuthError::new(&format!("Email sending failed: {}", e)));
-                    }
-                }
-                Err(e) => {
-                    return Err(AuthError::new(&format!(
-                        "Failed to create reset token: {}",
-                        e
-                    )));
-                }
-            }
+            let reset_token = PasswordResetToken::new(user.id, &plain_token)?;
+
+            // Save token to database, then send email with token
+            self.password_reset_repository.create(&reset_token).await?;
+            self.email_service
+                .send_password_reset_email(&request.email, &plain_token)
+                .await
+                .map_err(|e| AuthenticationError::EmailDeliveryError(e.to_string()))?;
         }
 
         // Always return success message regardless of whether email exists (security best practice)
@@ -158,101 +403,269 @@ impl AuthService {
     pub async fn reset_password(
         &self,
         request: ResetPasswordRequest,
-    ) -> Result<ResetPasswordResponse, AuthError> {
-        // Validate request
-        if let Err(validation_errors) = request.validate() {
-            return Err(AuthError::validation_error(&validation_errors));
-        }
+    ) -> Result<ResetPasswordResponse, AuthenticationError> {
+        request.validate()?;
 
-        // Find all valid tokens and check if any match
-        let mut matching_token: Option<PasswordResetToken> = None;
-        let mut user_id: Option<uuid::Uuid> = None;
+        let token = match self.find_reset_token(&request.token).await? {
+            Some(token) if token.is_valid() => token,
+            _ => return Err(AuthenticationError::PasswordResetTokenInvalid),
+        };
+        let uid = token.user_id;
 
-        // We need to check all potential tokens since we can't search by plain token
-        // This is a simplified approach - in production, you might want to optimize this
-        // by using a more efficient token storage strategy
+        // Reject passwords the user has used recently
+        self.ensure_password_not_reused(uid, &request.password)
+            .await?;
 
-        // For now, we'll iterate through recent tokens to find a match
-        // In production, consider adding a token index or using a different approach
+        // Mark token as used
+        self.password_reset_repository
+            .mark_as_used(token.id)
+            .await?;
 
-        // Since we can't easily find tokens by plain text, we'll check user validation differently
-        // Let's first try to extract any valid tokens from recent requests
+        self.set_password(uid, &request.password).await?;
+        Ok(ResetPasswordResponse {
+            message: "Password reset successfully".to_string(),
+        })
+    }
 
-        // Instead, let's use a different approach - check all recent non-expired, non-used tokens
-        // and verify against them
+    // Checks a reset token without using it up
+    pub async fn validate_reset_token(&self, token: &str) -> Result<(), AuthenticationError> {
+        let since = OffsetDateTime::now_utc() - RESET_TOKEN_LOOKBACK;
+        match self.find_reset_token(token).await? {
+            Some(token) if token.used || token.created_at <= since => {
+                Err(AuthenticationError::PasswordResetTokenInvalid)
+            }
+            Some(token) if token.is_expired() => Err(AuthenticationError::ExpiredResetToken),
+            Some(_) => Ok(()),
+            None => Err(AuthenticationError::PasswordResetTokenInvalid),
+        }
+    }
 
-        // For this implementation, we'll need to get users and check their tokens
-        // This is not the most efficient, but works for the demonstration
+    async fn find_reset_token(
+        &self,
+        token: &str,
+    ) -> Result<Option<PasswordResetToken>, AuthenticationError> {
+        Ok(self
+            .password_reset_repository
+            .find_by_token_hash(&PasswordResetToken::hash_token(token))
+            .await?)
+    }
 
-        // Alternative: We could store a mapping or use a different token structure
-        // For now, let's implement a working solution by checking all recent tokens
+    // Issue a reset token for a user without going through their inbox or the
+    // self-service rate limit; callers are responsible for auditing who asked
+    pub async fn create_reset_token_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<(String, PasswordResetToken), AuthenticationError> {
+        self.user_repository
+            .find_by_id(user_id)
+            .await?
+            .ok_or(AuthenticationError::UserNotFound)?;
 
-        // We'll need to modify our approach. Let's find tokens by trying to verify against recent ones.
-        // Since this is complex, let's implement a simpler approach by finding all recent tokens
-        // and checking them one by one.
+        let plain_token = PasswordResetToken::generate_secure_token();
+        let reset_token = PasswordResetToken::new(user_id, &plain_token)?;
+        let reset_token = self.password_reset_repository.create(&reset_token).await?;
 
-        // Get all users (not efficient, but works for demo - in production, optimize this)
-        let users = match self.user_repository.get_all().await {
-            Ok(users) => users,
-            Err(e) => {
-                return Err(AuthError::new(&format!("Database error: {}", e)));
-            }
-        };
+        Ok((plain_token, reset_token))
+    }
 
-        for user in users {
-            if let Ok(tokens) = self
-                .password_reset_repository
-                .find_valid_tokens_by_user_id(user.id)
-                .await
-            {
-                for token in tokens {
-                    if token.is_valid() {
-                        if let Ok(true) = token.verify_token(&request.token) {
-                            matching_token = Some(token);
-                            user_id = Some(user.id);
-                            break;
-                        }
-                    }
-                }
-            }
-            if matching_token.is_some() {
-                break;
-            }
+    // Keeps the current email and mails a confirmation token to `new_email`, which
+    // must already be validated and normalized. The current address is told about it.
+    pub async fn request_email_change(
+        &self,
+        user: &User,
+        new_email: &str,
+    ) -> Result<(), AuthenticationError> {
+        validate_email_not_disposable(new_email, &self.disposable_email_blocklist)?;
+        if self
+            .user_repository
+            .find_by_email(new_email)
+            .await?
+            .is_some()
+        {
+            return Err(AuthenticationError::EmailInUse);
         }
 
-        let (token, uid) = match (matching_token, user_id) {
-            (Some(t), Some(u)) => (t, u),
-            _ => {
-                return Err(AuthError::new("Invalid or expired reset token"));
-            }
-        };
-
-        // Mark token as used
-        if let Err(e) = self.password_reset_repository.mark_as_used(token.id).await {
-            return Err(AuthError::new(&format!(
-                "Failed to invalidate token: {}",
-                e
-            )));
+        let plain_token = PasswordResetToken::generate_secure_token();
+        let expires_at = OffsetDateTime::now_utc() + EMAIL_CHANGE_TOKEN_TTL;
+        let updated = self
+            .user_repository
+            .set_pending_email(
+                user.id,
+                new_email,
+                &hash_confirmation_token(&plain_token),
+                expires_at,
+            )
+            .await?;
+        if !updated {
+            return Err(AuthenticationError::UserNotFound);
         }
 
-        // Update user password
-        let new_password_hash = match User::hash_password(&request.password) {
-            Ok(hash) => hash,
-            Err(e) => {
-                return Err(AuthError::new(&format!("Password hashing error: {}", e)));
-            }
-        };
+        self.email_service
+            .send_email_change_confirmation(new_email, &plain_token)
+            .await
+            .map_err(|e| AuthenticationError::EmailDeliveryError(e.to_string()))?;
+        self.email_service
+            .send_email_change_notice(&user.email, new_email)
+            .await
+            .map_err(|e| AuthenticationError::EmailDeliveryError(e.to_string()))?;
+        Ok(())
+    }
 
+    // Switches the account to its pending email; returns the updated user
+    pub async fn confirm_email_change(&self, token: &str) -> Result<User, AuthenticationError> {
         match self
             .user_repository
-            .update(uid, None, None, Some(&new_password_hash))
+            .confirm_pending_email(&hash_confirmation_token(token))
             .await
         {
-            Ok(Some(_)) => Ok(ResetPasswordResponse {
-                message: "Password reset successfully".to_string(),
-            }),
-            Ok(None) => Err(AuthError::new("User not found")),
-            Err(e) => Err(AuthError::new(&format!("Failed to update password: {}", e))),
+            Ok(Some(user)) => Ok(user),
+            Ok(None) => Err(AuthenticationError::InvalidConfirmationToken),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                Err(AuthenticationError::EmailInUse)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn change_password(
+        &self,
+        user_id: Uuid,
+        new_password: &str,
+    ) -> Result<User, AuthenticationError> {
+        self.ensure_password_not_reused(user_id, new_password)
+            .await?;
+        let user = self.set_password(user_id, new_password).await?;
+
+        self.notify_password_changed(&user).await;
+        Ok(user)
+    }
+
+    // Notifications are best-effort: a failed email is logged, never returned
+    async fn notify_password_changed(&self, user: &User) {
+        if !self.notification_settings.password_changed {
+            return;
+        }
+        if let Err(e) = self
+            .email_service
+            .send_password_changed_email(&user.email)
+            .await
+        {
+            warn!(error = %e, "Failed to send password change notification");
+        }
+    }
+
+    pub async fn notify_new_sign_in(
+        &self,
+        user: &User,
+        ip_address: &str,
+        user_agent: Option<&str>,
+    ) {
+        if !self.notification_settings.new_sign_in {
+            return;
+        }
+        if let Err(e) = self
+            .email_service
+            .send_new_sign_in_email(
+                &user.email,
+                ip_address,
+                user_agent,
+                OffsetDateTime::now_utc(),
+            )
+            .await
+        {
+            warn!(error = %e, "Failed to send new sign-in notification");
+        }
+    }
+
+    pub async fn notify_account_locked(
+        &self,
+        user: &User,
+        lockout_duration: time::Duration,
+        unlock_token: Option<&str>,
+    ) {
+        if !self.notification_settings.account_locked {
+            return;
+        }
+        if let Err(e) = self
+            .email_service
+            .send_account_locked_email(&user.email, lockout_duration, unlock_token)
+            .await
+        {
+            warn!(error = %e, "Failed to send account lockout notification");
         }
     }
+
+    pub async fn send_unlock_email(
+        &self,
+        email: &str,
+        token: &str,
+    ) -> Result<(), AuthenticationError> {
+        self.email_service
+            .send_account_unlock_email(email, token)
+            .await
+            .map_err(|e| AuthenticationError::EmailDeliveryError(e.to_string()))
+    }
+
+    pub async fn send_login_code_email(
+        &self,
+        email: &str,
+        code: &str,
+        ttl: time::Duration,
+    ) -> Result<(), AuthenticationError> {
+        self.email_service
+            .send_login_code_email(email, code, ttl.whole_minutes())
+            .await
+            .map_err(|e| AuthenticationError::EmailDeliveryError(e.to_string()))
+    }
+
+    pub fn notification_settings(&self) -> NotificationSettings {
+        self.notification_settings
+    }
+
+    async fn ensure_password_not_reused(
+        &self,
+        user_id: Uuid,
+        password: &str,
+    ) -> Result<(), AuthenticationError> {
+        let recent_hashes = self
+            .password_history_repository
+            .recent_hashes(user_id, self.password_history_depth)
+            .await?;
+        if hashing::matches_any_async(recent_hashes, password).await? {
+            return Err(AuthenticationError::PasswordReused);
+        }
+        Ok(())
+    }
+
+    // Hash and store the new password, keeping a copy of the hash in the history.
+    // Every token issued under the old password stops working.
+    async fn set_password(
+        &self,
+        user_id: Uuid,
+        password: &str,
+    ) -> Result<User, AuthenticationError> {
+        let password_hash = self.argon2_params.hash_async(password).await?;
+        let user = self
+            .user_repository
+            .update(user_id, None, None, Some(&password_hash))
+            .await?
+            .ok_or(AuthenticationError::UserNotFound)?;
+        self.password_history_repository
+            .add(user_id, &password_hash)
+            .await?;
+        if let Some(epoch) = self.user_repository.invalidate_tokens(user_id).await? {
+            self.token_epochs.insert(user_id, Some(epoch));
+        }
+        Ok(user)
+    }
+}
+
+// Email change and account unlock tokens, and API keys, end in 64 random characters, so a plain
+// SHA-256 is enough and lets the token be looked up directly instead of checked
+// against every row
+pub fn hash_confirmation_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
 }
@@ -0,0 +1,187 @@
+use crate::app::models::auth::AuthenticationError;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::error;
+
+const HCAPTCHA_VERIFY_URL: &str = "https://api.hcaptcha.com/siteverify";
+const RECAPTCHA_VERIFY_URL: &str = "https://www.google.com/recaptcha/api/siteverify";
+const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptchaError {
+    #[error("CAPTCHA provider error: {0}")]
+    Provider(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptchaConfigError {
+    #[error("CAPTCHA_PROVIDER must be hcaptcha or recaptcha, got {0:?}")]
+    UnknownProvider(String),
+    #[error("CAPTCHA_FAILURE_THRESHOLD must be a non-negative integer")]
+    InvalidThreshold,
+}
+
+// Checks a token a client got by solving a CAPTCHA
+#[async_trait]
+pub trait CaptchaVerifier: Send + Sync {
+    async fn verify(&self, token: &str, remote_ip: &str) -> Result<bool, CaptchaError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptchaProvider {
+    HCaptcha,
+    ReCaptcha,
+}
+
+impl CaptchaProvider {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "hcaptcha" => Some(CaptchaProvider::HCaptcha),
+            "recaptcha" => Some(CaptchaProvider::ReCaptcha),
+            _ => None,
+        }
+    }
+
+    pub fn verify_url(&self) -> &'static str {
+        match self {
+            CaptchaProvider::HCaptcha => HCAPTCHA_VERIFY_URL,
+            CaptchaProvider::ReCaptcha => RECAPTCHA_VERIFY_URL,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CaptchaConfig {
+    pub provider: CaptchaProvider,
+    pub secret: String,
+    pub failure_threshold: u32,
+}
+
+impl CaptchaConfig {
+    // CAPTCHA_PROVIDER and CAPTCHA_SECRET; None (CAPTCHA disabled) unless both
+    // are set. CAPTCHA_FAILURE_THRESHOLD defaults to 3.
+    pub fn from_env() -> Result<Option<Self>, CaptchaConfigError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let (Some(provider), Some(secret)) = (var("CAPTCHA_PROVIDER"), var("CAPTCHA_SECRET"))
+        else {
+            return Ok(None);
+        };
+        let provider = CaptchaProvider::parse(&provider)
+            .ok_or(CaptchaConfigError::UnknownProvider(provider))?;
+        let failure_threshold = match var("CAPTCHA_FAILURE_THRESHOLD") {
+            Some(value) => value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| CaptchaConfigError::InvalidThreshold)?,
+            None => DEFAULT_FAILURE_THRESHOLD,
+        };
+
+        Ok(Some(Self {
+            provider,
+            secret,
+            failure_threshold,
+        }))
+    }
+}
+
+#[derive(Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+}
+
+// hCaptcha and reCAPTCHA share the same siteverify protocol
+pub struct HttpCaptchaVerifier {
+    verify_url: String,
+    secret: String,
+    http: reqwest::Client,
+}
+
+impl HttpCaptchaVerifier {
+    pub fn new(provider: CaptchaProvider, secret: String) -> Self {
+        Self {
+            verify_url: provider.verify_url().to_string(),
+            secret,
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaVerifier for HttpCaptchaVerifier {
+    async fn verify(&self, token: &str, remote_ip: &str) -> Result<bool, CaptchaError> {
+        let provider_error = |e: reqwest::Error| CaptchaError::Provider(e.to_string());
+
+        let response: SiteVerifyResponse = self
+            .http
+            .post(&self.verify_url)
+            .form(&[
+                ("secret", self.secret.as_str()),
+                ("response", token),
+                ("remoteip", remote_ip),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+
+        Ok(response.success)
+    }
+}
+
+// Asks for a CAPTCHA only once an IP has failed `failure_threshold` times, so
+// normal users never see one
+#[derive(Clone)]
+pub struct CaptchaService {
+    verifier: Arc<dyn CaptchaVerifier>,
+    failure_threshold: u32,
+}
+
+impl CaptchaService {
+    pub fn new(verifier: Arc<dyn CaptchaVerifier>, failure_threshold: u32) -> Self {
+        Self {
+            verifier,
+            failure_threshold,
+        }
+    }
+
+    pub fn from_config(config: CaptchaConfig) -> Self {
+        Self::new(
+            Arc::new(HttpCaptchaVerifier::new(config.provider, config.secret)),
+            config.failure_threshold,
+        )
+    }
+
+    pub fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    pub async fn check(
+        &self,
+        recent_failures: u32,
+        token: Option<&str>,
+        remote_ip: &str,
+    ) -> Result<(), AuthenticationError> {
+        if recent_failures < self.failure_threshold {
+            return Ok(());
+        }
+
+        let token = token
+            .filter(|token| !token.trim().is_empty())
+            .ok_or(AuthenticationError::CaptchaRequired)?;
+
+        // An unreachable provider fails closed; only IPs that already failed get here
+        match self.verifier.verify(token, remote_ip).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(AuthenticationError::CaptchaFailed),
+            Err(e) => {
+                error!("CAPTCHA verification unavailable: {}", e);
+                Err(AuthenticationError::CaptchaFailed)
+            }
+        }
+    }
+}
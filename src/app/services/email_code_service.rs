@@ -0,0 +1,105 @@
+use crate::app::hashing::{self, Argon2Params};
+use crate::app::models::otp::{EmailLoginCode, OtpChallenge, OtpError};
+use crate::app::repositories::email_login_code_repository::EmailLoginCodeRepository;
+use uuid::Uuid;
+
+const DEFAULT_EMAIL_CODE_TTL: time::Duration = time::Duration::minutes(10);
+const DEFAULT_MAX_EMAIL_CODE_ATTEMPTS: i32 = 5;
+
+// EMAIL_LOGIN_CODES=true enables passwordless sign-in with emailed codes
+pub fn get_email_login_codes() -> bool {
+    matches!(
+        std::env::var("EMAIL_LOGIN_CODES"),
+        Ok(value) if value.eq_ignore_ascii_case("true") || value == "1"
+    )
+}
+
+// Creates and checks the one-time codes for signing in by email instead of
+// with a password. Sending them is left to the caller.
+#[derive(Clone)]
+pub struct EmailCodeService {
+    repository: EmailLoginCodeRepository,
+    argon2_params: Argon2Params,
+    ttl: time::Duration,
+    max_attempts: i32,
+}
+
+impl EmailCodeService {
+    pub fn new(repository: EmailLoginCodeRepository) -> Self {
+        Self {
+            repository,
+            argon2_params: Argon2Params::default(),
+            ttl: DEFAULT_EMAIL_CODE_TTL,
+            max_attempts: DEFAULT_MAX_EMAIL_CODE_ATTEMPTS,
+        }
+    }
+
+    pub fn with_argon2_params(mut self, params: Argon2Params) -> Self {
+        self.argon2_params = params;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: time::Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    // Wrong codes allowed per code; the last one invalidates it
+    pub fn with_max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn ttl(&self) -> time::Duration {
+        self.ttl
+    }
+
+    // Replaces any open code of the user's; returns the new one in plain text
+    pub async fn issue(&self, user_id: Uuid) -> Result<String, OtpError> {
+        let code = OtpChallenge::generate_code();
+        let login_code = EmailLoginCode::new(
+            user_id,
+            self.argon2_params.hash_async(&code).await?,
+            self.ttl,
+        );
+
+        self.repository.consume_all_for_user(user_id).await?;
+        self.repository.create(&login_code).await?;
+        Ok(code)
+    }
+
+    // Closes the user's open code if `code` matches it
+    pub async fn verify(&self, user_id: Uuid, code: &str) -> Result<(), OtpError> {
+        let login_code = self
+            .repository
+            .find_open_for_user(user_id)
+            .await?
+            .ok_or(OtpError::InvalidChallenge)?;
+        if login_code.is_expired() {
+            self.repository.consume(login_code.id).await?;
+            return Err(OtpError::Expired);
+        }
+
+        let Some(attempts) = self
+            .repository
+            .reserve_attempt(login_code.id, self.max_attempts)
+            .await?
+        else {
+            return Err(OtpError::TooManyAttempts);
+        };
+
+        if hashing::verify_async(&login_code.code_hash, code.trim()).await? {
+            // Losing the race to a concurrent correct guess counts as already used
+            if !self.repository.consume(login_code.id).await? {
+                return Err(OtpError::InvalidChallenge);
+            }
+            return Ok(());
+        }
+
+        if attempts >= self.max_attempts {
+            self.repository.consume(login_code.id).await?;
+            return Err(OtpError::TooManyAttempts);
+        }
+        Err(OtpError::InvalidCode)
+    }
+}
@@ -1,4 +1,7 @@
+use crate::app::models::login_attempt::describe_duration;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use time::OffsetDateTime;
 
 #[derive(Debug, Clone)]
 pub struct EmailMessage {
@@ -8,19 +11,68 @@ pub struct EmailMessage {
     pub sent_at: time::OffsetDateTime,
 }
 
+// Which security notifications are mailed to account owners. Both are on unless
+// NOTIFY_PASSWORD_CHANGE or NOTIFY_NEW_SIGN_IN is set to false or 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotificationSettings {
+    pub password_changed: bool,
+    pub new_sign_in: bool,
+    pub account_locked: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            password_changed: true,
+            new_sign_in: true,
+            account_locked: true,
+        }
+    }
+}
+
+impl NotificationSettings {
+    pub fn from_env() -> Self {
+        let enabled = |name: &str| match std::env::var(name) {
+            Ok(value) => !(value.eq_ignore_ascii_case("false") || value == "0"),
+            Err(_) => true,
+        };
+        Self {
+            password_changed: enabled("NOTIFY_PASSWORD_CHANGE"),
+            new_sign_in: enabled("NOTIFY_NEW_SIGN_IN"),
+            account_locked: enabled("NOTIFY_ACCOUNT_LOCKED"),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            password_changed: false,
+            new_sign_in: false,
+            account_locked: false,
+        }
+    }
+}
+
 // Mock email service for testing - stores emails in memory
 #[derive(Clone)]
 pub struct MockEmailService {
     sent_emails: Arc<Mutex<Vec<EmailMessage>>>,
+    latency: Duration,
 }
 
 impl MockEmailService {
     pub fn new() -> Self {
         Self {
             sent_emails: Arc::new(Mutex::new(Vec::new())),
+            latency: Duration::from_millis(10),
         }
     }
 
+    // How long a password reset email takes to go out, to stand in for a slow provider
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
     pub async fn send_password_reset_email(
         &self,
         email: &str,
@@ -54,20 +106,266 @@ The Chronos Team
             sent_at: time::OffsetDateTime::now_utc(),
         };
 
+        // Simulate potential email sending delay
+        tokio::time::sleep(self.latency).await;
+
         // Store the email in our mock service
         if let Ok(mut emails) = self.sent_emails.lock() {
             emails.push(message);
         }
 
-        // Simulate potential email sending delay
-        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-
         println!("📧 Mock Email Sent to: {}", email);
         println!("🔐 Reset Token: {}", token);
 
         Ok(())
     }
 
+    // Sent after an enumeration-safe registration creates a new account
+    pub async fn send_welcome_email(&self, email: &str) -> Result<(), EmailError> {
+        let subject = "Welcome to Chronos".to_string();
+        let body = r#"
+Hello,
+
+Your Chronos account has been created. You can now sign in with this email address and the password you chose.
+
+If you did not create this account, please contact support.
+
+Best regards,
+The Chronos Team
+            "#
+        .to_string();
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    // Sent instead of creating a second account when an enumeration-safe
+    // registration uses an email that is already registered
+    pub async fn send_existing_account_email(&self, email: &str) -> Result<(), EmailError> {
+        let subject = "You already have a Chronos account".to_string();
+        let body = r#"
+Hello,
+
+Someone tried to create a Chronos account with this email address, but you already have one.
+
+If this was you, sign in with your existing password or use "Forgot password" to reset it. If it was not you, you can ignore this email; your account has not been changed.
+
+Best regards,
+The Chronos Team
+            "#
+        .to_string();
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    // Sent to the new address; the change only happens once this token comes back
+    pub async fn send_email_change_confirmation(
+        &self,
+        email: &str,
+        token: &str,
+    ) -> Result<(), EmailError> {
+        let subject = "Confirm your new email address - Chronos".to_string();
+        let body = format!(
+            r#"
+Hello,
+
+You asked to use this address for your Chronos account. To confirm the change, use the following token:
+
+{}
+
+This token will expire in 24 hours. Until then you keep signing in with your current address. If you did not ask for this, you can ignore this email.
+
+Best regards,
+The Chronos Team
+            "#,
+            token
+        );
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    // Sent to the current address so the owner notices a change they didn't ask for
+    pub async fn send_email_change_notice(
+        &self,
+        email: &str,
+        new_email: &str,
+    ) -> Result<(), EmailError> {
+        let subject = "Email change requested - Chronos".to_string();
+        let body = format!(
+            r#"
+Hello,
+
+Someone asked to change the email address of your Chronos account to {}. The change only takes effect once it is confirmed from that address.
+
+If this was not you, change your password right away.
+
+Best regards,
+The Chronos Team
+            "#,
+            new_email
+        );
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    // The code for signing in without a password; see EmailCodeService
+    pub async fn send_login_code_email(
+        &self,
+        email: &str,
+        code: &str,
+        ttl_minutes: i64,
+    ) -> Result<(), EmailError> {
+        let subject = "Your sign-in code - Chronos".to_string();
+        let body = format!(
+            r#"
+Hello,
+
+Use the following code to sign in to your Chronos account:
+
+{}
+
+This code will expire in {} minutes and can only be used once. If you did not ask for it, you can ignore this email.
+
+Best regards,
+The Chronos Team
+            "#,
+            code, ttl_minutes
+        );
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    // Sent to a locked-out account on request; the token ends the lockout early
+    pub async fn send_account_unlock_email(
+        &self,
+        email: &str,
+        token: &str,
+    ) -> Result<(), EmailError> {
+        let subject = "Unlock your account - Chronos".to_string();
+        let body = format!(
+            r#"
+Hello,
+
+Your Chronos account was locked after too many failed sign-in attempts. To unlock it now instead of waiting, use the following token:
+
+{}
+
+This token will expire in 1 hour and can only be used once. If you did not ask for this, someone may be trying to guess your password; consider changing it.
+
+Best regards,
+The Chronos Team
+            "#,
+            token
+        );
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    // Sent once when failed logins lock the account; the token is only included
+    // when self-service unlock is enabled
+    pub async fn send_account_locked_email(
+        &self,
+        email: &str,
+        lockout_duration: time::Duration,
+        unlock_token: Option<&str>,
+    ) -> Result<(), EmailError> {
+        let subject = "Your account was locked - Chronos".to_string();
+        let unlock = match unlock_token {
+            Some(token) => format!(
+                "To unlock it now instead of waiting, use the following token:\n\n{}\n\nThis token will expire in 1 hour and can only be used once.",
+                token
+            ),
+            None => "It unlocks on its own when the lockout ends.".to_string(),
+        };
+        let body = format!(
+            r#"
+Hello,
+
+Your Chronos account was locked for {} after too many failed sign-in attempts.
+
+{}
+
+If this wasn't you, someone may be trying to guess your password; consider changing it once you can sign in again.
+
+Best regards,
+The Chronos Team
+            "#,
+            describe_duration(&lockout_duration),
+            unlock
+        );
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    pub async fn send_password_changed_email(&self, email: &str) -> Result<(), EmailError> {
+        let subject = "Your password was changed - Chronos".to_string();
+        let body = r#"
+Hello,
+
+The password of your Chronos account was just changed, and you have been signed out on all devices.
+
+If you did not do this, reset your password right away using "Forgot password".
+
+Best regards,
+The Chronos Team
+            "#
+        .to_string();
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    pub async fn send_new_sign_in_email(
+        &self,
+        email: &str,
+        ip_address: &str,
+        user_agent: Option<&str>,
+        signed_in_at: OffsetDateTime,
+    ) -> Result<(), EmailError> {
+        let subject = "New sign-in to your account - Chronos".to_string();
+        let body = format!(
+            r#"
+Hello,
+
+Your Chronos account was signed in to from a device we haven't seen before:
+
+Time: {}
+IP address: {}
+Device: {}
+
+If this was you, there is nothing to do. If not, change your password right away.
+
+Best regards,
+The Chronos Team
+            "#,
+            signed_in_at,
+            ip_address,
+            user_agent.unwrap_or("Unknown")
+        );
+
+        self.store(email, subject, body);
+        Ok(())
+    }
+
+    fn store(&self, email: &str, subject: String, body: String) {
+        let message = EmailMessage {
+            to: email.to_string(),
+            subject,
+            body,
+            sent_at: time::OffsetDateTime::now_utc(),
+        };
+
+        if let Ok(mut emails) = self.sent_emails.lock() {
+            emails.push(message);
+        }
+    }
+
     // Helper method for testing - get all sent emails
     pub fn get_sent_emails(&self) -> Vec<EmailMessage> {
         if let Ok(emails) = self.sent_emails.lock() {
@@ -1,22 +1,152 @@
-use crate::app::models::jwt::{BlacklistedToken, Claims, JwtError, TokenPair, TokenType};
-use crate::app::models::login_attempt::RefreshTokenStorage;
+use crate::app::clock::{Clock, SystemClock};
+use crate::app::cookies::{AppEnvironment, read_cookie};
+use crate::app::hashing::{self, Argon2Params};
+use crate::app::models::api_key::{ApiKey, generate_api_key, is_api_key};
+use crate::app::models::jwt::{
+    AuthContext, BlacklistedToken, Claims, JwtError, KeyRotation, RevocationOutcome,
+    RevocationPolicy, StoredSigningKey, TokenPair, TokenType,
+};
+use crate::app::models::login_attempt::{RefreshTokenBinding, RefreshTokenStorage, TokenOrigin};
+use crate::app::models::password_reset::PasswordResetToken;
+use crate::app::models::role::DEFAULT_ROLE;
+use crate::app::models::scope::{default_scopes, is_password_change_only, password_change_scopes};
 use crate::app::models::user::User;
+use crate::app::repositories::api_key_repository::ApiKeyRepository;
 use crate::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use crate::app::repositories::role_repository::RoleRepository;
+use crate::app::repositories::signing_key_repository::SigningKeyRepository;
 use crate::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
-use argon2::{
-    Argon2, PasswordHasher,
-    password_hash::{SaltString, rand_core::OsRng},
+use crate::app::repositories::transaction::with_transaction;
+use crate::app::repositories::trusted_device_repository::TrustedDeviceRepository;
+use crate::app::repositories::user_repository::UserRepository;
+use crate::app::services::auth_service::hash_confirmation_token;
+use crate::app::telemetry::hash_user_id;
+use crate::app::token_epochs::TokenEpochCache;
+use axum::http::{HeaderMap, header::AUTHORIZATION};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
 };
-use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
 use time::OffsetDateTime;
+use tokio::sync::Mutex;
+use tracing::{Span, field, instrument, warn};
 use uuid::Uuid;
 
+// Key id of the key built from JWT_SECRET
+pub const DEFAULT_KEY_ID: &str = "default";
+
+const ACCESS_TOKEN_TTL: time::Duration = time::Duration::minutes(15);
+const REFRESH_TOKEN_TTL: time::Duration = time::Duration::days(7);
+const DEFAULT_REMEMBER_ME_TTL: time::Duration = time::Duration::days(30);
+// Same as jsonwebtoken's own default
+const DEFAULT_LEEWAY_SECS: u64 = 60;
+// HS256 keys shorter than the hash output weaken the signature
+pub const MIN_JWT_SECRET_BYTES: usize = 32;
+const DEVELOPMENT_JWT_SECRET: &str =
+    "your-256-bit-secret-for-development-only-change-in-production";
+// The value .env.example ships with
+const EXAMPLE_JWT_SECRET: &str =
+    "your-super-secret-jwt-key-change-this-in-production-with-at-least-256-bits";
+
+#[derive(Debug, thiserror::Error)]
+pub enum JwtKeyError {
+    #[error("A signing key with kid {0:?} already exists")]
+    DuplicateKey(String),
+    #[error("No signing key with kid {0:?}")]
+    UnknownKey(String),
+    #[error("The active signing key {0:?} cannot be removed")]
+    ActiveKey(String),
+    // Never echoes the entry, which may be a bare secret
+    #[error("JWT_SIGNING_KEYS entries must look like kid:secret")]
+    InvalidConfig,
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Clone)]
+struct SigningKey {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+impl SigningKey {
+    fn new(kid: String, secret: &str) -> Self {
+        Self {
+            kid,
+            encoding: EncodingKey::from_secret(secret.as_bytes()),
+            decoding: DecodingKey::from_secret(secret.as_bytes()),
+        }
+    }
+}
+
+// New tokens are signed with the active key; any key in the ring still
+// validates the tokens it signed until it is removed
+struct KeyRing {
+    keys: Vec<SigningKey>,
+    active: String,
+}
+
+impl KeyRing {
+    fn find(&self, kid: &str) -> Option<&SigningKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+}
+
+// Whether the ring has to (re)load signing_keys before its next use. Clones
+// share it; signing_key_listener marks it stale whenever any server rotates a
+// key, so rotations and revocations reach every instance.
+#[derive(Clone)]
+pub struct SigningKeyReload {
+    stale: Arc<AtomicBool>,
+    loading: Arc<Mutex<()>>,
+}
+
+impl SigningKeyReload {
+    pub fn new() -> Self {
+        Self {
+            stale: Arc::new(AtomicBool::new(true)),
+            loading: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn mark_stale(&self) {
+        self.stale.store(true, Ordering::Release);
+    }
+}
+
+impl Default for SigningKeyReload {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Clone)]
 pub struct JwtService {
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    keys: Arc<RwLock<KeyRing>>,
+    signing_key_repository: Option<SigningKeyRepository>,
+    api_key_repository: Option<ApiKeyRepository>,
+    stored_keys: SigningKeyReload,
     blacklist_repository: TokenBlacklistRepository,
     refresh_token_repository: RefreshTokenRepository,
+    role_repository: Option<RoleRepository>,
+    user_repository: Option<UserRepository>,
+    trusted_device_repository: Option<TrustedDeviceRepository>,
+    revocation_policy: RevocationPolicy,
+    argon2_params: Argon2Params,
+    expose_jti: bool,
+    remember_me_ttl: time::Duration,
+    refresh_idle_timeout: Option<time::Duration>,
+    refresh_token_binding: RefreshTokenBinding,
+    role_ttl_overrides: HashMap<String, time::Duration>,
+    leeway: u64,
+    audience: Option<String>,
+    access_cookie: Option<String>,
+    token_epochs: TokenEpochCache,
+    clock: Arc<dyn Clock>,
 }
 
 impl JwtService {
@@ -26,55 +156,545 @@ impl JwtService {
         refresh_token_repository: RefreshTokenRepository,
     ) -> Self {
         Self {
-            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            keys: Arc::new(RwLock::new(KeyRing {
+                keys: vec![SigningKey::new(DEFAULT_KEY_ID.to_string(), secret)],
+                active: DEFAULT_KEY_ID.to_string(),
+            })),
+            signing_key_repository: None,
+            api_key_repository: None,
+            stored_keys: SigningKeyReload::new(),
             blacklist_repository,
             refresh_token_repository,
+            role_repository: None,
+            user_repository: None,
+            trusted_device_repository: None,
+            revocation_policy: RevocationPolicy::default(),
+            argon2_params: Argon2Params::default(),
+            expose_jti: false,
+            remember_me_ttl: DEFAULT_REMEMBER_ME_TTL,
+            refresh_idle_timeout: None,
+            refresh_token_binding: RefreshTokenBinding::Off,
+            role_ttl_overrides: HashMap::new(),
+            leeway: DEFAULT_LEEWAY_SECS,
+            audience: None,
+            access_cookie: None,
+            token_epochs: TokenEpochCache::disabled(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    // Load token roles from the database instead of issuing only the default role
+    pub fn with_role_repository(mut self, role_repository: RoleRepository) -> Self {
+        self.role_repository = Some(role_repository);
+        self
+    }
+
+    // Re-load users on refresh so disabled or deleted accounts can't keep rotating tokens
+    pub fn with_user_repository(mut self, user_repository: UserRepository) -> Self {
+        self.user_repository = Some(user_repository);
+        self
+    }
+
+    // Logging out everywhere then also forgets the user's trusted devices
+    pub fn with_trusted_device_repository(
+        mut self,
+        trusted_device_repository: TrustedDeviceRepository,
+    ) -> Self {
+        self.trusted_device_repository = Some(trusted_device_repository);
+        self
+    }
+
+    pub fn with_argon2_params(mut self, params: Argon2Params) -> Self {
+        self.argon2_params = params;
+        self
+    }
+
+    // Include the access token's jti in issued token pairs
+    pub fn with_jti_exposure(mut self, expose_jti: bool) -> Self {
+        self.expose_jti = expose_jti;
+        self
+    }
+
+    // Refresh token lifetime for logins with remember_me
+    pub fn with_remember_me_ttl(mut self, ttl: time::Duration) -> Self {
+        self.remember_me_ttl = ttl;
+        self
+    }
+
+    // Refresh tokens unused for longer than this expire early; None keeps only the absolute expiry
+    pub fn with_refresh_idle_timeout(mut self, idle_timeout: Option<time::Duration>) -> Self {
+        self.refresh_idle_timeout = idle_timeout;
+        self
+    }
+
+    // Access token lifetimes by role; a user holding several of these roles gets the shortest
+    pub fn with_refresh_token_binding(mut self, binding: RefreshTokenBinding) -> Self {
+        self.refresh_token_binding = binding;
+        self
+    }
+
+    pub fn with_role_ttl_overrides(
+        mut self,
+        role_ttl_overrides: HashMap<String, time::Duration>,
+    ) -> Self {
+        self.role_ttl_overrides = role_ttl_overrides;
+        self
+    }
+
+    // Seconds of clock skew tolerated when checking exp and nbf
+    pub fn with_leeway(mut self, leeway: u64) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    // Only accept tokens whose aud includes `audience`, as a resource server for
+    // one client would. None accepts tokens for any client, and those without aud.
+    pub fn with_audience(mut self, audience: Option<String>) -> Self {
+        self.audience = audience;
+        self
+    }
+
+    // Name of the cookie that carries the access token with TOKEN_DELIVERY=cookie
+    // or both; requests without an Authorization header may authenticate with it
+    pub fn with_access_cookie(mut self, access_cookie: Option<String>) -> Self {
+        self.access_cookie = access_cookie;
+        self
+    }
+
+    // Share with AuthService so a password change is seen here without waiting
+    // for the cached epoch to expire
+    pub fn with_token_epoch_cache(mut self, token_epochs: TokenEpochCache) -> Self {
+        self.token_epochs = token_epochs;
+        self
+    }
+
+    // Issuing, expiry, nbf and idle timeout checks read the time from `clock`
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    // Persist keys created by rotate_signing_key, and load the stored ones into
+    // the ring before the first token is signed or validated
+    pub fn with_signing_key_repository(mut self, repository: SigningKeyRepository) -> Self {
+        self.signing_key_repository = Some(repository);
+        self.stored_keys.mark_stale();
+        self
+    }
+
+    // Share with a SigningKeyListener so keys rotated on other servers are
+    // loaded here too
+    pub fn with_signing_key_reload(mut self, reload: SigningKeyReload) -> Self {
+        self.stored_keys = reload;
+        self
+    }
+
+    // Accept API keys as bearer tokens, see authenticate
+    pub fn with_api_key_repository(mut self, repository: ApiKeyRepository) -> Self {
+        self.api_key_repository = Some(repository);
+        self
+    }
+
+    pub fn refresh_ttl(&self, remember_me: bool) -> time::Duration {
+        if remember_me {
+            self.remember_me_ttl
+        } else {
+            REFRESH_TOKEN_TTL
+        }
+    }
+
+    // Roles without an override don't count; with none at all the default lifetime applies
+    pub fn access_ttl(&self, roles: &[String]) -> time::Duration {
+        roles
+            .iter()
+            .filter_map(|role| self.role_ttl_overrides.get(role))
+            .min()
+            .copied()
+            .unwrap_or(ACCESS_TOKEN_TTL)
+    }
+
+    pub fn with_revocation_policy(mut self, revocation_policy: RevocationPolicy) -> Self {
+        self.revocation_policy = revocation_policy;
+        self
+    }
+
+    // Keys are shared between clones, so a rotation applies to every handler at once
+    pub fn add_key(&self, kid: &str, secret: &str) -> Result<(), JwtKeyError> {
+        let mut ring = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        if ring.find(kid).is_some() {
+            return Err(JwtKeyError::DuplicateKey(kid.to_string()));
+        }
+        ring.keys.push(SigningKey::new(kid.to_string(), secret));
+        Ok(())
+    }
+
+    pub fn set_active_key(&self, kid: &str) -> Result<(), JwtKeyError> {
+        let mut ring = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        if ring.find(kid).is_none() {
+            return Err(JwtKeyError::UnknownKey(kid.to_string()));
+        }
+        ring.active = kid.to_string();
+        Ok(())
+    }
+
+    // Retire a key once every token it signed has expired
+    pub fn remove_key(&self, kid: &str) -> Result<(), JwtKeyError> {
+        let mut ring = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        if ring.active == kid {
+            return Err(JwtKeyError::ActiveKey(kid.to_string()));
+        }
+        if ring.find(kid).is_none() {
+            return Err(JwtKeyError::UnknownKey(kid.to_string()));
+        }
+        ring.keys.retain(|key| key.kid != kid);
+        Ok(())
+    }
+
+    pub fn active_key_id(&self) -> String {
+        self.keys
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .active
+            .clone()
+    }
+
+    // Signs new tokens with a freshly generated key from now on. Unless
+    // `revoke_old` is set, tokens signed by the previous keys stay valid; with
+    // it, every key but the new one is dropped, logging everybody out.
+    pub async fn rotate_signing_key(&self, revoke_old: bool) -> Result<KeyRotation, JwtKeyError> {
+        self.load_stored_keys().await?;
+
+        let kid = Uuid::new_v4().simple().to_string();
+        let secret = PasswordResetToken::generate_secure_token();
+        let revoked = if revoke_old {
+            let ring = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+            ring.keys.iter().map(|key| key.kid.clone()).collect()
+        } else {
+            Vec::new()
+        };
+
+        if let Some(repository) = &self.signing_key_repository {
+            repository.rotate(&kid, &secret, &revoked).await?;
+        }
+
+        let mut ring = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        if revoke_old {
+            ring.keys.clear();
+        }
+        ring.keys.push(SigningKey::new(kid.clone(), &secret));
+        ring.active = kid.clone();
+        Ok(KeyRotation { kid, revoked })
+    }
+
+    // Runs once per service and its clones, and again whenever the stored keys
+    // are marked stale; keys given by the environment stay unless they were
+    // revoked, and the stored active key wins
+    async fn load_stored_keys(&self) -> Result<(), sqlx::Error> {
+        let Some(repository) = &self.signing_key_repository else {
+            return Ok(());
+        };
+        if !self.stored_keys.stale.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        let _loading = self.stored_keys.loading.lock().await;
+        // Cleared before reading, so a change notified meanwhile loads again
+        if !self.stored_keys.stale.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+        match repository.list().await {
+            Ok(stored) => {
+                self.merge_stored_keys(&stored);
+                Ok(())
+            }
+            Err(e) => {
+                self.stored_keys.mark_stale();
+                Err(e)
+            }
+        }
+    }
+
+    fn merge_stored_keys(&self, stored: &[StoredSigningKey]) {
+        let mut ring = self.keys.write().unwrap_or_else(PoisonError::into_inner);
+        for key in stored {
+            match &key.secret {
+                Some(secret) if key.revoked_at.is_none() => {
+                    if ring.find(&key.kid).is_none() {
+                        ring.keys.push(SigningKey::new(key.kid.clone(), secret));
+                    }
+                }
+                _ => ring.keys.retain(|ring_key| ring_key.kid != key.kid),
+            }
+        }
+        if let Some(active) = stored
+            .iter()
+            .find(|key| key.active && key.revoked_at.is_none())
+        {
+            ring.active = active.kid.clone();
+        }
+    }
+
+    fn sign(&self, claims: &Claims) -> Result<String, JwtError> {
+        let ring = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+        let key = ring
+            .find(&ring.active)
+            .ok_or_else(|| JwtError::TokenCreationError("No active signing key".to_string()))?;
+
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(key.kid.clone());
+        encode(&header, claims, &key.encoding)
+            .map_err(|e| JwtError::TokenCreationError(e.to_string()))
+    }
+
+    // Picks the key named by the token's kid. Tokens without one (issued before
+    // key ids existed) are tried against every key.
+    fn decode_claims(&self, token: &str, validation: &Validation) -> Result<Claims, JwtError> {
+        let decode_error = |e: jsonwebtoken::errors::Error| match e.kind() {
+            ErrorKind::ExpiredSignature => JwtError::ExpiredToken,
+            _ => JwtError::InvalidToken(e.to_string()),
+        };
+
+        let header = decode_header(token).map_err(decode_error)?;
+        let ring = self.keys.read().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(kid) = header.kid {
+            let key = ring
+                .find(&kid)
+                .ok_or_else(|| JwtError::InvalidToken(format!("Unknown signing key {:?}", kid)))?;
+            return decode::<Claims>(token, &key.decoding, validation)
+                .map(|data| data.claims)
+                .map_err(decode_error);
+        }
+
+        for key in &ring.keys {
+            match decode::<Claims>(token, &key.decoding, validation) {
+                Err(e) if *e.kind() == ErrorKind::InvalidSignature => continue,
+                result => return result.map(|data| data.claims).map_err(decode_error),
+            }
+        }
+        Err(decode_error(ErrorKind::InvalidSignature.into()))
+    }
+
+    // The user a refresh token is being rotated for, as currently stored
+    async fn load_refresh_user(&self, user_id: Uuid, claims: &Claims) -> Result<User, JwtError> {
+        let Some(user_repository) = &self.user_repository else {
+            // Without a repository, fall back to the identity carried by the token
+            return Ok(User {
+                id: user_id,
+                name: None,
+                email: claims.email.clone(),
+                username: None,
+                password_hash: String::new(), // Not used in token generation
+                password_changed_at: None,
+                created_at: Some(OffsetDateTime::now_utc()), // Not used in token generation
+                updated_at: Some(OffsetDateTime::now_utc()), // Not used in token generation
+            });
+        };
+
+        let database_error =
+            |e: sqlx::Error| JwtError::InvalidToken(format!("Database error: {}", e));
+
+        let is_active = user_repository
+            .is_active(user_id)
+            .await
+            .map_err(database_error)?;
+        let user = user_repository
+            .find_by_id(user_id)
+            .await
+            .map_err(database_error)?;
+
+        let reason = match (user, is_active) {
+            (Some(user), Some(true)) => return Ok(user),
+            (Some(_), Some(false)) => "User account is disabled",
+            _ => "User no longer exists",
+        };
+
+        self.revoke_all_user_refresh_tokens(user_id).await;
+        Err(JwtError::AccountUnavailable(reason.to_string()))
+    }
+
+    // Every account holds the default role; granted roles are added on top of it
+    async fn load_roles(&self, user_id: Uuid) -> Result<Vec<String>, JwtError> {
+        let mut roles = vec![DEFAULT_ROLE.to_string()];
+
+        if let Some(role_repository) = &self.role_repository {
+            let granted = role_repository.get_user_roles(user_id).await.map_err(|e| {
+                JwtError::TokenCreationError(format!("Failed to load roles: {}", e))
+            })?;
+            roles.extend(granted.into_iter().filter(|role| role != DEFAULT_ROLE));
         }
+
+        Ok(roles)
     }
 
+    #[instrument(name = "auth.generate_token_pair", skip_all, fields(user_id = %hash_user_id(user.id)))]
     pub async fn generate_token_pair(&self, user: &User) -> Result<TokenPair, JwtError> {
-        let now = OffsetDateTime::now_utc();
+        self.generate_token_pair_with_refresh_ttl(user, REFRESH_TOKEN_TTL)
+            .await
+    }
+
+    // Like generate_token_pair, with a refresh token that lives for `refresh_ttl`
+    pub async fn generate_token_pair_with_refresh_ttl(
+        &self,
+        user: &User,
+        refresh_ttl: time::Duration,
+    ) -> Result<TokenPair, JwtError> {
+        self.generate_token_pair_for_device(user, refresh_ttl, None)
+            .await
+    }
+
+    // Like generate_token_pair_with_refresh_ttl, labelling the session with `device_name`
+    pub async fn generate_token_pair_for_device(
+        &self,
+        user: &User,
+        refresh_ttl: time::Duration,
+        device_name: Option<String>,
+    ) -> Result<TokenPair, JwtError> {
+        self.generate_token_pair_for_client(user, refresh_ttl, device_name, Vec::new())
+            .await
+    }
+
+    // Like generate_token_pair_for_device, with `audience` as the tokens' aud
+    // (see Client::token_audience); refreshing them keeps it
+    pub async fn generate_token_pair_for_client(
+        &self,
+        user: &User,
+        refresh_ttl: time::Duration,
+        device_name: Option<String>,
+        audience: Vec<String>,
+    ) -> Result<TokenPair, JwtError> {
+        self.generate_token_pair_from(
+            user,
+            refresh_ttl,
+            device_name,
+            audience,
+            TokenOrigin::default(),
+        )
+        .await
+    }
+
+    // Like generate_token_pair_for_client, remembering the client the tokens were
+    // issued to so REFRESH_TOKEN_BINDING can check who refreshes them
+    pub async fn generate_token_pair_from(
+        &self,
+        user: &User,
+        refresh_ttl: time::Duration,
+        device_name: Option<String>,
+        audience: Vec<String>,
+        origin: TokenOrigin,
+    ) -> Result<TokenPair, JwtError> {
+        self.issue_token_pair(
+            user,
+            refresh_ttl,
+            device_name,
+            None,
+            false,
+            audience,
+            origin,
+        )
+        .await
+    }
+
+    // Like generate_token_pair_for_client, for a login whose password has expired.
+    // The tokens carry only PASSWORD_CHANGE and no roles, so they can change the
+    // password and nothing else; refreshing them keeps that restriction.
+    pub async fn generate_password_change_token_pair(
+        &self,
+        user: &User,
+        refresh_ttl: time::Duration,
+        device_name: Option<String>,
+        audience: Vec<String>,
+        origin: TokenOrigin,
+    ) -> Result<TokenPair, JwtError> {
+        self.issue_token_pair(user, refresh_ttl, device_name, None, true, audience, origin)
+            .await
+    }
+
+    // Like generate_token_pair, for tokens that only become valid at `not_before`.
+    // Their lifetimes count from then rather than from now.
+    pub async fn generate_token_pair_not_before(
+        &self,
+        user: &User,
+        not_before: OffsetDateTime,
+    ) -> Result<TokenPair, JwtError> {
+        self.issue_token_pair(
+            user,
+            REFRESH_TOKEN_TTL,
+            None,
+            Some(not_before),
+            false,
+            Vec::new(),
+            TokenOrigin::default(),
+        )
+        .await
+    }
 
-        // Access token: 15 minutes
-        let access_exp = now + time::Duration::minutes(15);
+    #[allow(clippy::too_many_arguments)]
+    async fn issue_token_pair(
+        &self,
+        user: &User,
+        refresh_ttl: time::Duration,
+        device_name: Option<String>,
+        not_before: Option<OffsetDateTime>,
+        password_change_only: bool,
+        audience: Vec<String>,
+        origin: TokenOrigin,
+    ) -> Result<TokenPair, JwtError> {
+        self.load_stored_keys().await.map_err(|e| {
+            JwtError::TokenCreationError(format!("Failed to load signing keys: {}", e))
+        })?;
+        let now = self.clock.now();
+        let nbf = not_before.unwrap_or(now);
+        let (roles, scopes) = if password_change_only {
+            (Vec::new(), password_change_scopes())
+        } else {
+            (self.load_roles(user.id).await?, default_scopes())
+        };
+
+        let access_ttl = self.access_ttl(&roles);
+        let access_exp = nbf + access_ttl;
+        let access_jti = Uuid::new_v4().to_string();
+        let refresh_jti = Uuid::new_v4().to_string();
         let access_claims = Claims {
             sub: user.id.to_string(),
             email: user.email.clone(),
-            roles: vec!["user".to_string()], // Default role, can be expanded
+            roles: roles.clone(),
             exp: access_exp.unix_timestamp() as usize,
             iat: now.unix_timestamp() as usize,
-            jti: Uuid::new_v4().to_string(),
+            jti: access_jti.clone(),
             token_type: TokenType::Access,
+            nbf: nbf.unix_timestamp() as usize,
+            sid: Some(refresh_jti.clone()),
+            scopes: scopes.clone(),
+            aud: audience.clone(),
         };
 
-        // Refresh token: 7 days
-        let refresh_exp = now + time::Duration::days(7);
-        let refresh_jti = Uuid::new_v4().to_string();
+        let refresh_exp = nbf + refresh_ttl;
         let refresh_claims = Claims {
             sub: user.id.to_string(),
             email: user.email.clone(),
-            roles: vec!["user".to_string()],
+            roles,
             exp: refresh_exp.unix_timestamp() as usize,
             iat: now.unix_timestamp() as usize,
             jti: refresh_jti.clone(),
             token_type: TokenType::Refresh,
+            nbf: nbf.unix_timestamp() as usize,
+            sid: None,
+            scopes,
+            aud: audience,
         };
 
-        let header = Header::new(Algorithm::HS256);
-
-        let access_token = encode(&header, &access_claims, &self.encoding_key)
-            .map_err(|e| JwtError::TokenCreationError(e.to_string()))?;
-
-        let refresh_token = encode(&header, &refresh_claims, &self.encoding_key)
-            .map_err(|e| JwtError::TokenCreationError(e.to_string()))?;
+        let access_token = self.sign(&access_claims)?;
+        let refresh_token = self.sign(&refresh_claims)?;
 
         // Hash the refresh token for secure storage
-        let token_hash = self.hash_token(&refresh_token)?;
+        let token_hash = self.hash_token(&refresh_token).await?;
 
         // Store refresh token in database
         let refresh_token_storage =
-            RefreshTokenStorage::new(refresh_jti, user.id, token_hash, refresh_exp);
+            RefreshTokenStorage::new(refresh_jti, user.id, token_hash, refresh_exp)
+                .with_device_name(device_name)
+                .with_origin(origin);
 
         self.refresh_token_repository
             .store_token(&refresh_token_storage)
@@ -87,8 +707,9 @@ impl JwtService {
             access_token,
             refresh_token,
             token_type: "Bearer".to_string(),
-            expires_in: 15 * 60,                  // 15 minutes in seconds
-            refresh_expires_in: 7 * 24 * 60 * 60, // 7 days in seconds
+            expires_in: access_ttl.whole_seconds() as usize,
+            refresh_expires_in: refresh_ttl.whole_seconds() as usize,
+            access_token_jti: self.expose_jti.then_some(access_jti),
         })
     }
 
@@ -99,13 +720,18 @@ impl JwtService {
         // Verify it's a refresh token
         match claims.token_type {
             TokenType::Refresh => {}
-            TokenType::Access => {
+            TokenType::Access | TokenType::ApiKey => {
                 return Err(JwtError::InvalidToken(
                     "Access token provided, refresh token required".to_string(),
                 ));
             }
         }
 
+        // Disabled or deleted users lose all their refresh tokens here
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| JwtError::InvalidClaims("Invalid user ID".to_string()))?;
+        self.load_refresh_user(user_id, &claims).await?;
+
         // Validate refresh token against database storage
         let stored_token = self
             .refresh_token_repository
@@ -117,19 +743,10 @@ impl JwtService {
             "Refresh token not found".to_string(),
         ))?;
 
-        if !stored_token.is_valid() {
-            return Err(JwtError::InvalidToken(
-                "Refresh token is revoked or expired".to_string(),
-            ));
-        }
+        self.check_stored_token(&stored_token)?;
 
-        // Verify the token hash matches
-        let token_hash = self.hash_token(refresh_token)?;
-        if stored_token.token_hash != token_hash {
-            return Err(JwtError::InvalidToken(
-                "Refresh token hash mismatch".to_string(),
-            ));
-        }
+        self.verify_stored_token(&stored_token, refresh_token)
+            .await?;
 
         // Update last used time
         self.refresh_token_repository
@@ -140,8 +757,8 @@ impl JwtService {
             })?;
 
         // Generate new access token
-        let now = OffsetDateTime::now_utc();
-        let exp = now + time::Duration::minutes(15);
+        let now = self.clock.now();
+        let exp = now + self.access_ttl(&claims.roles);
 
         let new_claims = Claims {
             sub: claims.sub,
@@ -151,11 +768,13 @@ impl JwtService {
             iat: now.unix_timestamp() as usize,
             jti: Uuid::new_v4().to_string(),
             token_type: TokenType::Access,
+            nbf: now.unix_timestamp() as usize,
+            sid: Some(stored_token.jti),
+            scopes: claims.scopes,
+            aud: claims.aud,
         };
 
-        let header = Header::new(Algorithm::HS256);
-        encode(&header, &new_claims, &self.encoding_key)
-            .map_err(|e| JwtError::TokenCreationError(e.to_string()))
+        self.sign(&new_claims)
     }
 
     // Refresh with token rotation for enhanced security
@@ -163,19 +782,53 @@ impl JwtService {
         &self,
         refresh_token: &str,
         user_id: Uuid,
+    ) -> Result<TokenPair, JwtError> {
+        self.refresh_with_rotation_from(refresh_token, user_id, &TokenOrigin::default())
+            .await
+    }
+
+    // Like refresh_with_rotation, for a refresh presented by `origin`. Under
+    // REFRESH_TOKEN_BINDING a token presented by another client is revoked.
+    #[instrument(
+        name = "auth.refresh",
+        skip_all,
+        fields(user_id = %hash_user_id(user_id), outcome = field::Empty)
+    )]
+    pub async fn refresh_with_rotation_from(
+        &self,
+        refresh_token: &str,
+        user_id: Uuid,
+        origin: &TokenOrigin,
+    ) -> Result<TokenPair, JwtError> {
+        let result = self
+            .rotate_refresh_token(refresh_token, user_id, origin)
+            .await;
+        let outcome = if result.is_ok() { "success" } else { "failure" };
+        Span::current().record("outcome", outcome);
+        result
+    }
+
+    async fn rotate_refresh_token(
+        &self,
+        refresh_token: &str,
+        user_id: Uuid,
+        origin: &TokenOrigin,
     ) -> Result<TokenPair, JwtError> {
         let claims = self.validate_token(refresh_token).await?;
 
         // Verify it's a refresh token
         match claims.token_type {
             TokenType::Refresh => {}
-            TokenType::Access => {
+            TokenType::Access | TokenType::ApiKey => {
                 return Err(JwtError::InvalidToken(
                     "Access token provided, refresh token required".to_string(),
                 ));
             }
         }
 
+        // Disabled or deleted users lose all their refresh tokens here
+        let user = self.load_refresh_user(user_id, &claims).await?;
+
         // Validate refresh token against database storage
         let stored_token = self
             .refresh_token_repository
@@ -187,18 +840,21 @@ impl JwtService {
             "Refresh token not found".to_string(),
         ))?;
 
-        if !stored_token.is_valid() {
-            return Err(JwtError::InvalidToken(
-                "Refresh token is revoked or expired".to_string(),
-            ));
-        }
+        self.check_stored_token(&stored_token)?;
 
-        // Verify the token hash matches
-        let token_hash = self.hash_token(refresh_token)?;
-        if stored_token.token_hash != token_hash {
-            return Err(JwtError::InvalidToken(
-                "Refresh token hash mismatch".to_string(),
-            ));
+        self.verify_stored_token(&stored_token, refresh_token)
+            .await?;
+
+        // The token may have been stolen, so it is not left usable by either client
+        let stored_origin = stored_token.origin();
+        if !self.refresh_token_binding.allows(&stored_origin, origin) {
+            self.refresh_token_repository
+                .revoke_token(&claims.jti)
+                .await
+                .map_err(|e| {
+                    JwtError::TokenCreationError(format!("Failed to revoke refresh token: {}", e))
+                })?;
+            return Err(JwtError::BindingMismatch);
         }
 
         // Revoke the old refresh token
@@ -209,38 +865,150 @@ impl JwtService {
                 JwtError::TokenCreationError(format!("Failed to revoke old refresh token: {}", e))
             })?;
 
-        // Create a mock user object for token generation
-        let user = User {
-            id: user_id,
-            name: None,
-            email: claims.email.clone(),
-            password_hash: String::new(), // Not used in token generation
-            created_at: Some(OffsetDateTime::now_utc()), // Not used in token generation
-            updated_at: Some(OffsetDateTime::now_utc()), // Not used in token generation
+        // The new refresh token lives as long as the one it replaces, so
+        // remember_me sessions stay long-lived across rotations
+        let lifetime = time::Duration::seconds(claims.exp as i64 - claims.iat as i64);
+        let refresh_ttl = if lifetime.is_positive() {
+            lifetime
+        } else {
+            REFRESH_TOKEN_TTL
         };
-
-        // Generate new token pair
-        self.generate_token_pair(&user).await
+        // The replacement token carries on the same named session, audience and
+        // origin, restricted to a password change if the one it replaces was.
+        // Tokens issued without an origin are bound to the first client refreshing them.
+        self.issue_token_pair(
+            &user,
+            refresh_ttl,
+            stored_token.device_name,
+            None,
+            is_password_change_only(&claims.scopes),
+            claims.aud,
+            stored_origin.or(origin.clone()),
+        )
+        .await
     }
 
     // Validate a token and return its claims
     pub async fn validate_token(&self, token: &str) -> Result<Claims, JwtError> {
+        // exp is still required, but checked below against our own clock
         let mut validation = Validation::new(Algorithm::HS256);
-        validation.validate_exp = true;
-
-        let token_data = decode::<Claims>(token, &self.decoding_key, &validation).map_err(|e| {
-            match e.kind() {
-                jsonwebtoken::errors::ErrorKind::ExpiredSignature => JwtError::ExpiredToken,
-                _ => JwtError::InvalidToken(e.to_string()),
+        validation.validate_exp = false;
+        match &self.audience {
+            Some(audience) => {
+                validation.set_audience(&[audience]);
+                validation.set_required_spec_claims(&["exp", "aud"]);
             }
-        })?;
+            None => validation.validate_aud = false,
+        }
+
+        self.load_stored_keys()
+            .await
+            .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))?;
+        let claims = self.decode_claims(token, &validation)?;
+        self.check_validity_window(&claims)?;
 
         // Check if token is blacklisted
-        if self.is_token_blacklisted(&token_data.claims.jti).await? {
+        if self.is_token_blacklisted(&claims.jti).await? {
             return Err(JwtError::BlacklistedToken);
         }
 
-        Ok(token_data.claims)
+        // Reject tokens issued before a password change or admin revocation
+        self.ensure_issued_after_epoch(&claims).await?;
+
+        Ok(claims)
+    }
+
+    // Tokens without an nbf claim decode with 0, so they are valid from the start
+    fn check_validity_window(&self, claims: &Claims) -> Result<(), JwtError> {
+        let now = self.clock.now().unix_timestamp();
+        let leeway = self.leeway as i64;
+        if (claims.exp as i64) < now - leeway {
+            return Err(JwtError::ExpiredToken);
+        }
+        if claims.nbf as i64 > now + leeway {
+            return Err(JwtError::InvalidToken("Token is not valid yet".to_string()));
+        }
+        Ok(())
+    }
+
+    // iat only has whole seconds, so a token from the same second as the epoch
+    // still passes. Without a user repository there is no epoch to check.
+    async fn ensure_issued_after_epoch(&self, claims: &Claims) -> Result<(), JwtError> {
+        let Some(user_repository) = &self.user_repository else {
+            return Ok(());
+        };
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| JwtError::InvalidClaims("Invalid user ID".to_string()))?;
+
+        let epoch = match self.token_epochs.get(user_id) {
+            Some(epoch) => epoch,
+            None => {
+                let epoch = user_repository
+                    .token_invalid_before(user_id)
+                    .await
+                    .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))?;
+                self.token_epochs.insert(user_id, epoch);
+                epoch
+            }
+        };
+
+        match epoch {
+            Some(epoch) if (claims.iat as i64) < epoch.unix_timestamp() => {
+                Err(JwtError::BlacklistedToken)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Stops every token the user holds at once: access tokens through the epoch,
+    // refresh tokens by revoking them too so they drop out of the session list.
+    // None if there is no live account.
+    pub async fn invalidate_user_tokens(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<RevocationOutcome>, JwtError> {
+        let user_repository = self.user_repository.as_ref().ok_or_else(|| {
+            JwtError::TokenCreationError("No user repository configured".to_string())
+        })?;
+
+        let Some(epoch) = user_repository
+            .invalidate_tokens(user_id)
+            .await
+            .map_err(|e| JwtError::TokenCreationError(format!("Database error: {}", e)))?
+        else {
+            return Ok(None);
+        };
+        self.token_epochs.insert(user_id, Some(epoch));
+
+        Ok(Some(self.revoke_all_user_refresh_tokens(user_id).await))
+    }
+
+    // Access tokens outlive a disable or delete, so the middleware checks the account
+    // on every request. Without a user repository there is nothing to check against.
+    pub async fn ensure_account_active(&self, claims: &Claims) -> Result<(), JwtError> {
+        let user_id = Uuid::parse_str(&claims.sub)
+            .map_err(|_| JwtError::InvalidClaims("Invalid user ID".to_string()))?;
+        self.ensure_user_active(user_id).await
+    }
+
+    async fn ensure_user_active(&self, user_id: Uuid) -> Result<(), JwtError> {
+        let Some(user_repository) = &self.user_repository else {
+            return Ok(());
+        };
+
+        match user_repository
+            .is_active(user_id)
+            .await
+            .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))?
+        {
+            Some(true) => Ok(()),
+            Some(false) => Err(JwtError::AccountUnavailable(
+                "User account is disabled".to_string(),
+            )),
+            None => Err(JwtError::AccountUnavailable(
+                "User no longer exists".to_string(),
+            )),
+        }
     }
 
     // Check if a token is blacklisted
@@ -251,26 +1019,67 @@ impl JwtService {
             .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))
     }
 
-    // Blacklist a token (for logout)
+    // Blacklist an access token (for logout). Refresh tokens are tracked in the
+    // refresh_tokens table, so revoking them there is enough to invalidate them.
     pub async fn blacklist_token(&self, token: &str) -> Result<(), JwtError> {
         let claims = self.decode_token_without_validation(token)?;
 
-        let expires_at = OffsetDateTime::from_unix_timestamp(claims.exp as i64)
-            .map_err(|_| JwtError::InvalidClaims("Invalid expiration time".to_string()))?;
-
-        let user_id = Uuid::parse_str(&claims.sub)
-            .map_err(|_| JwtError::InvalidClaims("Invalid user ID".to_string()))?;
-
-        let blacklisted_token =
-            BlacklistedToken::new(claims.jti, user_id, claims.token_type, expires_at);
+        if matches!(claims.token_type, TokenType::Refresh) {
+            return self.revoke_refresh_token(token).await;
+        }
 
         self.blacklist_repository
-            .blacklist_token(&blacklisted_token)
+            .blacklist_token(&blacklist_entry(claims)?)
             .await
             .map(|_| ())
             .map_err(|e| JwtError::TokenCreationError(format!("Failed to blacklist token: {}", e)))
     }
 
+    // Logs the user out everywhere: blacklists `access_token`, revokes every
+    // refresh token and forgets the trusted devices in one transaction. On
+    // failure none of it has happened, so the request can simply be repeated.
+    pub async fn logout_all_devices(&self, access_token: &str, user_id: Uuid) -> RevocationOutcome {
+        let failed = |error: String| RevocationOutcome {
+            revoked: 0,
+            complete: false,
+            error: Some(error),
+        };
+        let entry = match self
+            .decode_token_without_validation(access_token)
+            .and_then(blacklist_entry)
+        {
+            Ok(entry) => entry,
+            Err(e) => return failed(e.to_string()),
+        };
+
+        let result = with_transaction(self.refresh_token_repository.pool(), |tx| {
+            Box::pin(async move {
+                // A refresh token is revoked below along with the rest
+                if !matches!(entry.token_type, TokenType::Refresh) {
+                    self.blacklist_repository
+                        .blacklist_token_tx(tx, &entry)
+                        .await?;
+                }
+                if let Some(trusted_devices) = &self.trusted_device_repository {
+                    trusted_devices.delete_for_user_tx(tx, user_id).await?;
+                }
+                self.refresh_token_repository
+                    .revoke_user_tokens_tx(tx, user_id)
+                    .await
+            })
+        })
+        .await;
+
+        match result {
+            Ok(revoked) => RevocationOutcome {
+                revoked,
+                complete: true,
+                error: None,
+            },
+            Err(e) => failed(e.to_string()),
+        }
+    }
+
     // Decode token without validation (used for blacklisting expired tokens)
     pub fn decode_token_without_validation(&self, token: &str) -> Result<Claims, JwtError> {
         let mut validation = Validation::new(Algorithm::HS256);
@@ -278,10 +1087,7 @@ impl JwtService {
         validation.validate_nbf = false;
         validation.validate_aud = false;
 
-        let token_data = decode::<Claims>(token, &self.decoding_key, &validation)
-            .map_err(|e| JwtError::InvalidToken(e.to_string()))?;
-
-        Ok(token_data.claims)
+        self.decode_claims(token, &validation)
     }
 
     // Extract token from Authorization header
@@ -295,14 +1101,68 @@ impl JwtService {
         }
     }
 
+    // The request's Bearer token, or without an Authorization header the access
+    // token cookie when one is configured; None when neither is sent
+    pub fn extract_request_token<'a>(
+        &self,
+        headers: &'a HeaderMap,
+    ) -> Result<Option<&'a str>, JwtError> {
+        if let Some(auth_header) = headers.get(AUTHORIZATION) {
+            let auth_header = auth_header.to_str().map_err(|_| {
+                JwtError::InvalidToken("Invalid authorization header format".to_string())
+            })?;
+            return Self::extract_token_from_header(auth_header).map(Some);
+        }
+
+        Ok(self
+            .access_cookie
+            .as_deref()
+            .and_then(|name| read_cookie(headers, name))
+            .filter(|token| !token.is_empty()))
+    }
+
     // Hash a token for secure storage (used for refresh tokens)
-    fn hash_token(&self, token: &str) -> Result<String, JwtError> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let hash = argon2
-            .hash_password(token.as_bytes(), &salt)
-            .map_err(|e| JwtError::TokenCreationError(format!("Token hashing failed: {}", e)))?;
-        Ok(hash.to_string())
+    async fn hash_token(&self, token: &str) -> Result<String, JwtError> {
+        self.argon2_params
+            .hash_async(token)
+            .await
+            .map_err(|e| JwtError::TokenCreationError(format!("Token hashing failed: {}", e)))
+    }
+
+    // Revoked, past its absolute expiry, or idle for longer than the configured timeout
+    fn check_stored_token(&self, stored_token: &RefreshTokenStorage) -> Result<(), JwtError> {
+        let now = self.clock.now();
+        if !stored_token.is_valid_at(now) {
+            return Err(JwtError::InvalidToken(
+                "Refresh token is revoked or expired".to_string(),
+            ));
+        }
+        if self
+            .refresh_idle_timeout
+            .is_some_and(|idle_timeout| stored_token.is_idle_at(idle_timeout, now))
+        {
+            return Err(JwtError::ExpiredToken);
+        }
+        Ok(())
+    }
+
+    // Every stored hash has its own random salt, so hashing the presented token
+    // again can never reproduce it. Argon2 verification re-derives the hash with
+    // the stored salt and parameters and compares the outputs in constant time.
+    async fn verify_stored_token(
+        &self,
+        stored_token: &RefreshTokenStorage,
+        refresh_token: &str,
+    ) -> Result<(), JwtError> {
+        let matches = hashing::verify_async(&stored_token.token_hash, refresh_token)
+            .await
+            .map_err(|e| JwtError::InvalidToken(format!("Token verification failed: {}", e)))?;
+        if !matches {
+            return Err(JwtError::InvalidToken(
+                "Refresh token hash mismatch".to_string(),
+            ));
+        }
+        Ok(())
     }
 
     // Revoke refresh token on logout
@@ -310,30 +1170,175 @@ impl JwtService {
         let claims = self.decode_token_without_validation(refresh_token)?;
 
         if matches!(claims.token_type, TokenType::Refresh) {
-            self.refresh_token_repository
-                .revoke_token(&claims.jti)
-                .await
-                .map_err(|e| {
-                    JwtError::TokenCreationError(format!("Failed to revoke refresh token: {}", e))
-                })?;
+            self.revoke_session(&claims.jti).await?;
         }
 
         Ok(())
     }
 
-    // Revoke all refresh tokens for a user
-    pub async fn revoke_all_user_refresh_tokens(&self, user_id: Uuid) -> Result<(), JwtError> {
+    // Revoke the refresh token with this jti, e.g. the `sid` of an access token
+    pub async fn revoke_session(&self, jti: &str) -> Result<(), JwtError> {
         self.refresh_token_repository
-            .revoke_all_user_tokens(user_id)
+            .revoke_token(jti)
             .await
             .map_err(|e| {
-                JwtError::TokenCreationError(format!("Failed to revoke user tokens: {}", e))
-            })
+                JwtError::TokenCreationError(format!("Failed to revoke refresh token: {}", e))
+            })?;
+
+        Ok(())
+    }
+
+    // Revoke all refresh tokens for a user
+    // Revokes in batches, retrying a failed batch, and reports how far it got.
+    // Safe to call again after a partial run.
+    pub async fn revoke_all_user_refresh_tokens(&self, user_id: Uuid) -> RevocationOutcome {
+        let RevocationPolicy {
+            batch_size,
+            max_retries,
+        } = self.revocation_policy;
+        let mut revoked = 0;
+        let mut failures = 0;
+
+        loop {
+            match self
+                .refresh_token_repository
+                .revoke_user_tokens_batch(user_id, batch_size)
+                .await
+            {
+                Ok(count) => {
+                    revoked += count;
+                    failures = 0;
+                    if count < batch_size as u64 {
+                        return RevocationOutcome {
+                            revoked,
+                            complete: true,
+                            error: None,
+                        };
+                    }
+                }
+                Err(e) if failures < max_retries => {
+                    failures += 1;
+                    warn!(error = %e, attempt = failures, "Retrying refresh token revocation");
+                }
+                Err(e) => {
+                    return RevocationOutcome {
+                        revoked,
+                        complete: false,
+                        error: Some(e.to_string()),
+                    };
+                }
+            }
+        }
+    }
+
+    // The user's active sessions, newest first
+    pub async fn list_sessions(&self, user_id: Uuid) -> Result<Vec<RefreshTokenStorage>, JwtError> {
+        self.refresh_token_repository
+            .find_active_by_user(user_id)
+            .await
+            .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))
+    }
+
+    // Renames one of the user's active sessions; None if they have no session `jti`
+    pub async fn rename_session(
+        &self,
+        user_id: Uuid,
+        jti: &str,
+        device_name: &str,
+    ) -> Result<Option<RefreshTokenStorage>, JwtError> {
+        self.refresh_token_repository
+            .rename(user_id, jti, device_name)
+            .await
+            .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))
+    }
+
+    // The auth context for a bearer token: a JWT access token, or an API key
+    // when it carries API_KEY_PREFIX. Either way the account must still be active.
+    pub async fn authenticate(&self, token: &str) -> Result<AuthContext, JwtError> {
+        if !is_api_key(token) {
+            let claims = self.validate_token(token).await?;
+            self.ensure_account_active(&claims).await?;
+            return Ok(AuthContext::from(claims));
+        }
+
+        let Some(api_key_repository) = &self.api_key_repository else {
+            return Err(JwtError::InvalidToken(
+                "API keys are not enabled".to_string(),
+            ));
+        };
+        let owner = api_key_repository
+            .find_by_hash(&hash_confirmation_token(token))
+            .await
+            .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))?
+            .ok_or_else(|| JwtError::InvalidToken("Unknown API key".to_string()))?;
+        if owner.revoked_at.is_some() {
+            return Err(JwtError::BlacklistedToken);
+        }
+        if owner
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= self.clock.now())
+        {
+            return Err(JwtError::ExpiredToken);
+        }
+        self.ensure_user_active(owner.user_id).await?;
+
+        // Keys only get their scopes, never the owner's roles, so an admin's
+        // key can't reach the role-gated admin routes
+        Ok(AuthContext {
+            user_id: owner.user_id,
+            email: owner.email,
+            roles: Vec::new(),
+            jti: owner.key_id.to_string(),
+            session_id: None,
+            token_type: TokenType::ApiKey,
+            scopes: owner.scopes,
+        })
+    }
+
+    // Stores a new key for the user and returns it with its plaintext, which is
+    // not kept anywhere
+    pub async fn create_api_key(
+        &self,
+        user_id: Uuid,
+        name: Option<&str>,
+        scopes: &[String],
+        expires_in: Option<time::Duration>,
+    ) -> Result<(ApiKey, String), JwtError> {
+        let Some(api_key_repository) = &self.api_key_repository else {
+            return Err(JwtError::TokenCreationError(
+                "API keys are not enabled".to_string(),
+            ));
+        };
+        let key = generate_api_key();
+        let expires_at = expires_in.map(|ttl| self.clock.now() + ttl);
+
+        let api_key = api_key_repository
+            .create(
+                user_id,
+                name,
+                &hash_confirmation_token(&key),
+                scopes,
+                expires_at,
+            )
+            .await
+            .map_err(|e| JwtError::TokenCreationError(format!("Database error: {}", e)))?;
+        Ok((api_key, key))
+    }
+
+    // False if the user has no unrevoked key `id`
+    pub async fn revoke_api_key(&self, user_id: Uuid, id: Uuid) -> Result<bool, JwtError> {
+        let Some(api_key_repository) = &self.api_key_repository else {
+            return Ok(false);
+        };
+        api_key_repository
+            .revoke(user_id, id)
+            .await
+            .map_err(|e| JwtError::InvalidToken(format!("Database error: {}", e)))
     }
 
     // Clean up expired blacklisted tokens (maintenance task)
     pub async fn cleanup_expired_blacklisted_tokens(&self) -> Result<usize, JwtError> {
-        let now = OffsetDateTime::now_utc();
+        let now = self.clock.now();
         self.blacklist_repository
             .cleanup_expired_tokens(now)
             .await
@@ -351,10 +1356,226 @@ impl JwtService {
     }
 }
 
+// JWT_EXPOSE_JTI=true adds the access token's jti to login and refresh responses
+// The blacklist row for a token, kept until the token would have expired anyway
+fn blacklist_entry(claims: Claims) -> Result<BlacklistedToken, JwtError> {
+    let expires_at = OffsetDateTime::from_unix_timestamp(claims.exp as i64)
+        .map_err(|_| JwtError::InvalidClaims("Invalid expiration time".to_string()))?;
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| JwtError::InvalidClaims("Invalid user ID".to_string()))?;
+
+    Ok(BlacklistedToken::new(
+        claims.jti,
+        user_id,
+        claims.token_type,
+        expires_at,
+    ))
+}
+
+pub fn get_expose_jti() -> bool {
+    std::env::var("JWT_EXPOSE_JTI")
+        .map(|value| value.eq_ignore_ascii_case("true") || value == "1")
+        .unwrap_or(false)
+}
+
+// REMEMBER_ME_TTL_SECS, default 30 days; zero or unparseable values fall back to the default
+pub fn get_remember_me_ttl() -> time::Duration {
+    std::env::var("REMEMBER_ME_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(time::Duration::seconds)
+        .unwrap_or(DEFAULT_REMEMBER_ME_TTL)
+}
+
+// REFRESH_TOKEN_IDLE_TIMEOUT_SECS; unset, zero or unparseable values disable the idle timeout
+pub fn get_refresh_idle_timeout() -> Option<time::Duration> {
+    std::env::var("REFRESH_TOKEN_IDLE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(time::Duration::seconds)
+}
+
+// REFRESH_TOKEN_BINDING=off|user_agent|ip_subnet, default off; unknown values leave it off
+pub fn get_refresh_token_binding() -> RefreshTokenBinding {
+    let Ok(value) = std::env::var("REFRESH_TOKEN_BINDING") else {
+        return RefreshTokenBinding::Off;
+    };
+
+    RefreshTokenBinding::parse(&value).unwrap_or_else(|| {
+        warn!(value, "Ignoring unknown REFRESH_TOKEN_BINDING");
+        RefreshTokenBinding::Off
+    })
+}
+
+// ACCESS_TOKEN_ROLE_TTLS=role:secs,role:secs, e.g. admin:300; malformed entries are skipped
+pub fn get_role_ttl_overrides() -> HashMap<String, time::Duration> {
+    let Ok(value) = std::env::var("ACCESS_TOKEN_ROLE_TTLS") else {
+        return HashMap::new();
+    };
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| {
+            let parsed = entry.split_once(':').and_then(|(role, secs)| {
+                let secs = secs.trim().parse::<i64>().ok().filter(|secs| *secs > 0)?;
+                let role = role.trim();
+                (!role.is_empty()).then(|| (role.to_string(), time::Duration::seconds(secs)))
+            });
+            if parsed.is_none() {
+                warn!(entry, "Ignoring malformed ACCESS_TOKEN_ROLE_TTLS entry");
+            }
+            parsed
+        })
+        .collect()
+}
+
+// JWT_LEEWAY_SECS, default 60; zero turns the leeway off, unparseable values fall back to the default
+pub fn get_jwt_leeway() -> u64 {
+    std::env::var("JWT_LEEWAY_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_LEEWAY_SECS)
+}
+
+// JWT_AUDIENCE; unset or empty accepts tokens for any client
+pub fn get_jwt_audience() -> Option<String> {
+    std::env::var("JWT_AUDIENCE")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
 // Helper function to get JWT secret from environment
 pub fn get_jwt_secret() -> String {
     std::env::var("JWT_SECRET").unwrap_or_else(|_| {
         eprintln!("Warning: JWT_SECRET not set, using default (not secure for production!)");
-        "your-256-bit-secret-for-development-only-change-in-production".to_string()
+        DEVELOPMENT_JWT_SECRET.to_string()
     })
 }
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ConfigurationError {
+    #[error("JWT_SECRET must be set unless APP_ENV is development")]
+    MissingJwtSecret,
+    #[error("JWT_SECRET is a published placeholder; generate a random secret")]
+    DefaultJwtSecret,
+    #[error("JWT_SECRET must be at least {MIN_JWT_SECRET_BYTES} bytes for HS256")]
+    JwtSecretTooShort,
+    #[error(
+        "JWT_SIGNING_KEYS secret of kid {0:?} is a published placeholder; generate a random secret"
+    )]
+    DefaultSigningKeySecret(String),
+    #[error(
+        "JWT_SIGNING_KEYS secret of kid {0:?} must be at least {MIN_JWT_SECRET_BYTES} bytes for HS256"
+    )]
+    SigningKeySecretTooShort(String),
+    #[error("SIGNING_KEY_ENCRYPTION_KEY must be set unless APP_ENV is development")]
+    MissingSigningKeyEncryptionKey,
+    #[error("SIGNING_KEY_ENCRYPTION_KEY must be at least {MIN_JWT_SECRET_BYTES} bytes")]
+    SigningKeyEncryptionKeyTooShort,
+}
+
+// The secret JWT_SECRET should hold for `environment`. Development keeps the
+// built-in fallback and only warns; anywhere else the secret has to be set,
+// long enough, and not one of the placeholders anyone can read in this repo.
+pub fn resolve_jwt_secret(
+    secret: Option<String>,
+    environment: AppEnvironment,
+) -> Result<String, ConfigurationError> {
+    let secret = secret.filter(|secret| !secret.is_empty());
+    if !environment.is_production() {
+        let Some(secret) = secret else {
+            eprintln!("Warning: JWT_SECRET not set, using default (not secure for production!)");
+            return Ok(DEVELOPMENT_JWT_SECRET.to_string());
+        };
+        if secret.len() < MIN_JWT_SECRET_BYTES {
+            eprintln!(
+                "Warning: JWT_SECRET is shorter than {} bytes (refused outside development)",
+                MIN_JWT_SECRET_BYTES
+            );
+        }
+        return Ok(secret);
+    }
+
+    let secret = secret.ok_or(ConfigurationError::MissingJwtSecret)?;
+    if secret == DEVELOPMENT_JWT_SECRET || secret == EXAMPLE_JWT_SECRET {
+        return Err(ConfigurationError::DefaultJwtSecret);
+    }
+    if secret.len() < MIN_JWT_SECRET_BYTES {
+        return Err(ConfigurationError::JwtSecretTooShort);
+    }
+    Ok(secret)
+}
+
+// Checked once at server startup, before any token is signed
+pub fn load_jwt_secret() -> Result<String, ConfigurationError> {
+    resolve_jwt_secret(std::env::var("JWT_SECRET").ok(), AppEnvironment::from_env())
+}
+
+// Extra signing keys next to the JWT_SECRET one, for rotating without logging
+// everybody out
+#[derive(Debug, Clone, Default)]
+pub struct JwtKeyConfig {
+    pub keys: Vec<(String, String)>,
+    pub active: Option<String>,
+}
+
+impl JwtKeyConfig {
+    // JWT_SIGNING_KEYS=kid:secret,kid:secret and JWT_ACTIVE_KID; both optional
+    pub fn from_env() -> Result<Self, JwtKeyError> {
+        let keys = match std::env::var("JWT_SIGNING_KEYS") {
+            Ok(value) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| match entry.split_once(':') {
+                    Some((kid, secret)) if !kid.trim().is_empty() && !secret.is_empty() => {
+                        Ok((kid.trim().to_string(), secret.to_string()))
+                    }
+                    _ => Err(JwtKeyError::InvalidConfig),
+                })
+                .collect::<Result<_, _>>()?,
+            Err(_) => Vec::new(),
+        };
+        let active = std::env::var("JWT_ACTIVE_KID")
+            .ok()
+            .map(|kid| kid.trim().to_string())
+            .filter(|kid| !kid.is_empty());
+
+        Ok(Self { keys, active })
+    }
+
+    // Holds every secret to the rules of JWT_SECRET: outside development a
+    // placeholder or short secret is refused, in development only warned about
+    pub fn validate(&self, environment: AppEnvironment) -> Result<(), ConfigurationError> {
+        for (kid, secret) in &self.keys {
+            let error = if secret == DEVELOPMENT_JWT_SECRET || secret == EXAMPLE_JWT_SECRET {
+                ConfigurationError::DefaultSigningKeySecret(kid.clone())
+            } else if secret.len() < MIN_JWT_SECRET_BYTES {
+                ConfigurationError::SigningKeySecretTooShort(kid.clone())
+            } else {
+                continue;
+            };
+            if environment.is_production() {
+                return Err(error);
+            }
+            eprintln!("Warning: {} (refused outside development)", error);
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, service: &JwtService) -> Result<(), JwtKeyError> {
+        for (kid, secret) in &self.keys {
+            service.add_key(kid, secret)?;
+        }
+        if let Some(active) = &self.active {
+            service.set_active_key(active)?;
+        }
+        Ok(())
+    }
+}
@@ -1,8 +1,14 @@
 pub mod auth_service;
+pub mod captcha_service;
+pub mod email_code_service;
 pub mod email_service;
 pub mod jwt_service;
+pub mod oauth_service;
+pub mod otp_service;
 pub mod project_service;
 pub mod secure_login_service;
+pub mod sms_service;
 pub mod task_service;
 pub mod time_entry_service;
+pub mod trusted_device_service;
 pub mod user_service;
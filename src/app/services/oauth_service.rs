@@ -0,0 +1,409 @@
+use crate::app::hashing::Argon2Params;
+use crate::app::models::oauth::{GITHUB_PROVIDER, GOOGLE_PROVIDER, OAuthError, OAuthUserInfo};
+use crate::app::models::password_reset::PasswordResetToken;
+use crate::app::models::user::User;
+use crate::app::repositories::oauth_identity_repository::OAuthIdentityRepository;
+use crate::app::repositories::user_repository::UserRepository;
+use async_trait::async_trait;
+use reqwest::Url;
+use serde::Deserialize;
+use std::sync::Arc;
+
+const GOOGLE_AUTHORIZE_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const GOOGLE_USERINFO_URL: &str = "https://openidconnect.googleapis.com/v1/userinfo";
+const GOOGLE_SCOPES: &str = "openid email profile";
+
+const GITHUB_AUTHORIZE_URL: &str = "https://github.com/login/oauth/authorize";
+const GITHUB_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+const GITHUB_USER_URL: &str = "https://api.github.com/user";
+const GITHUB_EMAILS_URL: &str = "https://api.github.com/user/emails";
+const GITHUB_SCOPES: &str = "read:user user:email";
+
+// An OAuth2 / OIDC identity provider
+#[async_trait]
+pub trait OAuthProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Where to send the browser to ask for consent
+    fn authorization_url(&self, state: &str) -> String;
+
+    // Exchange an authorization code for the user's identity
+    async fn exchange_code(&self, code: &str) -> Result<OAuthUserInfo, OAuthError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct GoogleOAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+}
+
+impl GoogleOAuthConfig {
+    // GOOGLE_CLIENT_ID, GOOGLE_CLIENT_SECRET and GOOGLE_REDIRECT_URL; None unless all are set
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        Some(Self {
+            client_id: var("GOOGLE_CLIENT_ID")?,
+            client_secret: var("GOOGLE_CLIENT_SECRET")?,
+            redirect_url: var("GOOGLE_REDIRECT_URL")?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct GoogleTokenResponse {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct GoogleUserInfo {
+    sub: String,
+    email: String,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+}
+
+pub struct GoogleOAuthProvider {
+    config: GoogleOAuthConfig,
+    http: reqwest::Client,
+}
+
+impl GoogleOAuthProvider {
+    pub fn new(config: GoogleOAuthConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for GoogleOAuthProvider {
+    fn name(&self) -> &'static str {
+        GOOGLE_PROVIDER
+    }
+
+    fn authorization_url(&self, state: &str) -> String {
+        Url::parse_with_params(
+            GOOGLE_AUTHORIZE_URL,
+            &[
+                ("client_id", self.config.client_id.as_str()),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("response_type", "code"),
+                ("scope", GOOGLE_SCOPES),
+                ("state", state),
+            ],
+        )
+        .expect("Google authorize URL is valid")
+        .to_string()
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<OAuthUserInfo, OAuthError> {
+        let provider_error = |e: reqwest::Error| OAuthError::Provider(e.to_string());
+
+        let token: GoogleTokenResponse = self
+            .http
+            .post(GOOGLE_TOKEN_URL)
+            .form(&[
+                ("code", code),
+                ("client_id", self.config.client_id.as_str()),
+                ("client_secret", self.config.client_secret.as_str()),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("grant_type", "authorization_code"),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+
+        let user_info: GoogleUserInfo = self
+            .http
+            .get(GOOGLE_USERINFO_URL)
+            .bearer_auth(&token.access_token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+
+        Ok(OAuthUserInfo {
+            subject: user_info.sub,
+            email: user_info.email,
+            email_verified: user_info.email_verified,
+            name: user_info.name,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GitHubOAuthConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    // Defaults to the callback URL registered with the GitHub app
+    pub redirect_url: Option<String>,
+}
+
+impl GitHubOAuthConfig {
+    // GITHUB_CLIENT_ID and GITHUB_CLIENT_SECRET, None unless both are set;
+    // GITHUB_REDIRECT_URL is optional
+    pub fn from_env() -> Option<Self> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        Some(Self {
+            client_id: var("GITHUB_CLIENT_ID")?,
+            client_secret: var("GITHUB_CLIENT_SECRET")?,
+            redirect_url: var("GITHUB_REDIRECT_URL"),
+        })
+    }
+}
+
+// GitHub answers a failed code exchange with 200 and an `error` field
+#[derive(Deserialize)]
+struct GitHubTokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    id: u64,
+    login: String,
+    name: Option<String>,
+}
+
+// One entry of GET /user/emails
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubEmail {
+    pub email: String,
+    pub primary: bool,
+    pub verified: bool,
+}
+
+// The account's primary address. The public profile email may be missing or
+// unverified, so it is never used; whether this one is verified is left to
+// OAuthService, which refuses to link or create on an unverified address.
+pub fn primary_github_email(emails: &[GitHubEmail]) -> Option<&GitHubEmail> {
+    emails.iter().find(|email| email.primary)
+}
+
+pub struct GitHubOAuthProvider {
+    config: GitHubOAuthConfig,
+    http: reqwest::Client,
+}
+
+impl GitHubOAuthProvider {
+    pub fn new(config: GitHubOAuthConfig) -> Self {
+        Self {
+            config,
+            // The GitHub API rejects requests without a User-Agent
+            http: reqwest::Client::builder()
+                .user_agent("chronos")
+                .build()
+                .expect("HTTP client builds"),
+        }
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        access_token: &str,
+    ) -> Result<T, reqwest::Error> {
+        self.http
+            .get(url)
+            .bearer_auth(access_token)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for GitHubOAuthProvider {
+    fn name(&self) -> &'static str {
+        GITHUB_PROVIDER
+    }
+
+    fn authorization_url(&self, state: &str) -> String {
+        let mut params = vec![
+            ("client_id", self.config.client_id.as_str()),
+            ("scope", GITHUB_SCOPES),
+            ("state", state),
+        ];
+        if let Some(redirect_url) = &self.config.redirect_url {
+            params.push(("redirect_uri", redirect_url.as_str()));
+        }
+
+        Url::parse_with_params(GITHUB_AUTHORIZE_URL, &params)
+            .expect("GitHub authorize URL is valid")
+            .to_string()
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<OAuthUserInfo, OAuthError> {
+        let provider_error = |e: reqwest::Error| OAuthError::Provider(e.to_string());
+
+        let mut form = vec![
+            ("code", code),
+            ("client_id", self.config.client_id.as_str()),
+            ("client_secret", self.config.client_secret.as_str()),
+        ];
+        if let Some(redirect_url) = &self.config.redirect_url {
+            form.push(("redirect_uri", redirect_url.as_str()));
+        }
+
+        let token: GitHubTokenResponse = self
+            .http
+            .post(GITHUB_TOKEN_URL)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(provider_error)?
+            .json()
+            .await
+            .map_err(provider_error)?;
+        let access_token = match token {
+            GitHubTokenResponse {
+                access_token: Some(access_token),
+                ..
+            } => access_token,
+            GitHubTokenResponse {
+                error,
+                error_description,
+                ..
+            } => {
+                return Err(OAuthError::Provider(
+                    error_description
+                        .or(error)
+                        .unwrap_or_else(|| "No access token returned".to_string()),
+                ));
+            }
+        };
+
+        let user: GitHubUser = self
+            .get_json(GITHUB_USER_URL, &access_token)
+            .await
+            .map_err(provider_error)?;
+        let emails: Vec<GitHubEmail> = self
+            .get_json(GITHUB_EMAILS_URL, &access_token)
+            .await
+            .map_err(provider_error)?;
+        let primary = primary_github_email(&emails);
+
+        Ok(OAuthUserInfo {
+            subject: user.id.to_string(),
+            email: primary.map(|email| email.email.clone()).unwrap_or_default(),
+            email_verified: primary.is_some_and(|email| email.verified),
+            name: user.name.or(Some(user.login)),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct OAuthService {
+    provider: Arc<dyn OAuthProvider>,
+    user_repository: UserRepository,
+    identity_repository: OAuthIdentityRepository,
+    argon2_params: Argon2Params,
+}
+
+impl OAuthService {
+    pub fn new(
+        provider: Arc<dyn OAuthProvider>,
+        user_repository: UserRepository,
+        identity_repository: OAuthIdentityRepository,
+    ) -> Self {
+        Self {
+            provider,
+            user_repository,
+            identity_repository,
+            argon2_params: Argon2Params::default(),
+        }
+    }
+
+    pub fn with_argon2_params(mut self, params: Argon2Params) -> Self {
+        self.argon2_params = params;
+        self
+    }
+
+    pub fn provider_name(&self) -> &'static str {
+        self.provider.name()
+    }
+
+    pub fn authorization_url(&self, state: &str) -> String {
+        self.provider.authorization_url(state)
+    }
+
+    // Exchange the code and resolve it to a Chronos user
+    pub async fn complete_login(&self, code: &str) -> Result<User, OAuthError> {
+        let user_info = self.provider.exchange_code(code).await?;
+        self.find_or_create_user(&user_info).await
+    }
+
+    // Resolve a provider identity to a user: an existing link wins, then a
+    // verified email matching an existing account is linked, otherwise a new
+    // account is created
+    pub async fn find_or_create_user(&self, user_info: &OAuthUserInfo) -> Result<User, OAuthError> {
+        let provider = self.provider.name();
+
+        let user = if let Some(identity) = self
+            .identity_repository
+            .find_by_subject(provider, &user_info.subject)
+            .await?
+        {
+            self.user_repository
+                .find_by_id(identity.user_id)
+                .await?
+                .ok_or(OAuthError::AccountUnavailable)?
+        } else {
+            // Never link or create on an address the provider has not verified
+            if !user_info.email_verified {
+                return Err(OAuthError::EmailNotVerified);
+            }
+
+            let user = match self.user_repository.find_by_email(&user_info.email).await? {
+                Some(user) => user,
+                None => self.create_user(user_info).await?,
+            };
+
+            self.identity_repository
+                .create(user.id, provider, &user_info.subject, &user_info.email)
+                .await?;
+            user
+        };
+
+        if self.user_repository.is_active(user.id).await? != Some(true) {
+            return Err(OAuthError::AccountUnavailable);
+        }
+
+        Ok(user)
+    }
+
+    // OAuth-only accounts get a random password; the owner can set a real one
+    // through the password reset flow
+    async fn create_user(&self, user_info: &OAuthUserInfo) -> Result<User, OAuthError> {
+        let password = PasswordResetToken::generate_secure_token();
+        let user = User::new_async(
+            user_info.name.clone(),
+            user_info.email.clone(),
+            &password,
+            &self.argon2_params,
+        )
+        .await
+        .map_err(|e| OAuthError::UserCreation(e.to_string()))?;
+
+        Ok(self.user_repository.create(&user).await?)
+    }
+}
@@ -0,0 +1,232 @@
+use crate::app::hashing::{self, Argon2Params};
+use crate::app::models::otp::{OtpChallenge, OtpError, OtpPurpose};
+use crate::app::repositories::otp_challenge_repository::OtpChallengeRepository;
+use crate::app::repositories::user_repository::UserRepository;
+use crate::app::services::sms_service::{HttpSmsSender, SmsConfig, SmsSender};
+use std::sync::Arc;
+use tracing::warn;
+use uuid::Uuid;
+
+const DEFAULT_OTP_TTL: time::Duration = time::Duration::minutes(5);
+const DEFAULT_MAX_OTP_ATTEMPTS: i32 = 5;
+
+// Sends one-time codes by SMS and checks them: as a second factor when signing
+// in, and to prove a user owns the phone number they added
+#[derive(Clone)]
+pub struct OtpService {
+    sender: Arc<dyn SmsSender>,
+    challenge_repository: OtpChallengeRepository,
+    user_repository: UserRepository,
+    argon2_params: Argon2Params,
+    ttl: time::Duration,
+    max_attempts: i32,
+}
+
+impl OtpService {
+    pub fn new(
+        sender: Arc<dyn SmsSender>,
+        challenge_repository: OtpChallengeRepository,
+        user_repository: UserRepository,
+    ) -> Self {
+        Self {
+            sender,
+            challenge_repository,
+            user_repository,
+            argon2_params: Argon2Params::default(),
+            ttl: DEFAULT_OTP_TTL,
+            max_attempts: DEFAULT_MAX_OTP_ATTEMPTS,
+        }
+    }
+
+    pub fn from_config(
+        config: SmsConfig,
+        challenge_repository: OtpChallengeRepository,
+        user_repository: UserRepository,
+    ) -> Self {
+        Self::new(
+            Arc::new(HttpSmsSender::new(config)),
+            challenge_repository,
+            user_repository,
+        )
+    }
+
+    pub fn with_argon2_params(mut self, params: Argon2Params) -> Self {
+        self.argon2_params = params;
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: time::Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    // Wrong codes allowed per challenge; the last one invalidates it
+    pub fn with_max_attempts(mut self, max_attempts: i32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn ttl(&self) -> time::Duration {
+        self.ttl
+    }
+
+    // Texts a sign-in code to the user's verified number. The login options are
+    // kept with the challenge until the code comes back.
+    pub async fn start_login(
+        &self,
+        user_id: Uuid,
+        phone_number: &str,
+        remember_me: bool,
+        device_name: Option<String>,
+        client_id: Option<String>,
+    ) -> Result<OtpChallenge, OtpError> {
+        let code = OtpChallenge::generate_code();
+        let challenge = OtpChallenge::new(
+            user_id,
+            OtpPurpose::Login,
+            phone_number,
+            self.argon2_params.hash_async(&code).await?,
+            self.ttl,
+        )
+        .with_login_options(remember_me, device_name, client_id);
+
+        let body = format!(
+            "Your Chronos sign-in code is {}. It expires in {} minutes. Don't share it with anyone.",
+            code,
+            self.ttl.whole_minutes()
+        );
+        self.issue(&challenge, OtpPurpose::Login, &body).await?;
+        Ok(challenge)
+    }
+
+    // The login challenge, if `code` is right for it
+    pub async fn verify_login(
+        &self,
+        challenge_id: Uuid,
+        code: &str,
+    ) -> Result<OtpChallenge, OtpError> {
+        let challenge = self
+            .challenge_repository
+            .find_open(challenge_id, OtpPurpose::Login)
+            .await?
+            .ok_or(OtpError::InvalidChallenge)?;
+        self.check(challenge, code).await
+    }
+
+    // Stores the number unverified, which also turns SMS sign-in codes off until
+    // a code sent to the new number is confirmed
+    pub async fn start_phone_verification(
+        &self,
+        user_id: Uuid,
+        phone_number: &str,
+    ) -> Result<OtpChallenge, OtpError> {
+        self.user_repository
+            .set_phone_number(user_id, phone_number)
+            .await?;
+
+        let code = OtpChallenge::generate_code();
+        let challenge = OtpChallenge::new(
+            user_id,
+            OtpPurpose::PhoneVerification,
+            phone_number,
+            self.argon2_params.hash_async(&code).await?,
+            self.ttl,
+        );
+
+        let body = format!(
+            "Your Chronos verification code is {}. It expires in {} minutes.",
+            code,
+            self.ttl.whole_minutes()
+        );
+        self.issue(&challenge, OtpPurpose::PhoneVerification, &body)
+            .await?;
+        Ok(challenge)
+    }
+
+    // Marks the number verified; returns it
+    pub async fn confirm_phone(&self, user_id: Uuid, code: &str) -> Result<String, OtpError> {
+        let challenge = self
+            .challenge_repository
+            .find_open_for_user(user_id, OtpPurpose::PhoneVerification)
+            .await?
+            .ok_or(OtpError::NoPendingVerification)?;
+        let challenge = self.check(challenge, code).await?;
+
+        // The number may have been replaced or removed since the code was sent
+        if !self
+            .user_repository
+            .mark_phone_verified(user_id, &challenge.phone_number)
+            .await?
+        {
+            return Err(OtpError::NoPendingVerification);
+        }
+        Ok(challenge.phone_number)
+    }
+
+    // Turns SMS sign-in codes off; false if there was no number
+    pub async fn remove_phone(&self, user_id: Uuid) -> Result<bool, OtpError> {
+        for purpose in [OtpPurpose::Login, OtpPurpose::PhoneVerification] {
+            self.challenge_repository
+                .consume_all_for_user(user_id, purpose)
+                .await?;
+        }
+        Ok(self.user_repository.clear_phone_number(user_id).await?)
+    }
+
+    // The user's number and whether it is verified
+    pub async fn phone_number(&self, user_id: Uuid) -> Result<Option<(String, bool)>, OtpError> {
+        Ok(self.user_repository.phone_number(user_id).await?)
+    }
+
+    // Replaces any open challenge for the same purpose and sends the code. A
+    // challenge whose SMS didn't go out is closed straight away.
+    async fn issue(
+        &self,
+        challenge: &OtpChallenge,
+        purpose: OtpPurpose,
+        body: &str,
+    ) -> Result<(), OtpError> {
+        self.challenge_repository
+            .consume_all_for_user(challenge.user_id, purpose)
+            .await?;
+        self.challenge_repository.create(challenge).await?;
+
+        if let Err(e) = self.sender.send(&challenge.phone_number, body).await {
+            warn!(error = %e, "Failed to send SMS code");
+            if let Err(e) = self.challenge_repository.consume(challenge.id).await {
+                warn!(error = %e, "Failed to close undelivered OTP challenge");
+            }
+            return Err(OtpError::Delivery(e.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn check(&self, challenge: OtpChallenge, code: &str) -> Result<OtpChallenge, OtpError> {
+        if challenge.is_expired() {
+            self.challenge_repository.consume(challenge.id).await?;
+            return Err(OtpError::Expired);
+        }
+
+        let Some(attempts) = self
+            .challenge_repository
+            .reserve_attempt(challenge.id, self.max_attempts)
+            .await?
+        else {
+            return Err(OtpError::TooManyAttempts);
+        };
+
+        if hashing::verify_async(&challenge.code_hash, code.trim()).await? {
+            // Losing the race to a concurrent correct guess counts as already used
+            if !self.challenge_repository.consume(challenge.id).await? {
+                return Err(OtpError::InvalidChallenge);
+            }
+            return Ok(challenge);
+        }
+
+        if attempts >= self.max_attempts {
+            self.challenge_repository.consume(challenge.id).await?;
+            return Err(OtpError::TooManyAttempts);
+        }
+        Err(OtpError::InvalidCode)
+    }
+}
@@ -1,53 +1,235 @@
-use crate::app::models::auth::AuthError;
+use crate::app::clock::{Clock, SystemClock};
+use crate::app::metrics;
+use crate::app::models::auth::AuthenticationError;
+use crate::app::models::common::Pagination;
 use crate::app::models::jwt::{LoginRequest, LoginResponse};
-use crate::app::models::login_attempt::{AccountLockout, LoginAttempt};
-use crate::app::repositories::login_attempt_repository::{
-    AccountLockoutRepository, LoginAttemptRepository,
+use crate::app::models::login_attempt::{
+    AccountLockout, LockoutConfig, LockoutPolicy, LoginAttempt, TarpitConfig, TokenOrigin,
 };
-use crate::app::services::auth_service::AuthService;
+use crate::app::models::otp::{EmailCodeLoginRequest, OtpChallenge, OtpError};
+use crate::app::models::password_reset::PasswordResetToken;
+use crate::app::models::session::normalize_device_name;
+use crate::app::models::user::{User, UserStatus};
+use crate::app::repositories::client_repository::ClientRepository;
+use crate::app::repositories::login_attempt_repository::{AccountLockoutStore, LoginAttemptStore};
+use crate::app::services::auth_service::{AuthService, hash_confirmation_token};
+use crate::app::services::email_code_service::EmailCodeService;
 use crate::app::services::jwt_service::JwtService;
+use crate::app::services::otp_service::OtpService;
+use crate::app::services::trusted_device_service::TrustedDeviceService;
+use crate::app::telemetry::hash_user_id;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use time::OffsetDateTime;
+use tracing::{Span, field, instrument};
 use uuid::Uuid;
 
+const UNLOCK_TOKEN_TTL: time::Duration = time::Duration::hours(1);
+
+// PASSWORD_MAX_AGE_DAYS; unset, zero or unparseable values let passwords live forever
+pub fn get_password_max_age() -> Option<time::Duration> {
+    std::env::var("PASSWORD_MAX_AGE_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .map(time::Duration::days)
+}
+
 pub struct SecureLoginService {
     auth_service: AuthService,
     jwt_service: JwtService,
-    login_attempt_repository: LoginAttemptRepository,
-    account_lockout_repository: AccountLockoutRepository,
+    login_attempt_repository: Arc<dyn LoginAttemptStore>,
+    account_lockout_repository: Arc<dyn AccountLockoutStore>,
+    lockout_config: LockoutConfig,
+    tarpit_config: TarpitConfig,
+    otp_service: Option<OtpService>,
+    email_code_service: Option<EmailCodeService>,
+    trusted_device_service: Option<TrustedDeviceService>,
+    password_max_age: Option<time::Duration>,
+    client_repository: Option<ClientRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+// Either the tokens, or the SMS challenge that has to be answered first
+#[derive(Debug)]
+pub enum LoginOutcome {
+    Authenticated(LoginResponse),
+    ChallengeRequired(OtpChallenge),
 }
 
 impl SecureLoginService {
     pub fn new(
         auth_service: AuthService,
         jwt_service: JwtService,
-        login_attempt_repository: LoginAttemptRepository,
-        account_lockout_repository: AccountLockoutRepository,
+        login_attempt_repository: impl LoginAttemptStore + 'static,
+        account_lockout_repository: impl AccountLockoutStore + 'static,
     ) -> Self {
         Self {
             auth_service,
             jwt_service,
-            login_attempt_repository,
-            account_lockout_repository,
+            login_attempt_repository: Arc::new(login_attempt_repository),
+            account_lockout_repository: Arc::new(account_lockout_repository),
+            lockout_config: LockoutConfig::default(),
+            tarpit_config: TarpitConfig::disabled(),
+            otp_service: None,
+            email_code_service: None,
+            trusted_device_service: None,
+            password_max_age: None,
+            client_repository: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
+    pub fn with_lockout_config(mut self, lockout_config: LockoutConfig) -> Self {
+        self.lockout_config = lockout_config;
+        self
+    }
+
+    // Keeps the threshold and window, only changing how long lockouts last
+    pub fn with_lockout_policy(mut self, lockout_policy: LockoutPolicy) -> Self {
+        self.lockout_config.lockout_duration = lockout_policy;
+        self
+    }
+
+    // Failed logins are answered immediately unless this is set
+    pub fn with_tarpit_config(mut self, tarpit_config: TarpitConfig) -> Self {
+        self.tarpit_config = tarpit_config;
+        self
+    }
+
+    // Accounts with a verified phone number then get an SMS code after their password
+    pub fn with_otp_service(mut self, otp_service: OtpService) -> Self {
+        self.otp_service = Some(otp_service);
+        self
+    }
+
+    // Lets users sign in with a code mailed to them instead of their password
+    pub fn with_email_code_service(mut self, email_code_service: EmailCodeService) -> Self {
+        self.email_code_service = Some(email_code_service);
+        self
+    }
+
+    pub fn email_code_service(&self) -> Option<&EmailCodeService> {
+        self.email_code_service.as_ref()
+    }
+
+    // Lets devices that answered an SMS code skip it on later logins
+    pub fn with_trusted_device_service(
+        mut self,
+        trusted_device_service: TrustedDeviceService,
+    ) -> Self {
+        self.trusted_device_service = Some(trusted_device_service);
+        self
+    }
+
+    pub fn trusted_device_service(&self) -> Option<&TrustedDeviceService> {
+        self.trusted_device_service.as_ref()
+    }
+
+    // Logins with an older password only get tokens for changing it
+    pub fn with_password_max_age(mut self, max_age: Option<time::Duration>) -> Self {
+        self.password_max_age = max_age;
+        self
+    }
+
+    // Lets logins name a registered client_id; without it every client_id is unknown
+    pub fn with_client_repository(mut self, client_repository: ClientRepository) -> Self {
+        self.client_repository = Some(client_repository);
+        self
+    }
+
+    // Lockout and rate limit windows are measured from `clock`
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    // For callers that can't ask for an SMS code: accounts that need one are refused
     pub async fn secure_login(
         &self,
         request: LoginRequest,
         ip_address: String,
         user_agent: Option<String>,
-    ) -> Result<LoginResponse, AuthError> {
-        let rate_limit_window = OffsetDateTime::now_utc() - time::Duration::minutes(15);
+    ) -> Result<LoginResponse, AuthenticationError> {
+        match self.login(request, ip_address, user_agent).await? {
+            LoginOutcome::Authenticated(response) => Ok(response),
+            LoginOutcome::ChallengeRequired(_) => {
+                // Not something a client of this method can fix
+                Err(AuthenticationError::Internal(
+                    "SMS verification code required".to_string(),
+                ))
+            }
+        }
+    }
+
+    pub async fn login(
+        &self,
+        request: LoginRequest,
+        ip_address: String,
+        user_agent: Option<String>,
+    ) -> Result<LoginOutcome, AuthenticationError> {
+        self.login_from_device(request, ip_address, user_agent, None)
+            .await
+    }
+
+    // As login; accounts that need an SMS code skip it when `trusted_device`
+    // is the token of one of their trusted devices
+    #[instrument(
+        name = "auth.login",
+        skip_all,
+        fields(ip = %ip_address, user_id = field::Empty, outcome = field::Empty)
+    )]
+    pub async fn login_from_device(
+        &self,
+        request: LoginRequest,
+        ip_address: String,
+        user_agent: Option<String>,
+        trusted_device: Option<&str>,
+    ) -> Result<LoginOutcome, AuthenticationError> {
+        let started = Instant::now();
+        let result = self
+            .attempt_login(request, ip_address, user_agent, trusted_device)
+            .await;
+
+        let span = Span::current();
+        match &result {
+            Ok(LoginOutcome::Authenticated(response)) => {
+                metrics::record_login(true, started.elapsed());
+                span.record("user_id", hash_user_id(response.user.id));
+                span.record("outcome", "success");
+            }
+            // Counted once the code is entered
+            Ok(LoginOutcome::ChallengeRequired(challenge)) => {
+                span.record("user_id", hash_user_id(challenge.user_id));
+                span.record("outcome", "challenge");
+            }
+            Err(_) => {
+                metrics::record_login(false, started.elapsed());
+                span.record("outcome", "failure");
+            }
+        }
+        result
+    }
+
+    async fn attempt_login(
+        &self,
+        request: LoginRequest,
+        ip_address: String,
+        user_agent: Option<String>,
+        trusted_device: Option<&str>,
+    ) -> Result<LoginOutcome, AuthenticationError> {
+        let identifier = request.login_identifier().to_string();
+        let rate_limit_window = self.clock.now() - time::Duration::minutes(15);
         let ip_failures = self
             .login_attempt_repository
             .count_failed_attempts_by_ip(&ip_address, rate_limit_window)
-            .await
-            .map_err(|e| AuthError::new(&format!("Database error: {}", e)))?;
+            .await?;
 
         if ip_failures >= 5 {
+            metrics::record_rate_limit_hit("login");
             let attempt = LoginAttempt::new_failure(
                 ip_address,
-                request.email.clone(),
+                identifier,
                 "IP rate limit exceeded".to_string(),
                 user_agent,
             );
@@ -56,17 +238,20 @@ impl SecureLoginService {
                 eprintln!("Failed to log login attempt: {}", e);
             }
 
-            return Err(AuthError::new(
-                "Too many failed login attempts. Please try again later.",
+            return Err(AuthenticationError::TooManyAttempts(
+                "Too many failed login attempts. Please try again later.".to_string(),
             ));
         }
 
-        let user = match self.auth_service.find_user_by_email(&request.email).await {
+        // Checked before the credentials, so a typo in client_id isn't a failed login
+        let audience = self.client_audience(request.client_id.as_deref()).await?;
+
+        let user = match self.auth_service.find_user_for_login(&identifier).await {
             Ok(Some(user)) => user,
             Ok(None) => {
                 let attempt = LoginAttempt::new_failure(
                     ip_address,
-                    request.email,
+                    identifier,
                     "Invalid credentials".to_string(),
                     user_agent,
                 );
@@ -74,27 +259,29 @@ impl SecureLoginService {
                 if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
                     eprintln!("Failed to log login attempt: {}", e);
                 }
+                self.hold_back(&attempt.ip_address, None).await;
 
-                return Err(AuthError::new("Invalid email or password"));
+                return Err(AuthenticationError::InvalidCredentials);
             }
             Err(e) => {
-                return Err(AuthError::new(&format!("Database error: {}", e)));
+                return Err(e.into());
             }
         };
 
-        // Step 3: Check account lockout (10 failed attempts, 30 min lockout)
+        // Step 3: Check account lockout (LockoutConfig threshold, escalating duration)
         if let Ok(Some(lockout)) = self
             .account_lockout_repository
             .get_active_lockout(user.id)
             .await
         {
-            if lockout.is_locked() {
+            if lockout.is_locked_at(self.clock.now()) {
                 let attempt = LoginAttempt::new_failure(
                     ip_address,
-                    request.email.clone(),
+                    user.email.clone(),
                     "Account locked".to_string(),
                     user_agent,
-                );
+                )
+                .for_user(user.id);
 
                 if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
                     eprintln!("Failed to log login attempt: {}", e);
@@ -104,89 +291,565 @@ impl SecureLoginService {
                     .locked_until
                     .format(&time::format_description::well_known::Rfc3339)
                     .unwrap_or("unknown time".to_string());
-                return Err(AuthError::new(&format!(
+                return Err(AuthenticationError::Locked(format!(
                     "Account is temporarily locked until {}. Please try again later.",
                     locked_until
                 )));
             }
         }
 
-        let password_valid = match user.verify_password(&request.password) {
+        let password_valid = match user.verify_password_async(&request.password).await {
             Ok(valid) => valid,
             Err(_) => {
                 let attempt = LoginAttempt::new_failure(
                     ip_address,
-                    request.email.clone(),
+                    user.email.clone(),
                     "Password verification error".to_string(),
                     user_agent,
-                );
+                )
+                .for_user(user.id);
 
                 if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
                     eprintln!("Failed to log login attempt: {}", e);
                 }
 
-                return Err(AuthError::new("Authentication failed"));
+                return Err(AuthenticationError::AuthenticationFailed);
             }
         };
 
         if !password_valid {
             let attempt = LoginAttempt::new_failure(
                 ip_address.clone(),
-                request.email.clone(),
+                user.email.clone(),
                 "Invalid password".to_string(),
                 user_agent,
-            );
+            )
+            .for_user(user.id);
 
             if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
                 eprintln!("Failed to log login attempt: {}", e);
             }
+            self.hold_back(&ip_address, Some(user.id)).await;
 
-            // Check if we need to lock the account (10 failed attempts)
-            let user_failure_window = OffsetDateTime::now_utc() - time::Duration::hours(1);
+            // Lock the account once the failures within the window pass the threshold
+            let user_failure_window = self.clock.now() - self.lockout_config.window;
             if let Ok(user_failures) = self
                 .login_attempt_repository
-                .count_failed_attempts_by_email(&request.email, user_failure_window)
+                .count_failures_since_last_success(user.id, user_failure_window)
                 .await
             {
-                if user_failures >= 9 {
-                    // This is the 10th failure, lock the account
-                    let lockout = AccountLockout::new(user.id, (user_failures + 1) as i32, 30);
-                    if let Err(e) = self
+                if user_failures > i64::from(self.lockout_config.max_attempts) {
+                    let lockout_count = self.next_lockout_count(user.id).await;
+                    let duration = self
+                        .lockout_config
+                        .lockout_duration
+                        .duration_for(lockout_count);
+                    let lockout = AccountLockout::escalated(
+                        user.id,
+                        user_failures as i32,
+                        lockout_count,
+                        duration,
+                    );
+                    // Only the failure that creates the lockout notifies; the ones
+                    // during it are turned away above before getting here
+                    match self
                         .account_lockout_repository
                         .create_lockout(&lockout)
                         .await
                     {
-                        eprintln!("Failed to create account lockout: {}", e);
+                        Ok(()) => self.notify_lockout(&user, duration).await,
+                        Err(e) => eprintln!("Failed to create account lockout: {}", e),
                     }
-                    return Err(AuthError::new(
-                        "Account has been temporarily locked due to too many failed login attempts. Please try again in 30 minutes.",
-                    ));
+                    metrics::record_account_lockout();
+                    return Err(AuthenticationError::AccountLocked {
+                        lockout_duration: duration,
+                    });
                 }
             }
 
-            return Err(AuthError::new("Invalid email or password"));
+            return Err(AuthenticationError::InvalidCredentials);
+        }
+
+        // Checked after the password so only the account holder learns it is disabled
+        match self.auth_service.user_status(user.id).await {
+            Ok(Some(UserStatus::Active)) => {}
+            Ok(_) => {
+                let attempt = LoginAttempt::new_failure(
+                    ip_address,
+                    user.email.clone(),
+                    "Account disabled".to_string(),
+                    user_agent,
+                )
+                .for_user(user.id);
+
+                if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
+                    eprintln!("Failed to log login attempt: {}", e);
+                }
+
+                return Err(AuthenticationError::AccountDisabled);
+            }
+            Err(e) => {
+                return Err(e.into());
+            }
+        }
+
+        self.auth_service
+            .upgrade_password_hash(&user, &request.password)
+            .await;
+
+        let device_name = request
+            .device_name
+            .as_deref()
+            .and_then(normalize_device_name);
+
+        self.finish_login(
+            &user,
+            ip_address,
+            user_agent,
+            request.remember_me,
+            device_name,
+            request.client_id,
+            audience,
+            trusted_device,
+        )
+        .await
+    }
+
+    // A verified phone means the tokens wait for the SMS code, unless the login
+    // comes from a trusted device. Without an SMS provider such accounts can't
+    // sign in rather than skipping the second factor.
+    #[allow(clippy::too_many_arguments)]
+    async fn finish_login(
+        &self,
+        user: &User,
+        ip_address: String,
+        user_agent: Option<String>,
+        remember_me: bool,
+        device_name: Option<String>,
+        client_id: Option<String>,
+        audience: Vec<String>,
+        trusted_device: Option<&str>,
+    ) -> Result<LoginOutcome, AuthenticationError> {
+        let phone_number = self.auth_service.verified_phone_number(user.id).await?;
+        let phone_number = match phone_number {
+            Some(_) if self.is_trusted_device(user.id, trusted_device).await => None,
+            phone_number => phone_number,
+        };
+        if let Some(phone_number) = phone_number {
+            let Some(otp_service) = &self.otp_service else {
+                return Err(AuthenticationError::SmsUnavailable);
+            };
+            return match otp_service
+                .start_login(user.id, &phone_number, remember_me, device_name, client_id)
+                .await
+            {
+                Ok(challenge) => Ok(LoginOutcome::ChallengeRequired(challenge)),
+                Err(e) => {
+                    eprintln!("Failed to start SMS login challenge: {}", e);
+                    Err(AuthenticationError::SmsUnavailable)
+                }
+            };
+        }
+
+        self.issue_tokens(
+            user,
+            ip_address,
+            user_agent,
+            remember_me,
+            device_name,
+            audience,
+        )
+        .await
+        .map(LoginOutcome::Authenticated)
+    }
+
+    // Second step of a login that needed an SMS code
+    #[instrument(
+        name = "auth.login_otp",
+        skip_all,
+        fields(ip = %ip_address, user_id = field::Empty, outcome = field::Empty)
+    )]
+    pub async fn complete_otp_login(
+        &self,
+        challenge_id: Uuid,
+        code: &str,
+        ip_address: String,
+        user_agent: Option<String>,
+    ) -> Result<LoginResponse, OtpError> {
+        let started = Instant::now();
+        let result = self
+            .attempt_otp_login(challenge_id, code, ip_address, user_agent)
+            .await;
+        metrics::record_login(result.is_ok(), started.elapsed());
+
+        let span = Span::current();
+        match &result {
+            Ok(response) => {
+                span.record("user_id", hash_user_id(response.user.id));
+                span.record("outcome", "success");
+            }
+            Err(_) => {
+                span.record("outcome", "failure");
+            }
+        }
+        result
+    }
+
+    async fn attempt_otp_login(
+        &self,
+        challenge_id: Uuid,
+        code: &str,
+        ip_address: String,
+        user_agent: Option<String>,
+    ) -> Result<LoginResponse, OtpError> {
+        let otp_service = self.otp_service.as_ref().ok_or(OtpError::NotConfigured)?;
+        let challenge = otp_service.verify_login(challenge_id, code).await?;
+
+        // The account may have been disabled while the code was on its way
+        let user = self
+            .auth_service
+            .find_user_by_id(challenge.user_id)
+            .await?
+            .ok_or(OtpError::AccountUnavailable)?;
+        if self.auth_service.user_status(user.id).await? != Some(UserStatus::Active) {
+            return Err(OtpError::AccountUnavailable);
+        }
+
+        // The client may have been removed while the code was on its way
+        let audience = self
+            .client_audience(challenge.client_id.as_deref())
+            .await
+            .map_err(|_| OtpError::AuthenticationFailed)?;
+        self.issue_tokens(
+            &user,
+            ip_address,
+            user_agent,
+            challenge.remember_me,
+            challenge.device_name,
+            audience,
+        )
+        .await
+        .map_err(|_| OtpError::AuthenticationFailed)
+    }
+
+    // Errors count as untrusted, so the SMS code is asked for
+    async fn is_trusted_device(&self, user_id: Uuid, token: Option<&str>) -> bool {
+        let (Some(service), Some(token)) = (&self.trusted_device_service, token) else {
+            return false;
+        };
+        match service.is_trusted(user_id, token).await {
+            Ok(trusted) => trusted,
+            Err(e) => {
+                eprintln!("Failed to check trusted device: {}", e);
+                false
+            }
+        }
+    }
+
+    // Mails a sign-in code if `email` belongs to an active account, and does
+    // nothing otherwise; callers must answer the same way for both
+    pub async fn request_email_code(&self, email: &str) -> Result<(), OtpError> {
+        let Some(email_code_service) = &self.email_code_service else {
+            return Err(OtpError::NotConfigured);
+        };
+        let Some(user) = self.auth_service.find_user_by_email(email).await? else {
+            return Ok(());
+        };
+        if self.auth_service.user_status(user.id).await? != Some(UserStatus::Active) {
+            return Ok(());
+        }
+
+        let code = email_code_service.issue(user.id).await?;
+        self.auth_service
+            .send_login_code_email(&user.email, &code, email_code_service.ttl())
+            .await
+            .map_err(|e| OtpError::Delivery(e.to_string()))
+    }
+
+    // Signs in with a mailed code in place of the password. Accounts with a
+    // verified phone still have to answer an SMS challenge afterwards.
+    #[instrument(
+        name = "auth.login_email_code",
+        skip_all,
+        fields(ip = %ip_address, user_id = field::Empty, outcome = field::Empty)
+    )]
+    pub async fn complete_email_code_login(
+        &self,
+        request: EmailCodeLoginRequest,
+        ip_address: String,
+        user_agent: Option<String>,
+        trusted_device: Option<&str>,
+    ) -> Result<LoginOutcome, OtpError> {
+        let started = Instant::now();
+        let result = self
+            .attempt_email_code_login(request, ip_address, user_agent, trusted_device)
+            .await;
+
+        let span = Span::current();
+        match &result {
+            Ok(LoginOutcome::Authenticated(response)) => {
+                metrics::record_login(true, started.elapsed());
+                span.record("user_id", hash_user_id(response.user.id));
+                span.record("outcome", "success");
+            }
+            Ok(LoginOutcome::ChallengeRequired(challenge)) => {
+                span.record("user_id", hash_user_id(challenge.user_id));
+                span.record("outcome", "challenge");
+            }
+            Err(_) => {
+                metrics::record_login(false, started.elapsed());
+                span.record("outcome", "failure");
+            }
+        }
+        result
+    }
+
+    async fn attempt_email_code_login(
+        &self,
+        request: EmailCodeLoginRequest,
+        ip_address: String,
+        user_agent: Option<String>,
+        trusted_device: Option<&str>,
+    ) -> Result<LoginOutcome, OtpError> {
+        let email_code_service = self
+            .email_code_service
+            .as_ref()
+            .ok_or(OtpError::NotConfigured)?;
+        let user = self
+            .auth_service
+            .find_user_by_email(&request.email)
+            .await?
+            .ok_or(OtpError::InvalidChallenge)?;
+
+        // Checked before the code, so a typo in client_id doesn't use it up
+        let audience = self
+            .client_audience(request.client_id.as_deref())
+            .await
+            .map_err(|_| OtpError::AuthenticationFailed)?;
+
+        if let Err(e) = email_code_service.verify(user.id, &request.code).await {
+            let attempt = LoginAttempt::new_failure(
+                ip_address,
+                user.email.clone(),
+                "Invalid email code".to_string(),
+                user_agent,
+            )
+            .for_user(user.id);
+            if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
+                eprintln!("Failed to log login attempt: {}", e);
+            }
+            return Err(e);
+        }
+
+        // A locked or disabled account can't get around that with a code
+        if self.auth_service.user_status(user.id).await? != Some(UserStatus::Active) {
+            return Err(OtpError::AccountUnavailable);
+        }
+        let lockout = self
+            .account_lockout_repository
+            .get_active_lockout(user.id)
+            .await;
+        if matches!(lockout, Ok(Some(lockout)) if lockout.is_locked_at(self.clock.now())) {
+            return Err(OtpError::AccountUnavailable);
+        }
+
+        let device_name = request
+            .device_name
+            .as_deref()
+            .and_then(normalize_device_name);
+        self.finish_login(
+            &user,
+            ip_address,
+            user_agent,
+            request.remember_me,
+            device_name,
+            request.client_id,
+            audience,
+            trusted_device,
+        )
+        .await
+        .map_err(|_| OtpError::AuthenticationFailed)
+    }
+
+    // Provider sign-ins skip the password, but not the rest of login: a locked
+    // account is refused and a verified phone still gets its SMS challenge
+    #[instrument(
+        name = "auth.login_oauth",
+        skip_all,
+        fields(ip = %ip_address, user_id = field::Empty, outcome = field::Empty)
+    )]
+    pub async fn complete_oauth_login(
+        &self,
+        user: &User,
+        ip_address: String,
+        user_agent: Option<String>,
+        trusted_device: Option<&str>,
+    ) -> Result<LoginOutcome, AuthenticationError> {
+        let started = Instant::now();
+        let result = self
+            .attempt_oauth_login(user, ip_address, user_agent, trusted_device)
+            .await;
+
+        let span = Span::current();
+        span.record("user_id", hash_user_id(user.id));
+        match &result {
+            Ok(LoginOutcome::Authenticated(_)) => {
+                metrics::record_login(true, started.elapsed());
+                span.record("outcome", "success");
+            }
+            Ok(LoginOutcome::ChallengeRequired(_)) => {
+                span.record("outcome", "challenge");
+            }
+            Err(_) => {
+                metrics::record_login(false, started.elapsed());
+                span.record("outcome", "failure");
+            }
         }
+        result
+    }
 
-        let token_pair = match self.jwt_service.generate_token_pair(&user).await {
+    async fn attempt_oauth_login(
+        &self,
+        user: &User,
+        ip_address: String,
+        user_agent: Option<String>,
+        trusted_device: Option<&str>,
+    ) -> Result<LoginOutcome, AuthenticationError> {
+        if let Ok(Some(lockout)) = self
+            .account_lockout_repository
+            .get_active_lockout(user.id)
+            .await
+            && lockout.is_locked_at(self.clock.now())
+        {
+            let attempt = LoginAttempt::new_failure(
+                ip_address,
+                user.email.clone(),
+                "Account locked".to_string(),
+                user_agent,
+            )
+            .for_user(user.id);
+
+            if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
+                eprintln!("Failed to log login attempt: {}", e);
+            }
+
+            let locked_until = lockout
+                .locked_until
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or("unknown time".to_string());
+            return Err(AuthenticationError::Locked(format!(
+                "Account is temporarily locked until {}. Please try again later.",
+                locked_until
+            )));
+        }
+
+        self.finish_login(
+            user,
+            ip_address,
+            user_agent,
+            false,
+            None,
+            None,
+            Vec::new(),
+            trusted_device,
+        )
+        .await
+    }
+
+    // The aud of a login's tokens: empty without a client_id, else that of the
+    // registered client. Unknown clients are rejected.
+    async fn client_audience(
+        &self,
+        client_id: Option<&str>,
+    ) -> Result<Vec<String>, AuthenticationError> {
+        let Some(client_id) = client_id else {
+            return Ok(Vec::new());
+        };
+        let client = match &self.client_repository {
+            Some(repository) => repository.find(client_id).await?,
+            None => None,
+        };
+        client.map(|client| client.token_audience()).ok_or_else(|| {
+            AuthenticationError::InvalidInput {
+                field: "client_id",
+                message: "Unknown client_id".to_string(),
+            }
+        })
+    }
+
+    // Everything after the credentials have been checked: tokens, the
+    // successful attempt and new-device notifications, lifting any lockout
+    async fn issue_tokens(
+        &self,
+        user: &User,
+        ip_address: String,
+        user_agent: Option<String>,
+        remember_me: bool,
+        device_name: Option<String>,
+        audience: Vec<String>,
+    ) -> Result<LoginResponse, AuthenticationError> {
+        let email = user.email.clone();
+        let password_change_required = self
+            .password_max_age
+            .is_some_and(|max_age| user.password_expired(max_age, self.clock.now()));
+
+        let token_started = Instant::now();
+        let refresh_ttl = self.jwt_service.refresh_ttl(remember_me);
+        let origin = TokenOrigin::new(user_agent.clone(), Some(ip_address.clone()));
+        let token_result = if password_change_required {
+            self.jwt_service
+                .generate_password_change_token_pair(
+                    user,
+                    refresh_ttl,
+                    device_name,
+                    audience,
+                    origin,
+                )
+                .await
+        } else {
+            self.jwt_service
+                .generate_token_pair_from(user, refresh_ttl, device_name, audience, origin)
+                .await
+        };
+        metrics::record_token_generation(token_started.elapsed());
+
+        let token_pair = match token_result {
             Ok(tokens) => tokens,
             Err(_) => {
                 let attempt = LoginAttempt::new_failure(
                     ip_address.clone(),
-                    request.email.clone(),
+                    email,
                     "Token generation failed".to_string(),
                     user_agent,
-                );
+                )
+                .for_user(user.id);
 
                 if let Err(e) = self.login_attempt_repository.create_attempt(&attempt).await {
                     eprintln!("Failed to log login attempt: {}", e);
                 }
 
-                return Err(AuthError::new("Authentication failed"));
+                return Err(AuthenticationError::AuthenticationFailed);
             }
         };
 
-        let success_attempt =
-            LoginAttempt::new_success(ip_address, request.email, user.id, user_agent);
+        // Checked before this login is recorded, or every device would look known
+        if self.auth_service.notification_settings().new_sign_in {
+            match self
+                .login_attempt_repository
+                .is_new_device(user.id, &ip_address, user_agent.as_deref())
+                .await
+            {
+                Ok(true) => {
+                    self.auth_service
+                        .notify_new_sign_in(user, &ip_address, user_agent.as_deref())
+                        .await
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("Failed to check for a new sign-in device: {}", e),
+            }
+        }
+
+        let success_attempt = LoginAttempt::new_success(ip_address, email, user.id, user_agent);
 
         if let Err(e) = self
             .login_attempt_repository
@@ -204,35 +867,190 @@ impl SecureLoginService {
             eprintln!("Failed to unlock account: {}", e);
         }
 
+        if let Err(e) = self
+            .account_lockout_repository
+            .reset_escalation(user.id)
+            .await
+        {
+            eprintln!("Failed to reset lockout escalation: {}", e);
+        }
+
         Ok(LoginResponse {
             message: "Login successful".to_string(),
             user: user.to_response(),
             tokens: token_pair,
+            csrf_token: None,
+            password_change_required,
         })
     }
 
+    // Best-effort email to the owner of a freshly locked account, with an unlock
+    // token when self-service unlock is enabled
+    async fn notify_lockout(&self, user: &User, lockout_duration: time::Duration) {
+        if !self.auth_service.notification_settings().account_locked {
+            return;
+        }
+
+        let mut unlock_token = None;
+        if self.lockout_config.self_service_unlock {
+            let plain_token = PasswordResetToken::generate_secure_token();
+            let expires_at = self.clock.now() + UNLOCK_TOKEN_TTL;
+            match self
+                .account_lockout_repository
+                .set_unlock_token(user.id, &hash_confirmation_token(&plain_token), expires_at)
+                .await
+            {
+                Ok(true) => unlock_token = Some(plain_token),
+                Ok(false) => {}
+                Err(e) => eprintln!("Failed to create unlock token: {}", e),
+            }
+        }
+
+        self.auth_service
+            .notify_account_locked(user, lockout_duration, unlock_token.as_deref())
+            .await;
+    }
+
+    // One more than the previous lockout if it still counts towards the streak
+    // Delay owed for the failed logins recorded since the last success, from
+    // `ip_address` or against `user_id`, whichever has more
+    pub async fn failure_delay(&self, ip_address: &str, user_id: Option<Uuid>) -> Duration {
+        if !self.tarpit_config.is_enabled() {
+            return Duration::ZERO;
+        }
+
+        let since = self.clock.now() - self.tarpit_config.window;
+        let ip_failures = self
+            .login_attempt_repository
+            .count_ip_failures_since_last_success(ip_address, since)
+            .await
+            .unwrap_or(0);
+        let user_failures = match user_id {
+            Some(user_id) => self
+                .login_attempt_repository
+                .count_failures_since_last_success(user_id, since)
+                .await
+                .unwrap_or(0),
+            None => 0,
+        };
+        self.tarpit_config.delay_for(ip_failures.max(user_failures))
+    }
+
+    // Sleeps instead of blocking the worker, holding nothing in the meantime
+    async fn hold_back(&self, ip_address: &str, user_id: Option<Uuid>) {
+        let delay = self.failure_delay(ip_address, user_id).await;
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn next_lockout_count(&self, user_id: Uuid) -> i32 {
+        let ended_after = self.clock.now() - self.lockout_config.lockout_duration.cooldown();
+        match self
+            .account_lockout_repository
+            .get_escalating_lockout(user_id, ended_after)
+            .await
+        {
+            Ok(Some(previous)) => previous.lockout_count + 1,
+            Ok(None) => 1,
+            Err(e) => {
+                eprintln!("Failed to load previous lockout: {}", e);
+                1
+            }
+        }
+    }
+
     // Get login attempt statistics for security monitoring
-    pub async fn get_login_statistics(&self, email: &str) -> Result<Vec<LoginAttempt>, AuthError> {
+    pub async fn get_login_statistics(
+        &self,
+        email: &str,
+    ) -> Result<Vec<LoginAttempt>, AuthenticationError> {
         self.login_attempt_repository
             .get_recent_attempts_by_email(email, 10)
             .await
-            .map_err(|e| AuthError::new(&format!("Database error: {}", e)))
+            .map_err(AuthenticationError::from)
+    }
+
+    // The user's own sign-in attempts for GET /api/auth/login-history, newest first
+    pub async fn login_history(
+        &self,
+        user_id: Uuid,
+        pagination: Pagination,
+    ) -> Result<(Vec<LoginAttempt>, i64), AuthenticationError> {
+        self.login_attempt_repository
+            .recent_for_user(user_id, pagination)
+            .await
+            .map_err(AuthenticationError::from)
     }
 
     // Manual account unlock (admin function)
-    pub async fn unlock_account(&self, user_id: Uuid) -> Result<(), AuthError> {
+    pub async fn unlock_account(&self, user_id: Uuid) -> Result<(), AuthenticationError> {
         self.account_lockout_repository
             .unlock_account(user_id)
             .await
-            .map_err(|e| AuthError::new(&format!("Failed to unlock account: {}", e)))
+            .map_err(AuthenticationError::from)
+    }
+
+    // When `email` belongs to a locked account, the time its lockout ends
+    pub async fn lockout_status(
+        &self,
+        email: &str,
+    ) -> Result<Option<OffsetDateTime>, AuthenticationError> {
+        let Some(user) = self.auth_service.find_user_by_email(email).await? else {
+            return Ok(None);
+        };
+        let lockout = self
+            .account_lockout_repository
+            .get_active_lockout(user.id)
+            .await?;
+        let now = self.clock.now();
+        Ok(lockout
+            .filter(|lockout| lockout.is_locked_at(now))
+            .map(|lockout| lockout.locked_until))
+    }
+
+    // Mails a one-time unlock token if `email` belongs to a locked account and
+    // self-service unlock is enabled, and does nothing otherwise; callers must
+    // answer the same way for both
+    pub async fn request_unlock(&self, email: &str) -> Result<(), AuthenticationError> {
+        if !self.lockout_config.self_service_unlock {
+            return Ok(());
+        }
+        let Some(user) = self.auth_service.find_user_by_email(email).await? else {
+            return Ok(());
+        };
+
+        let plain_token = PasswordResetToken::generate_secure_token();
+        let expires_at = self.clock.now() + UNLOCK_TOKEN_TTL;
+        let locked = self
+            .account_lockout_repository
+            .set_unlock_token(user.id, &hash_confirmation_token(&plain_token), expires_at)
+            .await?;
+        if locked {
+            self.auth_service
+                .send_unlock_email(&user.email, &plain_token)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Ends the lockout the token was mailed for; returns the unlocked user
+    pub async fn consume_unlock_token(&self, token: &str) -> Result<Uuid, AuthenticationError> {
+        if !self.lockout_config.self_service_unlock {
+            return Err(AuthenticationError::InvalidConfirmationToken);
+        }
+        self.account_lockout_repository
+            .consume_unlock_token(&hash_confirmation_token(token))
+            .await?
+            .ok_or(AuthenticationError::InvalidConfirmationToken)
     }
 
     // Maintenance: cleanup old login attempts
-    pub async fn cleanup_old_login_attempts(&self, days: i64) -> Result<u64, AuthError> {
-        let cutoff = OffsetDateTime::now_utc() - time::Duration::days(days);
+    pub async fn cleanup_old_login_attempts(&self, days: i64) -> Result<u64, AuthenticationError> {
+        let cutoff = self.clock.now() - time::Duration::days(days);
         self.login_attempt_repository
             .cleanup_old_attempts(cutoff)
             .await
-            .map_err(|e| AuthError::new(&format!("Cleanup failed: {}", e)))
+            .map_err(AuthenticationError::from)
     }
 }
@@ -0,0 +1,148 @@
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use time::OffsetDateTime;
+
+const DEFAULT_SMS_API_URL: &str = "https://api.twilio.com";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SmsError {
+    #[error("SMS provider error: {0}")]
+    Provider(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum SmsConfigError {
+    #[error("SMS_API_URL is not a valid URL: {0}")]
+    InvalidUrl(String),
+}
+
+// Delivers a text message to an E.164 phone number
+#[async_trait]
+pub trait SmsSender: Send + Sync {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SmsConfig {
+    pub api_url: reqwest::Url,
+    pub account_sid: String,
+    pub auth_token: String,
+    pub from_number: String,
+}
+
+impl SmsConfig {
+    // SMS_ACCOUNT_SID, SMS_AUTH_TOKEN and SMS_FROM_NUMBER; None (SMS codes
+    // disabled) unless all three are set. SMS_API_URL defaults to Twilio's API.
+    pub fn from_env() -> Result<Option<Self>, SmsConfigError> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let (Some(account_sid), Some(auth_token), Some(from_number)) = (
+            var("SMS_ACCOUNT_SID"),
+            var("SMS_AUTH_TOKEN"),
+            var("SMS_FROM_NUMBER"),
+        ) else {
+            return Ok(None);
+        };
+        let api_url = var("SMS_API_URL").unwrap_or_else(|| DEFAULT_SMS_API_URL.to_string());
+        let api_url =
+            reqwest::Url::parse(api_url.trim()).map_err(|_| SmsConfigError::InvalidUrl(api_url))?;
+
+        Ok(Some(Self {
+            api_url,
+            account_sid,
+            auth_token,
+            from_number,
+        }))
+    }
+}
+
+// Twilio's Messages API; any provider speaking the same protocol works via SMS_API_URL
+pub struct HttpSmsSender {
+    config: SmsConfig,
+    http: reqwest::Client,
+}
+
+impl HttpSmsSender {
+    pub fn new(config: SmsConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    fn messages_url(&self) -> String {
+        format!(
+            "{}/2010-04-01/Accounts/{}/Messages.json",
+            self.config.api_url.as_str().trim_end_matches('/'),
+            self.config.account_sid
+        )
+    }
+}
+
+#[async_trait]
+impl SmsSender for HttpSmsSender {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError> {
+        self.http
+            .post(self.messages_url())
+            .basic_auth(&self.config.account_sid, Some(&self.config.auth_token))
+            .form(&[
+                ("To", to),
+                ("From", self.config.from_number.as_str()),
+                ("Body", body),
+            ])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| SmsError::Provider(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmsMessage {
+    pub to: String,
+    pub body: String,
+    pub sent_at: OffsetDateTime,
+}
+
+// Mock SMS sender for testing - stores messages in memory
+#[derive(Clone, Default)]
+pub struct MockSmsSender {
+    sent_messages: Arc<Mutex<Vec<SmsMessage>>>,
+}
+
+impl MockSmsSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get_sent_messages(&self) -> Vec<SmsMessage> {
+        self.sent_messages
+            .lock()
+            .map(|messages| messages.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn get_last_sent_message(&self) -> Option<SmsMessage> {
+        self.sent_messages
+            .lock()
+            .ok()
+            .and_then(|messages| messages.last().cloned())
+    }
+}
+
+#[async_trait]
+impl SmsSender for MockSmsSender {
+    async fn send(&self, to: &str, body: &str) -> Result<(), SmsError> {
+        let message = SmsMessage {
+            to: to.to_string(),
+            body: body.to_string(),
+            sent_at: OffsetDateTime::now_utc(),
+        };
+        if let Ok(mut messages) = self.sent_messages.lock() {
+            messages.push(message);
+        }
+        Ok(())
+    }
+}
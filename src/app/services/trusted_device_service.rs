@@ -0,0 +1,91 @@
+use crate::app::models::password_reset::PasswordResetToken;
+use crate::app::models::trusted_device::TrustedDevice;
+use crate::app::repositories::trusted_device_repository::TrustedDeviceRepository;
+use crate::app::services::auth_service::hash_confirmation_token;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const DEFAULT_TRUSTED_DEVICE_DAYS: i64 = 30;
+
+// TRUSTED_DEVICE_DAYS, default 30; zero turns trusted devices off and
+// unparseable values fall back to the default
+pub fn get_trusted_device_ttl() -> Option<time::Duration> {
+    let days = std::env::var("TRUSTED_DEVICE_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|days| *days >= 0)
+        .unwrap_or(DEFAULT_TRUSTED_DEVICE_DAYS);
+    (days > 0).then(|| time::Duration::days(days))
+}
+
+// Lets a device that answered an SMS code skip it on later logins for `ttl`.
+// The device holds a random token; only its hash is stored.
+#[derive(Clone)]
+pub struct TrustedDeviceService {
+    repository: TrustedDeviceRepository,
+    ttl: time::Duration,
+}
+
+impl TrustedDeviceService {
+    pub fn new(repository: TrustedDeviceRepository, ttl: time::Duration) -> Self {
+        Self { repository, ttl }
+    }
+
+    pub fn ttl(&self) -> time::Duration {
+        self.ttl
+    }
+
+    // Returns the new device's token, for its cookie
+    pub async fn trust(
+        &self,
+        user_id: Uuid,
+        user_agent: Option<String>,
+        ip_address: Option<String>,
+    ) -> Result<String, sqlx::Error> {
+        let token = PasswordResetToken::generate_secure_token();
+        let device = TrustedDevice::new(
+            user_id,
+            hash_confirmation_token(&token),
+            user_agent,
+            ip_address,
+            self.ttl,
+        );
+        self.repository.create(&device).await?;
+        Ok(token)
+    }
+
+    pub async fn is_trusted(&self, user_id: Uuid, token: &str) -> Result<bool, sqlx::Error> {
+        self.repository
+            .is_trusted(user_id, &hash_confirmation_token(token))
+            .await
+    }
+
+    // Replaces a used token so a copied cookie stops working once the device
+    // logs in again. Returns the new token and when the trust runs out, or None
+    // if `token` is no longer trusted.
+    pub async fn rotate(
+        &self,
+        user_id: Uuid,
+        token: &str,
+    ) -> Result<Option<(String, OffsetDateTime)>, sqlx::Error> {
+        let new_token = PasswordResetToken::generate_secure_token();
+        let expires_at = self
+            .repository
+            .rotate(
+                user_id,
+                &hash_confirmation_token(token),
+                &hash_confirmation_token(&new_token),
+            )
+            .await?;
+        Ok(expires_at.map(|expires_at| (new_token, expires_at)))
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> Result<Vec<TrustedDevice>, sqlx::Error> {
+        self.repository.list_for_user(user_id).await
+    }
+
+    // False if the user has no device `id`
+    pub async fn revoke(&self, user_id: Uuid, id: Uuid) -> Result<bool, sqlx::Error> {
+        self.repository.delete(user_id, id).await
+    }
+}
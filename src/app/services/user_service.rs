@@ -1,11 +1,28 @@
-use crate::app::models::user::User;
-use crate::app::repositories::user_repository::UserRepository;
+use crate::app::hashing::{Argon2Params, HashingError};
+use crate::app::models::account_export::{
+    AccountExport, LoginAttemptExport, SecurityEventExport, SessionExport,
+};
+use crate::app::models::auth::ProfileResponse;
+use crate::app::models::user::{AccountSummary, User, UserCursor, UserStatus};
+use crate::app::repositories::admin_audit_repository::AdminAuditRepository;
+use crate::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use crate::app::repositories::user_repository::UserStore;
+use std::sync::Arc;
+use time::OffsetDateTime;
 use uuid::Uuid;
 
+// How many of the most recent login attempts an export includes
+const EXPORT_LOGIN_ATTEMPT_LIMIT: i32 = 100;
+
 #[derive(Debug)]
 pub enum UserServiceError {
     DatabaseError(sqlx::Error),
     PasswordHashError(String),
+    UserNotFound,
+    InvalidPassword,
+    ExportUnavailable,
 }
 
 impl std::fmt::Display for UserServiceError {
@@ -13,6 +30,11 @@ impl std::fmt::Display for UserServiceError {
         match self {
             UserServiceError::DatabaseError(e) => write!(f, "Database error: {}", e),
             UserServiceError::PasswordHashError(e) => write!(f, "Password hash error: {}", e),
+            UserServiceError::UserNotFound => write!(f, "User not found"),
+            UserServiceError::InvalidPassword => write!(f, "Current password is incorrect"),
+            UserServiceError::ExportUnavailable => {
+                write!(f, "Account activity repositories are not configured")
+            }
         }
     }
 }
@@ -31,13 +53,54 @@ impl From<argon2::password_hash::Error> for UserServiceError {
     }
 }
 
+impl From<HashingError> for UserServiceError {
+    fn from(e: HashingError) -> Self {
+        UserServiceError::PasswordHashError(e.to_string())
+    }
+}
+
+// Repositories holding account activity, needed for data exports
+struct ActivityRepositories {
+    refresh_tokens: RefreshTokenRepository,
+    login_attempts: LoginAttemptRepository,
+    lockouts: AccountLockoutRepository,
+    audit: AdminAuditRepository,
+}
+
 pub struct UserService {
-    repository: UserRepository,
+    repository: Arc<dyn UserStore>,
+    activity: Option<ActivityRepositories>,
+    argon2_params: Argon2Params,
 }
 
 impl UserService {
-    pub fn new(repository: UserRepository) -> Self {
-        Self { repository }
+    pub fn new(repository: impl UserStore + 'static) -> Self {
+        Self {
+            repository: Arc::new(repository),
+            activity: None,
+            argon2_params: Argon2Params::default(),
+        }
+    }
+
+    pub fn with_argon2_params(mut self, params: Argon2Params) -> Self {
+        self.argon2_params = params;
+        self
+    }
+
+    pub fn with_activity_repositories(
+        mut self,
+        refresh_tokens: RefreshTokenRepository,
+        login_attempts: LoginAttemptRepository,
+        lockouts: AccountLockoutRepository,
+        audit: AdminAuditRepository,
+    ) -> Self {
+        self.activity = Some(ActivityRepositories {
+            refresh_tokens,
+            login_attempts,
+            lockouts,
+            audit,
+        });
+        self
     }
 
     pub async fn create_user(
@@ -46,7 +109,7 @@ impl UserService {
         email: String,
         password: &str,
     ) -> Result<User, UserServiceError> {
-        let user = User::new(name, email, password)?;
+        let user = User::new_async(name, email, password, &self.argon2_params).await?;
         let created_user = self.repository.create(&user).await?;
         Ok(created_user)
     }
@@ -59,6 +122,14 @@ impl UserService {
         Ok(user)
     }
 
+    // The user plus the roles, verification and session details GET /me reports
+    pub async fn get_account_summary(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<(User, AccountSummary)>, UserServiceError> {
+        Ok(self.repository.find_with_summary(id).await?)
+    }
+
     pub async fn get_user_by_email(
         &self,
         email: &str,
@@ -72,6 +143,31 @@ impl UserService {
         Ok(users)
     }
 
+    // One page of users plus the cursor for the next page, if there may be one
+    pub async fn list_users_page(
+        &self,
+        cursor: Option<UserCursor>,
+        limit: i64,
+    ) -> Result<(Vec<User>, Option<UserCursor>), Box<dyn std::error::Error>> {
+        let users = self.repository.list_after(cursor, limit).await?;
+        let next_cursor = if users.len() as i64 == limit {
+            users.last().and_then(UserCursor::from_user)
+        } else {
+            None
+        };
+        Ok((users, next_cursor))
+    }
+
+    // Accounts that aren't deleted
+    pub async fn count(&self) -> Result<i64, UserServiceError> {
+        Ok(self.repository.count_since(None).await?)
+    }
+
+    // Accounts registered after `since` that aren't deleted
+    pub async fn count_since(&self, since: OffsetDateTime) -> Result<i64, UserServiceError> {
+        Ok(self.repository.count_since(Some(since)).await?)
+    }
+
     pub async fn update_user(
         &self,
         id: Uuid,
@@ -82,7 +178,7 @@ impl UserService {
         let name_ref = name.as_deref();
         let email_ref = email.as_deref();
         let password_hash = if let Some(password) = password {
-            Some(User::hash_password(&password)?)
+            Some(self.argon2_params.hash_async(&password).await?)
         } else {
             None
         };
@@ -95,8 +191,104 @@ impl UserService {
         Ok(user)
     }
 
+    // Soft delete: the account disappears from lookups and logins but its row is
+    // kept for the audit trail. delete_account is the one that erases everything.
     pub async fn delete_user(&self, id: Uuid) -> Result<bool, Box<dyn std::error::Error>> {
-        let deleted = self.repository.delete(id).await?;
+        let deleted = self.repository.soft_delete(id).await?;
         Ok(deleted)
     }
+
+    // Blocks logins and every token the user already holds until enable_user
+    pub async fn disable_user(&self, id: Uuid) -> Result<(), UserServiceError> {
+        self.set_status(id, UserStatus::Disabled).await
+    }
+
+    pub async fn enable_user(&self, id: Uuid) -> Result<(), UserServiceError> {
+        self.set_status(id, UserStatus::Active).await
+    }
+
+    async fn set_status(&self, id: Uuid, status: UserStatus) -> Result<(), UserServiceError> {
+        if !self.repository.set_status(id, status).await? {
+            return Err(UserServiceError::UserNotFound);
+        }
+        Ok(())
+    }
+
+    // Self-service deletion: the caller must re-enter their password
+    pub async fn delete_account(
+        &self,
+        user_id: Uuid,
+        current_password: &str,
+    ) -> Result<(), UserServiceError> {
+        let user = self
+            .repository
+            .find_by_id(user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFound)?;
+
+        if !user.verify_password_async(current_password).await? {
+            return Err(UserServiceError::InvalidPassword);
+        }
+
+        if !self.repository.delete_account(user_id).await? {
+            return Err(UserServiceError::UserNotFound);
+        }
+
+        Ok(())
+    }
+
+    // Collect the user's profile, sessions, login history and security events
+    pub async fn export_user_data(&self, user_id: Uuid) -> Result<AccountExport, UserServiceError> {
+        let activity = self
+            .activity
+            .as_ref()
+            .ok_or(UserServiceError::ExportUnavailable)?;
+        let user = self
+            .repository
+            .find_by_id(user_id)
+            .await?
+            .ok_or(UserServiceError::UserNotFound)?;
+
+        let sessions = activity
+            .refresh_tokens
+            .find_active_by_user(user_id)
+            .await?
+            .into_iter()
+            .map(SessionExport::from)
+            .collect();
+
+        // Attempts are keyed by the email typed at login, so only the user's own address is queried
+        let login_attempts = activity
+            .login_attempts
+            .get_recent_attempts_by_email(&user.email, EXPORT_LOGIN_ATTEMPT_LIMIT)
+            .await?
+            .into_iter()
+            .map(LoginAttemptExport::from)
+            .collect();
+
+        let mut security_events: Vec<SecurityEventExport> = activity
+            .lockouts
+            .find_by_user(user_id)
+            .await?
+            .into_iter()
+            .map(SecurityEventExport::from)
+            .collect();
+        security_events.extend(
+            activity
+                .audit
+                .find_by_target_user(user_id)
+                .await?
+                .into_iter()
+                .map(SecurityEventExport::from),
+        );
+        security_events.sort_by_key(|event| std::cmp::Reverse(event.occurred_at));
+
+        Ok(AccountExport {
+            exported_at: OffsetDateTime::now_utc(),
+            profile: ProfileResponse::from(user),
+            sessions,
+            login_attempts,
+            security_events,
+        })
+    }
 }
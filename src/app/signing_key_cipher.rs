@@ -0,0 +1,137 @@
+// Encrypts the HS256 secrets of rotated signing keys before they are stored in
+// signing_keys, so reading the table is not enough to forge tokens.
+//
+// AES-256-GCM with a key derived from SIGNING_KEY_ENCRYPTION_KEY; the kid is
+// authenticated along with the secret, so a stored value can't be moved to
+// another row. Stored values look like `v1:<hex nonce and ciphertext>`.
+use crate::app::cookies::AppEnvironment;
+use crate::app::services::jwt_service::{ConfigurationError, MIN_JWT_SECRET_BYTES};
+use aes_gcm::Aes256Gcm;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use sha2::{Digest, Sha256};
+
+const STORED_PREFIX: &str = "v1:";
+const NONCE_BYTES: usize = 12;
+
+// Only used when APP_ENV is development and SIGNING_KEY_ENCRYPTION_KEY is unset
+const DEVELOPMENT_ENCRYPTION_KEY: &str = "development-signing-key-encryption-key-do-not-use";
+
+#[derive(Debug, thiserror::Error)]
+pub enum SigningKeyCipherError {
+    // Never echoes the stored value
+    #[error(
+        "Stored secret of signing key {0:?} can't be decrypted with SIGNING_KEY_ENCRYPTION_KEY"
+    )]
+    Undecryptable(String),
+}
+
+#[derive(Clone)]
+pub struct SigningKeyCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SigningKeyCipher {
+    pub fn new(encryption_key: &str) -> Self {
+        let key = Sha256::digest(encryption_key.as_bytes());
+        Self {
+            cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    pub fn encrypt(&self, kid: &str, secret: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let payload = Payload {
+            msg: secret.as_bytes(),
+            aad: kid.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload)
+            .expect("AES-GCM encryption of a signing key secret failed");
+
+        let hex: String = nonce
+            .iter()
+            .chain(&ciphertext)
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("{}{}", STORED_PREFIX, hex)
+    }
+
+    pub fn decrypt(&self, kid: &str, stored: &str) -> Result<String, SigningKeyCipherError> {
+        let undecryptable = || SigningKeyCipherError::Undecryptable(kid.to_string());
+        let bytes = stored
+            .strip_prefix(STORED_PREFIX)
+            .and_then(decode_hex)
+            .filter(|bytes| bytes.len() > NONCE_BYTES)
+            .ok_or_else(undecryptable)?;
+        let (nonce, ciphertext) = bytes.split_at(NONCE_BYTES);
+        let payload = Payload {
+            msg: ciphertext,
+            aad: kid.as_bytes(),
+        };
+        let secret = self
+            .cipher
+            .decrypt(nonce.into(), payload)
+            .map_err(|_| undecryptable())?;
+        String::from_utf8(secret).map_err(|_| undecryptable())
+    }
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Same rules as JWT_SECRET: required and at least 32 bytes outside development
+pub fn resolve_signing_key_encryption_key(
+    key: Option<String>,
+    environment: AppEnvironment,
+) -> Result<String, ConfigurationError> {
+    let key = key.filter(|key| !key.is_empty());
+    if !environment.is_production() {
+        let Some(key) = key else {
+            eprintln!(
+                "Warning: SIGNING_KEY_ENCRYPTION_KEY not set, using default (not secure for production!)"
+            );
+            return Ok(DEVELOPMENT_ENCRYPTION_KEY.to_string());
+        };
+        if key.len() < MIN_JWT_SECRET_BYTES {
+            eprintln!(
+                "Warning: SIGNING_KEY_ENCRYPTION_KEY is shorter than {} bytes (refused outside development)",
+                MIN_JWT_SECRET_BYTES
+            );
+        }
+        return Ok(key);
+    }
+
+    let key = key.ok_or(ConfigurationError::MissingSigningKeyEncryptionKey)?;
+    if key.len() < MIN_JWT_SECRET_BYTES {
+        return Err(ConfigurationError::SigningKeyEncryptionKeyTooShort);
+    }
+    Ok(key)
+}
+
+// Checked once at server startup, before any key is stored or loaded
+pub fn load_signing_key_cipher() -> Result<SigningKeyCipher, ConfigurationError> {
+    resolve_signing_key_encryption_key(
+        std::env::var("SIGNING_KEY_ENCRYPTION_KEY").ok(),
+        AppEnvironment::from_env(),
+    )
+    .map(|key| SigningKeyCipher::new(&key))
+}
+
+// For building the router, which load_signing_key_cipher has vetted by then
+pub fn get_signing_key_cipher() -> SigningKeyCipher {
+    let key = std::env::var("SIGNING_KEY_ENCRYPTION_KEY").unwrap_or_else(|_| {
+        eprintln!(
+            "Warning: SIGNING_KEY_ENCRYPTION_KEY not set, using default (not secure for production!)"
+        );
+        DEVELOPMENT_ENCRYPTION_KEY.to_string()
+    });
+    SigningKeyCipher::new(&key)
+}
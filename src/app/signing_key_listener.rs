@@ -0,0 +1,66 @@
+// Reloads stored signing keys as soon as any server rotates one. A trigger on
+// signing_keys notifies SIGNING_KEYS_CHANNEL after every change; every instance
+// listens and marks its key ring stale, so a key rotated on one server signs
+// and validates on all of them, and revoked keys stop validating everywhere.
+use crate::app::services::jwt_service::SigningKeyReload;
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+pub const SIGNING_KEYS_CHANNEL: &str = "signing_keys_changed";
+const LISTENER_TASK: &str = "signing_key_listener";
+// Pause after a failed reconnect before trying again
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub struct SigningKeyListener {
+    listener: PgListener,
+    reload: SigningKeyReload,
+}
+
+impl SigningKeyListener {
+    // Listening has started once this returns, so later changes are not missed
+    pub async fn connect(pool: &PgPool, reload: SigningKeyReload) -> Result<Self, sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(SIGNING_KEYS_CHANNEL).await?;
+
+        Ok(Self { listener, reload })
+    }
+
+    // Mark the keys stale on every change until `shutdown` flips to true.
+    // Notifications sent while the connection was down are lost, so the keys
+    // are reloaded after every reconnect as well.
+    pub fn spawn(mut self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    received = self.listener.try_recv() => received,
+                    _ = shutdown.changed() => break,
+                };
+                if *shutdown.borrow() {
+                    break;
+                }
+
+                match received {
+                    Ok(Some(_)) => self.reload.mark_stale(),
+                    Ok(None) => {
+                        warn!(
+                            task = LISTENER_TASK,
+                            "Connection lost, reloading signing keys"
+                        );
+                        self.reload.mark_stale();
+                    }
+                    Err(e) => {
+                        warn!(task = LISTENER_TASK, error = %e, "Failed to receive signing key notifications");
+                        self.reload.mark_stale();
+                        tokio::time::sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+
+            info!(task = LISTENER_TASK, "Signing key listener stopped");
+        })
+    }
+}
@@ -0,0 +1,101 @@
+// Liveness tracking for background tasks. A task records a heartbeat after
+// every run and is reported degraded once it misses its interval plus a margin.
+use crate::app::metrics;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+const DEFAULT_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    // Has not run yet but is still within its first interval
+    Starting,
+    Healthy,
+    Degraded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskHealth {
+    pub name: &'static str,
+    pub status: TaskStatus,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_run: Option<OffsetDateTime>,
+    pub last_removed: Option<u64>,
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LastRun {
+    at: OffsetDateTime,
+    removed: u64,
+}
+
+#[derive(Clone)]
+pub struct TaskHeartbeat {
+    name: &'static str,
+    interval: Duration,
+    margin: Duration,
+    started_at: OffsetDateTime,
+    last_run: Arc<RwLock<Option<LastRun>>>,
+}
+
+impl TaskHeartbeat {
+    pub fn new(name: &'static str, interval: Duration) -> Self {
+        Self {
+            name,
+            interval,
+            margin: DEFAULT_MARGIN,
+            started_at: OffsetDateTime::now_utc(),
+            last_run: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    // Call after every completed run with the number of rows it removed
+    pub fn beat(&self, removed: u64) {
+        let at = OffsetDateTime::now_utc();
+        if let Ok(mut last_run) = self.last_run.write() {
+            *last_run = Some(LastRun { at, removed });
+        }
+        metrics::record_background_task_run(self.name, removed, at);
+    }
+
+    pub fn health(&self) -> TaskHealth {
+        self.health_at(OffsetDateTime::now_utc())
+    }
+
+    // Health as of `now`; split out so a stalled task can be checked without waiting
+    pub fn health_at(&self, now: OffsetDateTime) -> TaskHealth {
+        let last_run = self.last_run.read().ok().and_then(|last_run| *last_run);
+        let deadline = self.interval + self.margin;
+
+        let status = match last_run {
+            Some(run) if now - run.at <= deadline => TaskStatus::Healthy,
+            None if now - self.started_at <= deadline => TaskStatus::Starting,
+            _ => TaskStatus::Degraded,
+        };
+
+        TaskHealth {
+            name: self.name,
+            status,
+            last_run: last_run.map(|run| run.at),
+            last_removed: last_run.map(|run| run.removed),
+            interval_secs: self.interval.as_secs(),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.health().status != TaskStatus::Degraded
+    }
+}
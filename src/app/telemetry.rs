@@ -0,0 +1,163 @@
+// Tracing subscriber setup, with optional OTLP export behind the `otel` feature
+use sha2::{Digest, Sha256};
+use tracing::Subscriber;
+use tracing_subscriber::{
+    EnvFilter, Layer, fmt::MakeWriter, layer::SubscriberExt, registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+use uuid::Uuid;
+
+#[cfg(feature = "otel")]
+use opentelemetry::trace::TracerProvider as _;
+#[cfg(feature = "otel")]
+use opentelemetry_otlp::WithExportConfig;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::{Resource, trace::SdkTracerProvider};
+
+const DEFAULT_LOG_FILTER: &str = "chronos=debug,tower_http=debug,axum::rejection=trace";
+#[cfg(feature = "otel")]
+const SERVICE_NAME: &str = "chronos";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TelemetryError {
+    #[cfg(feature = "otel")]
+    #[error("Failed to build OTLP exporter: {0}")]
+    Exporter(String),
+    #[error("Failed to install tracing subscriber: {0}")]
+    Subscriber(String),
+}
+
+// Flushes pending spans when dropped; keep it alive for the lifetime of the server
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl TelemetryGuard {
+    pub fn otlp_enabled(&self) -> bool {
+        #[cfg(feature = "otel")]
+        return self.tracer_provider.is_some();
+        #[cfg(not(feature = "otel"))]
+        false
+    }
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.tracer_provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+// How log records are written to stdout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    // One JSON object per line, for log ingestion
+    #[default]
+    Json,
+    // Human-readable lines for local development
+    Text,
+}
+
+impl LogFormat {
+    // LOG_FORMAT=text switches to human-readable output; JSON is the default
+    pub fn from_env() -> Self {
+        match std::env::var("LOG_FORMAT") {
+            Ok(value) if value.eq_ignore_ascii_case("text") => LogFormat::Text,
+            _ => LogFormat::Json,
+        }
+    }
+}
+
+// Single-line JSON records with the event's fields (event_type, request_id, ...) at the
+// top level next to timestamp, level and target
+pub fn json_layer<S, W>(make_writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_writer(make_writer)
+}
+
+// OTEL_EXPORTER_OTLP_ENDPOINT, e.g. http://localhost:4317; unset or empty disables export
+pub fn otlp_endpoint() -> Option<String> {
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+        .ok()
+        .filter(|endpoint| !endpoint.trim().is_empty())
+}
+
+// Stable pseudonym for a user id so spans can be correlated without exposing the id
+pub fn hash_user_id(user_id: Uuid) -> String {
+    let digest = Sha256::digest(user_id.as_bytes());
+    digest[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(feature = "otel")]
+fn build_tracer_provider(endpoint: &str) -> Result<SdkTracerProvider, TelemetryError> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .map_err(|e| TelemetryError::Exporter(e.to_string()))?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+        .build())
+}
+
+// Install the global subscriber: RUST_LOG filtering, console output in LOG_FORMAT
+// and, when configured, OTLP span export
+pub fn init() -> Result<TelemetryGuard, TelemetryError> {
+    let env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let format = LogFormat::from_env();
+    let registry = tracing_subscriber::registry()
+        .with(env_filter)
+        .with((format == LogFormat::Json).then(|| json_layer(std::io::stdout)))
+        .with((format == LogFormat::Text).then(tracing_subscriber::fmt::layer));
+
+    #[cfg(feature = "otel")]
+    {
+        let tracer_provider = otlp_endpoint()
+            .map(|endpoint| build_tracer_provider(&endpoint))
+            .transpose()?;
+        let otel_layer = tracer_provider.as_ref().map(|provider| {
+            tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME))
+        });
+
+        registry
+            .with(otel_layer)
+            .try_init()
+            .map_err(|e| TelemetryError::Subscriber(e.to_string()))?;
+
+        Ok(TelemetryGuard { tracer_provider })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        if otlp_endpoint().is_some() {
+            eprintln!(
+                "OTEL_EXPORTER_OTLP_ENDPOINT is set but Chronos was built without the `otel` feature"
+            );
+        }
+
+        registry
+            .try_init()
+            .map_err(|e| TelemetryError::Subscriber(e.to_string()))?;
+
+        Ok(TelemetryGuard {})
+    }
+}
@@ -0,0 +1,74 @@
+// Drops cached token_invalid_before values as soon as any server moves one.
+// A trigger on users sends the user id on TOKEN_EPOCH_CHANNEL whenever the
+// epoch changes; every instance listens and evicts that user from its cache,
+// so a password change or revocation on one server is enforced by all of them.
+use crate::app::token_epochs::TokenEpochCache;
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub const TOKEN_EPOCH_CHANNEL: &str = "token_epoch_changed";
+const LISTENER_TASK: &str = "token_epoch_listener";
+// Pause after a failed reconnect before trying again
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub struct TokenEpochListener {
+    listener: PgListener,
+    cache: TokenEpochCache,
+}
+
+impl TokenEpochListener {
+    // Listening has started once this returns, so later changes are not missed
+    pub async fn connect(pool: &PgPool, cache: TokenEpochCache) -> Result<Self, sqlx::Error> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(TOKEN_EPOCH_CHANNEL).await?;
+
+        Ok(Self { listener, cache })
+    }
+
+    // Evict users as their epochs change until `shutdown` flips to true.
+    // Notifications sent while the connection was down are lost, so the whole
+    // cache is dropped after every reconnect.
+    pub fn spawn(mut self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let received = tokio::select! {
+                    received = self.listener.try_recv() => received,
+                    _ = shutdown.changed() => break,
+                };
+                if *shutdown.borrow() {
+                    break;
+                }
+
+                match received {
+                    Ok(Some(notification)) => match notification.payload().parse::<Uuid>() {
+                        Ok(user_id) => self.cache.forget(user_id),
+                        Err(_) => warn!(
+                            task = LISTENER_TASK,
+                            payload = notification.payload(),
+                            "Ignoring malformed token epoch notification"
+                        ),
+                    },
+                    Ok(None) => {
+                        warn!(
+                            task = LISTENER_TASK,
+                            "Connection lost, dropping cached token epochs"
+                        );
+                        self.cache.clear();
+                    }
+                    Err(e) => {
+                        warn!(task = LISTENER_TASK, error = %e, "Failed to receive token epoch notifications");
+                        self.cache.clear();
+                        tokio::time::sleep(RETRY_DELAY).await;
+                    }
+                }
+            }
+
+            info!(task = LISTENER_TASK, "Token epoch listener stopped");
+        })
+    }
+}
@@ -0,0 +1,86 @@
+// Per-user token_invalid_before values, remembered for a short while so protected
+// routes don't read the users table on every request. Clones share their entries;
+// whoever moves an epoch records it here so this instance sees it immediately,
+// and other instances evict it when token_epoch_listener hears of the change.
+// Without the listener they catch up once their cached value expires.
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+pub const DEFAULT_TOKEN_EPOCH_CACHE_TTL: Duration = Duration::from_secs(30);
+
+// Expired entries are swept once the cache grows past this many users
+const SWEEP_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+struct CachedEpoch {
+    epoch: Option<OffsetDateTime>,
+    fetched_at: Instant,
+}
+
+#[derive(Clone)]
+pub struct TokenEpochCache {
+    entries: Arc<DashMap<Uuid, CachedEpoch>>,
+    ttl: Duration,
+}
+
+impl TokenEpochCache {
+    // A zero `ttl` turns caching off
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(DashMap::new()),
+            ttl,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(Duration::ZERO)
+    }
+
+    // Some(epoch) while a cached value is fresh; the epoch itself is None for
+    // users whose tokens have never been invalidated
+    pub fn get(&self, user_id: Uuid) -> Option<Option<OffsetDateTime>> {
+        let entry = *self.entries.get(&user_id)?;
+        if entry.fetched_at.elapsed() < self.ttl {
+            return Some(entry.epoch);
+        }
+        self.entries.remove(&user_id);
+        None
+    }
+
+    pub fn insert(&self, user_id: Uuid, epoch: Option<OffsetDateTime>) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        if self.entries.len() >= SWEEP_THRESHOLD {
+            self.entries
+                .retain(|_, entry| entry.fetched_at.elapsed() < self.ttl);
+        }
+        self.entries.insert(
+            user_id,
+            CachedEpoch {
+                epoch,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn forget(&self, user_id: Uuid) {
+        self.entries.remove(&user_id);
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+    }
+}
+
+// TOKEN_EPOCH_CACHE_TTL_SECS, default 30; 0 reads the epoch on every request
+pub fn get_token_epoch_cache_ttl() -> Duration {
+    std::env::var("TOKEN_EPOCH_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_TOKEN_EPOCH_CACHE_TTL)
+}
@@ -1,2 +1,3 @@
 pub mod postgres;
+pub mod shutdown;
 pub mod web;
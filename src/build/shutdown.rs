@@ -0,0 +1,94 @@
+// Stops the server without cutting off requests in flight, e.g. a login that is
+// halfway through writing its refresh token. Once the signal arrives no new
+// connections are accepted and open requests get a bounded time to finish.
+use axum::Router;
+use std::future::{Future, IntoFuture};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// SHUTDOWN_TIMEOUT_SECS, default 30; unparseable values keep the default
+pub fn get_shutdown_timeout() -> Duration {
+    std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT)
+}
+
+// Resolves on Ctrl+C, or on SIGTERM from an orchestrator rolling out a new version
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!(signal = "SIGINT", "Shutdown signal received"),
+        _ = terminate => info!(signal = "SIGTERM", "Shutdown signal received"),
+    }
+}
+
+// Serves `app` until `signal` resolves, then waits up to `drain_timeout` for
+// open requests before returning; whatever is still running is dropped
+pub async fn serve_with_graceful_shutdown<F>(
+    listener: TcpListener,
+    app: Router,
+    signal: F,
+    drain_timeout: Duration,
+) -> std::io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (draining_tx, mut draining_rx) = watch::channel(false);
+    let signal = async move {
+        signal.await;
+        let _ = draining_tx.send(true);
+    };
+
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(signal)
+    .into_future();
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => return result,
+        _ = draining_rx.wait_for(|draining| *draining) => {}
+    }
+
+    info!(
+        timeout_secs = drain_timeout.as_secs_f64(),
+        "Draining in-flight requests"
+    );
+    match tokio::time::timeout(drain_timeout, server).await {
+        Ok(result) => {
+            info!("All requests finished, server stopped");
+            result
+        }
+        Err(_) => {
+            warn!(
+                timeout_secs = drain_timeout.as_secs_f64(),
+                "Requests still running after the drain timeout, stopping anyway"
+            );
+            Ok(())
+        }
+    }
+}
@@ -1,26 +1,64 @@
-use crate::app::middleware::security::{SecurityHeadersLayer, get_cors_layer};
+use crate::app::cookies::AppEnvironment;
+use crate::app::geolocation::GeoResolver;
+use crate::app::maintenance::CleanupTask;
+use crate::app::middleware::request_id::RequestIdScopeLayer;
+use crate::app::middleware::security::{
+    SecurityHeadersConfig, SecurityHeadersLayer, get_cors_layer, set_trusted_proxies,
+    trusted_proxies_from_env,
+};
+use crate::app::security_events::{self, PiiMode, WebhookConfig, WebhookSink};
+use crate::app::services::jwt_service::{JwtKeyConfig, SigningKeyReload, load_jwt_secret};
+use crate::app::signing_key_cipher::load_signing_key_cipher;
+use crate::app::signing_key_listener::SigningKeyListener;
+use crate::app::telemetry;
+use crate::app::token_epoch_listener::TokenEpochListener;
+use crate::app::token_epochs::{TokenEpochCache, get_token_epoch_cache_ttl};
+use crate::build::shutdown::{get_shutdown_timeout, serve_with_graceful_shutdown, shutdown_signal};
 use crate::routes;
+use crate::routes::health::{self, HealthState};
 use axum::extract::connect_info::ConnectInfo;
 use sqlx::PgPool;
 use std::env;
-use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::net::TcpListener;
+use tokio::sync::watch;
 use tower::ServiceBuilder;
 use tower_http::{
     request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
     trace::TraceLayer,
 };
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing::{info, warn};
 
 pub async fn build(pool: PgPool) {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "chronos=debug,tower_http=debug,axum::rejection=trace".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Initialize tracing; spans are exported over OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set
+    let _telemetry = telemetry::init().expect("Failed to initialize tracing");
+
+    // Mask or hash emails and IPs in security events when LOG_PII_MODE asks for it
+    let pii_mode = PiiMode::from_env().expect("Invalid LOG_PII_MODE configuration");
+    security_events::set_pii_mode(pii_mode);
+
+    // Alert on high-severity security events when SECURITY_WEBHOOK_URL is set
+    let webhook_config = WebhookConfig::from_env().expect("Invalid security webhook configuration");
+    if let Some(config) = webhook_config {
+        security_events::add_security_event_sink(Arc::new(WebhookSink::from_config(config)));
+    }
+
+    // Add the client's country and region to security events when GEOIP_DATABASE_PATH is set
+    let geo_resolver = GeoResolver::from_env().expect("Invalid GEOIP_DATABASE_PATH");
+    security_events::set_geo_resolver(geo_resolver);
+
+    // Only proxies in TRUSTED_PROXIES may set the client address via X-Forwarded-For
+    set_trusted_proxies(trusted_proxies_from_env().expect("Invalid TRUSTED_PROXIES"));
+
+    // Outside development, refuse to start with a missing, placeholder or short JWT_SECRET,
+    // or with such a secret in JWT_SIGNING_KEYS
+    load_jwt_secret().expect("Invalid JWT_SECRET");
+    JwtKeyConfig::from_env()
+        .expect("Invalid JWT_SIGNING_KEYS")
+        .validate(AppEnvironment::from_env())
+        .expect("Invalid JWT_SIGNING_KEYS");
+    // Rotated signing keys are stored encrypted with SIGNING_KEY_ENCRYPTION_KEY
+    load_signing_key_cipher().expect("Invalid SIGNING_KEY_ENCRYPTION_KEY");
 
     let domain: String = env::var("APP_URL").expect("APP_URL must be set!");
     let port: String = env::var("APP_PORT").expect("APP_PORT must be set!");
@@ -28,23 +66,72 @@ pub async fn build(pool: PgPool) {
 
     let listener = TcpListener::bind(&url).await.unwrap();
 
-    // Create the router
-    let app = routes::create_router(pool);
+    // Purge expired tokens and old login attempts in the background
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let cleanup_task = CleanupTask::new(pool.clone());
+    let health_state = HealthState::new(pool.clone()).with_task(cleanup_task.heartbeat());
+    let cleanup = cleanup_task.spawn(shutdown_rx.clone());
+
+    // Evict cached token epochs as soon as another server moves them; without the
+    // listener they are only picked up once TOKEN_EPOCH_CACHE_TTL_SECS runs out
+    let token_epochs = TokenEpochCache::new(get_token_epoch_cache_ttl());
+    let epoch_listener = match TokenEpochListener::connect(&pool, token_epochs.clone()).await {
+        Ok(listener) => Some(listener.spawn(shutdown_rx.clone())),
+        Err(e) => {
+            warn!(error = %e, "Token epoch notifications unavailable, relying on the cache TTL");
+            None
+        }
+    };
+
+    // Load signing keys rotated on other servers as soon as they change; without
+    // the listener they are only picked up on restart
+    let signing_keys = SigningKeyReload::new();
+    let key_listener = match SigningKeyListener::connect(&pool, signing_keys.clone()).await {
+        Ok(listener) => Some(listener.spawn(shutdown_rx)),
+        Err(e) => {
+            warn!(error = %e, "Signing key notifications unavailable, rotations apply on restart");
+            None
+        }
+    };
+
+    // Create the router; the probes sit outside the auth and rate limiting layers
+    let app = routes::create_router_with_listeners(pool, token_epochs, signing_keys)
+        .merge(health::routes(health_state));
+
+    // CSP, HSTS and the other security headers, from the SECURITY_* variables
+    let security_headers =
+        SecurityHeadersConfig::from_env().expect("Invalid security header configuration");
 
     // Add security middleware layers
     let app = app.layer(
         ServiceBuilder::new()
             .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(RequestIdScopeLayer)
             .layer(PropagateRequestIdLayer::x_request_id())
             .layer(TraceLayer::new_for_http())
-            .layer(SecurityHeadersLayer)
+            .layer(SecurityHeadersLayer::new(&security_headers))
             .layer(get_cors_layer()),
     );
 
     println!("Server running on {}", &url);
+    if !AppEnvironment::from_env().is_production() {
+        println!("APP_ENV is development: auth cookies are sent without the Secure flag");
+    }
+
+    // On SIGTERM or Ctrl+C, stop accepting connections and give open requests
+    // SHUTDOWN_TIMEOUT_SECS to finish
+    serve_with_graceful_shutdown(listener, app, shutdown_signal(), get_shutdown_timeout())
+        .await
+        .unwrap();
 
-    axum::serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>()
-    ).await.unwrap();
+    // Let an in-flight cleanup cycle finish before exiting
+    let _ = shutdown_tx.send(true);
+    let _ = cleanup.await;
+    if let Some(epoch_listener) = epoch_listener {
+        let _ = epoch_listener.await;
+    }
+    if let Some(key_listener) = key_listener {
+        let _ = key_listener.await;
+    }
+    info!("Shutdown complete");
 }
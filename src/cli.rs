@@ -0,0 +1,207 @@
+// Command line entry point. `serve` (also the default with no subcommand) runs
+// the HTTP server; the others are one-off maintenance jobs against the same database.
+use crate::app::hashing::Argon2Params;
+use crate::app::maintenance::{CleanupReport, CleanupTask};
+use crate::app::models::auth::{AuthenticationError, FieldError, RegisterRequest};
+use crate::app::models::client::Client;
+use crate::app::models::jwt::{JwtError, RevocationOutcome};
+use crate::app::models::role::ADMIN_ROLE;
+use crate::app::models::user::UserResponse;
+use crate::app::repositories::client_repository::ClientRepository;
+use crate::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use crate::app::repositories::password_history_repository::PasswordHistoryRepository;
+use crate::app::repositories::password_reset_repository::PasswordResetRepository;
+use crate::app::repositories::role_repository::RoleRepository;
+use crate::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use crate::app::repositories::user_repository::UserRepository;
+use crate::app::services::auth_service::AuthService;
+use crate::app::services::email_service::MockEmailService;
+use crate::app::services::jwt_service::{JwtService, get_jwt_secret};
+use crate::build;
+use clap::{Parser, Subcommand};
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+
+#[derive(Debug, Parser)]
+#[command(name = "chronos", version, about = "Chronos time tracking API")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server
+    Serve,
+    /// Create a verified user with the admin role
+    CreateAdmin {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        password: String,
+    },
+    /// Register a client app that logins can ask audience-restricted tokens for
+    CreateClient {
+        client_id: String,
+        #[arg(long)]
+        name: String,
+        /// May be given more than once
+        #[arg(long = "redirect-uri")]
+        redirect_uris: Vec<String>,
+        /// Resource servers besides the client itself; may be given more than once
+        #[arg(long = "audience")]
+        audiences: Vec<String>,
+    },
+    /// Purge expired tokens and old login attempts once
+    CleanupTokens,
+    /// Revoke every access and refresh token issued to a user
+    RevokeUser { user_id: Uuid },
+}
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Registration(String),
+    #[error("Role '{0}' does not exist; have the migrations run?")]
+    MissingRole(&'static str),
+    #[error("User {0} not found")]
+    UserNotFound(Uuid),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Token(#[from] JwtError),
+}
+
+impl From<AuthenticationError> for CliError {
+    fn from(error: AuthenticationError) -> Self {
+        let details = error.field_errors();
+        if details.is_empty() {
+            CliError::Registration(error.message())
+        } else {
+            let details: Vec<String> = details.iter().map(FieldError::to_string).collect();
+            CliError::Registration(format!("{}: {}", error.message(), details.join("; ")))
+        }
+    }
+}
+
+pub async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let pool = build::postgres::build().await?;
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => build::web::build(pool).await,
+        Command::CreateAdmin { email, password } => {
+            let user = create_admin(pool, email, password).await?;
+            println!("Created admin {} ({})", user.email, user.id);
+        }
+        Command::CreateClient {
+            client_id,
+            name,
+            redirect_uris,
+            audiences,
+        } => {
+            let client = create_client(pool, client_id, name, redirect_uris, audiences).await?;
+            println!(
+                "Created client {} with audience {}",
+                client.client_id,
+                client.token_audience().join(", ")
+            );
+        }
+        Command::CleanupTokens => {
+            let report = cleanup_tokens(pool).await?;
+            println!(
+                "Removed {} rows: {} blacklisted tokens, {} refresh tokens, {} password reset tokens, {} SMS codes, {} email sign-in codes, {} trusted devices, {} login attempts",
+                report.total(),
+                report.blacklisted_tokens,
+                report.refresh_tokens,
+                report.password_reset_tokens,
+                report.otp_challenges,
+                report.email_login_codes,
+                report.trusted_devices,
+                report.login_attempts
+            );
+        }
+        Command::RevokeUser { user_id } => {
+            let outcome = revoke_user(pool, user_id).await?;
+            println!("Revoked {} sessions for user {}", outcome.revoked, user_id);
+            if !outcome.complete {
+                eprintln!(
+                    "Some refresh tokens could not be revoked: {}",
+                    outcome.error.unwrap_or_default()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Goes through registration so the email and password rules still apply
+pub async fn create_admin(
+    pool: PgPool,
+    email: String,
+    password: String,
+) -> Result<UserResponse, CliError> {
+    let user_repository = UserRepository::new(pool.clone());
+    let role_repository = RoleRepository::new(pool.clone());
+    let admin_role = role_repository
+        .find_by_name(ADMIN_ROLE)
+        .await?
+        .ok_or(CliError::MissingRole(ADMIN_ROLE))?;
+
+    let argon2_params = Argon2Params::from_env()
+        .map_err(|e| CliError::Registration(format!("Invalid Argon2 configuration: {}", e)))?;
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool),
+        MockEmailService::new(),
+    )
+    .with_argon2_params(argon2_params);
+
+    let user = auth_service
+        .register(RegisterRequest {
+            name: None,
+            email,
+            username: None,
+            password,
+            captcha_token: None,
+        })
+        .await?
+        .user;
+
+    user_repository.mark_verified(user.id).await?;
+    role_repository.grant_role(user.id, admin_role.id).await?;
+
+    Ok(user)
+}
+
+pub async fn create_client(
+    pool: PgPool,
+    client_id: String,
+    name: String,
+    redirect_uris: Vec<String>,
+    audiences: Vec<String>,
+) -> Result<Client, CliError> {
+    Ok(ClientRepository::new(pool)
+        .create(&client_id, &name, &redirect_uris, &audiences)
+        .await?)
+}
+
+pub async fn cleanup_tokens(pool: PgPool) -> Result<CleanupReport, CliError> {
+    Ok(CleanupTask::new(pool).run_once().await?)
+}
+
+pub async fn revoke_user(pool: PgPool, user_id: Uuid) -> Result<RevocationOutcome, CliError> {
+    let jwt_service = JwtService::new(
+        &get_jwt_secret(),
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_user_repository(UserRepository::new(pool));
+
+    jwt_service
+        .invalidate_user_tokens(user_id)
+        .await?
+        .ok_or(CliError::UserNotFound(user_id))
+}
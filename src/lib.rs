@@ -1,3 +1,6 @@
+pub mod api_types;
 pub mod app;
 pub mod build;
+#[cfg(feature = "server")]
+pub mod cli;
 pub mod routes;
@@ -1,12 +1,13 @@
-mod app;
-mod build;
-mod routes;
+use chronos::cli::{self, Cli};
+use clap::Parser;
 
 #[tokio::main]
 async fn main() {
     let _ = dotenvy::dotenv();
-    let pool = build::postgres::build()
-        .await
-        .expect("Failed to connect to Database");
-    build::web::build(pool).await;
+    let cli = Cli::parse();
+
+    if let Err(e) = cli::run(cli).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
 }
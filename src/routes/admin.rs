@@ -0,0 +1,588 @@
+use crate::app::hashing::{Argon2Params, PasswordHasher};
+use crate::app::metrics;
+use crate::app::middleware::auth_middleware::AuthUser;
+use crate::app::middleware::security::log_security_event;
+use crate::app::models::admin_audit::{
+    ADMIN_DISABLE_USER_ACTION, ADMIN_ENABLE_USER_ACTION, ADMIN_IMPORT_USERS_ACTION,
+    ADMIN_PASSWORD_RESET_ACTION, ADMIN_REVOKE_TOKENS_ACTION, ADMIN_ROTATE_SIGNING_KEY_ACTION,
+    AdminKeyRotationRequest, AdminKeyRotationResponse, AdminPasswordResetResponse,
+    AdminStatsResponse, AdminTokenRevocationResponse, AdminUserImportQuery,
+    AdminUserImportResponse, AdminUserImportResult, AdminUserImportRow, AdminUserStatusResponse,
+    ImportDuplicatePolicy,
+};
+use crate::app::models::auth::{AuthenticationError, normalize_email};
+use crate::app::models::common::{ErrorResponse, Paginated, Pagination};
+use crate::app::models::jwt::AuthContext;
+use crate::app::models::role::{Role, RoleAssignmentRequest, UserRolesResponse};
+use crate::app::models::user::{
+    AdminUserListQuery, User, UserListFilter, UserResponse, UserSort, UserStatus,
+};
+use crate::app::repositories::admin_audit_repository::AdminAuditRepository;
+use crate::app::repositories::login_attempt_repository::{
+    LoginAttemptRepository, RefreshTokenRepository,
+};
+use crate::app::repositories::password_history_repository::PasswordHistoryRepository;
+use crate::app::repositories::password_reset_repository::PasswordResetRepository;
+use crate::app::repositories::role_repository::RoleRepository;
+use crate::app::repositories::user_repository::UserRepository;
+use crate::app::services::auth_service::{AuthService, get_username_login};
+use crate::app::services::email_service::MockEmailService;
+use crate::app::services::jwt_service::JwtService;
+use crate::app::services::user_service::{UserService, UserServiceError};
+use axum::{
+    Json, Router,
+    extract::{ConnectInfo, Path, Query, State},
+    http::StatusCode,
+    routing::{get, post},
+};
+use sqlx::PgPool;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+use validator::Validate;
+
+// Rows per import request, and rows written per transaction
+const MAX_IMPORT_ROWS: usize = 1000;
+const IMPORT_CHUNK_SIZE: usize = 100;
+
+#[derive(Clone)]
+pub struct AdminState {
+    role_repository: RoleRepository,
+    user_repository: UserRepository,
+    audit_repository: AdminAuditRepository,
+    refresh_token_repository: RefreshTokenRepository,
+    login_attempt_repository: LoginAttemptRepository,
+    auth_service: AuthService,
+    user_service: Arc<UserService>,
+    jwt_service: Option<Arc<JwtService>>,
+    argon2_params: Argon2Params,
+}
+
+impl AdminState {
+    pub fn new(pool: PgPool) -> Self {
+        let auth_service = AuthService::new(
+            UserRepository::new(pool.clone()),
+            PasswordResetRepository::new(pool.clone()),
+            PasswordHistoryRepository::new(pool.clone()),
+            MockEmailService::new(),
+        );
+
+        Self {
+            role_repository: RoleRepository::new(pool.clone()),
+            user_repository: UserRepository::new(pool.clone()),
+            user_service: Arc::new(UserService::new(UserRepository::new(pool.clone()))),
+            refresh_token_repository: RefreshTokenRepository::new(pool.clone()),
+            login_attempt_repository: LoginAttemptRepository::new(pool.clone()),
+            audit_repository: AdminAuditRepository::new(pool),
+            auth_service,
+            jwt_service: None,
+            argon2_params: Argon2Params::default(),
+        }
+    }
+
+    // Cost of the hashes made for imported plaintext passwords
+    pub fn with_argon2_params(mut self, argon2_params: Argon2Params) -> Self {
+        self.argon2_params = argon2_params;
+        self
+    }
+
+    // Enables revoking a user's tokens; it has to share the token epoch cache
+    // the JWT middleware checks against
+    pub fn with_jwt_service(mut self, jwt_service: JwtService) -> Self {
+        self.jwt_service = Some(Arc::new(jwt_service));
+        self
+    }
+}
+
+pub fn routes() -> Router<AdminState> {
+    Router::new()
+        .route("/stats", get(stats))
+        .route("/users", get(list_users))
+        .route("/users/import", post(import_users))
+        .route("/users/{id}/roles", post(grant_role).delete(revoke_role))
+        .route("/users/{id}/password-reset", post(reset_user_password))
+        .route("/users/{id}/disable", post(disable_user))
+        .route("/users/{id}/enable", post(enable_user))
+        .route("/users/{id}/revoke-tokens", post(revoke_user_tokens))
+        .route("/jwt/rotate-key", post(rotate_signing_key))
+}
+
+fn error_response(status: StatusCode, error: impl ToString) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        status,
+        Json(ErrorResponse {
+            error: error.to_string(),
+        }),
+    )
+}
+
+// Newest users first unless `sort` says otherwise; `search` matches name or email
+async fn list_users(
+    State(state): State<AdminState>,
+    Query(query): Query<AdminUserListQuery>,
+) -> Result<Json<Paginated<UserResponse>>, (StatusCode, Json<ErrorResponse>)> {
+    let sort = match query.sort.as_deref() {
+        Some(sort) => UserSort::parse(sort).ok_or_else(|| {
+            error_response(
+                StatusCode::BAD_REQUEST,
+                "Invalid sort; use created_at, email or name, with a leading - for descending",
+            )
+        })?,
+        None => UserSort::default(),
+    };
+    let filter = UserListFilter {
+        search: query.search,
+        sort,
+    };
+    let pagination = Pagination::new(query.page, query.per_page);
+
+    let (users, total) = state
+        .user_repository
+        .list(&filter, pagination)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    Ok(Json(Paginated {
+        items: users.iter().map(User::to_response).collect(),
+        total,
+        page: pagination.page,
+        per_page: pagination.per_page,
+    }))
+}
+
+// Bulk-create users migrated from another system. Every row is validated and
+// hashed first, then the valid ones are written IMPORT_CHUNK_SIZE per
+// transaction. Bad rows are reported in the results without stopping the rest.
+async fn import_users(
+    State(state): State<AdminState>,
+    AuthUser(admin): AuthUser,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(query): Query<AdminUserImportQuery>,
+    Json(rows): Json<Vec<AdminUserImportRow>>,
+) -> Result<Json<AdminUserImportResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if rows.is_empty() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "No users to import",
+        ));
+    }
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            format!("At most {} users can be imported at once", MAX_IMPORT_ROWS),
+        ));
+    }
+
+    let hasher = PasswordHasher::new(state.argon2_params);
+    let usernames_enabled = get_username_login();
+    let mut results = Vec::with_capacity(rows.len());
+    let mut users = Vec::new();
+    let mut user_rows = Vec::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        let email = normalize_email(&row.email);
+        match row.into_user(&hasher, usernames_enabled).await {
+            Ok(user) => {
+                users.push(user);
+                user_rows.push(index);
+            }
+            Err(error) => results.push(AdminUserImportResult::failed(index, email, error)),
+        }
+    }
+
+    let update_existing = query.on_duplicate == ImportDuplicatePolicy::Update;
+    for (chunk, indexes) in users
+        .chunks(IMPORT_CHUNK_SIZE)
+        .zip(user_rows.chunks(IMPORT_CHUNK_SIZE))
+    {
+        match state.user_repository.import(chunk, update_existing).await {
+            Ok(outcomes) => {
+                for ((user, index), outcome) in chunk.iter().zip(indexes).zip(outcomes) {
+                    results.push(AdminUserImportResult::from_outcome(
+                        *index,
+                        user.email.clone(),
+                        outcome,
+                    ));
+                }
+            }
+            // The chunk was rolled back; earlier chunks stay imported
+            Err(e) => {
+                for (user, index) in chunk.iter().zip(indexes) {
+                    results.push(AdminUserImportResult::failed(
+                        *index,
+                        user.email.clone(),
+                        format!("Import failed: {}", e),
+                    ));
+                }
+            }
+        }
+    }
+    results.sort_by_key(|result| result.index);
+
+    let response = AdminUserImportResponse::new(results);
+    if let Err(e) = state
+        .audit_repository
+        .record(
+            admin.user_id,
+            ADMIN_IMPORT_USERS_ACTION,
+            None,
+            Some(&response.message),
+        )
+        .await
+    {
+        return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+
+    log_security_event(
+        ADMIN_IMPORT_USERS_ACTION,
+        &addr.ip().to_string(),
+        None,
+        Some(&admin.user_id.to_string()),
+        Some(&admin.email),
+        true,
+        Some(&response.message),
+    );
+
+    Ok(Json(response))
+}
+
+// Counts for the admin dashboard; each one is a single indexed COUNT query
+async fn stats(
+    State(state): State<AdminState>,
+) -> Result<Json<AdminStatsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let now = OffsetDateTime::now_utc();
+    let internal_error = |e: String| error_response(StatusCode::INTERNAL_SERVER_ERROR, e);
+
+    let user_service = &state.user_service;
+    let registered_since = |ago: Duration| async move {
+        user_service
+            .count_since(now - ago)
+            .await
+            .map_err(|e| internal_error(e.to_string()))
+    };
+
+    Ok(Json(AdminStatsResponse {
+        total_users: user_service
+            .count()
+            .await
+            .map_err(|e| internal_error(e.to_string()))?,
+        new_users_24h: registered_since(Duration::days(1)).await?,
+        new_users_7d: registered_since(Duration::days(7)).await?,
+        new_users_30d: registered_since(Duration::days(30)).await?,
+        active_sessions: state
+            .refresh_token_repository
+            .active_count()
+            .await
+            .map_err(|e| internal_error(e.to_string()))?,
+        failed_logins_24h: state
+            .login_attempt_repository
+            .failed_count_since(now - Duration::days(1))
+            .await
+            .map_err(|e| internal_error(e.to_string()))?,
+    }))
+}
+
+// Validate the request and make sure both the user and the role exist
+async fn resolve_assignment(
+    state: &AdminState,
+    user_id: Uuid,
+    request: &RoleAssignmentRequest,
+) -> Result<Role, (StatusCode, Json<ErrorResponse>)> {
+    if let Err(e) = request.validate() {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            format!("Validation error: {}", e),
+        ));
+    }
+
+    match state.user_repository.find_by_id(user_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(error_response(StatusCode::NOT_FOUND, "User not found")),
+        Err(e) => return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+
+    match state.role_repository.find_by_name(&request.role).await {
+        Ok(Some(role)) => Ok(role),
+        Ok(None) => Err(error_response(StatusCode::NOT_FOUND, "Role not found")),
+        Err(e) => Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+async fn user_roles_response(
+    state: &AdminState,
+    user_id: Uuid,
+) -> Result<Json<UserRolesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    match state.role_repository.get_user_roles(user_id).await {
+        Ok(roles) => Ok(Json(UserRolesResponse { user_id, roles })),
+        Err(e) => Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+async fn grant_role(
+    State(state): State<AdminState>,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<RoleAssignmentRequest>,
+) -> Result<Json<UserRolesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let role = resolve_assignment(&state, user_id, &request).await?;
+
+    if let Err(e) = state.role_repository.grant_role(user_id, role.id).await {
+        return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+
+    user_roles_response(&state, user_id).await
+}
+
+async fn revoke_role(
+    State(state): State<AdminState>,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<RoleAssignmentRequest>,
+) -> Result<Json<UserRolesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let role = resolve_assignment(&state, user_id, &request).await?;
+
+    match state.role_repository.revoke_role(user_id, role.id).await {
+        Ok(true) => user_roles_response(&state, user_id).await,
+        Ok(false) => Err(error_response(
+            StatusCode::NOT_FOUND,
+            "User does not have this role",
+        )),
+        Err(e) => Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+// Issue a reset token on a user's behalf, e.g. when they can't reach their inbox.
+// The audit entry is written first so a token is never handed out unrecorded.
+async fn reset_user_password(
+    State(state): State<AdminState>,
+    AuthUser(admin): AuthUser,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminPasswordResetResponse>, AuthenticationError> {
+    if state.user_repository.find_by_id(user_id).await?.is_none() {
+        return Err(AuthenticationError::UserNotFound);
+    }
+
+    state
+        .audit_repository
+        .record(
+            admin.user_id,
+            ADMIN_PASSWORD_RESET_ACTION,
+            Some(user_id),
+            Some("Reset token issued by admin"),
+        )
+        .await?;
+
+    let (plain_token, reset_token) = state
+        .auth_service
+        .create_reset_token_for_user(user_id)
+        .await?;
+
+    log_security_event(
+        ADMIN_PASSWORD_RESET_ACTION,
+        &addr.ip().to_string(),
+        None,
+        Some(&admin.user_id.to_string()),
+        Some(&admin.email),
+        true,
+        Some(&format!("Reset token issued for user {}", user_id)),
+    );
+    metrics::record_password_reset("admin_requested");
+
+    Ok(Json(AdminPasswordResetResponse {
+        message: "Password reset token issued".to_string(),
+        user_id,
+        reset_token: plain_token,
+        expires_at: reset_token.expires_at,
+    }))
+}
+
+// Logs the user out everywhere at once, e.g. after their account was compromised:
+// access tokens stop working immediately rather than when they expire
+async fn revoke_user_tokens(
+    State(state): State<AdminState>,
+    AuthUser(admin): AuthUser,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminTokenRevocationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let jwt_service = state.jwt_service.as_deref().ok_or_else(|| {
+        error_response(StatusCode::NOT_FOUND, "Token revocation is not configured")
+    })?;
+
+    let outcome = match jwt_service.invalidate_user_tokens(user_id).await {
+        Ok(Some(outcome)) => outcome,
+        Ok(None) => return Err(error_response(StatusCode::NOT_FOUND, "User not found")),
+        Err(e) => return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    };
+
+    let message = "All tokens revoked";
+    if let Err(e) = state
+        .audit_repository
+        .record(
+            admin.user_id,
+            ADMIN_REVOKE_TOKENS_ACTION,
+            Some(user_id),
+            Some(message),
+        )
+        .await
+    {
+        return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+
+    log_security_event(
+        ADMIN_REVOKE_TOKENS_ACTION,
+        &addr.ip().to_string(),
+        None,
+        Some(&admin.user_id.to_string()),
+        Some(&admin.email),
+        true,
+        Some(&format!(
+            "{} for user {}: {} sessions",
+            message, user_id, outcome.revoked
+        )),
+    );
+
+    Ok(Json(AdminTokenRevocationResponse {
+        message: message.to_string(),
+        user_id,
+        revoked_sessions: outcome.revoked,
+        complete: outcome.complete,
+    }))
+}
+
+// Incident response: sign new tokens with a fresh key without a redeploy.
+// Old tokens keep working unless `revoke_old` drops their keys as well.
+async fn rotate_signing_key(
+    State(state): State<AdminState>,
+    AuthUser(admin): AuthUser,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Option<Json<AdminKeyRotationRequest>>,
+) -> Result<Json<AdminKeyRotationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let jwt_service = state
+        .jwt_service
+        .as_deref()
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, "Key rotation is not configured"))?;
+    let Json(request) = request.unwrap_or_default();
+
+    let rotation = jwt_service
+        .rotate_signing_key(request.revoke_old)
+        .await
+        .map_err(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let message = if rotation.revoked.is_empty() {
+        "Signing key rotated"
+    } else {
+        "Signing key rotated and old keys revoked"
+    };
+    let details = format!(
+        "{}: new kid {}, revoked [{}]",
+        message,
+        rotation.kid,
+        rotation.revoked.join(", ")
+    );
+    if let Err(e) = state
+        .audit_repository
+        .record(
+            admin.user_id,
+            ADMIN_ROTATE_SIGNING_KEY_ACTION,
+            None,
+            Some(&details),
+        )
+        .await
+    {
+        return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+
+    log_security_event(
+        ADMIN_ROTATE_SIGNING_KEY_ACTION,
+        &addr.ip().to_string(),
+        None,
+        Some(&admin.user_id.to_string()),
+        Some(&admin.email),
+        true,
+        Some(&details),
+    );
+
+    Ok(Json(AdminKeyRotationResponse {
+        message: message.to_string(),
+        kid: rotation.kid,
+        revoked_kids: rotation.revoked,
+    }))
+}
+
+async fn disable_user(
+    State(state): State<AdminState>,
+    AuthUser(admin): AuthUser,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminUserStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
+    // An admin who disables themselves may leave nobody to re-enable them
+    if user_id == admin.user_id {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            "Admins cannot disable their own account",
+        ));
+    }
+
+    set_user_status(&state, &admin, addr, user_id, UserStatus::Disabled).await
+}
+
+async fn enable_user(
+    State(state): State<AdminState>,
+    AuthUser(admin): AuthUser,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<AdminUserStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
+    set_user_status(&state, &admin, addr, user_id, UserStatus::Active).await
+}
+
+// Deleted users count as not found: they can't be disabled or brought back
+async fn set_user_status(
+    state: &AdminState,
+    admin: &AuthContext,
+    addr: SocketAddr,
+    user_id: Uuid,
+    status: UserStatus,
+) -> Result<Json<AdminUserStatusResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let (action, message, result) = match status {
+        UserStatus::Disabled => (
+            ADMIN_DISABLE_USER_ACTION,
+            "User disabled",
+            state.user_service.disable_user(user_id).await,
+        ),
+        _ => (
+            ADMIN_ENABLE_USER_ACTION,
+            "User enabled",
+            state.user_service.enable_user(user_id).await,
+        ),
+    };
+    match result {
+        Ok(()) => {}
+        Err(UserServiceError::UserNotFound) => {
+            return Err(error_response(StatusCode::NOT_FOUND, "User not found"));
+        }
+        Err(e) => return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+
+    if let Err(e) = state
+        .audit_repository
+        .record(admin.user_id, action, Some(user_id), Some(message))
+        .await
+    {
+        return Err(error_response(StatusCode::INTERNAL_SERVER_ERROR, e));
+    }
+
+    log_security_event(
+        action,
+        &addr.ip().to_string(),
+        None,
+        Some(&admin.user_id.to_string()),
+        Some(&admin.email),
+        true,
+        Some(&format!("{}: {}", message, user_id)),
+    );
+
+    Ok(Json(AdminUserStatusResponse {
+        message: message.to_string(),
+        user_id,
+        status,
+    }))
+}
@@ -1,30 +1,69 @@
-use crate::app::middleware::auth_middleware::AuthUser;
+use crate::app::cookies::{AppEnvironment, CookiePolicy, TokenDelivery, read_cookie};
+use crate::app::metrics;
+use crate::app::middleware::auth_middleware::{AuthUser, scoped};
 use crate::app::middleware::security::{
-    SecurityState, check_password_reset_rate_limit, check_refresh_rate_limit,
-    check_registration_rate_limit, log_security_event,
+    SecurityState, check_account_unlock_rate_limit, check_email_code_rate_limit,
+    check_lockout_status_rate_limit, check_login_rate_limit, check_password_reset_rate_limit,
+    check_password_strength_rate_limit, check_refresh_rate_limit, check_registration_rate_limit,
+    check_reset_token_validation_rate_limit, constant_time_eq, extract_real_ip,
+    generate_csrf_token, log_security_event, rate_limit_headers,
 };
+use crate::app::models::account_export::AccountExport;
+use crate::app::models::api_key::{ApiKeyCreatedResponse, CreateApiKeyRequest, normalize_scopes};
 use crate::app::models::auth::{
-    AuthError, ChangePasswordRequest, ChangePasswordResponse, ForgotPasswordRequest,
-    ForgotPasswordResponse, ProfileResponse, ProfileUpdateRequest, RegisterRequest,
-    RegisterResponse, ResetPasswordRequest, ResetPasswordResponse,
+    ACCOUNT_UNLOCK_MESSAGE, AccountUnlockRequest, AccountUnlockResponse, AuthErrorResponse,
+    AuthenticationError, ChangeEmailRequest, ChangeEmailResponse, ChangePasswordRequest,
+    ChangePasswordResponse, ConfirmEmailChangeQuery, ConfirmEmailChangeResponse,
+    ConsumeUnlockTokenQuery, DeleteAccountRequest, ForgotPasswordRequest, ForgotPasswordResponse,
+    LockoutStatusQuery, LockoutStatusResponse, MeResponse, PasswordPolicy, PasswordStrengthRequest,
+    ProfileResponse, ProfileUpdateRequest, REGISTRATION_ACCEPTED_MESSAGE, RegisterRequest,
+    RegisterResponse, RegistrationAcceptedResponse, ResetPasswordRequest, ResetPasswordResponse,
+    StrengthReport, ValidateResetTokenQuery, ValidateResetTokenResponse, normalize_email,
+    password_strength,
 };
+use crate::app::models::common::{Paginated, Pagination};
 use crate::app::models::jwt::{
-    JwtError, LoginRequest, LoginResponse, LogoutRequest, LogoutResponse, RefreshTokenRequest,
-    RefreshTokenResponse,
+    CsrfTokenResponse, JwtError, LoginRequest, LoginResponse, LogoutRequest, LogoutResponse,
+    RefreshTokenRequest, RefreshTokenResponse, TokenErrorResponse, TokenType,
 };
-use crate::app::services::auth_service::AuthService;
+use crate::app::models::login_attempt::{LoginHistoryEntry, LoginHistoryQuery, TokenOrigin};
+use crate::app::models::oauth::{OAuthCallbackQuery, OAuthError};
+use crate::app::models::otp::{
+    EMAIL_CODE_MESSAGE, EmailCodeLoginRequest, EmailCodeRequest, EmailCodeResponse,
+    OtpChallengeResponse, OtpError, OtpLoginRequest, PhoneResponse, RemovePhoneRequest,
+    SetPhoneRequest, VerifyPhoneRequest, mask_phone_number, normalize_phone_number,
+};
+use crate::app::models::scope::{
+    ACCOUNT_WRITE, PASSWORD_CHANGE, PROFILE_READ, PROFILE_WRITE, SESSIONS_READ, SESSIONS_WRITE,
+};
+use crate::app::models::session::{
+    RenameSessionRequest, SessionListResponse, SessionResponse, normalize_device_name,
+};
+use crate::app::models::trusted_device::{TrustedDeviceListResponse, TrustedDeviceResponse};
+use crate::app::services::auth_service::{
+    AuthService, RegistrationMode, RegistrationOutcome, hash_confirmation_token,
+};
+use crate::app::services::captcha_service::CaptchaService;
 use crate::app::services::jwt_service::JwtService;
-use crate::app::services::secure_login_service::SecureLoginService;
-use crate::app::services::user_service::UserService;
+use crate::app::services::oauth_service::OAuthService;
+use crate::app::services::otp_service::OtpService;
+use crate::app::services::secure_login_service::{LoginOutcome, SecureLoginService};
+use crate::app::services::trusted_device_service::TrustedDeviceService;
+use crate::app::services::user_service::{UserService, UserServiceError};
 use axum::{
     Json, Router,
     extract::ConnectInfo,
+    extract::Path,
+    extract::Query,
     extract::State,
-    http::{HeaderMap, StatusCode},
-    routing::{get, post, put},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    routing::{delete, get, post, put},
 };
 use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 use validator::Validate;
 
@@ -35,6 +74,11 @@ pub struct AuthAppState {
     pub secure_login_service: Arc<SecureLoginService>,
     pub user_service: Arc<UserService>,
     pub security_state: Arc<SecurityState>,
+    pub cookie_policy: CookiePolicy,
+    pub google_oauth_service: Option<Arc<OAuthService>>,
+    pub github_oauth_service: Option<Arc<OAuthService>>,
+    pub captcha_service: Option<Arc<CaptchaService>>,
+    pub otp_service: Option<Arc<OtpService>>,
 }
 
 impl AuthAppState {
@@ -51,61 +95,285 @@ impl AuthAppState {
             secure_login_service: Arc::new(secure_login_service),
             user_service: Arc::new(user_service),
             security_state: Arc::new(security_state),
+            cookie_policy: CookiePolicy::for_environment(AppEnvironment::from_env()),
+            google_oauth_service: None,
+            github_oauth_service: None,
+            captcha_service: None,
+            otp_service: None,
         }
     }
+
+    pub fn with_cookie_policy(mut self, cookie_policy: CookiePolicy) -> Self {
+        self.cookie_policy = cookie_policy;
+        self
+    }
+
+    // Enables the Google login routes
+    pub fn with_google_oauth_service(mut self, oauth_service: OAuthService) -> Self {
+        self.google_oauth_service = Some(Arc::new(oauth_service));
+        self
+    }
+
+    // Enables the GitHub login routes
+    pub fn with_github_oauth_service(mut self, oauth_service: OAuthService) -> Self {
+        self.github_oauth_service = Some(Arc::new(oauth_service));
+        self
+    }
+
+    // Makes IPs with repeated failed logins or registrations solve a CAPTCHA
+    pub fn with_captcha_service(mut self, captcha_service: CaptchaService) -> Self {
+        self.captcha_service = Some(Arc::new(captcha_service));
+        self
+    }
+
+    // Enables the phone number routes; login codes are sent by the
+    // SecureLoginService, which needs its own with_otp_service
+    pub fn with_otp_service(mut self, otp_service: OtpService) -> Self {
+        self.otp_service = Some(Arc::new(otp_service));
+        self
+    }
 }
 
 pub fn routes() -> Router<AuthAppState> {
     let public_routes = Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
+        .route("/login/otp", post(login_otp))
+        .route("/email-otp/request", post(request_email_code))
+        .route("/email-otp/verify", post(login_email_code))
         .route("/forgot-password", post(forgot_password))
         .route("/reset-password", post(reset_password))
-        .route("/refresh", post(refresh_token));
+        .route("/reset-password/validate", get(validate_reset_token))
+        .route("/confirm-email-change", get(confirm_email_change))
+        .route("/lockout-status", get(lockout_status))
+        .route("/unlock", post(request_unlock))
+        .route("/unlock/consume", get(consume_unlock_token))
+        .route("/password/policy", get(password_policy))
+        .route("/password/strength", post(password_strength_check))
+        .route("/refresh", post(refresh_token))
+        .route("/csrf", get(csrf_token))
+        .route("/oauth/google/start", get(google_oauth_start))
+        .route("/oauth/google/callback", get(google_oauth_callback))
+        .route("/oauth/github/start", get(github_oauth_start))
+        .route("/oauth/github/callback", get(github_oauth_callback));
 
-    Router::new().merge(public_routes)
+    // Every rate-limited endpoint is public, so only these report X-RateLimit-*
+    Router::new()
+        .merge(public_routes)
+        .layer(middleware::from_fn(rate_limit_headers))
 }
 
+// Logout needs no scope, so any token or key can always end its own session
 pub fn protected_routes() -> Router<AuthAppState> {
     Router::new()
         .route("/logout", post(logout))
-        .route("/profile", get(get_profile))
-        .route("/profile", put(update_profile))
-        .route("/change-password", post(change_password))
-}
-
-fn extract_real_ip(addr: SocketAddr, headers: &HeaderMap) -> String {
-    // Check for forwarded IP headers first (for proxy/load balancer scenarios)
-    if let Some(forwarded_for) = headers.get("x-forwarded-for") {
-        if let Ok(forwarded_str) = forwarded_for.to_str() {
-            // x-forwarded-for can contain multiple IPs, take the first one
-            if let Some(first_ip) = forwarded_str.split(',').next() {
-                return first_ip.trim().to_string();
-            }
-        }
+        .route("/profile", scoped(PROFILE_READ, get(get_profile)))
+        .route("/me", scoped(PROFILE_READ, get(get_me)))
+        .route("/profile", scoped(PROFILE_WRITE, put(update_profile)))
+        .route("/change-email", scoped(ACCOUNT_WRITE, post(change_email)))
+        .route(
+            "/change-password",
+            scoped(PASSWORD_CHANGE, post(change_password)),
+        )
+        .route("/account", scoped(ACCOUNT_WRITE, delete(delete_account)))
+        .route("/account/export", scoped(PROFILE_READ, get(export_account)))
+        .route("/sessions", scoped(SESSIONS_READ, get(list_sessions)))
+        .route(
+            "/sessions/{jti}/name",
+            scoped(SESSIONS_WRITE, put(rename_session)),
+        )
+        .route("/api-keys", scoped(ACCOUNT_WRITE, post(create_api_key)))
+        .route(
+            "/api-keys/{id}",
+            scoped(ACCOUNT_WRITE, delete(revoke_api_key)),
+        )
+        .route("/login-history", scoped(SESSIONS_READ, get(login_history)))
+        .route("/phone", scoped(PROFILE_READ, get(get_phone)))
+        .route("/phone", scoped(ACCOUNT_WRITE, put(set_phone)))
+        .route("/phone", scoped(ACCOUNT_WRITE, delete(remove_phone)))
+        .route("/phone/verify", scoped(ACCOUNT_WRITE, post(verify_phone)))
+        .route(
+            "/trusted-devices",
+            scoped(SESSIONS_READ, get(list_trusted_devices)),
+        )
+        .route(
+            "/trusted-devices/{id}",
+            scoped(SESSIONS_WRITE, delete(revoke_trusted_device)),
+        )
+}
+
+// No-op unless CAPTCHA is configured and `ip` has reached the failure threshold
+async fn check_captcha(
+    state: &AuthAppState,
+    ip: &str,
+    token: Option<&str>,
+) -> Result<(), AuthenticationError> {
+    let Some(captcha_service) = &state.captcha_service else {
+        return Ok(());
+    };
+    let recent_failures = state.security_state.failed_attempts.count(ip);
+    captcha_service.check(recent_failures, token, ip).await
+}
+
+// With cookie sessions, moves the refresh token out of the login response into
+// its cookie and issues the CSRF token the client has to echo from now on.
+// TOKEN_DELIVERY=cookie moves the access token into a cookie as well; `both`
+// sets both cookies and leaves the tokens in the body too.
+fn start_cookie_session(
+    state: &AuthAppState,
+    response: &mut LoginResponse,
+) -> Result<HeaderMap, AuthenticationError> {
+    let mut cookies = HeaderMap::new();
+    if !state.cookie_policy.sessions_enabled() {
+        return Ok(cookies);
     }
 
-    if let Some(real_ip) = headers.get("x-real-ip") {
-        if let Ok(real_ip_str) = real_ip.to_str() {
-            return real_ip_str.to_string();
-        }
+    let delivery = state.cookie_policy.token_delivery();
+    if delivery.uses_cookies() {
+        let access_token = take_unless_both(delivery, &mut response.tokens.access_token);
+        let max_age = Duration::from_secs(response.tokens.expires_in as u64);
+        cookies.append(
+            header::SET_COOKIE,
+            state
+                .cookie_policy
+                .access_cookie(&access_token, Some(max_age))
+                .map_err(cookie_error)?,
+        );
+    }
+
+    let refresh_token = take_unless_both(delivery, &mut response.tokens.refresh_token);
+    let max_age = Duration::from_secs(response.tokens.refresh_expires_in as u64);
+    let csrf_token = generate_csrf_token();
+    cookies.append(
+        header::SET_COOKIE,
+        state
+            .cookie_policy
+            .refresh_cookie(&refresh_token, Some(max_age))
+            .map_err(cookie_error)?,
+    );
+    cookies.append(
+        header::SET_COOKIE,
+        state
+            .cookie_policy
+            .csrf_cookie(&csrf_token)
+            .map_err(cookie_error)?,
+    );
+    response.csrf_token = Some(csrf_token);
+    Ok(cookies)
+}
+
+// The token to set as a cookie; it stays in the body only with TOKEN_DELIVERY=both
+fn take_unless_both(delivery: TokenDelivery, token: &mut String) -> String {
+    match delivery {
+        TokenDelivery::Both => token.clone(),
+        _ => std::mem::take(token),
+    }
+}
+
+fn cookie_error(error: header::InvalidHeaderValue) -> AuthenticationError {
+    AuthenticationError::Internal(format!("Failed to set session cookie: {}", error))
+}
+
+// The token in the request's trusted device cookie, if any
+fn trusted_device_token(state: &AuthAppState, headers: &HeaderMap) -> Option<String> {
+    read_cookie(headers, &state.cookie_policy.trusted_device_cookie_name())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_string())
+}
+
+// Trusts the device that just answered an SMS code. Failing to only costs the
+// user the SMS code on their next login, so it doesn't fail the login.
+async fn trust_device(
+    state: &AuthAppState,
+    user_id: Uuid,
+    ip_address: &str,
+    user_agent: Option<&str>,
+    cookies: &mut HeaderMap,
+) -> Result<(), AuthenticationError> {
+    let Some(service) = state.secure_login_service.trusted_device_service() else {
+        return Ok(());
+    };
+    let result = service
+        .trust(
+            user_id,
+            user_agent.map(|s| s.to_string()),
+            Some(ip_address.to_string()),
+        )
+        .await;
+    log_security_event(
+        "trusted_device_added",
+        ip_address,
+        user_agent,
+        Some(&user_id.to_string()),
+        None,
+        result.is_ok(),
+        result.as_ref().err().map(|e| e.to_string()).as_deref(),
+    );
+    if let Ok(token) = result {
+        let max_age = Duration::from_secs(service.ttl().whole_seconds().max(0) as u64);
+        cookies.append(
+            header::SET_COOKIE,
+            state
+                .cookie_policy
+                .trusted_device_cookie(&token, max_age)
+                .map_err(cookie_error)?,
+        );
     }
+    Ok(())
+}
 
-    // Fall back to connection IP
-    addr.ip().to_string()
+// After a login that presented a trusted device cookie: swaps its token for a
+// new one, or clears the cookie once the device is no longer trusted
+async fn renew_trusted_device(
+    state: &AuthAppState,
+    user_id: Uuid,
+    token: &str,
+    cookies: &mut HeaderMap,
+) -> Result<(), AuthenticationError> {
+    let Some(service) = state.secure_login_service.trusted_device_service() else {
+        return Ok(());
+    };
+    let cookie = match service.rotate(user_id, token).await {
+        Ok(Some((new_token, expires_at))) => {
+            let remaining = (expires_at - time::OffsetDateTime::now_utc()).whole_seconds();
+            state
+                .cookie_policy
+                .trusted_device_cookie(&new_token, Duration::from_secs(remaining.max(0) as u64))
+        }
+        Ok(None) => state.cookie_policy.clear_trusted_device_cookie(),
+        // The old token stays valid, so the cookie is left alone
+        Err(_) => return Ok(()),
+    };
+    cookies.append(header::SET_COOKIE, cookie.map_err(cookie_error)?);
+    Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/register",
+    tag = "auth",
+    request_body = RegisterRequest,
+    responses(
+        (status = 201, description = "Account created", body = RegisterResponse),
+        (status = 202, description = "Registration accepted (REGISTRATION_ENUMERATION_SAFE=true)", body = RegistrationAcceptedResponse),
+        (status = 400, description = "Validation failed", body = AuthErrorResponse),
+        (status = 403, description = "CAPTCHA required or failed", body = AuthErrorResponse),
+        (status = 409, description = "Email or username already registered", body = AuthErrorResponse),
+        (status = 429, description = "Too many registrations from this IP", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
 async fn register(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(request): Json<RegisterRequest>,
-) -> Result<(StatusCode, Json<RegisterResponse>), (StatusCode, Json<AuthError>)> {
+) -> Result<Response, AuthenticationError> {
     let ip_address = extract_real_ip(addr, &headers);
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
 
     // Check rate limiting for registration attempts
-    if let Err(response) = check_registration_rate_limit(&state.security_state, &ip_address) {
+    if let Err(error) = check_registration_rate_limit(&state.security_state, &ip_address).await {
         log_security_event(
             "registration_rate_limit_exceeded",
             &ip_address,
@@ -115,12 +383,24 @@ async fn register(
             false,
             Some("Rate limit exceeded"),
         );
-        return Err((
-            StatusCode::TOO_MANY_REQUESTS,
-            Json(AuthError::new(
-                "Too many registration attempts. Please try again later.",
-            )),
-        ));
+        return Err(error);
+    }
+
+    if let Err(error) = check_captcha(&state, &ip_address, request.captcha_token.as_deref()).await {
+        log_security_event(
+            "registration_captcha_failed",
+            &ip_address,
+            user_agent,
+            None,
+            Some(&request.email),
+            false,
+            Some(&error.to_string()),
+        );
+        return Err(error);
+    }
+
+    if state.auth_service.registration_mode() == RegistrationMode::EnumerationSafe {
+        return register_enumeration_safe(&state, request, &ip_address, user_agent).await;
     }
 
     match state.auth_service.register(request.clone()).await {
@@ -134,9 +414,11 @@ async fn register(
                 true,
                 None,
             );
-            Ok((StatusCode::CREATED, Json(response)))
+            metrics::record_registration();
+            Ok((StatusCode::CREATED, Json(response)).into_response())
         }
         Err(error) => {
+            state.security_state.failed_attempts.record(&ip_address);
             log_security_event(
                 "user_registration_failed",
                 &ip_address,
@@ -144,30 +426,97 @@ async fn register(
                 None,
                 Some(&request.email),
                 false,
-                Some(&error.error),
+                Some(&error.to_string()),
             );
-            let status_code = match error.error.as_str() {
-                "Email already registered" => StatusCode::CONFLICT,
-                "Validation failed" => StatusCode::BAD_REQUEST,
-                _ if error.error.contains("validation") => StatusCode::BAD_REQUEST,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            Err(error)
+        }
+    }
+}
+
+// Duplicate emails get the same 202 as new registrations; only the server log
+// records which one it was
+async fn register_enumeration_safe(
+    state: &AuthAppState,
+    request: RegisterRequest,
+    ip_address: &str,
+    user_agent: Option<&str>,
+) -> Result<Response, AuthenticationError> {
+    let email = request.email.clone();
+
+    match state.auth_service.register_enumeration_safe(request).await {
+        Ok(outcome) => {
+            match &outcome {
+                RegistrationOutcome::Created(user) => {
+                    log_security_event(
+                        "user_registration",
+                        ip_address,
+                        user_agent,
+                        Some(&user.id.to_string()),
+                        Some(&email),
+                        true,
+                        None,
+                    );
+                    metrics::record_registration();
+                }
+                RegistrationOutcome::AlreadyRegistered => {
+                    state.security_state.failed_attempts.record(ip_address);
+                    log_security_event(
+                        "user_registration_duplicate",
+                        ip_address,
+                        user_agent,
+                        None,
+                        Some(&email),
+                        false,
+                        Some("Email already registered"),
+                    );
+                }
+            }
+
+            let response = RegistrationAcceptedResponse {
+                message: REGISTRATION_ACCEPTED_MESSAGE.to_string(),
             };
-            Err((status_code, Json(error)))
+            Ok((StatusCode::ACCEPTED, Json(response)).into_response())
+        }
+        Err(error) => {
+            state.security_state.failed_attempts.record(ip_address);
+            log_security_event(
+                "user_registration_failed",
+                ip_address,
+                user_agent,
+                None,
+                Some(&email),
+                false,
+                Some(&error.to_string()),
+            );
+            Err(error)
         }
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/forgot-password",
+    tag = "auth",
+    request_body = ForgotPasswordRequest,
+    responses(
+        (status = 200, description = "Reset email sent if the account exists", body = ForgotPasswordResponse),
+        (status = 400, description = "Validation failed", body = AuthErrorResponse),
+        (status = 429, description = "Too many reset requests for this email", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
 async fn forgot_password(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     Json(request): Json<ForgotPasswordRequest>,
-) -> Result<(StatusCode, Json<ForgotPasswordResponse>), (StatusCode, Json<AuthError>)> {
+) -> Result<(StatusCode, Json<ForgotPasswordResponse>), AuthenticationError> {
     let ip_address = extract_real_ip(addr, &headers);
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
 
     // Check rate limiting for password reset attempts
-    if let Err(response) = check_password_reset_rate_limit(&state.security_state, &request.email) {
+    if let Err(error) = check_password_reset_rate_limit(&state.security_state, &request.email).await
+    {
         log_security_event(
             "password_reset_rate_limit_exceeded",
             &ip_address,
@@ -177,12 +526,7 @@ async fn forgot_password(
             false,
             Some("Rate limit exceeded"),
         );
-        return Err((
-            StatusCode::TOO_MANY_REQUESTS,
-            Json(AuthError::new(
-                "Too many password reset requests. Please try again later.",
-            )),
-        ));
+        return Err(error);
     }
 
     match state.auth_service.forgot_password(request.clone()).await {
@@ -196,6 +540,7 @@ async fn forgot_password(
                 true,
                 None,
             );
+            metrics::record_password_reset("requested");
             Ok((StatusCode::OK, Json(response)))
         }
         Err(error) => {
@@ -206,88 +551,310 @@ async fn forgot_password(
                 None,
                 Some(&request.email),
                 false,
-                Some(&error.error),
+                Some(&error.to_string()),
             );
-            let status_code = match error.error.as_str() {
-                "Too many password reset requests. Please try again later." => {
-                    StatusCode::TOO_MANY_REQUESTS
-                }
-                "Validation failed" => StatusCode::BAD_REQUEST,
-                _ if error.error.contains("validation") => StatusCode::BAD_REQUEST,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            };
-            Err((status_code, Json(error)))
+            Err(error)
         }
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/reset-password",
+    tag = "auth",
+    request_body = ResetPasswordRequest,
+    responses(
+        (status = 200, description = "Password reset", body = ResetPasswordResponse),
+        (status = 400, description = "Invalid or expired token, or password rejected", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
 async fn reset_password(
     State(state): State<AuthAppState>,
     Json(request): Json<ResetPasswordRequest>,
-) -> Result<(StatusCode, Json<ResetPasswordResponse>), (StatusCode, Json<AuthError>)> {
-    match state.auth_service.reset_password(request).await {
-        Ok(response) => Ok((StatusCode::OK, Json(response))),
+) -> Result<(StatusCode, Json<ResetPasswordResponse>), AuthenticationError> {
+    let response = state.auth_service.reset_password(request).await?;
+    metrics::record_password_reset("completed");
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/reset-password/validate",
+    tag = "auth",
+    params(ValidateResetTokenQuery),
+    responses(
+        (status = 200, description = "Token can be used; it is not consumed", body = ValidateResetTokenResponse),
+        (status = 400, description = "Invalid, used or expired token", body = AuthErrorResponse),
+        (status = 429, description = "Rate limit exceeded", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn validate_reset_token(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<ValidateResetTokenQuery>,
+) -> Result<Json<ValidateResetTokenResponse>, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+
+    // Limited per IP so the endpoint can't be used to guess tokens
+    if let Err(error) =
+        check_reset_token_validation_rate_limit(&state.security_state, &ip_address).await
+    {
+        log_security_event(
+            "password_reset_validate_rate_limit_exceeded",
+            &ip_address,
+            user_agent,
+            None,
+            None,
+            false,
+            Some("Rate limit exceeded"),
+        );
+        return Err(error);
+    }
+
+    match state.auth_service.validate_reset_token(&query.token).await {
+        Ok(()) => Ok(Json(ValidateResetTokenResponse { valid: true })),
         Err(error) => {
-            let status_code = match error.error.as_str() {
-                "Invalid or expired reset token" => StatusCode::BAD_REQUEST,
-                "Validation failed" => StatusCode::BAD_REQUEST,
-                _ if error.error.contains("validation") => StatusCode::BAD_REQUEST,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            };
-            Err((status_code, Json(error)))
+            log_security_event(
+                "password_reset_token_rejected",
+                &ip_address,
+                user_agent,
+                None,
+                None,
+                false,
+                Some(error.code()),
+            );
+            Err(error)
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/auth/password/policy",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The rules new passwords must meet", body = PasswordPolicy),
+    ),
+)]
+async fn password_policy(State(state): State<AuthAppState>) -> (HeaderMap, Json<PasswordPolicy>) {
+    // Only changes with a restart, so clients and proxies may keep it for an hour
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=3600"),
+    );
+    (headers, Json(state.auth_service.password_policy()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/strength",
+    tag = "auth",
+    request_body = PasswordStrengthRequest,
+    responses(
+        (status = 200, description = "Strength estimate; nothing is stored", body = StrengthReport),
+        (status = 429, description = "Rate limit exceeded", body = AuthErrorResponse),
+    ),
+)]
+async fn password_strength_check(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<PasswordStrengthRequest>,
+) -> Result<Json<StrengthReport>, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+
+    // Called on every keystroke by password meters, so it gets its own, looser limit
+    if let Err(error) = check_password_strength_rate_limit(&state.security_state, &ip_address).await
+    {
+        let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+        log_security_event(
+            "password_strength_rate_limit_exceeded",
+            &ip_address,
+            user_agent,
+            None,
+            None,
+            false,
+            Some("Rate limit exceeded"),
+        );
+        return Err(error);
+    }
+
+    Ok(Json(password_strength(&request.password)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    params(
+        ("X-Device-Name" = Option<String>, Header, description = "Names the new session when the body has no device_name"),
+    ),
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 202, description = "Password accepted; an SMS code was sent and must be entered at /api/auth/login/otp", body = OtpChallengeResponse),
+        (status = 401, description = "Invalid email, username or password", body = AuthErrorResponse),
+        (status = 403, description = "CAPTCHA required or failed, or account disabled", body = AuthErrorResponse),
+        (status = 423, description = "Account temporarily locked", body = AuthErrorResponse),
+        (status = 429, description = "Too many logins from this IP or for this email, or too many failed attempts", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+        (status = 503, description = "The account needs an SMS code that can't be sent", body = AuthErrorResponse),
+    ),
+)]
 async fn login(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Json(request): Json<LoginRequest>,
-) -> Result<(StatusCode, Json<LoginResponse>), (StatusCode, Json<AuthError>)> {
-    let ip_address = addr.ip().to_string();
+    Json(mut request): Json<LoginRequest>,
+) -> Result<Response, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+
+    if request.device_name.is_none() {
+        request.device_name = headers
+            .get("x-device-name")
+            .and_then(|header| header.to_str().ok())
+            .map(|s| s.to_string());
+    }
 
     let user_agent = headers
         .get("user-agent")
         .and_then(|header| header.to_str().ok())
         .map(|s| s.to_string());
 
+    // The email, or with USERNAME_LOGIN possibly a username
+    let identifier = request.login_identifier().to_string();
+    if let Err(error) =
+        check_login_rate_limit(&state.security_state, &ip_address, &identifier).await
+    {
+        log_security_event(
+            "login_rate_limit_exceeded",
+            &ip_address,
+            user_agent.as_deref(),
+            None,
+            Some(&identifier),
+            false,
+            Some("Rate limit exceeded"),
+        );
+        return Err(error);
+    }
+
+    if let Err(error) = check_captcha(&state, &ip_address, request.captcha_token.as_deref()).await {
+        log_security_event(
+            "login_captcha_failed",
+            &ip_address,
+            user_agent.as_deref(),
+            None,
+            Some(&identifier),
+            false,
+            Some(&error.to_string()),
+        );
+        return Err(error);
+    }
+
+    let trusted_device = trusted_device_token(&state, &headers);
     match state
         .secure_login_service
-        .secure_login(request, ip_address, user_agent)
+        .login_from_device(
+            request,
+            ip_address.clone(),
+            user_agent.clone(),
+            trusted_device.as_deref(),
+        )
         .await
     {
-        Ok(response) => Ok((StatusCode::OK, Json(response))),
-        Err(error) => {
-            let status_code = match error.error.as_str() {
-                "Too many failed login attempts. Please try again later." => {
-                    StatusCode::TOO_MANY_REQUESTS
-                }
-                msg if msg.contains("Account is temporarily locked") => StatusCode::LOCKED,
-                msg if msg.contains("Account has been temporarily locked") => StatusCode::LOCKED,
-                "Invalid email or password" => StatusCode::UNAUTHORIZED,
-                "Authentication failed" => StatusCode::UNAUTHORIZED,
-                _ if error.error.contains("Database error") => StatusCode::INTERNAL_SERVER_ERROR,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
+        Ok(LoginOutcome::Authenticated(mut response)) => {
+            state.security_state.failed_attempts.clear(&ip_address);
+            let mut cookies = start_cookie_session(&state, &mut response)?;
+            if let Some(token) = &trusted_device {
+                renew_trusted_device(&state, response.user.id, token, &mut cookies).await?;
+            }
+            Ok((StatusCode::OK, cookies, Json(response)).into_response())
+        }
+        Ok(LoginOutcome::ChallengeRequired(challenge)) => {
+            log_security_event(
+                "login_otp_sent",
+                &ip_address,
+                user_agent.as_deref(),
+                Some(&challenge.user_id.to_string()),
+                None,
+                true,
+                None,
+            );
+            let response = OtpChallengeResponse {
+                message: "Enter the code sent to your phone".to_string(),
+                challenge_id: challenge.id,
+                sent_to: mask_phone_number(&challenge.phone_number),
+                expires_in: (challenge.expires_at - challenge.created_at).whole_seconds(),
             };
-            Err((status_code, Json(error)))
+            Ok((StatusCode::ACCEPTED, Json(response)).into_response())
+        }
+        Err(error) => {
+            state.security_state.failed_attempts.record(&ip_address);
+            // Only returned by the failure that locked the account, so this
+            // alerts once per lockout
+            if let AuthenticationError::AccountLocked { .. } = error {
+                log_security_event(
+                    "account_locked",
+                    &ip_address,
+                    user_agent.as_deref(),
+                    None,
+                    Some(&identifier),
+                    false,
+                    Some(&error.to_string()),
+                );
+            }
+            Err(error)
         }
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    request_body = RefreshTokenRequest,
+    params(
+        ("X-CSRF-Token" = Option<String>, Header, description = "Required with COOKIE_SESSIONS when the refresh token comes from its cookie"),
+    ),
+    responses(
+        (status = 200, description = "New access token", body = RefreshTokenResponse),
+        (status = 401, description = "Invalid, expired or revoked refresh token", body = AuthErrorResponse),
+        (status = 403, description = "Missing or mismatched CSRF token (COOKIE_SESSIONS)", body = AuthErrorResponse),
+        (status = 429, description = "Too many refreshes for this user", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
 async fn refresh_token(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Json(request): Json<RefreshTokenRequest>,
-) -> Result<(StatusCode, Json<RefreshTokenResponse>), (StatusCode, String)> {
-    let ip_address = addr.ip().to_string();
+    request: Option<Json<RefreshTokenRequest>>,
+) -> Result<(StatusCode, HeaderMap, Json<RefreshTokenResponse>), AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let cookie_sessions = state.cookie_policy.sessions_enabled();
+
+    // Cookie sessions send the token as a cookie; one in the body still wins
+    let refresh_token = request
+        .map(|Json(request)| request.refresh_token)
+        .filter(|token| !token.is_empty())
+        .or_else(|| {
+            cookie_sessions
+                .then(|| read_cookie(&headers, &state.cookie_policy.refresh_cookie_name()))
+                .flatten()
+                .map(str::to_string)
+        })
+        .ok_or(JwtError::MissingToken)?;
 
     // First, validate the refresh token to get user info for rate limiting
     let claims = match state
         .jwt_service
-        .decode_token_without_validation(&request.refresh_token)
+        .decode_token_without_validation(&refresh_token)
     {
         Ok(claims) => claims,
         Err(error) => {
@@ -300,18 +867,12 @@ async fn refresh_token(
                 false,
                 Some(&error.to_string()),
             );
-            let status_code = match error {
-                JwtError::ExpiredToken => StatusCode::UNAUTHORIZED,
-                JwtError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
-                JwtError::BlacklistedToken => StatusCode::UNAUTHORIZED,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            };
-            return Err((status_code, error.to_string()));
+            return Err(error.into());
         }
     };
 
     // Check rate limiting for token refresh attempts
-    if let Err(_) = check_refresh_rate_limit(&state.security_state, &claims.sub) {
+    if let Err(error) = check_refresh_rate_limit(&state.security_state, &claims.sub).await {
         log_security_event(
             "refresh_rate_limit_exceeded",
             &ip_address,
@@ -321,10 +882,7 @@ async fn refresh_token(
             false,
             Some("Rate limit exceeded"),
         );
-        return Err((
-            StatusCode::TOO_MANY_REQUESTS,
-            "Too many token refresh attempts. Please wait before retrying.".to_string(),
-        ));
+        return Err(error);
     }
 
     let user_id = match Uuid::parse_str(&claims.sub) {
@@ -339,23 +897,55 @@ async fn refresh_token(
                 false,
                 Some("Invalid user ID in token"),
             );
-            return Err((StatusCode::UNAUTHORIZED, "Invalid token".to_string()));
+            return Err(JwtError::InvalidClaims("Invalid user ID".to_string()).into());
         }
     };
 
     // Use token rotation for enhanced security
-    match state
+    let origin = TokenOrigin::new(user_agent.map(str::to_string), Some(ip_address.clone()));
+    let started = Instant::now();
+    let result = state
         .jwt_service
-        .refresh_with_rotation(&request.refresh_token, user_id)
-        .await
-    {
-        Ok(tokens) => {
+        .refresh_with_rotation_from(&refresh_token, user_id, &origin)
+        .await;
+    metrics::record_token_generation(started.elapsed());
+    metrics::record_token_refresh(result.is_ok());
+
+    match result {
+        Ok(mut tokens) => {
+            let mut cookies = HeaderMap::new();
+            let delivery = state.cookie_policy.token_delivery();
+            if delivery.uses_cookies() {
+                let access_token = take_unless_both(delivery, &mut tokens.access_token);
+                let max_age = Duration::from_secs(tokens.expires_in as u64);
+                cookies.append(
+                    header::SET_COOKIE,
+                    state
+                        .cookie_policy
+                        .access_cookie(&access_token, Some(max_age))
+                        .map_err(cookie_error)?,
+                );
+            }
+            let refresh_token = if cookie_sessions {
+                let max_age = Duration::from_secs(tokens.refresh_expires_in as u64);
+                cookies.append(
+                    header::SET_COOKIE,
+                    state
+                        .cookie_policy
+                        .refresh_cookie(&tokens.refresh_token, Some(max_age))
+                        .map_err(cookie_error)?,
+                );
+                (delivery == TokenDelivery::Both).then_some(tokens.refresh_token)
+            } else {
+                Some(tokens.refresh_token)
+            };
             let response = RefreshTokenResponse {
                 access_token: tokens.access_token,
-                refresh_token: Some(tokens.refresh_token),
+                refresh_token,
                 token_type: "Bearer".to_string(),
-                expires_in: 15 * 60,                        // 15 minutes
-                refresh_expires_in: Some(7 * 24 * 60 * 60), // 7 days
+                expires_in: tokens.expires_in,
+                refresh_expires_in: Some(tokens.refresh_expires_in),
+                access_token_jti: tokens.access_token_jti,
             };
             log_security_event(
                 "token_refreshed",
@@ -366,11 +956,16 @@ async fn refresh_token(
                 true,
                 Some("Token rotated"),
             );
-            Ok((StatusCode::OK, Json(response)))
+            Ok((StatusCode::OK, cookies, Json(response)))
         }
         Err(error) => {
+            // REFRESH_TOKEN_BINDING caught the token being used by another client
+            let event_type = match error {
+                JwtError::BindingMismatch => "token_reuse_detected",
+                _ => "token_refresh_failed",
+            };
             log_security_event(
-                "token_refresh_failed",
+                event_type,
                 &ip_address,
                 user_agent,
                 Some(&claims.sub),
@@ -378,34 +973,95 @@ async fn refresh_token(
                 false,
                 Some(&error.to_string()),
             );
-            let status_code = match error {
-                JwtError::ExpiredToken => StatusCode::UNAUTHORIZED,
-                JwtError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
-                JwtError::BlacklistedToken => StatusCode::UNAUTHORIZED,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            };
-            Err((status_code, error.to_string()))
+            Err(error.into())
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/auth/csrf",
+    tag = "auth",
+    responses(
+        (status = 200, description = "A new CSRF token, also set as the CSRF cookie", body = CsrfTokenResponse),
+        (status = 404, description = "Cookie sessions are not enabled", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn csrf_token(
+    State(state): State<AuthAppState>,
+) -> Result<(HeaderMap, Json<CsrfTokenResponse>), AuthenticationError> {
+    if !state.cookie_policy.sessions_enabled() {
+        return Err(AuthenticationError::NotFound(
+            "Cookie sessions are not enabled",
+        ));
+    }
+
+    // Replaces the previous token, so clients should use the one returned here
+    let csrf_token = generate_csrf_token();
+    let mut cookies = HeaderMap::new();
+    cookies.insert(
+        header::SET_COOKIE,
+        state
+            .cookie_policy
+            .csrf_cookie(&csrf_token)
+            .map_err(cookie_error)?,
+    );
+    Ok((cookies, Json(CsrfTokenResponse { csrf_token })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    request_body = LogoutRequest,
+    responses(
+        (status = 200, description = "Logged out", body = LogoutResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 403, description = "Missing or mismatched CSRF token (COOKIE_SESSIONS)", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
 async fn logout(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: axum::http::HeaderMap,
     Json(request): Json<LogoutRequest>,
-) -> Result<(StatusCode, Json<LogoutResponse>), (StatusCode, String)> {
+) -> Result<(StatusCode, HeaderMap, Json<LogoutResponse>), AuthenticationError> {
     let ip_address = extract_real_ip(addr, &headers);
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let cookie_sessions = state.cookie_policy.sessions_enabled();
 
-    // Extract and validate the access token from Authorization header
-    let auth_header = headers
-        .get(axum::http::header::AUTHORIZATION)
-        .and_then(|header| header.to_str().ok());
+    let mut cookies = HeaderMap::new();
+    if state.cookie_policy.token_delivery().uses_cookies() {
+        cookies.append(
+            header::SET_COOKIE,
+            state
+                .cookie_policy
+                .clear_access_cookie()
+                .map_err(cookie_error)?,
+        );
+    }
+    if cookie_sessions {
+        cookies.append(
+            header::SET_COOKIE,
+            state
+                .cookie_policy
+                .clear_refresh_cookie()
+                .map_err(cookie_error)?,
+        );
+        cookies.append(
+            header::SET_COOKIE,
+            state
+                .cookie_policy
+                .clear_csrf_cookie()
+                .map_err(cookie_error)?,
+        );
+    }
 
-    let access_token = if let Some(header) = auth_header {
-        JwtService::extract_token_from_header(header)
-            .map_err(|err| (StatusCode::UNAUTHORIZED, err.to_string()))?
+    // The access token from the Authorization header, or its cookie
+    let access_token = if let Some(token) = state.jwt_service.extract_request_token(&headers)? {
+        token
     } else {
         // Return success even if no token provided (prevents information leakage)
         log_security_event(
@@ -421,8 +1077,9 @@ async fn logout(
         let response = LogoutResponse {
             message: "Logged out successfully".to_string(),
             logged_out_devices: None,
+            complete: None,
         };
-        return Ok((StatusCode::OK, Json(response)));
+        return Ok((StatusCode::OK, cookies, Json(response)));
     };
 
     // Get user info from token (even if expired/invalid for logging)
@@ -439,51 +1096,59 @@ async fn logout(
 
     // Always try to perform logout operations, but don't fail if token is already invalid
     let mut logged_out_devices = 0u32;
+    // Set when every device was asked to log out; the count is never exposed
+    let mut all_devices_complete = None;
 
-    // Blacklist the current access token (ignore errors for invalid/expired tokens)
-    let _ = state.jwt_service.blacklist_token(access_token).await;
+    let logout_all = user_id.is_some() && request.logout_all_devices.unwrap_or(false);
+
+    // Blacklist the current access token (ignore errors for invalid/expired tokens).
+    // Logging out everywhere does this in the same transaction as the revocation.
+    if !logout_all {
+        let _ = state.jwt_service.blacklist_token(access_token).await;
+    }
     logged_out_devices += 1;
 
     if let Some(user_id) = user_id {
         // Handle "logout all devices" functionality
-        if request.logout_all_devices.unwrap_or(false) {
-            // Revoke all refresh tokens for the user
-            match state
+        if logout_all {
+            // Blacklist the access token and revoke all refresh tokens, all or nothing
+            let outcome = state
                 .jwt_service
-                .revoke_all_user_refresh_tokens(user_id)
-                .await
-            {
-                Ok(()) => {
-                    // Note: We can't easily count revoked devices, so we use a placeholder
-                    logged_out_devices = 999; // Indicates "all devices"
-                    log_security_event(
-                        "logout_all_devices",
-                        &ip_address,
-                        user_agent,
-                        Some(&user_id.to_string()),
-                        user_email.map(|x| x.as_str()),
-                        true,
-                        Some("All devices logged out"),
-                    );
-                }
-                Err(error) => {
-                    log_security_event(
-                        "logout_all_devices_failed",
-                        &ip_address,
-                        user_agent,
-                        Some(&user_id.to_string()),
-                        user_email.map(|x| x.as_str()),
-                        false,
-                        Some(&error.to_string()),
-                    );
-                }
+                .logout_all_devices(access_token, user_id)
+                .await;
+            all_devices_complete = Some(outcome.complete);
+
+            if outcome.complete {
+                log_security_event(
+                    "logout_all_devices",
+                    &ip_address,
+                    user_agent,
+                    Some(&user_id.to_string()),
+                    user_email.map(|x| x.as_str()),
+                    true,
+                    Some(&format!("Revoked {} refresh tokens", outcome.revoked)),
+                );
+            } else {
+                log_security_event(
+                    "logout_all_devices_failed",
+                    &ip_address,
+                    user_agent,
+                    Some(&user_id.to_string()),
+                    user_email.map(|x| x.as_str()),
+                    false,
+                    outcome.error.as_deref(),
+                );
             }
         } else {
             // Handle single device logout
             if let Some(refresh_token) = &request.refresh_token {
-                // Try to revoke the specific refresh token (ignore errors)
+                // Revocation alone invalidates the refresh token (ignore errors)
                 let _ = state.jwt_service.revoke_refresh_token(refresh_token).await;
-                let _ = state.jwt_service.blacklist_token(refresh_token).await;
+            } else if cookie_sessions {
+                // The refresh cookie is only sent to /refresh; the access token names its session
+                if let Some(sid) = claims.as_ref().and_then(|c| c.sid.as_deref()) {
+                    let _ = state.jwt_service.revoke_session(sid).await;
+                }
             }
         }
 
@@ -512,26 +1177,44 @@ async fn logout(
         );
     }
 
+    let message = if all_devices_complete == Some(false) {
+        "Could not log out of all devices. Please try again."
+    } else {
+        "Logged out successfully"
+    };
     let response = LogoutResponse {
-        message: "Logged out successfully".to_string(),
-        logged_out_devices: if logged_out_devices == 999 {
+        message: message.to_string(),
+        logged_out_devices: if all_devices_complete.is_some() {
             None // Don't expose the actual count for "all devices"
         } else if logged_out_devices > 0 {
             Some(logged_out_devices)
         } else {
             None
         },
+        complete: all_devices_complete,
     };
 
-    Ok((StatusCode::OK, Json(response)))
+    Ok((StatusCode::OK, cookies, Json(response)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/auth/profile",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The current user's profile", body = ProfileResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "User not found", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
 async fn get_profile(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     auth_user: AuthUser,
-) -> Result<(StatusCode, Json<ProfileResponse>), (StatusCode, Json<AuthError>)> {
+) -> Result<(StatusCode, Json<ProfileResponse>), AuthenticationError> {
     let ip_address = extract_real_ip(addr, &headers);
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
 
@@ -546,14 +1229,7 @@ async fn get_profile(
                 true,
                 None,
             );
-            let response = ProfileResponse {
-                id: user.id,
-                name: user.name,
-                email: user.email,
-                created_at: user.created_at,
-                updated_at: user.updated_at,
-            };
-            Ok((StatusCode::OK, Json(response)))
+            Ok((StatusCode::OK, Json(ProfileResponse::from(user))))
         }
         Ok(None) => {
             log_security_event(
@@ -565,10 +1241,7 @@ async fn get_profile(
                 false,
                 None,
             );
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(AuthError::new("User not found")),
-            ))
+            Err(AuthenticationError::UserNotFound)
         }
         Err(error) => {
             log_security_event(
@@ -580,21 +1253,60 @@ async fn get_profile(
                 false,
                 Some(&error.to_string()),
             );
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AuthError::new("Failed to retrieve profile")),
+            Err(AuthenticationError::Internal(
+                "Failed to retrieve profile".to_string(),
             ))
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/auth/me",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The current user with their roles, scopes and account state", body = MeResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "User not found", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn get_me(
+    State(state): State<AuthAppState>,
+    auth_user: AuthUser,
+) -> Result<Json<MeResponse>, AuthenticationError> {
+    let (user, summary) = state
+        .user_service
+        .get_account_summary(auth_user.user_id)
+        .await
+        .map_err(|_| AuthenticationError::Internal("Failed to retrieve profile".to_string()))?
+        .ok_or(AuthenticationError::UserNotFound)?;
+
+    Ok(Json(MeResponse::new(user, summary, auth_user.0.scopes)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/auth/profile",
+    tag = "auth",
+    request_body = ProfileUpdateRequest,
+    responses(
+        (status = 200, description = "Profile updated", body = ProfileResponse),
+        (status = 400, description = "Validation failed", body = AuthErrorResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "User not found", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
 async fn update_profile(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     auth_user: AuthUser,
     Json(request): Json<ProfileUpdateRequest>,
-) -> Result<(StatusCode, Json<ProfileResponse>), (StatusCode, Json<AuthError>)> {
+) -> Result<(StatusCode, Json<ProfileResponse>), AuthenticationError> {
     let ip_address = extract_real_ip(addr, &headers);
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
 
@@ -609,15 +1321,27 @@ async fn update_profile(
             false,
             Some("Validation failed"),
         );
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(AuthError::validation_error(&errors)),
-        ));
+        return Err(errors.into());
     }
 
-    // Get the current user to verify their password if needed
-    let current_user = match state.user_service.get_user_by_id(auth_user.user_id).await {
-        Ok(Some(user)) => user,
+    // Email changes go through change_email, passwords through change_password
+    let updated_user = match state
+        .user_service
+        .update_user(auth_user.user_id, request.name, None, None)
+        .await
+    {
+        Ok(Some(updated_user)) => {
+            log_security_event(
+                "profile_updated",
+                &ip_address,
+                user_agent,
+                Some(&updated_user.id.to_string()),
+                Some(&updated_user.email),
+                true,
+                None,
+            );
+            updated_user
+        }
         Ok(None) => {
             log_security_event(
                 "profile_update_user_not_found",
@@ -628,14 +1352,11 @@ async fn update_profile(
                 false,
                 None,
             );
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(AuthError::new("User not found")),
-            ));
+            return Err(AuthenticationError::UserNotFound);
         }
         Err(error) => {
             log_security_event(
-                "profile_update_db_error",
+                "profile_update_failed",
                 &ip_address,
                 user_agent,
                 Some(&auth_user.user_id.to_string()),
@@ -643,178 +1364,422 @@ async fn update_profile(
                 false,
                 Some(&error.to_string()),
             );
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AuthError::new("Failed to retrieve user information")),
+            return Err(AuthenticationError::Internal(
+                "Failed to update profile".to_string(),
             ));
         }
     };
 
-    // If email is being changed, require current password verification
-    let changing_email =
-        request.email.is_some() && request.email != Some(current_user.email.clone());
+    Ok((StatusCode::OK, Json(ProfileResponse::from(updated_user))))
+}
 
-    if changing_email {
-        if let Some(current_password) = &request.current_password {
-            match current_user.verify_password(current_password) {
-                Ok(true) => {} // Password is correct, continue
-                Ok(false) => {
-                    log_security_event(
-                        "profile_update_invalid_password",
-                        &ip_address,
-                        user_agent,
-                        Some(&auth_user.user_id.to_string()),
-                        Some(&current_user.email),
-                        false,
-                        None,
-                    );
-                    return Err((
-                        StatusCode::UNAUTHORIZED,
-                        Json(AuthError::new("Current password is incorrect")),
-                    ));
-                }
-                Err(error) => {
-                    log_security_event(
-                        "profile_update_password_error",
-                        &ip_address,
-                        user_agent,
-                        Some(&auth_user.user_id.to_string()),
-                        Some(&current_user.email),
-                        false,
-                        Some(&error.to_string()),
-                    );
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(AuthError::new("Failed to verify password")),
-                    ));
-                }
-            }
-        } else {
+#[utoipa::path(
+    post,
+    path = "/api/auth/change-email",
+    tag = "auth",
+    request_body = ChangeEmailRequest,
+    responses(
+        (status = 200, description = "Confirmation sent to the new address; the current email stays in use until it is confirmed", body = ChangeEmailResponse),
+        (status = 400, description = "Validation failed, or the new email is the current one", body = AuthErrorResponse),
+        (status = 401, description = "Access token rejected, or the current password is wrong; a rejected token gets a TokenErrorResponse body", body = AuthErrorResponse),
+        (status = 404, description = "User not found", body = AuthErrorResponse),
+        (status = 409, description = "Email already in use", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn change_email(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Json(mut request): Json<ChangeEmailRequest>,
+) -> Result<(StatusCode, Json<ChangeEmailResponse>), AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+
+    // Validate the request
+    if let Err(errors) = request.validate() {
+        log_security_event(
+            "email_change_validation_failed",
+            &ip_address,
+            user_agent,
+            Some(&auth_user.user_id.to_string()),
+            None,
+            false,
+            Some("Validation failed"),
+        );
+        return Err(errors.into());
+    }
+    request.new_email = normalize_email(&request.new_email);
+
+    let current_user = match state.user_service.get_user_by_id(auth_user.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
             log_security_event(
-                "profile_update_missing_password",
+                "email_change_user_not_found",
                 &ip_address,
                 user_agent,
                 Some(&auth_user.user_id.to_string()),
-                Some(&current_user.email),
+                None,
                 false,
                 None,
             );
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(AuthError::new(
-                    "Current password is required when changing email",
-                )),
+            return Err(AuthenticationError::UserNotFound);
+        }
+        Err(error) => {
+            log_security_event(
+                "email_change_db_error",
+                &ip_address,
+                user_agent,
+                Some(&auth_user.user_id.to_string()),
+                None,
+                false,
+                Some(&error.to_string()),
+            );
+            return Err(AuthenticationError::Internal(
+                "Failed to retrieve user information".to_string(),
             ));
         }
+    };
 
-        // Check if the new email is already in use
-        if let Some(new_email) = &request.email {
-            match state.user_service.get_user_by_email(new_email).await {
-                Ok(Some(_)) => {
-                    log_security_event(
-                        "profile_update_email_exists",
-                        &ip_address,
-                        user_agent,
-                        Some(&auth_user.user_id.to_string()),
-                        Some(new_email),
-                        false,
-                        None,
-                    );
-                    return Err((
-                        StatusCode::CONFLICT,
-                        Json(AuthError::new("Email address is already in use")),
-                    ));
-                }
-                Ok(None) => {} // Email is available
-                Err(error) => {
-                    log_security_event(
-                        "profile_update_email_check_error",
-                        &ip_address,
-                        user_agent,
-                        Some(&auth_user.user_id.to_string()),
-                        Some(new_email),
-                        false,
-                        Some(&error.to_string()),
-                    );
-                    return Err((
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(AuthError::new("Failed to check email availability")),
-                    ));
-                }
-            }
+    match current_user
+        .verify_password_async(&request.current_password)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            log_security_event(
+                "email_change_invalid_password",
+                &ip_address,
+                user_agent,
+                Some(&auth_user.user_id.to_string()),
+                Some(&current_user.email),
+                false,
+                None,
+            );
+            return Err(AuthenticationError::IncorrectPassword);
+        }
+        Err(error) => {
+            log_security_event(
+                "email_change_password_error",
+                &ip_address,
+                user_agent,
+                Some(&auth_user.user_id.to_string()),
+                Some(&current_user.email),
+                false,
+                Some(&error.to_string()),
+            );
+            return Err(AuthenticationError::Internal(
+                "Failed to verify password".to_string(),
+            ));
         }
     }
 
-    // Update the user profile
+    if request.new_email == current_user.email {
+        return Err(AuthenticationError::InvalidInput {
+            field: "new_email",
+            message: "Must differ from the current email".to_string(),
+        });
+    }
+
     match state
         .user_service
-        .update_user(
-            auth_user.user_id,
-            request.name.clone(),
-            request.email.clone(),
-            None, // Don't update password here, use change_password endpoint
-        )
+        .get_user_by_email(&request.new_email)
         .await
     {
-        Ok(Some(updated_user)) => {
+        Ok(None) => {}
+        Ok(Some(_)) => {
             log_security_event(
-                "profile_updated",
+                "email_change_email_exists",
                 &ip_address,
                 user_agent,
-                Some(&updated_user.id.to_string()),
-                Some(&updated_user.email),
-                true,
+                Some(&auth_user.user_id.to_string()),
+                Some(&request.new_email),
+                false,
                 None,
             );
-            let response = ProfileResponse {
-                id: updated_user.id,
-                name: updated_user.name,
-                email: updated_user.email,
-                created_at: updated_user.created_at,
-                updated_at: updated_user.updated_at,
-            };
-            Ok((StatusCode::OK, Json(response)))
+            return Err(AuthenticationError::EmailInUse);
         }
-        Ok(None) => {
+        Err(error) => {
             log_security_event(
-                "profile_update_user_not_found",
+                "email_change_email_check_error",
                 &ip_address,
                 user_agent,
                 Some(&auth_user.user_id.to_string()),
+                Some(&request.new_email),
+                false,
+                Some(&error.to_string()),
+            );
+            return Err(AuthenticationError::Internal(
+                "Failed to check email availability".to_string(),
+            ));
+        }
+    }
+
+    // The switch happens in confirm_email_change, once the new address confirms
+    if let Err(error) = state
+        .auth_service
+        .request_email_change(&current_user, &request.new_email)
+        .await
+    {
+        log_security_event(
+            "email_change_request_failed",
+            &ip_address,
+            user_agent,
+            Some(&auth_user.user_id.to_string()),
+            Some(&request.new_email),
+            false,
+            Some(&error.to_string()),
+        );
+        return Err(error);
+    }
+    log_security_event(
+        "email_change_requested",
+        &ip_address,
+        user_agent,
+        Some(&auth_user.user_id.to_string()),
+        Some(&current_user.email),
+        true,
+        None,
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ChangeEmailResponse {
+            message: "Check the new address for a confirmation link".to_string(),
+            pending_email: request.new_email,
+        }),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/confirm-email-change",
+    tag = "auth",
+    params(ConfirmEmailChangeQuery),
+    responses(
+        (status = 200, description = "Email changed", body = ConfirmEmailChangeResponse),
+        (status = 400, description = "Invalid or expired confirmation token", body = AuthErrorResponse),
+        (status = 409, description = "Email already in use", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn confirm_email_change(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<ConfirmEmailChangeQuery>,
+) -> Result<Json<ConfirmEmailChangeResponse>, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+
+    match state.auth_service.confirm_email_change(&query.token).await {
+        Ok(user) => {
+            log_security_event(
+                "email_changed",
+                &ip_address,
+                user_agent,
+                Some(&user.id.to_string()),
+                Some(&user.email),
+                true,
+                None,
+            );
+            Ok(Json(ConfirmEmailChangeResponse {
+                message: "Email address changed".to_string(),
+                email: user.email,
+            }))
+        }
+        Err(error) => {
+            log_security_event(
+                "email_change_confirmation_failed",
+                &ip_address,
+                user_agent,
+                None,
                 None,
                 false,
+                Some(&error.to_string()),
+            );
+            Err(error)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/lockout-status",
+    tag = "auth",
+    params(LockoutStatusQuery),
+    responses(
+        (status = 200, description = "Whether the account is locked; unknown emails report unlocked", body = LockoutStatusResponse),
+        (status = 429, description = "Rate limit exceeded", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn lockout_status(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<LockoutStatusQuery>,
+) -> Result<Json<LockoutStatusResponse>, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+
+    // Limited per IP so it can't be used to sweep for locked accounts
+    if let Err(error) = check_lockout_status_rate_limit(&state.security_state, &ip_address).await {
+        let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+        log_security_event(
+            "lockout_status_rate_limit_exceeded",
+            &ip_address,
+            user_agent,
+            None,
+            None,
+            false,
+            Some("Rate limit exceeded"),
+        );
+        return Err(error);
+    }
+
+    let locked_until = state
+        .secure_login_service
+        .lockout_status(&query.email)
+        .await?;
+    let retry_after = locked_until.map(|locked_until| {
+        let remaining = locked_until - time::OffsetDateTime::now_utc();
+        remaining.whole_seconds().max(1) as u64
+    });
+    Ok(Json(LockoutStatusResponse {
+        locked: locked_until.is_some(),
+        locked_until,
+        retry_after,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/unlock",
+    tag = "auth",
+    request_body = AccountUnlockRequest,
+    responses(
+        (status = 200, description = "Unlock email sent if the account is locked", body = AccountUnlockResponse),
+        (status = 400, description = "Validation failed", body = AuthErrorResponse),
+        (status = 429, description = "Too many unlock requests for this email", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn request_unlock(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<AccountUnlockRequest>,
+) -> Result<Json<AccountUnlockResponse>, AuthenticationError> {
+    request.validate()?;
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let email = normalize_email(&request.email);
+
+    if let Err(error) = check_account_unlock_rate_limit(&state.security_state, &email).await {
+        log_security_event(
+            "account_unlock_rate_limit_exceeded",
+            &ip_address,
+            user_agent,
+            None,
+            Some(&email),
+            false,
+            Some("Rate limit exceeded"),
+        );
+        return Err(error);
+    }
+
+    state.secure_login_service.request_unlock(&email).await?;
+    log_security_event(
+        "account_unlock_requested",
+        &ip_address,
+        user_agent,
+        None,
+        Some(&email),
+        true,
+        None,
+    );
+    Ok(Json(AccountUnlockResponse {
+        message: ACCOUNT_UNLOCK_MESSAGE.to_string(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/unlock/consume",
+    tag = "auth",
+    params(ConsumeUnlockTokenQuery),
+    responses(
+        (status = 200, description = "Account unlocked", body = AccountUnlockResponse),
+        (status = 400, description = "Invalid, used or expired unlock token", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn consume_unlock_token(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<ConsumeUnlockTokenQuery>,
+) -> Result<Json<AccountUnlockResponse>, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+
+    match state
+        .secure_login_service
+        .consume_unlock_token(&query.token)
+        .await
+    {
+        Ok(user_id) => {
+            log_security_event(
+                "account_unlocked",
+                &ip_address,
+                user_agent,
+                Some(&user_id.to_string()),
+                None,
+                true,
                 None,
             );
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(AuthError::new("User not found")),
-            ))
+            Ok(Json(AccountUnlockResponse {
+                message: "Account unlocked".to_string(),
+            }))
         }
         Err(error) => {
             log_security_event(
-                "profile_update_failed",
+                "account_unlock_failed",
                 &ip_address,
                 user_agent,
-                Some(&auth_user.user_id.to_string()),
-                request.email.as_deref(),
+                None,
+                None,
                 false,
                 Some(&error.to_string()),
             );
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AuthError::new("Failed to update profile")),
-            ))
+            Err(error)
         }
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/auth/change-password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = ChangePasswordResponse),
+        (status = 400, description = "Validation failed or password reused", body = AuthErrorResponse),
+        (status = 401, description = "Access token rejected, or the current password is wrong; a rejected token gets a TokenErrorResponse body", body = AuthErrorResponse),
+        (status = 404, description = "User not found", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
 async fn change_password(
     State(state): State<AuthAppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     auth_user: AuthUser,
     Json(request): Json<ChangePasswordRequest>,
-) -> Result<(StatusCode, Json<ChangePasswordResponse>), (StatusCode, Json<AuthError>)> {
+) -> Result<(StatusCode, Json<ChangePasswordResponse>), AuthenticationError> {
     let ip_address = extract_real_ip(addr, &headers);
     let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
 
@@ -829,10 +1794,7 @@ async fn change_password(
             false,
             Some("Validation failed"),
         );
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(AuthError::validation_error(&errors)),
-        ));
+        return Err(errors.into());
     }
 
     // Prevent users from using the same password
@@ -846,12 +1808,10 @@ async fn change_password(
             false,
             None,
         );
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(AuthError::new(
-                "New password must be different from current password",
-            )),
-        ));
+        return Err(AuthenticationError::InvalidInput {
+            field: "new_password",
+            message: "New password must be different from current password".to_string(),
+        });
     }
 
     // Get the current user to verify their password
@@ -867,10 +1827,7 @@ async fn change_password(
                 false,
                 None,
             );
-            return Err((
-                StatusCode::NOT_FOUND,
-                Json(AuthError::new("User not found")),
-            ));
+            return Err(AuthenticationError::UserNotFound);
         }
         Err(error) => {
             log_security_event(
@@ -882,15 +1839,17 @@ async fn change_password(
                 false,
                 Some(&error.to_string()),
             );
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AuthError::new("Failed to retrieve user information")),
+            return Err(AuthenticationError::Internal(
+                "Failed to retrieve user information".to_string(),
             ));
         }
     };
 
     // Verify current password
-    match current_user.verify_password(&request.current_password) {
+    match current_user
+        .verify_password_async(&request.current_password)
+        .await
+    {
         Ok(true) => {} // Password is correct, continue
         Ok(false) => {
             log_security_event(
@@ -902,10 +1861,7 @@ async fn change_password(
                 false,
                 None,
             );
-            return Err((
-                StatusCode::UNAUTHORIZED,
-                Json(AuthError::new("Current password is incorrect")),
-            ));
+            return Err(AuthenticationError::IncorrectPassword);
         }
         Err(error) => {
             log_security_event(
@@ -917,20 +1873,19 @@ async fn change_password(
                 false,
                 Some(&error.to_string()),
             );
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AuthError::new("Failed to verify current password")),
+            return Err(AuthenticationError::Internal(
+                "Failed to verify current password".to_string(),
             ));
         }
     }
 
     // Update the password
     match state
-        .user_service
-        .update_user(auth_user.user_id, None, None, Some(request.new_password))
+        .auth_service
+        .change_password(auth_user.user_id, &request.new_password)
         .await
     {
-        Ok(Some(_)) => {
+        Ok(_) => {
             log_security_event(
                 "password_changed",
                 &ip_address,
@@ -953,7 +1908,19 @@ async fn change_password(
             };
             Ok((StatusCode::OK, Json(response)))
         }
-        Ok(None) => {
+        Err(AuthenticationError::PasswordReused) => {
+            log_security_event(
+                "password_change_password_reused",
+                &ip_address,
+                user_agent,
+                Some(&auth_user.user_id.to_string()),
+                Some(&current_user.email),
+                false,
+                None,
+            );
+            Err(AuthenticationError::PasswordReused)
+        }
+        Err(AuthenticationError::UserNotFound) => {
             log_security_event(
                 "password_change_user_not_found",
                 &ip_address,
@@ -963,10 +1930,7 @@ async fn change_password(
                 false,
                 None,
             );
-            Err((
-                StatusCode::NOT_FOUND,
-                Json(AuthError::new("User not found")),
-            ))
+            Err(AuthenticationError::UserNotFound)
         }
         Err(error) => {
             log_security_event(
@@ -978,10 +1942,1227 @@ async fn change_password(
                 false,
                 Some(&error.to_string()),
             );
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(AuthError::new("Failed to change password")),
+            Err(AuthenticationError::Internal(
+                "Failed to change password".to_string(),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/account",
+    tag = "auth",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 204, description = "Account deleted"),
+        (status = 400, description = "Validation failed", body = AuthErrorResponse),
+        (status = 401, description = "Access token rejected, or the current password is wrong; a rejected token gets a TokenErrorResponse body", body = AuthErrorResponse),
+        (status = 404, description = "User not found", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn delete_account(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Json(request): Json<DeleteAccountRequest>,
+) -> Result<StatusCode, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let user_id = auth_user.user_id.to_string();
+
+    if let Err(errors) = request.validate() {
+        return Err(errors.into());
+    }
+
+    match state
+        .user_service
+        .delete_account(auth_user.user_id, &request.current_password)
+        .await
+    {
+        Ok(()) => {
+            log_security_event(
+                "account_deleted",
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                None,
+                true,
+                None,
+            );
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Err(UserServiceError::InvalidPassword) => {
+            log_security_event(
+                "account_deletion_invalid_password",
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                None,
+                false,
+                None,
+            );
+            Err(AuthenticationError::IncorrectPassword)
+        }
+        Err(UserServiceError::UserNotFound) => Err(AuthenticationError::UserNotFound),
+        Err(error) => {
+            log_security_event(
+                "account_deletion_error",
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                None,
+                false,
+                Some(&error.to_string()),
+            );
+            Err(AuthenticationError::Internal(
+                "Failed to delete account".to_string(),
             ))
         }
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/account/export",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Everything stored about the current user", body = AccountExport),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "User not found", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn export_account(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+) -> Result<([(header::HeaderName, &'static str); 1], Json<AccountExport>), AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let user_id = auth_user.user_id.to_string();
+
+    match state.user_service.export_user_data(auth_user.user_id).await {
+        Ok(export) => {
+            log_security_event(
+                "account_exported",
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                None,
+                true,
+                None,
+            );
+            Ok((
+                [(
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"chronos-export.json\"",
+                )],
+                Json(export),
+            ))
+        }
+        Err(UserServiceError::UserNotFound) => Err(AuthenticationError::UserNotFound),
+        Err(error) => {
+            log_security_event(
+                "account_export_error",
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                None,
+                false,
+                Some(&error.to_string()),
+            );
+            Err(AuthenticationError::Internal(
+                "Failed to export account data".to_string(),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The current user's active sessions, newest first", body = SessionListResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn list_sessions(
+    State(state): State<AuthAppState>,
+    auth_user: AuthUser,
+) -> Result<Json<SessionListResponse>, AuthenticationError> {
+    let sessions = state
+        .jwt_service
+        .list_sessions(auth_user.user_id)
+        .await
+        .map_err(|_| AuthenticationError::Internal("Failed to load sessions".to_string()))?;
+
+    let current = auth_user.session_id.as_deref();
+    Ok(Json(SessionListResponse {
+        sessions: sessions
+            .into_iter()
+            .map(|token| SessionResponse::new(token, current))
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/auth/sessions/{jti}/name",
+    tag = "auth",
+    params(("jti" = String, Path, description = "The session's refresh token jti")),
+    request_body = RenameSessionRequest,
+    responses(
+        (status = 200, description = "Session renamed", body = SessionResponse),
+        (status = 400, description = "Empty device name", body = AuthErrorResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "No active session with this jti", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn rename_session(
+    State(state): State<AuthAppState>,
+    Path(jti): Path<String>,
+    auth_user: AuthUser,
+    Json(request): Json<RenameSessionRequest>,
+) -> Result<Json<SessionResponse>, AuthenticationError> {
+    let Some(device_name) = normalize_device_name(&request.device_name) else {
+        return Err(AuthenticationError::InvalidInput {
+            field: "device_name",
+            message: "Must not be empty".to_string(),
+        });
+    };
+
+    match state
+        .jwt_service
+        .rename_session(auth_user.user_id, &jti, &device_name)
+        .await
+    {
+        Ok(Some(token)) => Ok(Json(SessionResponse::new(
+            token,
+            auth_user.session_id.as_deref(),
+        ))),
+        Ok(None) => Err(AuthenticationError::NotFound("Session not found")),
+        Err(_) => Err(AuthenticationError::Internal(
+            "Failed to rename session".to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/api-keys",
+    tag = "auth",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "Key created; the plaintext key is only shown in this response", body = ApiKeyCreatedResponse),
+        (status = 400, description = "Empty name, unknown scope or zero expiry", body = AuthErrorResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 403, description = "Authenticated with an API key, which cannot create keys", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn create_api_key(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<ApiKeyCreatedResponse>), AuthenticationError> {
+    // A leaked key must not be able to mint more keys
+    if auth_user.token_type == TokenType::ApiKey {
+        return Err(AuthenticationError::InsufficientPermissions);
+    }
+
+    let name = match request.name.as_deref() {
+        Some(name) => Some(normalize_device_name(name).ok_or(
+            AuthenticationError::InvalidInput {
+                field: "name",
+                message: "Must not be empty".to_string(),
+            },
+        )?),
+        None => None,
+    };
+    let scopes =
+        normalize_scopes(&request.scopes).map_err(|scope| AuthenticationError::InvalidInput {
+            field: "scopes",
+            message: format!("Unknown scope {:?}", scope),
+        })?;
+    let expires_in = match request.expires_in_days {
+        Some(0) => {
+            return Err(AuthenticationError::InvalidInput {
+                field: "expires_in_days",
+                message: "Must be at least 1".to_string(),
+            });
+        }
+        Some(days) => Some(time::Duration::days(days.into())),
+        None => None,
+    };
+
+    let (api_key, key) = state
+        .jwt_service
+        .create_api_key(auth_user.user_id, name.as_deref(), &scopes, expires_in)
+        .await
+        .map_err(|_| AuthenticationError::Internal("Failed to create API key".to_string()))?;
+
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    log_security_event(
+        "api_key_created",
+        &ip_address,
+        user_agent,
+        Some(&auth_user.user_id.to_string()),
+        None,
+        true,
+        Some(&format!("API key {}", api_key.id)),
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiKeyCreatedResponse::new(api_key, key)),
+    ))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/api-keys/{id}",
+    tag = "auth",
+    params(("id" = Uuid, Path, description = "The API key's id")),
+    responses(
+        (status = 204, description = "Key revoked; requests using it now get 401"),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "No unrevoked API key with this id", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn revoke_api_key(
+    State(state): State<AuthAppState>,
+    Path(id): Path<Uuid>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+) -> Result<StatusCode, AuthenticationError> {
+    match state
+        .jwt_service
+        .revoke_api_key(auth_user.user_id, id)
+        .await
+    {
+        Ok(true) => {
+            let ip_address = extract_real_ip(addr, &headers);
+            let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+            log_security_event(
+                "api_key_revoked",
+                &ip_address,
+                user_agent,
+                Some(&auth_user.user_id.to_string()),
+                None,
+                true,
+                Some(&format!("API key {}", id)),
+            );
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(AuthenticationError::NotFound("API key not found")),
+        Err(_) => Err(AuthenticationError::Internal(
+            "Failed to revoke API key".to_string(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/login-history",
+    tag = "auth",
+    params(LoginHistoryQuery),
+    responses(
+        (status = 200, description = "The current user's sign-in attempts, newest first", body = Paginated<LoginHistoryEntry>),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn login_history(
+    State(state): State<AuthAppState>,
+    Query(query): Query<LoginHistoryQuery>,
+    auth_user: AuthUser,
+) -> Result<Json<Paginated<LoginHistoryEntry>>, AuthenticationError> {
+    let pagination = Pagination::new(query.page, query.per_page);
+    let (attempts, total) = state
+        .secure_login_service
+        .login_history(auth_user.user_id, pagination)
+        .await
+        .map_err(|_| AuthenticationError::Internal("Failed to load login history".to_string()))?;
+
+    Ok(Json(Paginated {
+        items: attempts.into_iter().map(LoginHistoryEntry::from).collect(),
+        total,
+        page: pagination.page,
+        per_page: pagination.per_page,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/otp",
+    tag = "auth",
+    request_body = OtpLoginRequest,
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 400, description = "Wrong or expired code", body = AuthErrorResponse),
+        (status = 401, description = "Unknown or already used challenge", body = AuthErrorResponse),
+        (status = 403, description = "Account unavailable", body = AuthErrorResponse),
+        (status = 404, description = "SMS verification is not configured", body = AuthErrorResponse),
+        (status = 429, description = "Too many wrong codes; the challenge is invalidated", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn login_otp(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<OtpLoginRequest>,
+) -> Result<(StatusCode, HeaderMap, Json<LoginResponse>), AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers
+        .get("user-agent")
+        .and_then(|header| header.to_str().ok())
+        .map(|s| s.to_string());
+
+    match state
+        .secure_login_service
+        .complete_otp_login(
+            request.challenge_id,
+            &request.code,
+            ip_address.clone(),
+            user_agent.clone(),
+        )
+        .await
+    {
+        Ok(mut response) => {
+            state.security_state.failed_attempts.clear(&ip_address);
+            let mut cookies = start_cookie_session(&state, &mut response)?;
+            if request.trust_device {
+                trust_device(
+                    &state,
+                    response.user.id,
+                    &ip_address,
+                    user_agent.as_deref(),
+                    &mut cookies,
+                )
+                .await?;
+            }
+            Ok((StatusCode::OK, cookies, Json(response)))
+        }
+        Err(error) => {
+            state.security_state.failed_attempts.record(&ip_address);
+            let details = format!("challenge {}: {}", request.challenge_id, error);
+            log_otp_failure(
+                "login_otp_failed",
+                &error,
+                &ip_address,
+                user_agent.as_deref(),
+                None,
+                &details,
+            );
+            Err(error.into())
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/email-otp/request",
+    tag = "auth",
+    request_body = EmailCodeRequest,
+    responses(
+        (status = 200, description = "Sign-in code sent if the email is registered", body = EmailCodeResponse),
+        (status = 400, description = "Validation failed", body = AuthErrorResponse),
+        (status = 404, description = "Email sign-in codes are not enabled", body = AuthErrorResponse),
+        (status = 429, description = "Too many codes requested for this email", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn request_email_code(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<EmailCodeRequest>,
+) -> Result<Json<EmailCodeResponse>, AuthenticationError> {
+    let Some(email_code_service) = state.secure_login_service.email_code_service() else {
+        return Err(AuthenticationError::NotFound(
+            "Email sign-in codes are not enabled",
+        ));
+    };
+    request.validate()?;
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let email = normalize_email(&request.email);
+
+    if let Err(error) = check_email_code_rate_limit(&state.security_state, &email).await {
+        log_security_event(
+            "email_code_rate_limit_exceeded",
+            &ip_address,
+            user_agent,
+            None,
+            Some(&email),
+            false,
+            Some("Rate limit exceeded"),
+        );
+        return Err(error);
+    }
+
+    // Delivery failures are logged rather than returned, so they don't tell
+    // registered emails apart
+    let result = state.secure_login_service.request_email_code(&email).await;
+    log_security_event(
+        "email_code_requested",
+        &ip_address,
+        user_agent,
+        None,
+        Some(&email),
+        result.is_ok(),
+        result.as_ref().err().map(|e| e.to_string()).as_deref(),
+    );
+    Ok(Json(EmailCodeResponse {
+        message: EMAIL_CODE_MESSAGE.to_string(),
+        expires_in: email_code_service.ttl().whole_seconds(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/email-otp/verify",
+    tag = "auth",
+    request_body = EmailCodeLoginRequest,
+    params(
+        ("X-Device-Name" = Option<String>, Header, description = "Names the new session when the body has no device_name"),
+    ),
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 202, description = "Code accepted; an SMS code was sent and must be entered at /api/auth/login/otp", body = OtpChallengeResponse),
+        (status = 400, description = "Wrong, expired or used up code, or no code was sent to this email", body = AuthErrorResponse),
+        (status = 403, description = "Account unavailable", body = AuthErrorResponse),
+        (status = 404, description = "Email sign-in codes are not enabled", body = AuthErrorResponse),
+        (status = 429, description = "Too many logins from this IP or for this email", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+)]
+async fn login_email_code(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(mut request): Json<EmailCodeLoginRequest>,
+) -> Result<Response, AuthenticationError> {
+    if state.secure_login_service.email_code_service().is_none() {
+        return Err(AuthenticationError::NotFound(
+            "Email sign-in codes are not enabled",
+        ));
+    }
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers
+        .get("user-agent")
+        .and_then(|header| header.to_str().ok())
+        .map(|s| s.to_string());
+    request.email = normalize_email(&request.email);
+    if request.device_name.is_none() {
+        request.device_name = headers
+            .get("x-device-name")
+            .and_then(|header| header.to_str().ok())
+            .map(|s| s.to_string());
+    }
+
+    let email = request.email.clone();
+    if let Err(error) = check_login_rate_limit(&state.security_state, &ip_address, &email).await {
+        log_security_event(
+            "login_rate_limit_exceeded",
+            &ip_address,
+            user_agent.as_deref(),
+            None,
+            Some(&email),
+            false,
+            Some("Rate limit exceeded"),
+        );
+        return Err(error);
+    }
+
+    let trusted_device = trusted_device_token(&state, &headers);
+    match state
+        .secure_login_service
+        .complete_email_code_login(
+            request,
+            ip_address.clone(),
+            user_agent.clone(),
+            trusted_device.as_deref(),
+        )
+        .await
+    {
+        Ok(LoginOutcome::Authenticated(mut response)) => {
+            state.security_state.failed_attempts.clear(&ip_address);
+            let mut cookies = start_cookie_session(&state, &mut response)?;
+            if let Some(token) = &trusted_device {
+                renew_trusted_device(&state, response.user.id, token, &mut cookies).await?;
+            }
+            Ok((StatusCode::OK, cookies, Json(response)).into_response())
+        }
+        Ok(LoginOutcome::ChallengeRequired(challenge)) => {
+            log_security_event(
+                "login_otp_sent",
+                &ip_address,
+                user_agent.as_deref(),
+                Some(&challenge.user_id.to_string()),
+                None,
+                true,
+                None,
+            );
+            let response = OtpChallengeResponse {
+                message: "Enter the code sent to your phone".to_string(),
+                challenge_id: challenge.id,
+                sent_to: mask_phone_number(&challenge.phone_number),
+                expires_in: (challenge.expires_at - challenge.created_at).whole_seconds(),
+            };
+            Ok((StatusCode::ACCEPTED, Json(response)).into_response())
+        }
+        Err(error) => {
+            state.security_state.failed_attempts.record(&ip_address);
+            let details = format!("{}: {}", email, error);
+            log_otp_failure(
+                "email_code_login_failed",
+                &error,
+                &ip_address,
+                user_agent.as_deref(),
+                None,
+                &details,
+            );
+            // Whether the email is registered or has a code open isn't given away
+            let error = match error {
+                OtpError::InvalidChallenge | OtpError::Expired | OtpError::TooManyAttempts => {
+                    OtpError::InvalidCode
+                }
+                error => error,
+            };
+            Err(error.into())
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/phone",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The current user's phone number, if any", body = PhoneResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "SMS verification is not configured", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn get_phone(
+    State(state): State<AuthAppState>,
+    auth_user: AuthUser,
+) -> Result<Json<PhoneResponse>, AuthenticationError> {
+    let otp_service = require_otp_service(&state)?;
+    let phone = otp_service.phone_number(auth_user.user_id).await?;
+
+    Ok(Json(match phone {
+        Some((phone_number, verified)) => PhoneResponse {
+            phone_number: Some(phone_number),
+            verified,
+        },
+        None => PhoneResponse {
+            phone_number: None,
+            verified: false,
+        },
+    }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/auth/phone",
+    tag = "auth",
+    request_body = SetPhoneRequest,
+    responses(
+        (status = 202, description = "Number saved unverified; a code was sent to it", body = OtpChallengeResponse),
+        (status = 400, description = "Not an E.164 phone number", body = AuthErrorResponse),
+        (status = 401, description = "Access token rejected, or the current password is wrong; a rejected token gets a TokenErrorResponse body", body = AuthErrorResponse),
+        (status = 404, description = "SMS verification is not configured", body = AuthErrorResponse),
+        (status = 502, description = "The SMS could not be sent", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn set_phone(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Json(request): Json<SetPhoneRequest>,
+) -> Result<(StatusCode, Json<OtpChallengeResponse>), AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let user_id = auth_user.user_id.to_string();
+    let otp_service = require_otp_service(&state)?;
+
+    let Some(phone_number) = normalize_phone_number(&request.phone_number) else {
+        let error = AuthenticationError::InvalidInput {
+            field: "phone_number",
+            message: "Must be an international number such as +14155550100".to_string(),
+        };
+        return Err(error);
+    };
+
+    if let Err(error) = verify_current_password(&state, &auth_user, &request.current_password).await
+    {
+        log_security_event(
+            "phone_change_invalid_password",
+            &ip_address,
+            user_agent,
+            Some(&user_id),
+            None,
+            false,
+            None,
+        );
+        return Err(error);
+    }
+
+    let challenge = otp_service
+        .start_phone_verification(auth_user.user_id, &phone_number)
+        .await?;
+    log_security_event(
+        "phone_number_changed",
+        &ip_address,
+        user_agent,
+        Some(&user_id),
+        None,
+        true,
+        Some(&mask_phone_number(&phone_number)),
+    );
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(OtpChallengeResponse {
+            message: "Enter the code sent to your phone to verify it".to_string(),
+            challenge_id: challenge.id,
+            sent_to: mask_phone_number(&phone_number),
+            expires_in: otp_service.ttl().whole_seconds(),
+        }),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/phone/verify",
+    tag = "auth",
+    request_body = VerifyPhoneRequest,
+    responses(
+        (status = 200, description = "Number verified; sign-ins now need an SMS code", body = PhoneResponse),
+        (status = 400, description = "Wrong or expired code, or no number waiting for verification", body = AuthErrorResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "SMS verification is not configured", body = AuthErrorResponse),
+        (status = 429, description = "Too many wrong codes; the challenge is invalidated", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn verify_phone(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Json(request): Json<VerifyPhoneRequest>,
+) -> Result<Json<PhoneResponse>, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let user_id = auth_user.user_id.to_string();
+    let otp_service = require_otp_service(&state)?;
+
+    match otp_service
+        .confirm_phone(auth_user.user_id, &request.code)
+        .await
+    {
+        Ok(phone_number) => {
+            log_security_event(
+                "phone_verified",
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                None,
+                true,
+                Some(&mask_phone_number(&phone_number)),
+            );
+            Ok(Json(PhoneResponse {
+                phone_number: Some(phone_number),
+                verified: true,
+            }))
+        }
+        Err(error) => {
+            log_otp_failure(
+                "phone_verification_failed",
+                &error,
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                &error.to_string(),
+            );
+            Err(error.into())
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/phone",
+    tag = "auth",
+    request_body = RemovePhoneRequest,
+    responses(
+        (status = 204, description = "Number removed; sign-ins no longer need an SMS code"),
+        (status = 401, description = "Access token rejected, or the current password is wrong; a rejected token gets a TokenErrorResponse body", body = AuthErrorResponse),
+        (status = 404, description = "No phone number, or SMS verification is not configured", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn remove_phone(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+    Json(request): Json<RemovePhoneRequest>,
+) -> Result<StatusCode, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, &headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+    let user_id = auth_user.user_id.to_string();
+    let otp_service = require_otp_service(&state)?;
+
+    if let Err(error) = verify_current_password(&state, &auth_user, &request.current_password).await
+    {
+        log_security_event(
+            "phone_change_invalid_password",
+            &ip_address,
+            user_agent,
+            Some(&user_id),
+            None,
+            false,
+            None,
+        );
+        return Err(error);
+    }
+
+    match otp_service.remove_phone(auth_user.user_id).await {
+        Ok(true) => {
+            log_security_event(
+                "phone_number_removed",
+                &ip_address,
+                user_agent,
+                Some(&user_id),
+                None,
+                true,
+                None,
+            );
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(AuthenticationError::NotFound("No phone number set")),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn require_otp_service(state: &AuthAppState) -> Result<&OtpService, AuthenticationError> {
+    state
+        .otp_service
+        .as_deref()
+        .ok_or(AuthenticationError::Otp(OtpError::NotConfigured))
+}
+
+// Phone number changes need the password as well as the access token
+async fn verify_current_password(
+    state: &AuthAppState,
+    auth_user: &AuthUser,
+    current_password: &str,
+) -> Result<(), AuthenticationError> {
+    let user = match state.user_service.get_user_by_id(auth_user.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return Err(AuthenticationError::UserNotFound);
+        }
+        Err(_) => {
+            return Err(AuthenticationError::Internal(
+                "Failed to retrieve user information".to_string(),
+            ));
+        }
+    };
+
+    match user.verify_password_async(current_password).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(AuthenticationError::IncorrectPassword),
+        Err(_) => Err(AuthenticationError::Internal(
+            "Failed to verify current password".to_string(),
+        )),
+    }
+}
+
+// Running out of attempts on a code is logged as suspicious; other failures under `event`
+fn log_otp_failure(
+    event: &str,
+    error: &OtpError,
+    ip_address: &str,
+    user_agent: Option<&str>,
+    user_id: Option<&str>,
+    details: &str,
+) {
+    let event = match error {
+        OtpError::TooManyAttempts => "suspicious_activity",
+        _ => event,
+    };
+    log_security_event(
+        event,
+        ip_address,
+        user_agent,
+        user_id,
+        None,
+        false,
+        Some(details),
+    );
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/trusted-devices",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Devices that currently skip the SMS code at login, newest first", body = TrustedDeviceListResponse),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "Trusted devices are not enabled", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn list_trusted_devices(
+    State(state): State<AuthAppState>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+) -> Result<Json<TrustedDeviceListResponse>, AuthenticationError> {
+    let service = require_trusted_device_service(&state)?;
+    let devices = service
+        .list(auth_user.user_id)
+        .await
+        .map_err(|_| AuthenticationError::Internal("Failed to load trusted devices".to_string()))?;
+
+    let current =
+        trusted_device_token(&state, &headers).map(|token| hash_confirmation_token(&token));
+    Ok(Json(TrustedDeviceListResponse {
+        devices: devices
+            .into_iter()
+            .map(|device| TrustedDeviceResponse::new(device, current.as_deref()))
+            .collect(),
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/trusted-devices/{id}",
+    tag = "auth",
+    params(("id" = Uuid, Path, description = "The trusted device's id")),
+    responses(
+        (status = 204, description = "Device revoked; its next login needs the SMS code again"),
+        (status = 401, description = "Missing, invalid, expired or revoked access token", body = TokenErrorResponse),
+        (status = 404, description = "No trusted device with this id, or trusted devices are not enabled", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+async fn revoke_trusted_device(
+    State(state): State<AuthAppState>,
+    Path(id): Path<Uuid>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    auth_user: AuthUser,
+) -> Result<StatusCode, AuthenticationError> {
+    let service = require_trusted_device_service(&state)?;
+    match service.revoke(auth_user.user_id, id).await {
+        Ok(true) => {
+            let ip_address = extract_real_ip(addr, &headers);
+            let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+            log_security_event(
+                "trusted_device_revoked",
+                &ip_address,
+                user_agent,
+                Some(&auth_user.user_id.to_string()),
+                None,
+                true,
+                Some(&format!("Trusted device {}", id)),
+            );
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(AuthenticationError::NotFound("Trusted device not found")),
+        Err(_) => Err(AuthenticationError::Internal(
+            "Failed to revoke trusted device".to_string(),
+        )),
+    }
+}
+
+fn require_trusted_device_service(
+    state: &AuthAppState,
+) -> Result<&TrustedDeviceService, AuthenticationError> {
+    state
+        .secure_login_service
+        .trusted_device_service()
+        .ok_or(AuthenticationError::NotFound(
+            "Trusted devices are not enabled",
+        ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/google/start",
+    tag = "auth",
+    responses(
+        (status = 303, description = "Redirect to Google's consent screen"),
+        (status = 404, description = "Google login is not configured", body = AuthErrorResponse),
+    ),
+)]
+async fn google_oauth_start(
+    State(state): State<AuthAppState>,
+) -> Result<(HeaderMap, Redirect), AuthenticationError> {
+    oauth_start(&state, state.google_oauth_service.as_deref())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/google/callback",
+    tag = "auth",
+    params(OAuthCallbackQuery),
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 202, description = "An SMS code was sent and must be entered at /api/auth/login/otp", body = OtpChallengeResponse),
+        (status = 400, description = "Invalid state or authorization denied", body = AuthErrorResponse),
+        (status = 403, description = "Email not verified or account unavailable", body = AuthErrorResponse),
+        (status = 404, description = "Google login is not configured", body = AuthErrorResponse),
+        (status = 423, description = "Account temporarily locked", body = AuthErrorResponse),
+        (status = 502, description = "Google returned an error", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+        (status = 503, description = "The account needs an SMS code that can't be sent", body = AuthErrorResponse),
+    ),
+)]
+async fn google_oauth_callback(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<OAuthCallbackQuery>,
+) -> Result<Response, AuthenticationError> {
+    oauth_callback(
+        &state,
+        state.google_oauth_service.as_deref(),
+        addr,
+        &headers,
+        query,
+    )
+    .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/github/start",
+    tag = "auth",
+    responses(
+        (status = 303, description = "Redirect to GitHub's consent screen"),
+        (status = 404, description = "GitHub login is not configured", body = AuthErrorResponse),
+    ),
+)]
+async fn github_oauth_start(
+    State(state): State<AuthAppState>,
+) -> Result<(HeaderMap, Redirect), AuthenticationError> {
+    oauth_start(&state, state.github_oauth_service.as_deref())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/github/callback",
+    tag = "auth",
+    params(OAuthCallbackQuery),
+    responses(
+        (status = 200, description = "Logged in", body = LoginResponse),
+        (status = 202, description = "An SMS code was sent and must be entered at /api/auth/login/otp", body = OtpChallengeResponse),
+        (status = 400, description = "Invalid state or authorization denied", body = AuthErrorResponse),
+        (status = 403, description = "No verified primary email or account unavailable", body = AuthErrorResponse),
+        (status = 404, description = "GitHub login is not configured", body = AuthErrorResponse),
+        (status = 423, description = "Account temporarily locked", body = AuthErrorResponse),
+        (status = 502, description = "GitHub returned an error", body = AuthErrorResponse),
+        (status = 500, description = "Internal server error", body = AuthErrorResponse),
+        (status = 503, description = "The account needs an SMS code that can't be sent", body = AuthErrorResponse),
+    ),
+)]
+async fn github_oauth_callback(
+    State(state): State<AuthAppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(query): Query<OAuthCallbackQuery>,
+) -> Result<Response, AuthenticationError> {
+    oauth_callback(
+        &state,
+        state.github_oauth_service.as_deref(),
+        addr,
+        &headers,
+        query,
+    )
+    .await
+}
+
+// The state also goes into a cookie, so a callback only completes in the
+// browser that started the flow and a link carrying someone else's state fails
+fn oauth_start(
+    state: &AuthAppState,
+    oauth_service: Option<&OAuthService>,
+) -> Result<(HeaderMap, Redirect), AuthenticationError> {
+    let oauth_service = oauth_service.ok_or(OAuthError::NotConfigured)?;
+
+    let oauth_states = &state.security_state.oauth_states;
+    let oauth_state = oauth_states.issue();
+    let mut cookies = HeaderMap::new();
+    cookies.append(
+        header::SET_COOKIE,
+        state
+            .cookie_policy
+            .oauth_state_cookie(&oauth_state, oauth_states.ttl())
+            .map_err(cookie_error)?,
+    );
+    Ok((
+        cookies,
+        Redirect::to(&oauth_service.authorization_url(&oauth_state)),
+    ))
+}
+
+async fn oauth_callback(
+    state: &AuthAppState,
+    oauth_service: Option<&OAuthService>,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    query: OAuthCallbackQuery,
+) -> Result<Response, AuthenticationError> {
+    let ip_address = extract_real_ip(addr, headers);
+    let user_agent = headers.get("user-agent").and_then(|h| h.to_str().ok());
+
+    let oauth_service = oauth_service.ok_or(OAuthError::NotConfigured)?;
+    let provider = oauth_service.provider_name();
+
+    // The state is single-use, so check it before anything else
+    let state_cookie = read_cookie(headers, &state.cookie_policy.oauth_state_cookie_name());
+    let state_valid = match (query.state.as_deref(), state_cookie) {
+        (Some(oauth_state), Some(cookie)) if constant_time_eq(oauth_state, cookie) => {
+            state.security_state.oauth_states.consume(oauth_state)
+        }
+        _ => false,
+    };
+    if !state_valid {
+        log_security_event(
+            "oauth_invalid_state",
+            &ip_address,
+            user_agent,
+            None,
+            None,
+            false,
+            Some(provider),
+        );
+        return Err(OAuthError::InvalidState.into());
+    }
+
+    if let Some(error) = query.error {
+        return Err(OAuthError::AuthorizationDenied(error).into());
+    }
+    let code = query
+        .code
+        .ok_or_else(|| OAuthError::Provider("Missing code".to_string()))?;
+
+    let user = match oauth_service.complete_login(&code).await {
+        Ok(user) => user,
+        Err(error) => {
+            log_security_event(
+                "oauth_login_failed",
+                &ip_address,
+                user_agent,
+                None,
+                None,
+                false,
+                Some(&format!("{}: {}", provider, error)),
+            );
+            return Err(error.into());
+        }
+    };
+
+    // Lockout, the SMS code and trusted devices apply as they do to a password login
+    let trusted_device = trusted_device_token(state, headers);
+    let outcome = state
+        .secure_login_service
+        .complete_oauth_login(
+            &user,
+            ip_address.clone(),
+            user_agent.map(|s| s.to_string()),
+            trusted_device.as_deref(),
+        )
+        .await;
+    let clear_state_cookie = state
+        .cookie_policy
+        .clear_oauth_state_cookie()
+        .map_err(cookie_error)?;
+
+    match outcome {
+        Ok(LoginOutcome::Authenticated(mut response)) => {
+            log_security_event(
+                "oauth_login_success",
+                &ip_address,
+                user_agent,
+                Some(&user.id.to_string()),
+                Some(&user.email),
+                true,
+                Some(provider),
+            );
+            let mut cookies = start_cookie_session(state, &mut response)?;
+            if let Some(token) = &trusted_device {
+                renew_trusted_device(state, user.id, token, &mut cookies).await?;
+            }
+            cookies.append(header::SET_COOKIE, clear_state_cookie);
+            Ok((StatusCode::OK, cookies, Json(response)).into_response())
+        }
+        Ok(LoginOutcome::ChallengeRequired(challenge)) => {
+            log_security_event(
+                "login_otp_sent",
+                &ip_address,
+                user_agent,
+                Some(&challenge.user_id.to_string()),
+                None,
+                true,
+                Some(provider),
+            );
+            let response = OtpChallengeResponse {
+                message: "Enter the code sent to your phone".to_string(),
+                challenge_id: challenge.id,
+                sent_to: mask_phone_number(&challenge.phone_number),
+                expires_in: (challenge.expires_at - challenge.created_at).whole_seconds(),
+            };
+            let mut cookies = HeaderMap::new();
+            cookies.append(header::SET_COOKIE, clear_state_cookie);
+            Ok((StatusCode::ACCEPTED, cookies, Json(response)).into_response())
+        }
+        Err(error) => {
+            log_security_event(
+                "oauth_login_failed",
+                &ip_address,
+                user_agent,
+                Some(&user.id.to_string()),
+                Some(&user.email),
+                false,
+                Some(&format!("{}: {}", provider, error)),
+            );
+            Err(error)
+        }
+    }
+}
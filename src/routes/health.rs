@@ -0,0 +1,70 @@
+use crate::app::task_health::{TaskHealth, TaskHeartbeat};
+use axum::{Json, Router, extract::State, http::StatusCode, routing::get};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::time::Duration;
+
+// A probe should fail fast rather than wait out the pool's acquire timeout
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+pub struct HealthState {
+    pool: PgPool,
+    tasks: Vec<TaskHeartbeat>,
+}
+
+impl HealthState {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            tasks: Vec::new(),
+        }
+    }
+
+    // Background tasks whose heartbeat /ready reports
+    pub fn with_task(mut self, heartbeat: TaskHeartbeat) -> Self {
+        self.tasks.push(heartbeat);
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReadinessResponse {
+    pub status: &'static str,
+    pub database: &'static str,
+    pub tasks: Vec<TaskHealth>,
+}
+
+// Mounted outside the auth and rate limiting layers so probes need no credentials
+pub fn routes(state: HealthState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready))
+        .with_state(state)
+}
+
+async fn health() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+// Only the database decides readiness; a stalled task shows up in the body
+// without taking the instance out of rotation
+async fn ready(State(state): State<HealthState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let check = sqlx::query("SELECT 1").execute(&state.pool);
+    let database_up = matches!(tokio::time::timeout(READY_TIMEOUT, check).await, Ok(Ok(_)));
+
+    let (status_code, status, database) = if database_up {
+        (StatusCode::OK, "ready", "up")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable", "down")
+    };
+
+    (
+        status_code,
+        Json(ReadinessResponse {
+            status,
+            database,
+            tasks: state.tasks.iter().map(TaskHeartbeat::health).collect(),
+        }),
+    )
+}
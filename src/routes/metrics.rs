@@ -0,0 +1,13 @@
+use axum::{Router, extract::State, routing::get};
+use metrics_exporter_prometheus::PrometheusHandle;
+
+// Mounted outside the auth and rate limiting layers so scrapers need no credentials
+pub fn routes(handle: PrometheusHandle) -> Router {
+    Router::new()
+        .route("/metrics", get(render_metrics))
+        .with_state(handle)
+}
+
+async fn render_metrics(State(handle): State<PrometheusHandle>) -> String {
+    handle.render()
+}
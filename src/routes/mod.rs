@@ -1,83 +1,272 @@
-use crate::app::middleware::auth_middleware::jwt_auth_middleware_with_json_errors;
-use crate::app::middleware::security::SecurityState;
+use crate::app::cookies::CookiePolicy;
+use crate::app::hashing::Argon2Params;
+use crate::app::middleware::auth_middleware::{
+    RequireRole, jwt_auth_middleware_with_json_errors, require_role,
+};
+use crate::app::middleware::envelope::{get_response_envelope, wrap_success_response};
+use crate::app::middleware::ip_filter::{IpFilter, IpFilterLayer};
+use crate::app::middleware::security::{
+    SecurityState, csrf_protection, get_auth_body_limit, get_strict_content_type,
+    require_json_content_type,
+};
+use crate::app::middleware::timeout::{get_request_timeout, request_timeout};
+use crate::app::models::auth::DisposableEmailBlocklist;
+use crate::app::models::login_attempt::{LockoutConfig, TarpitConfig};
+use crate::app::models::role::ADMIN_ROLE;
+use crate::app::repositories::admin_audit_repository::AdminAuditRepository;
+use crate::app::repositories::api_key_repository::ApiKeyRepository;
+use crate::app::repositories::client_repository::ClientRepository;
+use crate::app::repositories::email_login_code_repository::EmailLoginCodeRepository;
 use crate::app::repositories::login_attempt_repository::{
     AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
 };
+use crate::app::repositories::oauth_identity_repository::OAuthIdentityRepository;
+use crate::app::repositories::otp_challenge_repository::OtpChallengeRepository;
+use crate::app::repositories::password_history_repository::PasswordHistoryRepository;
 use crate::app::repositories::password_reset_repository::PasswordResetRepository;
+use crate::app::repositories::role_repository::RoleRepository;
+use crate::app::repositories::signing_key_repository::SigningKeyRepository;
 use crate::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use crate::app::repositories::trusted_device_repository::TrustedDeviceRepository;
 use crate::app::repositories::user_repository::UserRepository;
 use crate::app::services::auth_service::AuthService;
+use crate::app::services::captcha_service::{CaptchaConfig, CaptchaService};
+use crate::app::services::email_code_service::{EmailCodeService, get_email_login_codes};
 use crate::app::services::email_service::MockEmailService;
-use crate::app::services::jwt_service::{JwtService, get_jwt_secret};
-use crate::app::services::secure_login_service::SecureLoginService;
+use crate::app::services::jwt_service::{
+    JwtKeyConfig, JwtService, SigningKeyReload, get_expose_jti, get_jwt_audience, get_jwt_leeway,
+    get_jwt_secret, get_refresh_idle_timeout, get_refresh_token_binding, get_remember_me_ttl,
+    get_role_ttl_overrides,
+};
+use crate::app::services::oauth_service::{
+    GitHubOAuthConfig, GitHubOAuthProvider, GoogleOAuthConfig, GoogleOAuthProvider, OAuthService,
+};
+use crate::app::services::otp_service::OtpService;
+use crate::app::services::secure_login_service::{SecureLoginService, get_password_max_age};
+use crate::app::services::sms_service::SmsConfig;
+use crate::app::services::trusted_device_service::{TrustedDeviceService, get_trusted_device_ttl};
 use crate::app::services::user_service::UserService;
+use crate::app::signing_key_cipher::get_signing_key_cipher;
+use crate::app::token_epochs::{TokenEpochCache, get_token_epoch_cache_ttl};
 use axum::{Router, middleware};
 use sqlx::PgPool;
+use tower_http::limit::RequestBodyLimitLayer;
 
+pub mod admin;
 pub mod auth;
+pub mod health;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod openapi;
 pub mod projects;
 pub mod tasks;
 pub mod time_entries;
 pub mod users;
 
 pub fn create_router(pool: PgPool) -> Router {
+    create_router_with_listeners(
+        pool,
+        TokenEpochCache::new(get_token_epoch_cache_ttl()),
+        SigningKeyReload::new(),
+    )
+}
+
+// Like create_router, but with a token epoch cache and a signing key reload
+// flag the caller also hands to a TokenEpochListener and a SigningKeyListener,
+// so changes made by other servers reach them
+pub fn create_router_with_listeners(
+    pool: PgPool,
+    // Shared so a password change or admin revocation is seen by the JWT middleware at once
+    token_epochs: TokenEpochCache,
+    signing_keys: SigningKeyReload,
+) -> Router {
     let users_state = users::AppState::new(pool.clone());
     let time_entries_state = time_entries::TimeEntriesState::new(pool.clone());
     let projects_state = projects::ProjectsState::new(pool.clone());
     let tasks_state = tasks::TasksState::new(pool.clone());
+    let admin_state = admin::AdminState::new(pool.clone());
 
     let user_repository = UserRepository::new(pool.clone());
     let password_reset_repository = PasswordResetRepository::new(pool.clone());
+    let password_history_repository = PasswordHistoryRepository::new(pool.clone());
     let token_blacklist_repository = TokenBlacklistRepository::new(pool.clone());
     let login_attempt_repository = LoginAttemptRepository::new(pool.clone());
     let account_lockout_repository = AccountLockoutRepository::new(pool.clone());
-    let refresh_token_repository = RefreshTokenRepository::new(pool);
+    let refresh_token_repository = RefreshTokenRepository::new(pool.clone());
+    let admin_audit_repository = AdminAuditRepository::new(pool.clone());
+    let oauth_identity_repository = OAuthIdentityRepository::new(pool.clone());
+    let otp_challenge_repository = OtpChallengeRepository::new(pool.clone());
+    let signing_key_repository = SigningKeyRepository::new(pool.clone(), get_signing_key_cipher());
+    let api_key_repository = ApiKeyRepository::new(pool.clone());
+    let client_repository = ClientRepository::new(pool.clone());
+    let email_login_code_repository = EmailLoginCodeRepository::new(pool.clone());
+    let trusted_device_repository = TrustedDeviceRepository::new(pool.clone());
+    let role_repository = RoleRepository::new(pool);
+
+    let argon2_params = Argon2Params::from_env().expect("Invalid Argon2 configuration");
 
     let email_service = MockEmailService::new();
-    let user_service = UserService::new(user_repository.clone());
-    let auth_service = AuthService::new(user_repository, password_reset_repository, email_service);
+    let user_service = UserService::new(user_repository.clone())
+        .with_activity_repositories(
+            refresh_token_repository.clone(),
+            login_attempt_repository.clone(),
+            account_lockout_repository.clone(),
+            admin_audit_repository,
+        )
+        .with_argon2_params(argon2_params);
+    // Google login is only enabled when GOOGLE_CLIENT_ID/SECRET/REDIRECT_URL are set
+    let google_oauth_service = GoogleOAuthConfig::from_env().map(|config| {
+        OAuthService::new(
+            std::sync::Arc::new(GoogleOAuthProvider::new(config)),
+            user_repository.clone(),
+            oauth_identity_repository.clone(),
+        )
+        .with_argon2_params(argon2_params)
+    });
+    // GitHub login is only enabled when GITHUB_CLIENT_ID/SECRET are set
+    let github_oauth_service = GitHubOAuthConfig::from_env().map(|config| {
+        OAuthService::new(
+            std::sync::Arc::new(GitHubOAuthProvider::new(config)),
+            user_repository.clone(),
+            oauth_identity_repository,
+        )
+        .with_argon2_params(argon2_params)
+    });
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        password_reset_repository,
+        password_history_repository,
+        email_service,
+    )
+    .with_argon2_params(argon2_params)
+    .with_token_epoch_cache(token_epochs.clone())
+    .with_disposable_email_blocklist(
+        DisposableEmailBlocklist::from_env().expect("Invalid disposable email configuration"),
+    );
 
+    let cookie_policy = CookiePolicy::from_env().expect("Invalid cookie configuration");
     let jwt_secret = get_jwt_secret();
     let jwt_service = JwtService::new(
         &jwt_secret,
         token_blacklist_repository,
         refresh_token_repository,
-    );
+    )
+    .with_role_repository(role_repository)
+    .with_user_repository(user_repository.clone())
+    .with_trusted_device_repository(trusted_device_repository.clone())
+    .with_argon2_params(argon2_params)
+    .with_jti_exposure(get_expose_jti())
+    .with_remember_me_ttl(get_remember_me_ttl())
+    .with_refresh_idle_timeout(get_refresh_idle_timeout())
+    .with_refresh_token_binding(get_refresh_token_binding())
+    .with_role_ttl_overrides(get_role_ttl_overrides())
+    .with_leeway(get_jwt_leeway())
+    .with_audience(get_jwt_audience())
+    .with_access_cookie(
+        cookie_policy
+            .token_delivery()
+            .uses_cookies()
+            .then(|| cookie_policy.access_cookie_name()),
+    )
+    .with_signing_key_repository(signing_key_repository)
+    .with_signing_key_reload(signing_keys)
+    .with_api_key_repository(api_key_repository)
+    .with_token_epoch_cache(token_epochs);
+    JwtKeyConfig::from_env()
+        .and_then(|config| config.apply(&jwt_service))
+        .expect("Invalid JWT key configuration");
+
+    // SMS sign-in codes are only enabled when SMS_ACCOUNT_SID/AUTH_TOKEN/FROM_NUMBER are set
+    let otp_service = SmsConfig::from_env()
+        .expect("Invalid SMS configuration")
+        .map(|config| {
+            OtpService::from_config(config, otp_challenge_repository, user_repository)
+                .with_argon2_params(argon2_params)
+        });
 
-    let secure_login_service = SecureLoginService::new(
+    let mut secure_login_service = SecureLoginService::new(
         auth_service.clone(),
         jwt_service.clone(),
         login_attempt_repository,
         account_lockout_repository,
-    );
+    )
+    .with_lockout_config(LockoutConfig::from_env().expect("Invalid lockout configuration"))
+    .with_tarpit_config(TarpitConfig::from_env().expect("Invalid login tarpit configuration"))
+    .with_password_max_age(get_password_max_age())
+    .with_client_repository(client_repository);
+    if let Some(otp_service) = &otp_service {
+        secure_login_service = secure_login_service.with_otp_service(otp_service.clone());
+    }
+    if get_email_login_codes() {
+        secure_login_service = secure_login_service.with_email_code_service(
+            EmailCodeService::new(email_login_code_repository).with_argon2_params(argon2_params),
+        );
+    }
+    if let Some(ttl) = get_trusted_device_ttl() {
+        secure_login_service = secure_login_service
+            .with_trusted_device_service(TrustedDeviceService::new(trusted_device_repository, ttl));
+    }
 
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::from_env().expect("Invalid rate limit configuration");
 
-    let auth_state = auth::AuthAppState::new(
+    let mut auth_state = auth::AuthAppState::new(
         auth_service,
         jwt_service.clone(),
         secure_login_service,
         user_service,
         security_state,
-    );
+    )
+    .with_cookie_policy(cookie_policy.clone());
+    if let Some(oauth_service) = google_oauth_service {
+        auth_state = auth_state.with_google_oauth_service(oauth_service);
+    }
+    if let Some(oauth_service) = github_oauth_service {
+        auth_state = auth_state.with_github_oauth_service(oauth_service);
+    }
+    if let Some(otp_service) = otp_service {
+        auth_state = auth_state.with_otp_service(otp_service);
+    }
+    if let Some(captcha_config) = CaptchaConfig::from_env().expect("Invalid CAPTCHA configuration")
+    {
+        auth_state = auth_state.with_captcha_service(CaptchaService::from_config(captcha_config));
+    }
 
-    let public_auth_routes = auth::routes().with_state(auth_state.clone());
+    let admin_state = admin_state
+        .with_jwt_service(jwt_service.clone())
+        .with_argon2_params(argon2_params);
 
-    let protected_auth_routes =
-        auth::protected_routes()
-            .with_state(auth_state)
-            .layer(middleware::from_fn_with_state(
-                std::sync::Arc::new(jwt_service.clone()),
-                jwt_auth_middleware_with_json_errors,
-            ));
+    let ip_filter_layer =
+        IpFilterLayer::new(IpFilter::from_env().expect("Invalid IP filter configuration"));
+    // Oversized bodies get 413 before any handler parses them
+    let body_limit_layer = RequestBodyLimitLayer::new(get_auth_body_limit());
+    // Strict mode answers 415 to auth request bodies not sent as application/json
+    let strict_content_type = get_strict_content_type();
+    let json_only = |router: Router| {
+        if strict_content_type {
+            router.layer(middleware::from_fn(require_json_content_type))
+        } else {
+            router
+        }
+    };
 
-    let protected_time_entries_routes =
-        time_entries::routes()
-            .with_state(time_entries_state)
-            .layer(middleware::from_fn_with_state(
-                std::sync::Arc::new(jwt_service.clone()),
-                jwt_auth_middleware_with_json_errors,
-            ));
+    let public_auth_routes = json_only(auth::routes().with_state(auth_state.clone()))
+        .layer(ip_filter_layer.clone())
+        .layer(body_limit_layer);
+
+    let protected_auth_routes = json_only(auth::protected_routes().with_state(auth_state))
+        .layer(middleware::from_fn_with_state(
+            std::sync::Arc::new(jwt_service.clone()),
+            jwt_auth_middleware_with_json_errors,
+        ))
+        .layer(ip_filter_layer)
+        .layer(body_limit_layer);
+
+    let protected_time_entries_routes = time_entries::routes()
+        .with_state(time_entries_state)
+        .layer(middleware::from_fn_with_state(
+            std::sync::Arc::new(jwt_service.clone()),
+            jwt_auth_middleware_with_json_errors,
+        ));
 
     let protected_projects_routes =
         projects::routes()
@@ -91,15 +280,72 @@ pub fn create_router(pool: PgPool) -> Router {
         tasks::routes()
             .with_state(tasks_state)
             .layer(middleware::from_fn_with_state(
-                std::sync::Arc::new(jwt_service),
+                std::sync::Arc::new(jwt_service.clone()),
                 jwt_auth_middleware_with_json_errors,
             ));
 
-    Router::new()
-        .nest("/api/users", users::routes().with_state(users_state))
+    // Raw user records are for admins only
+    let users_routes = users::routes()
+        .with_state(users_state)
+        .layer(middleware::from_fn_with_state(
+            RequireRole(ADMIN_ROLE),
+            require_role,
+        ))
+        .layer(middleware::from_fn_with_state(
+            std::sync::Arc::new(jwt_service.clone()),
+            jwt_auth_middleware_with_json_errors,
+        ));
+
+    let admin_routes = admin::routes()
+        .with_state(admin_state)
+        .layer(middleware::from_fn_with_state(
+            RequireRole(ADMIN_ROLE),
+            require_role,
+        ))
+        .layer(middleware::from_fn_with_state(
+            std::sync::Arc::new(jwt_service),
+            jwt_auth_middleware_with_json_errors,
+        ));
+
+    let router = Router::new()
+        .nest("/api/users", users_routes)
         .nest("/api/auth", public_auth_routes)
         .nest("/api/auth", protected_auth_routes)
         .nest("/api/time-entries", protected_time_entries_routes)
         .nest("/api/projects", protected_projects_routes)
         .nest("/api/tasks", protected_tasks_routes)
+        .nest("/api/admin", admin_routes);
+
+    // With RESPONSE_ENVELOPE, successful API responses are nested under "data";
+    // the OpenAPI document stays as it is for the tools that read it
+    let router = if get_response_envelope() {
+        router.layer(middleware::from_fn(wrap_success_response))
+    } else {
+        router
+    };
+    let router = router.merge(openapi::routes());
+
+    // Cookie sessions can be ridden by cross-site requests, so they need the CSRF header
+    let router = if cookie_policy.sessions_enabled() {
+        router.layer(middleware::from_fn_with_state(
+            cookie_policy,
+            csrf_protection,
+        ))
+    } else {
+        router
+    };
+
+    // Slow handlers get 504 after REQUEST_TIMEOUT_SECS and finish in the background
+    let router = match get_request_timeout() {
+        Some(timeout) => router.layer(middleware::from_fn_with_state(timeout, request_timeout)),
+        None => router,
+    };
+
+    #[cfg(feature = "metrics")]
+    if crate::app::metrics::metrics_enabled() {
+        let handle = crate::app::metrics::install_recorder();
+        return router.merge(metrics::routes(handle));
+    }
+
+    router
 }
@@ -0,0 +1,171 @@
+use crate::app::models::account_export::{
+    AccountExport, LoginAttemptExport, SecurityEventExport, SessionExport,
+};
+use crate::app::models::api_key::{ApiKeyCreatedResponse, CreateApiKeyRequest};
+use crate::app::models::auth::{
+    AccountUnlockRequest, AccountUnlockResponse, AuthErrorBody, AuthErrorResponse,
+    ChangeEmailRequest, ChangeEmailResponse, ChangePasswordRequest, ChangePasswordResponse,
+    ConfirmEmailChangeResponse, DeleteAccountRequest, FieldError, ForgotPasswordRequest,
+    ForgotPasswordResponse, LockoutStatusResponse, MeResponse, PasswordPolicy,
+    PasswordStrengthRequest, ProfileResponse, ProfileUpdateRequest, RegisterRequest,
+    RegisterResponse, RegistrationAcceptedResponse, ResetPasswordRequest, ResetPasswordResponse,
+    StrengthReport, ValidateResetTokenResponse,
+};
+use crate::app::models::common::{ErrorResponse, Paginated};
+use crate::app::models::jwt::{
+    CsrfTokenResponse, LoginRequest, LoginResponse, LogoutRequest, LogoutResponse,
+    RefreshTokenRequest, RefreshTokenResponse, TokenErrorResponse, TokenPair,
+};
+use crate::app::models::login_attempt::LoginHistoryEntry;
+use crate::app::models::otp::{
+    EmailCodeLoginRequest, EmailCodeRequest, EmailCodeResponse, OtpChallengeResponse,
+    OtpLoginRequest, PhoneResponse, RemovePhoneRequest, SetPhoneRequest, VerifyPhoneRequest,
+};
+use crate::app::models::session::{RenameSessionRequest, SessionListResponse, SessionResponse};
+use crate::app::models::trusted_device::{TrustedDeviceListResponse, TrustedDeviceResponse};
+use crate::app::models::user::UserResponse;
+use crate::routes::auth;
+use axum::Router;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+pub const DOCS_PATH: &str = "/api/docs";
+
+// Error bodies: AuthErrorResponse from the auth routes, TokenErrorResponse from the JWT
+// middleware and ErrorResponse from the user, project, task and time entry routes
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Chronos API"),
+    paths(
+        auth::register,
+        auth::login,
+        auth::login_otp,
+        auth::request_email_code,
+        auth::login_email_code,
+        auth::forgot_password,
+        auth::reset_password,
+        auth::validate_reset_token,
+        auth::confirm_email_change,
+        auth::lockout_status,
+        auth::request_unlock,
+        auth::consume_unlock_token,
+        auth::password_policy,
+        auth::password_strength_check,
+        auth::refresh_token,
+        auth::csrf_token,
+        auth::google_oauth_start,
+        auth::google_oauth_callback,
+        auth::github_oauth_start,
+        auth::github_oauth_callback,
+        auth::logout,
+        auth::get_profile,
+        auth::get_me,
+        auth::update_profile,
+        auth::change_email,
+        auth::change_password,
+        auth::delete_account,
+        auth::export_account,
+        auth::list_sessions,
+        auth::rename_session,
+        auth::create_api_key,
+        auth::revoke_api_key,
+        auth::login_history,
+        auth::get_phone,
+        auth::set_phone,
+        auth::verify_phone,
+        auth::remove_phone,
+        auth::list_trusted_devices,
+        auth::revoke_trusted_device,
+    ),
+    components(schemas(
+        AuthErrorResponse,
+        AuthErrorBody,
+        FieldError,
+        TokenErrorResponse,
+        ErrorResponse,
+        RegisterRequest,
+        RegisterResponse,
+        RegistrationAcceptedResponse,
+        LoginRequest,
+        LoginResponse,
+        TokenPair,
+        RefreshTokenRequest,
+        RefreshTokenResponse,
+        CsrfTokenResponse,
+        LogoutRequest,
+        LogoutResponse,
+        ForgotPasswordRequest,
+        ForgotPasswordResponse,
+        ResetPasswordRequest,
+        ResetPasswordResponse,
+        ValidateResetTokenResponse,
+        ConfirmEmailChangeResponse,
+        LockoutStatusResponse,
+        AccountUnlockRequest,
+        AccountUnlockResponse,
+        PasswordStrengthRequest,
+        PasswordPolicy,
+        StrengthReport,
+        ProfileResponse,
+        MeResponse,
+        ProfileUpdateRequest,
+        ChangeEmailRequest,
+        ChangeEmailResponse,
+        ChangePasswordRequest,
+        ChangePasswordResponse,
+        DeleteAccountRequest,
+        UserResponse,
+        AccountExport,
+        SessionExport,
+        LoginAttemptExport,
+        SecurityEventExport,
+        SessionResponse,
+        SessionListResponse,
+        RenameSessionRequest,
+        CreateApiKeyRequest,
+        ApiKeyCreatedResponse,
+        LoginHistoryEntry,
+        Paginated<LoginHistoryEntry>,
+        OtpChallengeResponse,
+        OtpLoginRequest,
+        EmailCodeRequest,
+        EmailCodeResponse,
+        EmailCodeLoginRequest,
+        PhoneResponse,
+        SetPhoneRequest,
+        VerifyPhoneRequest,
+        RemovePhoneRequest,
+        TrustedDeviceResponse,
+        TrustedDeviceListResponse,
+    )),
+    modifiers(&BearerAuth),
+    tags((name = "auth", description = "Registration, login, tokens and the current user's account"))
+)]
+pub struct ApiDoc;
+
+// The access token from login or refresh, sent as `Authorization: Bearer <token>`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+// Mounted outside the auth and rate limiting layers so the docs need no credentials
+pub fn routes() -> Router {
+    SwaggerUi::new(DOCS_PATH)
+        .url(OPENAPI_PATH, ApiDoc::openapi())
+        .into()
+}
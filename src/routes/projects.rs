@@ -1,14 +1,16 @@
+use crate::app::models::common::ErrorResponse;
 use crate::app::models::project::{ProjectResponse, CreateProjectRequest, UpdateProjectRequest};
 use crate::app::repositories::project_repository::ProjectRepository;
 use crate::app::services::project_service::{ProjectService, ProjectServiceError};
-use crate::app::middleware::auth_middleware::AuthUser;
+use crate::app::middleware::auth_middleware::{AuthUser, scoped};
+use crate::app::models::scope::{PROJECTS_READ, PROJECTS_WRITE};
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
     http::StatusCode,
     routing::{get, post, put, delete},
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use sqlx::PgPool;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -26,11 +28,6 @@ impl ProjectsState {
     }
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-}
-
 #[derive(Deserialize)]
 struct ProjectQueryParams {
     include_inactive: Option<bool>,
@@ -44,10 +41,13 @@ struct DeleteQueryParams {
 
 pub fn routes() -> Router<ProjectsState> {
     Router::new()
-        .route("/", get(list_projects).post(create_project))
-        .route("/{id}", get(get_project).put(update_project).delete(delete_project))
-        .route("/{id}/archive", put(archive_project))
-        .route("/{id}/restore", put(restore_project))
+        .route("/", scoped(PROJECTS_READ, get(list_projects)))
+        .route("/", scoped(PROJECTS_WRITE, post(create_project)))
+        .route("/{id}", scoped(PROJECTS_READ, get(get_project)))
+        .route("/{id}", scoped(PROJECTS_WRITE, put(update_project)))
+        .route("/{id}", scoped(PROJECTS_WRITE, delete(delete_project)))
+        .route("/{id}/archive", scoped(PROJECTS_WRITE, put(archive_project)))
+        .route("/{id}/restore", scoped(PROJECTS_WRITE, put(restore_project)))
 }
 
 async fn create_project(
@@ -1,15 +1,17 @@
+use crate::app::models::common::ErrorResponse;
 use crate::app::models::task::{TaskResponse, CreateTaskRequest, UpdateTaskRequest};
 use crate::app::repositories::task_repository::TaskRepository;
 use crate::app::repositories::project_repository::ProjectRepository;
 use crate::app::services::task_service::{TaskService, TaskServiceError};
-use crate::app::middleware::auth_middleware::AuthUser;
+use crate::app::middleware::auth_middleware::{AuthUser, scoped};
+use crate::app::models::scope::{TASKS_READ, TASKS_WRITE};
 use axum::{
     Json, Router,
     extract::{Path, Query, State},
     http::StatusCode,
     routing::{get, post, put, delete},
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use sqlx::PgPool;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -29,11 +31,6 @@ impl TasksState {
     }
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-}
-
 #[derive(Deserialize)]
 struct TaskQueryParams {
     include_inactive: Option<bool>,
@@ -48,10 +45,13 @@ struct DeleteQueryParams {
 
 pub fn routes() -> Router<TasksState> {
     Router::new()
-        .route("/", get(list_tasks).post(create_task))
-        .route("/{id}", get(get_task).put(update_task).delete(delete_task))
-        .route("/{id}/archive", put(archive_task))
-        .route("/{id}/restore", put(restore_task))
+        .route("/", scoped(TASKS_READ, get(list_tasks)))
+        .route("/", scoped(TASKS_WRITE, post(create_task)))
+        .route("/{id}", scoped(TASKS_READ, get(get_task)))
+        .route("/{id}", scoped(TASKS_WRITE, put(update_task)))
+        .route("/{id}", scoped(TASKS_WRITE, delete(delete_task)))
+        .route("/{id}/archive", scoped(TASKS_WRITE, put(archive_task)))
+        .route("/{id}/restore", scoped(TASKS_WRITE, put(restore_task)))
 }
 
 async fn create_task(
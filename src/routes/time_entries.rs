@@ -1,6 +1,8 @@
-use crate::app::middleware::auth_middleware::AuthUser;
+use crate::app::middleware::auth_middleware::{AuthUser, scoped};
+use crate::app::models::common::ErrorResponse;
+use crate::app::models::scope::{TIME_ENTRIES_READ, TIME_ENTRIES_WRITE};
 use crate::app::models::time_entry::{
-    CreateTimeEntryRequest, UpdateTimeEntryRequest, TimeEntryFilters, 
+    CreateTimeEntryRequest, StartTimerRequest, UpdateTimeEntryRequest, TimeEntryFilters, 
     TimeEntryResponse, TimeEntriesListResponse, TimeEntryError
 };
 use crate::app::repositories::time_entry_repository::TimeEntryRepository;
@@ -11,7 +13,6 @@ use axum::{
     http::StatusCode,
     routing::{get, post, patch, delete},
 };
-use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -30,29 +31,16 @@ impl TimeEntriesState {
     }
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-}
-
-#[derive(Deserialize, Validate)]
-struct StartTimerRequest {
-    #[validate(length(max = 1000, message = "Description cannot exceed 1000 characters"))]
-    pub description: Option<String>,
-    pub project_id: Option<Uuid>,
-    pub task_id: Option<Uuid>,
-}
-
 pub fn routes() -> Router<TimeEntriesState> {
     Router::new()
-        .route("/", post(create_time_entry))
-        .route("/", get(list_time_entries))
-        .route("/start", post(start_timer))
-        .route("/current", get(get_current_timer))
-        .route("/{id}", get(get_time_entry))
-        .route("/{id}", patch(update_time_entry))
-        .route("/{id}", delete(delete_time_entry))
-        .route("/{id}/stop", patch(stop_timer))
+        .route("/", scoped(TIME_ENTRIES_WRITE, post(create_time_entry)))
+        .route("/", scoped(TIME_ENTRIES_READ, get(list_time_entries)))
+        .route("/start", scoped(TIME_ENTRIES_WRITE, post(start_timer)))
+        .route("/current", scoped(TIME_ENTRIES_READ, get(get_current_timer)))
+        .route("/{id}", scoped(TIME_ENTRIES_READ, get(get_time_entry)))
+        .route("/{id}", scoped(TIME_ENTRIES_WRITE, patch(update_time_entry)))
+        .route("/{id}", scoped(TIME_ENTRIES_WRITE, delete(delete_time_entry)))
+        .route("/{id}/stop", scoped(TIME_ENTRIES_WRITE, patch(stop_timer)))
 }
 
 async fn create_time_entry(
@@ -1,17 +1,20 @@
-use crate::app::models::user::User;
+use crate::app::models::common::ErrorResponse;
+use crate::app::models::user::{UserCursor, UserListQuery, UserResponse};
 use crate::app::repositories::user_repository::UserRepository;
 use crate::app::services::user_service::UserService;
 use axum::{
     Json, Router,
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
     routing::get,
 };
-use serde::Serialize;
 use sqlx::PgPool;
 use std::sync::Arc;
 use uuid::Uuid;
 
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 100;
+
 #[derive(Clone)]
 pub struct AppState {
     user_service: Arc<UserService>,
@@ -25,11 +28,7 @@ impl AppState {
     }
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-}
-
+// Admin only; create_router puts these behind the JWT middleware and RequireRole
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_users))
@@ -37,26 +36,79 @@ pub fn routes() -> Router<AppState> {
         .route("/{id}", get(get_user))
 }
 
+// Without `limit` or `after` every user is returned. Otherwise one page is
+// returned and a `Link: <...>; rel="next"` header points at the next one.
 async fn list_users(
     State(state): State<AppState>,
-) -> Result<Json<Vec<User>>, (StatusCode, Json<ErrorResponse>)> {
-    match state.user_service.get_all_users().await {
-        Ok(users) => Ok(Json(users)),
-        Err(e) => Err((
+    Query(query): Query<UserListQuery>,
+) -> Result<(HeaderMap, Json<Vec<UserResponse>>), (StatusCode, Json<ErrorResponse>)> {
+    let internal_error = |e: Box<dyn std::error::Error>| {
+        (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
                 error: e.to_string(),
             }),
-        )),
+        )
+    };
+
+    if query.limit.is_none() && query.after.is_none() {
+        let users = state
+            .user_service
+            .get_all_users()
+            .await
+            .map_err(internal_error)?;
+        return Ok((
+            HeaderMap::new(),
+            Json(users.iter().map(|user| user.to_response()).collect()),
+        ));
     }
+
+    let cursor = match query.after.as_deref() {
+        Some(after) => Some(UserCursor::decode(after).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "Invalid cursor".to_string(),
+                }),
+            )
+        })?),
+        None => None,
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+
+    let (users, next_cursor) = state
+        .user_service
+        .list_users_page(cursor, limit)
+        .await
+        .map_err(internal_error)?;
+
+    let mut headers = HeaderMap::new();
+    if let Some(next_cursor) = next_cursor {
+        let link = format!(
+            "</api/users?limit={}&after={}>; rel=\"next\"",
+            limit,
+            next_cursor.encode()
+        );
+        if let Ok(value) = HeaderValue::from_str(&link) {
+            headers.insert(header::LINK, value);
+        }
+    }
+
+    Ok((
+        headers,
+        Json(users.iter().map(|user| user.to_response()).collect()),
+    ))
 }
 
 async fn get_user(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<User>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<UserResponse>, (StatusCode, Json<ErrorResponse>)> {
     match state.user_service.get_user_by_id(id).await {
-        Ok(Some(user)) => Ok(Json(user)),
+        Ok(Some(user)) => Ok(Json(user.to_response())),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
             Json(ErrorResponse {
@@ -75,9 +127,9 @@ async fn get_user(
 async fn get_user_by_email(
     State(state): State<AppState>,
     Path(email): Path<String>,
-) -> Result<Json<User>, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<Json<UserResponse>, (StatusCode, Json<ErrorResponse>)> {
     match state.user_service.get_user_by_email(&email).await {
-        Ok(Some(user)) => Ok(Json(user)),
+        Ok(Some(user)) => Ok(Json(user.to_response())),
         Ok(None) => Err((
             StatusCode::NOT_FOUND,
             Json(ErrorResponse {
@@ -0,0 +1,238 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::{User, UserStatus};
+use chronos::app::repositories::admin_audit_repository::AdminAuditRepository;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::user_service::{UserService, UserServiceError};
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "StatusPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Each test gets its own IP so failed logins don't add up across tests
+fn create_test_app(pool: PgPool, ip: &str) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("status-{}@example.com", Uuid::new_v4().simple()),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn create_admin(pool: &PgPool) -> User {
+    let user = create_user(pool).await;
+    let role_repository = RoleRepository::new(pool.clone());
+    let admin_role = role_repository
+        .find_by_name("admin")
+        .await
+        .unwrap()
+        .expect("admin role should be seeded");
+    role_repository
+        .grant_role(user.id, admin_role.id)
+        .await
+        .unwrap();
+    user
+}
+
+async fn login(app: &axum::Router, email: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": TEST_PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    send(app, request).await
+}
+
+async fn access_token(app: &axum::Router, email: &str) -> String {
+    let (status, body) = login(app, email).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+async fn get_profile(app: &axum::Router, token: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/profile")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+async fn admin_action(
+    app: &axum::Router,
+    token: &str,
+    user_id: Uuid,
+    action: &str,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(format!("/api/admin/users/{}/{}", user_id, action))
+        .method("POST")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_disabled_user_cannot_log_in_until_enabled() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.3.1");
+    let user_service = UserService::new(UserRepository::new(pool.clone()));
+    let user = create_user(&pool).await;
+
+    user_service.disable_user(user.id).await.unwrap();
+
+    let (status, body) = login(&app, &user.email).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "ACCOUNT_DISABLED");
+
+    user_service.enable_user(user.id).await.unwrap();
+
+    let (status, body) = login(&app, &user.email).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user"]["email"], user.email.as_str());
+}
+
+#[tokio::test]
+async fn test_tokens_of_disabled_user_are_rejected() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.3.2");
+    let user_service = UserService::new(UserRepository::new(pool.clone()));
+    let user = create_user(&pool).await;
+    let token = access_token(&app, &user.email).await;
+
+    let (status, _) = get_profile(&app, &token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    user_service.disable_user(user.id).await.unwrap();
+    let (status, body) = get_profile(&app, &token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "ACCOUNT_UNAVAILABLE");
+
+    // The token itself was never revoked, so it works again once re-enabled
+    user_service.enable_user(user.id).await.unwrap();
+    let (status, _) = get_profile(&app, &token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_admin_disable_and_enable_endpoints() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.3.3");
+    let admin = create_admin(&pool).await;
+    let admin_token = access_token(&app, &admin.email).await;
+    let user = create_user(&pool).await;
+
+    let (status, body) = admin_action(&app, &admin_token, user.id, "disable").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "disabled");
+    let (status, _) = login(&app, &user.email).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = admin_action(&app, &admin_token, user.id, "enable").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "active");
+    let (status, _) = login(&app, &user.email).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let actions: Vec<String> = AdminAuditRepository::new(pool.clone())
+        .find_by_target_user(user.id)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.action)
+        .collect();
+    assert!(actions.contains(&"admin_user_disabled".to_string()));
+    assert!(actions.contains(&"admin_user_enabled".to_string()));
+
+    let (status, _) = admin_action(&app, &admin_token, Uuid::new_v4(), "disable").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = admin_action(&app, &admin_token, admin.id, "disable").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Only admins may change another account's status
+    let user_token = access_token(&app, &user.email).await;
+    let (status, _) = admin_action(&app, &user_token, admin.id, "disable").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_soft_deleted_user_is_hidden_but_retained() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.3.4");
+    let user_repository = UserRepository::new(pool.clone());
+    let user_service = UserService::new(user_repository.clone());
+    let user = create_user(&pool).await;
+    let token = access_token(&app, &user.email).await;
+
+    assert!(user_service.delete_user(user.id).await.unwrap());
+
+    assert!(user_repository.find_by_id(user.id).await.unwrap().is_none());
+    assert!(
+        user_repository
+            .find_by_email(&user.email)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        user_repository.status(user.id).await.unwrap(),
+        Some(UserStatus::Deleted)
+    );
+
+    let (status, _) = login(&app, &user.email).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = get_profile(&app, &token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "ACCOUNT_UNAVAILABLE");
+
+    // Deletion is final
+    assert!(matches!(
+        user_service.enable_user(user.id).await,
+        Err(UserServiceError::UserNotFound)
+    ));
+    assert!(!user_service.delete_user(user.id).await.unwrap());
+}
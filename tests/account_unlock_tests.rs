@@ -0,0 +1,347 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::rate_limiter::RateLimitConfig;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::login_attempt::LockoutConfig;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::security_events::{
+    SecurityEvent, SecurityEventSink, Severity, add_security_event_sink,
+};
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "UnlockPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Accounts lock on the third failed login; each app gets its own client IP
+fn create_test_app(pool: &PgPool, email_service: MockEmailService) -> Router {
+    create_test_app_with_config(
+        pool,
+        email_service,
+        LockoutConfig {
+            max_attempts: 2,
+            ..LockoutConfig::default()
+        },
+    )
+}
+
+fn create_test_app_with_config(
+    pool: &PgPool,
+    email_service: MockEmailService,
+    config: LockoutConfig,
+) -> Router {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        email_service,
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+    .with_lockout_config(config);
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(UserRepository::new(pool.clone())),
+        SecurityState::new(RateLimitConfig::default()),
+    );
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("unlock-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn get(uri: String) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+fn post_json(uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+async fn login(app: &Router, email: &str, password: &str) -> StatusCode {
+    let body = json!({ "email": email, "password": password });
+    send(app, post_json("/api/auth/login", body)).await.0
+}
+
+async fn lockout_status(app: &Router, email: &str) -> Value {
+    let (status, body) = send(
+        app,
+        get(format!("/api/auth/lockout-status?email={}", email)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body
+}
+
+// The token is the one line of the email made of 64 letters and digits
+fn unlock_token(email_service: &MockEmailService) -> String {
+    let email = email_service.get_last_sent_email().unwrap();
+    find_token(&email.body).unwrap()
+}
+
+fn find_token(body: &str) -> Option<String> {
+    body.lines()
+        .map(str::trim)
+        .find(|line| line.len() == 64 && line.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(str::to_string)
+}
+
+fn lockout_emails(email_service: &MockEmailService, to: &str) -> Vec<String> {
+    email_service
+        .get_sent_emails()
+        .into_iter()
+        .filter(|email| email.to == to && email.subject.contains("account was locked"))
+        .map(|email| email.body)
+        .collect()
+}
+
+// Keeps every event it is handed; events from other tests are filtered by email
+#[derive(Default)]
+struct CapturingSink {
+    events: Mutex<Vec<SecurityEvent>>,
+}
+
+impl SecurityEventSink for CapturingSink {
+    fn record(&self, event: &SecurityEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_unlock_link_ends_lockout_early() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new();
+    let app = create_test_app(&pool, email_service.clone());
+    let user = create_user(&pool).await;
+
+    for _ in 0..2 {
+        assert_eq!(
+            login(&app, &user.email, "WrongPassword1!").await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+    assert_eq!(
+        login(&app, &user.email, "WrongPassword1!").await,
+        StatusCode::LOCKED
+    );
+    assert_eq!(login(&app, &user.email, PASSWORD).await, StatusCode::LOCKED);
+
+    let status = lockout_status(&app, &user.email).await;
+    assert_eq!(status["locked"], true);
+    assert!(status["locked_until"].is_string());
+    assert!(status["retry_after"].as_u64().unwrap() > 0);
+
+    let (status, body) = send(
+        &app,
+        post_json("/api/auth/unlock", json!({ "email": user.email })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["message"].is_string());
+    assert_eq!(email_service.get_last_sent_email().unwrap().to, user.email);
+    let token = unlock_token(&email_service);
+
+    let consume = || get(format!("/api/auth/unlock/consume?token={}", token));
+    let (status, _) = send(&app, consume()).await;
+    assert_eq!(status, StatusCode::OK);
+    // Single use
+    let (status, body) = send(&app, consume()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+
+    assert_eq!(lockout_status(&app, &user.email).await["locked"], false);
+    assert_eq!(login(&app, &user.email, PASSWORD).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_unlocked_and_unknown_accounts_get_generic_answers() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new();
+    let app = create_test_app(&pool, email_service.clone());
+    let user = create_user(&pool).await;
+    let unknown = format!("nobody-{}@example.com", Uuid::new_v4().simple());
+
+    let known_status = lockout_status(&app, &user.email).await;
+    let unknown_status = lockout_status(&app, &unknown).await;
+    assert_eq!(known_status, json!({ "locked": false }));
+    assert_eq!(unknown_status, known_status);
+
+    let (status, known_body) = send(
+        &app,
+        post_json("/api/auth/unlock", json!({ "email": user.email })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, unknown_body) = send(
+        &app,
+        post_json("/api/auth/unlock", json!({ "email": unknown })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(known_body, unknown_body);
+    // Nothing to unlock, so nothing is sent
+    assert_eq!(email_service.count_sent_emails(), 0);
+
+    let (status, _) = send(
+        &app,
+        get("/api/auth/unlock/consume?token=not-a-token".to_string()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_lockout_notifies_owner_once_with_unlock_token() {
+    let pool = setup_test_pool().await;
+    let sink = Arc::new(CapturingSink::default());
+    add_security_event_sink(sink.clone());
+    let email_service = MockEmailService::new();
+    let app = create_test_app(&pool, email_service.clone());
+    let user = create_user(&pool).await;
+
+    for _ in 0..2 {
+        login(&app, &user.email, "WrongPassword1!").await;
+    }
+    assert!(lockout_emails(&email_service, &user.email).is_empty());
+    assert_eq!(
+        login(&app, &user.email, "WrongPassword1!").await,
+        StatusCode::LOCKED
+    );
+
+    // Further attempts during the same lockout don't send anything more
+    for password in ["WrongPassword1!", PASSWORD] {
+        assert_eq!(login(&app, &user.email, password).await, StatusCode::LOCKED);
+    }
+    let emails = lockout_emails(&email_service, &user.email);
+    assert_eq!(emails.len(), 1);
+    assert_eq!(email_service.count_sent_emails(), 1);
+
+    let events: Vec<SecurityEvent> = sink
+        .events
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| {
+            event.event_type == "account_locked" && event.email.as_deref() == Some(&user.email)
+        })
+        .cloned()
+        .collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].severity, Severity::High);
+
+    // The token in the notification ends the lockout
+    let token = find_token(&emails[0]).unwrap();
+    let (status, _) = send(
+        &app,
+        get(format!("/api/auth/unlock/consume?token={}", token)),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(lockout_status(&app, &user.email).await["locked"], false);
+}
+
+#[tokio::test]
+async fn test_lockout_notification_without_self_service_unlock() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new();
+    let app = create_test_app_with_config(
+        &pool,
+        email_service.clone(),
+        LockoutConfig {
+            max_attempts: 2,
+            self_service_unlock: false,
+            ..LockoutConfig::default()
+        },
+    );
+    let user = create_user(&pool).await;
+
+    for _ in 0..3 {
+        login(&app, &user.email, "WrongPassword1!").await;
+    }
+    let emails = lockout_emails(&email_service, &user.email);
+    assert_eq!(emails.len(), 1);
+    assert!(find_token(&emails[0]).is_none());
+
+    // Nor can one be asked for
+    let (status, _) = send(
+        &app,
+        post_json("/api/auth/unlock", json!({ "email": user.email })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(email_service.count_sent_emails(), 1);
+    assert_eq!(login(&app, &user.email, PASSWORD).await, StatusCode::LOCKED);
+}
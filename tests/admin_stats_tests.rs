@@ -0,0 +1,142 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::user_repository::{InMemoryUserStore, UserRepository, UserStore};
+use chronos::app::services::user_service::UserService;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use time::{Duration, OffsetDateTime};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "StatsPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: PgPool) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        "192.168.12.1:8080".parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn new_user() -> User {
+    User::new_with_params(
+        None,
+        format!("stats-{}@example.com", Uuid::new_v4().simple()),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap()
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    UserRepository::new(pool.clone())
+        .create(&new_user())
+        .await
+        .unwrap()
+}
+
+async fn access_token(app: &axum::Router, pool: &PgPool, admin: bool) -> String {
+    let user = create_user(pool).await;
+    if admin {
+        let role_repository = RoleRepository::new(pool.clone());
+        let admin_role = role_repository
+            .find_by_name("admin")
+            .await
+            .unwrap()
+            .expect("admin role should be seeded");
+        role_repository
+            .grant_role(user.id, admin_role.id)
+            .await
+            .unwrap();
+    }
+
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": user.email, "password": TEST_PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+async fn stats(app: &axum::Router, token: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/admin/stats")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_stats_count_new_users() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+
+    let user_token = access_token(&app, &pool, false).await;
+    let (status, _) = stats(&app, &user_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let admin_token = access_token(&app, &pool, true).await;
+    let (status, before) = stats(&app, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    // The logins above opened sessions
+    assert!(before["active_sessions"].as_i64().unwrap() >= 2);
+
+    create_user(&pool).await;
+    create_user(&pool).await;
+
+    let (status, after) = stats(&app, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    let grew_by = |field: &str| after[field].as_i64().unwrap() - before[field].as_i64().unwrap();
+    assert_eq!(grew_by("total_users"), 2);
+    assert_eq!(grew_by("new_users_24h"), 2);
+    assert_eq!(grew_by("new_users_7d"), 2);
+    assert_eq!(grew_by("new_users_30d"), 2);
+}
+
+#[tokio::test]
+async fn test_user_service_counts_by_registration_time() {
+    let store = InMemoryUserStore::new();
+    let mut old_user = new_user();
+    old_user.created_at = Some(OffsetDateTime::now_utc() - Duration::days(10));
+    store.create(&old_user).await.unwrap();
+    store.create(&new_user()).await.unwrap();
+    let deleted = store.create(&new_user()).await.unwrap();
+    store.soft_delete(deleted.id).await.unwrap();
+
+    let service = UserService::new(store);
+    assert_eq!(service.count().await.unwrap(), 2);
+    let since = OffsetDateTime::now_utc() - Duration::days(7);
+    assert_eq!(service.count_since(since).await.unwrap(), 1);
+}
@@ -0,0 +1,210 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "ImportPass123!";
+const NEW_PASSWORD: &str = "ImportPass456!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Each test gets its own IP so failed logins don't add up across tests
+fn create_test_app(pool: PgPool, ip: &str) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn unique_email(prefix: &str) -> String {
+    format!("{}-{}@example.com", prefix, Uuid::new_v4().simple())
+}
+
+async fn create_user(pool: &PgPool, admin: bool) -> User {
+    let user = User::new_with_params(
+        None,
+        unique_email("import-admin"),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    let user = UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap();
+    if admin {
+        let role_repository = RoleRepository::new(pool.clone());
+        let admin_role = role_repository
+            .find_by_name("admin")
+            .await
+            .unwrap()
+            .expect("admin role should be seeded");
+        role_repository
+            .grant_role(user.id, admin_role.id)
+            .await
+            .unwrap();
+    }
+    user
+}
+
+async fn login(app: &axum::Router, email: &str, password: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": password }).to_string(),
+        ))
+        .unwrap();
+    send(app, request).await
+}
+
+async fn access_token(app: &axum::Router, pool: &PgPool, admin: bool) -> String {
+    let user = create_user(pool, admin).await;
+    let (status, body) = login(app, &user.email, TEST_PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+async fn import(app: &axum::Router, token: &str, query: &str, rows: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(format!("/api/admin/users/import{}", query))
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::from(rows.to_string()))
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_import_reports_each_row() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.14.1");
+    let admin_token = access_token(&app, &pool, true).await;
+    let existing = create_user(&pool, false).await;
+
+    let plaintext = unique_email("import-plain");
+    let bcrypt_user = unique_email("import-bcrypt");
+    let rows = json!([
+        { "email": plaintext, "name": "Plain", "password": TEST_PASSWORD },
+        { "email": bcrypt_user, "password_hash": bcrypt::hash(TEST_PASSWORD, 4).unwrap(), "hash_scheme": "bcrypt" },
+        { "email": "not-an-email", "password": TEST_PASSWORD },
+        { "email": unique_email("import-weak"), "password": "weak" },
+        { "email": unique_email("import-scheme"), "password_hash": bcrypt::hash(TEST_PASSWORD, 4).unwrap(), "hash_scheme": "pbkdf2" },
+        { "email": unique_email("import-nohash"), "hash_scheme": "bcrypt" },
+        { "email": existing.email, "password": NEW_PASSWORD },
+        { "email": plaintext, "password": NEW_PASSWORD }
+    ]);
+
+    let user_token = access_token(&app, &pool, false).await;
+    let (status, _) = import(&app, &user_token, "", rows.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = import(&app, &admin_token, "", rows).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["created"], 2);
+    assert_eq!(body["updated"], 0);
+    assert_eq!(body["skipped"], 2);
+    assert_eq!(body["failed"], 4);
+
+    let results = body["results"].as_array().unwrap();
+    let statuses: Vec<&str> = results
+        .iter()
+        .map(|result| result["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            "created", "created", "failed", "failed", "failed", "failed", "skipped", "skipped"
+        ]
+    );
+    for (index, result) in results.iter().enumerate() {
+        assert_eq!(result["index"], index);
+    }
+    assert_eq!(results[0]["email"], plaintext);
+    assert!(results[0]["user_id"].is_string());
+    assert_eq!(results[2]["error"], "Invalid email format");
+    assert!(
+        results[4]["error"]
+            .as_str()
+            .unwrap()
+            .contains("not a valid pbkdf2 hash")
+    );
+    assert_eq!(results[5]["error"], "Give either password or password_hash");
+    assert_eq!(results[6]["user_id"], existing.id.to_string());
+    // The repeated email within the batch is skipped too
+    assert_eq!(results[7]["user_id"], results[0]["user_id"]);
+
+    // Both imported users can log in with their original passwords
+    let (status, _) = login(&app, &plaintext, TEST_PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = login(&app, &bcrypt_user, TEST_PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+    // The skipped rows left the existing password alone
+    let (status, _) = login(&app, &existing.email, TEST_PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_import_can_update_existing_users() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.14.2");
+    let admin_token = access_token(&app, &pool, true).await;
+    let existing = create_user(&pool, false).await;
+
+    let (status, body) = import(
+        &app,
+        &admin_token,
+        "?on_duplicate=update",
+        json!([{ "email": existing.email, "name": "Migrated", "password": NEW_PASSWORD }]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["updated"], 1);
+    assert_eq!(body["results"][0]["status"], "updated");
+    assert_eq!(body["results"][0]["user_id"], existing.id.to_string());
+
+    let user = UserRepository::new(pool.clone())
+        .find_by_id(existing.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(user.name.as_deref(), Some("Migrated"));
+
+    let (status, _) = login(&app, &existing.email, TEST_PASSWORD).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = login(&app, &existing.email, NEW_PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = import(&app, &admin_token, "", json!([])).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
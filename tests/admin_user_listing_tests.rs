@@ -0,0 +1,249 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "ListingPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: PgPool) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        "192.168.1.60:8080".parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn create_user(pool: &PgPool, name: Option<String>, email: String) -> User {
+    let user = User::new_with_params(
+        name,
+        email,
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// Log in as a fresh user, granting the admin role first when asked
+async fn access_token(app: &axum::Router, pool: &PgPool, admin: bool) -> String {
+    let user = create_user(
+        pool,
+        None,
+        format!("listing-admin-{}@example.com", Uuid::new_v4().simple()),
+    )
+    .await;
+    if admin {
+        let role_repository = RoleRepository::new(pool.clone());
+        let admin_role = role_repository
+            .find_by_name("admin")
+            .await
+            .unwrap()
+            .expect("admin role should be seeded");
+        role_repository
+            .grant_role(user.id, admin_role.id)
+            .await
+            .unwrap();
+    }
+
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": user.email, "password": TEST_PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+async fn list(app: &axum::Router, token: &str, query: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(format!("/api/admin/users?{}", query))
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+fn emails(body: &Value) -> Vec<String> {
+    body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["email"].as_str().unwrap().to_string())
+        .collect()
+}
+
+// A marker no other row contains, so searches only see this test's users
+fn marker() -> String {
+    format!("mk{}", &Uuid::new_v4().simple().to_string()[..12])
+}
+
+#[tokio::test]
+async fn test_pagination_boundaries() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let token = access_token(&app, &pool, true).await;
+
+    let marker = marker();
+    let mut expected = Vec::new();
+    for i in 0..5 {
+        let email = format!("{}-{}@example.com", marker, i);
+        create_user(&pool, None, email.clone()).await;
+        expected.push(email);
+    }
+
+    let mut seen = Vec::new();
+    for (page, size) in [(1, 2), (2, 2), (3, 1)] {
+        let (status, body) = list(
+            &app,
+            &token,
+            &format!("search={}&sort=email&per_page=2&page={}", marker, page),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["total"], 5);
+        assert_eq!(body["page"], page);
+        assert_eq!(body["per_page"], 2);
+        assert_eq!(body["items"].as_array().unwrap().len(), size);
+        assert!(body["items"][0].get("password_hash").is_none());
+        seen.extend(emails(&body));
+    }
+    assert_eq!(seen, expected);
+
+    // Past the last page: nothing, but the total is still reported
+    let (status, body) = list(
+        &app,
+        &token,
+        &format!("search={}&per_page=2&page=4", marker),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 5);
+    assert!(body["items"].as_array().unwrap().is_empty());
+
+    let (_, body) = list(&app, &token, &format!("search={}&sort=-email", marker)).await;
+    let mut reversed = expected.clone();
+    reversed.reverse();
+    assert_eq!(emails(&body), reversed);
+}
+
+#[tokio::test]
+async fn test_out_of_range_pagination_is_clamped() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let token = access_token(&app, &pool, true).await;
+
+    let (status, body) = list(&app, &token, "per_page=5000").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["per_page"], 100);
+    assert!(body["items"].as_array().unwrap().len() <= 100);
+
+    let (_, body) = list(&app, &token, "page=0&per_page=0").await;
+    assert_eq!(body["page"], 1);
+    assert_eq!(body["per_page"], 1);
+    assert_eq!(body["items"].as_array().unwrap().len(), 1);
+
+    let (_, body) = list(&app, &token, "").await;
+    assert_eq!(body["page"], 1);
+    assert_eq!(body["per_page"], 20);
+}
+
+#[tokio::test]
+async fn test_search_matches_name_and_email() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let token = access_token(&app, &pool, true).await;
+
+    let marker = marker();
+    let by_email = create_user(&pool, None, format!("{}@example.com", marker)).await;
+    let by_name = create_user(
+        &pool,
+        Some(format!("Ada {}", marker)),
+        format!("listing-{}@example.com", Uuid::new_v4().simple()),
+    )
+    .await;
+
+    // Case-insensitive, on either column
+    let (status, body) = list(
+        &app,
+        &token,
+        &format!("search={}&sort=name", marker.to_uppercase()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 2);
+    assert_eq!(
+        emails(&body),
+        vec![by_name.email.clone(), by_email.email.clone()]
+    );
+    assert_eq!(body["items"][0]["name"], format!("Ada {}", marker));
+
+    // LIKE wildcards in the search are matched literally
+    let (_, body) = list(&app, &token, &format!("search={}%25", marker)).await;
+    assert_eq!(body["total"], 0);
+    let (_, body) = list(&app, &token, &format!("search={}_", marker)).await;
+    assert_eq!(body["total"], 0);
+}
+
+#[tokio::test]
+async fn test_invalid_sort_is_rejected() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let token = access_token(&app, &pool, true).await;
+
+    let (status, body) = list(&app, &token, "sort=password_hash").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("Invalid sort"));
+}
+
+#[tokio::test]
+async fn test_listing_requires_admin() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let token = access_token(&app, &pool, false).await;
+
+    let (status, _) = list(&app, &token, "").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let request = Request::builder()
+        .uri("/api/admin/users")
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,309 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::jwt::{JwtError, TokenType};
+use chronos::app::models::scope::default_scopes;
+use chronos::app::models::user::User;
+use chronos::app::repositories::api_key_repository::ApiKeyRepository;
+use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "ApiKeyPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Each test gets its own IP so logins don't add up across tests
+fn create_test_app(pool: PgPool) -> axum::Router {
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    routes::create_router(pool).layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("api-key-{}@example.com", Uuid::new_v4().simple()),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn login(app: &axum::Router, email: &str) -> String {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": TEST_PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+async fn create_key(app: &axum::Router, bearer: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/api-keys")
+        .method("POST")
+        .header("authorization", format!("Bearer {}", bearer))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    send(app, request).await
+}
+
+async fn revoke_key(app: &axum::Router, bearer: &str, id: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri(format!("/api/auth/api-keys/{}", id))
+        .method("DELETE")
+        .header("authorization", format!("Bearer {}", bearer))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await.0
+}
+
+async fn get_profile(app: &axum::Router, bearer: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/profile")
+        .header("authorization", format!("Bearer {}", bearer))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_api_key_authenticates_until_revoked() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let user = create_user(&pool).await;
+    let access_token = login(&app, &user.email).await;
+
+    let (status, body) = create_key(
+        &app,
+        &access_token,
+        json!({ "name": " CI runner ", "scopes": ["profile:read", "profile:read"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let key = body["key"].as_str().unwrap().to_string();
+    let id = body["id"].as_str().unwrap().to_string();
+    assert!(key.starts_with("chr_"));
+    assert_eq!(body["name"], "CI runner");
+    assert_eq!(body["scopes"], json!(["profile:read"]));
+    assert_eq!(body["expires_at"], Value::Null);
+
+    let (status, body) = get_profile(&app, &key).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email"], user.email.as_str());
+
+    // A key cannot be used to create further keys
+    let (status, _) = create_key(&app, &key, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    assert_eq!(
+        revoke_key(&app, &access_token, &id).await,
+        StatusCode::NO_CONTENT
+    );
+    let (status, body) = get_profile(&app, &key).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_REVOKED");
+    assert_eq!(
+        revoke_key(&app, &access_token, &id).await,
+        StatusCode::NOT_FOUND
+    );
+
+    // Unknown keys are rejected like any invalid token
+    let (status, _) = get_profile(&app, "chr_not-a-real-key").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_only_the_owner_can_revoke_and_bad_input_is_rejected() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let owner = create_user(&pool).await;
+    let other = create_user(&pool).await;
+    let owner_token = login(&app, &owner.email).await;
+    let other_token = login(&app, &other.email).await;
+
+    let (status, body) = create_key(&app, &owner_token, json!({ "expires_in_days": 30 })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(body["expires_at"].is_string());
+    let id = body["id"].as_str().unwrap();
+    assert_eq!(
+        revoke_key(&app, &other_token, id).await,
+        StatusCode::NOT_FOUND
+    );
+
+    for bad in [
+        json!({ "scopes": ["profile:read", "everything"] }),
+        json!({ "scopes": [""] }),
+        json!({ "name": "  " }),
+        json!({ "expires_in_days": 0 }),
+    ] {
+        let (status, _) = create_key(&app, &owner_token, bad).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn test_authenticate_reports_scopes_and_expiry() {
+    let pool = setup_test_pool().await;
+    let service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_api_key_repository(ApiKeyRepository::new(pool.clone()));
+    let user = create_user(&pool).await;
+
+    let scopes = vec!["projects:read".to_string()];
+    let (api_key, key) = service
+        .create_api_key(user.id, Some("Reporting"), &scopes, None)
+        .await
+        .unwrap();
+    let context = service.authenticate(&key).await.unwrap();
+    assert_eq!(context.user_id, user.id);
+    assert_eq!(context.email, user.email);
+    assert_eq!(context.token_type, TokenType::ApiKey);
+    assert_eq!(context.scopes, scopes);
+    assert_eq!(context.jti, api_key.id.to_string());
+
+    // JWTs still work, and carry every scope
+    let pair = service.generate_token_pair(&user).await.unwrap();
+    let context = service.authenticate(&pair.access_token).await.unwrap();
+    assert_eq!(context.token_type, TokenType::Access);
+    assert_eq!(context.scopes, default_scopes());
+
+    let (_, expired) = service
+        .create_api_key(user.id, None, &[], Some(time::Duration::seconds(-1)))
+        .await
+        .unwrap();
+    assert!(matches!(
+        service.authenticate(&expired).await,
+        Err(JwtError::ExpiredToken)
+    ));
+}
+
+async fn update_profile(app: &axum::Router, bearer: &str) -> StatusCode {
+    let request = Request::builder()
+        .uri("/api/auth/profile")
+        .method("PUT")
+        .header("authorization", format!("Bearer {}", bearer))
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "name": "Scoped" }).to_string()))
+        .unwrap();
+    send(app, request).await.0
+}
+
+#[tokio::test]
+async fn test_scopes_limit_what_a_key_can_do() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let user = create_user(&pool).await;
+    let access_token = login(&app, &user.email).await;
+
+    let (status, body) =
+        create_key(&app, &access_token, json!({ "scopes": ["profile:read"] })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let read_only = body["key"].as_str().unwrap().to_string();
+
+    let (status, _) = get_profile(&app, &read_only).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        update_profile(&app, &read_only).await,
+        StatusCode::FORBIDDEN
+    );
+    let (status, body) = create_key(&app, &read_only, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "INSUFFICIENT_PERMISSIONS");
+
+    // A login has every scope, and so does a key created without any
+    assert_eq!(update_profile(&app, &access_token).await, StatusCode::OK);
+    let (status, body) = create_key(&app, &access_token, json!({})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(body["scopes"].as_array().unwrap().len() > 1);
+    let full = body["key"].as_str().unwrap().to_string();
+    assert_eq!(update_profile(&app, &full).await, StatusCode::OK);
+
+    let (status, _) = create_key(&app, &access_token, json!({ "scopes": ["admin"] })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_admin_key_does_not_get_admin_access() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+    let user = create_user(&pool).await;
+    let role_repository = RoleRepository::new(pool.clone());
+    let admin_role = role_repository
+        .find_by_name("admin")
+        .await
+        .unwrap()
+        .expect("admin role should be seeded");
+    role_repository
+        .grant_role(user.id, admin_role.id)
+        .await
+        .unwrap();
+    let access_token = login(&app, &user.email).await;
+
+    let stats = |bearer: String| {
+        Request::builder()
+            .uri("/api/admin/stats")
+            .header("authorization", format!("Bearer {}", bearer))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let (status, _) = send(&app, stats(access_token.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The owner's admin role does not carry over to their keys
+    let (status, body) =
+        create_key(&app, &access_token, json!({ "scopes": ["profile:read"] })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let read_only = body["key"].as_str().unwrap().to_string();
+    let (status, _) = send(&app, stats(read_only)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = create_key(&app, &access_token, json!({})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let full = body["key"].as_str().unwrap().to_string();
+    let (status, _) = send(&app, stats(full)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
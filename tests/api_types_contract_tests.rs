@@ -0,0 +1,189 @@
+use chronos::api_types::ErrorResponse;
+use chronos::api_types::auth::{
+    AuthError, AuthErrorBody, AuthErrorResponse, FieldError, LoginResponse, LogoutRequest,
+    ProfileResponse, RefreshTokenResponse, RegisterRequest, RegisterResponse, TokenPair,
+};
+use chronos::api_types::time_entries::StartTimerRequest;
+use chronos::api_types::users::UserResponse;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+// Serialize, deserialize and serialize again: both encodings must be identical
+fn assert_round_trip<T: Serialize + DeserializeOwned>(value: &T) -> Value {
+    let first = serde_json::to_value(value).expect("Should serialize");
+    let parsed: T = serde_json::from_value(first.clone()).expect("Should deserialize");
+    let second = serde_json::to_value(&parsed).expect("Should serialize again");
+    assert_eq!(first, second);
+    first
+}
+
+fn keys(value: &Value) -> BTreeSet<&str> {
+    value
+        .as_object()
+        .expect("Expected a JSON object")
+        .keys()
+        .map(String::as_str)
+        .collect()
+}
+
+fn sample_timestamp() -> OffsetDateTime {
+    OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap()
+}
+
+fn sample_user() -> UserResponse {
+    UserResponse {
+        id: Uuid::new_v4(),
+        name: Some("Contract User".to_string()),
+        email: "contract@example.com".to_string(),
+        username: None,
+        created_at: Some(sample_timestamp()),
+    }
+}
+
+#[test]
+fn test_register_contract() {
+    let request = RegisterRequest {
+        name: Some("Contract User".to_string()),
+        email: "contract@example.com".to_string(),
+        username: None,
+        password: "SecurePass1!".to_string(),
+        captcha_token: None,
+    };
+    let json = assert_round_trip(&request);
+    assert_eq!(keys(&json), BTreeSet::from(["name", "email", "password"]));
+
+    let response = RegisterResponse {
+        message: "User registered successfully".to_string(),
+        user: sample_user(),
+    };
+    let json = assert_round_trip(&response);
+    assert_eq!(keys(&json), BTreeSet::from(["message", "user"]));
+    assert_eq!(
+        keys(&json["user"]),
+        BTreeSet::from(["id", "name", "email", "created_at"])
+    );
+    assert_eq!(json["user"]["created_at"], "2023-11-14T22:13:20Z");
+}
+
+#[test]
+fn test_login_contract() {
+    let response = LoginResponse {
+        message: "Login successful".to_string(),
+        user: sample_user(),
+        tokens: TokenPair {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            token_type: "Bearer".to_string(),
+            expires_in: 900,
+            refresh_expires_in: 604800,
+            access_token_jti: None,
+        },
+        csrf_token: None,
+        password_change_required: false,
+    };
+    let json = assert_round_trip(&response);
+    assert_eq!(
+        keys(&json),
+        BTreeSet::from(["message", "user", "tokens", "password_change_required"])
+    );
+    assert_eq!(
+        keys(&json["tokens"]),
+        BTreeSet::from([
+            "access_token",
+            "refresh_token",
+            "token_type",
+            "expires_in",
+            "refresh_expires_in"
+        ])
+    );
+}
+
+#[test]
+fn test_refresh_and_logout_contract() {
+    let response = RefreshTokenResponse {
+        access_token: "access".to_string(),
+        refresh_token: None,
+        token_type: "Bearer".to_string(),
+        expires_in: 900,
+        refresh_expires_in: None,
+        access_token_jti: None,
+    };
+    let json = assert_round_trip(&response);
+    assert!(json["refresh_token"].is_null());
+
+    let request = LogoutRequest {
+        refresh_token: Some("refresh".to_string()),
+        logout_all_devices: Some(true),
+    };
+    let json = assert_round_trip(&request);
+    assert_eq!(
+        keys(&json),
+        BTreeSet::from(["refresh_token", "logout_all_devices"])
+    );
+}
+
+#[test]
+fn test_profile_contract() {
+    let response = ProfileResponse {
+        id: Uuid::new_v4(),
+        name: None,
+        email: "contract@example.com".to_string(),
+        created_at: Some(sample_timestamp()),
+        updated_at: None,
+    };
+    let json = assert_round_trip(&response);
+    assert_eq!(
+        keys(&json),
+        BTreeSet::from(["id", "name", "email", "created_at", "updated_at"])
+    );
+    assert!(json.get("password_hash").is_none());
+}
+
+#[test]
+fn test_error_contracts() {
+    let error = AuthError::with_details("Validation failed", vec!["email: Invalid".to_string()]);
+    let json = assert_round_trip(&error);
+    assert_eq!(keys(&json), BTreeSet::from(["error", "details"]));
+
+    let error = AuthErrorResponse {
+        error: AuthErrorBody {
+            code: "VALIDATION_FAILED".to_string(),
+            message: "Validation failed".to_string(),
+            details: vec![FieldError::new("email", "Invalid email format")],
+        },
+    };
+    let json = assert_round_trip(&error);
+    assert_eq!(keys(&json), BTreeSet::from(["error"]));
+    assert_eq!(
+        keys(&json["error"]),
+        BTreeSet::from(["code", "message", "details"])
+    );
+    assert_eq!(
+        keys(&json["error"]["details"][0]),
+        BTreeSet::from(["field", "message"])
+    );
+
+    let error = ErrorResponse {
+        error: "Project not found".to_string(),
+    };
+    let json = assert_round_trip(&error);
+    assert_eq!(keys(&json), BTreeSet::from(["error"]));
+}
+
+#[test]
+fn test_start_timer_contract() {
+    let request = StartTimerRequest {
+        description: Some("Writing docs".to_string()),
+        project_id: Some(Uuid::new_v4()),
+        task_id: None,
+    };
+    let json = assert_round_trip(&request);
+    assert_eq!(
+        keys(&json),
+        BTreeSet::from(["description", "project_id", "task_id"])
+    );
+}
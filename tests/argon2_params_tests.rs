@@ -0,0 +1,136 @@
+use argon2::PasswordHash;
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::auth::{AuthenticationError, RegisterRequest};
+use chronos::app::models::user::User;
+use chronos::app::repositories::password_history_repository::InMemoryPasswordHistoryStore;
+use chronos::app::repositories::password_reset_repository::InMemoryPasswordResetStore;
+use chronos::app::repositories::user_repository::InMemoryUserStore;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use std::time::{Duration, Instant};
+
+// Users and password history shared by every service built on them
+#[derive(Clone, Default)]
+struct Stores {
+    users: InMemoryUserStore,
+    password_history: InMemoryPasswordHistoryStore,
+}
+
+fn create_auth_service(stores: &Stores, params: Argon2Params) -> AuthService {
+    AuthService::new(
+        stores.users.clone(),
+        InMemoryPasswordResetStore::new(),
+        stores.password_history.clone(),
+        MockEmailService::new(),
+    )
+    .with_argon2_params(params)
+}
+
+fn low_cost() -> Argon2Params {
+    Argon2Params::new(1024, 1, 1).unwrap()
+}
+
+fn high_cost() -> Argon2Params {
+    Argon2Params::new(16 * 1024, 3, 1).unwrap()
+}
+
+// Fastest of a few runs, to keep scheduler noise out of the comparison
+fn fastest_hash(params: &Argon2Params) -> Duration {
+    (0..3)
+        .map(|_| {
+            let started = Instant::now();
+            params.hash("TimingPass123!").unwrap();
+            started.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+#[test]
+fn test_default_params_match_argon2_defaults() {
+    let params = Argon2Params::default();
+    let defaults = argon2::Params::default();
+
+    assert_eq!(params.memory_kib(), defaults.m_cost());
+    assert_eq!(params.iterations(), defaults.t_cost());
+    assert_eq!(params.parallelism(), defaults.p_cost());
+}
+
+#[test]
+fn test_invalid_params_are_rejected() {
+    assert!(Argon2Params::new(19 * 1024, 0, 1).is_err());
+    assert!(Argon2Params::new(19 * 1024, 2, 0).is_err());
+    // Argon2 needs at least 8 KiB of memory per lane
+    assert!(Argon2Params::new(8, 1, 4).is_err());
+}
+
+#[test]
+fn test_hash_records_configured_params() {
+    let hash = high_cost().hash("RecordedPass123!").unwrap();
+    let parsed = PasswordHash::new(&hash).unwrap();
+
+    assert_eq!(parsed.algorithm.as_str(), "argon2id");
+    assert_eq!(parsed.params.get_decimal("m"), Some(16 * 1024));
+    assert_eq!(parsed.params.get_decimal("t"), Some(3));
+    assert_eq!(parsed.params.get_decimal("p"), Some(1));
+}
+
+#[test]
+fn test_higher_cost_hashes_take_longer() {
+    let low = fastest_hash(&low_cost());
+    let high = fastest_hash(&high_cost());
+
+    assert!(
+        high > low * 2,
+        "expected high cost ({:?}) to be well above low cost ({:?})",
+        high,
+        low
+    );
+}
+
+#[test]
+fn test_hash_verifies_regardless_of_current_params() {
+    let user = User::new_with_params(
+        None,
+        "argon2@example.com".to_string(),
+        "CrossPass123!",
+        &low_cost(),
+    )
+    .unwrap();
+
+    // Verification reads the cost from the hash, not from any configuration
+    assert!(user.verify_password("CrossPass123!").unwrap());
+    assert!(!user.verify_password("WrongPass123!").unwrap());
+}
+
+#[tokio::test]
+async fn test_service_with_new_params_accepts_hashes_from_old_params() {
+    let stores = Stores::default();
+
+    let registered = create_auth_service(&stores, low_cost())
+        .register(RegisterRequest {
+            name: None,
+            email: "argon2@example.com".to_string(),
+            username: None,
+            password: "OriginalPass123!".to_string(),
+            captcha_token: None,
+        })
+        .await
+        .unwrap();
+
+    let auth_service = create_auth_service(&stores, high_cost());
+
+    // The password history check has to verify against the low-cost hash
+    let reused = auth_service
+        .change_password(registered.user.id, "OriginalPass123!")
+        .await;
+    assert!(matches!(reused, Err(AuthenticationError::PasswordReused)));
+
+    let user = auth_service
+        .change_password(registered.user.id, "UpdatedPass456!")
+        .await
+        .unwrap();
+    let parsed = PasswordHash::new(&user.password_hash).unwrap();
+    assert_eq!(parsed.params.get_decimal("m"), Some(16 * 1024));
+    assert!(user.verify_password("UpdatedPass456!").unwrap());
+}
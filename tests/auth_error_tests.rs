@@ -0,0 +1,185 @@
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::auth::{
+    AuthError, AuthenticationError, ForgotPasswordRequest, RegisterRequest, ResetPasswordRequest,
+};
+use chronos::app::models::password_reset::PasswordResetToken;
+use chronos::app::repositories::password_history_repository::InMemoryPasswordHistoryStore;
+use chronos::app::repositories::password_reset_repository::{
+    InMemoryPasswordResetStore, PasswordResetStore,
+};
+use chronos::app::repositories::user_repository::InMemoryUserStore;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use serde_json::Value;
+use uuid::Uuid;
+
+const PASSWORD: &str = "ErrorPass123!";
+
+fn create_auth_service() -> (AuthService, InMemoryPasswordResetStore) {
+    let reset_tokens = InMemoryPasswordResetStore::new();
+    let auth_service = AuthService::new(
+        InMemoryUserStore::new(),
+        reset_tokens.clone(),
+        InMemoryPasswordHistoryStore::new(),
+        MockEmailService::new(),
+    )
+    .with_argon2_params(Argon2Params::new(1024, 1, 1).unwrap());
+    (auth_service, reset_tokens)
+}
+
+fn register_request(email: &str, password: &str) -> RegisterRequest {
+    RegisterRequest {
+        name: None,
+        email: email.to_string(),
+        username: None,
+        password: password.to_string(),
+        captcha_token: None,
+    }
+}
+
+fn unique_email() -> String {
+    format!("errors-{}@example.com", Uuid::new_v4())
+}
+
+async fn into_parts(error: AuthenticationError) -> (StatusCode, Value) {
+    let response = error.into_response();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_invalid_registration_is_bad_request() {
+    let (auth_service, _) = create_auth_service();
+
+    let error = auth_service
+        .register(register_request(&unique_email(), "weak"))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AuthenticationError::ValidationFailed(_)));
+
+    let (status, body) = into_parts(error).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "VALIDATION_FAILED");
+    let details = body["error"]["details"].as_array().unwrap();
+    assert!(details.iter().any(|detail| {
+        detail["field"] == "password"
+            && detail["message"]
+                .as_str()
+                .unwrap()
+                .starts_with("strength score")
+    }));
+}
+
+#[tokio::test]
+async fn test_duplicate_registration_is_conflict() {
+    let (auth_service, _) = create_auth_service();
+    let email = unique_email();
+    auth_service
+        .register(register_request(&email, PASSWORD))
+        .await
+        .unwrap();
+
+    let error = auth_service
+        .register(register_request(&email, PASSWORD))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AuthenticationError::EmailAlreadyRegistered));
+
+    let (status, body) = into_parts(error).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"]["code"], "EMAIL_IN_USE");
+}
+
+#[tokio::test]
+async fn test_unknown_reset_token_is_bad_request() {
+    let (auth_service, _) = create_auth_service();
+
+    let error = auth_service
+        .reset_password(ResetPasswordRequest {
+            token: PasswordResetToken::generate_secure_token(),
+            password: PASSWORD.to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        AuthenticationError::PasswordResetTokenInvalid
+    ));
+
+    let (status, body) = into_parts(error).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn test_reusing_the_current_password_on_reset_is_bad_request() {
+    let (auth_service, reset_tokens) = create_auth_service();
+    let user = auth_service
+        .register(register_request(&unique_email(), PASSWORD))
+        .await
+        .unwrap()
+        .user;
+    let plain_token = PasswordResetToken::generate_secure_token();
+    reset_tokens
+        .create(&PasswordResetToken::new(user.id, &plain_token).unwrap())
+        .await
+        .unwrap();
+
+    let error = auth_service
+        .reset_password(ResetPasswordRequest {
+            token: plain_token,
+            password: PASSWORD.to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AuthenticationError::PasswordReused));
+
+    let (status, body) = into_parts(error).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "PASSWORD_REUSED");
+}
+
+#[tokio::test]
+async fn test_repeated_reset_requests_are_rate_limited() {
+    let (auth_service, _) = create_auth_service();
+    let email = unique_email();
+    auth_service
+        .register(register_request(&email, PASSWORD))
+        .await
+        .unwrap();
+
+    let request = ForgotPasswordRequest {
+        email: email.clone(),
+    };
+    for _ in 0..3 {
+        auth_service.forgot_password(request.clone()).await.unwrap();
+    }
+    let error = auth_service.forgot_password(request).await.unwrap_err();
+    assert!(matches!(error, AuthenticationError::TooManyAttempts(_)));
+
+    let (status, body) = into_parts(error).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["error"]["code"], "RATE_LIMITED");
+}
+
+#[tokio::test]
+async fn test_auth_error_bridge_keeps_details_only() {
+    let with_details = AuthenticationError::from(AuthError::with_details(
+        "Validation failed",
+        vec!["email: Invalid email format".to_string()],
+    ));
+    let (status, body) = into_parts(with_details).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["details"][0]["field"], "email");
+
+    // A message alone no longer picks the status
+    let message_only = AuthenticationError::from(AuthError::new("Email already registered"));
+    assert!(matches!(message_only, AuthenticationError::Internal(_)));
+    let (status, _) = into_parts(message_only).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
@@ -3,7 +3,10 @@ use axum::{
     extract::connect_info::MockConnectInfo,
     http::{Request, StatusCode},
 };
-use chronos::app::middleware::security::{SecurityHeadersLayer, get_cors_layer};
+use chronos::app::middleware::ip_filter::IpNetwork;
+use chronos::app::middleware::security::{
+    SecurityHeadersLayer, get_cors_layer, set_trusted_proxies,
+};
 use chronos::routes;
 use dotenvy::dotenv;
 use serde_json::{Value, json};
@@ -27,6 +30,8 @@ async fn setup_test_pool() -> PgPool {
 }
 
 async fn create_test_app() -> axum::Router {
+    // The mock peer is the proxy, so X-Forwarded-For picks the client
+    set_trusted_proxies(vec![IpNetwork::parse("192.168.1.1").unwrap()]);
     let pool = setup_test_pool().await;
     let app = routes::create_router(pool);
 
@@ -36,7 +41,7 @@ async fn create_test_app() -> axum::Router {
             .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
             .layer(PropagateRequestIdLayer::x_request_id())
             .layer(TraceLayer::new_for_http())
-            .layer(SecurityHeadersLayer)
+            .layer(SecurityHeadersLayer::default())
             .layer(get_cors_layer())
             .layer(MockConnectInfo(
                 "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
@@ -491,3 +496,33 @@ async fn test_empty_refresh_token() {
     // Should return 401 Unauthorized for empty token
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 }
+
+#[tokio::test]
+async fn test_cors_exposes_custom_headers() {
+    let app = create_test_app().await;
+
+    let request = Request::builder()
+        .uri("/api/auth/profile")
+        .method("GET")
+        .header("origin", "http://localhost:3000")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+
+    let exposed = response
+        .headers()
+        .get("access-control-expose-headers")
+        .expect("CORS responses should list exposed headers")
+        .to_str()
+        .unwrap()
+        .to_string();
+    for header in ["retry-after", "x-request-id", "x-ratelimit-remaining"] {
+        assert!(
+            exposed.contains(header),
+            "{} should be exposed, got {}",
+            header,
+            exposed
+        );
+    }
+}
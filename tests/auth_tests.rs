@@ -45,7 +45,9 @@ mod auth_tests {
         let valid_request = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "john.doe@example.com".to_string(),
+            username: None,
             password: "SecurePass1!".to_string(),
+            captcha_token: None,
         };
 
         assert!(valid_request.validate().is_ok());
@@ -56,7 +58,9 @@ mod auth_tests {
         let invalid_email_request = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "invalid-email".to_string(),
+            username: None,
             password: "SecurePass1!".to_string(),
+            captcha_token: None,
         };
 
         assert!(invalid_email_request.validate().is_err());
@@ -67,7 +71,9 @@ mod auth_tests {
         let invalid_password_request = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "john.doe@example.com".to_string(),
+            username: None,
             password: "weak".to_string(),
+            captcha_token: None,
         };
 
         assert!(invalid_password_request.validate().is_err());
@@ -135,7 +141,9 @@ mod auth_tests {
             id: uuid::Uuid::new_v4(),
             name: Some("John Doe".to_string()),
             email: "invalid-email".to_string(),
+            username: None,
             password_hash: "hash".to_string(),
+            password_changed_at: None,
             created_at: Some(time::OffsetDateTime::now_utc()),
             updated_at: Some(time::OffsetDateTime::now_utc()),
         };
@@ -146,7 +154,9 @@ mod auth_tests {
             id: uuid::Uuid::new_v4(),
             name: Some("John Doe".to_string()),
             email: "john.doe@example.com".to_string(),
+            username: None,
             password_hash: "hash".to_string(),
+            password_changed_at: None,
             created_at: Some(time::OffsetDateTime::now_utc()),
             updated_at: Some(time::OffsetDateTime::now_utc()),
         };
@@ -0,0 +1,84 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode, header},
+};
+use chronos::app::middleware::security::DEFAULT_AUTH_BODY_LIMIT;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::json;
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: PgPool, ip: &str) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+// A registration whose name alone is bigger than the limit
+fn oversized_registration() -> String {
+    json!({
+        "name": "x".repeat(DEFAULT_AUTH_BODY_LIMIT + 1),
+        "email": "not-an-email",
+        "password": "short",
+    })
+    .to_string()
+}
+
+#[tokio::test]
+async fn test_oversized_register_body_is_rejected() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool, "192.168.11.1");
+    let body = oversized_registration();
+
+    // Announced by Content-Length, so it is refused before the body is read
+    let request = Request::builder()
+        .uri("/api/auth/register")
+        .method("POST")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_LENGTH, body.len())
+        .body(Body::from(body.clone()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Without Content-Length reading stops at the limit
+    let request = Request::builder()
+        .uri("/api/auth/register")
+        .method("POST")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_body_within_limit_is_parsed() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool, "192.168.11.2");
+
+    let request = Request::builder()
+        .uri("/api/auth/register")
+        .method("POST")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            json!({ "email": "not-an-email", "password": "short" }).to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    // Reaches validation instead of being cut off
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,225 @@
+use async_trait::async_trait;
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::captcha_service::{CaptchaError, CaptchaService, CaptchaVerifier};
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "CaptchaPass123!";
+const GOOD_TOKEN: &str = "solved-captcha";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Accepts only GOOD_TOKEN and counts how often it was asked
+#[derive(Clone, Default)]
+struct MockCaptchaVerifier {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl CaptchaVerifier for MockCaptchaVerifier {
+    async fn verify(&self, token: &str, _remote_ip: &str) -> Result<bool, CaptchaError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(token == GOOD_TOKEN)
+    }
+}
+
+// The auth routes behind a fresh client address, so failures from other tests don't count
+fn create_test_app(pool: &PgPool, captcha: Option<CaptchaService>) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+
+    let mut state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+    if let Some(captcha) = captcha {
+        state = state.with_captcha_service(captcha);
+    }
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let address = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(address.parse::<SocketAddr>().unwrap()))
+}
+
+async fn create_user(pool: &PgPool) -> String {
+    let email = format!("captcha-{}@example.com", Uuid::new_v4());
+    let user = User::new(None, email.clone(), PASSWORD).unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap();
+    email
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn login(
+    app: &Router,
+    email: &str,
+    password: &str,
+    captcha_token: Option<&str>,
+) -> (StatusCode, Value) {
+    let mut body = json!({ "email": email, "password": password });
+    if let Some(token) = captcha_token {
+        body["captcha_token"] = json!(token);
+    }
+    post(app, "/api/auth/login", body).await
+}
+
+#[tokio::test]
+async fn test_login_needs_captcha_only_after_failures() {
+    let pool = setup_test_pool().await;
+    let verifier = MockCaptchaVerifier::default();
+    let app = create_test_app(
+        &pool,
+        Some(CaptchaService::new(Arc::new(verifier.clone()), 2)),
+    );
+    let email = create_user(&pool).await;
+
+    // Below the threshold nobody is asked for a CAPTCHA
+    for _ in 0..2 {
+        let (status, _) = login(&app, &email, "WrongPass123!", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(verifier.calls.load(Ordering::SeqCst), 0);
+
+    let (status, body) = login(&app, &email, PASSWORD, None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["message"], "CAPTCHA verification required");
+
+    let (status, body) = login(&app, &email, PASSWORD, Some("wrong-answer")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["message"], "CAPTCHA verification failed");
+
+    let (status, _) = login(&app, &email, PASSWORD, Some(GOOD_TOKEN)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verifier.calls.load(Ordering::SeqCst), 2);
+
+    // A successful login clears the failures for that address
+    let (status, _) = login(&app, &email, PASSWORD, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_registration_checks_captcha() {
+    let pool = setup_test_pool().await;
+    // A threshold of zero asks every client
+    let app = create_test_app(
+        &pool,
+        Some(CaptchaService::new(
+            Arc::new(MockCaptchaVerifier::default()),
+            0,
+        )),
+    );
+    let email = format!("captcha-{}@example.com", Uuid::new_v4());
+
+    let (status, body) = post(
+        &app,
+        "/api/auth/register",
+        json!({ "email": email, "password": PASSWORD }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["message"], "CAPTCHA verification required");
+
+    let (status, body) = post(
+        &app,
+        "/api/auth/register",
+        json!({ "email": email, "password": PASSWORD, "captcha_token": "wrong-answer" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["message"], "CAPTCHA verification failed");
+
+    let (status, _) = post(
+        &app,
+        "/api/auth/register",
+        json!({ "email": email, "password": PASSWORD, "captcha_token": GOOD_TOKEN }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_captcha_disabled_never_blocks() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, None);
+    let email = create_user(&pool).await;
+
+    for _ in 0..3 {
+        let (status, _) = login(&app, &email, "WrongPass123!", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let (status, _) = login(&app, &email, PASSWORD, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
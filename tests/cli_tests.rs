@@ -0,0 +1,90 @@
+#![cfg(feature = "server")]
+
+use chronos::app::models::jwt::{BlacklistedToken, TokenType};
+use chronos::app::models::role::ADMIN_ROLE;
+use chronos::app::models::user::User;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::cli::{self, CliError};
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn unique_email() -> String {
+    format!("cli-{}@example.com", Uuid::new_v4().simple())
+}
+
+#[tokio::test]
+async fn test_cleanup_tokens_purges_expired_rows() {
+    let pool = setup_test_pool().await;
+    let user = User::new(None, unique_email(), "CliPass123!").unwrap();
+    let user_id = UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+        .id;
+    let jti = Uuid::new_v4().to_string();
+    let repository = TokenBlacklistRepository::new(pool.clone());
+    repository
+        .blacklist_token(&BlacklistedToken::new(
+            jti.clone(),
+            user_id,
+            TokenType::Access,
+            OffsetDateTime::now_utc() - time::Duration::hours(1),
+        ))
+        .await
+        .unwrap();
+
+    let report = cli::cleanup_tokens(pool.clone()).await.unwrap();
+
+    assert!(report.blacklisted_tokens >= 1);
+    assert!(repository.get_by_jti(&jti).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_create_admin_grants_admin_role() {
+    let pool = setup_test_pool().await;
+    let email = unique_email();
+
+    let user = cli::create_admin(pool.clone(), email.clone(), "CliAdmin123!".to_string())
+        .await
+        .unwrap();
+    assert_eq!(user.email, email);
+
+    let roles = RoleRepository::new(pool.clone())
+        .get_user_roles(user.id)
+        .await
+        .unwrap();
+    assert!(roles.iter().any(|role| role == ADMIN_ROLE));
+    let verified: Option<bool> = sqlx::query_scalar("SELECT is_verified FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(verified, Some(true));
+
+    // Registration rules still apply
+    let result = cli::create_admin(pool, unique_email(), "short".to_string()).await;
+    assert!(matches!(result, Err(CliError::Registration(_))));
+}
+
+#[tokio::test]
+async fn test_revoke_unknown_user_is_an_error() {
+    let pool = setup_test_pool().await;
+    let user_id = Uuid::new_v4();
+
+    let result = cli::revoke_user(pool, user_id).await;
+    assert!(matches!(result, Err(CliError::UserNotFound(id)) if id == user_id));
+}
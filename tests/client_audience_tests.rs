@@ -0,0 +1,203 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::auth::AuthenticationError;
+use chronos::app::models::jwt::{JwtError, LoginRequest, LoginResponse};
+use chronos::app::models::user::User;
+use chronos::app::repositories::client_repository::ClientRepository;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use uuid::Uuid;
+
+const PASSWORD: &str = "AudiencePass123!";
+const SECRET: &str = "test-secret-key";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// A JwtService accepting only tokens for `audience`, or any token with None
+fn create_jwt_service(pool: &PgPool, audience: Option<&str>) -> JwtService {
+    JwtService::new(
+        SECRET,
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_audience(audience.map(str::to_string))
+}
+
+fn create_login_service(pool: &PgPool) -> SecureLoginService {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+
+    SecureLoginService::new(
+        auth_service,
+        create_jwt_service(pool, None),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+    .with_client_repository(ClientRepository::new(pool.clone()))
+}
+
+// Unique per run, since client ids are the table's key
+async fn register_client(pool: &PgPool, name: &str, audiences: &[String]) -> String {
+    let client_id = format!("{}-{}", name, &Uuid::new_v4().simple().to_string()[..8]);
+    ClientRepository::new(pool.clone())
+        .create(
+            &client_id,
+            name,
+            &["https://app.example.com/callback".to_string()],
+            audiences,
+        )
+        .await
+        .unwrap();
+    client_id
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("audience-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn login(
+    service: &SecureLoginService,
+    user: &User,
+    client_id: Option<&str>,
+) -> Result<LoginResponse, AuthenticationError> {
+    let request = LoginRequest {
+        email: user.email.clone(),
+        identifier: None,
+        password: PASSWORD.to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: client_id.map(str::to_string),
+    };
+    let bytes = Uuid::new_v4().into_bytes();
+    let ip = format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2]);
+    service.secure_login(request, ip, None).await
+}
+
+#[tokio::test]
+async fn test_token_for_one_client_is_rejected_by_another() {
+    let pool = setup_test_pool().await;
+    let client_a = register_client(&pool, "client-a", &[]).await;
+    let client_b = register_client(&pool, "client-b", &[]).await;
+    let user = create_user(&pool).await;
+
+    let tokens = login(&create_login_service(&pool), &user, Some(&client_a))
+        .await
+        .unwrap()
+        .tokens;
+
+    let claims = create_jwt_service(&pool, Some(&client_a))
+        .validate_token(&tokens.access_token)
+        .await
+        .unwrap();
+    assert_eq!(claims.aud, vec![client_a.clone()]);
+
+    let result = create_jwt_service(&pool, Some(&client_b))
+        .validate_token(&tokens.access_token)
+        .await;
+    assert!(matches!(result, Err(JwtError::InvalidToken(_))));
+
+    // Without a required audience, tokens for any client are accepted
+    assert!(
+        create_jwt_service(&pool, None)
+            .validate_token(&tokens.access_token)
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_tokens_without_audience_fail_when_one_is_required() {
+    let pool = setup_test_pool().await;
+    let client_a = register_client(&pool, "client-a", &[]).await;
+    let user = create_user(&pool).await;
+
+    let tokens = login(&create_login_service(&pool), &user, None)
+        .await
+        .unwrap()
+        .tokens;
+
+    let result = create_jwt_service(&pool, Some(&client_a))
+        .validate_token(&tokens.access_token)
+        .await;
+    assert!(matches!(result, Err(JwtError::InvalidToken(_))));
+}
+
+#[tokio::test]
+async fn test_extra_audiences_and_refresh_keep_the_audience() {
+    let pool = setup_test_pool().await;
+    let api = format!("api-{}", Uuid::new_v4().simple());
+    let client_a = register_client(&pool, "client-a", std::slice::from_ref(&api)).await;
+    let user = create_user(&pool).await;
+
+    let tokens = login(&create_login_service(&pool), &user, Some(&client_a))
+        .await
+        .unwrap()
+        .tokens;
+
+    // The resource server named in the client's audiences accepts its tokens too
+    let api_service = create_jwt_service(&pool, Some(&api));
+    assert!(
+        api_service
+            .validate_token(&tokens.access_token)
+            .await
+            .is_ok()
+    );
+
+    let rotated = create_jwt_service(&pool, None)
+        .refresh_with_rotation(&tokens.refresh_token, user.id)
+        .await
+        .unwrap();
+    let claims = api_service
+        .validate_token(&rotated.access_token)
+        .await
+        .unwrap();
+    assert_eq!(claims.aud, vec![client_a, api]);
+}
+
+#[tokio::test]
+async fn test_unknown_client_id_is_rejected() {
+    let pool = setup_test_pool().await;
+    let user = create_user(&pool).await;
+
+    let result = login(&create_login_service(&pool), &user, Some("no-such-client")).await;
+    match result {
+        Err(AuthenticationError::InvalidInput { field, .. }) => assert_eq!(field, "client_id"),
+        other => panic!(
+            "expected an unknown client error, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+}
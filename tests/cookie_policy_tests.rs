@@ -0,0 +1,174 @@
+use chronos::app::cookies::{AppEnvironment, CookiePolicy, TokenDelivery};
+use std::time::Duration;
+
+#[test]
+fn test_production_cookies_are_secure() {
+    let policy = CookiePolicy::for_environment(AppEnvironment::Production);
+    let cookie = policy
+        .build("refresh_token", "abc", Some(Duration::from_secs(60)))
+        .unwrap();
+    let cookie = cookie.to_str().unwrap();
+
+    assert!(policy.is_secure());
+    assert!(cookie.contains("; Secure"));
+    assert!(cookie.contains("; HttpOnly"));
+    assert!(cookie.contains("; SameSite=Strict"));
+    assert!(cookie.contains("; Max-Age=60"));
+}
+
+#[test]
+fn test_development_cookies_are_not_secure() {
+    let policy = CookiePolicy::for_environment(AppEnvironment::Development);
+    let cookie = policy.build("refresh_token", "abc", None).unwrap();
+    let cookie = cookie.to_str().unwrap();
+
+    assert!(!policy.is_secure());
+    assert!(!cookie.contains("Secure"));
+    // HttpOnly and SameSite are never relaxed
+    assert!(cookie.contains("; HttpOnly"));
+    assert!(cookie.contains("; SameSite=Strict"));
+    assert!(!cookie.contains("Max-Age"));
+}
+
+#[test]
+fn test_cleared_cookie_expires_immediately() {
+    let cookie = CookiePolicy::default().clear("refresh_token").unwrap();
+    let cookie = cookie.to_str().unwrap();
+
+    assert!(cookie.starts_with("refresh_token=;"));
+    assert!(cookie.contains("; Max-Age=0"));
+    assert!(cookie.contains("; Secure"));
+}
+
+#[test]
+fn test_environment_parsing_defaults_to_production() {
+    assert_eq!(
+        AppEnvironment::parse("development"),
+        AppEnvironment::Development
+    );
+    assert_eq!(AppEnvironment::parse(" Dev "), AppEnvironment::Development);
+    assert_eq!(AppEnvironment::parse("test"), AppEnvironment::Development);
+    assert_eq!(
+        AppEnvironment::parse("production"),
+        AppEnvironment::Production
+    );
+    assert_eq!(AppEnvironment::parse("staging"), AppEnvironment::Production);
+    assert_eq!(AppEnvironment::parse(""), AppEnvironment::Production);
+}
+
+// RFC 6265 path-match: would a browser send a cookie with `cookie_path` to `request_path`?
+fn path_matches(cookie_path: &str, request_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+fn cookie_path(cookie: &str) -> &str {
+    cookie
+        .split("; ")
+        .find_map(|attribute| attribute.strip_prefix("Path="))
+        .unwrap()
+}
+
+#[test]
+fn test_configured_cookie_names_use_prefix() {
+    let policy = CookiePolicy::default().with_name_prefix("chronos_");
+
+    assert_eq!(policy.access_cookie_name(), "chronos_access_token");
+    assert_eq!(policy.refresh_cookie_name(), "chronos_refresh_token");
+    assert_eq!(
+        policy.trusted_device_cookie_name(),
+        "chronos_trusted_device"
+    );
+
+    let access = policy.access_cookie("abc", None).unwrap();
+    let refresh = policy.refresh_cookie("def", None).unwrap();
+    assert!(
+        access
+            .to_str()
+            .unwrap()
+            .starts_with("chronos_access_token=abc;")
+    );
+    assert!(
+        refresh
+            .to_str()
+            .unwrap()
+            .starts_with("chronos_refresh_token=def;")
+    );
+}
+
+#[test]
+fn test_default_cookie_names_and_paths() {
+    let policy = CookiePolicy::default();
+
+    assert_eq!(policy.access_cookie_name(), "access_token");
+    assert_eq!(policy.refresh_cookie_name(), "refresh_token");
+    assert_eq!(policy.trusted_device_cookie_name(), "trusted_device");
+    assert_eq!(policy.path(), "/");
+    assert_eq!(policy.refresh_path(), "/api/auth/refresh");
+    assert_eq!(policy.domain(), None);
+}
+
+#[test]
+fn test_refresh_cookie_is_only_sent_to_refresh_endpoint() {
+    let policy = CookiePolicy::default();
+    let access = policy.access_cookie("abc", None).unwrap();
+    let refresh = policy.refresh_cookie("def", None).unwrap();
+    let access_path = cookie_path(access.to_str().unwrap());
+    let refresh_path = cookie_path(refresh.to_str().unwrap());
+
+    assert!(path_matches(refresh_path, "/api/auth/refresh"));
+    assert!(!path_matches(refresh_path, "/api/auth/login"));
+    assert!(!path_matches(refresh_path, "/api/auth/refresh-all"));
+    assert!(!path_matches(refresh_path, "/api/users"));
+
+    assert!(path_matches(access_path, "/api/users"));
+    assert!(path_matches(access_path, "/api/auth/profile"));
+}
+
+#[test]
+fn test_configured_path_and_domain_are_applied() {
+    let policy = CookiePolicy::default()
+        .with_path("/chronos")
+        .with_refresh_path("/chronos/api/auth/refresh")
+        .with_domain("example.com");
+
+    let access = policy.access_cookie("abc", None).unwrap();
+    let access = access.to_str().unwrap();
+    assert!(access.contains("; Path=/chronos;"));
+    assert!(access.contains("; Domain=example.com"));
+
+    // Clearing has to target the same path and domain or the browser keeps the cookie
+    let cleared = policy.clear_refresh_cookie().unwrap();
+    let cleared = cleared.to_str().unwrap();
+    assert!(cleared.starts_with("refresh_token=;"));
+    assert!(cleared.contains("; Path=/chronos/api/auth/refresh;"));
+    assert!(cleared.contains("; Domain=example.com"));
+    assert!(cleared.contains("; Max-Age=0"));
+
+    let cleared = policy.clear_access_cookie().unwrap();
+    assert!(cleared.to_str().unwrap().contains("; Path=/chronos;"));
+}
+
+#[test]
+fn test_cookie_token_delivery_implies_cookie_sessions() {
+    assert_eq!(
+        TokenDelivery::parse(" Cookie "),
+        Some(TokenDelivery::Cookie)
+    );
+    assert_eq!(TokenDelivery::parse("both"), Some(TokenDelivery::Both));
+    assert_eq!(TokenDelivery::parse("body"), Some(TokenDelivery::Body));
+    assert_eq!(TokenDelivery::parse("header"), None);
+
+    let policy = CookiePolicy::default();
+    assert_eq!(policy.token_delivery(), TokenDelivery::Body);
+    assert!(!policy.sessions_enabled());
+
+    for delivery in [TokenDelivery::Cookie, TokenDelivery::Both] {
+        assert!(
+            CookiePolicy::default()
+                .with_token_delivery(delivery)
+                .sessions_enabled()
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode, header},
+    middleware,
+    response::Response,
+};
+use chronos::app::cookies::{AppEnvironment, CookiePolicy};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::security::{CSRF_HEADER, SecurityState, csrf_protection};
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "CsrfPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: &PgPool, cookie_sessions: bool) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let cookie_policy =
+        CookiePolicy::for_environment(AppEnvironment::Development).with_sessions(cookie_sessions);
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    )
+    .with_cookie_policy(cookie_policy.clone());
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(middleware::from_fn_with_state(
+            cookie_policy,
+            csrf_protection,
+        ))
+        .layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("csrf-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// `name=value` pairs from the response's Set-Cookie headers
+fn set_cookies(response: &Response) -> Vec<String> {
+    response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .map(|value| {
+            let cookie = value.to_str().unwrap();
+            cookie.split(';').next().unwrap().to_string()
+        })
+        .collect()
+}
+
+async fn json_body(response: Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap_or(Value::Null)
+}
+
+// Logs in and returns the cookies the browser would keep plus the CSRF token
+async fn login(app: &Router, email: &str) -> (String, String) {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let cookies = set_cookies(&response).join("; ");
+    let body = json_body(response).await;
+    assert!(body["tokens"]["access_token"].is_string());
+    // The refresh token only travels in its HttpOnly cookie
+    assert!(body["tokens"].get("refresh_token").is_none());
+    (cookies, body["csrf_token"].as_str().unwrap().to_string())
+}
+
+fn refresh_request(cookies: &str, csrf_token: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .uri("/api/auth/refresh")
+        .method("POST")
+        .header(header::COOKIE, cookies);
+    if let Some(csrf_token) = csrf_token {
+        builder = builder.header(CSRF_HEADER, csrf_token);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_login_sets_session_cookies() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, true);
+    let user = create_user(&pool).await;
+
+    let (cookies, csrf_token) = login(&app, &user.email).await;
+
+    assert!(cookies.contains("refresh_token="));
+    assert!(cookies.contains(&format!("csrf_token={}", csrf_token)));
+}
+
+#[tokio::test]
+async fn test_refresh_without_csrf_header_is_rejected() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, true);
+    let user = create_user(&pool).await;
+    let (cookies, csrf_token) = login(&app, &user.email).await;
+
+    let response = app
+        .clone()
+        .oneshot(refresh_request(&cookies, None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        json_body(response).await["error"]["code"],
+        "CSRF_TOKEN_INVALID"
+    );
+
+    let response = app
+        .clone()
+        .oneshot(refresh_request(&cookies, Some("not-the-token")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(refresh_request(&cookies, Some(&csrf_token)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    // The rotated refresh token replaces the cookie instead of appearing in the body
+    assert!(
+        set_cookies(&response)
+            .iter()
+            .any(|cookie| cookie.starts_with("refresh_token="))
+    );
+    assert!(json_body(response).await["refresh_token"].is_null());
+}
+
+#[tokio::test]
+async fn test_csrf_endpoint_issues_fresh_token() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, true);
+    let user = create_user(&pool).await;
+    let (cookies, old_token) = login(&app, &user.email).await;
+
+    let request = Request::builder()
+        .uri("/api/auth/csrf")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let csrf_cookie = set_cookies(&response).join("; ");
+    let csrf_token = json_body(response).await["csrf_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_ne!(csrf_token, old_token);
+    assert_eq!(csrf_cookie, format!("csrf_token={}", csrf_token));
+
+    // The browser now holds the new CSRF cookie next to the refresh cookie
+    let refresh_cookie = cookies
+        .split("; ")
+        .find(|cookie| cookie.starts_with("refresh_token="))
+        .unwrap();
+    let cookies = format!("{}; {}", refresh_cookie, csrf_cookie);
+    let response = app
+        .clone()
+        .oneshot(refresh_request(&cookies, Some(&old_token)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .oneshot(refresh_request(&cookies, Some(&csrf_token)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_csrf_endpoint_requires_cookie_sessions() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, false);
+
+    let request = Request::builder()
+        .uri("/api/auth/csrf")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_unsafe_request_with_only_the_access_cookie_needs_csrf_token() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, true);
+
+    // After a browser restart the session-only CSRF cookie is gone, while the
+    // access cookie with its Max-Age is still sent
+    let access_cookie =
+        CookiePolicy::for_environment(AppEnvironment::Development).access_cookie_name();
+    let request = Request::builder()
+        .uri("/api/auth/password/strength")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header(
+            header::COOKIE,
+            format!("{}=some-access-token", access_cookie),
+        )
+        .body(Body::from(json!({ "password": PASSWORD }).to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        json_body(response).await["error"]["code"],
+        "CSRF_TOKEN_INVALID"
+    );
+}
@@ -0,0 +1,332 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware,
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::auth_middleware::jwt_auth_middleware_with_json_errors;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::auth::AuthenticationError;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "ChangePass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// The auth routes, the service behind them and the mailbox they send to
+fn create_test_app(pool: &PgPool) -> (Router, AuthService, MockEmailService) {
+    let user_repository = UserRepository::new(pool.clone());
+    let email_service = MockEmailService::new();
+
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        email_service.clone(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+
+    let state = AuthAppState::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+    let app = Router::new()
+        .nest("/api/auth", auth::routes().with_state(state.clone()))
+        .nest(
+            "/api/auth",
+            auth::protected_routes()
+                .with_state(state)
+                .layer(middleware::from_fn_with_state(
+                    Arc::new(jwt_service),
+                    jwt_auth_middleware_with_json_errors,
+                )),
+        )
+        .layer(MockConnectInfo(
+            "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
+        ));
+
+    (app, auth_service, email_service)
+}
+
+fn unique_email(prefix: &str) -> String {
+    format!("{}-{}@example.com", prefix, Uuid::new_v4().simple())
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        unique_email("email-change"),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// The token is the only 64 character alphanumeric line of the confirmation email
+fn confirmation_token(email_service: &MockEmailService, to: &str) -> String {
+    let message = email_service
+        .get_sent_emails()
+        .into_iter()
+        .find(|message| message.to == to)
+        .expect("no confirmation email sent");
+    message
+        .body
+        .lines()
+        .map(str::trim)
+        .find(|line| line.len() == 64 && line.chars().all(|c| c.is_ascii_alphanumeric()))
+        .expect("no token in confirmation email")
+        .to_string()
+}
+
+async fn confirm(app: &Router, token: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(format!("/api/auth/confirm-email-change?token={}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn login(app: &Router, user: &User) -> String {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": user.email, "password": PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+async fn change_email(
+    app: &Router,
+    access_token: &str,
+    new_email: &str,
+    current_password: &str,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/change-email")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::from(
+            json!({ "new_email": new_email, "current_password": current_password }).to_string(),
+        ))
+        .unwrap();
+    send(app, request).await
+}
+
+async fn current_email(pool: &PgPool, user: &User) -> String {
+    UserRepository::new(pool.clone())
+        .find_by_id(user.id)
+        .await
+        .unwrap()
+        .unwrap()
+        .email
+}
+
+#[tokio::test]
+async fn test_email_switches_only_after_confirmation() {
+    let pool = setup_test_pool().await;
+    let (app, auth_service, email_service) = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    let new_email = unique_email("email-change-new");
+
+    auth_service
+        .request_email_change(&user, &new_email)
+        .await
+        .unwrap();
+
+    // Nothing changes until the new address confirms, but the old one is told
+    assert_eq!(current_email(&pool, &user).await, user.email);
+    let notice = email_service
+        .get_sent_emails()
+        .into_iter()
+        .find(|message| message.to == user.email)
+        .expect("no notice sent to the current address");
+    assert!(notice.body.contains(&new_email));
+
+    let token = confirmation_token(&email_service, &new_email);
+    let (status, body) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email"], new_email.as_str());
+    assert_eq!(current_email(&pool, &user).await, new_email);
+
+    // The token is single use
+    let (status, _) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_expired_confirmation_leaves_email_unchanged() {
+    let pool = setup_test_pool().await;
+    let (app, auth_service, email_service) = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    let new_email = unique_email("email-change-expired");
+
+    auth_service
+        .request_email_change(&user, &new_email)
+        .await
+        .unwrap();
+    let token = confirmation_token(&email_service, &new_email);
+
+    sqlx::query(
+        "UPDATE users SET email_change_expires_at = NOW() - INTERVAL '1 minute' WHERE id = $1",
+    )
+    .bind(user.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (status, body) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+    assert_eq!(current_email(&pool, &user).await, user.email);
+}
+
+#[tokio::test]
+async fn test_email_taken_before_confirmation_is_rejected() {
+    let pool = setup_test_pool().await;
+    let (app, auth_service, email_service) = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    let other = create_user(&pool).await;
+
+    assert!(matches!(
+        auth_service.request_email_change(&user, &other.email).await,
+        Err(AuthenticationError::EmailInUse)
+    ));
+
+    let new_email = unique_email("email-change-race");
+    auth_service
+        .request_email_change(&user, &new_email)
+        .await
+        .unwrap();
+    let token = confirmation_token(&email_service, &new_email);
+
+    // Someone registers the address while the confirmation is in flight
+    let squatter = User::new_with_params(
+        None,
+        new_email.clone(),
+        "ChangePass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&squatter)
+        .await
+        .unwrap();
+
+    let (status, _) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(current_email(&pool, &user).await, user.email);
+}
+
+#[tokio::test]
+async fn test_change_email_with_wrong_password_is_unauthorized() {
+    let pool = setup_test_pool().await;
+    let (app, _, email_service) = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    let access_token = login(&app, &user).await;
+    let new_email = unique_email("email-change-wrong-password");
+
+    let (status, body) = change_email(&app, &access_token, &new_email, "WrongPass123!").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INCORRECT_PASSWORD");
+    assert!(
+        email_service
+            .get_sent_emails()
+            .iter()
+            .all(|message| message.to != new_email)
+    );
+}
+
+#[tokio::test]
+async fn test_change_email_to_taken_address_is_conflict() {
+    let pool = setup_test_pool().await;
+    let (app, _, _) = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    let other = create_user(&pool).await;
+    let access_token = login(&app, &user).await;
+
+    let (status, body) = change_email(&app, &access_token, &other.email, PASSWORD).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"]["code"], "EMAIL_IN_USE");
+    assert_eq!(current_email(&pool, &user).await, user.email);
+}
+
+#[tokio::test]
+async fn test_change_email_starts_confirmation() {
+    let pool = setup_test_pool().await;
+    let (app, _, email_service) = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    let access_token = login(&app, &user).await;
+    let new_email = unique_email("email-change-route");
+
+    let (status, body) = change_email(&app, &access_token, &new_email, PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["pending_email"], new_email.as_str());
+    assert_eq!(current_email(&pool, &user).await, user.email);
+
+    let token = confirmation_token(&email_service, &new_email);
+    let (status, _) = confirm(&app, &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(current_email(&pool, &user).await, new_email);
+}
@@ -0,0 +1,261 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::rate_limiter::RateLimitConfig;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::otp::EMAIL_CODE_MESSAGE;
+use chronos::app::models::user::User;
+use chronos::app::repositories::email_login_code_repository::EmailLoginCodeRepository;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_code_service::EmailCodeService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const MAX_ATTEMPTS: i32 = 3;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Wired like create_router does for EMAIL_LOGIN_CODES=true; each app gets its own client IP
+fn create_test_app(pool: &PgPool, email_service: MockEmailService) -> Router {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        email_service,
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let email_code_service = EmailCodeService::new(EmailLoginCodeRepository::new(pool.clone()))
+        .with_argon2_params(Argon2Params::new(1024, 1, 1).unwrap())
+        .with_max_attempts(MAX_ATTEMPTS);
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+    .with_email_code_service(email_code_service);
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(UserRepository::new(pool.clone())),
+        SecurityState::new(RateLimitConfig::default()),
+    );
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("email-code-{}@example.com", Uuid::new_v4().simple()),
+        "EmailCodePass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn post_json(uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+async fn request_code(app: &Router, email: &str) -> (StatusCode, Value) {
+    send(
+        app,
+        post_json("/api/auth/email-otp/request", json!({ "email": email })),
+    )
+    .await
+}
+
+async fn verify_code(app: &Router, email: &str, code: &str) -> (StatusCode, Value) {
+    send(
+        app,
+        post_json(
+            "/api/auth/email-otp/verify",
+            json!({ "email": email, "code": code }),
+        ),
+    )
+    .await
+}
+
+// The code is on a line of its own in the email
+fn sent_code(email_service: &MockEmailService, to: &str) -> String {
+    let email = email_service.get_last_sent_email().unwrap();
+    assert_eq!(email.to, to);
+    email
+        .body
+        .lines()
+        .map(str::trim)
+        .find(|line| line.len() == 6 && line.chars().all(|c| c.is_ascii_digit()))
+        .unwrap()
+        .to_string()
+}
+
+// Differs from `code`, so it is always wrong
+fn wrong_code(code: &str) -> String {
+    code.chars()
+        .map(|c| char::from_digit((c.to_digit(10).unwrap() + 1) % 10, 10).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_request_then_verify_signs_in() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new();
+    let app = create_test_app(&pool, email_service.clone());
+    let user = create_user(&pool).await;
+
+    let (status, body) = request_code(&app, &user.email).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["message"], EMAIL_CODE_MESSAGE);
+    assert_eq!(body["expires_in"], 600);
+    let code = sent_code(&email_service, &user.email);
+
+    let (status, body) = verify_code(&app, &user.email, &code).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user"]["id"], user.id.to_string());
+    assert!(body["tokens"]["access_token"].is_string());
+    assert!(body["tokens"]["refresh_token"].is_string());
+
+    // Each code signs in once
+    let (status, body) = verify_code(&app, &user.email, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_CODE");
+}
+
+#[tokio::test]
+async fn test_unknown_email_gets_the_same_answer() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new();
+    let app = create_test_app(&pool, email_service.clone());
+    let email = format!("nobody-{}@example.com", Uuid::new_v4().simple());
+
+    let (status, body) = request_code(&app, &email).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["message"], EMAIL_CODE_MESSAGE);
+    assert!(email_service.get_sent_emails().is_empty());
+
+    // Indistinguishable from a wrong code for a registered email
+    let (status, body) = verify_code(&app, &email, "123456").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_CODE");
+}
+
+#[tokio::test]
+async fn test_wrong_codes_lock_the_code() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new();
+    let app = create_test_app(&pool, email_service.clone());
+    let user = create_user(&pool).await;
+
+    request_code(&app, &user.email).await;
+    let code = sent_code(&email_service, &user.email);
+
+    for _ in 0..MAX_ATTEMPTS {
+        let (status, body) = verify_code(&app, &user.email, &wrong_code(&code)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "INVALID_CODE");
+    }
+
+    // Out of attempts, so even the right code is refused
+    let (status, _) = verify_code(&app, &user.email, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A new code works again
+    request_code(&app, &user.email).await;
+    let code = sent_code(&email_service, &user.email);
+    let (status, _) = verify_code(&app, &user.email, &code).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_expired_code_is_refused() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new();
+    let app = create_test_app(&pool, email_service.clone());
+    let user = create_user(&pool).await;
+
+    request_code(&app, &user.email).await;
+    let code = sent_code(&email_service, &user.email);
+    sqlx::query(
+        "UPDATE email_login_codes SET expires_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1",
+    )
+    .bind(user.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (status, body) = verify_code(&app, &user.email, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_CODE");
+}
+
+#[tokio::test]
+async fn test_requesting_codes_is_rate_limited_per_email() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, MockEmailService::new());
+    let user = create_user(&pool).await;
+    let limit = RateLimitConfig::default().email_code.max_attempts;
+
+    for _ in 0..limit {
+        let (status, _) = request_code(&app, &user.email).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, _) = request_code(&app, &user.email).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,97 @@
+use chronos::app::geolocation::{GeoLocation, GeoResolver};
+use chronos::app::middleware::security::log_security_event;
+use chronos::app::security_events::{
+    SecurityEvent, SecurityEventSink, add_security_event_sink, set_geo_resolver,
+};
+use std::sync::{Arc, Mutex};
+
+// A tiny GeoLite2-City database listing only 81.2.69.0/24 (GB, England)
+// and 216.160.83.0/24 (US, Washington)
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/GeoLite2-City-Test.mmdb"
+);
+
+// Keeps every event it is handed
+#[derive(Default)]
+struct CapturingSink {
+    events: Mutex<Vec<SecurityEvent>>,
+}
+
+impl SecurityEventSink for CapturingSink {
+    fn record(&self, event: &SecurityEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+fn location(country: &str, region: &str) -> Option<GeoLocation> {
+    Some(GeoLocation {
+        country: Some(country.to_string()),
+        region: Some(region.to_string()),
+    })
+}
+
+#[test]
+fn test_known_ips_resolve_to_their_country() {
+    let resolver = GeoResolver::open(FIXTURE).unwrap();
+
+    assert_eq!(resolver.lookup("81.2.69.160"), location("GB", "England"));
+    assert_eq!(
+        resolver.lookup(" 216.160.83.56 "),
+        location("US", "Washington")
+    );
+}
+
+#[test]
+fn test_unknown_ips_resolve_to_none() {
+    let resolver = GeoResolver::open(FIXTURE).unwrap();
+
+    assert_eq!(resolver.lookup("81.2.70.1"), None);
+    assert_eq!(resolver.lookup("10.0.0.1"), None);
+    assert_eq!(resolver.lookup("2001:db8::1"), None);
+    assert_eq!(resolver.lookup("unknown"), None);
+}
+
+#[test]
+fn test_missing_database_is_an_error() {
+    assert!(GeoResolver::open("/nonexistent/GeoLite2-City.mmdb").is_err());
+}
+
+#[test]
+fn test_security_events_carry_the_location() {
+    let sink = Arc::new(CapturingSink::default());
+    add_security_event_sink(sink.clone());
+    set_geo_resolver(Some(GeoResolver::open(FIXTURE).unwrap()));
+
+    log_security_event(
+        "geo_test_login",
+        "81.2.69.160",
+        None,
+        None,
+        None,
+        true,
+        None,
+    );
+    log_security_event("geo_test_login", "10.0.0.1", None, None, None, true, None);
+    set_geo_resolver(None);
+    log_security_event(
+        "geo_test_login",
+        "81.2.69.160",
+        None,
+        None,
+        None,
+        true,
+        None,
+    );
+
+    let events = sink.events.lock().unwrap();
+    let located: Vec<_> = events
+        .iter()
+        .filter(|event| event.event_type == "geo_test_login")
+        .map(|event| (event.country.as_deref(), event.region.as_deref()))
+        .collect();
+    assert_eq!(
+        located,
+        vec![(Some("GB"), Some("England")), (None, None), (None, None)]
+    );
+}
@@ -0,0 +1,101 @@
+use axum::{Router, routing::get};
+use chronos::build::shutdown::serve_with_graceful_shutdown;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+// A server whose only route takes `delay` to answer, and the sender that shuts it down
+async fn start_server(
+    delay: Duration,
+    drain_timeout: Duration,
+) -> (
+    SocketAddr,
+    oneshot::Sender<()>,
+    tokio::task::JoinHandle<std::io::Result<()>>,
+) {
+    let app = Router::new().route(
+        "/slow",
+        get(move || async move {
+            tokio::time::sleep(delay).await;
+            "done"
+        }),
+    );
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_with_graceful_shutdown(
+        listener,
+        app,
+        async move {
+            let _ = shutdown_rx.await;
+        },
+        drain_timeout,
+    ));
+    (addr, shutdown_tx, server)
+}
+
+async fn get_slow(addr: SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /slow HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_server_resolves_when_the_signal_fires() {
+    let (_, shutdown_tx, server) = start_server(Duration::ZERO, Duration::from_secs(5)).await;
+
+    shutdown_tx.send(()).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not stop after the shutdown signal")
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_in_flight_request_finishes_during_shutdown() {
+    let (addr, shutdown_tx, server) =
+        start_server(Duration::from_millis(300), Duration::from_secs(5)).await;
+
+    let request = tokio::spawn(get_slow(addr));
+    // Let the request reach the handler before shutting down
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    shutdown_tx.send(()).unwrap();
+
+    let response = request.await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.ends_with("done"));
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server did not stop after draining")
+        .unwrap()
+        .unwrap();
+
+    // Nothing is accepted any more
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn test_drain_timeout_bounds_shutdown() {
+    let (addr, shutdown_tx, server) =
+        start_server(Duration::from_secs(60), Duration::from_millis(200)).await;
+
+    let _request = tokio::spawn(get_slow(addr));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    shutdown_tx.send(()).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server waited past the drain timeout")
+        .unwrap()
+        .unwrap();
+}
@@ -0,0 +1,163 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    routing::get,
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::json;
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const CONCURRENT_LOGINS: usize = 8;
+const PASSWORD: &str = "TestPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// The auth routes plus a handler that does no work, to measure scheduling delay
+fn create_test_app(pool: &PgPool) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .route("/ping", get(|| async { "pong" }))
+        .layer(MockConnectInfo(
+            "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+async fn create_users(pool: &PgPool, count: usize) -> Vec<String> {
+    let repository = UserRepository::new(pool.clone());
+    let mut emails = Vec::new();
+    for _ in 0..count {
+        let email = format!("concurrent-{}@example.com", Uuid::new_v4());
+        let user = User::new(None, email.clone(), PASSWORD).unwrap();
+        repository.create(&user).await.unwrap();
+        emails.push(email);
+    }
+    emails
+}
+
+async fn login(app: Router, email: String) -> StatusCode {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": PASSWORD }).to_string(),
+        ))
+        .unwrap();
+
+    app.oneshot(request).await.unwrap().status()
+}
+
+// Spawned so it has to be picked up by a runtime worker, just like a real request
+async fn ping(app: &Router) -> Duration {
+    let request = Request::builder().uri("/ping").body(Body::empty()).unwrap();
+
+    let started = Instant::now();
+    let response = tokio::spawn(app.clone().oneshot(request))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    started.elapsed()
+}
+
+// How long one password hash holds a thread on this machine
+fn single_hash_duration() -> Duration {
+    (0..2)
+        .map(|_| {
+            let started = Instant::now();
+            Argon2Params::default().hash(PASSWORD).unwrap();
+            started.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+// With Argon2 running inline, every worker thread would be busy hashing and a
+// ping would wait up to a full hash before being picked up
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_lightweight_requests_stay_responsive_during_login_burst() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool);
+    let emails = create_users(&pool, CONCURRENT_LOGINS).await;
+    let hash_duration = single_hash_duration();
+
+    let logins: Vec<_> = emails
+        .into_iter()
+        .map(|email| tokio::spawn(login(app.clone(), email)))
+        .collect();
+
+    // Give the logins a moment to reach the hashing stage
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let mut slowest_ping = Duration::ZERO;
+    while logins.iter().any(|login| !login.is_finished()) {
+        slowest_ping = slowest_ping.max(ping(&app).await);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    for login in logins {
+        assert_eq!(login.await.unwrap(), StatusCode::OK);
+    }
+    assert!(
+        slowest_ping < hash_duration / 4,
+        "slowest ping took {:?}; a single hash takes {:?}",
+        slowest_ping,
+        hash_duration
+    );
+}
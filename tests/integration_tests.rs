@@ -22,7 +22,9 @@ mod integration_tests {
         let valid_request = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "john.doe@example.com".to_string(),
+            username: None,
             password: "SecurePass1!".to_string(),
+            captcha_token: None,
         };
 
         // Test serialization
@@ -50,6 +52,7 @@ mod integration_tests {
                 id: uuid::Uuid::new_v4(),
                 name: Some("John Doe".to_string()),
                 email: "john.doe@example.com".to_string(),
+                username: None,
                 created_at: Some(time::OffsetDateTime::now_utc()),
             },
         };
@@ -82,7 +85,9 @@ mod integration_tests {
         let invalid_email = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "invalid-email".to_string(),
+            username: None,
             password: "SecurePass1!".to_string(),
+            captcha_token: None,
         };
         assert!(invalid_email.validate().is_err());
 
@@ -90,7 +95,9 @@ mod integration_tests {
         let weak_password = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "john.doe@example.com".to_string(),
+            username: None,
             password: "weak".to_string(),
+            captcha_token: None,
         };
         assert!(weak_password.validate().is_err());
 
@@ -98,7 +105,9 @@ mod integration_tests {
         let valid_request = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "john.doe@example.com".to_string(),
+            username: None,
             password: "SecurePass1!".to_string(),
+            captcha_token: None,
         };
         assert!(valid_request.validate().is_ok());
 
@@ -106,7 +115,9 @@ mod integration_tests {
         let no_name = RegisterRequest {
             name: None,
             email: "john.doe@example.com".to_string(),
+            username: None,
             password: "SecurePass1!".to_string(),
+            captcha_token: None,
         };
         assert!(no_name.validate().is_ok());
     }
@@ -128,7 +139,9 @@ mod integration_tests {
             let request = RegisterRequest {
                 name: Some("Test User".to_string()),
                 email: "test@example.com".to_string(),
+                username: None,
                 password: password.to_string(),
+                captcha_token: None,
             };
 
             let is_valid = request.validate().is_ok();
@@ -146,7 +159,9 @@ mod integration_tests {
         let request = RegisterRequest {
             name: Some("John Doe".to_string()),
             email: "invalid-email".to_string(),
+            username: None,
             password: "weak".to_string(),
+            captcha_token: None,
         };
 
         if let Err(validation_errors) = request.validate() {
@@ -374,7 +389,12 @@ mod integration_tests {
     async fn test_login_request_structure() {
         let login_request = LoginRequest {
             email: "test@example.com".to_string(),
+            identifier: None,
             password: "TestPassword123!".to_string(),
+            captcha_token: None,
+            remember_me: false,
+            device_name: None,
+            client_id: None,
         };
 
         // Test serialization
@@ -401,6 +421,7 @@ mod integration_tests {
             token_type: "Bearer".to_string(),
             expires_in: 900,            // 15 minutes
             refresh_expires_in: 604800, // 7 days
+            access_token_jti: None,
         };
 
         let response = LoginResponse {
@@ -409,9 +430,12 @@ mod integration_tests {
                 id: uuid::Uuid::new_v4(),
                 name: Some("Test User".to_string()),
                 email: "test@example.com".to_string(),
+                username: None,
                 created_at: Some(time::OffsetDateTime::now_utc()),
             },
             tokens: token_pair,
+            csrf_token: None,
+            password_change_required: false,
         };
 
         // Test serialization
@@ -454,6 +478,7 @@ mod integration_tests {
             token_type: "Bearer".to_string(),
             expires_in: 900,
             refresh_expires_in: Some(604800),
+            access_token_jti: None,
         };
 
         // Test serialization
@@ -501,6 +526,7 @@ mod integration_tests {
             token_type: "Bearer".to_string(),
             expires_in: 900,
             refresh_expires_in: 604800,
+            access_token_jti: None,
         };
 
         // Test serialization
@@ -525,6 +551,10 @@ mod integration_tests {
             iat: 1234567800,
             jti: "jwt-id-123".to_string(),
             token_type: TokenType::Access,
+            nbf: 1234567800,
+            sid: None,
+            scopes: Vec::new(),
+            aud: Vec::new(),
         };
 
         // Test serialization
@@ -579,14 +609,21 @@ mod integration_tests {
         let register_request = RegisterRequest {
             name: Some("Test User".to_string()),
             email: "test@example.com".to_string(),
+            username: None,
             password: "TestPassword123!".to_string(),
+            captcha_token: None,
         };
         assert!(register_request.validate().is_ok());
 
         // Step 2: Login
         let login_request = LoginRequest {
             email: "test@example.com".to_string(),
+            identifier: None,
             password: "TestPassword123!".to_string(),
+            captcha_token: None,
+            remember_me: false,
+            device_name: None,
+            client_id: None,
         };
 
         // Step 3: Expected login response with tokens
@@ -596,6 +633,7 @@ mod integration_tests {
             token_type: "Bearer".to_string(),
             expires_in: 900,
             refresh_expires_in: 604800,
+            access_token_jti: None,
         };
 
         let login_response = LoginResponse {
@@ -604,9 +642,12 @@ mod integration_tests {
                 id: uuid::Uuid::new_v4(),
                 name: Some("Test User".to_string()),
                 email: "test@example.com".to_string(),
+                username: None,
                 created_at: Some(time::OffsetDateTime::now_utc()),
             },
             tokens: mock_token_pair.clone(),
+            csrf_token: None,
+            password_change_required: false,
         };
 
         // Step 4: Using access token for authenticated requests
@@ -623,6 +664,7 @@ mod integration_tests {
             token_type: "Bearer".to_string(),
             expires_in: 900,
             refresh_expires_in: Some(604800),
+            access_token_jti: None,
         };
 
         // Step 6: Logout
@@ -648,7 +690,12 @@ mod integration_tests {
         // Invalid login credentials structure
         let invalid_login = LoginRequest {
             email: "nonexistent@example.com".to_string(),
+            identifier: None,
             password: "wrongpassword".to_string(),
+            captcha_token: None,
+            remember_me: false,
+            device_name: None,
+            client_id: None,
         };
 
         // This should serialize fine (the error would come from the backend)
@@ -0,0 +1,196 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{HeaderMap, Request, StatusCode},
+    routing::get,
+};
+use chronos::app::middleware::ip_filter::{IpFilter, IpFilterLayer, IpNetwork};
+use chronos::app::middleware::security::{client_ip, set_trusted_proxies};
+use serde_json::Value;
+use std::net::{IpAddr, SocketAddr};
+use tower::ServiceExt;
+
+fn networks(cidrs: &[&str]) -> Vec<IpNetwork> {
+    cidrs
+        .iter()
+        .map(|cidr| IpNetwork::parse(cidr).unwrap())
+        .collect()
+}
+
+fn ip(value: &str) -> IpAddr {
+    value.parse().unwrap()
+}
+
+fn create_test_app(filter: IpFilter, peer: &str) -> Router {
+    Router::new()
+        .route("/login", get(|| async { "ok" }))
+        .layer(IpFilterLayer::new(filter))
+        .layer(MockConnectInfo(peer.parse::<SocketAddr>().unwrap()))
+}
+
+async fn send(app: Router, forwarded_for: Option<&str>) -> (StatusCode, Value) {
+    let mut request = Request::builder().uri("/login");
+    if let Some(forwarded_for) = forwarded_for {
+        request = request.header("x-forwarded-for", forwarded_for);
+    }
+
+    let response = app
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_network_parsing() {
+    assert!(IpNetwork::parse("10.0.0.0/8").is_some());
+    assert!(IpNetwork::parse("192.168.1.7").is_some());
+    assert!(IpNetwork::parse("2001:db8::/32").is_some());
+    assert!(IpNetwork::parse("::1").is_some());
+
+    assert!(IpNetwork::parse("10.0.0.0/33").is_none());
+    assert!(IpNetwork::parse("2001:db8::/129").is_none());
+    assert!(IpNetwork::parse("10.0.0/8").is_none());
+    assert!(IpNetwork::parse("example.com").is_none());
+}
+
+#[test]
+fn test_network_contains_ipv4_and_ipv6() {
+    let v4 = IpNetwork::parse("10.1.0.0/16").unwrap();
+    assert!(v4.contains(ip("10.1.255.3")));
+    assert!(!v4.contains(ip("10.2.0.1")));
+    // IPv4 clients on a dual-stack socket
+    assert!(v4.contains(ip("::ffff:10.1.2.3")));
+
+    let v6 = IpNetwork::parse("2001:db8::/32").unwrap();
+    assert!(v6.contains(ip("2001:db8:1234::1")));
+    assert!(!v6.contains(ip("2001:db9::1")));
+    assert!(!v6.contains(ip("10.1.2.3")));
+
+    assert!(
+        IpNetwork::parse("0.0.0.0/0")
+            .unwrap()
+            .contains(ip("8.8.8.8"))
+    );
+    let host = IpNetwork::parse("192.168.1.7").unwrap();
+    assert!(host.contains(ip("192.168.1.7")));
+    assert!(!host.contains(ip("192.168.1.8")));
+}
+
+#[test]
+fn test_deny_list_wins_over_allow_list() {
+    let filter = IpFilter::new(networks(&["10.0.0.0/8"]), networks(&["10.6.6.0/24"]));
+
+    assert!(filter.is_allowed(Some(ip("10.1.1.1"))));
+    assert!(!filter.is_allowed(Some(ip("10.6.6.6"))));
+    assert!(!filter.is_allowed(Some(ip("172.16.0.1"))));
+    assert!(!filter.is_allowed(None));
+}
+
+#[tokio::test]
+async fn test_allowed_ip_passes() {
+    let filter = IpFilter::new(networks(&["192.168.0.0/16", "2001:db8::/32"]), Vec::new());
+
+    let (status, _) = send(create_test_app(filter.clone(), "192.168.4.2:5000"), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(create_test_app(filter, "[2001:db8::5]:5000"), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_denied_ip_is_blocked_with_generic_body() {
+    let filter = IpFilter::new(
+        Vec::new(),
+        networks(&["203.0.113.0/24", "2001:db8:bad::/48"]),
+    );
+
+    let (status, body) = send(create_test_app(filter.clone(), "203.0.113.9:5000"), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body, serde_json::json!({ "error": "Forbidden" }));
+
+    let (status, _) = send(
+        create_test_app(filter.clone(), "[2001:db8:bad::1]:5000"),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(create_test_app(filter, "198.51.100.1:5000"), None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_forwarded_address_is_filtered_behind_a_proxy() {
+    set_trusted_proxies(networks(&["10.0.0.0/8"]));
+    let filter = IpFilter::new(Vec::new(), networks(&["203.0.113.0/24"]));
+    // The proxy itself is fine, the client it forwards for is not
+    let app = create_test_app(filter, "10.0.0.1:5000");
+
+    let (status, _) = send(app.clone(), Some("203.0.113.9, 10.0.0.1")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(app, Some("198.51.100.1")).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_forwarded_address_from_untrusted_peer_is_ignored() {
+    set_trusted_proxies(networks(&["10.0.0.0/8"]));
+    let filter = IpFilter::new(Vec::new(), networks(&["203.0.113.0/24"]));
+
+    // A denied client can't pass as someone else by sending the header itself
+    let app = create_test_app(filter, "203.0.113.9:5000");
+    let (status, _) = send(app, Some("198.51.100.1")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+fn forwarded_headers(forwarded_for: Option<&str>, real_ip: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(forwarded_for) = forwarded_for {
+        headers.insert("x-forwarded-for", forwarded_for.parse().unwrap());
+    }
+    if let Some(real_ip) = real_ip {
+        headers.insert("x-real-ip", real_ip.parse().unwrap());
+    }
+    headers
+}
+
+#[test]
+fn test_client_ip_trusts_headers_only_from_trusted_proxies() {
+    let trusted = networks(&["10.0.0.0/8"]);
+    let proxy: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+    let direct: SocketAddr = "198.51.100.7:5000".parse().unwrap();
+
+    let headers = forwarded_headers(Some("203.0.113.9"), Some("203.0.113.10"));
+    assert_eq!(client_ip(direct, &headers, &trusted), ip("198.51.100.7"));
+    assert_eq!(client_ip(proxy, &headers, &[]), ip("10.0.0.1"));
+    assert_eq!(client_ip(proxy, &headers, &trusted), ip("203.0.113.9"));
+
+    // Addresses left of the first untrusted hop were made up by the client
+    let headers = forwarded_headers(Some("1.2.3.4, 203.0.113.9, 10.0.0.2"), None);
+    assert_eq!(client_ip(proxy, &headers, &trusted), ip("203.0.113.9"));
+
+    // X-Real-IP only when X-Forwarded-For is unusable, the socket address last
+    let headers = forwarded_headers(Some("not-an-ip"), Some("203.0.113.10"));
+    assert_eq!(client_ip(proxy, &headers, &trusted), ip("203.0.113.10"));
+    let headers = forwarded_headers(None, Some("garbage"));
+    assert_eq!(client_ip(proxy, &headers, &trusted), ip("10.0.0.1"));
+}
+
+#[tokio::test]
+async fn test_empty_config_is_a_no_op() {
+    let filter = IpFilter::default();
+    assert!(filter.is_empty());
+
+    let (status, _) = send(create_test_app(filter.clone(), "203.0.113.9:5000"), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(create_test_app(filter, "10.0.0.1:5000"), Some("not-an-ip")).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,200 @@
+use chronos::app::models::jwt::{Claims, JwtError, TokenType};
+use chronos::app::models::scope::default_scopes;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::jwt_service::{DEFAULT_KEY_ID, JwtKeyError, JwtService};
+use dotenvy::dotenv;
+use jsonwebtoken::{EncodingKey, Header, decode_header, encode};
+use sqlx::PgPool;
+use std::env;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+const SECRET_A: &str = "rotation-test-secret-a";
+const SECRET_B: &str = "rotation-test-secret-b";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_service(pool: &PgPool, secret: &str) -> JwtService {
+    JwtService::new(
+        secret,
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new(
+        None,
+        format!("rotation-{}@example.com", Uuid::new_v4()),
+        "RotationPass123!",
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn access_token(service: &JwtService, user: &User) -> String {
+    service
+        .generate_token_pair(user)
+        .await
+        .unwrap()
+        .access_token
+}
+
+fn kid(token: &str) -> Option<String> {
+    decode_header(token).unwrap().kid
+}
+
+// Signed the way tokens were before key ids, with no kid in the header
+fn token_without_kid(secret: &str, exp_offset: i64) -> String {
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let claims = Claims {
+        sub: Uuid::new_v4().to_string(),
+        email: "legacy@example.com".to_string(),
+        roles: vec!["user".to_string()],
+        exp: (now + exp_offset) as usize,
+        iat: now as usize,
+        jti: Uuid::new_v4().to_string(),
+        token_type: TokenType::Access,
+        nbf: now as usize,
+        sid: None,
+        scopes: default_scopes(),
+        aud: Vec::new(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_tokens_from_old_and_new_keys_validate_after_rotation() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool, SECRET_A);
+    let user = create_user(&pool).await;
+
+    let token_a = access_token(&service, &user).await;
+    assert_eq!(kid(&token_a).as_deref(), Some(DEFAULT_KEY_ID));
+
+    service.add_key("b", SECRET_B).unwrap();
+    service.set_active_key("b").unwrap();
+    assert_eq!(service.active_key_id(), "b");
+
+    let token_b = access_token(&service, &user).await;
+    assert_eq!(kid(&token_b).as_deref(), Some("b"));
+
+    assert_eq!(
+        service.validate_token(&token_a).await.unwrap().sub,
+        user.id.to_string()
+    );
+    assert_eq!(
+        service.validate_token(&token_b).await.unwrap().sub,
+        user.id.to_string()
+    );
+
+    // Clones share the key ring, so the handlers see the rotation too
+    let clone = service.clone();
+    assert!(clone.validate_token(&token_b).await.is_ok());
+
+    // A server that never learned about key b rejects its tokens
+    let other = create_service(&pool, SECRET_A);
+    assert!(matches!(
+        other.validate_token(&token_b).await,
+        Err(JwtError::InvalidToken(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_retired_key_no_longer_validates() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool, SECRET_A);
+    let user = create_user(&pool).await;
+    let token_a = access_token(&service, &user).await;
+
+    service.add_key("b", SECRET_B).unwrap();
+    assert!(matches!(
+        service.remove_key(DEFAULT_KEY_ID),
+        Err(JwtKeyError::ActiveKey(_))
+    ));
+
+    service.set_active_key("b").unwrap();
+    service.remove_key(DEFAULT_KEY_ID).unwrap();
+
+    assert!(matches!(
+        service.validate_token(&token_a).await,
+        Err(JwtError::InvalidToken(_))
+    ));
+    assert!(
+        service
+            .validate_token(&access_token(&service, &user).await)
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_key_management_errors() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool, SECRET_A);
+
+    assert!(matches!(
+        service.add_key(DEFAULT_KEY_ID, SECRET_B),
+        Err(JwtKeyError::DuplicateKey(_))
+    ));
+    assert!(matches!(
+        service.set_active_key("missing"),
+        Err(JwtKeyError::UnknownKey(_))
+    ));
+    assert!(matches!(
+        service.remove_key("missing"),
+        Err(JwtKeyError::UnknownKey(_))
+    ));
+    assert_eq!(service.active_key_id(), DEFAULT_KEY_ID);
+}
+
+#[tokio::test]
+async fn test_tokens_without_kid_try_every_key() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool, SECRET_A);
+    service.add_key("b", SECRET_B).unwrap();
+
+    assert!(
+        service
+            .validate_token(&token_without_kid(SECRET_A, 600))
+            .await
+            .is_ok()
+    );
+    assert!(
+        service
+            .validate_token(&token_without_kid(SECRET_B, 600))
+            .await
+            .is_ok()
+    );
+    assert!(matches!(
+        service
+            .validate_token(&token_without_kid(SECRET_B, -3600))
+            .await,
+        Err(JwtError::ExpiredToken)
+    ));
+    assert!(matches!(
+        service
+            .validate_token(&token_without_kid("some-other-secret", 600))
+            .await,
+        Err(JwtError::InvalidToken(_))
+    ));
+}
@@ -0,0 +1,86 @@
+use chronos::app::clock::MockClock;
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::jwt::JwtError;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::jwt_service::JwtService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use time::Duration;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_jwt_service(pool: &PgPool, clock: &MockClock, leeway: u64) -> JwtService {
+    JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_clock(clock.clone())
+    .with_leeway(leeway)
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("leeway-{}@example.com", Uuid::new_v4().simple()),
+        "LeewayPass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// An access token the clock has moved `secs` seconds past the expiry of
+async fn expired_access_token(pool: &PgPool, clock: &MockClock, secs: i64) -> String {
+    let jwt_service = create_jwt_service(pool, clock, 0);
+    let user = create_user(pool).await;
+    let tokens = jwt_service.generate_token_pair(&user).await.unwrap();
+    clock.advance(Duration::seconds(tokens.expires_in as i64 + secs));
+    tokens.access_token
+}
+
+#[tokio::test]
+async fn test_recently_expired_token_passes_within_leeway() {
+    let pool = setup_test_pool().await;
+    let clock = MockClock::default();
+    let token = expired_access_token(&pool, &clock, 5).await;
+
+    assert!(
+        create_jwt_service(&pool, &clock, 30)
+            .validate_token(&token)
+            .await
+            .is_ok()
+    );
+
+    let result = create_jwt_service(&pool, &clock, 0)
+        .validate_token(&token)
+        .await;
+    assert!(matches!(result, Err(JwtError::ExpiredToken)));
+}
+
+#[tokio::test]
+async fn test_leeway_does_not_extend_past_its_window() {
+    let pool = setup_test_pool().await;
+    let clock = MockClock::default();
+    let token = expired_access_token(&pool, &clock, 120).await;
+
+    let result = create_jwt_service(&pool, &clock, 30)
+        .validate_token(&token)
+        .await;
+    assert!(matches!(result, Err(JwtError::ExpiredToken)));
+}
@@ -0,0 +1,93 @@
+use chronos::app::clock::{Clock, MockClock};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::jwt::JwtError;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::jwt_service::JwtService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use time::Duration;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_jwt_service(pool: &PgPool, clock: &MockClock) -> JwtService {
+    JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_clock(clock.clone())
+    .with_leeway(0)
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("nbf-{}@example.com", Uuid::new_v4().simple()),
+        "NotBeforePass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_tokens_are_valid_from_issue_by_default() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_jwt_service(&pool, &MockClock::default());
+    let user = create_user(&pool).await;
+
+    let tokens = jwt_service.generate_token_pair(&user).await.unwrap();
+    let claims = jwt_service
+        .validate_token(&tokens.access_token)
+        .await
+        .unwrap();
+    assert_eq!(claims.nbf, claims.iat);
+}
+
+#[tokio::test]
+async fn test_token_with_future_nbf_is_rejected_until_then() {
+    let pool = setup_test_pool().await;
+    let clock = MockClock::default();
+    let jwt_service = create_jwt_service(&pool, &clock);
+    let user = create_user(&pool).await;
+
+    let not_before = clock.now() + Duration::seconds(10);
+    let tokens = jwt_service
+        .generate_token_pair_not_before(&user, not_before)
+        .await
+        .unwrap();
+
+    let result = jwt_service.validate_token(&tokens.access_token).await;
+    assert!(matches!(result, Err(JwtError::InvalidToken(_))));
+
+    // Still readable without validation, e.g. for logging
+    let claims = jwt_service
+        .decode_token_without_validation(&tokens.access_token)
+        .unwrap();
+    assert_eq!(claims.nbf, not_before.unix_timestamp() as usize);
+    // The lifetime counts from activation
+    assert_eq!(claims.exp - claims.nbf, 15 * 60);
+
+    clock.advance(Duration::seconds(10));
+    assert!(
+        jwt_service
+            .validate_token(&tokens.access_token)
+            .await
+            .is_ok()
+    );
+}
@@ -1,4 +1,6 @@
-use chronos::app::models::jwt::{Claims, RefreshTokenResponse, TokenType};
+use chronos::app::clock::MockClock;
+use chronos::app::hashing::{self, Argon2Params};
+use chronos::app::models::jwt::{Claims, JwtError, RefreshTokenResponse, TokenType};
 use chronos::app::models::user::User;
 use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
 use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
@@ -7,7 +9,6 @@ use chronos::app::services::jwt_service::JwtService;
 use dotenvy::dotenv;
 use sqlx::PgPool;
 use std::env;
-use time::OffsetDateTime;
 use uuid::Uuid;
 
 async fn setup_test_pool() -> PgPool {
@@ -67,6 +68,34 @@ async fn test_token_pair_generation() {
     let _ = user_repository.delete(test_user.id).await;
 }
 
+#[tokio::test]
+async fn test_token_pair_exposes_access_jti_when_enabled() {
+    let pool = setup_test_pool().await;
+
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let test_user = create_test_user(&pool, &format!("_jti_{}", Uuid::new_v4())).await;
+
+    // Off by default
+    let token_pair = jwt_service.generate_token_pair(&test_user).await.unwrap();
+    assert!(token_pair.access_token_jti.is_none());
+
+    let jwt_service = jwt_service.with_jti_exposure(true);
+    let token_pair = jwt_service.generate_token_pair(&test_user).await.unwrap();
+    let claims = jwt_service
+        .validate_token(&token_pair.access_token)
+        .await
+        .unwrap();
+    assert_eq!(token_pair.access_token_jti, Some(claims.jti));
+
+    // Cleanup
+    let user_repository = UserRepository::new(pool);
+    let _ = user_repository.delete(test_user.id).await;
+}
+
 #[tokio::test]
 async fn test_access_token_validation() {
     let pool = setup_test_pool().await;
@@ -382,3 +411,207 @@ async fn test_token_decode_without_validation() {
     // This method should work even for expired tokens (not tested here due to timing)
     // and blacklisted tokens (the validation bypass is the point)
 }
+
+#[tokio::test]
+async fn test_disabled_user_cannot_refresh() {
+    let pool = setup_test_pool().await;
+    let blacklist_repo = TokenBlacklistRepository::new(pool.clone());
+    let refresh_repo = RefreshTokenRepository::new(pool.clone());
+    let user_repository = UserRepository::new(pool.clone());
+
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        blacklist_repo,
+        refresh_repo.clone(),
+    )
+    .with_user_repository(user_repository.clone());
+    let test_user = create_test_user(&pool, &format!("_disabled_{}", Uuid::new_v4())).await;
+
+    let tokens = jwt_service
+        .generate_token_pair(&test_user)
+        .await
+        .expect("Should generate token pair");
+
+    user_repository
+        .set_active(test_user.id, false)
+        .await
+        .expect("Should disable user");
+
+    let result = jwt_service
+        .refresh_with_rotation(&tokens.refresh_token, test_user.id)
+        .await;
+    assert!(matches!(result, Err(JwtError::AccountUnavailable(_))));
+
+    let result = jwt_service
+        .refresh_access_token(&tokens.refresh_token)
+        .await;
+    assert!(matches!(result, Err(JwtError::AccountUnavailable(_))));
+
+    // Every refresh token the user held is revoked
+    let claims = jwt_service
+        .decode_token_without_validation(&tokens.refresh_token)
+        .unwrap();
+    let stored = refresh_repo
+        .find_by_jti(&claims.jti)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!stored.is_valid());
+
+    let _ = user_repository.delete(test_user.id).await;
+}
+
+#[tokio::test]
+async fn test_deleted_user_cannot_refresh() {
+    let pool = setup_test_pool().await;
+    let blacklist_repo = TokenBlacklistRepository::new(pool.clone());
+    let refresh_repo = RefreshTokenRepository::new(pool.clone());
+    let user_repository = UserRepository::new(pool.clone());
+
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        blacklist_repo,
+        refresh_repo,
+    )
+    .with_user_repository(user_repository.clone());
+    let test_user = create_test_user(&pool, &format!("_deleted_{}", Uuid::new_v4())).await;
+
+    let tokens = jwt_service
+        .generate_token_pair(&test_user)
+        .await
+        .expect("Should generate token pair");
+
+    user_repository.delete(test_user.id).await.unwrap();
+
+    let result = jwt_service
+        .refresh_with_rotation(&tokens.refresh_token, test_user.id)
+        .await;
+    assert!(matches!(result, Err(JwtError::AccountUnavailable(_))));
+}
+
+#[tokio::test]
+async fn test_refresh_token_verified_against_salted_hash() {
+    let pool = setup_test_pool().await;
+    let refresh_repo = RefreshTokenRepository::new(pool.clone());
+
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        TokenBlacklistRepository::new(pool.clone()),
+        refresh_repo.clone(),
+    );
+    let test_user = create_test_user(&pool, &format!("_salted_{}", Uuid::new_v4())).await;
+
+    let tokens = jwt_service
+        .generate_token_pair(&test_user)
+        .await
+        .expect("Should generate token pair");
+    let claims = jwt_service
+        .decode_token_without_validation(&tokens.refresh_token)
+        .unwrap();
+    let stored = refresh_repo
+        .find_by_jti(&claims.jti)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Hashing the same token again picks a new salt, so the stored hash can't be
+    // reproduced; it can only be verified
+    let rehashed = Argon2Params::default().hash(&tokens.refresh_token).unwrap();
+    assert_ne!(rehashed, stored.token_hash);
+    assert!(hashing::verify(&stored.token_hash, &tokens.refresh_token).unwrap());
+
+    jwt_service
+        .refresh_access_token(&tokens.refresh_token)
+        .await
+        .expect("Correct refresh token should verify");
+    jwt_service
+        .refresh_with_rotation(&tokens.refresh_token, test_user.id)
+        .await
+        .expect("Correct refresh token should rotate");
+
+    let user_repository = UserRepository::new(pool);
+    let _ = user_repository.delete(test_user.id).await;
+}
+
+#[tokio::test]
+async fn test_refresh_token_with_mismatched_hash_is_rejected() {
+    let pool = setup_test_pool().await;
+
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let test_user = create_test_user(&pool, &format!("_mismatch_{}", Uuid::new_v4())).await;
+
+    let tokens = jwt_service
+        .generate_token_pair(&test_user)
+        .await
+        .expect("Should generate token pair");
+    let claims = jwt_service
+        .decode_token_without_validation(&tokens.refresh_token)
+        .unwrap();
+
+    // The token still carries a valid signature and jti, but the stored hash
+    // belongs to a different token
+    let other_hash = Argon2Params::default()
+        .hash(&format!("{}tampered", tokens.refresh_token))
+        .unwrap();
+    sqlx::query("UPDATE refresh_tokens SET token_hash = $1 WHERE jti = $2")
+        .bind(&other_hash)
+        .bind(&claims.jti)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let result = jwt_service
+        .refresh_access_token(&tokens.refresh_token)
+        .await;
+    assert!(matches!(result, Err(JwtError::InvalidToken(ref e)) if e.contains("hash mismatch")));
+    let result = jwt_service
+        .refresh_with_rotation(&tokens.refresh_token, test_user.id)
+        .await;
+    assert!(matches!(result, Err(JwtError::InvalidToken(ref e)) if e.contains("hash mismatch")));
+
+    let user_repository = UserRepository::new(pool);
+    let _ = user_repository.delete(test_user.id).await;
+}
+
+#[tokio::test]
+async fn test_idle_refresh_token_expires() {
+    let pool = setup_test_pool().await;
+    let clock = MockClock::default();
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_clock(clock.clone())
+    .with_refresh_idle_timeout(Some(time::Duration::days(3)));
+    let test_user = create_test_user(&pool, &format!("_idle_{}", Uuid::new_v4())).await;
+
+    let tokens = jwt_service
+        .generate_token_pair(&test_user)
+        .await
+        .expect("Should generate token pair");
+    assert!(
+        jwt_service
+            .refresh_access_token(&tokens.refresh_token)
+            .await
+            .is_ok()
+    );
+
+    clock.advance(time::Duration::days(4));
+
+    let result = jwt_service
+        .refresh_access_token(&tokens.refresh_token)
+        .await;
+    assert!(matches!(result, Err(JwtError::ExpiredToken)));
+    let result = jwt_service
+        .refresh_with_rotation(&tokens.refresh_token, test_user.id)
+        .await;
+    assert!(matches!(result, Err(JwtError::ExpiredToken)));
+
+    let user_repository = UserRepository::new(pool);
+    let _ = user_repository.delete(test_user.id).await;
+}
@@ -0,0 +1,87 @@
+use chronos::app::cookies::AppEnvironment;
+use chronos::app::services::jwt_service::{
+    ConfigurationError, JwtKeyConfig, MIN_JWT_SECRET_BYTES, resolve_jwt_secret,
+};
+
+const STRONG_SECRET: &str = "0123456789abcdef0123456789abcdef0123456789abcdef";
+
+#[test]
+fn test_production_requires_a_secret() {
+    for secret in [None, Some(String::new())] {
+        assert_eq!(
+            resolve_jwt_secret(secret, AppEnvironment::Production),
+            Err(ConfigurationError::MissingJwtSecret)
+        );
+    }
+}
+
+#[test]
+fn test_production_rejects_short_and_placeholder_secrets() {
+    let short = "a".repeat(MIN_JWT_SECRET_BYTES - 1);
+    assert_eq!(
+        resolve_jwt_secret(Some(short), AppEnvironment::Production),
+        Err(ConfigurationError::JwtSecretTooShort)
+    );
+
+    let example =
+        "your-super-secret-jwt-key-change-this-in-production-with-at-least-256-bits".to_string();
+    assert_eq!(
+        resolve_jwt_secret(Some(example), AppEnvironment::Production),
+        Err(ConfigurationError::DefaultJwtSecret)
+    );
+
+    assert_eq!(
+        resolve_jwt_secret(Some(STRONG_SECRET.to_string()), AppEnvironment::Production),
+        Ok(STRONG_SECRET.to_string())
+    );
+}
+
+#[test]
+fn test_development_falls_back_to_the_default() {
+    let secret = resolve_jwt_secret(None, AppEnvironment::Development).unwrap();
+    assert!(secret.len() >= MIN_JWT_SECRET_BYTES);
+    // Rejected once the environment says production
+    assert_eq!(
+        resolve_jwt_secret(Some(secret), AppEnvironment::Production),
+        Err(ConfigurationError::DefaultJwtSecret)
+    );
+
+    // Short secrets are only warned about
+    assert_eq!(
+        resolve_jwt_secret(Some("dev".to_string()), AppEnvironment::Development),
+        Ok("dev".to_string())
+    );
+}
+
+#[test]
+fn test_signing_key_secrets_are_checked_like_jwt_secret() {
+    let config = |secret: &str| JwtKeyConfig {
+        keys: vec![
+            ("current".to_string(), STRONG_SECRET.to_string()),
+            ("next".to_string(), secret.to_string()),
+        ],
+        active: Some("current".to_string()),
+    };
+
+    let short = "a".repeat(MIN_JWT_SECRET_BYTES - 1);
+    assert_eq!(
+        config(&short).validate(AppEnvironment::Production),
+        Err(ConfigurationError::SigningKeySecretTooShort(
+            "next".to_string()
+        ))
+    );
+    let example = "your-super-secret-jwt-key-change-this-in-production-with-at-least-256-bits";
+    assert_eq!(
+        config(example).validate(AppEnvironment::Production),
+        Err(ConfigurationError::DefaultSigningKeySecret(
+            "next".to_string()
+        ))
+    );
+    assert_eq!(
+        config(STRONG_SECRET).validate(AppEnvironment::Production),
+        Ok(())
+    );
+
+    // Only warned about in development
+    assert_eq!(config(&short).validate(AppEnvironment::Development), Ok(()));
+}
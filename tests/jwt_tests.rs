@@ -282,6 +282,10 @@ async fn test_token_claims_structure() {
         iat: 1234567800,
         jti: "jwt-123".to_string(),
         token_type: TokenType::Access,
+        nbf: 1234567800,
+        sid: None,
+        scopes: Vec::new(),
+        aud: Vec::new(),
     };
 
     assert_eq!(claims.sub, "user-123");
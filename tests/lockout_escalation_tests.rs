@@ -0,0 +1,317 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::auth::AuthenticationError;
+use chronos::app::models::jwt::LoginRequest;
+use chronos::app::models::login_attempt::{LockoutConfig, LockoutPolicy};
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use time::Duration;
+use uuid::Uuid;
+
+const PASSWORD: &str = "LockoutPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_service(pool: &PgPool, policy: LockoutPolicy) -> SecureLoginService {
+    create_service_with_config(
+        pool,
+        LockoutConfig {
+            lockout_duration: policy,
+            ..LockoutConfig::default()
+        },
+    )
+}
+
+fn create_service_with_config(pool: &PgPool, config: LockoutConfig) -> SecureLoginService {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+
+    SecureLoginService::new(
+        auth_service,
+        jwt_service,
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+    .with_lockout_config(config)
+}
+
+// Low-cost hash so the many failed logins below stay quick
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("lockout-{}@example.com", Uuid::new_v4()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// A fresh address per attempt, so the per-IP limit never kicks in first
+fn random_ip() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2])
+}
+
+async fn login(
+    service: &SecureLoginService,
+    email: &str,
+    password: &str,
+) -> Result<(), AuthenticationError> {
+    let request = LoginRequest {
+        email: email.to_string(),
+        identifier: None,
+        password: password.to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
+    };
+    service
+        .secure_login(request, random_ip(), None)
+        .await
+        .map(|_| ())
+}
+
+// Fail logins until the account gets locked and return the lockout message
+async fn trip_lockout(service: &SecureLoginService, email: &str) -> String {
+    for _ in 0..10 {
+        let error = login(service, email, "WrongPassword1!").await.unwrap_err();
+        if let AuthenticationError::AccountLocked { .. } = error {
+            return error.to_string();
+        }
+    }
+    panic!("account was not locked after 10 failed logins");
+}
+
+// Stands in for the lockout running out, without waiting for it
+async fn end_lockout(pool: &PgPool, user: &User) {
+    AccountLockoutRepository::new(pool.clone())
+        .unlock_account(user.id)
+        .await
+        .unwrap();
+}
+
+// Lockout durations, oldest first
+async fn lockout_durations(pool: &PgPool, user: &User) -> Vec<(i32, Duration)> {
+    let mut lockouts = AccountLockoutRepository::new(pool.clone())
+        .find_by_user(user.id)
+        .await
+        .unwrap();
+    lockouts.reverse();
+    lockouts
+        .iter()
+        .map(|lockout| (lockout.lockout_count, lockout.duration()))
+        .collect()
+}
+
+#[test]
+fn test_policy_durations_escalate_and_cap() {
+    let policy = LockoutPolicy::default();
+
+    assert_eq!(policy.duration_for(1), Duration::minutes(1));
+    assert_eq!(policy.duration_for(2), Duration::minutes(5));
+    assert_eq!(policy.duration_for(3), Duration::minutes(15));
+    assert_eq!(policy.duration_for(4), Duration::hours(1));
+    assert_eq!(policy.duration_for(10), Duration::hours(1));
+    assert!(LockoutPolicy::new(Vec::new(), Duration::hours(1)).is_none());
+}
+
+#[tokio::test]
+async fn test_repeated_lockouts_get_longer() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool, LockoutPolicy::default());
+    let user = create_user(&pool).await;
+
+    let mut messages = Vec::new();
+    for _ in 0..5 {
+        messages.push(trip_lockout(&service, &user.email).await);
+        end_lockout(&pool, &user).await;
+    }
+
+    assert!(messages[0].ends_with("Please try again in 1 minute."));
+    assert!(messages[1].ends_with("Please try again in 5 minutes."));
+    assert!(messages[2].ends_with("Please try again in 15 minutes."));
+    assert!(messages[3].ends_with("Please try again in 1 hour."));
+    assert!(messages[4].ends_with("Please try again in 1 hour."));
+
+    let durations = lockout_durations(&pool, &user).await;
+    assert_eq!(
+        durations,
+        vec![
+            (1, Duration::minutes(1)),
+            (2, Duration::minutes(5)),
+            (3, Duration::minutes(15)),
+            (4, Duration::hours(1)),
+            (5, Duration::hours(1)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_successful_login_resets_escalation() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool, LockoutPolicy::default());
+    let user = create_user(&pool).await;
+
+    trip_lockout(&service, &user.email).await;
+    end_lockout(&pool, &user).await;
+    trip_lockout(&service, &user.email).await;
+    end_lockout(&pool, &user).await;
+
+    login(&service, &user.email, PASSWORD).await.unwrap();
+
+    let message = trip_lockout(&service, &user.email).await;
+    assert!(message.ends_with("Please try again in 1 minute."));
+
+    let counts: Vec<i32> = lockout_durations(&pool, &user)
+        .await
+        .into_iter()
+        .map(|(count, _)| count)
+        .collect();
+    assert_eq!(counts, vec![1, 2, 1]);
+}
+
+#[tokio::test]
+async fn test_escalation_resets_after_cooldown() {
+    let pool = setup_test_pool().await;
+    // With no cooldown, a lockout that has already ended never counts towards the next
+    let policy = LockoutPolicy::new(
+        vec![Duration::minutes(1), Duration::minutes(5)],
+        Duration::ZERO,
+    )
+    .unwrap();
+    let service = create_service(&pool, policy);
+    let user = create_user(&pool).await;
+
+    trip_lockout(&service, &user.email).await;
+    end_lockout(&pool, &user).await;
+    let message = trip_lockout(&service, &user.email).await;
+
+    assert!(message.ends_with("Please try again in 1 minute."));
+    assert_eq!(
+        lockout_durations(&pool, &user).await,
+        vec![(1, Duration::minutes(1)), (1, Duration::minutes(1))]
+    );
+}
+
+fn three_attempt_config() -> LockoutConfig {
+    LockoutConfig {
+        max_attempts: 3,
+        ..LockoutConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn test_configured_threshold_locks_on_next_failure() {
+    let pool = setup_test_pool().await;
+    let service = create_service_with_config(&pool, three_attempt_config());
+    let user = create_user(&pool).await;
+
+    for _ in 0..3 {
+        let error = login(&service, &user.email, "WrongPassword1!")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuthenticationError::InvalidCredentials));
+    }
+
+    let error = login(&service, &user.email, "WrongPassword1!")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Account has been temporarily locked due to too many failed login attempts. Please try again in 1 minute."
+    );
+    assert_eq!(
+        lockout_durations(&pool, &user).await,
+        vec![(1, Duration::minutes(1))]
+    );
+
+    // The right password doesn't get through while the lockout lasts
+    let error = login(&service, &user.email, PASSWORD).await.unwrap_err();
+    assert!(error.to_string().contains("Account is temporarily locked"));
+}
+
+#[tokio::test]
+async fn test_successful_login_resets_failure_count() {
+    let pool = setup_test_pool().await;
+    let service = create_service_with_config(&pool, three_attempt_config());
+    let user = create_user(&pool).await;
+
+    for _ in 0..3 {
+        login(&service, &user.email, "WrongPassword1!")
+            .await
+            .unwrap_err();
+    }
+    login(&service, &user.email, PASSWORD).await.unwrap();
+
+    // Three more failures are allowed again before the account locks
+    for _ in 0..3 {
+        let error = login(&service, &user.email, "WrongPassword1!")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuthenticationError::InvalidCredentials));
+    }
+    assert!(lockout_durations(&pool, &user).await.is_empty());
+
+    let error = login(&service, &user.email, "WrongPassword1!")
+        .await
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Account has been temporarily locked")
+    );
+}
+
+#[tokio::test]
+async fn test_failures_outside_the_window_are_not_counted() {
+    let pool = setup_test_pool().await;
+    // A zero window ignores every earlier failure
+    let service = create_service_with_config(
+        &pool,
+        LockoutConfig {
+            window: Duration::ZERO,
+            ..three_attempt_config()
+        },
+    );
+    let user = create_user(&pool).await;
+
+    for _ in 0..5 {
+        let error = login(&service, &user.email, "WrongPassword1!")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AuthenticationError::InvalidCredentials));
+    }
+}
@@ -0,0 +1,143 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "HistoryPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Each test gets its own IP so failed logins don't add up across tests
+fn create_test_app(pool: PgPool, ip: &str) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("history-{}@example.com", Uuid::new_v4().simple()),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn login(
+    app: &axum::Router,
+    email: &str,
+    password: &str,
+    user_agent: &str,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("user-agent", user_agent)
+        .body(Body::from(
+            json!({ "email": email, "password": password }).to_string(),
+        ))
+        .unwrap();
+    send(app, request).await
+}
+
+async fn login_history(app: &axum::Router, access_token: &str, query: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(format!("/api/auth/login-history{}", query))
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_login_history_lists_successful_and_failed_attempts() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.7.1");
+    let user = create_user(&pool).await;
+    let other = create_user(&pool).await;
+
+    let (status, _) = login(&app, &user.email, "WrongPass123!", "history-test/1").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = login(&app, &user.email, TEST_PASSWORD, "history-test/2").await;
+    assert_eq!(status, StatusCode::OK);
+    let access_token = body["tokens"]["access_token"].as_str().unwrap().to_string();
+    // Someone else's sign-in must not show up
+    let (status, _) = login(&app, &other.email, TEST_PASSWORD, "history-test/3").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = login_history(&app, &access_token, "").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["page"], 1);
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+
+    // Newest first
+    assert_eq!(items[0]["success"], true);
+    assert_eq!(items[0]["user_agent"], "history-test/2");
+    assert!(items[0]["failure_reason"].is_null());
+    assert_eq!(items[1]["success"], false);
+    assert_eq!(items[1]["user_agent"], "history-test/1");
+    assert_eq!(items[1]["failure_reason"], "Invalid password");
+    for item in items {
+        assert_eq!(item["ip_address"], "192.168.7.1");
+        assert!(item["created_at"].is_string());
+        assert!(item.get("email").is_none());
+        assert!(item.get("id").is_none());
+    }
+
+    let (status, body) = login_history(&app, &access_token, "?page=2&per_page=1").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["total"], 2);
+    assert_eq!(body["per_page"], 1);
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["success"], false);
+}
+
+#[tokio::test]
+async fn test_login_history_requires_authentication() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool, "192.168.7.2");
+
+    let request = Request::builder()
+        .uri("/api/auth/login-history")
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,198 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode, header},
+};
+use chronos::app::middleware::ip_filter::IpNetwork;
+use chronos::app::middleware::rate_limiter::{RateLimitConfig, RateLimitRule};
+use chronos::app::middleware::security::{SecurityState, set_trusted_proxies};
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Auth state allowing 3 logins per IP and per email a minute
+fn create_test_state(pool: &PgPool) -> AuthAppState {
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let config = RateLimitConfig {
+        login: RateLimitRule::new(3, Duration::from_secs(60)),
+        login_email: RateLimitRule::new(3, Duration::from_secs(60)),
+        ..RateLimitConfig::default()
+    };
+
+    AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::new(config),
+    )
+}
+
+// Routers built from the same state share its rate limiter
+fn create_test_app(state: AuthAppState, ip: &str) -> Router {
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(
+            format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+// Failed logins are also counted per IP in the database, so reruns need fresh addresses
+fn unique_ip() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2])
+}
+
+fn unique_email() -> String {
+    format!("login-limit-{}@example.com", Uuid::new_v4().simple())
+}
+
+async fn login(app: &Router, email: &str) -> (StatusCode, Option<u64>, Value) {
+    login_forwarded_for(app, email, None).await
+}
+
+// `client` goes in X-Forwarded-For, as a proxy in front of the server sets it
+async fn login_forwarded_for(
+    app: &Router,
+    email: &str,
+    client: Option<&str>,
+) -> (StatusCode, Option<u64>, Value) {
+    let mut request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json");
+    if let Some(client) = client {
+        request = request.header("x-forwarded-for", client);
+    }
+    let request = request
+        .body(Body::from(
+            json!({ "email": email, "password": "WrongPass123!" }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        retry_after,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn test_many_logins_from_one_ip_are_rate_limited() {
+    let pool = setup_test_pool().await;
+    let state = create_test_state(&pool);
+    let app = create_test_app(state.clone(), &unique_ip());
+
+    // A different account each time, so no single account gets locked
+    for _ in 0..3 {
+        let (status, _, _) = login(&app, &unique_email()).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let (status, retry_after, body) = login(&app, &unique_email()).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert!((1..=60).contains(&retry_after.unwrap()));
+    assert_eq!(body["error"]["code"], "RATE_LIMITED");
+
+    // Another client isn't held back by the first one
+    let other_app = create_test_app(state, &unique_ip());
+    let (status, _, _) = login(&other_app, &unique_email()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_many_logins_for_one_email_are_rate_limited() {
+    let pool = setup_test_pool().await;
+    let state = create_test_state(&pool);
+    let email = unique_email();
+
+    for _ in 0..3 {
+        let app = create_test_app(state.clone(), &unique_ip());
+        let (status, _, _) = login(&app, &email).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    let app = create_test_app(state, &unique_ip());
+    let (status, retry_after, _) = login(&app, &email).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after.is_some());
+
+    // The IP itself has only been used once
+    let (status, _, _) = login(&app, &unique_email()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_clients_behind_a_trusted_proxy_are_limited_separately() {
+    let pool = setup_test_pool().await;
+    let state = create_test_state(&pool);
+    let proxy = unique_ip();
+    set_trusted_proxies(vec![IpNetwork::parse(&proxy).unwrap()]);
+    let app = create_test_app(state, &proxy);
+    let (first_client, second_client) = (unique_ip(), unique_ip());
+
+    for _ in 0..3 {
+        let (status, _, _) = login_forwarded_for(&app, &unique_email(), Some(&first_client)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let (status, _, _) = login_forwarded_for(&app, &unique_email(), Some(&first_client)).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Counted under its own address, not the proxy's
+    let (status, _, _) = login_forwarded_for(&app, &unique_email(), Some(&second_client)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
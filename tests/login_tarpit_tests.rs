@@ -0,0 +1,165 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::jwt::LoginRequest;
+use chronos::app::models::login_attempt::TarpitConfig;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use dotenvy::dotenv;
+use serde_json::json;
+use sqlx::PgPool;
+use std::env;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const PASSWORD: &str = "TarpitPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_service(pool: &PgPool) -> SecureLoginService {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    SecureLoginService::new(
+        auth_service,
+        jwt_service,
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+}
+
+// Short delays so the test stays fast
+fn short_tarpit() -> TarpitConfig {
+    TarpitConfig {
+        base_delay: Duration::from_millis(20),
+        max_delay: Duration::from_millis(60),
+        ..TarpitConfig::default()
+    }
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("tarpit-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+fn unique_ip() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2])
+}
+
+fn login_request(email: &str, password: &str) -> LoginRequest {
+    serde_json::from_value(json!({ "email": email, "password": password })).unwrap()
+}
+
+#[test]
+fn test_delay_doubles_up_to_the_cap() {
+    let config = short_tarpit();
+    let delays: Vec<u64> = (0..5)
+        .map(|failures| config.delay_for(failures).as_millis() as u64)
+        .collect();
+    assert_eq!(delays, vec![0, 20, 40, 60, 60]);
+
+    assert!(!TarpitConfig::disabled().is_enabled());
+    assert_eq!(TarpitConfig::disabled().delay_for(10), Duration::ZERO);
+}
+
+#[tokio::test]
+async fn test_delay_grows_with_failures_and_resets_on_success() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool).with_tarpit_config(short_tarpit());
+    let user = create_user(&pool).await;
+    let ip = unique_ip();
+    let fail = || {
+        service.login(
+            login_request(&user.email, "WrongPassword1!"),
+            ip.clone(),
+            None,
+        )
+    };
+
+    assert_eq!(
+        service.failure_delay(&ip, Some(user.id)).await,
+        Duration::ZERO
+    );
+
+    let mut delays = Vec::new();
+    for _ in 0..3 {
+        let started = Instant::now();
+        assert!(fail().await.is_err());
+        let delay = service.failure_delay(&ip, Some(user.id)).await;
+        // The failed login itself waited that long
+        assert!(started.elapsed() >= delay);
+        delays.push(delay.as_millis());
+    }
+    assert_eq!(delays, vec![20, 40, 60]);
+
+    let response = service
+        .login(login_request(&user.email, PASSWORD), ip.clone(), None)
+        .await;
+    assert!(response.is_ok());
+    assert_eq!(
+        service.failure_delay(&ip, Some(user.id)).await,
+        Duration::ZERO
+    );
+
+    assert!(fail().await.is_err());
+    assert_eq!(
+        service.failure_delay(&ip, Some(user.id)).await,
+        Duration::from_millis(20)
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_emails_are_slowed_by_ip() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool).with_tarpit_config(short_tarpit());
+    let ip = unique_ip();
+
+    for _ in 0..2 {
+        let email = format!("nobody-{}@example.com", Uuid::new_v4().simple());
+        let result = service
+            .login(login_request(&email, PASSWORD), ip.clone(), None)
+            .await;
+        assert!(result.is_err());
+    }
+    assert_eq!(
+        service.failure_delay(&ip, None).await,
+        Duration::from_millis(40)
+    );
+
+    // Off by default
+    let untarpitted = create_service(&pool);
+    assert_eq!(untarpitted.failure_delay(&ip, None).await, Duration::ZERO);
+}
@@ -36,7 +36,7 @@ async fn create_test_app() -> axum::Router {
             .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
             .layer(PropagateRequestIdLayer::x_request_id())
             .layer(TraceLayer::new_for_http())
-            .layer(SecurityHeadersLayer)
+            .layer(SecurityHeadersLayer::default())
             .layer(get_cors_layer())
             .layer(MockConnectInfo(
                 "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
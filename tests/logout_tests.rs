@@ -1,4 +1,6 @@
-use chronos::app::models::jwt::{Claims, JwtError, LogoutRequest, LogoutResponse, TokenType};
+use chronos::app::models::jwt::{
+    Claims, JwtError, LogoutRequest, LogoutResponse, RevocationPolicy, TokenType,
+};
 use chronos::app::models::user::User;
 use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
 use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
@@ -81,6 +83,7 @@ async fn test_logout_response_serialization() {
     let response = LogoutResponse {
         message: "Logged out successfully".to_string(),
         logged_out_devices: Some(1),
+        complete: None,
     };
 
     let json = serde_json::to_string(&response).expect("Should serialize");
@@ -93,6 +96,7 @@ async fn test_logout_response_serialization() {
     let response_no_count = LogoutResponse {
         message: "Logged out successfully".to_string(),
         logged_out_devices: None,
+        complete: None,
     };
 
     let json_no_count = serde_json::to_string(&response_no_count).expect("Should serialize");
@@ -146,11 +150,6 @@ async fn test_single_device_logout_flow() {
         .await
         .expect("Should revoke refresh token");
 
-    jwt_service
-        .blacklist_token(&tokens.refresh_token)
-        .await
-        .expect("Should blacklist refresh token");
-
     // Verify access token is now blacklisted
     let access_result = jwt_service.validate_token(&tokens.access_token).await;
     assert!(access_result.is_err());
@@ -159,14 +158,6 @@ async fn test_single_device_logout_flow() {
         JwtError::BlacklistedToken
     ));
 
-    // Verify refresh token is also blacklisted
-    let refresh_result = jwt_service.validate_token(&tokens.refresh_token).await;
-    assert!(refresh_result.is_err());
-    assert!(matches!(
-        refresh_result.unwrap_err(),
-        JwtError::BlacklistedToken
-    ));
-
     // Verify refresh token can't be used for new tokens
     let refresh_attempt = jwt_service
         .refresh_access_token(&tokens.refresh_token)
@@ -178,6 +169,60 @@ async fn test_single_device_logout_flow() {
     let _ = user_repository.delete(test_user.id).await;
 }
 
+#[tokio::test]
+async fn test_logged_out_refresh_token_rejected_without_blacklist_entry() {
+    let pool = setup_test_pool().await;
+    let blacklist_repo = TokenBlacklistRepository::new(pool.clone());
+    let refresh_repo = RefreshTokenRepository::new(pool.clone());
+
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        blacklist_repo.clone(),
+        refresh_repo.clone(),
+    );
+    let test_user = create_test_user(&pool, "_refresh_revocation").await;
+
+    let tokens = jwt_service
+        .generate_token_pair(&test_user)
+        .await
+        .expect("Should generate token pair");
+    let refresh_claims = jwt_service
+        .decode_token_without_validation(&tokens.refresh_token)
+        .expect("Should decode refresh token");
+
+    // Blacklisting a refresh token revokes it instead of adding a blacklist row
+    jwt_service
+        .blacklist_token(&tokens.refresh_token)
+        .await
+        .expect("Should invalidate refresh token");
+
+    let is_blacklisted = blacklist_repo
+        .is_blacklisted(&refresh_claims.jti)
+        .await
+        .expect("Should query blacklist");
+    assert!(!is_blacklisted);
+
+    let stored_token = refresh_repo
+        .find_by_jti(&refresh_claims.jti)
+        .await
+        .expect("Should query refresh tokens")
+        .expect("Refresh token should be stored");
+    assert!(stored_token.revoked_at.is_some());
+
+    // The revoked refresh token can no longer be exchanged
+    let refresh_attempt = jwt_service
+        .refresh_access_token(&tokens.refresh_token)
+        .await;
+    assert!(matches!(
+        refresh_attempt.unwrap_err(),
+        JwtError::InvalidToken(message) if message.contains("revoked")
+    ));
+
+    // Cleanup
+    let user_repository = UserRepository::new(pool);
+    let _ = user_repository.delete(test_user.id).await;
+}
+
 #[tokio::test]
 async fn test_logout_all_devices_flow() {
     let pool = setup_test_pool().await;
@@ -228,10 +273,10 @@ async fn test_logout_all_devices_flow() {
     );
 
     // Logout all devices - revoke all refresh tokens for the user
-    jwt_service
+    let outcome = jwt_service
         .revoke_all_user_refresh_tokens(test_user.id)
-        .await
-        .expect("Should revoke all user refresh tokens");
+        .await;
+    assert!(outcome.complete, "Should revoke all user refresh tokens");
 
     // Verify all refresh tokens can no longer be used
     let refresh1_result = jwt_service
@@ -263,6 +308,82 @@ async fn test_logout_all_devices_flow() {
     let _ = user_repository.delete(test_user.id).await;
 }
 
+#[tokio::test]
+async fn test_logout_all_devices_reports_partial_revocation() {
+    let pool = setup_test_pool().await;
+    let jwt_service = JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_revocation_policy(RevocationPolicy {
+        batch_size: 1,
+        max_retries: 2,
+    });
+    let suffix = Uuid::new_v4().simple().to_string();
+    let test_user = create_test_user(&pool, &format!("_partial_revoke_{}", suffix)).await;
+
+    // Three devices; the newest is revoked last
+    for _ in 0..2 {
+        jwt_service.generate_token_pair(&test_user).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let newest = jwt_service.generate_token_pair(&test_user).await.unwrap();
+    let newest_jti = jwt_service
+        .decode_token_without_validation(&newest.refresh_token)
+        .unwrap()
+        .jti;
+
+    // Make the database reject revoking the newest token
+    let trigger = format!("fail_revoke_{}", suffix);
+    sqlx::raw_sql(&format!(
+        r#"
+        CREATE FUNCTION {trigger}() RETURNS trigger AS $$
+        BEGIN RAISE EXCEPTION 'simulated revocation failure'; END
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER {trigger} BEFORE UPDATE ON refresh_tokens
+        FOR EACH ROW WHEN (OLD.jti = '{newest_jti}') EXECUTE FUNCTION {trigger}();
+        "#
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let outcome = jwt_service
+        .revoke_all_user_refresh_tokens(test_user.id)
+        .await;
+
+    sqlx::raw_sql(&format!(
+        "DROP TRIGGER {trigger} ON refresh_tokens; DROP FUNCTION {trigger}();"
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(outcome.revoked, 2);
+    assert!(!outcome.complete);
+    assert!(
+        outcome
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("simulated revocation failure")
+    );
+
+    // Retrying finishes the job without touching the already revoked tokens
+    let retry = jwt_service
+        .revoke_all_user_refresh_tokens(test_user.id)
+        .await;
+    assert_eq!(retry.revoked, 1);
+    assert!(retry.complete);
+    assert!(
+        jwt_service
+            .refresh_access_token(&newest.refresh_token)
+            .await
+            .is_err()
+    );
+}
+
 #[tokio::test]
 async fn test_logout_with_invalid_token() {
     let pool = setup_test_pool().await;
@@ -513,5 +634,6 @@ async fn test_logout_edge_cases() {
     let uuid = Uuid::new_v4();
     let nonexistent_result = jwt_service.revoke_all_user_refresh_tokens(uuid).await;
     // Should succeed (no tokens to revoke for this user)
-    assert!(nonexistent_result.is_ok());
+    assert!(nonexistent_result.complete);
+    assert_eq!(nonexistent_result.revoked, 0);
 }
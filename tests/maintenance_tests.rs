@@ -0,0 +1,98 @@
+use chronos::app::maintenance::CleanupTask;
+use chronos::app::models::jwt::{BlacklistedToken, TokenType};
+use chronos::app::models::user::User;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::task_health::TaskStatus;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+async fn create_test_user(pool: &PgPool) -> Uuid {
+    let email = format!("cleanup-{}@example.com", Uuid::new_v4());
+    let user = User::new(None, email, "TestPass123!").unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+        .id
+}
+
+async fn blacklist(pool: &PgPool, user_id: Uuid, expires_at: OffsetDateTime) -> String {
+    let jti = Uuid::new_v4().to_string();
+    let token = BlacklistedToken::new(jti.clone(), user_id, TokenType::Access, expires_at);
+    TokenBlacklistRepository::new(pool.clone())
+        .blacklist_token(&token)
+        .await
+        .unwrap();
+    jti
+}
+
+#[tokio::test]
+async fn test_run_once_removes_expired_blacklist_entries() {
+    let pool = setup_test_pool().await;
+    let user_id = create_test_user(&pool).await;
+    let now = OffsetDateTime::now_utc();
+    let expired = blacklist(&pool, user_id, now - time::Duration::hours(1)).await;
+    let live = blacklist(&pool, user_id, now + time::Duration::hours(1)).await;
+
+    let task = CleanupTask::new(pool.clone());
+    let report = task.run_once().await.unwrap();
+
+    let repository = TokenBlacklistRepository::new(pool.clone());
+    assert!(repository.get_by_jti(&expired).await.unwrap().is_none());
+    assert!(repository.get_by_jti(&live).await.unwrap().is_some());
+    assert!(report.blacklisted_tokens >= 1);
+
+    let health = task.heartbeat().health();
+    assert_eq!(health.status, TaskStatus::Healthy);
+    assert_eq!(health.last_removed, Some(report.total()));
+}
+
+#[tokio::test]
+async fn test_spawned_task_runs_and_stops_on_shutdown() {
+    let pool = setup_test_pool().await;
+    let user_id = create_test_user(&pool).await;
+    let expired = blacklist(
+        &pool,
+        user_id,
+        OffsetDateTime::now_utc() - time::Duration::hours(1),
+    )
+    .await;
+
+    let task = CleanupTask::new(pool.clone()).with_interval(Duration::from_secs(3600));
+    let heartbeat = task.heartbeat();
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let handle = task.spawn(shutdown_rx);
+
+    // The first cycle runs immediately on spawn
+    for _ in 0..50 {
+        if heartbeat.health().last_run.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(heartbeat.health().status, TaskStatus::Healthy);
+
+    let repository = TokenBlacklistRepository::new(pool.clone());
+    assert!(repository.get_by_jti(&expired).await.unwrap().is_none());
+
+    shutdown_tx.send(true).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), handle)
+        .await
+        .expect("cleanup task did not stop on shutdown")
+        .unwrap();
+}
@@ -0,0 +1,185 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware,
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::auth_middleware::jwt_auth_middleware_with_json_errors;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "WhoAmIPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: &PgPool) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service.clone(),
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state.clone()))
+        .nest(
+            "/api/auth",
+            auth::protected_routes()
+                .with_state(state)
+                .layer(middleware::from_fn_with_state(
+                    Arc::new(jwt_service),
+                    jwt_auth_middleware_with_json_errors,
+                )),
+        )
+        .layer(MockConnectInfo(
+            "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        Some("Who Am I".to_string()),
+        format!("me-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn login(app: &Router, user: &User) -> String {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": user.email, "password": PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+async fn get_me(app: &Router, access_token: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/me")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_me_reports_profile_and_permissions() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    let access_token = login(&app, &user).await;
+
+    let (status, body) = get_me(&app, &access_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["id"], user.id.to_string());
+    assert_eq!(body["email"], user.email);
+    assert_eq!(body["name"], "Who Am I");
+    assert_eq!(body["roles"], json!([]));
+    assert!(!body["scopes"].as_array().unwrap().is_empty());
+    assert_eq!(body["two_factor_enabled"], false);
+    assert_eq!(body["email_verified"], false);
+    assert_eq!(body["active_sessions"], 1);
+    assert!(body.get("password_hash").is_none());
+}
+
+#[tokio::test]
+async fn test_me_reflects_roles_and_two_factor() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool);
+    let user = create_user(&pool).await;
+    // Logged in before the phone is verified, since that makes logins wait for an SMS code
+    let access_token = login(&app, &user).await;
+    login(&app, &user).await;
+
+    let roles = RoleRepository::new(pool.clone());
+    let admin = roles.find_by_name("admin").await.unwrap().unwrap();
+    roles.grant_role(user.id, admin.id).await.unwrap();
+    let users = UserRepository::new(pool.clone());
+    users
+        .set_phone_number(user.id, "+15555550123")
+        .await
+        .unwrap();
+    users
+        .mark_phone_verified(user.id, "+15555550123")
+        .await
+        .unwrap();
+    users.mark_verified(user.id).await.unwrap();
+
+    let (status, body) = get_me(&app, &access_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["roles"], json!(["admin"]));
+    assert_eq!(body["two_factor_enabled"], true);
+    assert_eq!(body["email_verified"], true);
+    assert_eq!(body["active_sessions"], 2);
+}
@@ -0,0 +1,115 @@
+//! Run with `cargo test --features metrics --test metrics_tests`
+#![cfg(feature = "metrics")]
+
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::json;
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "MetricsPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: PgPool) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        "192.168.1.60:8080".parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn post_json(app: &axum::Router, uri: &str, body: serde_json::Value) -> StatusCode {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+async fn scrape(app: &axum::Router) -> String {
+    let request = Request::builder()
+        .uri("/metrics")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+// Value of a sample line such as `chronos_logins_total{result="success"} 3`
+fn sample_value(output: &str, sample: &str) -> f64 {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix(sample)?.trim().parse().ok())
+        .unwrap_or(0.0)
+}
+
+#[tokio::test]
+async fn test_login_increments_metrics() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool);
+
+    let email = format!("metrics_{}@example.com", Uuid::new_v4().simple());
+    let status = post_json(
+        &app,
+        "/api/auth/register",
+        json!({ "email": email, "password": TEST_PASSWORD }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // The endpoint is public: no Authorization header
+    let before = scrape(&app).await;
+    let successes = sample_value(&before, r#"chronos_logins_total{result="success"}"#);
+    let failures = sample_value(&before, r#"chronos_logins_total{result="failure"}"#);
+
+    let status = post_json(
+        &app,
+        "/api/auth/login",
+        json!({ "email": email, "password": TEST_PASSWORD }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let status = post_json(
+        &app,
+        "/api/auth/login",
+        json!({ "email": email, "password": "WrongPass123!" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let after = scrape(&app).await;
+    assert_eq!(
+        sample_value(&after, r#"chronos_logins_total{result="success"}"#),
+        successes + 1.0
+    );
+    assert_eq!(
+        sample_value(&after, r#"chronos_logins_total{result="failure"}"#),
+        failures + 1.0
+    );
+    assert!(sample_value(&after, "chronos_registrations_total") >= 1.0);
+    assert!(sample_value(&after, "chronos_login_duration_seconds_count") >= 2.0);
+    assert!(after.contains("chronos_token_generation_duration_seconds_bucket"));
+}
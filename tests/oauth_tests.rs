@@ -0,0 +1,448 @@
+use async_trait::async_trait;
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode, header},
+};
+use chronos::app::middleware::security::{OAuthStateStore, SecurityState};
+use chronos::app::models::login_attempt::AccountLockout;
+use chronos::app::models::oauth::{OAuthError, OAuthUserInfo};
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::oauth_identity_repository::OAuthIdentityRepository;
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::oauth_service::{
+    GitHubEmail, OAuthProvider, OAuthService, primary_github_email,
+};
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+// Stands in for Google: each authorization code maps to a fixed identity
+struct MockOAuthProvider {
+    identities: HashMap<String, OAuthUserInfo>,
+}
+
+#[async_trait]
+impl OAuthProvider for MockOAuthProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn authorization_url(&self, state: &str) -> String {
+        format!("https://provider.example.com/auth?state={}", state)
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<OAuthUserInfo, OAuthError> {
+        self.identities
+            .get(code)
+            .cloned()
+            .ok_or_else(|| OAuthError::Provider("invalid_grant".to_string()))
+    }
+}
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn identity(subject: &str, email: &str, email_verified: bool) -> OAuthUserInfo {
+    OAuthUserInfo {
+        subject: subject.to_string(),
+        email: email.to_string(),
+        email_verified,
+        name: Some("OAuth User".to_string()),
+    }
+}
+
+fn create_service(pool: &PgPool, identities: Vec<(&str, OAuthUserInfo)>) -> OAuthService {
+    let provider = MockOAuthProvider {
+        identities: identities
+            .into_iter()
+            .map(|(code, info)| (code.to_string(), info))
+            .collect(),
+    };
+
+    OAuthService::new(
+        Arc::new(provider),
+        UserRepository::new(pool.clone()),
+        OAuthIdentityRepository::new(pool.clone()),
+    )
+}
+
+async fn count_users_with_email(pool: &PgPool, email: &str) -> i64 {
+    sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE email = $1", email)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .unwrap_or(0)
+}
+
+#[tokio::test]
+async fn test_first_login_creates_user_and_repeat_login_links() {
+    let pool = setup_test_pool().await;
+    let subject = Uuid::new_v4().to_string();
+    let email = format!("oauth-new-{}@example.com", subject);
+    let changed_email = format!("oauth-changed-{}@example.com", subject);
+
+    let service = create_service(
+        &pool,
+        vec![
+            ("first", identity(&subject, &email, true)),
+            // Same Google account after the user changed their address there
+            ("second", identity(&subject, &changed_email, true)),
+        ],
+    );
+
+    let created = service.complete_login("first").await.unwrap();
+    assert_eq!(created.email, email);
+    assert_eq!(created.name.as_deref(), Some("OAuth User"));
+
+    let repeat = service.complete_login("second").await.unwrap();
+    assert_eq!(repeat.id, created.id);
+    assert_eq!(count_users_with_email(&pool, &changed_email).await, 0);
+
+    let identities = OAuthIdentityRepository::new(pool.clone())
+        .find_by_user(created.id)
+        .await
+        .unwrap();
+    assert_eq!(identities.len(), 1);
+    assert_eq!(identities[0].provider, "mock");
+    assert_eq!(identities[0].subject, subject);
+}
+
+#[tokio::test]
+async fn test_existing_password_account_is_linked_not_duplicated() {
+    let pool = setup_test_pool().await;
+    let email = format!("oauth-existing-{}@example.com", Uuid::new_v4());
+    let user_repository = UserRepository::new(pool.clone());
+    let existing = user_repository
+        .create(&User::new(None, email.clone(), "TestPass123!").unwrap())
+        .await
+        .unwrap();
+
+    let subject = Uuid::new_v4().to_string();
+    let service = create_service(&pool, vec![("code", identity(&subject, &email, true))]);
+
+    let user = service.complete_login("code").await.unwrap();
+    assert_eq!(user.id, existing.id);
+    assert_eq!(count_users_with_email(&pool, &email).await, 1);
+
+    // The original password keeps working
+    assert!(user.verify_password("TestPass123!").unwrap());
+}
+
+#[tokio::test]
+async fn test_unverified_email_is_rejected() {
+    let pool = setup_test_pool().await;
+    let email = format!("oauth-unverified-{}@example.com", Uuid::new_v4());
+    let service = create_service(
+        &pool,
+        vec![("code", identity(&Uuid::new_v4().to_string(), &email, false))],
+    );
+
+    let result = service.complete_login("code").await;
+    assert!(matches!(result, Err(OAuthError::EmailNotVerified)));
+    assert_eq!(count_users_with_email(&pool, &email).await, 0);
+}
+
+#[tokio::test]
+async fn test_failed_code_exchange_is_a_provider_error() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool, vec![]);
+
+    let result = service.complete_login("unknown").await;
+    assert!(matches!(result, Err(OAuthError::Provider(_))));
+}
+
+#[test]
+fn test_oauth_state_is_single_use() {
+    let store = OAuthStateStore::default();
+    let state = store.issue();
+
+    assert!(store.consume(&state));
+    assert!(!store.consume(&state));
+    assert!(!store.consume("forged-state"));
+}
+
+#[test]
+fn test_expired_oauth_state_is_rejected() {
+    let store = OAuthStateStore::new(Duration::ZERO);
+    let state = store.issue();
+
+    assert!(!store.consume(&state));
+}
+
+fn github_email(email: &str, primary: bool, verified: bool) -> GitHubEmail {
+    GitHubEmail {
+        email: email.to_string(),
+        primary,
+        verified,
+    }
+}
+
+#[test]
+fn test_github_primary_email_is_selected() {
+    let emails = vec![
+        github_email("secondary@example.com", false, true),
+        github_email("primary@example.com", true, true),
+        github_email("noreply@example.com", false, false),
+    ];
+
+    let primary = primary_github_email(&emails).unwrap();
+    assert_eq!(primary.email, "primary@example.com");
+    assert!(primary.verified);
+}
+
+#[test]
+fn test_github_unverified_primary_is_not_replaced() {
+    // A verified secondary address must not stand in for the primary
+    let emails = vec![
+        github_email("verified@example.com", false, true),
+        github_email("primary@example.com", true, false),
+    ];
+
+    let primary = primary_github_email(&emails).unwrap();
+    assert_eq!(primary.email, "primary@example.com");
+    assert!(!primary.verified);
+    assert!(primary_github_email(&[]).is_none());
+}
+
+// Auth routes with GitHub login backed by the mock provider
+fn create_github_app(pool: &PgPool, oauth_service: OAuthService) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    )
+    .with_github_oauth_service(oauth_service);
+
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(
+            "192.168.10.1:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Option<String>, Value) {
+    get_with_cookie(app, uri, None).await
+}
+
+async fn get_with_cookie(
+    app: &Router,
+    uri: &str,
+    cookie: Option<&str>,
+) -> (StatusCode, Option<String>, Value) {
+    let mut request = Request::builder().uri(uri);
+    if let Some(cookie) = cookie {
+        request = request.header(header::COOKIE, cookie);
+    }
+    let response = app
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(header::LOCATION)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        location,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn test_github_callback_requires_issued_state() {
+    let pool = setup_test_pool().await;
+    let subject = Uuid::new_v4().to_string();
+    let email = format!("oauth-github-{}@example.com", subject);
+    let app = create_github_app(
+        &pool,
+        create_service(&pool, vec![("code", identity(&subject, &email, true))]),
+    );
+
+    let (status, _, body) = get(
+        &app,
+        "/api/auth/oauth/github/callback?code=code&state=forged",
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_STATE");
+    assert_eq!(count_users_with_email(&pool, &email).await, 0);
+
+    let (status, location, _) = get(&app, "/api/auth/oauth/github/start").await;
+    assert_eq!(status, StatusCode::SEE_OTHER);
+    let location = location.unwrap();
+    let state = location.split("state=").nth(1).unwrap();
+    let cookie = format!("oauth_state={}", state);
+
+    let callback = format!("/api/auth/oauth/github/callback?code=code&state={}", state);
+    let (status, _, body) = get_with_cookie(&app, &callback, Some(&cookie)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user"]["email"], email);
+    assert!(body["tokens"]["access_token"].is_string());
+
+    // The state is spent once the login went through
+    let (status, _, _) = get_with_cookie(&app, &callback, Some(&cookie)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_github_callback_requires_state_cookie_of_starting_browser() {
+    let pool = setup_test_pool().await;
+    let subject = Uuid::new_v4().to_string();
+    let email = format!("oauth-github-{}@example.com", subject);
+    let app = create_github_app(
+        &pool,
+        create_service(&pool, vec![("code", identity(&subject, &email, true))]),
+    );
+
+    let request = Request::builder()
+        .uri("/api/auth/oauth/github/start")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+    assert!(set_cookie.contains("HttpOnly"));
+    assert!(set_cookie.contains("SameSite=Lax"));
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    let state = location.split("state=").nth(1).unwrap().to_string();
+
+    // A callback link handed to another browser carries no cookie, or a different one
+    let callback = format!("/api/auth/oauth/github/callback?code=code&state={}", state);
+    let (status, _, body) = get(&app, &callback).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_STATE");
+    let (status, _, _) = get_with_cookie(&app, &callback, Some("oauth_state=other")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(count_users_with_email(&pool, &email).await, 0);
+
+    // A rejected callback does not spend the state of the browser that started the flow
+    let cookie = format!("oauth_state={}", state);
+    let (status, _, _) = get_with_cookie(&app, &callback, Some(&cookie)).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+// Runs the whole flow in one browser: start, then the callback with its state cookie
+async fn github_login(app: &Router, code: &str) -> (StatusCode, Value) {
+    let (_, location, _) = get(app, "/api/auth/oauth/github/start").await;
+    let state = location
+        .unwrap()
+        .split("state=")
+        .nth(1)
+        .unwrap()
+        .to_string();
+    let callback = format!(
+        "/api/auth/oauth/github/callback?code={}&state={}",
+        code, state
+    );
+    let cookie = format!("oauth_state={}", state);
+    let (status, _, body) = get_with_cookie(app, &callback, Some(&cookie)).await;
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_github_callback_applies_sms_code_and_lockout() {
+    let pool = setup_test_pool().await;
+    let user_repository = UserRepository::new(pool.clone());
+
+    let phone_email = format!("oauth-phone-{}@example.com", Uuid::new_v4());
+    let phone_user = user_repository
+        .create(&User::new(None, phone_email.clone(), "TestPass123!").unwrap())
+        .await
+        .unwrap();
+    user_repository
+        .set_phone_number(phone_user.id, "+15555550123")
+        .await
+        .unwrap();
+    user_repository
+        .mark_phone_verified(phone_user.id, "+15555550123")
+        .await
+        .unwrap();
+
+    let locked_email = format!("oauth-locked-{}@example.com", Uuid::new_v4());
+    let locked_user = user_repository
+        .create(&User::new(None, locked_email.clone(), "TestPass123!").unwrap())
+        .await
+        .unwrap();
+    AccountLockoutRepository::new(pool.clone())
+        .create_lockout(&AccountLockout::new(locked_user.id, 10, 30))
+        .await
+        .unwrap();
+
+    let app = create_github_app(
+        &pool,
+        create_service(
+            &pool,
+            vec![
+                (
+                    "phone",
+                    identity(&Uuid::new_v4().to_string(), &phone_email, true),
+                ),
+                (
+                    "locked",
+                    identity(&Uuid::new_v4().to_string(), &locked_email, true),
+                ),
+            ],
+        ),
+    );
+
+    // The provider stands in for the password, not for the SMS code; without
+    // an SMS provider the account can't sign in at all
+    let (status, body) = github_login(&app, "phone").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body["tokens"].is_null());
+
+    let (status, body) = github_login(&app, "locked").await;
+    assert_eq!(status, StatusCode::LOCKED);
+    assert_eq!(body["error"]["code"], "ACCOUNT_LOCKED");
+}
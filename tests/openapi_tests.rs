@@ -0,0 +1,59 @@
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use chronos::routes::openapi;
+use serde_json::Value;
+use tower::ServiceExt;
+
+async fn get(uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = openapi::routes()
+        .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, body.to_vec())
+}
+
+#[tokio::test]
+async fn test_openapi_document_lists_auth_routes() {
+    let (status, body) = get("/api/openapi.json").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let spec: Value = serde_json::from_slice(&body).unwrap();
+    let login = &spec["paths"]["/api/auth/login"]["post"];
+    assert!(login.is_object());
+    assert_eq!(
+        login["responses"]["401"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/AuthErrorResponse"
+    );
+    assert!(spec["components"]["schemas"]["FieldError"].is_object());
+    assert!(spec["components"]["schemas"]["ErrorResponse"].is_object());
+    assert!(spec["components"]["schemas"]["TokenErrorResponse"].is_object());
+}
+
+#[tokio::test]
+async fn test_protected_routes_require_bearer_jwt() {
+    let (_, body) = get("/api/openapi.json").await;
+    let spec: Value = serde_json::from_slice(&body).unwrap();
+
+    let scheme = &spec["components"]["securitySchemes"]["bearer_auth"];
+    assert_eq!(scheme["type"], "http");
+    assert_eq!(scheme["scheme"], "bearer");
+    assert_eq!(scheme["bearerFormat"], "JWT");
+
+    let profile = &spec["paths"]["/api/auth/profile"]["get"];
+    assert!(profile["security"][0]["bearer_auth"].is_array());
+    // Public routes carry no security requirement
+    assert!(spec["paths"]["/api/auth/login"]["post"]["security"].is_null());
+}
+
+#[tokio::test]
+async fn test_swagger_ui_is_served() {
+    let (status, body) = get("/api/docs/").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(String::from_utf8_lossy(&body).contains("swagger"));
+}
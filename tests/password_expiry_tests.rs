@@ -0,0 +1,240 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware,
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::auth_middleware::jwt_auth_middleware_with_json_errors;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "ExpiringPass123!";
+const NEW_PASSWORD: &str = "RotatedPass456!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Passwords older than a day need changing
+fn create_test_app(pool: &PgPool) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+    .with_password_max_age(Some(Duration::days(1)));
+
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service.clone(),
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state.clone()))
+        .nest(
+            "/api/auth",
+            auth::protected_routes()
+                .with_state(state)
+                .layer(middleware::from_fn_with_state(
+                    Arc::new(jwt_service),
+                    jwt_auth_middleware_with_json_errors,
+                )),
+        )
+        .layer(MockConnectInfo(
+            "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+// A user whose password was set `password_age` ago
+async fn create_user(pool: &PgPool, password_age: Duration) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("expiry-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    let user = UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap();
+    sqlx::query(
+        "UPDATE users SET password_changed_at = NOW() - make_interval(secs => $2) WHERE id = $1",
+    )
+    .bind(user.id)
+    .bind(password_age.whole_seconds() as f64)
+    .execute(pool)
+    .await
+    .unwrap();
+    user
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn post(uri: &str, access_token: Option<&str>, body: Value) -> Request<Body> {
+    let mut request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json");
+    if let Some(token) = access_token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+async fn login(app: &Router, user: &User, password: &str) -> Value {
+    let (status, body) = send(
+        app,
+        post(
+            "/api/auth/login",
+            None,
+            json!({ "email": user.email, "password": password }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body
+}
+
+async fn get_profile(app: &Router, access_token: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/profile")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_recent_password_gets_full_access() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool);
+    let user = create_user(&pool, Duration::hours(1)).await;
+
+    let body = login(&app, &user, PASSWORD).await;
+    assert_eq!(body["password_change_required"], false);
+
+    let access_token = body["tokens"]["access_token"].as_str().unwrap();
+    let (status, _) = get_profile(&app, access_token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_expired_password_only_allows_change_password() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool);
+    let user = create_user(&pool, Duration::days(2)).await;
+
+    let body = login(&app, &user, PASSWORD).await;
+    assert_eq!(body["password_change_required"], true);
+    let access_token = body["tokens"]["access_token"].as_str().unwrap();
+
+    let (status, body) = get_profile(&app, access_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "INSUFFICIENT_PERMISSIONS");
+    let (status, _) = send(
+        &app,
+        post(
+            "/api/auth/api-keys",
+            Some(access_token),
+            json!({ "name": "ci", "scopes": ["profile:read"] }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(
+        &app,
+        post(
+            "/api/auth/change-password",
+            Some(access_token),
+            json!({ "current_password": PASSWORD, "new_password": NEW_PASSWORD }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Logging in with the new password lifts the restriction
+    let body = login(&app, &user, NEW_PASSWORD).await;
+    assert_eq!(body["password_change_required"], false);
+    let access_token = body["tokens"]["access_token"].as_str().unwrap();
+    let (status, _) = get_profile(&app, access_token).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_refresh_keeps_the_restriction() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool);
+    let user = create_user(&pool, Duration::days(2)).await;
+
+    let body = login(&app, &user, PASSWORD).await;
+    let refresh_token = body["tokens"]["refresh_token"].as_str().unwrap();
+
+    let (status, body) = send(
+        &app,
+        post(
+            "/api/auth/refresh",
+            None,
+            json!({ "refresh_token": refresh_token }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let access_token = body["access_token"].as_str().unwrap();
+
+    let (status, _) = get_profile(&app, access_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,166 @@
+use chronos::app::hashing::{Argon2Params, HashFormat, PasswordHasher};
+use chronos::app::models::jwt::LoginRequest;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use dotenvy::dotenv;
+use pbkdf2::Pbkdf2;
+use pbkdf2::password_hash::{PasswordHasher as _, SaltString, rand_core::OsRng};
+use sqlx::PgPool;
+use std::env;
+use uuid::Uuid;
+
+const PASSWORD: &str = "ImportedPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_service(pool: &PgPool) -> SecureLoginService {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    )
+    .with_argon2_params(Argon2Params::new(1024, 1, 1).unwrap());
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+
+    SecureLoginService::new(
+        auth_service,
+        jwt_service,
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+}
+
+// Low costs keep the tests fast; the formats are what matter here
+fn bcrypt_hash(password: &str) -> String {
+    bcrypt::hash(password, 4).unwrap()
+}
+
+fn pbkdf2_hash(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    let params = pbkdf2::Params {
+        rounds: 1000,
+        output_length: 32,
+    };
+    Pbkdf2
+        .hash_password_customized(password.as_bytes(), None, None, params, &salt)
+        .unwrap()
+        .to_string()
+}
+
+// An account imported with `password_hash` as it came from the old system
+async fn import_user(pool: &PgPool, password_hash: String) -> User {
+    let user = User::with_password_hash(
+        None,
+        format!("imported-{}@example.com", Uuid::new_v4().simple()),
+        password_hash,
+    );
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn login(service: &SecureLoginService, email: &str) {
+    let request = LoginRequest {
+        email: email.to_string(),
+        identifier: None,
+        password: PASSWORD.to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
+    };
+    let bytes = Uuid::new_v4().into_bytes();
+    let ip = format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2]);
+    service.secure_login(request, ip, None).await.unwrap();
+}
+
+#[test]
+fn test_legacy_hashes_verify() {
+    let hasher = PasswordHasher::default();
+
+    for hash in [bcrypt_hash(PASSWORD), pbkdf2_hash(PASSWORD)] {
+        assert!(hasher.verify_password(&hash, PASSWORD).unwrap());
+        assert!(!hasher.verify_password(&hash, "WrongPass123!").unwrap());
+        assert!(hasher.needs_rehash(&hash));
+    }
+
+    let argon2_hash = hasher.hash_password(PASSWORD).unwrap();
+    assert_eq!(HashFormat::detect(&argon2_hash), Some(HashFormat::Argon2id));
+    assert!(!hasher.needs_rehash(&argon2_hash));
+    assert!(
+        hasher
+            .verify_password("{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=", PASSWORD)
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_login_upgrades_bcrypt_hash() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool);
+    let user = import_user(&pool, bcrypt_hash(PASSWORD)).await;
+    assert_eq!(
+        HashFormat::detect(&user.password_hash),
+        Some(HashFormat::Bcrypt)
+    );
+
+    login(&service, &user.email).await;
+
+    let stored = UserRepository::new(pool.clone())
+        .find_by_id(user.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        HashFormat::detect(&stored.password_hash),
+        Some(HashFormat::Argon2id)
+    );
+    assert!(stored.verify_password(PASSWORD).unwrap());
+    // Not a password change, so password expiry is unaffected
+    assert_eq!(stored.password_changed_at, user.password_changed_at);
+
+    // And the upgraded hash keeps working
+    login(&service, &user.email).await;
+}
+
+#[tokio::test]
+async fn test_login_upgrades_pbkdf2_hash() {
+    let pool = setup_test_pool().await;
+    let service = create_service(&pool);
+    let user = import_user(&pool, pbkdf2_hash(PASSWORD)).await;
+
+    login(&service, &user.email).await;
+
+    let stored = UserRepository::new(pool)
+        .find_by_id(user.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        HashFormat::detect(&stored.password_hash),
+        Some(HashFormat::Argon2id)
+    );
+}
@@ -1,9 +1,9 @@
+use chronos::app::clock::{Clock, MockClock};
 use chronos::app::models::auth::{AuthError, ForgotPasswordRequest, ResetPasswordRequest};
 use chronos::app::models::password_reset::PasswordResetToken;
 use chronos::app::models::user::User;
 use chronos::app::services::email_service::MockEmailService;
 use serde_json;
-use time::OffsetDateTime;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -37,20 +37,24 @@ mod password_reset_tests {
         let user_id = Uuid::new_v4();
         let plain_token = PasswordResetToken::generate_secure_token();
 
-        let mut reset_token = PasswordResetToken::new(user_id, &plain_token).unwrap();
+        let clock = MockClock::default();
+        let mut reset_token =
+            PasswordResetToken::new_at(user_id, &plain_token, clock.now()).unwrap();
 
         // Test valid token
-        assert!(reset_token.is_valid());
+        assert!(reset_token.is_valid_at(clock.now()));
 
-        // Test expired token
-        reset_token.expires_at = OffsetDateTime::now_utc() - time::Duration::hours(1);
-        assert!(reset_token.is_expired());
-        assert!(!reset_token.is_valid());
+        // Still valid right up to the hour, expired after it
+        clock.advance(time::Duration::hours(1));
+        assert!(!reset_token.is_expired_at(clock.now()));
+        clock.advance(time::Duration::seconds(1));
+        assert!(reset_token.is_expired_at(clock.now()));
+        assert!(!reset_token.is_valid_at(clock.now()));
 
         // Test used token
-        reset_token.expires_at = OffsetDateTime::now_utc() + time::Duration::hours(1);
+        clock.set(reset_token.created_at);
         reset_token.used = true;
-        assert!(!reset_token.is_valid());
+        assert!(!reset_token.is_valid_at(clock.now()));
     }
 
     #[test]
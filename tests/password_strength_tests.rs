@@ -0,0 +1,216 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode, header},
+};
+use chronos::app::middleware::rate_limiter::{RateLimitConfig, RateLimitRule};
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::auth::{PasswordPolicy, password_strength};
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::{AuthService, get_password_history_depth};
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: &PgPool, security_state: SecurityState) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        security_state,
+    );
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(
+            "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[test]
+fn test_common_passwords_score_low() {
+    for password in ["password", "123456", "qwerty", "Password1", "letmein!"] {
+        let report = password_strength(password);
+        assert!(report.score <= 1, "{} scored {}", password, report.score);
+        assert!(!report.feedback.is_empty());
+    }
+}
+
+#[test]
+fn test_strong_passwords_score_high() {
+    for password in [
+        "correct-horse-battery-staple-92",
+        "Tz8#qL!vW2m$Rp4x",
+        "my cat eats 7 purple tacos on Tuesdays",
+    ] {
+        let report = password_strength(password);
+        assert!(report.score >= 3, "{} scored {}", password, report.score);
+    }
+}
+
+#[tokio::test]
+async fn test_strength_endpoint_returns_report() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, SecurityState::default());
+
+    let (status, body) = post(
+        &app,
+        "/api/auth/password/strength",
+        json!({ "password": "password" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["score"], 0);
+    assert!(body["feedback"].as_array().is_some_and(|f| !f.is_empty()));
+    assert!(body["crack_time_estimate"].is_string());
+
+    let (_, body) = post(
+        &app,
+        "/api/auth/password/strength",
+        json!({ "password": "Tz8#qL!vW2m$Rp4x" }),
+    )
+    .await;
+    assert_eq!(body["score"], 4);
+}
+
+#[tokio::test]
+async fn test_policy_endpoint_returns_configured_policy() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, SecurityState::default());
+
+    let request = Request::builder()
+        .uri("/api/auth/password/policy")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        "public, max-age=3600"
+    );
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let policy: PasswordPolicy = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        policy,
+        PasswordPolicy::current(get_password_history_depth())
+    );
+    assert_eq!(policy.min_length, 8);
+    assert!(policy.require_uppercase && policy.require_lowercase);
+    assert!(policy.require_digit && policy.require_special);
+    assert!(!policy.breach_check);
+}
+
+#[tokio::test]
+async fn test_strength_endpoint_is_rate_limited() {
+    let pool = setup_test_pool().await;
+    let config = RateLimitConfig {
+        password_strength: RateLimitRule::new(2, Duration::from_secs(60)),
+        ..RateLimitConfig::default()
+    };
+    let app = create_test_app(&pool, SecurityState::new(config));
+
+    for _ in 0..2 {
+        let (status, _) = post(
+            &app,
+            "/api/auth/password/strength",
+            json!({ "password": "anything" }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, _) = post(
+        &app,
+        "/api/auth/password/strength",
+        json!({ "password": "anything" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_weak_registration_password_reports_score() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, SecurityState::default());
+
+    let (status, body) = post(
+        &app,
+        "/api/auth/register",
+        json!({
+            "email": format!("strength-{}@example.com", Uuid::new_v4()),
+            "password": "password"
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let details = body["error"]["details"].as_array().unwrap();
+    assert!(details.contains(&json!({
+        "field": "password",
+        "message": "strength score 0 of 4"
+    })));
+}
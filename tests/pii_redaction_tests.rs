@@ -0,0 +1,93 @@
+use chronos::app::middleware::security::log_security_event;
+use chronos::app::security_events::{
+    PiiMode, SecurityEvent, SecurityEventSink, add_security_event_sink, set_pii_mode,
+};
+use std::sync::{Arc, Mutex};
+
+fn hashed(salt: &str) -> PiiMode {
+    PiiMode::Hashed {
+        salt: salt.to_string(),
+    }
+}
+
+// Keeps every event it is handed
+#[derive(Default)]
+struct CapturingSink {
+    events: Mutex<Vec<SecurityEvent>>,
+}
+
+impl SecurityEventSink for CapturingSink {
+    fn record(&self, event: &SecurityEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[test]
+fn test_full_mode_keeps_values() {
+    let mode = PiiMode::Full;
+
+    assert_eq!(mode.email("jane@example.com"), "jane@example.com");
+    assert_eq!(mode.ip("203.0.113.7"), "203.0.113.7");
+}
+
+#[test]
+fn test_masked_mode_output() {
+    let mode = PiiMode::Masked;
+
+    assert_eq!(mode.email("jane@example.com"), "j***@example.com");
+    assert_eq!(mode.email("@example.com"), "***@example.com");
+    assert_eq!(mode.email("not-an-email"), "***");
+
+    assert_eq!(mode.ip("203.0.113.7"), "203.0.113.0");
+    assert_eq!(
+        mode.ip("2001:db8:85a3:8d3:1319:8a2e:370:7348"),
+        "2001:db8:85a3::"
+    );
+    assert_eq!(mode.ip("unknown"), "***");
+}
+
+#[test]
+fn test_hashed_mode_output() {
+    let mode = hashed("pepper");
+
+    let email = mode.email("jane@example.com");
+    assert_eq!(email.len(), 16);
+    assert!(email.chars().all(|c| c.is_ascii_hexdigit()));
+    assert!(!email.contains("jane"));
+    // Stable, so events can be correlated, and case-insensitive for emails
+    assert_eq!(mode.email("Jane@Example.com"), email);
+    assert_ne!(mode.email("john@example.com"), email);
+
+    let ip = mode.ip("203.0.113.7");
+    assert_eq!(ip.len(), 16);
+    assert_eq!(mode.ip("203.0.113.7"), ip);
+    assert_ne!(mode.ip("203.0.113.8"), ip);
+
+    // A different salt gives unrelated hashes
+    assert_ne!(hashed("salt").email("jane@example.com"), email);
+}
+
+#[test]
+fn test_dispatched_events_are_redacted() {
+    let sink = Arc::new(CapturingSink::default());
+    add_security_event_sink(sink.clone());
+    set_pii_mode(PiiMode::Masked);
+
+    log_security_event(
+        "login_failed",
+        "198.51.100.23",
+        None,
+        None,
+        Some("jane@example.com"),
+        false,
+        None,
+    );
+
+    let events = sink.events.lock().unwrap();
+    let event = events
+        .iter()
+        .find(|event| event.event_type == "login_failed")
+        .unwrap();
+    assert_eq!(event.ip_address, "198.51.100.0");
+    assert_eq!(event.email.as_deref(), Some("j***@example.com"));
+}
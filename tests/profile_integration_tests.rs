@@ -37,7 +37,7 @@ async fn create_test_app() -> axum::Router {
             .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
             .layer(PropagateRequestIdLayer::x_request_id())
             .layer(TraceLayer::new_for_http())
-            .layer(SecurityHeadersLayer)
+            .layer(SecurityHeadersLayer::default())
             .layer(get_cors_layer())
             .layer(MockConnectInfo(
                 "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
@@ -188,7 +188,7 @@ async fn test_update_profile_name_only() {
 }
 
 #[tokio::test]
-async fn test_update_profile_email_with_password() {
+async fn test_change_email_with_password() {
     let app = create_test_app().await;
     let test_email = "profileupdate2@example.com";
     let new_email = "updated2@example.com";
@@ -198,15 +198,15 @@ async fn test_update_profile_email_with_password() {
     register_test_user(&app, test_email, test_password).await;
     let access_token = login_test_user(&app, test_email, test_password).await;
 
-    // Update profile - email change with current password
+    // Request an email change with the current password
     let update_request = Request::builder()
-        .uri("/api/auth/profile")
-        .method("PUT")
+        .uri("/api/auth/change-email")
+        .method("POST")
         .header("content-type", "application/json")
         .header("authorization", format!("Bearer {}", access_token))
         .body(Body::from(
             json!({
-                "email": new_email,
+                "new_email": new_email,
                 "current_password": test_password
             })
             .to_string(),
@@ -221,11 +221,12 @@ async fn test_update_profile_email_with_password() {
         .unwrap();
     let response_json: Value = serde_json::from_slice(&body).unwrap();
 
-    assert_eq!(response_json["email"].as_str().unwrap(), new_email);
+    // The new address has to be confirmed before it replaces the old one
+    assert_eq!(response_json["pending_email"].as_str().unwrap(), new_email);
 }
 
 #[tokio::test]
-async fn test_update_profile_email_without_password() {
+async fn test_change_email_without_password() {
     let app = create_test_app().await;
     let test_email = "profileupdate3@example.com";
     let new_email = "updated3@example.com";
@@ -235,15 +236,16 @@ async fn test_update_profile_email_without_password() {
     register_test_user(&app, test_email, test_password).await;
     let access_token = login_test_user(&app, test_email, test_password).await;
 
-    // Update profile - email change without current password (should fail)
+    // Request an email change without the current password (should fail)
     let update_request = Request::builder()
-        .uri("/api/auth/profile")
-        .method("PUT")
+        .uri("/api/auth/change-email")
+        .method("POST")
         .header("content-type", "application/json")
         .header("authorization", format!("Bearer {}", access_token))
         .body(Body::from(
             json!({
-                "email": new_email
+                "new_email": new_email,
+                "current_password": ""
             })
             .to_string(),
         ))
@@ -257,16 +259,15 @@ async fn test_update_profile_email_without_password() {
         .unwrap();
     let response_json: Value = serde_json::from_slice(&body).unwrap();
 
-    assert!(
-        response_json["error"]
-            .as_str()
-            .unwrap()
-            .contains("Current password is required")
+    assert_eq!(response_json["error"]["code"], "VALIDATION_FAILED");
+    assert_eq!(
+        response_json["error"]["details"][0]["field"],
+        "current_password"
     );
 }
 
 #[tokio::test]
-async fn test_update_profile_email_wrong_password() {
+async fn test_change_email_wrong_password() {
     let app = create_test_app().await;
     let test_email = "profileupdate4@example.com";
     let new_email = "updated4@example.com";
@@ -277,15 +278,15 @@ async fn test_update_profile_email_wrong_password() {
     register_test_user(&app, test_email, test_password).await;
     let access_token = login_test_user(&app, test_email, test_password).await;
 
-    // Update profile - email change with wrong password
+    // Request an email change with the wrong password
     let update_request = Request::builder()
-        .uri("/api/auth/profile")
-        .method("PUT")
+        .uri("/api/auth/change-email")
+        .method("POST")
         .header("content-type", "application/json")
         .header("authorization", format!("Bearer {}", access_token))
         .body(Body::from(
             json!({
-                "email": new_email,
+                "new_email": new_email,
                 "current_password": wrong_password
             })
             .to_string(),
@@ -301,7 +302,7 @@ async fn test_update_profile_email_wrong_password() {
     let response_json: Value = serde_json::from_slice(&body).unwrap();
 
     assert!(
-        response_json["error"]
+        response_json["error"]["message"]
             .as_str()
             .unwrap()
             .contains("Current password is incorrect")
@@ -309,7 +310,7 @@ async fn test_update_profile_email_wrong_password() {
 }
 
 #[tokio::test]
-async fn test_update_profile_invalid_email() {
+async fn test_change_email_invalid_email() {
     let app = create_test_app().await;
     let test_email = "profileupdate5@example.com";
     let test_password = "TestPass123!";
@@ -318,15 +319,16 @@ async fn test_update_profile_invalid_email() {
     register_test_user(&app, test_email, test_password).await;
     let access_token = login_test_user(&app, test_email, test_password).await;
 
-    // Update profile - invalid email format
+    // Request an email change to an invalid address
     let update_request = Request::builder()
-        .uri("/api/auth/profile")
-        .method("PUT")
+        .uri("/api/auth/change-email")
+        .method("POST")
         .header("content-type", "application/json")
         .header("authorization", format!("Bearer {}", access_token))
         .body(Body::from(
             json!({
-                "email": "invalid-email-format"
+                "new_email": "invalid-email-format",
+                "current_password": test_password
             })
             .to_string(),
         ))
@@ -341,11 +343,12 @@ async fn test_update_profile_invalid_email() {
     let response_json: Value = serde_json::from_slice(&body).unwrap();
 
     assert!(
-        response_json["error"]
+        response_json["error"]["message"]
             .as_str()
             .unwrap()
             .contains("Validation failed")
     );
+    assert_eq!(response_json["error"]["details"][0]["field"], "new_email");
 }
 
 #[tokio::test]
@@ -426,7 +429,7 @@ async fn test_change_password_wrong_current() {
     let response_json: Value = serde_json::from_slice(&body).unwrap();
 
     assert!(
-        response_json["error"]
+        response_json["error"]["message"]
             .as_str()
             .unwrap()
             .contains("Current password is incorrect")
@@ -466,8 +469,12 @@ async fn test_change_password_same_password() {
         .unwrap();
     let response_json: Value = serde_json::from_slice(&body).unwrap();
 
+    assert_eq!(
+        response_json["error"]["details"][0]["field"],
+        "new_password"
+    );
     assert!(
-        response_json["error"]
+        response_json["error"]["details"][0]["message"]
             .as_str()
             .unwrap()
             .contains("New password must be different")
@@ -509,13 +516,76 @@ async fn test_change_password_weak_new_password() {
     let response_json: Value = serde_json::from_slice(&body).unwrap();
 
     assert!(
-        response_json["error"]
+        response_json["error"]["message"]
             .as_str()
             .unwrap()
             .contains("Validation failed")
     );
 }
 
+async fn change_password(
+    app: &axum::Router,
+    access_token: &str,
+    current_password: &str,
+    new_password: &str,
+) -> (StatusCode, Value) {
+    let change_request = Request::builder()
+        .uri("/api/auth/change-password")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::from(
+            json!({
+                "current_password": current_password,
+                "new_password": new_password
+            })
+            .to_string(),
+        ))
+        .unwrap();
+
+    let response = app.clone().oneshot(change_request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_change_password_rejects_recent_password() {
+    let app = create_test_app().await;
+    let test_email = "passwordhistory@example.com";
+    let first_password = "FirstPass123!";
+    let second_password = "SecondPass123!";
+    let third_password = "ThirdPass123!";
+    let fresh_password = "FreshPass123!";
+
+    register_test_user(&app, test_email, first_password).await;
+    let access_token = login_test_user(&app, test_email, first_password).await;
+
+    // Change the password twice
+    let (status, _) = change_password(&app, &access_token, first_password, second_password).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = change_password(&app, &access_token, second_password, third_password).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Going back to an earlier password is rejected
+    let (status, response_json) =
+        change_password(&app, &access_token, third_password, first_password).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(
+        response_json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("recent passwords")
+    );
+
+    // A password that was never used is accepted
+    let (status, _) = change_password(&app, &access_token, third_password, fresh_password).await;
+    assert_eq!(status, StatusCode::OK);
+    login_test_user(&app, test_email, fresh_password).await;
+}
+
 #[tokio::test]
 async fn test_profile_endpoints_unauthorized() {
     let app = create_test_app().await;
@@ -528,6 +598,11 @@ async fn test_profile_endpoints_unauthorized() {
             "/api/auth/change-password",
             json!({"current_password": "test", "new_password": "test"}),
         ),
+        (
+            "DELETE",
+            "/api/auth/account",
+            json!({"current_password": "test"}),
+        ),
     ];
 
     for (method, uri, body) in requests {
@@ -548,3 +623,224 @@ async fn test_profile_endpoints_unauthorized() {
         );
     }
 }
+
+async fn delete_account(
+    app: &axum::Router,
+    access_token: &str,
+    current_password: &str,
+) -> StatusCode {
+    let delete_request = Request::builder()
+        .uri("/api/auth/account")
+        .method("DELETE")
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::from(
+            json!({ "current_password": current_password }).to_string(),
+        ))
+        .unwrap();
+
+    app.clone().oneshot(delete_request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn test_delete_account_success() {
+    let app = create_test_app().await;
+    let pool = setup_test_pool().await;
+    let test_email = format!("deleteaccount-{}@example.com", Uuid::new_v4());
+    let test_password = "TestPass123!";
+
+    let (user_id, _email) = register_test_user(&app, &test_email, test_password).await;
+    let user_id = Uuid::parse_str(&user_id).unwrap();
+
+    // Log in directly to keep hold of the refresh token
+    let login_request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": test_email, "password": test_password }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(login_request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let tokens: Value = serde_json::from_slice(&body).unwrap();
+    let access_token = tokens["tokens"]["access_token"].as_str().unwrap();
+    let refresh_token = tokens["tokens"]["refresh_token"].as_str().unwrap();
+
+    assert_eq!(
+        delete_account(&app, access_token, test_password).await,
+        StatusCode::NO_CONTENT
+    );
+
+    // The account and its refresh tokens are gone
+    let users = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE id = $1", user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, Some(0));
+    let refresh_tokens = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = $1",
+        user_id
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(refresh_tokens, Some(0));
+    let login_attempts = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM login_attempts WHERE email = $1",
+        test_email
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(login_attempts, Some(0));
+
+    // The old refresh token can no longer be used
+    let refresh_request = Request::builder()
+        .uri("/api/auth/refresh")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "refresh_token": refresh_token }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(refresh_request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Logging in again fails as if the account never existed
+    let login_request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": test_email, "password": test_password }).to_string(),
+        ))
+        .unwrap();
+    let response = app.oneshot(login_request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let response_json: Value = serde_json::from_slice(&body).unwrap();
+    assert!(
+        response_json["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Invalid email or password")
+    );
+}
+
+#[tokio::test]
+async fn test_delete_account_wrong_password() {
+    let app = create_test_app().await;
+    let pool = setup_test_pool().await;
+    let test_email = format!("deleteaccountwrong-{}@example.com", Uuid::new_v4());
+    let test_password = "TestPass123!";
+
+    let (user_id, _email) = register_test_user(&app, &test_email, test_password).await;
+    let user_id = Uuid::parse_str(&user_id).unwrap();
+    let access_token = login_test_user(&app, &test_email, test_password).await;
+
+    assert_eq!(
+        delete_account(&app, &access_token, "WrongPass123!").await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // Nothing was removed
+    let users = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE id = $1", user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, Some(1));
+    login_test_user(&app, &test_email, test_password).await;
+}
+
+#[tokio::test]
+async fn test_export_account_data() {
+    let app = create_test_app().await;
+    let test_email = format!("exportaccount-{}@example.com", Uuid::new_v4());
+    let test_password = "TestPass123!";
+
+    let (user_id, _email) = register_test_user(&app, &test_email, test_password).await;
+    let access_token = login_test_user(&app, &test_email, test_password).await;
+
+    let export_request = Request::builder()
+        .uri("/api/auth/account/export")
+        .method("GET")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(export_request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-disposition"],
+        "attachment; filename=\"chronos-export.json\""
+    );
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let raw = String::from_utf8(body.to_vec()).unwrap();
+    let export: Value = serde_json::from_str(&raw).unwrap();
+
+    assert_eq!(export["profile"]["id"].as_str().unwrap(), user_id);
+    assert_eq!(export["profile"]["email"].as_str().unwrap(), test_email);
+    assert_eq!(export["sessions"].as_array().unwrap().len(), 1);
+    assert!(
+        export["login_attempts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|attempt| attempt["success"] == true)
+    );
+
+    // No secrets anywhere in the document
+    assert!(!raw.contains("password_hash"));
+    assert!(!raw.contains("$argon2"));
+    assert!(!raw.contains("token_hash"));
+    assert!(!raw.contains("jti"));
+    assert!(!raw.contains(&access_token));
+}
+
+#[tokio::test]
+async fn test_name_with_html_characters_is_stored_intact() {
+    let app = create_test_app().await;
+    let pool = setup_test_pool().await;
+    let test_email = format!("rawname-{}@example.com", Uuid::new_v4());
+    let test_password = "TestPass123!";
+    let name = "Procter & Gamble <R&D>";
+
+    register_test_user(&app, &test_email, test_password).await;
+    let access_token = login_test_user(&app, &test_email, test_password).await;
+
+    let update_request = Request::builder()
+        .uri("/api/auth/profile")
+        .method("PUT")
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::from(json!({ "name": name }).to_string()))
+        .unwrap();
+    let response = app.oneshot(update_request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Names are reflected only as JSON, which browsers must not sniff as HTML
+    let headers = response.headers();
+    assert_eq!(headers["content-type"], "application/json");
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let response_json: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(response_json["name"].as_str().unwrap(), name);
+
+    let stored = sqlx::query_scalar!("SELECT first_name FROM users WHERE email = $1", test_email)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(stored.as_deref(), Some(name));
+}
@@ -1,4 +1,4 @@
-use chronos::app::models::auth::{ChangePasswordRequest, ProfileUpdateRequest};
+use chronos::app::models::auth::{ChangeEmailRequest, ChangePasswordRequest, ProfileUpdateRequest};
 use chronos::app::models::user::User;
 use time::OffsetDateTime;
 use uuid::Uuid;
@@ -14,7 +14,9 @@ mod profile_tests {
             id: Uuid::new_v4(),
             name: Some("Test User".to_string()),
             email: "test@example.com".to_string(),
+            username: None,
             password_hash: "$argon2id$v=19$m=19456,t=2,p=1$test$hash".to_string(),
+            password_changed_at: None,
             created_at: Some(OffsetDateTime::now_utc()),
             updated_at: Some(OffsetDateTime::now_utc()),
         }
@@ -25,24 +27,8 @@ mod profile_tests {
         let valid_requests = vec![
             ProfileUpdateRequest {
                 name: Some("John Doe".to_string()),
-                email: Some("john.doe@example.com".to_string()),
-                current_password: Some("OldPassword1!".to_string()),
-            },
-            ProfileUpdateRequest {
-                name: Some("Jane Smith".to_string()),
-                email: None,
-                current_password: None,
-            },
-            ProfileUpdateRequest {
-                name: None,
-                email: Some("jane.smith@example.com".to_string()),
-                current_password: Some("CurrentPass1!".to_string()),
-            },
-            ProfileUpdateRequest {
-                name: None,
-                email: None,
-                current_password: None,
             },
+            ProfileUpdateRequest { name: None },
         ];
 
         for request in valid_requests {
@@ -54,26 +40,23 @@ mod profile_tests {
     }
 
     #[test]
-    fn test_profile_update_request_validation_invalid_email() {
-        let invalid_requests = vec![
-            ProfileUpdateRequest {
-                name: Some("John Doe".to_string()),
-                email: Some("invalid-email".to_string()),
-                current_password: Some("Password1!".to_string()),
-            },
-            ProfileUpdateRequest {
-                name: Some("Jane Smith".to_string()),
-                email: Some("@example.com".to_string()),
-                current_password: Some("Password1!".to_string()),
-            },
-            ProfileUpdateRequest {
-                name: Some("Bob Johnson".to_string()),
-                email: Some("user@".to_string()),
-                current_password: Some("Password1!".to_string()),
-            },
-        ];
+    fn test_change_email_request_validation_valid() {
+        let request = ChangeEmailRequest {
+            new_email: "john.doe@example.com".to_string(),
+            current_password: "CurrentPass1!".to_string(),
+        };
+        assert!(request.validate().is_ok());
+    }
 
-        for request in invalid_requests {
+    #[test]
+    fn test_change_email_request_validation_invalid_email() {
+        let invalid_emails = vec!["invalid-email", "@example.com", "user@"];
+
+        for new_email in invalid_emails {
+            let request = ChangeEmailRequest {
+                new_email: new_email.to_string(),
+                current_password: "Password1!".to_string(),
+            };
             assert!(
                 request.validate().is_err(),
                 "Invalid email should fail validation"
@@ -81,6 +64,16 @@ mod profile_tests {
         }
     }
 
+    #[test]
+    fn test_change_email_request_requires_current_password() {
+        let request = ChangeEmailRequest {
+            new_email: "new@example.com".to_string(),
+            current_password: String::new(),
+        };
+        let errors = request.validate().unwrap_err();
+        assert!(errors.field_errors().contains_key("current_password"));
+    }
+
     #[test]
     fn test_change_password_request_validation_valid() {
         let valid_requests = vec![
@@ -229,56 +222,4 @@ mod profile_tests {
             "Same password should produce different hashes due to random salt"
         );
     }
-
-    #[test]
-    fn test_profile_update_email_change_logic() {
-        // This test verifies the logic for determining when email changes require password verification
-        let current_email = "current@example.com".to_string();
-
-        // Test cases where email change is detected
-        let email_changing_requests = vec![
-            ProfileUpdateRequest {
-                name: None,
-                email: Some("new@example.com".to_string()),
-                current_password: Some("Password1!".to_string()),
-            },
-            ProfileUpdateRequest {
-                name: Some("Test User".to_string()),
-                email: Some("different@example.com".to_string()),
-                current_password: Some("Password1!".to_string()),
-            },
-        ];
-
-        for request in email_changing_requests {
-            let changing_email =
-                request.email.is_some() && request.email != Some(current_email.clone());
-            assert!(
-                changing_email,
-                "Should detect email change when new email is different"
-            );
-        }
-
-        // Test cases where email is not changing
-        let non_changing_requests = vec![
-            ProfileUpdateRequest {
-                name: Some("Test User".to_string()),
-                email: None,
-                current_password: None,
-            },
-            ProfileUpdateRequest {
-                name: Some("Test User".to_string()),
-                email: Some(current_email.clone()),
-                current_password: None,
-            },
-        ];
-
-        for request in non_changing_requests {
-            let changing_email =
-                request.email.is_some() && request.email != Some(current_email.clone());
-            assert!(
-                !changing_email,
-                "Should not detect email change when email is same or None"
-            );
-        }
-    }
 }
@@ -0,0 +1,261 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::middleware::security::{SecurityHeadersLayer, get_cors_layer};
+use chronos::app::repositories::admin_audit_repository::AdminAuditRepository;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceBuilder;
+use tower::ServiceExt;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "RolePass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: PgPool) -> axum::Router {
+    routes::create_router(pool).layer(
+        ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(TraceLayer::new_for_http())
+            .layer(SecurityHeadersLayer::default())
+            .layer(get_cors_layer())
+            .layer(MockConnectInfo(
+                "192.168.1.50:8080".parse::<SocketAddr>().unwrap(),
+            )),
+    )
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+// Register a user with a unique email and return its id
+async fn register_user(app: &axum::Router, prefix: &str) -> (Uuid, String) {
+    let email = format!("{}_{}@example.com", prefix, Uuid::new_v4().simple());
+    let request = Request::builder()
+        .uri("/api/auth/register")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": TEST_PASSWORD }).to_string(),
+        ))
+        .unwrap();
+
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let user_id = Uuid::parse_str(body["user"]["id"].as_str().unwrap()).unwrap();
+    (user_id, email)
+}
+
+async fn login(app: &axum::Router, email: &str) -> Value {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": TEST_PASSWORD }).to_string(),
+        ))
+        .unwrap();
+
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body
+}
+
+fn role_request(method: &str, user_id: Uuid, access_token: &str, role: &str) -> Request<Body> {
+    Request::builder()
+        .uri(format!("/api/admin/users/{}/roles", user_id))
+        .method(method)
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::from(json!({ "role": role }).to_string()))
+        .unwrap()
+}
+
+fn password_reset_request(user_id: Uuid, access_token: &str) -> Request<Body> {
+    Request::builder()
+        .uri(format!("/api/admin/users/{}/password-reset", user_id))
+        .method("POST")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn make_admin(pool: &PgPool, user_id: Uuid) {
+    let role_repository = RoleRepository::new(pool.clone());
+    let admin_role = role_repository
+        .find_by_name("admin")
+        .await
+        .unwrap()
+        .expect("admin role should be seeded");
+    role_repository
+        .grant_role(user_id, admin_role.id)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_non_admin_is_forbidden_from_admin_routes() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool);
+
+    let (user_id, email) = register_user(&app, "rbac_regular").await;
+    let login_data = login(&app, &email).await;
+    let access_token = login_data["tokens"]["access_token"].as_str().unwrap();
+
+    let (status, body) = send(&app, role_request("POST", user_id, access_token, "admin")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body["error"]["code"], "INSUFFICIENT_PERMISSIONS");
+
+    let (status, _) = send(&app, role_request("DELETE", user_id, access_token, "admin")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(&app, password_reset_request(user_id, access_token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_admin_routes_require_authentication() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool);
+
+    let request = Request::builder()
+        .uri(format!("/api/admin/users/{}/roles", Uuid::new_v4()))
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "role": "admin" }).to_string()))
+        .unwrap();
+
+    let (status, _) = send(&app, request).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_admin_can_grant_and_revoke_roles() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+
+    let (admin_id, admin_email) = register_user(&app, "rbac_admin").await;
+    let (target_id, target_email) = register_user(&app, "rbac_target").await;
+    make_admin(&pool, admin_id).await;
+
+    // Roles are read from the database when the token is issued
+    let login_data = login(&app, &admin_email).await;
+    let access_token = login_data["tokens"]["access_token"].as_str().unwrap();
+
+    let (status, body) = send(&app, role_request("POST", target_id, access_token, "admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user_id"], target_id.to_string());
+    assert_eq!(body["roles"], json!(["admin"]));
+
+    // The promoted user can now reach the admin routes with a fresh token
+    let target_login = login(&app, &target_email).await;
+    let target_token = target_login["tokens"]["access_token"].as_str().unwrap();
+    let (status, _) = send(&app, role_request("POST", target_id, target_token, "user")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(
+        &app,
+        role_request("DELETE", target_id, access_token, "admin"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["roles"], json!(["user"]));
+
+    // Revoking a role the user does not hold
+    let (status, _) = send(
+        &app,
+        role_request("DELETE", target_id, access_token, "admin"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Unknown roles are rejected
+    let (status, body) = send(
+        &app,
+        role_request("POST", target_id, access_token, "superuser"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"], "Role not found");
+}
+
+#[tokio::test]
+async fn test_admin_can_reset_user_password() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone());
+
+    let (admin_id, admin_email) = register_user(&app, "rbac_reset_admin").await;
+    let (target_id, target_email) = register_user(&app, "rbac_reset_target").await;
+    make_admin(&pool, admin_id).await;
+
+    let login_data = login(&app, &admin_email).await;
+    let access_token = login_data["tokens"]["access_token"].as_str().unwrap();
+
+    let (status, body) = send(&app, password_reset_request(target_id, access_token)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user_id"], target_id.to_string());
+    let reset_token = body["reset_token"].as_str().unwrap();
+
+    // The issued token works with the regular reset flow
+    let new_password = "AdminReset456!";
+    let request = Request::builder()
+        .uri("/api/auth/reset-password")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "token": reset_token, "password": new_password }).to_string(),
+        ))
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": target_email, "password": new_password }).to_string(),
+        ))
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The action is recorded against the admin who performed it
+    let entries = AdminAuditRepository::new(pool)
+        .find_by_target_user(target_id)
+        .await
+        .unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].admin_id, admin_id);
+    assert_eq!(entries[0].action, "admin_password_reset");
+
+    // Unknown users are rejected
+    let (status, _) = send(&app, password_reset_request(Uuid::new_v4(), access_token)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,84 @@
+//! Runs against a real Redis: `REDIS_URL=redis://localhost:6379 cargo test --features redis`
+#![cfg(feature = "redis")]
+
+use chronos::app::middleware::rate_limiter::{RateLimiter, RedisRateLimiter};
+use chronos::app::middleware::security::{SecurityState, check_registration_rate_limit};
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+fn create_limiter() -> RedisRateLimiter {
+    dotenvy::dotenv().ok();
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
+
+    // A unique prefix keeps repeated runs from seeing each other's counters
+    RedisRateLimiter::new(&redis_url)
+        .expect("Invalid REDIS_URL")
+        .with_key_prefix(&format!("chronos:test:{}:", Uuid::new_v4()))
+}
+
+#[tokio::test]
+async fn test_redis_rate_limiter_sliding_window() {
+    let limiter = create_limiter();
+    let window = Duration::from_millis(500);
+
+    for i in 0..3 {
+        assert!(
+            limiter
+                .check("window", 3, window)
+                .await
+                .unwrap()
+                .is_allowed(),
+            "Hit {} should be allowed",
+            i + 1
+        );
+    }
+    assert!(
+        !limiter
+            .check("window", 3, window)
+            .await
+            .unwrap()
+            .is_allowed()
+    );
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert!(
+        limiter
+            .check("window", 3, window)
+            .await
+            .unwrap()
+            .is_allowed()
+    );
+}
+
+#[tokio::test]
+async fn test_redis_rate_limiter_shared_between_instances() {
+    let limiter = Arc::new(create_limiter());
+    // The security checks fail open, so make sure Redis is actually reachable first
+    limiter
+        .check("ping", 1, Duration::from_secs(1))
+        .await
+        .expect("Redis should be reachable");
+
+    let first_instance = SecurityState::default().with_rate_limiter(limiter.clone());
+    let second_instance = SecurityState::default().with_rate_limiter(limiter);
+
+    // Registration allows 5 attempts per IP, regardless of which instance sees them
+    for _ in 0..3 {
+        check_registration_rate_limit(&first_instance, "10.2.2.2")
+            .await
+            .unwrap();
+    }
+    for _ in 0..2 {
+        check_registration_rate_limit(&second_instance, "10.2.2.2")
+            .await
+            .unwrap();
+    }
+
+    assert!(
+        check_registration_rate_limit(&first_instance, "10.2.2.2")
+            .await
+            .is_err()
+    );
+}
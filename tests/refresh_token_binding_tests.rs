@@ -0,0 +1,275 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::ip_filter::IpNetwork;
+use chronos::app::middleware::security::{SecurityState, set_trusted_proxies};
+use chronos::app::models::jwt::JwtError;
+use chronos::app::models::login_attempt::{RefreshTokenBinding, TokenOrigin};
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "BindingPass123!";
+const BROWSER: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:130.0) Gecko/20100101 Firefox/130.0";
+const SCRIPT: &str = "python-requests/2.31.0";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_jwt_service(pool: &PgPool, binding: RefreshTokenBinding) -> JwtService {
+    JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_refresh_token_binding(binding)
+}
+
+// Wired like create_router does for REFRESH_TOKEN_BINDING
+fn create_test_app(pool: &PgPool, binding: RefreshTokenBinding) -> Router {
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    create_test_app_at(pool, binding, addr.parse().unwrap())
+}
+
+// Every request comes from `peer`, e.g. a proxy in front of the server
+fn create_test_app_at(pool: &PgPool, binding: RefreshTokenBinding, peer: SocketAddr) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = create_jwt_service(pool, binding);
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(peer))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("binding-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+// The refresh token from logging in with `user_agent`
+async fn login(app: &Router, email: &str, user_agent: &str) -> String {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("user-agent", user_agent)
+        .body(Body::from(
+            json!({ "email": email, "password": PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["refresh_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn refresh(app: &Router, refresh_token: &str, user_agent: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/refresh")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("user-agent", user_agent)
+        .body(Body::from(
+            json!({ "refresh_token": refresh_token }).to_string(),
+        ))
+        .unwrap();
+    send(app, request).await
+}
+
+#[tokio::test]
+async fn test_user_agent_binding_rejects_other_clients() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, RefreshTokenBinding::UserAgent);
+    let user = create_user(&pool).await;
+
+    let refresh_token = login(&app, &user.email, BROWSER).await;
+
+    // The browser that logged in keeps refreshing, and its new token stays bound
+    let (status, body) = refresh(&app, &refresh_token, BROWSER).await;
+    assert_eq!(status, StatusCode::OK);
+    let rotated = body["refresh_token"].as_str().unwrap().to_string();
+
+    let (status, body) = refresh(&app, &rotated, SCRIPT).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "TOKEN_REVOKED");
+
+    // The token is revoked, so the browser can't use it afterwards either
+    let (status, _) = refresh(&app, &rotated, BROWSER).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_refresh_is_unbound_when_binding_is_off() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, RefreshTokenBinding::Off);
+    let user = create_user(&pool).await;
+
+    let refresh_token = login(&app, &user.email, BROWSER).await;
+
+    let (status, body) = refresh(&app, &refresh_token, SCRIPT).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["refresh_token"].is_string());
+}
+
+#[tokio::test]
+async fn test_ip_subnet_binding_allows_the_same_network() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_jwt_service(&pool, RefreshTokenBinding::IpSubnet);
+    let user = create_user(&pool).await;
+    let origin = |ip: &str| TokenOrigin::new(Some(BROWSER.to_string()), Some(ip.to_string()));
+
+    let tokens = jwt_service
+        .generate_token_pair_from(
+            &user,
+            time::Duration::days(1),
+            None,
+            Vec::new(),
+            origin("203.0.113.10"),
+        )
+        .await
+        .unwrap();
+
+    // Another address in the same /24, even with a different user agent
+    let tokens = jwt_service
+        .refresh_with_rotation_from(
+            &tokens.refresh_token,
+            user.id,
+            &TokenOrigin::new(Some(SCRIPT.to_string()), Some("203.0.113.99".to_string())),
+        )
+        .await
+        .unwrap();
+
+    let result = jwt_service
+        .refresh_with_rotation_from(&tokens.refresh_token, user.id, &origin("198.51.100.7"))
+        .await;
+    assert!(matches!(result, Err(JwtError::BindingMismatch)));
+
+    // Tokens issued without an origin are not checked
+    let unbound = jwt_service
+        .generate_token_pair_for_client(&user, time::Duration::days(1), None, Vec::new())
+        .await
+        .unwrap();
+    let result = jwt_service
+        .refresh_with_rotation_from(&unbound.refresh_token, user.id, &origin("198.51.100.7"))
+        .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_ip_subnet_binding_uses_the_client_behind_a_trusted_proxy() {
+    let pool = setup_test_pool().await;
+    let bytes = Uuid::new_v4().into_bytes();
+    let proxy = format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2]);
+    set_trusted_proxies(vec![IpNetwork::parse(&proxy).unwrap()]);
+    let app = create_test_app_at(
+        &pool,
+        RefreshTokenBinding::IpSubnet,
+        format!("{}:8080", proxy).parse().unwrap(),
+    );
+    let user = create_user(&pool).await;
+    let forwarded = |uri: &str, client: &str, body: Value| {
+        Request::builder()
+            .uri(uri)
+            .method("POST")
+            .header("content-type", "application/json")
+            .header("user-agent", BROWSER)
+            .header("x-forwarded-for", client)
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let login = forwarded(
+        "/api/auth/login",
+        "203.0.113.10",
+        json!({ "email": user.email, "password": PASSWORD }),
+    );
+    let (status, body) = send(&app, login).await;
+    assert_eq!(status, StatusCode::OK);
+    let refresh_token = body["tokens"]["refresh_token"].as_str().unwrap();
+
+    // Bound to the client's network, not the proxy's, which every request shares
+    let refresh = forwarded(
+        "/api/auth/refresh",
+        "203.0.113.99",
+        json!({ "refresh_token": refresh_token }),
+    );
+    let (status, body) = send(&app, refresh).await;
+    assert_eq!(status, StatusCode::OK);
+    let rotated = body["refresh_token"].as_str().unwrap();
+
+    let refresh = forwarded(
+        "/api/auth/refresh",
+        "198.51.100.7",
+        json!({ "refresh_token": rotated }),
+    );
+    let (status, _) = send(&app, refresh).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
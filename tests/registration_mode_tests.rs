@@ -0,0 +1,172 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::{AuthService, RegistrationMode};
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// The auth routes with a fixed registration mode, plus the mailbox they send to
+fn create_test_app(pool: &PgPool, mode: RegistrationMode) -> (Router, MockEmailService) {
+    let user_repository = UserRepository::new(pool.clone());
+    let email_service = MockEmailService::new();
+
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        email_service.clone(),
+    )
+    .with_registration_mode(mode);
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+    let app = Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(
+            "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
+        ));
+
+    (app, email_service)
+}
+
+async fn register(app: &Router, email: &str, password: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/register")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({
+                "name": "Registration Test",
+                "email": email,
+                "password": password
+            })
+            .to_string(),
+        ))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+async fn count_users_with_email(pool: &PgPool, email: &str) -> i64 {
+    sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE email = $1", email)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .unwrap_or(0)
+}
+
+fn unique_email() -> String {
+    format!("register-{}@example.com", Uuid::new_v4())
+}
+
+#[tokio::test]
+async fn test_explicit_mode_rejects_duplicate_with_conflict() {
+    let pool = setup_test_pool().await;
+    let (app, emails) = create_test_app(&pool, RegistrationMode::Explicit);
+    let email = unique_email();
+
+    let (status, body) = register(&app, &email, "TestPass123!").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["user"]["email"], email.as_str());
+
+    let (status, body) = register(&app, &email, "OtherPass456!").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"]["code"], "EMAIL_IN_USE");
+    assert_eq!(body["error"]["message"], "Email already registered");
+
+    assert_eq!(count_users_with_email(&pool, &email).await, 1);
+    assert_eq!(emails.count_sent_emails(), 0);
+}
+
+#[tokio::test]
+async fn test_enumeration_safe_mode_answers_duplicates_like_new_registrations() {
+    let pool = setup_test_pool().await;
+    let (app, emails) = create_test_app(&pool, RegistrationMode::EnumerationSafe);
+    let email = unique_email();
+
+    let (new_status, new_body) = register(&app, &email, "TestPass123!").await;
+    let (duplicate_status, duplicate_body) = register(&app, &email, "OtherPass456!").await;
+
+    assert_eq!(new_status, StatusCode::ACCEPTED);
+    assert_eq!(duplicate_status, new_status);
+    assert_eq!(duplicate_body, new_body);
+    assert!(new_body.get("user").is_none());
+
+    // Only the first registration created an account, and its password was kept
+    assert_eq!(count_users_with_email(&pool, &email).await, 1);
+    let user: User = UserRepository::new(pool.clone())
+        .find_by_email(&email)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user.verify_password("TestPass123!").unwrap());
+
+    let sent = emails.get_sent_emails();
+    assert_eq!(sent.len(), 2);
+    assert!(sent.iter().all(|message| message.to == email));
+    assert_eq!(sent[0].subject, "Welcome to Chronos");
+    assert_eq!(sent[1].subject, "You already have a Chronos account");
+}
+
+#[tokio::test]
+async fn test_enumeration_safe_mode_still_rejects_invalid_requests() {
+    let pool = setup_test_pool().await;
+    let (app, emails) = create_test_app(&pool, RegistrationMode::EnumerationSafe);
+
+    let (status, _) = register(&app, &unique_email(), "weak").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(emails.count_sent_emails(), 0);
+}
@@ -0,0 +1,165 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::jwt::{LoginRequest, LoginResponse};
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use time::Duration;
+use uuid::Uuid;
+
+const PASSWORD: &str = "RememberPass123!";
+const DAY: usize = 24 * 60 * 60;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_services(pool: &PgPool, jwt_service: JwtService) -> SecureLoginService {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+
+    SecureLoginService::new(
+        auth_service,
+        jwt_service,
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+}
+
+fn create_jwt_service(pool: &PgPool) -> JwtService {
+    JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("remember-{}@example.com", Uuid::new_v4()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn login(service: &SecureLoginService, email: &str, remember_me: bool) -> LoginResponse {
+    let request = LoginRequest {
+        email: email.to_string(),
+        identifier: None,
+        password: PASSWORD.to_string(),
+        captcha_token: None,
+        remember_me,
+        device_name: None,
+        client_id: None,
+    };
+    let bytes = Uuid::new_v4().into_bytes();
+    let ip = format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2]);
+    service.secure_login(request, ip, None).await.unwrap()
+}
+
+// Seconds between the refresh token's iat and exp
+fn refresh_lifetime(jwt_service: &JwtService, refresh_token: &str) -> usize {
+    let claims = jwt_service
+        .decode_token_without_validation(refresh_token)
+        .unwrap();
+    claims.exp - claims.iat
+}
+
+#[tokio::test]
+async fn test_remember_me_extends_refresh_token() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_jwt_service(&pool);
+    let service = create_services(&pool, jwt_service.clone());
+    let user = create_user(&pool).await;
+
+    let regular = login(&service, &user.email, false).await.tokens;
+    assert_eq!(regular.refresh_expires_in, 7 * DAY);
+    assert_eq!(
+        refresh_lifetime(&jwt_service, &regular.refresh_token),
+        7 * DAY
+    );
+
+    let remembered = login(&service, &user.email, true).await.tokens;
+    assert_eq!(remembered.refresh_expires_in, 30 * DAY);
+    assert_eq!(
+        refresh_lifetime(&jwt_service, &remembered.refresh_token),
+        30 * DAY
+    );
+
+    // The access token is short-lived either way
+    assert_eq!(regular.expires_in, remembered.expires_in);
+}
+
+#[tokio::test]
+async fn test_remember_me_lifetime_is_configurable() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_jwt_service(&pool).with_remember_me_ttl(Duration::days(90));
+    let service = create_services(&pool, jwt_service.clone());
+    let user = create_user(&pool).await;
+
+    let tokens = login(&service, &user.email, true).await.tokens;
+    assert_eq!(tokens.refresh_expires_in, 90 * DAY);
+    assert_eq!(
+        refresh_lifetime(&jwt_service, &tokens.refresh_token),
+        90 * DAY
+    );
+}
+
+#[tokio::test]
+async fn test_remember_me_survives_rotation() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_jwt_service(&pool);
+    let service = create_services(&pool, jwt_service.clone());
+    let user = create_user(&pool).await;
+
+    let remembered = login(&service, &user.email, true).await.tokens;
+    let rotated = jwt_service
+        .refresh_with_rotation(&remembered.refresh_token, user.id)
+        .await
+        .unwrap();
+    assert_eq!(rotated.refresh_expires_in, 30 * DAY);
+    assert_eq!(
+        refresh_lifetime(&jwt_service, &rotated.refresh_token),
+        30 * DAY
+    );
+
+    let regular = login(&service, &user.email, false).await.tokens;
+    let rotated = jwt_service
+        .refresh_with_rotation(&regular.refresh_token, user.id)
+        .await
+        .unwrap();
+    assert_eq!(rotated.refresh_expires_in, 7 * DAY);
+}
+
+#[test]
+fn test_remember_me_defaults_to_off() {
+    let request: LoginRequest =
+        serde_json::from_str(r#"{"email": "a@example.com", "password": "x"}"#).unwrap();
+    assert!(!request.remember_me);
+}
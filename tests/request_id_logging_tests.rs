@@ -0,0 +1,143 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode},
+    routing::post,
+};
+use chronos::app::middleware::request_id::RequestIdScopeLayer;
+use chronos::app::middleware::security::log_security_event;
+use chronos::app::telemetry;
+use serde_json::Value;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tower::{ServiceBuilder, ServiceExt};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+// Everything the JSON layer writes, shared with the test
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    // One record per line; panics if a line is not a JSON object
+    fn records(&self) -> Vec<Value> {
+        let output = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+        output
+            .lines()
+            .map(|line| {
+                let record: Value = serde_json::from_str(line).unwrap();
+                assert!(record.is_object(), "not a JSON object: {}", line);
+                record
+            })
+            .collect()
+    }
+
+    fn security_events(&self) -> Vec<Value> {
+        self.records()
+            .into_iter()
+            .filter(|record| record["event_type"] == "login_failed")
+            .collect()
+    }
+}
+
+// The request id layers in the order build::web uses, around a handler that logs
+fn create_test_app() -> Router {
+    Router::new()
+        .route(
+            "/login",
+            post(|| async {
+                log_security_event(
+                    "login_failed",
+                    "10.0.0.1",
+                    Some("test-agent"),
+                    None,
+                    Some("user@example.com"),
+                    false,
+                    Some("Invalid credentials"),
+                );
+                StatusCode::UNAUTHORIZED
+            }),
+        )
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(RequestIdScopeLayer)
+                .layer(PropagateRequestIdLayer::x_request_id()),
+        )
+}
+
+async fn login(request_id: Option<&str>) -> Option<String> {
+    let mut request = Request::builder().uri("/login").method("POST");
+    if let Some(request_id) = request_id {
+        request = request.header("x-request-id", request_id);
+    }
+
+    let response = create_test_app()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    response
+        .headers()
+        .get("x-request-id")
+        .map(|value| value.to_str().unwrap().to_string())
+}
+
+#[tokio::test]
+async fn test_security_event_includes_client_request_id() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let _guard = tracing_subscriber::registry()
+        .with(telemetry::json_layer(move || writer.clone()))
+        .set_default();
+
+    login(Some("req-7f3a")).await;
+
+    let events = logs.security_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["request_id"], "req-7f3a");
+    assert_eq!(events[0]["ip_address"], "10.0.0.1");
+    assert_eq!(events[0]["level"], "WARN");
+    assert!(events[0]["timestamp"].is_string());
+}
+
+#[tokio::test]
+async fn test_generated_request_id_matches_response_header() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let _guard = tracing_subscriber::registry()
+        .with(telemetry::json_layer(move || writer.clone()))
+        .set_default();
+
+    let response_id = login(None).await.expect("x-request-id response header");
+
+    let events = logs.security_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["request_id"], response_id.as_str());
+}
+
+#[tokio::test]
+async fn test_security_event_outside_a_request_has_no_request_id() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let _guard = tracing_subscriber::registry()
+        .with(telemetry::json_layer(move || writer.clone()))
+        .set_default();
+
+    log_security_event("login_failed", "10.0.0.1", None, None, None, false, None);
+
+    let events = logs.security_events();
+    assert_eq!(events.len(), 1);
+    assert!(events[0].get("request_id").is_none());
+}
@@ -0,0 +1,145 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware,
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::rate_limiter::RateLimitConfig;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::middleware::timeout::request_timeout;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TIMEOUT: Duration = Duration::from_millis(100);
+const EMAIL_LATENCY: Duration = Duration::from_millis(400);
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// The auth routes behind a short deadline, with a mail provider slower than it
+fn create_test_app(pool: &PgPool, email_service: MockEmailService) -> Router {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        email_service,
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(UserRepository::new(pool.clone())),
+        SecurityState::new(RateLimitConfig::default()),
+    );
+
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(middleware::from_fn_with_state(TIMEOUT, request_timeout))
+        .layer(MockConnectInfo(
+            "192.168.13.2:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("timeout-{}@example.com", Uuid::new_v4().simple()),
+        "TimeoutPass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_slow_request_gets_504_and_still_completes() {
+    let pool = setup_test_pool().await;
+    let email_service = MockEmailService::new().with_latency(EMAIL_LATENCY);
+    let app = create_test_app(&pool, email_service.clone());
+    let user = create_user(&pool).await;
+
+    let request = Request::builder()
+        .uri("/api/auth/forgot-password")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(json!({ "email": user.email }).to_string()))
+        .unwrap();
+    let (status, body) = send(&app, request).await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["error"], "Request timed out");
+    assert_eq!(email_service.count_sent_emails(), 0);
+
+    // The handler was not dropped halfway: the email still goes out
+    for _ in 0..50 {
+        if email_service.count_sent_emails() > 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(email_service.count_sent_emails(), 1);
+    assert_eq!(email_service.get_last_sent_email().unwrap().to, user.email);
+}
+
+#[tokio::test]
+async fn test_fast_request_is_unaffected() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, MockEmailService::new());
+
+    let request = Request::builder()
+        .uri("/api/auth/csrf")
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_ne!(status, StatusCode::GATEWAY_TIMEOUT);
+}
@@ -0,0 +1,200 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::rate_limiter::{RateLimitConfig, RateLimitRule};
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::auth::AuthenticationError;
+use chronos::app::models::password_reset::PasswordResetToken;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::InMemoryPasswordHistoryStore;
+use chronos::app::repositories::password_reset_repository::{
+    InMemoryPasswordResetStore, PasswordResetStore,
+};
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::{InMemoryUserStore, UserRepository, UserStore};
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// An AuthService whose users and reset tokens live in memory
+async fn create_auth_service() -> (AuthService, InMemoryPasswordResetStore, User) {
+    let users = InMemoryUserStore::new();
+    let reset_tokens = InMemoryPasswordResetStore::new();
+    let user = User::new_with_params(
+        None,
+        format!("reset-{}@example.com", Uuid::new_v4()),
+        "ResetPass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    let user = users.create(&user).await.unwrap();
+
+    let auth_service = AuthService::new(
+        users,
+        reset_tokens.clone(),
+        InMemoryPasswordHistoryStore::new(),
+        MockEmailService::new(),
+    );
+    (auth_service, reset_tokens, user)
+}
+
+async fn issue_token(store: &InMemoryPasswordResetStore, user_id: Uuid) -> (String, Uuid) {
+    let plain_token = PasswordResetToken::generate_secure_token();
+    let token = PasswordResetToken::new(user_id, &plain_token).unwrap();
+    store.create(&token).await.unwrap();
+    (plain_token, token.id)
+}
+
+#[tokio::test]
+async fn test_valid_token_is_accepted_and_not_consumed() {
+    let (auth_service, reset_tokens, user) = create_auth_service().await;
+    let (plain_token, _) = issue_token(&reset_tokens, user.id).await;
+
+    assert!(
+        auth_service
+            .validate_reset_token(&plain_token)
+            .await
+            .is_ok()
+    );
+    assert!(
+        auth_service
+            .validate_reset_token(&plain_token)
+            .await
+            .is_ok()
+    );
+    assert_eq!(
+        reset_tokens
+            .find_valid_tokens_by_user_id(user.id)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_used_token_is_invalid() {
+    let (auth_service, reset_tokens, user) = create_auth_service().await;
+    let (plain_token, id) = issue_token(&reset_tokens, user.id).await;
+    reset_tokens.mark_as_used(id).await.unwrap();
+
+    let result = auth_service.validate_reset_token(&plain_token).await;
+    assert!(matches!(
+        result,
+        Err(AuthenticationError::PasswordResetTokenInvalid)
+    ));
+
+    let result = auth_service.validate_reset_token("not-a-token").await;
+    assert!(matches!(
+        result,
+        Err(AuthenticationError::PasswordResetTokenInvalid)
+    ));
+}
+
+#[tokio::test]
+async fn test_expired_token_is_reported_as_expired() {
+    let (auth_service, reset_tokens, user) = create_auth_service().await;
+    let plain_token = PasswordResetToken::generate_secure_token();
+    let mut token = PasswordResetToken::new(user.id, &plain_token).unwrap();
+    token.created_at = OffsetDateTime::now_utc() - time::Duration::hours(2);
+    token.expires_at = OffsetDateTime::now_utc() - time::Duration::hours(1);
+    reset_tokens.create(&token).await.unwrap();
+
+    let result = auth_service.validate_reset_token(&plain_token).await;
+    assert!(matches!(
+        result,
+        Err(AuthenticationError::ExpiredResetToken)
+    ));
+}
+
+fn create_test_app(pool: &PgPool, auth_service: AuthService) -> Router {
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let config = RateLimitConfig {
+        password_reset_validate: RateLimitRule::new(2, Duration::from_secs(60)),
+        ..RateLimitConfig::default()
+    };
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(UserRepository::new(pool.clone())),
+        SecurityState::new(config),
+    );
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()))
+}
+
+async fn validate(app: &Router, token: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(format!("/api/auth/reset-password/validate?token={}", token))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_validate_endpoint_is_rate_limited() {
+    let pool = setup_test_pool().await;
+    let (auth_service, reset_tokens, user) = create_auth_service().await;
+    let (plain_token, _) = issue_token(&reset_tokens, user.id).await;
+    let app = create_test_app(&pool, auth_service);
+
+    let (status, body) = validate(&app, &plain_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["valid"], true);
+
+    let (status, body) = validate(&app, "not-a-token").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_TOKEN");
+
+    // Even the right token is refused once the IP is over the limit
+    let (status, _) = validate(&app, &plain_token).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
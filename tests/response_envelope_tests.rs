@@ -0,0 +1,75 @@
+use axum::{
+    Json, Router,
+    body::Body,
+    http::{Request, StatusCode},
+    middleware,
+    routing::{delete, get},
+};
+use chronos::app::middleware::envelope::wrap_success_response;
+use chronos::app::models::auth::AuthenticationError;
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+fn routes() -> Router {
+    Router::new()
+        .route(
+            "/profile",
+            get(|| async { Json(json!({ "name": "Jane", "email": "jane@example.com" })) }),
+        )
+        .route(
+            "/missing",
+            get(|| async { AuthenticationError::UserNotFound }),
+        )
+        .route("/account", delete(|| async { StatusCode::NO_CONTENT }))
+}
+
+async fn call(app: Router, method: &str, uri: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-request-id", "req-123")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_responses_are_bare_by_default() {
+    let (status, body) = call(routes(), "GET", "/profile").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "name": "Jane", "email": "jane@example.com" }));
+}
+
+#[tokio::test]
+async fn test_success_is_wrapped_with_meta() {
+    let app = routes().layer(middleware::from_fn(wrap_success_response));
+    let (status, body) = call(app, "GET", "/profile").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"],
+        json!({ "name": "Jane", "email": "jane@example.com" })
+    );
+    assert_eq!(body["meta"]["request_id"], "req-123");
+    assert!(body["meta"]["timestamp"].is_string());
+}
+
+#[tokio::test]
+async fn test_errors_and_empty_responses_are_left_alone() {
+    let app = routes().layer(middleware::from_fn(wrap_success_response));
+
+    let (status, body) = call(app.clone(), "GET", "/missing").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"]["code"], "USER_NOT_FOUND");
+    assert!(body.get("data").is_none());
+
+    let (status, body) = call(app, "DELETE", "/account").await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(body, Value::Null);
+}
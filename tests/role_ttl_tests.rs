@@ -0,0 +1,111 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::role::ADMIN_ROLE;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::jwt_service::JwtService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::env;
+use time::Duration;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_jwt_service(pool: &PgPool) -> JwtService {
+    JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_role_repository(RoleRepository::new(pool.clone()))
+    .with_role_ttl_overrides(HashMap::from([(
+        ADMIN_ROLE.to_string(),
+        Duration::minutes(5),
+    )]))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("role-ttl-{}@example.com", Uuid::new_v4().simple()),
+        "RoleTtlPass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn make_admin(pool: &PgPool, user_id: Uuid) {
+    let role_repository = RoleRepository::new(pool.clone());
+    let admin_role = role_repository
+        .find_by_name(ADMIN_ROLE)
+        .await
+        .unwrap()
+        .expect("admin role should be seeded");
+    role_repository
+        .grant_role(user_id, admin_role.id)
+        .await
+        .unwrap();
+}
+
+// Seconds between the access token's iat and exp
+fn access_lifetime(jwt_service: &JwtService, access_token: &str) -> usize {
+    let claims = jwt_service
+        .decode_token_without_validation(access_token)
+        .unwrap();
+    claims.exp - claims.iat
+}
+
+#[tokio::test]
+async fn test_admin_access_token_is_shorter_lived() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_jwt_service(&pool);
+    let user = create_user(&pool).await;
+    let admin = create_user(&pool).await;
+    make_admin(&pool, admin.id).await;
+
+    let user_tokens = jwt_service.generate_token_pair(&user).await.unwrap();
+    let admin_tokens = jwt_service.generate_token_pair(&admin).await.unwrap();
+
+    let user_lifetime = access_lifetime(&jwt_service, &user_tokens.access_token);
+    let admin_lifetime = access_lifetime(&jwt_service, &admin_tokens.access_token);
+    assert!(admin_lifetime < user_lifetime);
+    assert_eq!(admin_lifetime, 5 * 60);
+    assert_eq!(user_lifetime, 15 * 60);
+
+    // expires_in reports the lifetime each token actually got
+    assert_eq!(admin_tokens.expires_in, admin_lifetime);
+    assert_eq!(user_tokens.expires_in, user_lifetime);
+}
+
+#[tokio::test]
+async fn test_shortest_role_ttl_applies() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_jwt_service(&pool).with_role_ttl_overrides(HashMap::from([
+        ("user".to_string(), Duration::minutes(10)),
+        (ADMIN_ROLE.to_string(), Duration::minutes(5)),
+    ]));
+
+    let roles = ["user".to_string(), ADMIN_ROLE.to_string()];
+    assert_eq!(jwt_service.access_ttl(&roles), Duration::minutes(5));
+    assert_eq!(jwt_service.access_ttl(&roles[..1]), Duration::minutes(10));
+    // Roles without an override keep the default
+    assert_eq!(
+        jwt_service.access_ttl(&["auditor".to_string()]),
+        Duration::minutes(15)
+    );
+}
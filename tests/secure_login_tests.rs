@@ -1,10 +1,11 @@
-use chronos::app::models::auth::AuthError;
+use chronos::app::models::auth::AuthenticationError;
 use chronos::app::models::jwt::{LoginRequest, LoginResponse};
 use chronos::app::models::login_attempt::{AccountLockout, LoginAttempt, RefreshTokenStorage};
 use chronos::app::models::user::User;
 use chronos::app::repositories::login_attempt_repository::{
     AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
 };
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
 use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
 use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
 use chronos::app::repositories::user_repository::UserRepository;
@@ -32,13 +33,19 @@ async fn setup_test_pool() -> PgPool {
 async fn setup_secure_login_service(pool: PgPool) -> SecureLoginService {
     let user_repository = UserRepository::new(pool.clone());
     let password_reset_repository = PasswordResetRepository::new(pool.clone());
+    let password_history_repository = PasswordHistoryRepository::new(pool.clone());
     let token_blacklist_repository = TokenBlacklistRepository::new(pool.clone());
     let login_attempt_repository = LoginAttemptRepository::new(pool.clone());
     let account_lockout_repository = AccountLockoutRepository::new(pool.clone());
     let refresh_token_repository = RefreshTokenRepository::new(pool.clone());
     let email_service = MockEmailService::new();
 
-    let auth_service = AuthService::new(user_repository, password_reset_repository, email_service);
+    let auth_service = AuthService::new(
+        user_repository,
+        password_reset_repository,
+        password_history_repository,
+        email_service,
+    );
     let jwt_service = JwtService::new(
         "test-secret-key",
         token_blacklist_repository,
@@ -76,7 +83,12 @@ async fn test_successful_login() {
 
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "SecurePassword123!".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
@@ -107,7 +119,12 @@ async fn test_invalid_credentials() {
 
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "WrongPassword".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
@@ -120,7 +137,7 @@ async fn test_invalid_credentials() {
 
     assert!(result.is_err());
     let error = result.unwrap_err();
-    assert_eq!(error.error, "Invalid email or password");
+    assert!(matches!(error, AuthenticationError::InvalidCredentials));
 
     // Cleanup
     let user_repository = UserRepository::new(pool);
@@ -134,7 +151,12 @@ async fn test_nonexistent_user() {
 
     let request = LoginRequest {
         email: "nonexistent@example.com".to_string(),
+        identifier: None,
         password: "AnyPassword".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
@@ -147,7 +169,7 @@ async fn test_nonexistent_user() {
 
     assert!(result.is_err());
     let error = result.unwrap_err();
-    assert_eq!(error.error, "Invalid email or password");
+    assert!(matches!(error, AuthenticationError::InvalidCredentials));
 }
 
 #[tokio::test]
@@ -163,7 +185,12 @@ async fn test_ip_rate_limiting() {
     for _ in 0..5 {
         let request = LoginRequest {
             email: user.email.clone(),
+            identifier: None,
             password: "WrongPassword".to_string(),
+            captcha_token: None,
+            remember_me: false,
+            device_name: None,
+            client_id: None,
         };
 
         let _ = service
@@ -174,7 +201,12 @@ async fn test_ip_rate_limiting() {
     // 6th attempt should be rate limited
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "WrongPassword".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service.secure_login(request, ip_address, user_agent).await;
@@ -182,7 +214,7 @@ async fn test_ip_rate_limiting() {
     assert!(result.is_err());
     let error = result.unwrap_err();
     assert_eq!(
-        error.error,
+        error.to_string(),
         "Too many failed login attempts. Please try again later."
     );
 
@@ -203,7 +235,12 @@ async fn test_account_lockout() {
     for i in 0..10 {
         let request = LoginRequest {
             email: user.email.clone(),
+            identifier: None,
             password: "WrongPassword".to_string(),
+            captcha_token: None,
+            remember_me: false,
+            device_name: None,
+            client_id: None,
         };
 
         let result = service
@@ -221,7 +258,12 @@ async fn test_account_lockout() {
     // 11th attempt should indicate account lockout
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "WrongPassword".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
@@ -230,7 +272,11 @@ async fn test_account_lockout() {
 
     assert!(result.is_err());
     let error = result.unwrap_err();
-    assert!(error.error.contains("Account has been temporarily locked"));
+    assert!(
+        error
+            .to_string()
+            .contains("Account has been temporarily locked")
+    );
 
     // Cleanup
     let user_repository = UserRepository::new(pool);
@@ -253,7 +299,12 @@ async fn test_locked_account_rejects_valid_credentials() {
 
     let request = LoginRequest {
         email: user.email.clone(),
-        password: "SecurePassword123!".to_string(), // Correct password
+        identifier: None,
+        password: "SecurePassword123!".to_string(), // Correct password,
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
@@ -266,7 +317,7 @@ async fn test_locked_account_rejects_valid_credentials() {
 
     assert!(result.is_err());
     let error = result.unwrap_err();
-    assert!(error.error.contains("Account is temporarily locked"));
+    assert!(error.to_string().contains("Account is temporarily locked"));
 
     // Cleanup
     let user_repository = UserRepository::new(pool);
@@ -292,7 +343,12 @@ async fn test_successful_login_unlocks_account() {
 
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "SecurePassword123!".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
@@ -320,7 +376,12 @@ async fn test_login_statistics_tracking() {
     // Make a successful login
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "SecurePassword123!".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
@@ -335,7 +396,12 @@ async fn test_login_statistics_tracking() {
     // Make a failed login
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "WrongPassword".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let _ = service
@@ -385,7 +451,12 @@ async fn test_manual_account_unlock() {
     // Should now be able to login with correct credentials
     let request = LoginRequest {
         email: user.email.clone(),
+        identifier: None,
         password: "SecurePassword123!".to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
     };
 
     let result = service
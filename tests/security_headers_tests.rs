@@ -0,0 +1,98 @@
+use axum::{
+    Router,
+    body::Body,
+    http::{HeaderMap, Request},
+    routing::get,
+};
+use chronos::app::middleware::security::{
+    DEFAULT_CONTENT_SECURITY_POLICY, HstsConfig, SecurityHeadersConfig, SecurityHeadersLayer,
+};
+use tower::ServiceExt;
+
+async fn headers_with(layer: SecurityHeadersLayer) -> HeaderMap {
+    let app = Router::new()
+        .route("/", get(|| async { "ok" }))
+        .layer(layer);
+    let response = app
+        .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    response.headers().clone()
+}
+
+#[tokio::test]
+async fn test_defaults_apply_when_unconfigured() {
+    let headers = headers_with(SecurityHeadersLayer::default()).await;
+
+    assert_eq!(
+        headers["content-security-policy"],
+        DEFAULT_CONTENT_SECURITY_POLICY
+    );
+    assert_eq!(
+        headers["strict-transport-security"],
+        "max-age=31536000; includeSubDomains; preload"
+    );
+    assert_eq!(headers["x-frame-options"], "DENY");
+    assert_eq!(
+        headers["referrer-policy"],
+        "strict-origin-when-cross-origin"
+    );
+    assert_eq!(
+        headers["permissions-policy"],
+        "geolocation=(), microphone=(), camera=()"
+    );
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+}
+
+#[tokio::test]
+async fn test_custom_values_replace_the_defaults() {
+    let csp = "default-src 'self'; script-src 'self' https://cdn.example.com";
+    let config = SecurityHeadersConfig {
+        content_security_policy: Some(csp.to_string()),
+        hsts: Some(HstsConfig {
+            max_age: 600,
+            include_subdomains: false,
+            preload: false,
+        }),
+        frame_options: Some("SAMEORIGIN".to_string()),
+        referrer_policy: Some("no-referrer".to_string()),
+        ..SecurityHeadersConfig::default()
+    };
+    let headers = headers_with(SecurityHeadersLayer::new(&config)).await;
+
+    assert_eq!(headers["content-security-policy"], csp);
+    assert_eq!(headers["strict-transport-security"], "max-age=600");
+    assert_eq!(headers["x-frame-options"], "SAMEORIGIN");
+    assert_eq!(headers["referrer-policy"], "no-referrer");
+    assert_eq!(
+        headers["permissions-policy"],
+        "geolocation=(), microphone=(), camera=()"
+    );
+}
+
+#[tokio::test]
+async fn test_disabled_headers_are_left_out() {
+    let config = SecurityHeadersConfig {
+        hsts: None,
+        content_security_policy: None,
+        ..SecurityHeadersConfig::default()
+    };
+    let headers = headers_with(SecurityHeadersLayer::new(&config)).await;
+
+    assert!(!headers.contains_key("strict-transport-security"));
+    assert!(!headers.contains_key("content-security-policy"));
+    assert_eq!(headers["x-frame-options"], "DENY");
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+}
+
+#[tokio::test]
+async fn test_invalid_values_are_skipped() {
+    let config = SecurityHeadersConfig {
+        content_security_policy: Some("default-src 'self'\nscript-src *".to_string()),
+        ..SecurityHeadersConfig::default()
+    };
+    let headers = headers_with(SecurityHeadersLayer::new(&config)).await;
+
+    assert!(!headers.contains_key("content-security-policy"));
+    assert_eq!(headers["x-frame-options"], "DENY");
+}
@@ -0,0 +1,174 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::jwt::LoginRequest;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::{
+    InMemoryPasswordHistoryStore, PasswordHistoryRepository,
+};
+use chronos::app::repositories::password_reset_repository::{
+    InMemoryPasswordResetStore, PasswordResetRepository,
+};
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::{InMemoryUserStore, UserRepository, UserStore};
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::{EmailMessage, MockEmailService, NotificationSettings};
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use uuid::Uuid;
+
+const PASSWORD: &str = "NotifyPass123!";
+const NEW_SIGN_IN_SUBJECT: &str = "New sign-in to your account - Chronos";
+const PASSWORD_CHANGED_SUBJECT: &str = "Your password was changed - Chronos";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Login and auth services sharing one mailbox
+fn create_services(
+    pool: &PgPool,
+    settings: NotificationSettings,
+) -> (SecureLoginService, AuthService, MockEmailService) {
+    let email_service = MockEmailService::new();
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        email_service.clone(),
+    )
+    .with_argon2_params(Argon2Params::new(1024, 1, 1).unwrap())
+    .with_notification_settings(settings);
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service,
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    (secure_login_service, auth_service, email_service)
+}
+
+async fn create_user(store: &impl UserStore) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("notify-{}@example.com", Uuid::new_v4()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    store.create(&user).await.unwrap()
+}
+
+async fn login(service: &SecureLoginService, email: &str, ip: &str, user_agent: &str) {
+    let request = LoginRequest {
+        email: email.to_string(),
+        identifier: None,
+        password: PASSWORD.to_string(),
+        captcha_token: None,
+        remember_me: false,
+        device_name: None,
+        client_id: None,
+    };
+    service
+        .secure_login(request, ip.to_string(), Some(user_agent.to_string()))
+        .await
+        .unwrap();
+}
+
+fn sent_with_subject(email_service: &MockEmailService, subject: &str) -> Vec<EmailMessage> {
+    email_service
+        .get_sent_emails()
+        .into_iter()
+        .filter(|message| message.subject == subject)
+        .collect()
+}
+
+// Each test signs in from its own addresses so earlier runs don't make them known
+fn random_ip() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    format!("10.{}.{}.{}", bytes[0], bytes[1], bytes[2])
+}
+
+#[tokio::test]
+async fn test_new_device_sign_in_sends_notification() {
+    let pool = setup_test_pool().await;
+    let (service, _, email_service) = create_services(&pool, NotificationSettings::default());
+    let user = create_user(&UserRepository::new(pool.clone())).await;
+    let (home, travel) = (random_ip(), random_ip());
+
+    // The first sign-in has nothing to compare against, and a known device is quiet
+    login(&service, &user.email, &home, "Firefox").await;
+    login(&service, &user.email, &home, "Firefox").await;
+    assert!(sent_with_subject(&email_service, NEW_SIGN_IN_SUBJECT).is_empty());
+
+    login(&service, &user.email, &travel, "Firefox").await;
+    login(&service, &user.email, &home, "Safari").await;
+    let notices = sent_with_subject(&email_service, NEW_SIGN_IN_SUBJECT);
+    assert_eq!(notices.len(), 2);
+    assert!(notices.iter().all(|message| message.to == user.email));
+    assert!(notices[0].body.contains(&travel));
+    assert!(notices[1].body.contains("Safari"));
+
+    // Both are known now
+    login(&service, &user.email, &travel, "Firefox").await;
+    assert_eq!(
+        sent_with_subject(&email_service, NEW_SIGN_IN_SUBJECT).len(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_password_change_sends_notification() {
+    // Changing a password only needs the auth service, so no database is involved
+    let users = InMemoryUserStore::new();
+    let email_service = MockEmailService::new();
+    let auth_service = AuthService::new(
+        users.clone(),
+        InMemoryPasswordResetStore::new(),
+        InMemoryPasswordHistoryStore::new(),
+        email_service.clone(),
+    )
+    .with_argon2_params(Argon2Params::new(1024, 1, 1).unwrap());
+    let user = create_user(&users).await;
+
+    auth_service
+        .change_password(user.id, "ChangedPass456!")
+        .await
+        .unwrap();
+
+    let notices = sent_with_subject(&email_service, PASSWORD_CHANGED_SUBJECT);
+    assert_eq!(notices.len(), 1);
+    assert_eq!(notices[0].to, user.email);
+}
+
+#[tokio::test]
+async fn test_disabled_notifications_are_not_sent() {
+    let pool = setup_test_pool().await;
+    let (service, auth_service, email_service) =
+        create_services(&pool, NotificationSettings::disabled());
+    let user = create_user(&UserRepository::new(pool.clone())).await;
+
+    login(&service, &user.email, &random_ip(), "Firefox").await;
+    login(&service, &user.email, &random_ip(), "Firefox").await;
+    auth_service
+        .change_password(user.id, "ChangedPass456!")
+        .await
+        .unwrap();
+
+    assert_eq!(email_service.count_sent_emails(), 0);
+}
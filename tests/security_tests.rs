@@ -1,38 +1,44 @@
+use chronos::app::middleware::rate_limiter::{
+    InMemoryRateLimiter, RateLimitConfig, RateLimitDecision, RateLimitRule, RateLimiter,
+};
 use chronos::app::middleware::security::{
-    SecurityState, check_password_reset_rate_limit, check_refresh_rate_limit,
-    check_registration_rate_limit, log_security_event,
+    SecurityState, check_login_rate_limit, check_password_reset_rate_limit,
+    check_refresh_rate_limit, check_registration_rate_limit, log_security_event,
+    rate_limit_headers,
 };
+use chronos::app::models::auth::AuthenticationError;
+use std::sync::Arc;
 use std::time::Duration;
 
 #[tokio::test]
 async fn test_registration_rate_limiting() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
     let test_ip = "192.168.1.1";
 
     // First 5 attempts should succeed
     for i in 0..5 {
-        let result = check_registration_rate_limit(&security_state, test_ip);
+        let result = check_registration_rate_limit(&security_state, test_ip).await;
         assert!(result.is_ok(), "Attempt {} should succeed", i + 1);
     }
 
     // 6th attempt should fail due to rate limit
-    let result = check_registration_rate_limit(&security_state, test_ip);
+    let result = check_registration_rate_limit(&security_state, test_ip).await;
     assert!(result.is_err(), "6th attempt should be rate limited");
 }
 
 #[tokio::test]
 async fn test_refresh_rate_limiting() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
     let test_user_id = "user-123";
 
     // First 10 attempts should succeed
     for i in 0..10 {
-        let result = check_refresh_rate_limit(&security_state, test_user_id);
+        let result = check_refresh_rate_limit(&security_state, test_user_id).await;
         assert!(result.is_ok(), "Refresh attempt {} should succeed", i + 1);
     }
 
     // 11th attempt should fail due to rate limit
-    let result = check_refresh_rate_limit(&security_state, test_user_id);
+    let result = check_refresh_rate_limit(&security_state, test_user_id).await;
     assert!(
         result.is_err(),
         "11th refresh attempt should be rate limited"
@@ -41,12 +47,12 @@ async fn test_refresh_rate_limiting() {
 
 #[tokio::test]
 async fn test_password_reset_rate_limiting() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
     let test_email = "test@example.com";
 
     // First 3 attempts should succeed
     for i in 0..3 {
-        let result = check_password_reset_rate_limit(&security_state, test_email);
+        let result = check_password_reset_rate_limit(&security_state, test_email).await;
         assert!(
             result.is_ok(),
             "Password reset attempt {} should succeed",
@@ -55,7 +61,7 @@ async fn test_password_reset_rate_limiting() {
     }
 
     // 4th attempt should fail due to rate limit
-    let result = check_password_reset_rate_limit(&security_state, test_email);
+    let result = check_password_reset_rate_limit(&security_state, test_email).await;
     assert!(
         result.is_err(),
         "4th password reset attempt should be rate limited"
@@ -64,7 +70,7 @@ async fn test_password_reset_rate_limiting() {
 
 #[tokio::test]
 async fn test_rate_limit_isolation() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
 
     // Different IPs should have separate rate limits
     let ip1 = "192.168.1.1";
@@ -72,52 +78,82 @@ async fn test_rate_limit_isolation() {
 
     // Exhaust rate limit for ip1
     for _ in 0..5 {
-        check_registration_rate_limit(&security_state, ip1).unwrap();
+        check_registration_rate_limit(&security_state, ip1)
+            .await
+            .unwrap();
     }
 
     // ip1 should be rate limited
-    assert!(check_registration_rate_limit(&security_state, ip1).is_err());
+    assert!(
+        check_registration_rate_limit(&security_state, ip1)
+            .await
+            .is_err()
+    );
 
     // ip2 should still work
-    assert!(check_registration_rate_limit(&security_state, ip2).is_ok());
+    assert!(
+        check_registration_rate_limit(&security_state, ip2)
+            .await
+            .is_ok()
+    );
 }
 
 #[tokio::test]
 async fn test_rate_limit_different_users() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
 
     let user1 = "user-123";
     let user2 = "user-456";
 
     // Exhaust rate limit for user1
     for _ in 0..10 {
-        check_refresh_rate_limit(&security_state, user1).unwrap();
+        check_refresh_rate_limit(&security_state, user1)
+            .await
+            .unwrap();
     }
 
     // user1 should be rate limited
-    assert!(check_refresh_rate_limit(&security_state, user1).is_err());
+    assert!(
+        check_refresh_rate_limit(&security_state, user1)
+            .await
+            .is_err()
+    );
 
     // user2 should still work
-    assert!(check_refresh_rate_limit(&security_state, user2).is_ok());
+    assert!(
+        check_refresh_rate_limit(&security_state, user2)
+            .await
+            .is_ok()
+    );
 }
 
 #[tokio::test]
 async fn test_rate_limit_different_emails() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
 
     let email1 = "test1@example.com";
     let email2 = "test2@example.com";
 
     // Exhaust rate limit for email1
     for _ in 0..3 {
-        check_password_reset_rate_limit(&security_state, email1).unwrap();
+        check_password_reset_rate_limit(&security_state, email1)
+            .await
+            .unwrap();
     }
 
     // email1 should be rate limited
-    assert!(check_password_reset_rate_limit(&security_state, email1).is_err());
+    assert!(
+        check_password_reset_rate_limit(&security_state, email1)
+            .await
+            .is_err()
+    );
 
     // email2 should still work
-    assert!(check_password_reset_rate_limit(&security_state, email2).is_ok());
+    assert!(
+        check_password_reset_rate_limit(&security_state, email2)
+            .await
+            .is_ok()
+    );
 }
 
 #[test]
@@ -149,29 +185,33 @@ fn test_security_logging() {
 
 #[tokio::test]
 async fn test_security_state_creation() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::new(RateLimitConfig::default());
 
-    // Verify state is properly initialized
-    assert_eq!(security_state.ip_rate_limiter.len(), 0);
-    assert_eq!(security_state.user_rate_limiter.len(), 0);
-    assert_eq!(security_state.email_rate_limiter.len(), 0);
+    // Verify state is properly initialized with a fresh limiter
+    assert!(
+        check_registration_rate_limit(&security_state, "10.0.0.1")
+            .await
+            .is_ok()
+    );
 }
 
 #[tokio::test]
 async fn test_security_state_default() {
     let security_state = SecurityState::default();
 
-    // Verify default implementation works the same as new()
-    assert_eq!(security_state.ip_rate_limiter.len(), 0);
-    assert_eq!(security_state.user_rate_limiter.len(), 0);
-    assert_eq!(security_state.email_rate_limiter.len(), 0);
+    // Verify default implementation uses the default limits
+    assert!(
+        check_refresh_rate_limit(&security_state, "user-default")
+            .await
+            .is_ok()
+    );
 }
 
 #[tokio::test]
 async fn test_concurrent_rate_limiting() {
     use tokio::task::JoinSet;
 
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
     let test_ip = "192.168.1.100";
 
     let mut join_set = JoinSet::new();
@@ -183,7 +223,7 @@ async fn test_concurrent_rate_limiting() {
 
         join_set.spawn(async move {
             tokio::time::sleep(Duration::from_millis(i * 10)).await;
-            check_registration_rate_limit(&security_state_clone, &ip_clone)
+            check_registration_rate_limit(&security_state_clone, &ip_clone).await
         });
     }
 
@@ -205,7 +245,7 @@ async fn test_concurrent_rate_limiting() {
 // Integration test that simulates realistic rate limiting scenarios
 #[tokio::test]
 async fn test_realistic_rate_limiting_scenario() {
-    let security_state = SecurityState::new();
+    let security_state = SecurityState::default();
 
     // Simulate multiple users from different IPs performing various operations
     let scenarios = vec![
@@ -217,7 +257,7 @@ async fn test_realistic_rate_limiting_scenario() {
     for (ip, email, user_id) in scenarios {
         // Each IP should be able to do 5 registrations
         for i in 0..5 {
-            let result = check_registration_rate_limit(&security_state, ip);
+            let result = check_registration_rate_limit(&security_state, ip).await;
             assert!(
                 result.is_ok(),
                 "Registration {} for {} should succeed",
@@ -228,14 +268,16 @@ async fn test_realistic_rate_limiting_scenario() {
 
         // 6th registration should fail
         assert!(
-            check_registration_rate_limit(&security_state, ip).is_err(),
+            check_registration_rate_limit(&security_state, ip)
+                .await
+                .is_err(),
             "6th registration for {} should fail",
             ip
         );
 
         // Each email should be able to do 3 password resets
         for i in 0..3 {
-            let result = check_password_reset_rate_limit(&security_state, email);
+            let result = check_password_reset_rate_limit(&security_state, email).await;
             assert!(
                 result.is_ok(),
                 "Password reset {} for {} should succeed",
@@ -246,14 +288,16 @@ async fn test_realistic_rate_limiting_scenario() {
 
         // 4th password reset should fail
         assert!(
-            check_password_reset_rate_limit(&security_state, email).is_err(),
+            check_password_reset_rate_limit(&security_state, email)
+                .await
+                .is_err(),
             "4th password reset for {} should fail",
             email
         );
 
         // Each user should be able to do 10 refresh attempts
         for i in 0..10 {
-            let result = check_refresh_rate_limit(&security_state, user_id);
+            let result = check_refresh_rate_limit(&security_state, user_id).await;
             assert!(
                 result.is_ok(),
                 "Refresh {} for {} should succeed",
@@ -264,9 +308,290 @@ async fn test_realistic_rate_limiting_scenario() {
 
         // 11th refresh should fail
         assert!(
-            check_refresh_rate_limit(&security_state, user_id).is_err(),
+            check_refresh_rate_limit(&security_state, user_id)
+                .await
+                .is_err(),
             "11th refresh for {} should fail",
             user_id
         );
     }
 }
+
+#[tokio::test]
+async fn test_in_memory_rate_limiter_sliding_window() {
+    let limiter = InMemoryRateLimiter::new();
+    let window = Duration::from_millis(100);
+
+    assert!(
+        limiter
+            .check("window-key", 2, window)
+            .await
+            .unwrap()
+            .is_allowed()
+    );
+    assert!(
+        limiter
+            .check("window-key", 2, window)
+            .await
+            .unwrap()
+            .is_allowed()
+    );
+    assert!(
+        !limiter
+            .check("window-key", 2, window)
+            .await
+            .unwrap()
+            .is_allowed()
+    );
+
+    // Hits fall out of the window and free up capacity again
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert!(
+        limiter
+            .check("window-key", 2, window)
+            .await
+            .unwrap()
+            .is_allowed()
+    );
+}
+
+#[tokio::test]
+async fn test_in_memory_rate_limiter_cleanup() {
+    let limiter = InMemoryRateLimiter::new();
+
+    limiter
+        .check("cleanup-key", 5, Duration::from_secs(60))
+        .await
+        .unwrap();
+    assert_eq!(limiter.len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    limiter.cleanup(Duration::from_millis(10)).await;
+    assert!(limiter.is_empty());
+}
+
+#[tokio::test]
+async fn test_security_state_uses_provided_rate_limiter() {
+    let limiter = Arc::new(InMemoryRateLimiter::new());
+    let security_state = SecurityState::default().with_rate_limiter(limiter.clone());
+
+    check_registration_rate_limit(&security_state, "10.1.1.1")
+        .await
+        .unwrap();
+    check_password_reset_rate_limit(&security_state, "shared@example.com")
+        .await
+        .unwrap();
+
+    // Each endpoint keys its counters separately
+    assert_eq!(limiter.len(), 2);
+}
+
+#[tokio::test]
+async fn test_configured_limit_applies_on_third_attempt() {
+    let config = RateLimitConfig {
+        registration: RateLimitRule::new(2, Duration::from_secs(60)),
+        ..RateLimitConfig::default()
+    };
+    let security_state = SecurityState::new(config);
+
+    for i in 0..2 {
+        assert!(
+            check_registration_rate_limit(&security_state, "10.3.3.3")
+                .await
+                .is_ok(),
+            "Attempt {} should succeed",
+            i + 1
+        );
+    }
+
+    match check_registration_rate_limit(&security_state, "10.3.3.3").await {
+        Err(AuthenticationError::RateLimitExceeded { retry_after }) => {
+            assert!((1..=60).contains(&retry_after));
+        }
+        other => panic!("3rd attempt should be rate limited, got {:?}", other),
+    }
+
+    // Other endpoints keep their defaults
+    for _ in 0..3 {
+        check_password_reset_rate_limit(&security_state, "config@example.com")
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_login_email_limit_applies_across_ips() {
+    let config = RateLimitConfig {
+        login_email: RateLimitRule::new(2, Duration::from_secs(60)),
+        ..RateLimitConfig::default()
+    };
+    let security_state = SecurityState::new(config);
+
+    check_login_rate_limit(&security_state, "10.4.4.1", "target@example.com")
+        .await
+        .unwrap();
+    check_login_rate_limit(&security_state, "10.4.4.2", "Target@Example.com")
+        .await
+        .unwrap();
+    assert!(matches!(
+        check_login_rate_limit(&security_state, "10.4.4.3", "target@example.com").await,
+        Err(AuthenticationError::RateLimitExceeded { .. })
+    ));
+
+    // Other accounts from the same IPs are unaffected
+    check_login_rate_limit(&security_state, "10.4.4.3", "other@example.com")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_rate_limit_response_has_retry_after_header() {
+    use axum::http::{StatusCode, header};
+    use axum::response::IntoResponse;
+
+    let config = RateLimitConfig {
+        refresh: RateLimitRule::new(1, Duration::from_secs(30)),
+        ..RateLimitConfig::default()
+    };
+    let security_state = SecurityState::new(config);
+
+    check_refresh_rate_limit(&security_state, "user-retry")
+        .await
+        .unwrap();
+    let response = check_refresh_rate_limit(&security_state, "user-retry")
+        .await
+        .unwrap_err()
+        .into_response();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=30).contains(&retry_after));
+}
+
+#[tokio::test]
+async fn test_in_memory_rate_limiter_reports_retry_after() {
+    let limiter = InMemoryRateLimiter::new();
+    let window = Duration::from_secs(10);
+
+    limiter.check("retry-key", 1, window).await.unwrap();
+    match limiter.check("retry-key", 1, window).await.unwrap() {
+        RateLimitDecision::Limited { retry_after } => {
+            assert!(retry_after <= window);
+            assert!(retry_after > Duration::from_secs(9));
+        }
+        RateLimitDecision::Allowed { .. } => panic!("Second hit should be limited"),
+    }
+}
+
+#[tokio::test]
+async fn test_rate_limit_status_remaining_decrements_and_resets() {
+    let config = RateLimitConfig {
+        registration: RateLimitRule::new(3, Duration::from_millis(300)),
+        ..RateLimitConfig::default()
+    };
+    let security_state = SecurityState::new(config);
+
+    for expected_remaining in [2, 1, 0] {
+        let status = check_registration_rate_limit(&security_state, "10.5.5.5")
+            .await
+            .unwrap()
+            .expect("In-memory limiter always reports its state");
+        assert_eq!(status.limit, 3);
+        assert_eq!(status.remaining, expected_remaining);
+        assert_eq!(status.reset_after, 1);
+    }
+    assert!(
+        check_registration_rate_limit(&security_state, "10.5.5.5")
+            .await
+            .is_err()
+    );
+
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    let status = check_registration_rate_limit(&security_state, "10.5.5.5")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.remaining, 2);
+}
+
+#[tokio::test]
+async fn test_login_rate_limit_status_reports_tightest_limit() {
+    let config = RateLimitConfig {
+        login: RateLimitRule::new(5, Duration::from_secs(60)),
+        login_email: RateLimitRule::new(2, Duration::from_secs(60)),
+        ..RateLimitConfig::default()
+    };
+    let security_state = SecurityState::new(config);
+
+    let status = check_login_rate_limit(&security_state, "10.6.6.6", "tight@example.com")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.limit, 2);
+    assert_eq!(status.remaining, 1);
+}
+
+#[tokio::test]
+async fn test_rate_limit_headers_on_responses() {
+    use axum::{
+        Router,
+        body::Body,
+        extract::State,
+        http::{Request, StatusCode, header},
+        middleware,
+        response::{IntoResponse, Response},
+        routing::post,
+    };
+    use tower::ServiceExt;
+
+    async fn refresh(State(security_state): State<Arc<SecurityState>>) -> Response {
+        match check_refresh_rate_limit(&security_state, "user-headers").await {
+            Ok(_) => StatusCode::OK.into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+
+    let config = RateLimitConfig {
+        refresh: RateLimitRule::new(2, Duration::from_secs(30)),
+        ..RateLimitConfig::default()
+    };
+    let app = Router::new()
+        .route("/refresh", post(refresh))
+        .with_state(Arc::new(SecurityState::new(config)))
+        .layer(middleware::from_fn(rate_limit_headers));
+
+    let send = || async {
+        let request = Request::builder()
+            .uri("/refresh")
+            .method("POST")
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request).await.unwrap()
+    };
+    let header_value = |response: &Response, name: &str| -> u64 {
+        response.headers()[name].to_str().unwrap().parse().unwrap()
+    };
+
+    for expected_remaining in [1, 0] {
+        let response = send().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header_value(&response, "x-ratelimit-limit"), 2);
+        assert_eq!(
+            header_value(&response, "x-ratelimit-remaining"),
+            expected_remaining
+        );
+        assert!((1..=30).contains(&header_value(&response, "x-ratelimit-reset")));
+    }
+
+    let response = send().await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(header_value(&response, "x-ratelimit-remaining"), 0);
+    assert_eq!(
+        header_value(&response, "x-ratelimit-reset"),
+        header_value(&response, header::RETRY_AFTER.as_str())
+    );
+}
@@ -0,0 +1,206 @@
+use axum::{Json, Router, extract::State, http::StatusCode, routing::post};
+use chronos::app::middleware::security::log_security_event;
+use chronos::app::security_events::{
+    SecurityEvent, SecurityEventSink, Severity, WebhookSink, add_security_event_sink,
+};
+use serde_json::Value;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+// Long enough for a spawned delivery to reach the mock server
+const DELIVERY_WAIT: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
+struct MockWebhook {
+    received: mpsc::UnboundedSender<Value>,
+    // Requests to fail with 503 before accepting one
+    failures_left: Arc<AtomicUsize>,
+}
+
+async fn receive(State(mock): State<MockWebhook>, Json(body): Json<Value>) -> StatusCode {
+    let failing = mock
+        .failures_left
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok();
+    let _ = mock.received.send(body);
+    if failing {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::NO_CONTENT
+    }
+}
+
+// A local webhook receiver; every body it gets comes out of the returned channel
+async fn start_mock_webhook(failures: usize) -> (reqwest::Url, mpsc::UnboundedReceiver<Value>) {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mock = MockWebhook {
+        received: sender,
+        failures_left: Arc::new(AtomicUsize::new(failures)),
+    };
+    let app = Router::new().route("/hook", post(receive)).with_state(mock);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let url = format!("http://{}/hook", addr).parse().unwrap();
+    (url, receiver)
+}
+
+fn event(event_type: &str, severity: Severity) -> SecurityEvent {
+    SecurityEvent {
+        event_type: event_type.to_string(),
+        severity,
+        request_id: Some("req-webhook".to_string()),
+        ip_address: "203.0.113.7".to_string(),
+        user_agent: None,
+        user_id: None,
+        email: None,
+        success: false,
+        details: None,
+        occurred_at: OffsetDateTime::now_utc(),
+        country: None,
+        region: None,
+    }
+}
+
+#[tokio::test]
+async fn test_critical_event_triggers_webhook() {
+    let (url, mut received) = start_mock_webhook(0).await;
+    let sink = WebhookSink::new(url, Severity::High);
+
+    sink.record(&event("suspicious_activity", Severity::Critical));
+
+    let body = tokio::time::timeout(DELIVERY_WAIT, received.recv())
+        .await
+        .expect("webhook was not called")
+        .unwrap();
+    assert_eq!(body["event_type"], "suspicious_activity");
+    assert_eq!(body["severity"], "critical");
+    assert_eq!(body["request_id"], "req-webhook");
+    assert_eq!(body["ip_address"], "203.0.113.7");
+}
+
+#[tokio::test]
+async fn test_low_event_does_not_trigger_webhook() {
+    let (url, mut received) = start_mock_webhook(0).await;
+    let sink = WebhookSink::new(url, Severity::High);
+
+    sink.record(&event("profile_accessed", Severity::Low));
+    sink.record(&event("profile_update_failed", Severity::Medium));
+
+    tokio::time::sleep(DELIVERY_WAIT).await;
+    assert!(received.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_failed_delivery_is_retried() {
+    let (url, mut received) = start_mock_webhook(2).await;
+    let sink = WebhookSink::new(url.clone(), Severity::High)
+        .with_max_attempts(3)
+        .with_retry_delay(Duration::from_millis(10));
+
+    sink.deliver(&event("admin_password_reset", Severity::Critical))
+        .await
+        .unwrap();
+    for _ in 0..3 {
+        assert_eq!(
+            received.recv().await.unwrap()["event_type"],
+            "admin_password_reset"
+        );
+    }
+
+    // Giving up after the last attempt reports the error instead of panicking
+    let (url, _received) = start_mock_webhook(5).await;
+    let sink = WebhookSink::new(url, Severity::High)
+        .with_max_attempts(2)
+        .with_retry_delay(Duration::from_millis(10));
+    assert!(
+        sink.deliver(&event("admin_password_reset", Severity::Critical))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_events_beyond_queue_capacity_are_dropped() {
+    // Accepts connections but never answers, so the worker is stuck on its first delivery
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url =
+        reqwest::Url::parse(&format!("http://{}/alerts", listener.local_addr().unwrap())).unwrap();
+    let sink = WebhookSink::new(url, Severity::High)
+        .with_timeout(Duration::from_secs(5))
+        .with_queue_capacity(2);
+
+    for _ in 0..10 {
+        sink.record(&event("suspicious_activity", Severity::Critical));
+    }
+
+    // At most one event in flight plus two queued; the rest are dropped, not spawned
+    assert!(sink.dropped_events() >= 7);
+    assert!(sink.dropped_events() <= 8);
+}
+
+#[tokio::test]
+async fn test_log_security_event_reaches_installed_webhook() {
+    let (url, mut received) = start_mock_webhook(0).await;
+    add_security_event_sink(Arc::new(WebhookSink::new(url, Severity::High)));
+
+    log_security_event(
+        "profile_accessed",
+        "203.0.113.7",
+        None,
+        Some("user-1"),
+        None,
+        true,
+        None,
+    );
+    log_security_event(
+        "suspicious_activity",
+        "203.0.113.7",
+        None,
+        None,
+        None,
+        false,
+        Some("Request blocked by IP filter"),
+    );
+
+    let body = tokio::time::timeout(DELIVERY_WAIT, received.recv())
+        .await
+        .expect("webhook was not called")
+        .unwrap();
+    assert_eq!(body["event_type"], "suspicious_activity");
+    assert_eq!(body["details"], "Request blocked by IP filter");
+
+    tokio::time::sleep(DELIVERY_WAIT).await;
+    assert!(received.try_recv().is_err());
+}
+
+#[test]
+fn test_event_severities() {
+    assert_eq!(
+        Severity::for_event("suspicious_activity", false),
+        Severity::Critical
+    );
+    assert_eq!(
+        Severity::for_event("registration_rate_limit_exceeded", false),
+        Severity::High
+    );
+    assert_eq!(
+        Severity::for_event("login_captcha_failed", false),
+        Severity::High
+    );
+    assert_eq!(
+        Severity::for_event("profile_update_failed", false),
+        Severity::Medium
+    );
+    assert_eq!(Severity::for_event("profile_accessed", true), Severity::Low);
+    assert_eq!(Severity::parse("CRITICAL"), Some(Severity::Critical));
+    assert_eq!(Severity::parse("urgent"), None);
+}
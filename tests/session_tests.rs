@@ -0,0 +1,224 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "SessionPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Each test gets its own IP so logins don't add up across tests
+fn create_test_app(pool: PgPool, ip: &str) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("session-{}@example.com", Uuid::new_v4().simple()),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// Logs in and returns the token pair
+async fn login(app: &axum::Router, email: &str, body: Value, header: Option<&str>) -> Value {
+    let mut body = body;
+    body["email"] = json!(email);
+    body["password"] = json!(TEST_PASSWORD);
+
+    let mut request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json");
+    if let Some(device_name) = header {
+        request = request.header("x-device-name", device_name);
+    }
+    let (status, body) = send(app, request.body(Body::from(body.to_string())).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"].clone()
+}
+
+async fn list_sessions(app: &axum::Router, access_token: &str) -> Vec<Value> {
+    let request = Request::builder()
+        .uri("/api/auth/sessions")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    let (status, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    body["sessions"].as_array().unwrap().clone()
+}
+
+async fn rename(
+    app: &axum::Router,
+    access_token: &str,
+    jti: &str,
+    device_name: &str,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(format!("/api/auth/sessions/{}/name", jti))
+        .method("PUT")
+        .header("authorization", format!("Bearer {}", access_token))
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "device_name": device_name }).to_string(),
+        ))
+        .unwrap();
+    send(app, request).await
+}
+
+fn session_named<'a>(sessions: &'a [Value], device_name: &str) -> &'a Value {
+    sessions
+        .iter()
+        .find(|session| session["device_name"] == device_name)
+        .unwrap_or_else(|| panic!("no session named {}", device_name))
+}
+
+#[tokio::test]
+async fn test_current_session_is_flagged() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.4.1");
+    let user = create_user(&pool).await;
+
+    let laptop = login(&app, &user.email, json!({ "device_name": "Laptop" }), None).await;
+    let phone = login(&app, &user.email, json!({}), Some("  Phone  ")).await;
+
+    let sessions = list_sessions(&app, laptop["access_token"].as_str().unwrap()).await;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(session_named(&sessions, "Laptop")["current"], true);
+    assert_eq!(session_named(&sessions, "Phone")["current"], false);
+    assert!(sessions[0].get("token_hash").is_none());
+
+    let sessions = list_sessions(&app, phone["access_token"].as_str().unwrap()).await;
+    assert_eq!(session_named(&sessions, "Laptop")["current"], false);
+    assert_eq!(session_named(&sessions, "Phone")["current"], true);
+
+    // The body wins over the header, and unnamed sessions are still listed
+    login(
+        &app,
+        &user.email,
+        json!({ "device_name": "Tablet" }),
+        Some("Ignored"),
+    )
+    .await;
+    login(&app, &user.email, json!({}), None).await;
+    let sessions = list_sessions(&app, phone["access_token"].as_str().unwrap()).await;
+    assert_eq!(sessions.len(), 4);
+    session_named(&sessions, "Tablet");
+    assert!(
+        sessions
+            .iter()
+            .any(|session| session["device_name"].is_null())
+    );
+}
+
+#[tokio::test]
+async fn test_rename_persists_across_refresh() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.4.2");
+    let user = create_user(&pool).await;
+    let tokens = login(&app, &user.email, json!({ "device_name": "Laptop" }), None).await;
+    let access_token = tokens["access_token"].as_str().unwrap();
+
+    let jti = list_sessions(&app, access_token).await[0]["jti"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let (status, body) = rename(&app, access_token, &jti, " Work laptop ").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["device_name"], "Work laptop");
+    assert_eq!(body["current"], true);
+
+    let sessions = list_sessions(&app, access_token).await;
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0]["device_name"], "Work laptop");
+
+    // Rotation replaces the refresh token but keeps the session's name and flag
+    let request = Request::builder()
+        .uri("/api/auth/refresh")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "refresh_token": tokens["refresh_token"] }).to_string(),
+        ))
+        .unwrap();
+    let (status, refreshed) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let sessions = list_sessions(&app, refreshed["access_token"].as_str().unwrap()).await;
+    assert_eq!(sessions.len(), 1);
+    assert_ne!(sessions[0]["jti"], jti.as_str());
+    assert_eq!(sessions[0]["device_name"], "Work laptop");
+    assert_eq!(sessions[0]["current"], true);
+}
+
+#[tokio::test]
+async fn test_rename_rejects_empty_and_foreign_sessions() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.4.3");
+    let owner = create_user(&pool).await;
+    let other = create_user(&pool).await;
+    let owner_tokens = login(&app, &owner.email, json!({ "device_name": "Desk" }), None).await;
+    let other_tokens = login(&app, &other.email, json!({}), None).await;
+    let owner_token = owner_tokens["access_token"].as_str().unwrap();
+    let jti = list_sessions(&app, owner_token).await[0]["jti"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let (status, _) = rename(&app, owner_token, &jti, " \n ").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Someone else's session looks the same as one that doesn't exist
+    let (status, _) = rename(
+        &app,
+        other_tokens["access_token"].as_str().unwrap(),
+        &jti,
+        "Mine now",
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = rename(&app, owner_token, "no-such-session", "Desk").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    assert_eq!(
+        list_sessions(&app, owner_token).await[0]["device_name"],
+        "Desk"
+    );
+}
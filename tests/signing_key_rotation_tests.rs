@@ -0,0 +1,257 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware,
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::auth_middleware::{
+    RequireRole, jwt_auth_middleware_with_json_errors, require_role,
+};
+use chronos::app::models::role::ADMIN_ROLE;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::signing_key_repository::SigningKeyRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::jwt_service::{DEFAULT_KEY_ID, JwtService, SigningKeyReload};
+use chronos::app::signing_key_cipher::SigningKeyCipher;
+use chronos::app::signing_key_listener::SigningKeyListener;
+use chronos::routes::admin::{self, AdminState};
+use dotenvy::dotenv;
+use jsonwebtoken::decode_header;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const SECRET: &str = "key-rotation-endpoint-secret";
+const ENCRYPTION_KEY: &str = "key-rotation-endpoint-encryption-key";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_service(pool: &PgPool) -> JwtService {
+    JwtService::new(
+        SECRET,
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_role_repository(RoleRepository::new(pool.clone()))
+}
+
+// The admin routes as create_router guards them, around `jwt_service`
+fn create_admin_app(pool: &PgPool, jwt_service: &JwtService) -> Router {
+    let admin_routes = admin::routes()
+        .with_state(AdminState::new(pool.clone()).with_jwt_service(jwt_service.clone()))
+        .layer(middleware::from_fn_with_state(
+            RequireRole(ADMIN_ROLE),
+            require_role,
+        ))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(jwt_service.clone()),
+            jwt_auth_middleware_with_json_errors,
+        ));
+    Router::new()
+        .nest("/api/admin", admin_routes)
+        .layer(MockConnectInfo(
+            "192.168.13.1:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+async fn create_admin(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("key-admin-{}@example.com", Uuid::new_v4().simple()),
+        "KeyAdminPass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    let user = UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap();
+    let role_repository = RoleRepository::new(pool.clone());
+    let admin_role = role_repository
+        .find_by_name(ADMIN_ROLE)
+        .await
+        .unwrap()
+        .expect("admin role should be seeded");
+    role_repository
+        .grant_role(user.id, admin_role.id)
+        .await
+        .unwrap();
+    user
+}
+
+async fn access_token(service: &JwtService, user: &User) -> String {
+    service
+        .generate_token_pair(user)
+        .await
+        .unwrap()
+        .access_token
+}
+
+fn kid(token: &str) -> String {
+    decode_header(token).unwrap().kid.unwrap()
+}
+
+async fn rotate(app: &Router, token: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/admin/jwt/rotate-key")
+        .method("POST")
+        .header("authorization", format!("Bearer {}", token));
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_rotation_switches_kid_and_revoke_old_drops_old_tokens() {
+    let pool = setup_test_pool().await;
+    let jwt_service = create_service(&pool);
+    let app = create_admin_app(&pool, &jwt_service);
+    let admin = create_admin(&pool).await;
+    let old_token = access_token(&jwt_service, &admin).await;
+    assert_eq!(kid(&old_token), DEFAULT_KEY_ID);
+
+    let (status, body) = rotate(&app, &old_token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    let new_kid = body["kid"].as_str().unwrap().to_string();
+    assert_ne!(new_kid, DEFAULT_KEY_ID);
+    assert_eq!(body["revoked_kids"], json!([]));
+
+    let new_token = access_token(&jwt_service, &admin).await;
+    assert_eq!(kid(&new_token), new_kid);
+    assert!(jwt_service.validate_token(&old_token).await.is_ok());
+
+    let (status, body) = rotate(&app, &new_token, Some(json!({ "revoke_old": true }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let newest_kid = body["kid"].as_str().unwrap().to_string();
+    assert_eq!(body["revoked_kids"], json!([DEFAULT_KEY_ID, new_kid]));
+    assert_eq!(jwt_service.active_key_id(), newest_kid);
+
+    assert!(jwt_service.validate_token(&old_token).await.is_err());
+    assert!(jwt_service.validate_token(&new_token).await.is_err());
+    let (status, _) = rotate(&app, &new_token, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+// Until the listener has marked the ring stale and it reloaded, whether it
+// validates `token` may still be the answer from before the change
+async fn wait_for_validity(service: &JwtService, token: &str, valid: bool) -> bool {
+    for _ in 0..50 {
+        if service.validate_token(token).await.is_ok() == valid {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_rotated_keys_reach_other_servers_and_survive_a_restart() {
+    let pool = setup_test_pool().await;
+    // Only this test writes signing_keys; every other service loads it
+    let clear = || sqlx::query("DELETE FROM signing_keys").execute(&pool);
+    clear().await.unwrap();
+
+    let repository = SigningKeyRepository::new(pool.clone(), SigningKeyCipher::new(ENCRYPTION_KEY));
+    let admin = create_admin(&pool).await;
+    let first = create_service(&pool).with_signing_key_repository(repository.clone());
+    let old_token = access_token(&first, &admin).await;
+
+    // Another server, running since before the rotation
+    let reload = SigningKeyReload::new();
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let listener = SigningKeyListener::connect(&pool, reload.clone())
+        .await
+        .expect("Failed to listen for signing key changes")
+        .spawn(shutdown_rx);
+    let other = create_service(&pool)
+        .with_signing_key_repository(repository.clone())
+        .with_signing_key_reload(reload);
+    assert!(other.validate_token(&old_token).await.is_ok());
+
+    let rotation = first.rotate_signing_key(false).await.unwrap();
+    let rotated_token = access_token(&first, &admin).await;
+    assert!(
+        wait_for_validity(&other, &rotated_token, true).await,
+        "the other server should load the rotated key"
+    );
+    assert_eq!(other.active_key_id(), rotation.kid);
+
+    // The secret is only stored encrypted, and only the same key decrypts it
+    let stored: String = sqlx::query_scalar("SELECT secret FROM signing_keys WHERE kid = $1")
+        .bind(&rotation.kid)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(stored.starts_with("v1:"));
+    let other_key = SigningKeyRepository::new(pool.clone(), SigningKeyCipher::new("another-key"));
+    assert!(other_key.list().await.is_err());
+
+    // A new process with the same environment picks up the rotated key
+    let restarted = create_service(&pool).with_signing_key_repository(repository.clone());
+    let token = access_token(&restarted, &admin).await;
+    assert_eq!(kid(&token), rotation.kid);
+    assert!(restarted.validate_token(&old_token).await.is_ok());
+
+    let revocation = restarted.rotate_signing_key(true).await.unwrap();
+    assert!(revocation.revoked.contains(&DEFAULT_KEY_ID.to_string()));
+    assert!(
+        wait_for_validity(&other, &rotated_token, false).await,
+        "the other server should drop the revoked keys"
+    );
+    assert!(other.validate_token(&old_token).await.is_err());
+    shutdown_tx.send(true).unwrap();
+    listener.await.unwrap();
+
+    // JWT_SECRET is still configured, but its key stays revoked
+    let restarted = create_service(&pool).with_signing_key_repository(repository);
+    assert!(restarted.validate_token(&old_token).await.is_err());
+    assert!(restarted.validate_token(&token).await.is_err());
+    let token = access_token(&restarted, &admin).await;
+    assert_eq!(kid(&token), revocation.kid);
+
+    clear().await.unwrap();
+}
+
+#[test]
+fn test_cipher_binds_secrets_to_their_kid() {
+    let cipher = SigningKeyCipher::new(ENCRYPTION_KEY);
+    let stored = cipher.encrypt("2026-01", "signing-secret");
+    assert!(!stored.contains("signing-secret"));
+    assert_ne!(stored, cipher.encrypt("2026-01", "signing-secret"));
+
+    assert_eq!(
+        cipher.decrypt("2026-01", &stored).unwrap(),
+        "signing-secret"
+    );
+    assert!(cipher.decrypt("2026-02", &stored).is_err());
+    assert!(cipher.decrypt("2026-01", "signing-secret").is_err());
+}
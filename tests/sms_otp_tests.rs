@@ -0,0 +1,284 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::otp_challenge_repository::OtpChallengeRepository;
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::security_events::{SecurityEvent, SecurityEventSink, add_security_event_sink};
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::{MockEmailService, NotificationSettings};
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::otp_service::OtpService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::sms_service::MockSmsSender;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "OtpPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// The public auth routes with SMS codes going to an in-memory sender
+fn create_test_app(pool: &PgPool, ip: &str) -> (Router, OtpService, MockSmsSender) {
+    let user_repository = UserRepository::new(pool.clone());
+    let sms_sender = MockSmsSender::new();
+    let otp_service = OtpService::new(
+        Arc::new(sms_sender.clone()),
+        OtpChallengeRepository::new(pool.clone()),
+        user_repository.clone(),
+    )
+    .with_argon2_params(Argon2Params::new(1024, 1, 1).unwrap());
+
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    )
+    .with_notification_settings(NotificationSettings::disabled());
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+    .with_otp_service(otp_service.clone());
+
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    )
+    .with_otp_service(otp_service.clone());
+    let app = Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(
+            format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+        ));
+
+    (app, otp_service, sms_sender)
+}
+
+async fn send(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+// The only six digit word of the last SMS sent to `to`
+fn last_code(sms_sender: &MockSmsSender, to: &str) -> String {
+    let message = sms_sender
+        .get_sent_messages()
+        .into_iter()
+        .rev()
+        .find(|message| message.to == to)
+        .expect("no SMS sent");
+    message
+        .body
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|word| word.len() == 6)
+        .expect("no code in SMS")
+        .to_string()
+}
+
+fn wrong_code(code: &str) -> String {
+    format!("{:06}", (code.parse::<u32>().unwrap() + 1) % 1_000_000)
+}
+
+// A user whose verified phone number makes every sign-in need an SMS code
+async fn create_user_with_phone(
+    pool: &PgPool,
+    otp_service: &OtpService,
+    sms_sender: &MockSmsSender,
+) -> (User, String) {
+    let user = User::new_with_params(
+        None,
+        format!("otp-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    let user = UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap();
+
+    let digits = Uuid::new_v4().as_u128() % 10_000_000_000;
+    let phone_number = format!("+49{:010}", digits);
+    otp_service
+        .start_phone_verification(user.id, &phone_number)
+        .await
+        .unwrap();
+    otp_service
+        .confirm_phone(user.id, &last_code(sms_sender, &phone_number))
+        .await
+        .unwrap();
+
+    (user, phone_number)
+}
+
+// Returns the challenge id from a login that stopped for an SMS code
+async fn start_login(app: &Router, email: &str) -> String {
+    let (status, body) = send(
+        app,
+        "/api/auth/login",
+        json!({ "email": email, "password": PASSWORD }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert!(body.get("tokens").is_none());
+    body["challenge_id"].as_str().unwrap().to_string()
+}
+
+async fn submit_code(app: &Router, challenge_id: &str, code: &str) -> (StatusCode, Value) {
+    send(
+        app,
+        "/api/auth/login/otp",
+        json!({ "challenge_id": challenge_id, "code": code }),
+    )
+    .await
+}
+
+#[derive(Default)]
+struct RecordingSink {
+    events: Mutex<Vec<SecurityEvent>>,
+}
+
+impl SecurityEventSink for RecordingSink {
+    fn record(&self, event: &SecurityEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_login_with_sms_code() {
+    let pool = setup_test_pool().await;
+    let (app, otp_service, sms_sender) = create_test_app(&pool, "192.168.5.1");
+    let (user, phone_number) = create_user_with_phone(&pool, &otp_service, &sms_sender).await;
+
+    let challenge_id = start_login(&app, &user.email).await;
+    let code = last_code(&sms_sender, &phone_number);
+
+    let (status, body) = submit_code(&app, &challenge_id, &code).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user"]["email"], user.email.as_str());
+    assert!(body["tokens"]["access_token"].is_string());
+
+    // A code only works once
+    let (status, _) = submit_code(&app, &challenge_id, &code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Without a verified number the password is enough again
+    otp_service.remove_phone(user.id).await.unwrap();
+    let (status, body) = send(
+        &app,
+        "/api/auth/login",
+        json!({ "email": user.email, "password": PASSWORD }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["tokens"]["access_token"].is_string());
+}
+
+#[tokio::test]
+async fn test_too_many_wrong_codes_invalidate_challenge() {
+    let pool = setup_test_pool().await;
+    let (app, otp_service, sms_sender) = create_test_app(&pool, "192.168.5.2");
+    let (user, phone_number) = create_user_with_phone(&pool, &otp_service, &sms_sender).await;
+    let sink = Arc::new(RecordingSink::default());
+    add_security_event_sink(sink.clone());
+
+    let challenge_id = start_login(&app, &user.email).await;
+    let code = last_code(&sms_sender, &phone_number);
+
+    for _ in 0..4 {
+        let (status, body) = submit_code(&app, &challenge_id, &wrong_code(&code)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "INVALID_CODE");
+    }
+    let (status, _) = submit_code(&app, &challenge_id, &wrong_code(&code)).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // The right code is no use once the challenge is gone
+    let (status, _) = submit_code(&app, &challenge_id, &code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let events = sink.events.lock().unwrap();
+    assert!(events.iter().any(|event| {
+        event.event_type == "suspicious_activity"
+            && event
+                .details
+                .as_deref()
+                .is_some_and(|details| details.contains(&challenge_id))
+    }));
+}
+
+#[tokio::test]
+async fn test_expired_code_is_rejected() {
+    let pool = setup_test_pool().await;
+    let (app, otp_service, sms_sender) = create_test_app(&pool, "192.168.5.3");
+    let (user, phone_number) = create_user_with_phone(&pool, &otp_service, &sms_sender).await;
+
+    let challenge_id = start_login(&app, &user.email).await;
+    let code = last_code(&sms_sender, &phone_number);
+
+    sqlx::query("UPDATE otp_challenges SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(Uuid::parse_str(&challenge_id).unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let (status, body) = submit_code(&app, &challenge_id, &code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "CODE_EXPIRED");
+    let (status, _) = submit_code(&app, &challenge_id, &code).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // A fresh login sends a new code that works
+    let challenge_id = start_login(&app, &user.email).await;
+    let (status, _) =
+        submit_code(&app, &challenge_id, &last_code(&sms_sender, &phone_number)).await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,142 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware,
+};
+use chronos::app::middleware::rate_limiter::RateLimitConfig;
+use chronos::app::middleware::security::{SecurityState, require_json_content_type};
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// The auth routes as create_router builds them with STRICT_CONTENT_TYPE=true
+fn create_strict_app(pool: &PgPool) -> Router {
+    let auth_service = AuthService::new(
+        UserRepository::new(pool.clone()),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(UserRepository::new(pool.clone())),
+        SecurityState::new(RateLimitConfig::default()),
+    );
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    let auth_routes = auth::routes()
+        .with_state(state)
+        .layer(middleware::from_fn(require_json_content_type));
+    Router::new()
+        .nest("/api/auth", auth_routes)
+        .layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()))
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn strength_check(content_type: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder()
+        .uri("/api/auth/password/strength")
+        .method("POST");
+    if let Some(content_type) = content_type {
+        request = request.header("content-type", content_type);
+    }
+    request
+        .body(Body::from(
+            json!({ "password": "StrongP@ssw0rd123" }).to_string(),
+        ))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_json_content_type_passes_in_strict_mode() {
+    let pool = setup_test_pool().await;
+    let app = create_strict_app(&pool);
+
+    let (status, _) = send(&app, strength_check(Some("application/json"))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(
+        &app,
+        strength_check(Some("application/json; charset=utf-8")),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // GET routes have no body to check
+    let request = Request::builder()
+        .uri("/api/auth/csrf")
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_ne!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
+
+#[tokio::test]
+async fn test_missing_or_wrong_content_type_is_rejected_in_strict_mode() {
+    let pool = setup_test_pool().await;
+    let app = create_strict_app(&pool);
+
+    for content_type in [None, Some("text/plain")] {
+        let (status, body) = send(&app, strength_check(content_type)).await;
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        assert_eq!(body["error"]["code"], "UNSUPPORTED_MEDIA_TYPE");
+    }
+
+    // A body-less refresh, as cookie sessions send it, is not a JSON body
+    let request = Request::builder()
+        .uri("/api/auth/refresh")
+        .method("POST")
+        .body(Body::empty())
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_ne!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+}
@@ -0,0 +1,65 @@
+use chronos::app::task_health::{TaskHeartbeat, TaskStatus};
+use std::time::Duration;
+use time::OffsetDateTime;
+
+#[test]
+fn test_task_is_healthy_after_heartbeat() {
+    let heartbeat = TaskHeartbeat::new("cleanup", Duration::from_secs(60));
+    heartbeat.beat(7);
+
+    let health = heartbeat.health();
+    assert_eq!(health.status, TaskStatus::Healthy);
+    assert_eq!(health.last_removed, Some(7));
+    assert!(health.last_run.is_some());
+    assert!(heartbeat.is_healthy());
+}
+
+#[test]
+fn test_stalled_task_is_reported_degraded() {
+    let heartbeat =
+        TaskHeartbeat::new("cleanup", Duration::from_secs(60)).with_margin(Duration::from_secs(30));
+    heartbeat.beat(3);
+
+    let now = OffsetDateTime::now_utc();
+    // Still within interval plus margin
+    assert_eq!(
+        heartbeat
+            .health_at(now + time::Duration::seconds(80))
+            .status,
+        TaskStatus::Healthy
+    );
+
+    // The task missed its slot: degraded, but the last run is still reported
+    let health = heartbeat.health_at(now + time::Duration::seconds(120));
+    assert_eq!(health.status, TaskStatus::Degraded);
+    assert_eq!(health.last_removed, Some(3));
+}
+
+#[test]
+fn test_task_that_never_ran_degrades_after_first_interval() {
+    let heartbeat =
+        TaskHeartbeat::new("cleanup", Duration::from_secs(60)).with_margin(Duration::from_secs(30));
+    let now = OffsetDateTime::now_utc();
+
+    let health = heartbeat.health_at(now);
+    assert_eq!(health.status, TaskStatus::Starting);
+    assert_eq!(health.last_run, None);
+
+    assert_eq!(
+        heartbeat
+            .health_at(now + time::Duration::seconds(120))
+            .status,
+        TaskStatus::Degraded
+    );
+}
+
+#[test]
+fn test_health_serializes_for_health_endpoint() {
+    let heartbeat = TaskHeartbeat::new("cleanup", Duration::from_secs(60));
+    let json = serde_json::to_value(heartbeat.health()).unwrap();
+
+    assert_eq!(json["name"], "cleanup");
+    assert_eq!(json["status"], "starting");
+    assert_eq!(json["interval_secs"], 60);
+    assert!(json["last_run"].is_null());
+}
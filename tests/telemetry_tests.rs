@@ -0,0 +1,27 @@
+use chronos::app::telemetry::{self, hash_user_id};
+use uuid::Uuid;
+
+#[test]
+fn test_init_without_otlp_endpoint() {
+    // SAFETY: this is the only test in the binary that touches the environment
+    unsafe { std::env::remove_var("OTEL_EXPORTER_OTLP_ENDPOINT") };
+    assert!(telemetry::otlp_endpoint().is_none());
+
+    let guard = telemetry::init().expect("Tracing should initialize without an OTLP endpoint");
+    assert!(!guard.otlp_enabled());
+
+    // Spans are still created and recorded locally
+    let span = tracing::info_span!("auth.login", outcome = "success");
+    let _entered = span.enter();
+    tracing::info!("telemetry smoke test");
+}
+
+#[test]
+fn test_hashed_user_id_is_stable_and_opaque() {
+    let user_id = Uuid::new_v4();
+
+    assert_eq!(hash_user_id(user_id), hash_user_id(user_id));
+    assert_ne!(hash_user_id(user_id), hash_user_id(Uuid::new_v4()));
+    assert_eq!(hash_user_id(user_id).len(), 16);
+    assert_ne!(hash_user_id(user_id), user_id.simple().to_string()[..16]);
+}
@@ -0,0 +1,308 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode, header},
+    middleware,
+    response::Response,
+};
+use chronos::app::cookies::{AppEnvironment, CookiePolicy, TokenDelivery};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::auth_middleware::jwt_auth_middleware_with_json_errors;
+use chronos::app::middleware::security::{CSRF_HEADER, SecurityState, csrf_protection};
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "DeliveryPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Wired like create_router does for TOKEN_DELIVERY, with production cookies
+fn create_test_app(pool: &PgPool, delivery: TokenDelivery) -> Router {
+    let cookie_policy =
+        CookiePolicy::for_environment(AppEnvironment::Production).with_token_delivery(delivery);
+    let user_repository = UserRepository::new(pool.clone());
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    );
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_access_cookie(
+        delivery
+            .uses_cookies()
+            .then(|| cookie_policy.access_cookie_name()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service.clone(),
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    )
+    .with_cookie_policy(cookie_policy.clone());
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state.clone()))
+        .nest(
+            "/api/auth",
+            auth::protected_routes()
+                .with_state(state)
+                .layer(middleware::from_fn_with_state(
+                    Arc::new(jwt_service),
+                    jwt_auth_middleware_with_json_errors,
+                )),
+        )
+        .layer(middleware::from_fn_with_state(
+            cookie_policy,
+            csrf_protection,
+        ))
+        .layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()))
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("delivery-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// Full Set-Cookie values of the response
+fn set_cookies(response: &Response) -> Vec<String> {
+    response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .map(|value| value.to_str().unwrap().to_string())
+        .collect()
+}
+
+// `name=value` of the Set-Cookie header for `name`
+fn cookie_pair(cookies: &[String], name: &str) -> Option<String> {
+    cookies
+        .iter()
+        .map(|cookie| cookie.split(';').next().unwrap())
+        .find(|pair| pair.starts_with(&format!("{}=", name)))
+        .map(str::to_string)
+}
+
+async fn json_body(response: Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&body).unwrap_or(Value::Null)
+}
+
+async fn login(app: &Router, email: &str) -> (Vec<String>, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": PASSWORD }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let cookies = set_cookies(&response);
+    (cookies, json_body(response).await)
+}
+
+fn profile_request(cookie: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder().uri("/api/auth/profile");
+    if let Some(cookie) = cookie {
+        builder = builder.header(header::COOKIE, cookie);
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_body_delivery_keeps_tokens_in_body() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, TokenDelivery::Body);
+    let user = create_user(&pool).await;
+
+    let (cookies, body) = login(&app, &user.email).await;
+
+    assert!(cookies.is_empty());
+    assert!(body["tokens"]["access_token"].is_string());
+    assert!(body["tokens"]["refresh_token"].is_string());
+    assert!(body.get("csrf_token").is_none());
+
+    // Without cookie delivery an access token cookie is ignored
+    let cookie = format!(
+        "access_token={}",
+        body["tokens"]["access_token"].as_str().unwrap()
+    );
+    let response = app
+        .clone()
+        .oneshot(profile_request(Some(&cookie)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_cookie_delivery_moves_tokens_into_cookies() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, TokenDelivery::Cookie);
+    let user = create_user(&pool).await;
+
+    let (cookies, body) = login(&app, &user.email).await;
+
+    assert!(body["tokens"].get("access_token").is_none());
+    assert!(body["tokens"].get("refresh_token").is_none());
+    assert!(body["tokens"]["expires_in"].is_number());
+    let csrf_token = body["csrf_token"].as_str().unwrap().to_string();
+    let access_cookie = cookies
+        .iter()
+        .find(|cookie| cookie.starts_with("access_token="))
+        .expect("access token cookie should be set");
+    for attribute in ["HttpOnly", "SameSite=Strict", "Secure", "Max-Age=900"] {
+        assert!(access_cookie.contains(attribute), "{}", access_cookie);
+    }
+
+    // The middleware takes the access token from its cookie
+    let access = cookie_pair(&cookies, "access_token").unwrap();
+    let response = app
+        .clone()
+        .oneshot(profile_request(Some(&access)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(json_body(response).await["email"], user.email);
+
+    let response = app.clone().oneshot(profile_request(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Refreshing reads the refresh cookie and answers with new cookies only
+    let refresh = cookie_pair(&cookies, "refresh_token").unwrap();
+    let csrf = cookie_pair(&cookies, "csrf_token").unwrap();
+    let request = Request::builder()
+        .uri("/api/auth/refresh")
+        .method("POST")
+        .header(header::COOKIE, format!("{}; {}", refresh, csrf))
+        .header(CSRF_HEADER, &csrf_token)
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let refreshed = set_cookies(&response);
+    let body = json_body(response).await;
+    assert!(body.get("access_token").is_none());
+    assert!(body["refresh_token"].is_null());
+    let new_access = cookie_pair(&refreshed, "access_token").unwrap();
+    assert_ne!(new_access, access);
+    assert!(cookie_pair(&refreshed, "refresh_token").is_some());
+
+    let response = app
+        .clone()
+        .oneshot(profile_request(Some(&new_access)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Logout finds the token in its cookie and clears it
+    let request = Request::builder()
+        .uri("/api/auth/logout")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header(header::COOKIE, format!("{}; {}", new_access, csrf))
+        .header(CSRF_HEADER, &csrf_token)
+        .body(Body::from("{}"))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let cleared = set_cookies(&response);
+    assert!(
+        cleared
+            .iter()
+            .any(|cookie| cookie.starts_with("access_token=;") && cookie.contains("Max-Age=0"))
+    );
+
+    let response = app
+        .oneshot(profile_request(Some(&new_access)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_both_delivery_sets_cookies_and_keeps_body() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, TokenDelivery::Both);
+    let user = create_user(&pool).await;
+
+    let (cookies, body) = login(&app, &user.email).await;
+
+    let access_token = body["tokens"]["access_token"].as_str().unwrap();
+    let refresh_token = body["tokens"]["refresh_token"].as_str().unwrap();
+    assert_eq!(
+        cookie_pair(&cookies, "access_token").unwrap(),
+        format!("access_token={}", access_token)
+    );
+    assert_eq!(
+        cookie_pair(&cookies, "refresh_token").unwrap(),
+        format!("refresh_token={}", refresh_token)
+    );
+
+    // Either way of sending the token works
+    let request = Request::builder()
+        .uri("/api/auth/profile")
+        .header("authorization", format!("Bearer {}", access_token))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let access = cookie_pair(&cookies, "access_token").unwrap();
+    let response = app.oneshot(profile_request(Some(&access))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
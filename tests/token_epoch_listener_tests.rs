@@ -0,0 +1,79 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::token_epoch_listener::TokenEpochListener;
+use chronos::app::token_epochs::TokenEpochCache;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use std::time::Duration;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("epoch-notify-{}@example.com", Uuid::new_v4().simple()),
+        "EpochPass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+// Waits for the listener to evict `user_id`, which normally takes milliseconds
+async fn wait_for_eviction(cache: &TokenEpochCache, user_id: Uuid) -> bool {
+    for _ in 0..50 {
+        if cache.get(user_id).is_none() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    false
+}
+
+#[tokio::test]
+async fn test_epoch_change_evicts_cached_value_on_other_instances() {
+    let pool = setup_test_pool().await;
+    let user = create_user(&pool).await;
+    let bystander = create_user(&pool).await;
+
+    // The cache of a server that did not make the change; its TTL alone would
+    // keep the stale value for an hour
+    let cache = TokenEpochCache::new(Duration::from_secs(3600));
+    cache.insert(user.id, None);
+    cache.insert(bystander.id, None);
+
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let listener = TokenEpochListener::connect(&pool, cache.clone())
+        .await
+        .expect("Failed to listen for token epoch changes")
+        .spawn(shutdown_rx);
+
+    let epoch = UserRepository::new(pool.clone())
+        .invalidate_tokens(user.id)
+        .await
+        .unwrap();
+    assert!(epoch.is_some());
+
+    assert!(
+        wait_for_eviction(&cache, user.id).await,
+        "cached epoch should be dropped after the notification"
+    );
+    assert_eq!(cache.get(bystander.id), Some(None));
+
+    shutdown_tx.send(true).unwrap();
+    listener.await.unwrap();
+}
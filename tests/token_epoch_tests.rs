@@ -0,0 +1,215 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "EpochPass123!";
+const NEW_PASSWORD: &str = "EpochPass456!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Each test gets its own IP so failed logins don't add up across tests
+fn create_test_app(pool: PgPool, ip: &str) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn post_json(uri: &str, token: Option<&str>, body: Value) -> Request<Body> {
+    let mut request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json");
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("epoch-{}@example.com", Uuid::new_v4().simple()),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn create_admin(pool: &PgPool) -> User {
+    let user = create_user(pool).await;
+    let role_repository = RoleRepository::new(pool.clone());
+    let admin_role = role_repository
+        .find_by_name("admin")
+        .await
+        .unwrap()
+        .expect("admin role should be seeded");
+    role_repository
+        .grant_role(user.id, admin_role.id)
+        .await
+        .unwrap();
+    user
+}
+
+// Returns the access and refresh token of a fresh login
+async fn login(app: &axum::Router, email: &str, password: &str) -> (String, String) {
+    let (status, body) = send(
+        app,
+        post_json(
+            "/api/auth/login",
+            None,
+            json!({ "email": email, "password": password }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    (
+        body["tokens"]["access_token"].as_str().unwrap().to_string(),
+        body["tokens"]["refresh_token"]
+            .as_str()
+            .unwrap()
+            .to_string(),
+    )
+}
+
+async fn get_profile(app: &axum::Router, token: &str) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri("/api/auth/profile")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    send(app, request).await
+}
+
+// Token iat has whole seconds, so let the clock move past the login's second
+async fn wait_for_next_second() {
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+}
+
+#[tokio::test]
+async fn test_password_change_rejects_earlier_access_tokens() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.6.1");
+    let user = create_user(&pool).await;
+
+    let (token, _) = login(&app, &user.email, TEST_PASSWORD).await;
+    let (other_device_token, other_device_refresh) = login(&app, &user.email, TEST_PASSWORD).await;
+    // Caches the user's epoch, which the password change has to replace
+    let (status, _) = get_profile(&app, &other_device_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    wait_for_next_second().await;
+    let (status, _) = send(
+        &app,
+        post_json(
+            "/api/auth/change-password",
+            Some(&token),
+            json!({ "current_password": TEST_PASSWORD, "new_password": NEW_PASSWORD }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Both tokens are well within their 15 minutes but were issued before the change
+    for token in [&token, &other_device_token] {
+        let (status, body) = get_profile(&app, token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "TOKEN_REVOKED");
+    }
+    let (status, _) = send(
+        &app,
+        post_json(
+            "/api/auth/refresh",
+            None,
+            json!({ "refresh_token": other_device_refresh }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Logging in again straight away works
+    let (token, _) = login(&app, &user.email, NEW_PASSWORD).await;
+    let (status, body) = get_profile(&app, &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["email"], user.email.as_str());
+}
+
+#[tokio::test]
+async fn test_admin_revocation_rejects_earlier_access_tokens() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.6.2");
+    let admin = create_admin(&pool).await;
+    let user = create_user(&pool).await;
+    let (admin_token, _) = login(&app, &admin.email, TEST_PASSWORD).await;
+    let (user_token, _) = login(&app, &user.email, TEST_PASSWORD).await;
+
+    let (status, _) = get_profile(&app, &user_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    wait_for_next_second().await;
+    let (status, body) = send(
+        &app,
+        post_json(
+            &format!("/api/admin/users/{}/revoke-tokens", user.id),
+            Some(&admin_token),
+            json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["revoked_sessions"], 1);
+    assert_eq!(body["complete"], true);
+
+    let (status, body) = get_profile(&app, &user_token).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_REVOKED");
+
+    // Only the target user is affected
+    let (status, _) = get_profile(&app, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(
+        &app,
+        post_json(
+            &format!("/api/admin/users/{}/revoke-tokens", Uuid::new_v4()),
+            Some(&admin_token),
+            json!({}),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
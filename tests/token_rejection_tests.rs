@@ -0,0 +1,215 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode, header},
+};
+use chronos::app::models::jwt::{Claims, TokenType};
+use chronos::app::models::scope::default_scopes;
+use chronos::app::services::jwt_service::get_jwt_secret;
+use chronos::routes;
+use dotenvy::dotenv;
+use jsonwebtoken::{EncodingKey, Header, encode};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use time::OffsetDateTime;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "RejectPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: PgPool) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        "192.168.1.60:8080".parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+// Status, error code and WWW-Authenticate challenge of a response
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value, Option<String>) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let challenge = response
+        .headers()
+        .get(header::WWW_AUTHENTICATE)
+        .map(|value| value.to_str().unwrap().to_string());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+        challenge,
+    )
+}
+
+fn profile_request(authorization: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().uri("/api/auth/profile").method("GET");
+    if let Some(authorization) = authorization {
+        request = request.header("authorization", authorization);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+// Register and log in a fresh user, returning the access token
+async fn login_new_user(app: &axum::Router) -> String {
+    let email = format!("reject_{}@example.com", Uuid::new_v4().simple());
+    let credentials = json!({ "email": email, "password": TEST_PASSWORD }).to_string();
+
+    let register = Request::builder()
+        .uri("/api/auth/register")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(credentials.clone()))
+        .unwrap();
+    assert_eq!(send(app, register).await.0, StatusCode::CREATED);
+
+    let login = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(credentials))
+        .unwrap();
+    let (status, body, _) = send(app, login).await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+// Correctly signed access token that expired an hour ago, well past the validation leeway
+fn expired_access_token() -> String {
+    let now = OffsetDateTime::now_utc().unix_timestamp() as usize;
+    let claims = Claims {
+        sub: Uuid::new_v4().to_string(),
+        email: "expired@example.com".to_string(),
+        roles: vec!["user".to_string()],
+        exp: now - 3600,
+        iat: now - 7200,
+        jti: Uuid::new_v4().to_string(),
+        token_type: TokenType::Access,
+        nbf: now - 7200,
+        sid: None,
+        scopes: default_scopes(),
+        aud: Vec::new(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(get_jwt_secret().as_bytes()),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_valid_token_reaches_profile() {
+    let app = create_test_app(setup_test_pool().await);
+    let access_token = login_new_user(&app).await;
+
+    let (status, _, challenge) = send(
+        &app,
+        profile_request(Some(&format!("Bearer {}", access_token))),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(challenge, None);
+}
+
+#[tokio::test]
+async fn test_expired_token_is_reported_as_expired() {
+    let app = create_test_app(setup_test_pool().await);
+
+    let (status, body, challenge) = send(
+        &app,
+        profile_request(Some(&format!("Bearer {}", expired_access_token()))),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_EXPIRED");
+    assert_eq!(
+        challenge.as_deref(),
+        Some(r#"Bearer error="invalid_token", error_description="Token has expired""#)
+    );
+}
+
+#[tokio::test]
+async fn test_logged_out_token_is_reported_as_revoked() {
+    let app = create_test_app(setup_test_pool().await);
+    let access_token = login_new_user(&app).await;
+    let authorization = format!("Bearer {}", access_token);
+
+    let logout = Request::builder()
+        .uri("/api/auth/logout")
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("authorization", &authorization)
+        .body(Body::from(json!({}).to_string()))
+        .unwrap();
+    assert_eq!(send(&app, logout).await.0, StatusCode::OK);
+
+    let (status, body, challenge) = send(&app, profile_request(Some(&authorization))).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_REVOKED");
+    assert_eq!(
+        challenge.as_deref(),
+        Some(r#"Bearer error="invalid_token", error_description="Token has been revoked""#)
+    );
+}
+
+#[tokio::test]
+async fn test_malformed_and_forged_tokens_are_reported_as_invalid() {
+    let app = create_test_app(setup_test_pool().await);
+
+    let (status, body, challenge) = send(&app, profile_request(Some("Bearer not-a-jwt"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_INVALID");
+    assert_eq!(
+        challenge.as_deref(),
+        Some(r#"Bearer error="invalid_token", error_description="Invalid token""#)
+    );
+
+    // A well-formed token signed with another key must not count as merely expired
+    let forged = {
+        let mut parts: Vec<String> = expired_access_token()
+            .split('.')
+            .map(str::to_string)
+            .collect();
+        parts[2] = "c2lnbmF0dXJl".to_string();
+        parts.join(".")
+    };
+    let (status, body, _) = send(&app, profile_request(Some(&format!("Bearer {}", forged)))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_INVALID");
+
+    let (status, body, challenge) = send(&app, profile_request(Some("Basic dXNlcjpwYXNz"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_INVALID");
+    assert_eq!(
+        challenge.as_deref(),
+        Some(
+            r#"Bearer error="invalid_request", error_description="Invalid authorization header format""#
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_missing_token_gets_bare_challenge() {
+    let app = create_test_app(setup_test_pool().await);
+
+    let (status, body, challenge) = send(&app, profile_request(None)).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "TOKEN_MISSING");
+    assert_eq!(challenge.as_deref(), Some("Bearer"));
+}
@@ -0,0 +1,173 @@
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::jwt::{BlacklistedToken, TokenType};
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::RefreshTokenRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::transaction::with_transaction;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::jwt_service::JwtService;
+use dotenvy::dotenv;
+use sqlx::PgPool;
+use std::env;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        format!("transaction-{}@example.com", Uuid::new_v4().simple()),
+        "TransactionPass123!",
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+fn jwt_service(pool: &PgPool) -> JwtService {
+    JwtService::new(
+        "test_secret_key_at_least_256_bits_long",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+}
+
+// Makes the database reject `operation` (UPDATE, DELETE, ...) on `table` for
+// rows matching `condition`; returns the SQL that removes the trigger again
+async fn fail_on(pool: &PgPool, operation: &str, table: &str, condition: &str) -> String {
+    let trigger = format!("fail_tx_{}", Uuid::new_v4().simple());
+    sqlx::raw_sql(&format!(
+        r#"
+        CREATE FUNCTION {trigger}() RETURNS trigger AS $$
+        BEGIN RAISE EXCEPTION 'simulated failure'; END
+        $$ LANGUAGE plpgsql;
+        CREATE TRIGGER {trigger} BEFORE {operation} ON {table}
+        FOR EACH ROW WHEN ({condition}) EXECUTE FUNCTION {trigger}();
+        "#
+    ))
+    .execute(pool)
+    .await
+    .unwrap();
+    format!("DROP TRIGGER {trigger} ON {table}; DROP FUNCTION {trigger}();")
+}
+
+async fn active_sessions(pool: &PgPool, user: &User) -> usize {
+    RefreshTokenRepository::new(pool.clone())
+        .find_active_by_user(user.id)
+        .await
+        .unwrap()
+        .len()
+}
+
+#[tokio::test]
+async fn test_failed_second_step_rolls_back_the_first() {
+    let pool = setup_test_pool().await;
+    let user = create_user(&pool).await;
+    let blacklist = TokenBlacklistRepository::new(pool.clone());
+    let entry = BlacklistedToken::new(
+        Uuid::new_v4().to_string(),
+        user.id,
+        TokenType::Access,
+        OffsetDateTime::now_utc() + Duration::hours(1),
+    );
+
+    let result: Result<(), sqlx::Error> = with_transaction(&pool, |tx| {
+        Box::pin(async {
+            blacklist.blacklist_token_tx(tx, &entry).await?;
+            Err(sqlx::Error::RowNotFound)
+        })
+    })
+    .await;
+
+    assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+    assert!(!blacklist.is_blacklisted(&entry.jti).await.unwrap());
+
+    // The same steps without the failure are committed
+    with_transaction(&pool, |tx| {
+        Box::pin(async { blacklist.blacklist_token_tx(tx, &entry).await })
+    })
+    .await
+    .unwrap();
+    assert!(blacklist.is_blacklisted(&entry.jti).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_logout_all_devices_is_all_or_nothing() {
+    let pool = setup_test_pool().await;
+    let jwt_service = jwt_service(&pool);
+    let user = create_user(&pool).await;
+
+    let current = jwt_service.generate_token_pair(&user).await.unwrap();
+    jwt_service.generate_token_pair(&user).await.unwrap();
+    let current_jti = jwt_service
+        .decode_token_without_validation(&current.access_token)
+        .unwrap()
+        .jti;
+
+    // Blacklisting runs first, so the failure has to undo it
+    let drop_trigger = fail_on(
+        &pool,
+        "UPDATE",
+        "refresh_tokens",
+        &format!("OLD.user_id = '{}'", user.id),
+    )
+    .await;
+    let outcome = jwt_service
+        .logout_all_devices(&current.access_token, user.id)
+        .await;
+    sqlx::raw_sql(&drop_trigger).execute(&pool).await.unwrap();
+
+    assert!(!outcome.complete);
+    assert_eq!(outcome.revoked, 0);
+    assert!(
+        outcome
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("simulated failure")
+    );
+    let blacklist = TokenBlacklistRepository::new(pool.clone());
+    assert!(!blacklist.is_blacklisted(&current_jti).await.unwrap());
+    assert_eq!(active_sessions(&pool, &user).await, 2);
+
+    // Repeating the request finishes the job
+    let retry = jwt_service
+        .logout_all_devices(&current.access_token, user.id)
+        .await;
+    assert!(retry.complete);
+    assert_eq!(retry.revoked, 2);
+    assert!(blacklist.is_blacklisted(&current_jti).await.unwrap());
+    assert_eq!(active_sessions(&pool, &user).await, 0);
+}
+
+#[tokio::test]
+async fn test_failed_account_deletion_keeps_everything() {
+    let pool = setup_test_pool().await;
+    let jwt_service = jwt_service(&pool);
+    let user = create_user(&pool).await;
+    jwt_service.generate_token_pair(&user).await.unwrap();
+    let users = UserRepository::new(pool.clone());
+
+    // Sessions are deleted before the user row, which is the step that fails
+    let drop_trigger = fail_on(&pool, "DELETE", "users", &format!("OLD.id = '{}'", user.id)).await;
+    let result = users.delete_account(user.id).await;
+    sqlx::raw_sql(&drop_trigger).execute(&pool).await.unwrap();
+
+    assert!(result.is_err());
+    assert!(users.find_by_id(user.id).await.unwrap().is_some());
+    assert_eq!(active_sessions(&pool, &user).await, 1);
+
+    assert!(users.delete_account(user.id).await.unwrap());
+    assert!(users.find_by_id(user.id).await.unwrap().is_none());
+}
@@ -0,0 +1,421 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{HeaderMap, Request, StatusCode, header},
+    middleware,
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::middleware::auth_middleware::jwt_auth_middleware_with_json_errors;
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::models::user::User;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::otp_challenge_repository::OtpChallengeRepository;
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::trusted_device_repository::TrustedDeviceRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::{MockEmailService, NotificationSettings};
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::otp_service::OtpService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::sms_service::MockSmsSender;
+use chronos::app::services::trusted_device_service::TrustedDeviceService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "TrustedPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// SMS codes go to an in-memory sender and devices stay trusted for 30 days;
+// each app gets its own client IP
+fn create_test_app(pool: &PgPool) -> (Router, OtpService, MockSmsSender) {
+    let user_repository = UserRepository::new(pool.clone());
+    let sms_sender = MockSmsSender::new();
+    let otp_service = OtpService::new(
+        Arc::new(sms_sender.clone()),
+        OtpChallengeRepository::new(pool.clone()),
+        user_repository.clone(),
+    )
+    .with_argon2_params(Argon2Params::new(1024, 1, 1).unwrap());
+
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    )
+    .with_notification_settings(NotificationSettings::disabled());
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    )
+    .with_trusted_device_repository(TrustedDeviceRepository::new(pool.clone()));
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    )
+    .with_otp_service(otp_service.clone())
+    .with_trusted_device_service(TrustedDeviceService::new(
+        TrustedDeviceRepository::new(pool.clone()),
+        time::Duration::days(30),
+    ));
+
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service.clone(),
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    )
+    .with_otp_service(otp_service.clone());
+
+    let bytes = Uuid::new_v4().into_bytes();
+    let addr = format!("10.{}.{}.{}:8080", bytes[0], bytes[1], bytes[2]);
+    let app = Router::new()
+        .nest("/api/auth", auth::routes().with_state(state.clone()))
+        .nest(
+            "/api/auth",
+            auth::protected_routes()
+                .with_state(state)
+                .layer(middleware::from_fn_with_state(
+                    Arc::new(jwt_service),
+                    jwt_auth_middleware_with_json_errors,
+                )),
+        )
+        .layer(MockConnectInfo(addr.parse::<SocketAddr>().unwrap()));
+
+    (app, otp_service, sms_sender)
+}
+
+async fn send(app: &Router, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        headers,
+        serde_json::from_slice(&body).unwrap_or(Value::Null),
+    )
+}
+
+// The only six digit word of the last SMS sent to `to`
+fn last_code(sms_sender: &MockSmsSender, to: &str) -> String {
+    let message = sms_sender
+        .get_sent_messages()
+        .into_iter()
+        .rev()
+        .find(|message| message.to == to)
+        .expect("no SMS sent");
+    message
+        .body
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|word| word.len() == 6)
+        .expect("no code in SMS")
+        .to_string()
+}
+
+// A user whose verified phone number makes every sign-in need an SMS code
+async fn create_user_with_phone(
+    pool: &PgPool,
+    otp_service: &OtpService,
+    sms_sender: &MockSmsSender,
+) -> (User, String) {
+    let user = User::new_with_params(
+        None,
+        format!("trusted-{}@example.com", Uuid::new_v4().simple()),
+        PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    let user = UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap();
+
+    let digits = Uuid::new_v4().as_u128() % 10_000_000_000;
+    let phone_number = format!("+49{:010}", digits);
+    otp_service
+        .start_phone_verification(user.id, &phone_number)
+        .await
+        .unwrap();
+    otp_service
+        .confirm_phone(user.id, &last_code(sms_sender, &phone_number))
+        .await
+        .unwrap();
+
+    (user, phone_number)
+}
+
+// The value the response set the trusted device cookie to, if it set it
+fn trusted_device_cookie(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.strip_prefix("trusted_device="))
+        .map(|value| value.split(';').next().unwrap().to_string())
+        .next()
+}
+
+async fn login(
+    app: &Router,
+    email: &str,
+    trusted_device: Option<&str>,
+) -> (StatusCode, HeaderMap, Value) {
+    login_with_password(app, email, PASSWORD, trusted_device).await
+}
+
+async fn login_with_password(
+    app: &Router,
+    email: &str,
+    password: &str,
+    trusted_device: Option<&str>,
+) -> (StatusCode, HeaderMap, Value) {
+    let mut request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json");
+    if let Some(token) = trusted_device {
+        request = request.header(header::COOKIE, format!("trusted_device={}", token));
+    }
+    let body = json!({ "email": email, "password": password });
+    send(app, request.body(Body::from(body.to_string())).unwrap()).await
+}
+
+// Logs in with the SMS code, asking to trust the device; returns its cookie
+// and the access token
+async fn trust_device(
+    app: &Router,
+    sms_sender: &MockSmsSender,
+    email: &str,
+    phone_number: &str,
+) -> (String, String) {
+    trust_device_with_password(app, sms_sender, email, PASSWORD, phone_number).await
+}
+
+async fn trust_device_with_password(
+    app: &Router,
+    sms_sender: &MockSmsSender,
+    email: &str,
+    password: &str,
+    phone_number: &str,
+) -> (String, String) {
+    let (status, _, body) = login_with_password(app, email, password, None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let body = json!({
+        "challenge_id": body["challenge_id"],
+        "code": last_code(sms_sender, phone_number),
+        "trust_device": true,
+    });
+    let request = Request::builder()
+        .uri("/api/auth/login/otp")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let (status, headers, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let access_token = body["tokens"]["access_token"].as_str().unwrap().to_string();
+    (trusted_device_cookie(&headers).unwrap(), access_token)
+}
+
+fn authorized(method: &str, uri: String, access_token: &str, cookie: &str) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .method(method)
+        .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+        .header(header::COOKIE, format!("trusted_device={}", cookie))
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_trusted_device_skips_the_sms_code() {
+    let pool = setup_test_pool().await;
+    let (app, otp_service, sms_sender) = create_test_app(&pool);
+    let (user, phone_number) = create_user_with_phone(&pool, &otp_service, &sms_sender).await;
+
+    let (cookie, _) = trust_device(&app, &sms_sender, &user.email, &phone_number).await;
+    let sent = sms_sender.get_sent_messages().len();
+
+    let (status, headers, body) = login(&app, &user.email, Some(&cookie)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["tokens"]["access_token"].is_string());
+    assert_eq!(sms_sender.get_sent_messages().len(), sent);
+
+    // Each use replaces the token, so the old cookie no longer counts
+    let rotated = trusted_device_cookie(&headers).unwrap();
+    assert_ne!(rotated, cookie);
+    let (status, _, _) = login(&app, &user.email, Some(&cookie)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let (status, _, _) = login(&app, &user.email, Some(&rotated)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Without the cookie the code is asked for as before
+    let (status, _, _) = login(&app, &user.email, None).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn test_revoked_device_needs_the_sms_code_again() {
+    let pool = setup_test_pool().await;
+    let (app, otp_service, sms_sender) = create_test_app(&pool);
+    let (user, phone_number) = create_user_with_phone(&pool, &otp_service, &sms_sender).await;
+
+    let (cookie, access_token) = trust_device(&app, &sms_sender, &user.email, &phone_number).await;
+
+    let (status, _, body) = send(
+        &app,
+        authorized(
+            "GET",
+            "/api/auth/trusted-devices".to_string(),
+            &access_token,
+            &cookie,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let devices = body["devices"].as_array().unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0]["current"], true);
+    let id = devices[0]["id"].as_str().unwrap();
+
+    let (status, _, _) = send(
+        &app,
+        authorized(
+            "DELETE",
+            format!("/api/auth/trusted-devices/{}", id),
+            &access_token,
+            &cookie,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _, _) = login(&app, &user.email, Some(&cookie)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let (status, _, _) = send(
+        &app,
+        authorized(
+            "DELETE",
+            format!("/api/auth/trusted-devices/{}", id),
+            &access_token,
+            &cookie,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_trust_runs_out() {
+    let pool = setup_test_pool().await;
+    let (app, otp_service, sms_sender) = create_test_app(&pool);
+    let (user, phone_number) = create_user_with_phone(&pool, &otp_service, &sms_sender).await;
+
+    let (cookie, _) = trust_device(&app, &sms_sender, &user.email, &phone_number).await;
+    sqlx::query(
+        "UPDATE trusted_devices SET expires_at = NOW() - INTERVAL '1 minute' WHERE user_id = $1",
+    )
+    .bind(user.id)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let (status, _, _) = login(&app, &user.email, Some(&cookie)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+}
+
+fn authorized_json(uri: &str, access_token: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header(header::AUTHORIZATION, format!("Bearer {}", access_token))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_password_change_and_logout_all_forget_trusted_devices() {
+    let pool = setup_test_pool().await;
+    let (app, otp_service, sms_sender) = create_test_app(&pool);
+    let (user, phone_number) = create_user_with_phone(&pool, &otp_service, &sms_sender).await;
+
+    // Changing the password ends every session, the trust of devices included
+    let (cookie, access_token) = trust_device(&app, &sms_sender, &user.email, &phone_number).await;
+    let (status, _, _) = send(
+        &app,
+        authorized_json(
+            "/api/auth/change-password",
+            &access_token,
+            json!({ "current_password": PASSWORD, "new_password": "TrustedPass456!" }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) =
+        login_with_password(&app, &user.email, "TrustedPass456!", Some(&cookie)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    // So does logging out everywhere
+    let (cookie, access_token) = trust_device_with_password(
+        &app,
+        &sms_sender,
+        &user.email,
+        "TrustedPass456!",
+        &phone_number,
+    )
+    .await;
+    let (status, _, _) = send(
+        &app,
+        authorized_json(
+            "/api/auth/logout",
+            &access_token,
+            json!({ "logout_all_devices": true }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _, _) =
+        login_with_password(&app, &user.email, "TrustedPass456!", Some(&cookie)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+
+    let devices: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM trusted_devices WHERE user_id = $1")
+            .bind(user.id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(devices, 0);
+}
@@ -0,0 +1,215 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::models::user::{User, UserCursor};
+use chronos::app::repositories::role_repository::RoleRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::env;
+use std::net::SocketAddr;
+use time::OffsetDateTime;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Users sharing one created_at, so only the id can order them
+async fn create_users_at(
+    repository: &UserRepository,
+    created_at: OffsetDateTime,
+    count: usize,
+) -> Vec<Uuid> {
+    let mut ids = Vec::new();
+    for _ in 0..count {
+        let mut user = User::new(
+            None,
+            format!("listing-{}@example.com", Uuid::new_v4()),
+            "TestPass123!",
+        )
+        .unwrap();
+        user.created_at = Some(created_at);
+        ids.push(repository.create(&user).await.unwrap().id);
+    }
+    ids
+}
+
+// Logs in a fresh user, granting it the admin role first if asked
+async fn access_token(app: &axum::Router, pool: &PgPool, admin: bool) -> String {
+    let email = format!("listing-caller-{}@example.com", Uuid::new_v4());
+    let user = UserRepository::new(pool.clone())
+        .create(&User::new(None, email.clone(), "TestPass123!").unwrap())
+        .await
+        .unwrap();
+    if admin {
+        let role_repository = RoleRepository::new(pool.clone());
+        let admin_role = role_repository
+            .find_by_name("admin")
+            .await
+            .unwrap()
+            .expect("admin role should be seeded");
+        role_repository
+            .grant_role(user.id, admin_role.id)
+            .await
+            .unwrap();
+    }
+
+    let request = Request::builder()
+        .uri("/api/auth/login")
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({ "email": email, "password": "TestPass123!" }).to_string(),
+        ))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+fn get(uri: &str, token: &str) -> Request<Body> {
+    Request::builder()
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap()
+}
+
+// Spread runs of the test apart so their seeded blocks never share a timestamp
+fn rand_micros() -> i64 {
+    (Uuid::new_v4().as_u128() % 1_000_000_000) as i64
+}
+
+#[tokio::test]
+async fn test_keyset_pagination_survives_concurrent_inserts() {
+    let pool = setup_test_pool().await;
+    let repository = UserRepository::new(pool.clone());
+
+    // A timestamp far in the past so rows created by other tests sort after ours
+    let created_at = OffsetDateTime::from_unix_timestamp(946_684_800).unwrap()
+        + time::Duration::microseconds(rand_micros());
+    let seeded: HashSet<Uuid> = create_users_at(&repository, created_at, 5)
+        .await
+        .into_iter()
+        .collect();
+
+    // Start paging just above the seeded block, two users per page
+    let start = UserCursor {
+        created_at: created_at + time::Duration::microseconds(1),
+        id: Uuid::nil(),
+    };
+    let first_page = repository.list_after(Some(start), 2).await.unwrap();
+    assert_eq!(first_page.len(), 2);
+    let mut cursor = UserCursor::from_user(first_page.last().unwrap());
+    let mut seen: Vec<Uuid> = first_page.iter().map(|u| u.id).collect();
+
+    // Someone registers with the same timestamp while we are paging
+    let inserted = create_users_at(&repository, created_at, 1).await[0];
+
+    loop {
+        let page = repository.list_after(cursor, 2).await.unwrap();
+        let ours: Vec<Uuid> = page
+            .iter()
+            .filter(|u| u.created_at == Some(created_at))
+            .map(|u| u.id)
+            .collect();
+        seen.extend(&ours);
+        if ours.len() < page.len() || page.len() < 2 {
+            break;
+        }
+        cursor = UserCursor::from_user(page.last().unwrap());
+    }
+
+    // Every seeded user exactly once
+    let unique: HashSet<Uuid> = seen.iter().copied().collect();
+    assert_eq!(unique.len(), seen.len(), "a user was returned twice");
+    for id in &seeded {
+        assert!(unique.contains(id), "a seeded user was skipped");
+    }
+    // The late insert shows up at most once, depending on where its id sorts
+    assert!(seen.iter().filter(|id| **id == inserted).count() <= 1);
+}
+
+#[tokio::test]
+async fn test_users_listing_returns_next_link() {
+    let pool = setup_test_pool().await;
+    let repository = UserRepository::new(pool.clone());
+    create_users_at(&repository, OffsetDateTime::now_utc(), 2).await;
+    let app = routes::create_router(pool.clone()).layer(MockConnectInfo(
+        "192.168.13.1:8080".parse::<SocketAddr>().unwrap(),
+    ));
+    let token = access_token(&app, &pool, true).await;
+
+    let response = app
+        .clone()
+        .oneshot(get("/api/users?limit=1", &token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let link = response.headers()["link"].to_str().unwrap().to_string();
+    assert!(link.starts_with("</api/users?limit=1&after="));
+    assert!(link.ends_with(">; rel=\"next\""));
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let first: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(first.as_array().unwrap().len(), 1);
+    assert!(first[0].get("password_hash").is_none());
+
+    // Following the link yields the next user, not the same one again
+    let next = &link[1..link.find('>').unwrap()];
+    let response = app.clone().oneshot(get(next, &token)).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let second: Value = serde_json::from_slice(&body).unwrap();
+    assert_ne!(second[0]["id"], first[0]["id"]);
+
+    let response = app
+        .oneshot(get("/api/users?after=not-a-cursor", &token))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_users_routes_require_admin() {
+    let pool = setup_test_pool().await;
+    let app = routes::create_router(pool.clone()).layer(MockConnectInfo(
+        "192.168.13.2:8080".parse::<SocketAddr>().unwrap(),
+    ));
+
+    let request = Request::builder()
+        .uri("/api/users")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let token = access_token(&app, &pool, false).await;
+    for uri in [
+        "/api/users?limit=1",
+        &format!("/api/users/{}", Uuid::new_v4()),
+    ] {
+        let response = app.clone().oneshot(get(uri, &token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,187 @@
+use axum::{
+    Router,
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::middleware::security::SecurityState;
+use chronos::app::repositories::login_attempt_repository::{
+    AccountLockoutRepository, LoginAttemptRepository, RefreshTokenRepository,
+};
+use chronos::app::repositories::password_history_repository::PasswordHistoryRepository;
+use chronos::app::repositories::password_reset_repository::PasswordResetRepository;
+use chronos::app::repositories::token_blacklist_repository::TokenBlacklistRepository;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::app::services::auth_service::AuthService;
+use chronos::app::services::email_service::MockEmailService;
+use chronos::app::services::jwt_service::JwtService;
+use chronos::app::services::secure_login_service::SecureLoginService;
+use chronos::app::services::user_service::UserService;
+use chronos::routes::auth::{self, AuthAppState};
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const PASSWORD: &str = "UsernamePass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+fn create_test_app(pool: &PgPool, username_login: bool) -> Router {
+    let user_repository = UserRepository::new(pool.clone());
+
+    let auth_service = AuthService::new(
+        user_repository.clone(),
+        PasswordResetRepository::new(pool.clone()),
+        PasswordHistoryRepository::new(pool.clone()),
+        MockEmailService::new(),
+    )
+    .with_username_login(username_login);
+    let jwt_service = JwtService::new(
+        "test-secret-key",
+        TokenBlacklistRepository::new(pool.clone()),
+        RefreshTokenRepository::new(pool.clone()),
+    );
+    let secure_login_service = SecureLoginService::new(
+        auth_service.clone(),
+        jwt_service.clone(),
+        LoginAttemptRepository::new(pool.clone()),
+        AccountLockoutRepository::new(pool.clone()),
+    );
+
+    let state = AuthAppState::new(
+        auth_service,
+        jwt_service,
+        secure_login_service,
+        UserService::new(user_repository),
+        SecurityState::default(),
+    );
+    Router::new()
+        .nest("/api/auth", auth::routes().with_state(state))
+        .layer(MockConnectInfo(
+            "192.168.1.1:8080".parse::<SocketAddr>().unwrap(),
+        ))
+}
+
+// Unique per run, so tests don't collide on the users table
+fn unique_username() -> String {
+    format!("User_{}", &Uuid::new_v4().simple().to_string()[..12])
+}
+
+fn unique_email() -> String {
+    format!("username-{}@example.com", Uuid::new_v4().simple())
+}
+
+async fn post(app: &Router, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+async fn register(app: &Router, email: &str, username: &str) -> (StatusCode, Value) {
+    post(
+        app,
+        "/api/auth/register",
+        json!({ "email": email, "username": username, "password": PASSWORD }),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn test_register_with_username() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, true);
+    let username = unique_username();
+
+    let (status, body) = register(&app, &unique_email(), &username).await;
+    assert_eq!(status, StatusCode::CREATED);
+    // Stored lowercase so lookups ignore case
+    assert_eq!(body["user"]["username"], username.to_lowercase());
+
+    let stored = UserRepository::new(pool)
+        .find_by_username(&username.to_lowercase())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.id.to_string(), body["user"]["id"].as_str().unwrap());
+}
+
+#[tokio::test]
+async fn test_login_by_username_or_email() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, true);
+    let email = unique_email();
+    let username = unique_username();
+    let (status, _) = register(&app, &email, &username).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = post(
+        &app,
+        "/api/auth/login",
+        json!({ "identifier": username.to_uppercase(), "password": PASSWORD }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user"]["email"], email);
+    assert!(body["tokens"]["access_token"].is_string());
+
+    let (status, _) = post(
+        &app,
+        "/api/auth/login",
+        json!({ "identifier": email, "password": PASSWORD }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = post(
+        &app,
+        "/api/auth/login",
+        json!({ "identifier": username, "password": "WrongPass123!" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"]["code"], "INVALID_CREDENTIALS");
+}
+
+#[tokio::test]
+async fn test_duplicate_username_conflicts() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, true);
+    let username = unique_username();
+    let (status, _) = register(&app, &unique_email(), &username).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Usernames differing only in case are the same username
+    let (status, body) = register(&app, &unique_email(), &username.to_uppercase()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"]["code"], "USERNAME_IN_USE");
+}
+
+#[tokio::test]
+async fn test_username_rejected_when_disabled() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(&pool, false);
+
+    let (status, body) = register(&app, &unique_email(), &unique_username()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["details"][0]["field"], "username");
+}
@@ -0,0 +1,162 @@
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+};
+use chronos::app::hashing::Argon2Params;
+use chronos::app::models::user::User;
+use chronos::app::repositories::user_repository::UserRepository;
+use chronos::routes;
+use dotenvy::dotenv;
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use std::env;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const TEST_PASSWORD: &str = "FieldPass123!";
+
+async fn setup_test_pool() -> PgPool {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set for tests");
+
+    sqlx::PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to test database")
+}
+
+// Each test gets its own IP so failed logins don't add up across tests
+fn create_test_app(pool: PgPool, ip: &str) -> axum::Router {
+    routes::create_router(pool).layer(MockConnectInfo(
+        format!("{}:8080", ip).parse::<SocketAddr>().unwrap(),
+    ))
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
+
+fn json_request(method: &str, uri: &str, token: Option<&str>, body: Value) -> Request<Body> {
+    let mut request = Request::builder()
+        .uri(uri)
+        .method(method)
+        .header("content-type", "application/json");
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    request.body(Body::from(body.to_string())).unwrap()
+}
+
+fn unique_email() -> String {
+    format!("fields-{}@example.com", Uuid::new_v4().simple())
+}
+
+async fn create_user(pool: &PgPool) -> User {
+    let user = User::new_with_params(
+        None,
+        unique_email(),
+        TEST_PASSWORD,
+        &Argon2Params::new(1024, 1, 1).unwrap(),
+    )
+    .unwrap();
+    UserRepository::new(pool.clone())
+        .create(&user)
+        .await
+        .unwrap()
+}
+
+async fn login(app: &axum::Router, email: &str) -> String {
+    let (status, body) = send(
+        app,
+        json_request(
+            "POST",
+            "/api/auth/login",
+            None,
+            json!({ "email": email, "password": TEST_PASSWORD }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    body["tokens"]["access_token"].as_str().unwrap().to_string()
+}
+
+fn detail_fields(body: &Value) -> Vec<&str> {
+    body["error"]["details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|detail| detail["field"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_invalid_email_change_names_the_field() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool.clone(), "192.168.8.1");
+    let user = create_user(&pool).await;
+    let token = login(&app, &user.email).await;
+
+    let (status, body) = send(
+        &app,
+        json_request(
+            "POST",
+            "/api/auth/change-email",
+            Some(&token),
+            json!({ "new_email": "invalid-email-format", "current_password": TEST_PASSWORD }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "VALIDATION_FAILED");
+    assert_eq!(body["error"]["message"], "Validation failed");
+    assert_eq!(
+        body["error"]["details"],
+        json!([{ "field": "new_email", "message": "Invalid email format" }])
+    );
+}
+
+#[tokio::test]
+async fn test_register_lists_every_rejected_field() {
+    let pool = setup_test_pool().await;
+    let app = create_test_app(pool, "192.168.8.2");
+
+    let (status, body) = send(
+        &app,
+        json_request(
+            "POST",
+            "/api/auth/register",
+            None,
+            json!({ "email": "not-an-email", "password": "short" }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "VALIDATION_FAILED");
+    let fields = detail_fields(&body);
+    assert!(fields.contains(&"email"));
+    assert!(fields.contains(&"password"));
+
+    // Errors that aren't about a field carry a code but no details
+    let email = unique_email();
+    let request = json!({ "email": email, "password": TEST_PASSWORD });
+    let (status, _) = send(
+        &app,
+        json_request("POST", "/api/auth/register", None, request.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, body) = send(
+        &app,
+        json_request("POST", "/api/auth/register", None, request),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["error"]["code"], "EMAIL_IN_USE");
+    assert!(detail_fields(&body).is_empty());
+}